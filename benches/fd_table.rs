@@ -15,7 +15,7 @@ fn file_descriptor_table(c: &mut Criterion) {
     group.bench_function("descriptor table", |b: &mut criterion::Bencher<'_>| {
         b.iter(|| {
             let guard = descriptor_table.access(&id).unwrap().unwrap();
-            let _fd = guard.file.lock().unwrap();
+            let _reader = guard.reader();
         });
     });
 
@@ -55,7 +55,7 @@ fn file_descriptor_table_threading(c: &mut Criterion) {
                             std::thread::spawn(move || {
                                 for _ in 0..(files_to_open / thread_count) {
                                     let guard = table.access(&id).unwrap().unwrap();
-                                    let _fd = guard.file.lock().unwrap();
+                                    let _reader = guard.reader();
                                 }
                             })
                         })
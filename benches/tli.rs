@@ -16,6 +16,7 @@ fn tli_find_item(c: &mut Criterion) {
                 items.push(KeyedBlockHandle {
                     end_key: x.to_be_bytes().into(),
                     offset: x,
+                    item_count: 1,
                 });
             }
 
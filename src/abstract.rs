@@ -3,19 +3,48 @@
 // (found in the LICENSE-* files in the repository)
 
 use crate::{
-    compaction::CompactionStrategy, config::TreeType, tree::inner::MemtableId, AnyTree, BlobTree,
-    Config, KvPair, Memtable, Segment, SegmentId, SeqNo, Snapshot, Tree, UserKey, UserValue,
-    ValueType,
+    any_tree::SnapshotTree,
+    compaction::CompactionStrategy,
+    config::TreeType,
+    range::{prefix_to_range, to_owned_bounds},
+    tree::inner::MemtableId,
+    AnyTree, BlobTree, Config, KvPair, MemoryTree, Memtable, ReadOptions, Segment, SegmentId,
+    SeqNo, Snapshot, Tree, UserKey, UserValue, ValueType,
 };
 use enum_dispatch::enum_dispatch;
 use std::{
-    ops::RangeBounds,
+    ops::{Bound, RangeBounds},
     sync::{Arc, RwLockWriteGuard},
 };
 
 pub type RangeItem = crate::Result<KvPair>;
 
 /// Generic Tree API
+///
+/// The trait is object-safe: every generic method has an object-safe
+/// counterpart taking byte slices and owned bounds (e.g. [`AbstractTree::get_bytes`]
+/// or [`AbstractTree::range_bounds`]) that implementors provide.
+/// Boxed and shared trait objects implement the trait themselves, so the
+/// tree implementation can be selected at runtime.
+///
+/// # Examples
+///
+/// ```
+/// # let folder = tempfile::tempdir()?;
+/// use lsm_tree::{AbstractTree, Config};
+///
+/// # let use_blob_tree = false;
+/// let tree: Box<dyn AbstractTree> = if use_blob_tree {
+///     Box::new(Config::new(folder).open_as_blob_tree()?)
+/// } else {
+///     Box::new(Config::new(folder).open()?)
+/// };
+///
+/// tree.insert("a", "abc", 0);
+/// assert_eq!(Some("abc".as_bytes().into()), tree.get("a")?);
+/// #
+/// # Ok::<(), lsm_tree::Error>(())
+/// ```
 #[allow(clippy::module_name_repetitions)]
 #[enum_dispatch]
 pub trait AbstractTree {
@@ -285,7 +314,7 @@ pub trait AbstractTree {
     /// ```
     #[must_use]
     fn iter(&self) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
-        self.range::<UserKey, _>(..)
+        self.range_bounds((Bound::Unbounded, Bound::Unbounded), None, None)
     }
 
     /// Returns an iterator that scans through the entire tree, returning keys only.
@@ -353,15 +382,134 @@ pub trait AbstractTree {
         &self,
         seqno: SeqNo,
         index: Option<Arc<Memtable>>,
+    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
+        self.range_bounds((Bound::Unbounded, Bound::Unbounded), Some(seqno), index)
+    }
+
+    /// Creates a bounded iterator, reading the latest state of the tree
+    /// if `seqno` is `None`.
+    ///
+    /// Items of the ephemeral `index` are read in addition to the tree's items.
+    ///
+    /// This is the object-safe primitive that all range, prefix and full scans are
+    /// built on, so it is also callable on a `dyn AbstractTree`.
+    fn range_bounds(
+        &self,
+        bounds: (Bound<UserKey>, Bound<UserKey>),
+        seqno: Option<SeqNo>,
+        index: Option<Arc<Memtable>>,
     ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static>;
 
+    /// Creates a bounded iterator that is configured by the given [`ReadOptions`].
+    ///
+    /// Items of the ephemeral `index` are read in addition to the tree's items.
+    ///
+    /// Like [`AbstractTree::range_bounds`], this is object-safe.
+    fn range_bounds_with_options(
+        &self,
+        bounds: (Bound<UserKey>, Bound<UserKey>),
+        options: &ReadOptions,
+        index: Option<Arc<Memtable>>,
+    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
+        let iter = self.range_bounds(options.apply_start_after(bounds), options.seqno, index);
+
+        let iter: Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>>> = if options.key_only {
+            Box::new(iter.map(|item| item.map(|(key, _)| (key, UserValue::from(&[][..])))))
+        } else {
+            iter
+        };
+
+        Box::new(options.limited(iter))
+    }
+
+    /// Returns an iterator that scans through the entire tree,
+    /// configured by the given [`ReadOptions`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let folder = tempfile::tempdir()?;
+    /// use lsm_tree::{AbstractTree, Config, ReadOptions, Tree};
+    ///
+    /// let tree = Config::new(folder).open()?;
+    ///
+    /// tree.insert("a", "abc", 0);
+    /// tree.insert("f", "abc", 1);
+    /// tree.insert("g", "abc", 2);
+    ///
+    /// let options = ReadOptions::default().seqno(2).fill_cache(false);
+    /// assert_eq!(2, tree.iter_with_options(&options).count());
+    /// #
+    /// # Ok::<(), lsm_tree::Error>(())
+    /// ```
+    fn iter_with_options(
+        &self,
+        options: &ReadOptions,
+    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
+        self.range_bounds_with_options((Bound::Unbounded, Bound::Unbounded), options, None)
+    }
+
+    /// Returns an iterator over a range of items,
+    /// configured by the given [`ReadOptions`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let folder = tempfile::tempdir()?;
+    /// use lsm_tree::{AbstractTree, Config, ReadOptions, Tree};
+    ///
+    /// let tree = Config::new(folder).open()?;
+    ///
+    /// tree.insert("a", "abc", 0);
+    /// tree.insert("f", "abc", 1);
+    /// tree.insert("g", "abc", 2);
+    ///
+    /// let options = ReadOptions::default().key_only(true).limit(1);
+    /// let items = tree
+    ///     .range_with_options("a"..="f", &options)
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    ///
+    /// assert_eq!(1, items.len());
+    /// assert!(items[0].1.is_empty());
+    /// #
+    /// # Ok::<(), lsm_tree::Error>(())
+    /// ```
+    fn range_with_options<K: AsRef<[u8]>, R: RangeBounds<K>>(
+        &self,
+        range: R,
+        options: &ReadOptions,
+    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static>
+    where
+        Self: Sized,
+    {
+        self.range_bounds_with_options(to_owned_bounds(&range), options, None)
+    }
+
+    /// Returns an iterator over a prefixed set of items,
+    /// configured by the given [`ReadOptions`].
+    fn prefix_with_options<K: AsRef<[u8]>>(
+        &self,
+        prefix: K,
+        options: &ReadOptions,
+    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static>
+    where
+        Self: Sized,
+    {
+        self.range_bounds_with_options(prefix_to_range(prefix.as_ref()), options, None)
+    }
+
     /// Creates an bounded iterator over a snapshot instant.
     fn range_with_seqno<K: AsRef<[u8]>, R: RangeBounds<K>>(
         &self,
         range: R,
         seqno: SeqNo,
         index: Option<Arc<Memtable>>,
-    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static>;
+    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static>
+    where
+        Self: Sized,
+    {
+        self.range_bounds(to_owned_bounds(&range), Some(seqno), index)
+    }
 
     /// Creates a prefix iterator over a snapshot instant.
     fn prefix_with_seqno<K: AsRef<[u8]>>(
@@ -369,7 +517,12 @@ pub trait AbstractTree {
         prefix: K,
         seqno: SeqNo,
         index: Option<Arc<Memtable>>,
-    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static>;
+    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static>
+    where
+        Self: Sized,
+    {
+        self.range_bounds(prefix_to_range(prefix.as_ref()), Some(seqno), index)
+    }
 
     /// Returns an iterator over a range of items.
     ///
@@ -393,7 +546,12 @@ pub trait AbstractTree {
     fn range<K: AsRef<[u8]>, R: RangeBounds<K>>(
         &self,
         range: R,
-    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static>;
+    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static>
+    where
+        Self: Sized,
+    {
+        self.range_bounds(to_owned_bounds(&range), None, None)
+    }
 
     /// Returns an iterator over a prefixed set of items.
     ///
@@ -417,7 +575,41 @@ pub trait AbstractTree {
     fn prefix<K: AsRef<[u8]>>(
         &self,
         prefix: K,
-    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static>;
+    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static>
+    where
+        Self: Sized,
+    {
+        self.range_bounds(prefix_to_range(prefix.as_ref()), None, None)
+    }
+
+    /// Retrieves an item, reading the latest state of the tree
+    /// if `seqno` is `None`.
+    ///
+    /// This is the object-safe primitive of all point reads.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn get_bytes(&self, key: &[u8], seqno: Option<SeqNo>) -> crate::Result<Option<UserValue>>;
+
+    /// Retrieves a batch of items, as visible at the given seqno.
+    ///
+    /// The values are returned in the order of the keys.
+    ///
+    /// This is the object-safe primitive of [`Snapshot::get_many`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn get_many_bytes(
+        &self,
+        keys: &[&[u8]],
+        seqno: SeqNo,
+    ) -> crate::Result<Vec<Option<UserValue>>> {
+        keys.iter()
+            .map(|key| self.get_bytes(key, Some(seqno)))
+            .collect()
+    }
 
     /// Retrieves an item from the tree.
     ///
@@ -439,7 +631,12 @@ pub trait AbstractTree {
     /// # Errors
     ///
     /// Will return `Err` if an IO error occurs.
-    fn get<K: AsRef<[u8]>>(&self, key: K) -> crate::Result<Option<UserValue>>;
+    fn get<K: AsRef<[u8]>>(&self, key: K) -> crate::Result<Option<UserValue>>
+    where
+        Self: Sized,
+    {
+        self.get_bytes(key.as_ref(), None)
+    }
 
     /// Retrieves an item from a snapshot instant.
     ///
@@ -450,7 +647,74 @@ pub trait AbstractTree {
         &self,
         key: K,
         seqno: SeqNo,
-    ) -> crate::Result<Option<UserValue>>;
+    ) -> crate::Result<Option<UserValue>>
+    where
+        Self: Sized,
+    {
+        self.get_bytes(key.as_ref(), Some(seqno))
+    }
+
+    /// Retrieves a byte window of an item's value.
+    ///
+    /// The window is clamped to the length of the value, so a window
+    /// past the end of the value returns an empty value.
+    ///
+    /// Values are stored in compressed data blocks, so the block containing
+    /// the value is still read as a whole, but only the requested window is returned
+    /// to the caller. Uncompressed blobs of blob trees are only read in the window,
+    /// and of chunked values only the chunks overlapping the window are read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let folder = tempfile::tempdir()?;
+    /// use lsm_tree::{AbstractTree, Config, Tree};
+    ///
+    /// let tree = Config::new(folder).open()?;
+    /// tree.insert("a", "my_value", 0);
+    ///
+    /// let item = tree.get_range_of_value("a", 3..)?;
+    /// assert_eq!(Some("value".as_bytes().into()), item);
+    /// #
+    /// # Ok::<(), lsm_tree::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn get_range_of_value<K: AsRef<[u8]>, R: RangeBounds<usize>>(
+        &self,
+        key: K,
+        range: R,
+    ) -> crate::Result<Option<UserValue>>
+    where
+        Self: Sized,
+    {
+        self.get_range_of_value_bytes(
+            key.as_ref(),
+            (range.start_bound().cloned(), range.end_bound().cloned()),
+            None,
+        )
+    }
+
+    /// Retrieves a byte window of an item's value, reading the latest state
+    /// of the tree if `seqno` is `None`.
+    ///
+    /// This is the object-safe primitive of [`AbstractTree::get_range_of_value`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn get_range_of_value_bytes(
+        &self,
+        key: &[u8],
+        range: (Bound<usize>, Bound<usize>),
+        seqno: Option<SeqNo>,
+    ) -> crate::Result<Option<UserValue>> {
+        Ok(self
+            .get_bytes(key, seqno)?
+            .map(|value| value_window(&value, &range)))
+    }
 
     /// Opens a read-only point-in-time snapshot of the tree
     ///
@@ -487,6 +751,18 @@ pub trait AbstractTree {
         self.snapshot(seqno)
     }
 
+    /// Returns `true` if the key exists, reading the latest state of the tree
+    /// if `seqno` is `None`.
+    ///
+    /// This is the object-safe primitive of all key lookups.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    fn contains_key_bytes(&self, key: &[u8], seqno: Option<SeqNo>) -> crate::Result<bool> {
+        self.get_bytes(key, seqno).map(|x| x.is_some())
+    }
+
     /// Returns `true` if the tree contains the specified key.
     ///
     /// # Examples
@@ -507,8 +783,11 @@ pub trait AbstractTree {
     /// # Errors
     ///
     /// Will return `Err` if an IO error occurs.
-    fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> crate::Result<bool> {
-        self.get(key).map(|x| x.is_some())
+    fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> crate::Result<bool>
+    where
+        Self: Sized,
+    {
+        self.contains_key_bytes(key.as_ref(), None)
     }
 
     /// Returns `true` if the snapshot instant contains the specified key.
@@ -516,10 +795,21 @@ pub trait AbstractTree {
     /// # Errors
     ///
     /// Will return `Err` if an IO error occurs.
-    fn contains_key_with_seqno<K: AsRef<[u8]>>(&self, key: K, seqno: SeqNo) -> crate::Result<bool> {
-        self.get_with_seqno(key, seqno).map(|x| x.is_some())
+    fn contains_key_with_seqno<K: AsRef<[u8]>>(&self, key: K, seqno: SeqNo) -> crate::Result<bool>
+    where
+        Self: Sized,
+    {
+        self.contains_key_bytes(key.as_ref(), Some(seqno))
     }
 
+    /// Writes an item of the given type into the active memtable.
+    ///
+    /// This is the object-safe primitive of all writes.
+    ///
+    /// Returns the added item's size and new size of the memtable.
+    fn insert_bytes(&self, key: &[u8], value: &[u8], seqno: SeqNo, r#type: ValueType)
+        -> (u32, u32);
+
     /// Inserts a key-value pair into the tree.
     ///
     /// If the key already exists, the item will be overwritten.
@@ -541,7 +831,24 @@ pub trait AbstractTree {
     /// # Errors
     ///
     /// Will return `Err` if an IO error occurs.
-    fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V, seqno: SeqNo) -> (u32, u32);
+    fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V, seqno: SeqNo) -> (u32, u32)
+    where
+        Self: Sized,
+    {
+        self.insert_bytes(key.as_ref(), value.as_ref(), seqno, ValueType::Value)
+    }
+
+    /// Writes an item of the given type into a write-locked memtable.
+    ///
+    /// This is the object-safe variant of [`AbstractTree::raw_insert_with_lock`].
+    fn raw_insert_bytes_with_lock(
+        &self,
+        lock: &RwLockWriteGuard<'_, Memtable>,
+        key: &[u8],
+        value: &[u8],
+        seqno: SeqNo,
+        r#type: ValueType,
+    ) -> (u32, u32);
 
     /// Inserts a key-value pair.
     fn raw_insert_with_lock<K: AsRef<[u8]>, V: AsRef<[u8]>>(
@@ -551,7 +858,12 @@ pub trait AbstractTree {
         value: V,
         seqno: SeqNo,
         r#type: ValueType,
-    ) -> (u32, u32);
+    ) -> (u32, u32)
+    where
+        Self: Sized,
+    {
+        self.raw_insert_bytes_with_lock(lock, key.as_ref(), value.as_ref(), seqno, r#type)
+    }
 
     /// Removes an item from the tree.
     ///
@@ -580,7 +892,12 @@ pub trait AbstractTree {
     /// # Errors
     ///
     /// Will return `Err` if an IO error occurs.
-    fn remove<K: AsRef<[u8]>>(&self, key: K, seqno: SeqNo) -> (u32, u32);
+    fn remove<K: AsRef<[u8]>>(&self, key: K, seqno: SeqNo) -> (u32, u32)
+    where
+        Self: Sized,
+    {
+        self.insert_bytes(key.as_ref(), &[], seqno, ValueType::Tombstone)
+    }
 
     /// Removes an item from the tree.
     ///
@@ -589,6 +906,8 @@ pub trait AbstractTree {
     /// This may cause older versions of the value to be resurrected, so it should
     /// only be used and preferred in scenarios where a key is only ever written once.
     ///
+    /// To detect misuse, see [`Config::weak_tombstone_checks`].
+    ///
     /// Returns the added item's size and new size of the memtable.
     ///
     /// # Examples
@@ -614,5 +933,274 @@ pub trait AbstractTree {
     /// # Errors
     ///
     /// Will return `Err` if an IO error occurs.
-    fn remove_weak<K: AsRef<[u8]>>(&self, key: K, seqno: SeqNo) -> (u32, u32);
+    fn remove_weak<K: AsRef<[u8]>>(&self, key: K, seqno: SeqNo) -> (u32, u32)
+    where
+        Self: Sized,
+    {
+        self.insert_bytes(key.as_ref(), &[], seqno, ValueType::WeakTombstone)
+    }
+}
+
+/// Returns the given byte window of a value, clamped to its length
+pub fn value_window<R: RangeBounds<usize>>(value: &UserValue, range: &R) -> UserValue {
+    let (start, end) = value_window_bounds(value.len(), range);
+    value.get(start..end).unwrap_or_default().into()
 }
+
+/// Returns the start and end of a byte window, clamped to the given value length.
+pub fn value_window_bounds<R: RangeBounds<usize>>(len: usize, range: &R) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&idx) => idx,
+        Bound::Excluded(&idx) => idx.saturating_add(1),
+        Bound::Unbounded => 0,
+    }
+    .min(len);
+
+    let end = match range.end_bound() {
+        Bound::Included(&idx) => idx.saturating_add(1),
+        Bound::Excluded(&idx) => idx,
+        Bound::Unbounded => len,
+    }
+    .clamp(start, len);
+
+    (start, end)
+}
+
+/// Forwards the object-safe methods of [`AbstractTree`] to the pointee,
+/// so the generic methods are available on boxed (and shared) trait objects
+macro_rules! impl_abstract_tree_for_pointer {
+    ($ptr:ident) => {
+        impl<T: AbstractTree + ?Sized> AbstractTree for $ptr<T> {
+            fn verify(&self) -> crate::Result<usize> {
+                (**self).verify()
+            }
+
+            fn flush_memtable(
+                &self,
+                segment_id: SegmentId,
+                memtable: &Arc<Memtable>,
+                seqno_threshold: SeqNo,
+            ) -> crate::Result<Option<Arc<Segment>>> {
+                (**self).flush_memtable(segment_id, memtable, seqno_threshold)
+            }
+
+            fn register_segments(&self, segments: &[Arc<Segment>]) -> crate::Result<()> {
+                (**self).register_segments(segments)
+            }
+
+            fn lock_active_memtable(&self) -> RwLockWriteGuard<'_, Memtable> {
+                (**self).lock_active_memtable()
+            }
+
+            fn set_active_memtable(&self, memtable: Memtable) {
+                (**self).set_active_memtable(memtable);
+            }
+
+            fn sealed_memtable_count(&self) -> usize {
+                (**self).sealed_memtable_count()
+            }
+
+            fn add_sealed_memtable(&self, id: MemtableId, memtable: Arc<Memtable>) {
+                (**self).add_sealed_memtable(id, memtable);
+            }
+
+            fn compact(
+                &self,
+                strategy: Arc<dyn CompactionStrategy>,
+                seqno_threshold: SeqNo,
+            ) -> crate::Result<()> {
+                (**self).compact(strategy, seqno_threshold)
+            }
+
+            fn get_next_segment_id(&self) -> SegmentId {
+                (**self).get_next_segment_id()
+            }
+
+            fn tree_config(&self) -> &Config {
+                (**self).tree_config()
+            }
+
+            fn get_highest_seqno(&self) -> Option<SeqNo> {
+                (**self).get_highest_seqno()
+            }
+
+            fn active_memtable_size(&self) -> u32 {
+                (**self).active_memtable_size()
+            }
+
+            fn tree_type(&self) -> TreeType {
+                (**self).tree_type()
+            }
+
+            fn rotate_memtable(&self) -> Option<(MemtableId, Arc<Memtable>)> {
+                (**self).rotate_memtable()
+            }
+
+            fn segment_count(&self) -> usize {
+                (**self).segment_count()
+            }
+
+            fn first_level_segment_count(&self) -> usize {
+                (**self).first_level_segment_count()
+            }
+
+            fn is_first_level_disjoint(&self) -> bool {
+                (**self).is_first_level_disjoint()
+            }
+
+            fn approximate_len(&self) -> usize {
+                (**self).approximate_len()
+            }
+
+            fn disk_space(&self) -> u64 {
+                (**self).disk_space()
+            }
+
+            fn get_highest_memtable_seqno(&self) -> Option<SeqNo> {
+                (**self).get_highest_memtable_seqno()
+            }
+
+            fn get_highest_persisted_seqno(&self) -> Option<SeqNo> {
+                (**self).get_highest_persisted_seqno()
+            }
+
+            fn len(&self) -> crate::Result<usize> {
+                (**self).len()
+            }
+
+            fn is_empty(&self) -> crate::Result<bool> {
+                (**self).is_empty()
+            }
+
+            fn first_key_value(&self) -> crate::Result<Option<KvPair>> {
+                (**self).first_key_value()
+            }
+
+            fn last_key_value(&self) -> crate::Result<Option<KvPair>> {
+                (**self).last_key_value()
+            }
+
+            fn iter(&self) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
+                (**self).iter()
+            }
+
+            fn keys(
+                &self,
+            ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<UserKey>> + 'static> {
+                (**self).keys()
+            }
+
+            fn values(
+                &self,
+            ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<UserValue>> + 'static> {
+                (**self).values()
+            }
+
+            fn keys_with_seqno(
+                &self,
+                seqno: SeqNo,
+                index: Option<Arc<Memtable>>,
+            ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<UserKey>> + 'static> {
+                (**self).keys_with_seqno(seqno, index)
+            }
+
+            fn values_with_seqno(
+                &self,
+                seqno: SeqNo,
+                index: Option<Arc<Memtable>>,
+            ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<UserValue>> + 'static> {
+                (**self).values_with_seqno(seqno, index)
+            }
+
+            fn iter_with_seqno(
+                &self,
+                seqno: SeqNo,
+                index: Option<Arc<Memtable>>,
+            ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
+                (**self).iter_with_seqno(seqno, index)
+            }
+
+            fn range_bounds(
+                &self,
+                bounds: (Bound<UserKey>, Bound<UserKey>),
+                seqno: Option<SeqNo>,
+                index: Option<Arc<Memtable>>,
+            ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
+                (**self).range_bounds(bounds, seqno, index)
+            }
+
+            fn range_bounds_with_options(
+                &self,
+                bounds: (Bound<UserKey>, Bound<UserKey>),
+                options: &ReadOptions,
+                index: Option<Arc<Memtable>>,
+            ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
+                (**self).range_bounds_with_options(bounds, options, index)
+            }
+
+            fn iter_with_options(
+                &self,
+                options: &ReadOptions,
+            ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
+                (**self).iter_with_options(options)
+            }
+
+            fn get_bytes(
+                &self,
+                key: &[u8],
+                seqno: Option<SeqNo>,
+            ) -> crate::Result<Option<UserValue>> {
+                (**self).get_bytes(key, seqno)
+            }
+
+            fn get_many_bytes(
+                &self,
+                keys: &[&[u8]],
+                seqno: SeqNo,
+            ) -> crate::Result<Vec<Option<UserValue>>> {
+                (**self).get_many_bytes(keys, seqno)
+            }
+
+            fn snapshot(&self, seqno: SeqNo) -> Snapshot {
+                (**self).snapshot(seqno)
+            }
+
+            fn contains_key_bytes(&self, key: &[u8], seqno: Option<SeqNo>) -> crate::Result<bool> {
+                (**self).contains_key_bytes(key, seqno)
+            }
+
+            fn get_range_of_value_bytes(
+                &self,
+                key: &[u8],
+                range: (Bound<usize>, Bound<usize>),
+                seqno: Option<SeqNo>,
+            ) -> crate::Result<Option<UserValue>> {
+                (**self).get_range_of_value_bytes(key, range, seqno)
+            }
+
+            fn insert_bytes(
+                &self,
+                key: &[u8],
+                value: &[u8],
+                seqno: SeqNo,
+                r#type: ValueType,
+            ) -> (u32, u32) {
+                (**self).insert_bytes(key, value, seqno, r#type)
+            }
+
+            fn raw_insert_bytes_with_lock(
+                &self,
+                lock: &RwLockWriteGuard<'_, Memtable>,
+                key: &[u8],
+                value: &[u8],
+                seqno: SeqNo,
+                r#type: ValueType,
+            ) -> (u32, u32) {
+                (**self).raw_insert_bytes_with_lock(lock, key, value, seqno, r#type)
+            }
+        }
+    };
+}
+
+impl_abstract_tree_for_pointer!(Box);
+impl_abstract_tree_for_pointer!(Arc);
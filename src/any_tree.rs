@@ -2,7 +2,7 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use crate::{BlobTree, Tree};
+use crate::{BlobTree, MemoryTree, Tree};
 use enum_dispatch::enum_dispatch;
 
 /// May be a standard [`Tree`] or a [`BlobTree`]
@@ -15,3 +15,23 @@ pub enum AnyTree {
     /// Key-value separated LSM-tree, see [`BlobTree`]
     Blob(BlobTree),
 }
+
+/// Tree that a [`crate::Snapshot`] reads from
+///
+/// Unlike [`AnyTree`], may also be a [`MemoryTree`].
+#[derive(Clone)]
+#[enum_dispatch(AbstractTree)]
+pub enum SnapshotTree {
+    Standard(Tree),
+    Blob(BlobTree),
+    Memory(MemoryTree),
+}
+
+impl From<AnyTree> for SnapshotTree {
+    fn from(value: AnyTree) -> Self {
+        match value {
+            AnyTree::Standard(tree) => Self::Standard(tree),
+            AnyTree::Blob(tree) => Self::Blob(tree),
+        }
+    }
+}
@@ -0,0 +1,299 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{chunked::read_chunks, compression::MyCompressor, value::MaybeInlineValue};
+use crate::{
+    coding::Decode,
+    r#abstract::RangeItem,
+    read_pool::{Job, ReadPool},
+    UserKey, UserValue,
+};
+use std::{
+    collections::VecDeque,
+    io::Cursor,
+    iter::Fuse,
+    sync::{mpsc, Arc},
+};
+use value_log::{ValueHandle, ValueLog};
+
+/// Maximum amount of items that are resolved in a single batch
+const MAX_BATCH_SIZE: usize = 32;
+
+/// Value log and chunk log of a blob tree
+#[derive(Clone)]
+pub struct BlobLogs {
+    blobs: ValueLog<MyCompressor>,
+    chunks: ValueLog<MyCompressor>,
+}
+
+/// Batch whose blobs are read ahead of the consumer
+enum Pending {
+    /// Blobs are being read by the read-ahead threads of the tree
+    Reading(mpsc::Receiver<Vec<RangeItem>>),
+
+    /// The read-ahead threads are disabled, so blobs are read when the batch is needed
+    Deferred(Vec<RangeItem>),
+}
+
+impl Pending {
+    /// Starts reading the blobs of a batch in the background
+    fn start(pool: &ReadPool, vlog: &BlobLogs, batch: Vec<RangeItem>) -> Self {
+        if !pool.is_enabled() {
+            return Self::Deferred(batch);
+        }
+
+        let (result, receiver) = mpsc::sync_channel(1);
+        let vlog = vlog.clone();
+
+        let job: Job = Box::new(move || {
+            // NOTE: If the scan was dropped in the meantime, the result is discarded
+            let _ = result.send(resolve_batch(&vlog, batch));
+        });
+
+        // NOTE: If no read-ahead thread could be started, the batch is read right away
+        if let Err(job) = pool.spawn(job) {
+            job();
+        }
+
+        Self::Reading(receiver)
+    }
+
+    /// Waits for the blobs of the batch to be read
+    fn wait(self, vlog: &BlobLogs) -> Vec<RangeItem> {
+        match self {
+            Self::Reading(receiver) => receiver.recv().unwrap_or_else(|_| {
+                log::error!("Blob read-ahead job failed");
+                vec![Err(crate::Error::Unrecoverable)]
+            }),
+            Self::Deferred(batch) => resolve_batch(vlog, batch),
+        }
+    }
+}
+
+/// Resolves value handles of an index tree scan in batches, reading ahead of the consumer
+///
+/// Instead of reading one blob per item, runs of items are taken from
+/// the index tree, and their blobs are fetched in blob file order,
+/// so the value log is read (mostly) sequentially.
+///
+/// While the consumer works through a batch, the blobs of the next batch are
+/// read by the read-ahead threads of the tree (see [`crate::Config::read_ahead_threads`]).
+///
+/// If reading a batch fails, the batch is replaced by a single error.
+///
+/// The batch size starts at 1 and doubles with every batch,
+/// so short scans do not fetch more blobs than they need.
+pub struct BatchedIter<I: DoubleEndedIterator<Item = RangeItem>> {
+    inner: Fuse<I>,
+    vlog: BlobLogs,
+    pool: Arc<ReadPool>,
+
+    front: VecDeque<RangeItem>,
+    front_pending: Option<Pending>,
+    front_batch_size: usize,
+
+    back: VecDeque<RangeItem>,
+    back_pending: Option<Pending>,
+    back_batch_size: usize,
+}
+
+impl<I: DoubleEndedIterator<Item = RangeItem>> BatchedIter<I> {
+    pub fn new(
+        inner: I,
+        blobs: ValueLog<MyCompressor>,
+        chunks: ValueLog<MyCompressor>,
+        pool: Arc<ReadPool>,
+    ) -> Self {
+        Self {
+            inner: inner.fuse(),
+            vlog: BlobLogs { blobs, chunks },
+            pool,
+            front: VecDeque::new(),
+            front_pending: None,
+            front_batch_size: 1,
+            back: VecDeque::new(),
+            back_pending: None,
+            back_batch_size: 1,
+        }
+    }
+
+    fn take_front(&mut self) -> Vec<RangeItem> {
+        let batch = (&mut self.inner)
+            .take(self.front_batch_size)
+            .collect::<Vec<_>>();
+
+        self.front_batch_size = (self.front_batch_size * 2).min(MAX_BATCH_SIZE);
+        batch
+    }
+
+    fn take_back(&mut self) -> Vec<RangeItem> {
+        let batch = (&mut self.inner)
+            .rev()
+            .take(self.back_batch_size)
+            .collect::<Vec<_>>();
+
+        self.back_batch_size = (self.back_batch_size * 2).min(MAX_BATCH_SIZE);
+        batch
+    }
+
+    fn fill_front(&mut self) {
+        let batch = match self.front_pending.take() {
+            Some(pending) => pending.wait(&self.vlog),
+            None => {
+                let batch = self.take_front();
+                resolve_batch(&self.vlog, batch)
+            }
+        };
+        self.front.extend(batch);
+
+        // NOTE: Read the next batch while the consumer works through this one
+        let next = self.take_front();
+
+        if !next.is_empty() {
+            self.front_pending = Some(Pending::start(&self.pool, &self.vlog, next));
+        }
+    }
+
+    fn fill_back(&mut self) {
+        let batch = match self.back_pending.take() {
+            Some(pending) => pending.wait(&self.vlog),
+            None => {
+                let batch = self.take_back();
+                resolve_batch(&self.vlog, batch)
+            }
+        };
+        self.back.extend(batch);
+
+        let next = self.take_back();
+
+        if !next.is_empty() {
+            self.back_pending = Some(Pending::start(&self.pool, &self.vlog, next));
+        }
+    }
+}
+
+impl<I: DoubleEndedIterator<Item = RangeItem>> Iterator for BatchedIter<I> {
+    type Item = RangeItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front.is_empty() {
+            self.fill_front();
+        }
+
+        if let Some(item) = self.front.pop_front() {
+            return Some(item);
+        }
+
+        // NOTE: If the index tree is exhausted, the remaining items have already
+        // been taken by the other end, its pending batch being closest to this end
+        if let Some(pending) = self.back_pending.take() {
+            self.back.extend(pending.wait(&self.vlog));
+        }
+
+        self.back.pop_back()
+    }
+}
+
+impl<I: DoubleEndedIterator<Item = RangeItem>> DoubleEndedIterator for BatchedIter<I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back.is_empty() {
+            self.fill_back();
+        }
+
+        if let Some(item) = self.back.pop_front() {
+            return Some(item);
+        }
+
+        if let Some(pending) = self.front_pending.take() {
+            self.front.extend(pending.wait(&self.vlog));
+        }
+
+        self.front.pop_back()
+    }
+}
+
+/// Resolves the value handle of a single index tree item
+pub fn resolve_item(
+    blobs: &ValueLog<MyCompressor>,
+    chunks: &ValueLog<MyCompressor>,
+    item: RangeItem,
+) -> RangeItem {
+    let vlog = BlobLogs {
+        blobs: blobs.clone(),
+        chunks: chunks.clone(),
+    };
+    resolve(&vlog, decode(item), None)
+}
+
+/// Resolves the value handles of a batch of index tree items, keeping the order of items
+fn resolve_batch(vlog: &BlobLogs, batch: Vec<RangeItem>) -> Vec<RangeItem> {
+    let items = batch.into_iter().map(decode).collect::<Vec<_>>();
+
+    let mut vhandles = items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, item)| match item {
+            Ok((_, MaybeInlineValue::Indirect { vhandle, .. })) => Some((idx, vhandle)),
+            _ => None,
+        })
+        .collect::<Vec<(usize, &ValueHandle)>>();
+
+    // NOTE: Read blobs in file order
+    vhandles.sort_by_key(|(_, vhandle)| (vhandle.segment_id, vhandle.offset));
+
+    let mut blobs = items.iter().map(|_| None).collect::<Vec<_>>();
+
+    for (idx, vhandle) in vhandles {
+        if let Some(slot) = blobs.get_mut(idx) {
+            *slot = Some(read_blob(&vlog.blobs, vhandle));
+        }
+    }
+
+    items
+        .into_iter()
+        .zip(blobs)
+        .map(|(item, blob)| resolve(vlog, item, blob))
+        .collect()
+}
+
+/// Decodes the value of an index tree item
+fn decode(item: RangeItem) -> crate::Result<(UserKey, MaybeInlineValue)> {
+    let (key, value) = item?;
+    let value = MaybeInlineValue::decode_from(&mut Cursor::new(value))?;
+    Ok((key, value))
+}
+
+/// Resolves the value of a decoded index tree item, using its blob if it was already read
+fn resolve(
+    vlog: &BlobLogs,
+    item: crate::Result<(UserKey, MaybeInlineValue)>,
+    blob: Option<crate::Result<UserValue>>,
+) -> RangeItem {
+    let (key, value) = item?;
+
+    match value {
+        MaybeInlineValue::Inline(bytes) => Ok((key, bytes)),
+        MaybeInlineValue::Indirect { vhandle, .. } => {
+            let bytes = match blob {
+                Some(blob) => blob?,
+                None => read_blob(&vlog.blobs, &vhandle)?,
+            };
+            Ok((key, bytes))
+        }
+        MaybeInlineValue::Chunked { chunks } => {
+            let bytes = read_chunks(&vlog.chunks, &chunks)?;
+            Ok((key, bytes))
+        }
+    }
+}
+
+/// Reads the blob a value handle points to
+fn read_blob(vlog: &ValueLog<MyCompressor>, vhandle: &ValueHandle) -> crate::Result<UserValue> {
+    let Some(bytes) = vlog.get(vhandle)? else {
+        log::error!("Blob {vhandle:?} is missing in value log");
+        return Err(crate::Error::Unrecoverable);
+    };
+
+    Ok(bytes)
+}
@@ -0,0 +1,80 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::compression::MyCompressor;
+use crate::{UserKey, UserValue};
+use value_log::{ValueHandle, ValueLog};
+
+/// Size of the chunk key suffix (chunk index)
+const CHUNK_KEY_SUFFIX_LEN: usize = std::mem::size_of::<u32>();
+
+/// Maximum size of the key of a chunked value, so its chunk keys fit into blob files
+pub const MAX_CHUNKED_KEY_LEN: usize = u16::MAX as usize - CHUNK_KEY_SUFFIX_LEN;
+
+/// Returns the key that a chunk of a value is stored under in the chunk log.
+///
+/// Blobs are garbage collected by looking up their key in the index tree,
+/// so every chunk needs its own key, which leads back to the value's key.
+///
+/// Chunks are stored in their own value log, so a chunk key can never
+/// be mistaken for the key of a value.
+pub fn chunk_key(key: &[u8], idx: u32) -> UserKey {
+    let mut chunk_key = Vec::with_capacity(key.len() + CHUNK_KEY_SUFFIX_LEN);
+    chunk_key.extend_from_slice(key);
+    chunk_key.extend_from_slice(&idx.to_be_bytes());
+    chunk_key.into()
+}
+
+/// Splits a chunk key into the key of its value and the chunk index.
+///
+/// Returns `None` if the key is too short to be a chunk key.
+pub fn parse_chunk_key(chunk_key: &[u8]) -> Option<(&[u8], u32)> {
+    let key_len = chunk_key.len().checked_sub(CHUNK_KEY_SUFFIX_LEN)?;
+
+    let (key, idx) = chunk_key.split_at(key_len);
+    let idx = u32::from_be_bytes(idx.try_into().ok()?);
+
+    Some((key, idx))
+}
+
+/// Reads the chunks of a value from the value log, and reassembles the value.
+pub fn read_chunks(
+    vlog: &ValueLog<MyCompressor>,
+    chunks: &[(ValueHandle, u32)],
+) -> crate::Result<UserValue> {
+    let size = chunks.iter().map(|(_, size)| u64::from(*size)).sum::<u64>();
+
+    let mut value: Vec<u8> = Vec::with_capacity(usize::try_from(size).unwrap_or_default());
+
+    for (vhandle, _) in chunks {
+        let Some(chunk) = vlog.get(vhandle)? else {
+            log::error!("Chunk {vhandle:?} of value is missing in value log");
+            return Err(crate::Error::Unrecoverable);
+        };
+
+        value.extend_from_slice(&chunk);
+    }
+
+    Ok(value.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn chunk_key_round_trip() {
+        let key = chunk_key(b"abc", 5);
+        assert_eq!(Some((b"abc".as_slice(), 5)), parse_chunk_key(&key));
+
+        let key = chunk_key(b"", u32::MAX);
+        assert_eq!(Some((b"".as_slice(), u32::MAX)), parse_chunk_key(&key));
+    }
+
+    #[test]
+    fn chunk_key_parse_short_key() {
+        assert_eq!(None, parse_chunk_key(b"abc"));
+    }
+}
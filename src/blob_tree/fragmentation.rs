@@ -0,0 +1,130 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+/// Blob file (value log segment) ID
+pub type BlobFileId = u64;
+
+/// Fragmentation statistics of a single blob file
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[allow(clippy::module_name_repetitions)]
+pub struct BlobFileStats {
+    /// Blob file ID
+    pub id: BlobFileId,
+
+    /// Uncompressed size of all blobs in the file
+    pub total_bytes: u64,
+
+    /// Uncompressed size of all blobs that are not referenced anymore
+    pub stale_bytes: u64,
+
+    /// Amount of blobs in the file
+    pub item_count: u64,
+
+    /// Amount of blobs that are still referenced
+    pub live_item_count: u64,
+}
+
+impl BlobFileStats {
+    /// Returns the ratio of stale bytes in the blob file.
+    #[must_use]
+    pub fn stale_ratio(&self) -> f32 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let ratio = self.stale_bytes as f32 / self.total_bytes as f32;
+
+        ratio
+    }
+}
+
+/// Fragmentation report of the value log
+///
+/// The statistics reflect the last scan of the index tree,
+/// see [`crate::BlobTree::gc_scan_stats`].
+#[derive(Clone, Debug, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct FragmentationReport {
+    /// Per-blob-file statistics, sorted by blob file ID
+    pub blob_files: Vec<BlobFileStats>,
+}
+
+impl FragmentationReport {
+    /// Returns the uncompressed size of all blobs.
+    #[must_use]
+    pub fn total_bytes(&self) -> u64 {
+        self.blob_files.iter().map(|x| x.total_bytes).sum()
+    }
+
+    /// Returns the uncompressed size of all stale blobs.
+    #[must_use]
+    pub fn stale_bytes(&self) -> u64 {
+        self.blob_files.iter().map(|x| x.stale_bytes).sum()
+    }
+
+    /// Returns the space amplification of the value log.
+    ///
+    /// A value of 1.0 means there is no stale data.
+    /// Returns 0.0 if all data is stale.
+    #[must_use]
+    pub fn space_amp(&self) -> f32 {
+        let total_bytes = self.total_bytes();
+        let live_bytes = total_bytes.saturating_sub(self.stale_bytes());
+
+        if live_bytes == 0 {
+            return 0.0;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let space_amp = total_bytes as f32 / live_bytes as f32;
+
+        space_amp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn fragmentation_report_space_amp() {
+        let report = FragmentationReport {
+            blob_files: vec![
+                BlobFileStats {
+                    id: 0,
+                    total_bytes: 100,
+                    stale_bytes: 50,
+                    item_count: 2,
+                    live_item_count: 1,
+                },
+                BlobFileStats {
+                    id: 1,
+                    total_bytes: 100,
+                    stale_bytes: 0,
+                    item_count: 2,
+                    live_item_count: 2,
+                },
+            ],
+        };
+
+        assert_eq!(200, report.total_bytes());
+        assert_eq!(50, report.stale_bytes());
+        assert!((report.space_amp() - (200.0 / 150.0)).abs() < f32::EPSILON);
+
+        let stale_ratio = report
+            .blob_files
+            .first()
+            .map(BlobFileStats::stale_ratio)
+            .unwrap_or_default();
+        assert!((stale_ratio - 0.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn fragmentation_report_empty() {
+        let report = FragmentationReport::default();
+        assert!(report.space_amp().abs() < f32::EPSILON);
+    }
+}
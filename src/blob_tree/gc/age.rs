@@ -0,0 +1,55 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use std::time::{Duration, SystemTime};
+use value_log::{Compressor, GcStrategy, ValueLog};
+
+/// Picks blob files that are older than some age and contain any stale data
+///
+/// Complements the space amplification based strategies, so ancient, slightly
+/// fragmented blob files are eventually rewritten in long-lived deployments,
+/// even when the overall space amplification stays below its target.
+#[allow(clippy::module_name_repetitions)]
+pub struct AgeStrategy {
+    max_age: Duration,
+}
+
+impl AgeStrategy {
+    /// Creates a new strategy that targets blob files older than `max_age`
+    #[must_use]
+    pub fn new(max_age: Duration) -> Self {
+        Self { max_age }
+    }
+}
+
+impl<C: Compressor + Clone> GcStrategy<C> for AgeStrategy {
+    fn pick(&self, value_log: &ValueLog<C>) -> Vec<u64> {
+        let now = SystemTime::now();
+
+        value_log
+            .manifest
+            .list_segments()
+            .into_iter()
+            .filter(|segment| segment.stale_ratio() > 0.0)
+            .filter(|segment| {
+                // NOTE: Blob files are immutable, so the modification time is the time of creation
+                let age = std::fs::metadata(&segment.path)
+                    .and_then(|metadata| metadata.modified())
+                    .map(|created_at| now.duration_since(created_at).unwrap_or_default());
+
+                match age {
+                    Ok(age) => age >= self.max_age,
+                    Err(e) => {
+                        log::warn!(
+                            "Failed to get age of blob file {}: {e:?}",
+                            segment.path.display()
+                        );
+                        false
+                    }
+                }
+            })
+            .map(|segment| segment.id)
+            .collect()
+    }
+}
@@ -2,7 +2,11 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use crate::{blob_tree::value::MaybeInlineValue, coding::Decode, Memtable};
+use crate::{
+    blob_tree::{chunked::parse_chunk_key, value::MaybeInlineValue},
+    coding::Decode,
+    Memtable,
+};
 use std::{io::Cursor, sync::RwLockWriteGuard};
 use value_log::ValueHandle;
 
@@ -17,7 +21,7 @@ impl<'a> GcReader<'a> {
         Self { tree, memtable }
     }
 
-    fn get_internal(&self, key: &[u8]) -> crate::Result<Option<MaybeInlineValue>> {
+    pub fn get_internal(&self, key: &[u8]) -> crate::Result<Option<MaybeInlineValue>> {
         let Some(item) = self
             .tree
             .get_internal_entry_with_lock(self.memtable, key, true, None)?
@@ -31,6 +35,19 @@ impl<'a> GcReader<'a> {
 
         Ok(Some(item))
     }
+
+    /// Returns the value handle of a chunk, given its key in the chunk log.
+    pub fn get_chunk(&self, key: &[u8]) -> crate::Result<Option<ValueHandle>> {
+        let Some((key, idx)) = parse_chunk_key(key) else {
+            return Ok(None);
+        };
+
+        let Some(MaybeInlineValue::Chunked { chunks }) = self.get_internal(key)? else {
+            return Ok(None);
+        };
+
+        Ok(chunks.get(idx as usize).map(|(vhandle, _)| vhandle.clone()))
+    }
 }
 
 impl<'a> value_log::IndexReader for GcReader<'a> {
@@ -45,8 +62,19 @@ impl<'a> value_log::IndexReader for GcReader<'a> {
         };
 
         match item {
-            MaybeInlineValue::Inline(_) => Ok(None),
+            MaybeInlineValue::Inline(_) | MaybeInlineValue::Chunked { .. } => Ok(None),
             MaybeInlineValue::Indirect { vhandle, .. } => Ok(Some(vhandle)),
         }
     }
 }
+
+/// Index reader of the chunk log, in which every blob is a chunk of a chunked value
+pub struct ChunkGcReader<'a>(pub GcReader<'a>);
+
+impl value_log::IndexReader for ChunkGcReader<'_> {
+    fn get(&self, key: &[u8]) -> std::io::Result<Option<ValueHandle>> {
+        self.0
+            .get_chunk(key)
+            .map_err(|e| std::io::Error::other(e.to_string()))
+    }
+}
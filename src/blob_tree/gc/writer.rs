@@ -2,13 +2,19 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
+use super::reader::GcReader;
 use crate::{
-    blob_tree::value::MaybeInlineValue, coding::Encode, value::InternalValue, Memtable, SeqNo,
-    UserKey,
+    blob_tree::{chunked::parse_chunk_key, value::MaybeInlineValue},
+    coding::Encode,
+    value::InternalValue,
+    HashMap, Memtable, SeqNo, UserKey,
 };
 use std::sync::RwLockWriteGuard;
 use value_log::ValueHandle;
 
+/// Chunks of a chunked value, by the key of the value
+type ChunkLists = HashMap<UserKey, Vec<(ValueHandle, u32)>>;
+
 #[allow(clippy::module_name_repetitions)]
 pub struct GcWriter<'a> {
     seqno: SeqNo,
@@ -24,6 +30,30 @@ impl<'a> GcWriter<'a> {
             buffer: Vec::with_capacity(100),
         }
     }
+
+    /// Writes the values of relocated blobs back into the index tree.
+    fn write_values(
+        &self,
+        values: impl Iterator<Item = (UserKey, MaybeInlineValue)>,
+    ) -> std::io::Result<()> {
+        use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+
+        #[allow(clippy::significant_drop_in_scrutinee)]
+        for (key, value) in values {
+            let buf = value
+                .encode_into_vec()
+                .map_err(|e| IoError::new(IoErrorKind::Other, e.to_string()))?;
+
+            self.memtable.insert(InternalValue::from_components(
+                key,
+                buf,
+                self.seqno,
+                crate::ValueType::Value,
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 impl<'a> value_log::IndexWriter for GcWriter<'a> {
@@ -38,24 +68,90 @@ impl<'a> value_log::IndexWriter for GcWriter<'a> {
     }
 
     fn finish(&mut self) -> std::io::Result<()> {
-        use std::io::{Error as IoError, ErrorKind as IoErrorKind};
-
         log::trace!("Finish blob GC index writer");
 
-        #[allow(clippy::significant_drop_in_scrutinee)]
-        for (key, vhandle, size) in self.buffer.drain(..) {
-            let buf = MaybeInlineValue::Indirect { vhandle, size }
-                .encode_into_vec()
-                .map_err(|e| IoError::new(IoErrorKind::Other, e.to_string()))?;
+        let buffer = std::mem::take(&mut self.buffer);
 
-            self.memtable.insert(InternalValue::from_components(
-                key,
-                buf,
-                self.seqno,
-                crate::ValueType::Value,
-            ));
+        self.write_values(
+            buffer
+                .into_iter()
+                .map(|(key, vhandle, size)| (key, MaybeInlineValue::Indirect { vhandle, size })),
+        )
+    }
+}
+
+/// Index writer of the chunk log, which moves relocated chunks into the chunk lists of their values
+pub struct ChunkGcWriter<'a> {
+    tree: &'a crate::Tree,
+    inner: GcWriter<'a>,
+}
+
+impl<'a> ChunkGcWriter<'a> {
+    pub fn new(
+        tree: &'a crate::Tree,
+        seqno: SeqNo,
+        memtable: &'a RwLockWriteGuard<'a, Memtable>,
+    ) -> Self {
+        Self {
+            tree,
+            inner: GcWriter::new(seqno, memtable),
         }
+    }
 
-        Ok(())
+    /// Moves relocated chunks into the chunk lists of their values.
+    fn relocate_chunks(&mut self) -> crate::Result<ChunkLists> {
+        let reader = GcReader::new(self.tree, self.inner.memtable);
+
+        let mut chunked_values = ChunkLists::default();
+
+        for (key, vhandle, size) in self.inner.buffer.drain(..) {
+            let Some((value_key, idx)) = parse_chunk_key(&key) else {
+                continue;
+            };
+
+            if !chunked_values.contains_key(value_key) {
+                if let Some(MaybeInlineValue::Chunked { chunks }) =
+                    reader.get_internal(value_key)?
+                {
+                    chunked_values.insert(value_key.into(), chunks);
+                }
+            }
+
+            // NOTE: The GC reader only keeps chunks that are still referenced,
+            // and the memtable is locked, so the chunk list can not have changed
+            if let Some(chunk) = chunked_values
+                .get_mut(value_key)
+                .and_then(|chunks| chunks.get_mut(idx as usize))
+            {
+                *chunk = (vhandle, size);
+            }
+        }
+
+        Ok(chunked_values)
+    }
+}
+
+impl value_log::IndexWriter for ChunkGcWriter<'_> {
+    fn insert_indirect(
+        &mut self,
+        key: &[u8],
+        vhandle: ValueHandle,
+        size: u32,
+    ) -> std::io::Result<()> {
+        self.inner.insert_indirect(key, vhandle, size)
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        log::trace!("Finish chunk GC index writer");
+
+        let chunked_values = self
+            .relocate_chunks()
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+
+        self.inner.write_values(
+            chunked_values
+                .into_iter()
+                .map(|(key, chunks)| (key, MaybeInlineValue::Chunked { chunks })),
+        )
     }
 }
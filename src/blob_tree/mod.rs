@@ -2,47 +2,72 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
+mod batched;
+mod chunked;
 mod compression;
+mod fragmentation;
 mod gc;
 pub mod index;
+mod ranged;
+mod shared;
+mod streaming;
 pub mod value;
 
 use crate::{
     coding::{Decode, Encode},
     compaction::stream::CompactionStream,
-    file::BLOBS_FOLDER,
+    config::ConfigFlags,
+    file::{BLOBS_FOLDER, CHUNKS_FOLDER},
     r#abstract::{AbstractTree, RangeItem},
     tree::inner::MemtableId,
     value::InternalValue,
-    Config, KvPair, Memtable, SegmentId, SeqNo, Slice, Snapshot, UserKey, UserValue, ValueType,
+    CompressionType, Config, KvPair, Memtable, ReadOptions, SegmentId, SeqNo, Snapshot, Tree,
+    UserKey, UserValue, ValueType,
 };
+use batched::BatchedIter;
 use compression::MyCompressor;
-use gc::{reader::GcReader, writer::GcWriter};
+use gc::{
+    reader::{ChunkGcReader, GcReader},
+    writer::{ChunkGcWriter, GcWriter},
+};
 use index::IndexTree;
+use shared::{BlobFileOwnership, OwnedStrategy, Sharing};
 use std::{
     io::Cursor,
-    ops::RangeBounds,
+    ops::{Bound, RangeBounds},
     sync::{Arc, RwLockWriteGuard},
+    time::Instant,
 };
 use value::MaybeInlineValue;
-use value_log::ValueLog;
-
-fn resolve_value_handle(vlog: &ValueLog<MyCompressor>, item: RangeItem) -> RangeItem {
-    match item {
-        Ok((key, value)) => {
-            let mut cursor = Cursor::new(value);
-            let item = MaybeInlineValue::decode_from(&mut cursor)?;
-
-            match item {
-                MaybeInlineValue::Inline(bytes) => Ok((key, bytes)),
-                MaybeInlineValue::Indirect { vhandle, .. } => match vlog.get(&vhandle) {
-                    Ok(Some(bytes)) => Ok((key, bytes)),
-                    Err(e) => Err(e.into()),
-                    _ => panic!("value handle did not match any blob - this is a bug"),
-                },
-            }
-        }
-        Err(e) => Err(e),
+use value_log::{ValueHandle, ValueLog};
+
+pub use fragmentation::{BlobFileStats, FragmentationReport};
+pub use gc::age::AgeStrategy;
+pub use shared::SharedValueLog;
+pub use streaming::{StreamingValueReader, StreamingValueWriter};
+
+/// Extracts the value handles of an index tree item that point into
+/// the value log, or the chunk log if `chunk_log` is set, skipping inlined values
+fn index_vhandles(item: RangeItem, chunk_log: bool) -> Vec<std::io::Result<(ValueHandle, u32)>> {
+    use std::io::Error as IoError;
+    use MaybeInlineValue::{Chunked, Indirect, Inline};
+
+    let Ok((_, v)) = item else {
+        return vec![Err(IoError::other(
+            "Failed to load KV pair from index tree",
+        ))];
+    };
+
+    let mut cursor = Cursor::new(v);
+    let value = match MaybeInlineValue::decode_from(&mut cursor) {
+        Ok(v) => v,
+        Err(e) => return vec![Err(IoError::other(e.to_string()))],
+    };
+
+    match value {
+        Indirect { vhandle, size } if !chunk_log => vec![Ok((vhandle, size))],
+        Chunked { chunks } if chunk_log => chunks.into_iter().map(Ok).collect(),
+        Inline(_) | Indirect { .. } | Chunked { .. } => vec![],
     }
 }
 
@@ -62,60 +87,295 @@ pub struct BlobTree {
     /// Log-structured value-log that stores large values
     #[doc(hidden)]
     pub blobs: ValueLog<MyCompressor>,
+
+    /// Value log that stores the chunks of chunked values
+    ///
+    /// Chunks are kept apart from other blobs, so garbage collection
+    /// never needs to guess whether a blob is a chunk from its key.
+    /// The chunk log is never shared with other trees.
+    chunks: ValueLog<MyCompressor>,
+
+    /// Owned blob files, if the value log is shared with other trees
+    sharing: Option<Arc<Sharing>>,
 }
 
 impl BlobTree {
-    pub(crate) fn open(config: Config) -> crate::Result<Self> {
-        let path = &config.path;
+    /// Resolves a value of the index tree, reading it from the value log if it is not inlined.
+    fn resolve_value(&self, value: MaybeInlineValue) -> crate::Result<Option<UserValue>> {
+        use MaybeInlineValue::{Chunked, Indirect, Inline};
+
+        Ok(match value {
+            Inline(bytes) => Some(bytes),
+            Indirect { vhandle, .. } => {
+                // Resolve indirection using value log
+                self.blobs.get(&vhandle)?
+            }
+            Chunked { chunks } => Some(chunked::read_chunks(&self.chunks, &chunks)?),
+        })
+    }
+
+    /// Returns the compression of the blob files of the value log.
+    fn blob_compression(&self) -> CompressionType {
+        self.sharing
+            .as_ref()
+            .map_or(self.index.config.blob_compression, |sharing| {
+                sharing.vlog.compression()
+            })
+    }
+
+    /// Registers the blob files of a finished blob writer into the value log.
+    fn register_blob_writer(
+        &self,
+        blob_writer: value_log::SegmentWriter<MyCompressor>,
+    ) -> crate::Result<()> {
+        if let Some(sharing) = &self.sharing {
+            sharing.vlog.track_blob_files(&sharing.owner, || {
+                self.blobs.register_writer(blob_writer).map_err(Into::into)
+            })?;
+        } else {
+            self.blobs.register_writer(blob_writer)?;
+        }
 
-        let vlog_path = path.join(BLOBS_FOLDER);
-        let vlog_cfg = value_log::Config::<MyCompressor>::default()
-            .blob_cache(config.blob_cache.clone())
-            .segment_size_bytes(config.blob_file_target_size)
-            .compression(MyCompressor(config.blob_compression));
+        Ok(())
+    }
 
+    /// Starts writing a large value, which is streamed into the value log
+    /// without going through the memtable.
+    ///
+    /// The key is committed once the returned writer is finished.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let folder = tempfile::tempdir()?;
+    /// use lsm_tree::{AbstractTree, Config};
+    /// use std::io::Write;
+    ///
+    /// let tree = Config::new(folder).open_as_blob_tree()?;
+    ///
+    /// let mut writer = tree.insert_streaming("a", 0);
+    /// writer.write_all(b"hello ")?;
+    /// writer.write_all(b"world")?;
+    /// writer.finish()?;
+    ///
+    /// assert_eq!(Some("hello world".as_bytes().into()), tree.get("a")?);
+    /// #
+    /// # Ok::<(), lsm_tree::Error>(())
+    /// ```
+    pub fn insert_streaming<K: Into<UserKey>>(
+        &self,
+        key: K,
+        seqno: SeqNo,
+    ) -> StreamingValueWriter<'_> {
+        StreamingValueWriter::new(self, key.into(), seqno)
+    }
+
+    /// Writes a value from the given reader into the value log, and commits the key.
+    ///
+    /// Like with [`BlobTree::insert_streaming`], the value bypasses the memtable,
+    /// and values that reach the configured chunk size
+    /// (see [`Config::blob_chunk_size`]) are split into chunks.
+    ///
+    /// Returns the added item's size and new size of the memtable.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the value exceeds
+    /// the maximum streamed value size.
+    pub fn insert_from_reader<K: Into<UserKey>, R: std::io::Read>(
+        &self,
+        key: K,
+        reader: &mut R,
+        seqno: SeqNo,
+    ) -> crate::Result<(u32, u32)> {
+        let mut writer = self.insert_streaming(key, seqno);
+        std::io::copy(reader, &mut writer)?;
+        writer.finish()
+    }
+
+    /// Returns a reader over the value of the given key, reading it piece by piece.
+    ///
+    /// Chunked values (see [`BlobTree::insert_streaming`]) are read one chunk at a time,
+    /// so they never need to be held in memory as a whole.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn get_streaming<K: AsRef<[u8]>>(
+        &self,
+        key: K,
+        seqno: Option<SeqNo>,
+    ) -> crate::Result<Option<StreamingValueReader>> {
+        let key = key.as_ref();
+
+        let item = match seqno {
+            Some(seqno) => self.index.get_internal_with_seqno(key, seqno)?,
+            None => self.index.get_internal(key)?,
+        };
+
+        Ok(item.map(|value| StreamingValueReader::new(self, value)))
+    }
+
+    pub(crate) fn open(config: Config) -> crate::Result<Self> {
+        // NOTE: Blob files are not encrypted, so values would be stored in plaintext
+        if config.cipher().is_some() {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "blob trees do not support encryption",
+            )));
+        }
+
+        // NOTE: Open the index tree first, because it restores
+        // the persisted blob settings into its config
         let index: IndexTree = config.open()?.into();
+        let config = &index.config;
+
+        let vlog_cfg = || {
+            value_log::Config::<MyCompressor>::default()
+                .blob_cache(config.blob_cache.clone())
+                .segment_size_bytes(config.blob_file_target_size)
+                .compression(MyCompressor(config.blob_compression))
+        };
+
+        let (blobs, sharing) = if let Some(vlog) = config.shared_value_log.clone() {
+            vlog.register(&index.0)?;
+
+            let owner = BlobFileOwnership::recover(&config.path)?;
+            let blobs = vlog.blobs().clone();
+
+            (blobs, Some(Arc::new(Sharing { vlog, owner })))
+        } else {
+            let vlog_path = config.path.join(BLOBS_FOLDER);
+            (ValueLog::open(vlog_path, vlog_cfg())?, None)
+        };
+
+        let chunks = ValueLog::open(config.path.join(CHUNKS_FOLDER), vlog_cfg())?;
 
         Ok(Self {
             index,
-            blobs: ValueLog::open(vlog_path, vlog_cfg)?,
+            blobs,
+            chunks,
+            sharing,
         })
     }
 
+    /// Inserts a key-value pair, checking the key & value sizes first.
+    ///
+    /// See [`Tree::try_insert`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the key is empty, or the key or value
+    /// exceed the configured maximum sizes.
+    pub fn try_insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+        &self,
+        key: K,
+        value: V,
+        seqno: SeqNo,
+    ) -> crate::Result<(u32, u32)> {
+        self.index
+            .check_kv_size(key.as_ref(), value.as_ref().len())?;
+        Ok(self.insert(key, value, seqno))
+    }
+
+    /// Counts the items in the given range, without fetching any blobs.
+    ///
+    /// See [`Tree::range_len`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn range_len<K: AsRef<[u8]>, R: RangeBounds<K>>(&self, range: R) -> crate::Result<usize> {
+        self.index.range_len(range)
+    }
+
+    /// Counts the items with the given prefix, without fetching any blobs.
+    ///
+    /// See [`Tree::prefix_len`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn prefix_len<K: AsRef<[u8]>>(&self, prefix: K) -> crate::Result<usize> {
+        self.index.prefix_len(prefix)
+    }
+
     /// Scans the index tree, collecting statistics about
     /// value log fragmentation
-    #[doc(hidden)]
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the value log is shared
+    /// with a tree that is not open.
     pub fn gc_scan_stats(&self, seqno: SeqNo) -> crate::Result<crate::GcReport> {
-        use std::io::{Error as IoError, ErrorKind as IoErrorKind};
-        use MaybeInlineValue::{Indirect, Inline};
+        let Some(sharing) = &self.sharing else {
+            // IMPORTANT: Lock + snapshot memtable to avoid read skew + preventing tampering with memtable
+            let _memtable_lock = self.index.read_lock_active_memtable();
+            let snapshot = self.index.snapshot(seqno);
 
-        // IMPORTANT: Lock + snapshot memtable to avoid read skew + preventing tampering with memtable
-        let _memtable_lock = self.index.read_lock_active_memtable();
-        let snapshot = self.index.snapshot(seqno);
+            self.scan_chunk_stats(&snapshot)?;
 
-        self.blobs
-            .scan_for_stats(snapshot.iter().filter_map(|kv| {
-                let Ok((_, v)) = kv else {
-                    return Some(Err(IoError::new(
-                        IoErrorKind::Other,
-                        "Failed to load KV pair from index tree",
-                    )));
-                };
+            return self
+                .blobs
+                .scan_for_stats(snapshot.iter().flat_map(|item| index_vhandles(item, false)))
+                .map_err(Into::into);
+        };
 
-                let mut cursor = Cursor::new(v);
-                let value = match MaybeInlineValue::decode_from(&mut cursor) {
-                    Ok(v) => v,
-                    Err(e) => return Some(Err(IoError::new(IoErrorKind::Other, e.to_string()))),
-                };
+        // NOTE: The value log is shared, so blobs may be referenced by any of the trees
+        let Some(trees) = sharing.vlog.trees() else {
+            return Err(crate::Error::Io(std::io::Error::other(
+                "not all trees that share the value log are open",
+            )));
+        };
 
-                match value {
-                    Indirect { vhandle, size } => Some(Ok((vhandle, size))),
-                    Inline(_) => None,
-                }
-            }))
+        // IMPORTANT: Lock + snapshot memtables to avoid read skew + preventing tampering with memtables
+        let _memtable_locks = trees
+            .iter()
+            .map(Tree::read_lock_active_memtable)
+            .collect::<Vec<_>>();
+
+        let snapshots = trees
+            .iter()
+            .map(|tree| tree.snapshot(seqno))
+            .collect::<Vec<_>>();
+
+        self.scan_chunk_stats(&self.index.snapshot(seqno))?;
+
+        self.blobs
+            .scan_for_stats(
+                snapshots
+                    .iter()
+                    .flat_map(Snapshot::iter)
+                    .flat_map(|item| index_vhandles(item, false)),
+            )
             .map_err(Into::into)
     }
 
+    /// Scans the index tree to find stale chunks in the chunk log.
+    fn scan_chunk_stats(&self, snapshot: &Snapshot) -> crate::Result<()> {
+        self.chunks
+            .scan_for_stats(snapshot.iter().flat_map(|item| index_vhandles(item, true)))?;
+
+        Ok(())
+    }
+
+    /// Rewrites chunk files of the chunk log, and drops stale chunk files.
+    fn apply_chunk_gc_strategy(
+        &self,
+        strategy: &impl value_log::GcStrategy<MyCompressor>,
+        seqno: SeqNo,
+        memtable_lock: &RwLockWriteGuard<'_, Memtable>,
+    ) -> crate::Result<()> {
+        self.chunks.apply_gc_strategy(
+            strategy,
+            &ChunkGcReader(GcReader::new(&self.index, memtable_lock)),
+            ChunkGcWriter::new(&self.index, seqno, memtable_lock),
+        )?;
+        self.chunks.drop_stale_segments()?;
+
+        Ok(())
+    }
+
     pub fn apply_gc_strategy(
         &self,
         strategy: &impl value_log::GcStrategy<MyCompressor>,
@@ -124,14 +384,137 @@ impl BlobTree {
         // IMPORTANT: Write lock memtable to avoid read skew
         let memtable_lock = self.index.lock_active_memtable();
 
-        self.blobs.apply_gc_strategy(
-            strategy,
-            &GcReader::new(&self.index, &memtable_lock),
-            GcWriter::new(seqno, &memtable_lock),
-        )?;
+        self.apply_chunk_gc_strategy(strategy, seqno, &memtable_lock)?;
+
+        let Some(sharing) = &self.sharing else {
+            self.blobs.apply_gc_strategy(
+                strategy,
+                &GcReader::new(&self.index, &memtable_lock),
+                GcWriter::new(seqno, &memtable_lock),
+            )?;
 
-        // NOTE: We still have the memtable lock, can't use gc_drop_stale because recursive locking
-        self.blobs.drop_stale_segments().map_err(Into::into)
+            // NOTE: We still have the memtable lock, can't use gc_drop_stale because recursive locking
+            return self.blobs.drop_stale_segments().map_err(Into::into);
+        };
+
+        // NOTE: Blobs of other trees can not be found in our index tree,
+        // so we can only rewrite blob files that we own
+        let strategy = OwnedStrategy {
+            inner: strategy,
+            owner: &sharing.owner,
+        };
+
+        sharing.vlog.track_blob_files(&sharing.owner, || {
+            self.blobs.apply_gc_strategy(
+                &strategy,
+                &GcReader::new(&self.index, &memtable_lock),
+                GcWriter::new(seqno, &memtable_lock),
+            )?;
+            self.blobs.drop_stale_segments().map_err(Into::into)
+        })
+    }
+
+    /// Returns per-blob-file fragmentation statistics of the value log.
+    ///
+    /// The statistics reflect the last scan of the index tree, so
+    /// [`BlobTree::gc_scan_stats`] should be called first to get an up-to-date report.
+    #[must_use]
+    pub fn fragmentation(&self) -> FragmentationReport {
+        let mut blob_files = self
+            .blobs
+            .manifest
+            .list_segments()
+            .into_iter()
+            .map(|segment| {
+                let item_count = segment.meta.item_count;
+                let stale_items = segment.gc_stats.stale_items();
+
+                BlobFileStats {
+                    id: segment.id,
+                    total_bytes: segment.meta.total_uncompressed_bytes,
+                    stale_bytes: segment.gc_stats.stale_bytes(),
+                    item_count,
+                    live_item_count: item_count.saturating_sub(stale_items),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        blob_files.sort_by_key(|x| x.id);
+
+        FragmentationReport { blob_files }
+    }
+
+    /// Runs a garbage collection cycle on the value log, rewriting the most
+    /// fragmented blob files until the value log's space amplification is below `factor`.
+    ///
+    /// Live values of rewritten blob files are written back into the index tree
+    /// using the given `seqno`, which also serves as the snapshot for scanning the index tree.
+    ///
+    /// Returns the amount of bytes that were reclaimed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the value log is shared
+    /// with a tree that is not open.
+    pub fn gc_with_space_amp_target(&self, factor: f32, seqno: SeqNo) -> crate::Result<u64> {
+        let strategy = value_log::SpaceAmpStrategy::new(factor);
+        self.run_gc(&strategy, seqno)
+    }
+
+    /// Runs a garbage collection cycle on the value log, rewriting all
+    /// blob files whose stale ratio exceeds `ratio`.
+    ///
+    /// Live values of rewritten blob files are written back into the index tree
+    /// using the given `seqno`, which also serves as the snapshot for scanning the index tree.
+    ///
+    /// Returns the amount of bytes that were reclaimed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the value log is shared
+    /// with a tree that is not open.
+    pub fn gc_with_staleness_threshold(&self, ratio: f32, seqno: SeqNo) -> crate::Result<u64> {
+        let strategy = value_log::StaleThresholdStrategy::new(ratio);
+        self.run_gc(&strategy, seqno)
+    }
+
+    /// Runs a garbage collection cycle on the value log, rewriting all
+    /// blob files that are older than `max_age` and contain any stale data.
+    ///
+    /// Live values of rewritten blob files are written back into the index tree
+    /// using the given `seqno`, which also serves as the snapshot for scanning the index tree.
+    ///
+    /// Returns the amount of bytes that were reclaimed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the value log is shared
+    /// with a tree that is not open.
+    pub fn gc_with_age_threshold(
+        &self,
+        max_age: std::time::Duration,
+        seqno: SeqNo,
+    ) -> crate::Result<u64> {
+        let strategy = AgeStrategy::new(max_age);
+        self.run_gc(&strategy, seqno)
+    }
+
+    fn run_gc(
+        &self,
+        strategy: &impl value_log::GcStrategy<MyCompressor>,
+        seqno: SeqNo,
+    ) -> crate::Result<u64> {
+        let space_before = self.blob_disk_space();
+
+        self.gc_scan_stats(seqno)?;
+        self.apply_gc_strategy(strategy, seqno)?;
+
+        let space_after = self.blob_disk_space();
+        let reclaimed = space_before.saturating_sub(space_after);
+
+        log::info!("Blob GC reclaimed {reclaimed} bytes");
+
+        Ok(reclaimed)
     }
 
     /// Drops all stale blob segment files
@@ -140,7 +523,20 @@ impl BlobTree {
         // IMPORTANT: Write lock memtable to avoid read skew
         let _lock = self.index.lock_active_memtable();
 
-        self.blobs.drop_stale_segments().map_err(Into::into)
+        let dropped_chunk_bytes = self.chunks.drop_stale_segments()?;
+
+        let Some(sharing) = &self.sharing else {
+            return Ok(dropped_chunk_bytes + self.blobs.drop_stale_segments()?);
+        };
+
+        sharing.vlog.track_blob_files(&sharing.owner, || {
+            Ok(dropped_chunk_bytes + self.blobs.drop_stale_segments()?)
+        })
+    }
+
+    /// Returns the disk space used by the value log and the chunk log.
+    fn blob_disk_space(&self) -> u64 {
+        self.blobs.manifest.disk_space_used() + self.chunks.manifest.disk_space_used()
     }
 
     #[doc(hidden)]
@@ -160,6 +556,68 @@ impl BlobTree {
 
         Ok(Some(segment))
     }
+
+    /// Returns the GC watermark of the index tree, see [`Tree::gc_watermark`].
+    #[must_use]
+    pub fn gc_watermark(&self) -> SeqNo {
+        self.index.gc_watermark()
+    }
+
+    /// Synchronously flushes the active memtable to a disk segment,
+    /// using the [GC watermark](Tree::gc_watermark) as GC threshold.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn flush_active_memtable_auto(&self) -> crate::Result<Option<Arc<crate::Segment>>> {
+        self.flush_active_memtable(self.gc_watermark())
+    }
+
+    /// Creates a writer for a segment of the index tree that is written by a flush.
+    #[cfg_attr(not(feature = "bloom"), allow(unused_mut))]
+    fn create_index_segment_writer(
+        &self,
+        segment_id: SegmentId,
+        folder: std::path::PathBuf,
+    ) -> crate::Result<crate::segment::writer::Writer> {
+        use crate::segment::writer::{Options, Writer as SegmentWriter};
+
+        let mut segment_writer = SegmentWriter::new(Options {
+            segment_id,
+            data_block_size: self.index.config.data_block_size,
+            index_block_size: self.index.config.index_block_size,
+            evict_tombstones: false,
+            folder,
+        })?
+        .use_compression(self.index.compression())
+        .use_cipher(self.index.config.segment_cipher())
+        .use_sync(self.index.sync_tracker.sync_on_write())
+        .use_seqno_index(self.index.config.flags.contains(ConfigFlags::SEQNO_INDEX))
+        .use_prefix_fences(self.index.config.prefix_fence_len)
+        .use_one_level_index(self.index.config.one_level_index_max_size)
+        .use_shortened_index_keys(
+            self.index
+                .config
+                .flags
+                .contains(ConfigFlags::SHORTEN_INDEX_KEYS),
+        )
+        .use_value_checksums(
+            self.index
+                .config
+                .flags
+                .contains(ConfigFlags::VALUE_CHECKSUMS),
+        )
+        .use_logical_clock(self.index.config.deterministic_seed.is_some());
+
+        #[cfg(feature = "bloom")]
+        {
+            segment_writer = segment_writer
+                .use_bloom_policy(self.index.config.bloom_policy(0))
+                .use_bloom_hash_count(self.index.config.bloom_hash_count);
+        }
+
+        Ok(segment_writer)
+    }
 }
 
 impl AbstractTree for BlobTree {
@@ -179,7 +637,8 @@ impl AbstractTree for BlobTree {
     fn verify(&self) -> crate::Result<usize> {
         let index_tree_sum = self.index.verify()?;
         let vlog_sum = self.blobs.verify()?;
-        Ok(index_tree_sum + vlog_sum)
+        let chunk_log_sum = self.chunks.verify()?;
+        Ok(index_tree_sum + vlog_sum + chunk_log_sum)
     }
 
     fn keys_with_seqno(
@@ -209,6 +668,26 @@ impl AbstractTree for BlobTree {
         Box::new(self.iter().map(|x| x.map(|(_, v)| v)))
     }
 
+    fn first_key_value(&self) -> crate::Result<Option<KvPair>> {
+        // NOTE: Only reads a single item, so it is not throttled like expensive scans
+        self.index
+            .0
+            .create_iter(None, None)
+            .next()
+            .map(|item| batched::resolve_item(&self.blobs, &self.chunks, item))
+            .transpose()
+    }
+
+    fn last_key_value(&self) -> crate::Result<Option<KvPair>> {
+        // NOTE: Only reads a single item, so it is not throttled like expensive scans
+        self.index
+            .0
+            .create_iter(None, None)
+            .next_back()
+            .map(|item| batched::resolve_item(&self.blobs, &self.chunks, item))
+            .transpose()
+    }
+
     fn flush_memtable(
         &self,
         segment_id: SegmentId,
@@ -216,45 +695,53 @@ impl AbstractTree for BlobTree {
         eviction_seqno: SeqNo,
     ) -> crate::Result<Option<Arc<crate::Segment>>> {
         use crate::{
+            error::{ErrorContext, Operation},
             file::SEGMENTS_FOLDER,
-            segment::writer::{Options, Writer as SegmentWriter},
         };
         use value::MaybeInlineValue;
 
+        let start = Instant::now();
+
         let lsm_segment_folder = self.index.config.path.join(SEGMENTS_FOLDER);
 
         log::debug!("flushing memtable & performing key-value separation");
         log::debug!("=> to LSM segments in {:?}", lsm_segment_folder);
         log::debug!("=> to blob segment at {:?}", self.blobs.path);
 
-        let mut segment_writer = SegmentWriter::new(Options {
-            segment_id,
-            data_block_size: self.index.config.data_block_size,
-            index_block_size: self.index.config.index_block_size,
-            evict_tombstones: false,
-            folder: lsm_segment_folder,
-        })?
-        .use_compression(self.index.config.compression);
+        let context = ErrorContext::new(Operation::Flush)
+            .with_segment_id(segment_id)
+            .with_path(lsm_segment_folder.join(segment_id.to_string()));
 
-        #[cfg(feature = "bloom")]
-        {
-            segment_writer = segment_writer.use_bloom_policy(
-                crate::segment::writer::BloomConstructionPolicy::FpRate(0.0001),
-            );
-        }
+        let mut segment_writer = self
+            .create_index_segment_writer(segment_id, lsm_segment_folder)
+            .map_err(|e| e.with_context(context.clone()))?;
 
         let mut blob_writer = self.blobs.get_writer()?;
 
         let iter = memtable.iter().map(Ok);
         let compaction_filter = CompactionStream::new(iter, eviction_seqno);
 
+        let mut checksum = self
+            .index
+            .config
+            .flags
+            .contains(ConfigFlags::VERIFY_FLUSHES)
+            .then(crate::tree::flush_verify::ItemStreamChecksum::new);
+
+        let mut write_index = |item: InternalValue| {
+            if let Some(checksum) = &mut checksum {
+                checksum.update(&item);
+            }
+            segment_writer.write(item)
+        };
+
         for item in compaction_filter {
             let item = item?;
 
             if item.is_tombstone() {
                 // NOTE: Still need to add tombstone to index tree
                 // But no blob to blob writer
-                segment_writer.write(InternalValue::new(item.key, vec![]))?;
+                write_index(InternalValue::new(item.key, vec![]))?;
                 continue;
             }
 
@@ -263,15 +750,15 @@ impl AbstractTree for BlobTree {
             let value = MaybeInlineValue::decode_from(&mut cursor)?;
             let value = match value {
                 MaybeInlineValue::Inline(value) => value,
-                indirection @ MaybeInlineValue::Indirect { .. } => {
+                indirection @ (MaybeInlineValue::Indirect { .. }
+                | MaybeInlineValue::Chunked { .. }) => {
                     // NOTE: This is a previous indirection, just write it to index tree
                     // without writing the blob again
 
                     let mut serialized_indirection = vec![];
                     indirection.encode_into(&mut serialized_indirection)?;
 
-                    segment_writer
-                        .write(InternalValue::new(item.key.clone(), serialized_indirection))?;
+                    write_index(InternalValue::new(item.key.clone(), serialized_indirection))?;
 
                     continue;
                 }
@@ -291,22 +778,33 @@ impl AbstractTree for BlobTree {
                 let mut serialized_indirection = vec![];
                 indirection.encode_into(&mut serialized_indirection)?;
 
-                segment_writer
-                    .write(InternalValue::new(item.key.clone(), serialized_indirection))?;
+                write_index(InternalValue::new(item.key.clone(), serialized_indirection))?;
 
                 blob_writer.write(&item.key.user_key, value)?;
             } else {
                 let direct = MaybeInlineValue::Inline(value);
                 let serialized_direct = direct.encode_into_vec()?;
-                segment_writer.write(InternalValue::new(item.key, serialized_direct))?;
+                write_index(InternalValue::new(item.key, serialized_direct))?;
             }
         }
 
         log::trace!("Register blob writer into value log");
-        self.blobs.register_writer(blob_writer)?;
+        self.register_blob_writer(blob_writer)?;
 
         log::trace!("Creating segment");
-        self.index.consume_writer(segment_id, segment_writer)
+        let segment = self
+            .index
+            .consume_writer(segment_id, segment_writer)
+            .map_err(|e| e.with_context(context.clone()))?;
+
+        if let (Some(segment), Some(checksum)) = (&segment, &checksum) {
+            self.index
+                .verify_flushed_segment(segment, checksum, context)?;
+        }
+
+        self.index.record_flush(segment.as_ref(), start.elapsed());
+
+        Ok(segment)
     }
 
     fn register_segments(&self, segments: &[Arc<crate::Segment>]) -> crate::Result<()> {
@@ -371,14 +869,42 @@ impl AbstractTree for BlobTree {
 
     // NOTE: Override the default implementation to not fetch
     // data from the value log, so we get much faster key reads
-    fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> crate::Result<bool> {
-        self.index.contains_key(key)
+    fn contains_key_bytes(&self, key: &[u8], seqno: Option<SeqNo>) -> crate::Result<bool> {
+        self.index.contains_key_bytes(key, seqno)
     }
 
-    // NOTE: Override the default implementation to not fetch
-    // data from the value log, so we get much faster key reads
-    fn contains_key_with_seqno<K: AsRef<[u8]>>(&self, key: K, seqno: SeqNo) -> crate::Result<bool> {
-        self.index.contains_key_with_seqno(key, seqno)
+    // NOTE: Override the default implementation to only read
+    // the requested window of blobs from the value log
+    fn get_range_of_value_bytes(
+        &self,
+        key: &[u8],
+        range: (Bound<usize>, Bound<usize>),
+        seqno: Option<SeqNo>,
+    ) -> crate::Result<Option<UserValue>> {
+        use MaybeInlineValue::{Chunked, Indirect, Inline};
+
+        let item = match seqno {
+            Some(seqno) => self.index.get_internal_with_seqno(key, seqno)?,
+            None => self.index.get_internal(key)?,
+        };
+
+        let value = match item {
+            Some(Inline(bytes)) => Some(crate::r#abstract::value_window(&bytes, &range)),
+            Some(Indirect { vhandle, .. }) => {
+                ranged::read_blob_window(&self.blobs, self.blob_compression(), &vhandle, &range)?
+            }
+            Some(Chunked { chunks }) => Some(ranged::read_chunks_window(
+                &self.chunks,
+                self.index.config.blob_compression,
+                &chunks,
+                &range,
+            )?),
+            None => None,
+        };
+
+        self.index.emit_read(value.as_ref());
+
+        Ok(value)
     }
 
     // NOTE: Override the default implementation to not fetch
@@ -389,7 +915,7 @@ impl AbstractTree for BlobTree {
 
     #[must_use]
     fn disk_space(&self) -> u64 {
-        self.index.disk_space() + self.blobs.manifest.disk_space_used()
+        self.index.disk_space() + self.blob_disk_space()
     }
 
     fn get_highest_memtable_seqno(&self) -> Option<SeqNo> {
@@ -406,75 +932,53 @@ impl AbstractTree for BlobTree {
         Snapshot::new(Blob(self.clone()), seqno)
     }
 
-    fn iter_with_seqno(
+    fn range_bounds(
         &self,
-        seqno: SeqNo,
+        bounds: (Bound<UserKey>, Bound<UserKey>),
+        seqno: Option<SeqNo>,
         index: Option<Arc<Memtable>>,
     ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
-        self.range_with_seqno::<UserKey, _>(.., seqno, index)
-    }
-
-    fn range_with_seqno<K: AsRef<[u8]>, R: RangeBounds<K>>(
-        &self,
-        range: R,
-        seqno: SeqNo,
-        index: Option<Arc<Memtable>>,
-    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
-        let vlog = self.blobs.clone();
-        Box::new(
-            self.index
-                .0
-                .create_range(&range, Some(seqno), index)
-                .map(move |item| resolve_value_handle(&vlog, item)),
-        )
+        self.index.throttle_scan(&bounds, || {
+            BatchedIter::new(
+                self.index.0.create_range(&bounds, seqno, index),
+                self.blobs.clone(),
+                self.chunks.clone(),
+                self.index.read_pool.clone(),
+            )
+        })
     }
 
-    fn prefix_with_seqno<K: AsRef<[u8]>>(
+    fn range_bounds_with_options(
         &self,
-        prefix: K,
-        seqno: SeqNo,
+        bounds: (Bound<UserKey>, Bound<UserKey>),
+        options: &ReadOptions,
         index: Option<Arc<Memtable>>,
     ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
-        let vlog = self.blobs.clone();
-        Box::new(
-            self.index
+        // NOTE: Values are empty anyway, so blobs do not need to be resolved
+        if options.key_only {
+            return self
+                .index
                 .0
-                .create_prefix(prefix, Some(seqno), index)
-                .map(move |item| resolve_value_handle(&vlog, item)),
-        )
-    }
-
-    fn range<K: AsRef<[u8]>, R: RangeBounds<K>>(
-        &self,
-        range: R,
-    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
-        let vlog = self.blobs.clone();
-        Box::new(
-            self.index
-                .0
-                .create_range(&range, None, None)
-                .map(move |item| resolve_value_handle(&vlog, item)),
-        )
-    }
+                .range_bounds_with_options(bounds, options, index);
+        }
 
-    fn prefix<K: AsRef<[u8]>>(
-        &self,
-        prefix: K,
-    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
-        let vlog = self.blobs.clone();
-        Box::new(
-            self.index
-                .0
-                .create_prefix(prefix, None, None)
-                .map(move |item| resolve_value_handle(&vlog, item)),
-        )
+        self.index.throttle_scan(&bounds.clone(), || {
+            options.limited(BatchedIter::new(
+                self.index
+                    .0
+                    .create_range_with_options(bounds, options, index),
+                self.blobs.clone(),
+                self.chunks.clone(),
+                self.index.read_pool.clone(),
+            ))
+        })
     }
 
-    fn raw_insert_with_lock<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+    fn raw_insert_bytes_with_lock(
         &self,
         lock: &RwLockWriteGuard<'_, Memtable>,
-        key: K,
-        value: V,
+        key: &[u8],
+        value: &[u8],
         seqno: SeqNo,
         r#type: ValueType,
     ) -> (u32, u32) {
@@ -483,68 +987,84 @@ impl AbstractTree for BlobTree {
         // NOTE: Initially, we always write an inline value
         // On memtable flush, depending on the values' sizes, they will be separated
         // into inline or indirect values
-        let item = MaybeInlineValue::Inline(value.as_ref().into());
+        let item = MaybeInlineValue::Inline(value.into());
 
         let value = item.encode_into_vec().expect("should serialize");
 
-        let value = InternalValue::from_components(key.as_ref(), value, seqno, r#type);
+        let value = InternalValue::from_components(key, value, seqno, r#type);
         lock.insert(value)
     }
 
-    fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V, seqno: SeqNo) -> (u32, u32) {
+    fn insert_bytes(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        seqno: SeqNo,
+        r#type: ValueType,
+    ) -> (u32, u32) {
         use value::MaybeInlineValue;
 
+        // NOTE: Tombstones have no value to separate
+        if r#type != ValueType::Value {
+            return self.index.insert_bytes(key, value, seqno, r#type);
+        }
+
         // NOTE: Initially, we always write an inline value
         // On memtable flush, depending on the values' sizes, they will be separated
         // into inline or indirect values
-        let item = MaybeInlineValue::Inline(value.as_ref().into());
+        let item = MaybeInlineValue::Inline(value.into());
 
         let value = item.encode_into_vec().expect("should serialize");
 
-        self.index.insert(key, value, seqno)
+        self.index.insert_bytes(key, &value, seqno, r#type)
     }
 
-    fn get_with_seqno<K: AsRef<[u8]>>(
+    fn get_many_bytes(
         &self,
-        key: K,
+        keys: &[&[u8]],
         seqno: SeqNo,
-    ) -> crate::Result<Option<crate::UserValue>> {
-        use value::MaybeInlineValue::{Indirect, Inline};
-
-        let Some(value) = self.index.get_internal_with_seqno(key.as_ref(), seqno)? else {
-            return Ok(None);
-        };
+    ) -> crate::Result<Vec<Option<UserValue>>> {
+        // NOTE: The index tree is read consistently, blobs are never rewritten in place,
+        // so resolving them afterwards still returns the values of the snapshot
+        let items = self.index.get_many_internal_entries(keys, seqno)?;
+
+        let mut values = Vec::with_capacity(items.len());
+
+        for item in items {
+            let value = match item {
+                Some(item) => {
+                    let item = MaybeInlineValue::decode_from(&mut Cursor::new(item.value))?;
+                    self.resolve_value(item)?
+                }
+                None => None,
+            };
 
-        match value {
-            Inline(bytes) => Ok(Some(bytes)),
-            Indirect { vhandle, .. } => {
-                // Resolve indirection using value log
-                Ok(self.blobs.get(&vhandle)?.map(Slice::from))
-            }
+            self.index.emit_read(value.as_ref());
+            values.push(value);
         }
+
+        Ok(values)
     }
 
-    fn get<K: AsRef<[u8]>>(&self, key: K) -> crate::Result<Option<Slice>> {
-        use value::MaybeInlineValue::{Indirect, Inline};
+    fn get_bytes(&self, key: &[u8], seqno: Option<SeqNo>) -> crate::Result<Option<UserValue>> {
+        #[cfg(feature = "metrics")]
+        let start = Instant::now();
 
-        let Some(value) = self.index.get_internal(key.as_ref())? else {
-            return Ok(None);
+        let item = match seqno {
+            Some(seqno) => self.index.get_internal_with_seqno(key, seqno)?,
+            None => self.index.get_internal(key)?,
         };
 
-        match value {
-            Inline(bytes) => Ok(Some(bytes)),
-            Indirect { vhandle, .. } => {
-                // Resolve indirection using value log
-                Ok(self.blobs.get(&vhandle)?.map(Slice::from))
-            }
-        }
-    }
+        let value = match item {
+            Some(item) => self.resolve_value(item)?,
+            None => None,
+        };
 
-    fn remove<K: AsRef<[u8]>>(&self, key: K, seqno: SeqNo) -> (u32, u32) {
-        self.index.remove(key, seqno)
-    }
+        #[cfg(feature = "metrics")]
+        self.index.latencies.get.record(start.elapsed());
+
+        self.index.emit_read(value.as_ref());
 
-    fn remove_weak<K: AsRef<[u8]>>(&self, key: K, seqno: SeqNo) -> (u32, u32) {
-        self.index.remove_weak(key, seqno)
+        Ok(value)
     }
 }
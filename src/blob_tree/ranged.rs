@@ -0,0 +1,114 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::compression::MyCompressor;
+use crate::{coding::DecodeError, r#abstract::value_window_bounds, CompressionType, UserValue};
+use byteorder::{BigEndian, ReadBytesExt};
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    ops::Bound,
+};
+use value_log::{ValueHandle, ValueLog};
+
+/// Header of a blob in a blob file
+///
+/// NOTE: Needs to match the blob format of `value-log`
+const BLOB_HEADER_MAGIC: &[u8] = &[b'V', b'L', b'G', b'B', b'L', b'O', b'B', 1];
+
+/// Byte window of a value
+pub type Window = (Bound<usize>, Bound<usize>);
+
+/// Reads a byte window of a blob from the value log, without reading the rest of the blob.
+///
+/// Compressed blobs can only be decompressed as a whole, so they are read completely.
+///
+/// NOTE: The checksum of a blob covers the whole blob, so it is not verified
+/// when only a window of the blob is read.
+pub fn read_blob_window(
+    vlog: &ValueLog<MyCompressor>,
+    compression: CompressionType,
+    vhandle: &ValueHandle,
+    window: &Window,
+) -> crate::Result<Option<UserValue>> {
+    if compression != CompressionType::None {
+        return Ok(vlog
+            .get(vhandle)?
+            .map(|value| crate::r#abstract::value_window(&value, window)));
+    }
+
+    let Some(segment) = vlog.manifest.get_segment(vhandle.segment_id) else {
+        return Ok(None);
+    };
+
+    let mut reader = BufReader::new(File::open(&segment.path)?);
+    reader.seek(SeekFrom::Start(vhandle.offset))?;
+
+    let mut magic = [0; BLOB_HEADER_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+
+    if magic != BLOB_HEADER_MAGIC {
+        return Err(crate::Error::Decode(DecodeError::invalid_header(
+            "Blob",
+            BLOB_HEADER_MAGIC,
+            &magic,
+        )));
+    }
+
+    let _checksum = reader.read_u64::<BigEndian>()?;
+
+    let key_len = reader.read_u16::<BigEndian>()?;
+    reader.seek_relative(key_len.into())?;
+
+    let value_len = reader.read_u32::<BigEndian>()?;
+    let (start, end) = value_window_bounds(value_len as usize, window);
+
+    let value_offset = reader.stream_position()?;
+    reader.seek(SeekFrom::Start(value_offset + start as u64))?;
+
+    let mut value = vec![0; end - start];
+    reader.read_exact(&mut value)?;
+
+    Ok(Some(value.into()))
+}
+
+/// Reads a byte window of a chunked value, only reading the chunks that overlap the window.
+pub fn read_chunks_window(
+    vlog: &ValueLog<MyCompressor>,
+    compression: CompressionType,
+    chunks: &[(ValueHandle, u32)],
+    window: &Window,
+) -> crate::Result<UserValue> {
+    let size = chunks.iter().map(|(_, size)| *size as usize).sum::<usize>();
+    let (start, end) = value_window_bounds(size, window);
+
+    let mut value = Vec::with_capacity(end - start);
+    let mut chunk_start = 0;
+
+    for (vhandle, chunk_size) in chunks {
+        if chunk_start >= end {
+            break;
+        }
+
+        let chunk_end = chunk_start + *chunk_size as usize;
+
+        if chunk_end > start {
+            let chunk_window = (
+                Bound::Included(start.saturating_sub(chunk_start)),
+                Bound::Excluded(end.min(chunk_end) - chunk_start),
+            );
+
+            let Some(part) = read_blob_window(vlog, compression, vhandle, &chunk_window)? else {
+                log::error!("Chunk {vhandle:?} of value is missing in value log");
+                return Err(crate::Error::Unrecoverable);
+            };
+
+            value.extend_from_slice(&part);
+        }
+
+        chunk_start = chunk_end;
+    }
+
+    Ok(value.into())
+}
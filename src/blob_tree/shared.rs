@@ -0,0 +1,371 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{compression::MyCompressor, fragmentation::BlobFileId};
+use crate::{
+    file::{rewrite_atomic, OWNED_BLOB_FILES_FILE, SHARED_TREES_FILE},
+    path::absolute_path,
+    tree::inner::TreeInner,
+    CompressionType, Config, HashSet, Tree,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, Weak},
+};
+use value_log::{GcStrategy, ValueLog};
+
+/// A value log that is shared between multiple blob trees
+///
+/// Sharing a value log reduces the amount of blob files, and allows
+/// managing space amplification globally instead of per tree.
+///
+/// Every blob file is owned by the tree that wrote it, which is persisted in the tree's folder.
+/// Garbage collection of a tree only rewrites blob files that are owned by it, while
+/// scanning for stale blobs takes the index trees of all trees into account.
+///
+/// The folders of all trees that ever used the value log are persisted, and
+/// scanning for stale blobs fails unless all of them are currently open, because the
+/// blobs of a closed tree would otherwise be mistaken for stale blobs and be deleted.
+///
+/// All trees sharing a value log need to use the same sequence number generator.
+#[derive(Clone)]
+pub struct SharedValueLog(Arc<SharedValueLogInner>);
+
+struct SharedValueLogInner {
+    /// Value log
+    blobs: ValueLog<MyCompressor>,
+
+    /// Compression of the blob files
+    compression: CompressionType,
+
+    /// Serializes changes to the set of blob files, so
+    /// new blob files can be attributed to the tree that created them
+    file_lock: Mutex<()>,
+
+    /// Index trees that share the value log, and are currently open
+    trees: Mutex<Vec<Weak<TreeInner>>>,
+
+    /// Folders of all trees that share the value log
+    registry: TreeRegistry,
+}
+
+impl SharedValueLog {
+    /// Opens a value log in the given folder that can be shared between trees.
+    ///
+    /// The blob file settings (compression, target size, blob cache) are taken from the config.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn open<P: AsRef<Path>>(path: P, config: &Config) -> crate::Result<Self> {
+        let vlog_cfg = value_log::Config::<MyCompressor>::default()
+            .blob_cache(config.blob_cache.clone())
+            .segment_size_bytes(config.blob_file_target_size)
+            .compression(MyCompressor(config.blob_compression));
+
+        let path = path.as_ref();
+        let blobs = ValueLog::open(path, vlog_cfg)?;
+
+        Ok(Self(Arc::new(SharedValueLogInner {
+            blobs,
+            compression: config.blob_compression,
+            file_lock: Mutex::default(),
+            trees: Mutex::default(),
+            registry: TreeRegistry::recover(path)?,
+        })))
+    }
+
+    /// Removes a tree (by its folder) from the value log.
+    ///
+    /// Needs to be called after deleting a tree that used the value log,
+    /// otherwise its blobs can never be garbage collected again.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn unregister<P: AsRef<Path>>(&self, tree_path: P) -> crate::Result<()> {
+        self.0.registry.remove(&absolute_path(tree_path.as_ref()))
+    }
+
+    /// Returns the value log.
+    pub(crate) fn blobs(&self) -> &ValueLog<MyCompressor> {
+        &self.0.blobs
+    }
+
+    /// Returns the compression of the blob files.
+    pub(crate) fn compression(&self) -> CompressionType {
+        self.0.compression
+    }
+
+    /// Registers an index tree that uses the value log.
+    pub(crate) fn register(&self, tree: &Tree) -> crate::Result<()> {
+        self.0.registry.insert(&tree.config.path)?;
+
+        self.lock_trees().push(Arc::downgrade(&tree.0));
+
+        Ok(())
+    }
+
+    fn lock_trees(&self) -> MutexGuard<'_, Vec<Weak<TreeInner>>> {
+        self.0.trees.lock().expect("lock is poisoned")
+    }
+
+    /// Returns all index trees that use the value log.
+    ///
+    /// Returns `None` if any registered tree is currently closed, because
+    /// then the blobs that are still referenced can not be determined.
+    pub(crate) fn trees(&self) -> Option<Vec<Tree>> {
+        let trees = {
+            let mut trees = self.lock_trees();
+
+            // NOTE: Forget about closed trees
+            trees.retain(|tree| tree.strong_count() > 0);
+
+            trees
+                .iter()
+                .filter_map(Weak::upgrade)
+                .map(Tree)
+                .collect::<Vec<_>>()
+        };
+
+        let open_paths = trees
+            .iter()
+            .map(|tree| tree.config.path.as_path())
+            .collect::<HashSet<_>>();
+
+        let closed_tree = self.0.registry.find(|path| !open_paths.contains(path));
+
+        if let Some(path) = closed_tree {
+            log::debug!(
+                "Tree at {} shares the value log, but is not open",
+                path.display()
+            );
+            return None;
+        }
+
+        Some(trees)
+    }
+
+    /// Runs `f`, attributing all blob files it creates to `owner`.
+    ///
+    /// Blob files that do not exist anymore are removed from `owner`.
+    pub(crate) fn track_blob_files<T>(
+        &self,
+        owner: &BlobFileOwnership,
+        f: impl FnOnce() -> crate::Result<T>,
+    ) -> crate::Result<T> {
+        let _lock = self.0.file_lock.lock().expect("lock is poisoned");
+
+        let before = self
+            .0
+            .blobs
+            .manifest
+            .list_segment_ids()
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        let result = f();
+
+        let after = self
+            .0
+            .blobs
+            .manifest
+            .list_segment_ids()
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        owner.update(after.difference(&before).copied(), &after)?;
+
+        result
+    }
+}
+
+/// Folders of the trees that share a value log, persisted in the value log's folder
+struct TreeRegistry {
+    path: PathBuf,
+    paths: Mutex<HashSet<PathBuf>>,
+}
+
+impl TreeRegistry {
+    fn recover(vlog_path: &Path) -> crate::Result<Self> {
+        let path = vlog_path.join(SHARED_TREES_FILE);
+
+        let mut paths = HashSet::default();
+
+        if path.try_exists()? {
+            let bytes = std::fs::read(&path)?;
+            let mut reader = bytes.as_slice();
+
+            let count = reader.read_u64::<BigEndian>()?;
+
+            for _ in 0..count {
+                let len = reader.read_u32::<BigEndian>()? as usize;
+
+                let mut buf = vec![0; len];
+                reader.read_exact(&mut buf)?;
+
+                paths.insert(PathBuf::from(String::from_utf8_lossy(&buf).into_owned()));
+            }
+        }
+
+        log::debug!(
+            "{} trees share value log at {}",
+            paths.len(),
+            vlog_path.display()
+        );
+
+        Ok(Self {
+            path,
+            paths: Mutex::new(paths),
+        })
+    }
+
+    fn lock_paths(&self) -> MutexGuard<'_, HashSet<PathBuf>> {
+        self.paths.lock().expect("lock is poisoned")
+    }
+
+    fn find(&self, pred: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+        self.lock_paths().iter().find(|path| pred(path)).cloned()
+    }
+
+    // NOTE: The lock is held while persisting, so concurrent changes are written in order
+    fn insert(&self, tree_path: &Path) -> crate::Result<()> {
+        let mut paths = self.lock_paths();
+
+        if paths.insert(tree_path.into()) {
+            self.persist(&paths)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn remove(&self, tree_path: &Path) -> crate::Result<()> {
+        let mut paths = self.lock_paths();
+
+        if paths.remove(tree_path) {
+            self.persist(&paths)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn persist(&self, paths: &HashSet<PathBuf>) -> crate::Result<()> {
+        let mut bytes = vec![];
+        bytes.write_u64::<BigEndian>(paths.len() as u64)?;
+
+        for path in paths {
+            let path = path.to_string_lossy();
+
+            // NOTE: Truncation is okay, paths are much shorter than 4 GiB
+            #[allow(clippy::cast_possible_truncation)]
+            bytes.write_u32::<BigEndian>(path.len() as u32)?;
+
+            bytes.write_all(path.as_bytes())?;
+        }
+
+        rewrite_atomic(&self.path, &bytes)?;
+
+        Ok(())
+    }
+}
+
+/// Membership of a blob tree in a shared value log
+pub struct Sharing {
+    pub vlog: SharedValueLog,
+    pub owner: BlobFileOwnership,
+}
+
+/// Blob files of a shared value log that are owned by a tree
+pub struct BlobFileOwnership {
+    path: PathBuf,
+    ids: Mutex<HashSet<BlobFileId>>,
+}
+
+impl BlobFileOwnership {
+    /// Recovers the owned blob files of the tree in the given folder.
+    pub fn recover<P: AsRef<Path>>(tree_path: P) -> crate::Result<Self> {
+        let path = tree_path.as_ref().join(OWNED_BLOB_FILES_FILE);
+
+        let mut ids = HashSet::default();
+
+        if path.try_exists()? {
+            let bytes = std::fs::read(&path)?;
+            let mut reader = bytes.as_slice();
+
+            let count = reader.read_u64::<BigEndian>()?;
+
+            for _ in 0..count {
+                ids.insert(reader.read_u64::<BigEndian>()?);
+            }
+        }
+
+        log::debug!("Tree owns {} blob files of shared value log", ids.len());
+
+        Ok(Self {
+            path,
+            ids: Mutex::new(ids),
+        })
+    }
+
+    /// Returns `true` if the tree owns the blob file.
+    pub fn contains(&self, id: BlobFileId) -> bool {
+        self.lock_ids().contains(&id)
+    }
+
+    fn lock_ids(&self) -> MutexGuard<'_, HashSet<BlobFileId>> {
+        self.ids.lock().expect("lock is poisoned")
+    }
+
+    fn update(
+        &self,
+        added: impl Iterator<Item = BlobFileId>,
+        existing: &HashSet<BlobFileId>,
+    ) -> crate::Result<()> {
+        let mut ids = self.lock_ids();
+
+        let len_before = ids.len();
+        ids.retain(|id| existing.contains(id));
+        let dropped_any = ids.len() < len_before;
+
+        let len_before = ids.len();
+        ids.extend(added);
+        let added_any = ids.len() > len_before;
+
+        if !dropped_any && !added_any {
+            return Ok(());
+        }
+
+        let mut bytes = Vec::with_capacity(std::mem::size_of::<u64>() * (ids.len() + 1));
+        bytes.write_u64::<BigEndian>(ids.len() as u64)?;
+
+        for id in ids.iter() {
+            bytes.write_u64::<BigEndian>(*id)?;
+        }
+
+        rewrite_atomic(&self.path, &bytes)?;
+
+        // NOTE: The lock is held while persisting, so concurrent changes are written in order
+        drop(ids);
+
+        Ok(())
+    }
+}
+
+/// Restricts a GC strategy to blob files that are owned by a tree
+pub struct OwnedStrategy<'a, S: GcStrategy<MyCompressor>> {
+    pub inner: &'a S,
+    pub owner: &'a BlobFileOwnership,
+}
+
+impl<S: GcStrategy<MyCompressor>> GcStrategy<MyCompressor> for OwnedStrategy<'_, S> {
+    fn pick(&self, value_log: &ValueLog<MyCompressor>) -> Vec<BlobFileId> {
+        self.inner
+            .pick(value_log)
+            .into_iter()
+            .filter(|id| self.owner.contains(*id))
+            .collect()
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{
+    chunked::{chunk_key, MAX_CHUNKED_KEY_LEN},
+    compression::MyCompressor,
+    value::MaybeInlineValue,
+    BlobTree,
+};
+use crate::{coding::Encode, AbstractTree, SeqNo, UserKey, UserValue, ValueType};
+use std::io::{Cursor, Read, Write};
+use value_log::{SegmentWriter, ValueHandle, ValueLog};
+
+/// Writes a large value into the value log of a [`BlobTree`] piece by piece
+///
+/// The value bypasses the memtable, and is written into its own blob file.
+/// Its key is only committed (pointing to the blob) once [`StreamingValueWriter::finish`]
+/// is called; dropping the writer discards the value.
+///
+/// NOTE: Blob files checksum (and possibly compress) each blob as a whole,
+/// so the value is buffered until it is finished, or a chunk is full.
+/// Every full chunk (see [`crate::Config::blob_chunk_size`]) is written into
+/// the tree's chunk log right away, so at most one chunk is held in memory;
+/// chunked values are reassembled on read.
+pub struct StreamingValueWriter<'a> {
+    tree: &'a BlobTree,
+    key: UserKey,
+    seqno: SeqNo,
+    buffer: Vec<u8>,
+
+    /// Amount of bytes written so far
+    size: u64,
+
+    /// Blob writer of the chunks, if the value is chunked
+    blob_writer: Option<SegmentWriter<MyCompressor>>,
+
+    /// Chunks that have been written into the blob writer
+    chunks: Vec<(ValueHandle, u32)>,
+}
+
+impl<'a> StreamingValueWriter<'a> {
+    pub(crate) fn new(tree: &'a BlobTree, key: UserKey, seqno: SeqNo) -> Self {
+        Self {
+            tree,
+            key,
+            seqno,
+            buffer: Vec::new(),
+            size: 0,
+            blob_writer: None,
+            chunks: Vec::new(),
+        }
+    }
+
+    /// Writes the buffer as the next chunk of the value.
+    fn write_chunk(&mut self) -> crate::Result<()> {
+        if self.key.len() > MAX_CHUNKED_KEY_LEN {
+            return Err(crate::Error::KeyTooLarge(self.key.len()));
+        }
+
+        let blob_writer = match &mut self.blob_writer {
+            Some(blob_writer) => blob_writer,
+            None => self.blob_writer.insert(self.tree.chunks.get_writer()?),
+        };
+
+        // NOTE: A value never has more than 2^32 chunks
+        #[allow(clippy::cast_possible_truncation)]
+        let idx = self.chunks.len() as u32;
+
+        let vhandle = blob_writer.get_next_value_handle();
+        blob_writer.write(chunk_key(&self.key, idx), &self.buffer)?;
+
+        // NOTE: The buffer never exceeds the chunk size, which is 32-bit max
+        #[allow(clippy::cast_possible_truncation)]
+        self.chunks.push((vhandle, self.buffer.len() as u32));
+
+        self.buffer.clear();
+
+        Ok(())
+    }
+
+    /// Writes the value into the value log, and commits the key.
+    ///
+    /// Returns the added item's size and new size of the memtable.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn finish(mut self) -> crate::Result<(u32, u32)> {
+        log::trace!(
+            "Finishing streamed value of {} bytes for key {:?}",
+            self.size,
+            self.key
+        );
+
+        let indirection = if self.chunks.is_empty() {
+            // NOTE: The buffer never exceeds the chunk size, which is 32-bit max
+            #[allow(clippy::cast_possible_truncation)]
+            let size = self.buffer.len() as u32;
+
+            let mut blob_writer = self.tree.blobs.get_writer()?;
+            let vhandle = blob_writer.get_next_value_handle();
+            blob_writer.write(&self.key, &self.buffer)?;
+
+            // NOTE: Release the value before committing the key
+            drop(std::mem::take(&mut self.buffer));
+
+            self.tree.register_blob_writer(blob_writer)?;
+
+            MaybeInlineValue::Indirect { vhandle, size }
+        } else {
+            if !self.buffer.is_empty() {
+                self.write_chunk()?;
+            }
+
+            // NOTE: Release the value before committing the key
+            drop(std::mem::take(&mut self.buffer));
+
+            // NOTE: The blob writer is created by the first chunk, trivial
+            #[allow(clippy::expect_used)]
+            let blob_writer = self
+                .blob_writer
+                .take()
+                .expect("chunked value should have blob writer");
+
+            self.tree.chunks.register_writer(blob_writer)?;
+
+            log::trace!(
+                "Streamed value for key {:?} was split into {} chunks",
+                self.key,
+                self.chunks.len()
+            );
+
+            MaybeInlineValue::Chunked {
+                chunks: std::mem::take(&mut self.chunks),
+            }
+        };
+
+        let serialized_indirection = indirection.encode_into_vec()?;
+
+        Ok(self.tree.index.insert_bytes(
+            &self.key,
+            &serialized_indirection,
+            self.seqno,
+            ValueType::Value,
+        ))
+    }
+}
+
+impl Write for StreamingValueWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+
+        let config = &self.tree.index.config;
+
+        if self.size + buf.len() as u64 > config.max_streamed_value_size {
+            return Err(IoError::new(
+                IoErrorKind::InvalidInput,
+                "value exceeds maximum value size",
+            ));
+        }
+
+        let chunk_size = config.blob_chunk_size as usize;
+        let mut rest = buf;
+
+        while !rest.is_empty() {
+            let (head, tail) = rest.split_at((chunk_size - self.buffer.len()).min(rest.len()));
+            self.buffer.extend_from_slice(head);
+            rest = tail;
+
+            // NOTE: Write every chunk as soon as it is full,
+            // so the buffer never holds more than a single chunk
+            if self.buffer.len() >= chunk_size {
+                self.write_chunk()
+                    .map_err(|e| IoError::other(e.to_string()))?;
+            }
+        }
+
+        self.size += buf.len() as u64;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads a value of a [`BlobTree`] piece by piece
+///
+/// Chunked values are read one chunk at a time,
+/// so at most a single chunk of the value is held in memory.
+pub struct StreamingValueReader {
+    vlog: ValueLog<MyCompressor>,
+
+    /// Blobs of the value that have not been read yet
+    pending: std::vec::IntoIter<ValueHandle>,
+
+    /// Blob that is currently being read
+    current: Cursor<UserValue>,
+}
+
+impl StreamingValueReader {
+    pub(crate) fn new(tree: &BlobTree, value: MaybeInlineValue) -> Self {
+        let (vlog, pending, current) = match value {
+            MaybeInlineValue::Inline(bytes) => (tree.blobs.clone(), vec![], bytes),
+            MaybeInlineValue::Indirect { vhandle, .. } => {
+                (tree.blobs.clone(), vec![vhandle], UserValue::from(&[][..]))
+            }
+            MaybeInlineValue::Chunked { chunks } => (
+                tree.chunks.clone(),
+                chunks.into_iter().map(|(vhandle, _)| vhandle).collect(),
+                UserValue::from(&[][..]),
+            ),
+        };
+
+        Self {
+            vlog,
+            pending: pending.into_iter(),
+            current: Cursor::new(current),
+        }
+    }
+}
+
+impl Read for StreamingValueReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let n = self.current.read(buf)?;
+
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+
+            let Some(vhandle) = self.pending.next() else {
+                return Ok(0);
+            };
+
+            let Some(blob) = self.vlog.get(&vhandle).map_err(std::io::Error::other)? else {
+                log::error!("Blob {vhandle:?} of value is missing in value log");
+                return Err(std::io::Error::other("blob of value is missing"));
+            };
+
+            self.current = Cursor::new(blob);
+        }
+    }
+}
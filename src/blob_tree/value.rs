@@ -20,6 +20,10 @@ pub enum MaybeInlineValue {
 
     /// The value is a handle (pointer) into the value log
     Indirect { vhandle: ValueHandle, size: u32 },
+
+    /// The value is too large for a single blob, so it is split into chunks,
+    /// each of which is stored as its own blob in the value log
+    Chunked { chunks: Vec<(ValueHandle, u32)> },
 }
 
 impl Encode for ValueHandle {
@@ -40,6 +44,7 @@ impl Decode for ValueHandle {
 
 const TAG_INLINE: u8 = 0;
 const TAG_INDIRECT: u8 = 1;
+const TAG_CHUNKED: u8 = 2;
 
 impl Encode for MaybeInlineValue {
     fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
@@ -58,6 +63,18 @@ impl Encode for MaybeInlineValue {
                 vhandle.encode_into(writer)?;
                 writer.write_u32_varint(*size)?;
             }
+            Self::Chunked { chunks } => {
+                writer.write_u8(TAG_CHUNKED)?;
+
+                // NOTE: A chunked value never has more than 2^32 chunks
+                #[allow(clippy::cast_possible_truncation)]
+                writer.write_u32_varint(chunks.len() as u32)?;
+
+                for (vhandle, size) in chunks {
+                    vhandle.encode_into(writer)?;
+                    writer.write_u32_varint(*size)?;
+                }
+            }
         }
         Ok(())
     }
@@ -79,6 +96,19 @@ impl Decode for MaybeInlineValue {
                 let size = reader.read_u32_varint()?;
                 Ok(Self::Indirect { vhandle, size })
             }
+            TAG_CHUNKED => {
+                let len = reader.read_u32_varint()? as usize;
+
+                let mut chunks = Vec::with_capacity(len.min(1_024));
+
+                for _ in 0..len {
+                    let vhandle = ValueHandle::decode_from(reader)?;
+                    let size = reader.read_u32_varint()?;
+                    chunks.push((vhandle, size));
+                }
+
+                Ok(Self::Chunked { chunks })
+            }
             x => Err(DecodeError::InvalidTag(("MaybeInlineValue", x))),
         }
     }
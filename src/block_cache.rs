@@ -5,12 +5,18 @@
 use crate::either::Either::{self, Left, Right};
 use crate::segment::id::GlobalSegmentId;
 use crate::segment::{block_index::IndexBlock, value_block::ValueBlock};
-use quick_cache::Weighter;
 use quick_cache::{sync::Cache, Equivalent};
-use std::sync::Arc;
+use quick_cache::{Lifecycle, Weighter};
+use std::collections::HashMap;
+use std::sync::{
+    atomic::{AtomicU64, Ordering::Relaxed},
+    Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+};
 
 type Item = Either<Arc<ValueBlock>, Arc<IndexBlock>>;
 
+type PinCounts = HashMap<(GlobalSegmentId, u64), (usize, u64)>;
+
 // (Type (disk or index), Segment ID, Block offset)
 #[derive(Eq, std::hash::Hash, PartialEq)]
 struct CacheKey(GlobalSegmentId, u64);
@@ -40,6 +46,43 @@ impl Weighter<CacheKey, Item> for BlockWeighter {
     }
 }
 
+/// Blocks that are exempt from eviction
+///
+/// Pins are reference counted, so overlapping pinned ranges can be unpinned independently.
+#[derive(Default)]
+struct PinnedBlocks {
+    /// Pin count & weight per block
+    blocks: RwLock<PinCounts>,
+
+    /// Summed up weight of all pinned blocks
+    bytes: AtomicU64,
+}
+
+impl PinnedBlocks {
+    /// Read-locks the pinned blocks
+    fn read_lock_blocks(&self) -> RwLockReadGuard<'_, PinCounts> {
+        self.blocks.read().expect("lock is poisoned")
+    }
+
+    /// Write-locks the pinned blocks for exclusive access
+    fn lock_blocks(&self) -> RwLockWriteGuard<'_, PinCounts> {
+        self.blocks.write().expect("lock is poisoned")
+    }
+}
+
+#[derive(Clone, Default)]
+struct BlockLifecycle(Arc<PinnedBlocks>);
+
+impl Lifecycle<CacheKey, Item> for BlockLifecycle {
+    type RequestState = ();
+
+    fn begin_request(&self) -> Self::RequestState {}
+
+    fn is_pinned(&self, key: &CacheKey, _: &Item) -> bool {
+        self.0.read_lock_blocks().contains_key(&(key.0, key.1))
+    }
+}
+
 /// Block cache, in which blocks are cached in-memory
 /// after being retrieved from disk
 ///
@@ -65,28 +108,50 @@ impl Weighter<CacheKey, Item> for BlockWeighter {
 /// # Ok::<(), lsm_tree::Error>(())
 /// ```
 pub struct BlockCache {
-    data: Cache<CacheKey, Item, BlockWeighter, xxhash_rust::xxh3::Xxh3Builder>,
-    capacity: u64,
+    data: Cache<CacheKey, Item, BlockWeighter, xxhash_rust::xxh3::Xxh3Builder, BlockLifecycle>,
+    capacity: AtomicU64,
+    pinned: Arc<PinnedBlocks>,
+
+    /// Amount of block lookups that were answered by the cache
+    hits: AtomicU64,
+
+    /// Amount of block lookups that were not answered by the cache
+    misses: AtomicU64,
 }
 
 impl BlockCache {
     /// Creates a new block cache with roughly `n` bytes of capacity.
     #[must_use]
     pub fn with_capacity_bytes(bytes: u64) -> Self {
-        use quick_cache::sync::DefaultLifecycle;
+        let lifecycle = BlockLifecycle::default();
 
         Self {
+            pinned: lifecycle.0.clone(),
             data: Cache::with(
                 1_000_000,
                 bytes,
                 BlockWeighter,
                 xxhash_rust::xxh3::Xxh3Builder::new(),
-                DefaultLifecycle::default(),
+                lifecycle,
             ),
-            capacity: bytes,
+            capacity: AtomicU64::new(bytes),
+            hits: AtomicU64::default(),
+            misses: AtomicU64::default(),
         }
     }
 
+    /// Sets the cache capacity in bytes.
+    ///
+    /// If the cache is shrunk, blocks are evicted until it fits the new capacity.
+    /// Because a shared cache is a single instance, the new capacity applies
+    /// to all trees using the cache at once.
+    ///
+    /// Setting the capacity to 0 disables caching.
+    pub fn set_capacity(&self, bytes: u64) {
+        self.capacity.store(bytes, Relaxed);
+        self.data.set_capacity(bytes);
+    }
+
     /// Returns the amount of cached bytes.
     #[must_use]
     pub fn size(&self) -> u64 {
@@ -96,7 +161,7 @@ impl BlockCache {
     /// Returns the cache capacity in bytes.
     #[must_use]
     pub fn capacity(&self) -> u64 {
-        self.capacity
+        self.capacity.load(Relaxed)
     }
 
     /// Returns the number of cached blocks.
@@ -111,6 +176,81 @@ impl BlockCache {
         self.data.is_empty()
     }
 
+    /// Returns the amount of bytes of pinned blocks.
+    ///
+    /// Pinned blocks count towards the cache size, but are never evicted.
+    #[must_use]
+    pub fn pinned_bytes(&self) -> u64 {
+        self.pinned.bytes.load(Relaxed)
+    }
+
+    /// Returns the number of pinned blocks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn pinned_len(&self) -> usize {
+        self.pinned.read_lock_blocks().len()
+    }
+
+    /// Pins a block, so it is not evicted once it is inserted.
+    ///
+    /// Pins are reference counted, so the block needs to be unpinned
+    /// as many times as it has been pinned.
+    #[doc(hidden)]
+    pub fn pin(&self, segment_id: GlobalSegmentId, offset: u64, weight: u64) {
+        let mut blocks = self.pinned.lock_blocks();
+
+        let (count, _) = blocks.entry((segment_id, offset)).or_insert_with(|| {
+            self.pinned.bytes.fetch_add(weight, Relaxed);
+            (0, weight)
+        });
+        *count += 1;
+
+        drop(blocks);
+    }
+
+    /// Releases a pin of a block, see [`BlockCache::pin`].
+    #[doc(hidden)]
+    pub fn unpin(&self, segment_id: GlobalSegmentId, offset: u64) {
+        let mut blocks = self.pinned.lock_blocks();
+
+        let Some((count, weight)) = blocks.get_mut(&(segment_id, offset)) else {
+            return;
+        };
+        *count -= 1;
+
+        if *count == 0 {
+            self.pinned.bytes.fetch_sub(*weight, Relaxed);
+            blocks.remove(&(segment_id, offset));
+        }
+    }
+
+    /// Releases all pins of the blocks of a segment, regardless of their pin count.
+    #[doc(hidden)]
+    pub fn unpin_segment(&self, segment_id: GlobalSegmentId) {
+        let mut blocks = self.pinned.lock_blocks();
+
+        blocks.retain(|&(id, _), &mut (_, weight)| {
+            if id == segment_id {
+                self.pinned.bytes.fetch_sub(weight, Relaxed);
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// Returns `true` if the block is pinned.
+    #[doc(hidden)]
+    #[must_use]
+    pub fn is_pinned(&self, segment_id: GlobalSegmentId, offset: u64) -> bool {
+        self.pinned
+            .read_lock_blocks()
+            .contains_key(&(segment_id, offset))
+    }
+
     #[doc(hidden)]
     pub fn insert_disk_block(
         &self,
@@ -118,7 +258,7 @@ impl BlockCache {
         offset: u64,
         value: Arc<ValueBlock>,
     ) {
-        if self.capacity > 0 {
+        if self.capacity() > 0 {
             self.data.insert((segment_id, offset).into(), Left(value));
         }
     }
@@ -130,11 +270,38 @@ impl BlockCache {
         offset: u64,
         value: Arc<IndexBlock>,
     ) {
-        if self.capacity > 0 {
+        if self.capacity() > 0 {
             self.data.insert((segment_id, offset).into(), Right(value));
         }
     }
 
+    /// Returns the amount of block lookups that were answered by the cache.
+    #[must_use]
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Relaxed)
+    }
+
+    /// Returns the amount of block lookups that were not answered by the cache.
+    #[must_use]
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Relaxed)
+    }
+
+    fn record_lookup(&self, hit: bool) {
+        if hit {
+            self.hits.fetch_add(1, Relaxed);
+        } else {
+            self.misses.fetch_add(1, Relaxed);
+        }
+    }
+
+    /// Returns `true` if the data block is cached, without counting as an access.
+    #[doc(hidden)]
+    #[must_use]
+    pub fn contains_disk_block(&self, segment_id: GlobalSegmentId, offset: u64) -> bool {
+        self.data.contains_key(&(segment_id, offset))
+    }
+
     #[doc(hidden)]
     #[must_use]
     pub fn get_disk_block(
@@ -143,8 +310,9 @@ impl BlockCache {
         offset: u64,
     ) -> Option<Arc<ValueBlock>> {
         let key = (segment_id, offset);
-        let item = self.data.get(&key)?;
-        Some(item.left())
+        let item = self.data.get(&key);
+        self.record_lookup(item.is_some());
+        Some(item?.left())
     }
 
     #[doc(hidden)]
@@ -155,7 +323,8 @@ impl BlockCache {
         offset: u64,
     ) -> Option<Arc<IndexBlock>> {
         let key = (segment_id, offset);
-        let item = self.data.get(&key)?;
-        Some(item.right())
+        let item = self.data.get(&key);
+        self.record_lookup(item.is_some());
+        Some(item?.right())
     }
 }
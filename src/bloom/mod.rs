@@ -7,6 +7,7 @@ mod bit_array;
 use crate::{
     coding::{Decode, DecodeError, Encode, EncodeError},
     file::MAGIC_BYTES,
+    mlock::MemoryLock,
 };
 use bit_array::BitArray;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
@@ -14,7 +15,41 @@ use std::io::{Read, Write};
 
 pub type CompositeHash = (u64, u64);
 
-/// A standard bloom filter
+/// Size of the blocks of a blocked bloom filter, which is one (64 byte) cache line
+const CACHE_LINE_BITS: usize = 512;
+
+/// Upper bound of the hash count of a stored filter
+///
+/// The hash count is configured as (or derived from the bits per key as) a `u8`.
+const MAX_HASH_COUNT: usize = u8::MAX as usize;
+
+/// Memory layout of a bloom filter
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Layout {
+    /// The bits of a key are spread over the whole filter
+    ///
+    /// Written by older versions, new filters are always blocked.
+    Standard,
+
+    /// The bits of a key are all set in a single block,
+    /// so every lookup touches just one cache line
+    Blocked {
+        /// Size of a block in bits
+        block_bits: usize,
+    },
+}
+
+impl Layout {
+    /// Filter type in the file header
+    fn filter_type(self) -> u8 {
+        match self {
+            Self::Standard => 0,
+            Self::Blocked { .. } => 1,
+        }
+    }
+}
+
+/// A (cache-line-blocked) bloom filter
 ///
 /// Allows buffering the key hashes before actual filter construction
 /// which is needed to properly calculate the filter size, as the amount of items
@@ -22,6 +57,9 @@ pub type CompositeHash = (u64, u64);
 ///
 /// The filter uses double hashing instead of `k` hash functions, see:
 /// <https://fjall-rs.github.io/post/bloom-filter-hash-sharing>
+///
+/// The first hash selects the block of a key, the second hash
+/// is split into two halves that select the `k` bits inside the block.
 #[derive(Debug, Eq, PartialEq)]
 #[allow(clippy::module_name_repetitions)]
 pub struct BloomFilter {
@@ -33,6 +71,12 @@ pub struct BloomFilter {
 
     /// Number of hash functions
     k: usize,
+
+    /// Memory layout of the bits
+    layout: Layout,
+
+    /// Lock of the raw bytes, if they are locked into RAM
+    memory_lock: MemoryLock,
 }
 
 impl Encode for BloomFilter {
@@ -40,14 +84,20 @@ impl Encode for BloomFilter {
         // Write header
         writer.write_all(&MAGIC_BYTES)?;
 
-        // NOTE: Filter type (unused)
-        writer.write_u8(0)?;
+        writer.write_u8(self.layout.filter_type())?;
 
         // NOTE: Hash type (unused)
         writer.write_u8(0)?;
 
         writer.write_u64::<BigEndian>(self.m as u64)?;
         writer.write_u64::<BigEndian>(self.k as u64)?;
+
+        if let Layout::Blocked { block_bits } = self.layout {
+            // NOTE: Block size is bounded by the block count, which is checked when decoding
+            #[allow(clippy::cast_possible_truncation)]
+            writer.write_u32::<BigEndian>(block_bits as u32)?;
+        }
+
         writer.write_all(self.inner.bytes())?;
 
         Ok(())
@@ -61,24 +111,60 @@ impl Decode for BloomFilter {
         reader.read_exact(&mut magic)?;
 
         if magic != MAGIC_BYTES {
-            return Err(DecodeError::InvalidHeader("BloomFilter"));
+            return Err(DecodeError::invalid_header(
+                "BloomFilter",
+                &MAGIC_BYTES,
+                &magic,
+            ));
         }
 
-        // NOTE: Filter type (unused)
         let filter_type = reader.read_u8()?;
-        assert_eq!(0, filter_type, "Invalid filter type");
 
         // NOTE: Hash type (unused)
         let hash_type = reader.read_u8()?;
-        assert_eq!(0, hash_type, "Invalid bloom hash type");
+        if hash_type != 0 {
+            return Err(DecodeError::InvalidTag(("BloomHashType", hash_type)));
+        }
 
         let m = reader.read_u64::<BigEndian>()? as usize;
         let k = reader.read_u64::<BigEndian>()? as usize;
 
+        if m == 0 {
+            return Err(DecodeError::InvalidContent {
+                name: "BloomFilter",
+                reason: "filter has no bits".into(),
+            });
+        }
+
+        if k == 0 || k > MAX_HASH_COUNT {
+            return Err(DecodeError::InvalidContent {
+                name: "BloomFilter",
+                reason: format!("invalid hash count of {k}"),
+            });
+        }
+
+        let layout = match filter_type {
+            0 => Layout::Standard,
+            1 => {
+                let block_bits = reader.read_u32::<BigEndian>()? as usize;
+
+                // NOTE: Blocks need to be byte-aligned and make up the whole filter
+                if block_bits == 0 || block_bits % 8 != 0 || m % block_bits != 0 {
+                    return Err(DecodeError::InvalidContent {
+                        name: "BloomFilter",
+                        reason: format!("invalid block size of {block_bits} bits for {m} bits"),
+                    });
+                }
+
+                Layout::Blocked { block_bits }
+            }
+            tag => return Err(DecodeError::InvalidTag(("BloomFilterType", tag))),
+        };
+
         let mut bytes = vec![0; m / 8];
         reader.read_exact(&mut bytes)?;
 
-        Ok(Self::from_raw(m, k, bytes.into_boxed_slice()))
+        Ok(Self::from_raw(m, k, layout, bytes.into_boxed_slice()))
     }
 }
 
@@ -94,14 +180,62 @@ impl BloomFilter {
         self.len() == 0
     }
 
-    fn from_raw(m: usize, k: usize, bytes: Box<[u8]>) -> Self {
+    fn from_raw(m: usize, k: usize, layout: Layout, bytes: Box<[u8]>) -> Self {
         Self {
             inner: BitArray::from_bytes(bytes),
             m,
             k,
+            layout,
+            memory_lock: MemoryLock::default(),
+        }
+    }
+
+    /// Constructs a blocked bloom filter with at least `m` bits.
+    fn blocked(m: usize, k: usize) -> Self {
+        let m = m.div_ceil(CACHE_LINE_BITS).max(1) * CACHE_LINE_BITS;
+
+        Self {
+            inner: BitArray::with_capacity(m / 8),
+            m,
+            k,
+            layout: Layout::Blocked {
+                block_bits: CACHE_LINE_BITS,
+            },
+            memory_lock: MemoryLock::default(),
         }
     }
 
+    /// Overrides the number of hash functions (bits set per key).
+    ///
+    /// By default, `k` is derived from the bits per key, which minimizes the false positive rate.
+    /// Fewer hash functions trade a higher false positive rate for faster lookups.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0 or larger than 255.
+    #[must_use]
+    pub fn with_hash_count(mut self, k: usize) -> Self {
+        assert!(k > 0, "bloom filter needs at least one hash function");
+        assert!(
+            k <= MAX_HASH_COUNT,
+            "bloom filter has too many hash functions"
+        );
+
+        self.k = k;
+        self
+    }
+
+    /// Tries to lock the filter into RAM, so it is never paged out.
+    ///
+    /// Locking is best-effort, see [`crate::Config::mlock_index_and_filters`].
+    #[must_use]
+    pub fn with_memory_lock(mut self, enabled: bool) -> Self {
+        if enabled {
+            self.memory_lock.lock(self.inner.bytes());
+        }
+        self
+    }
+
     /// Constructs a bloom filter that can hold `n` items
     /// while maintaining a certain false positive rate `fpr`.
     #[must_use]
@@ -117,11 +251,7 @@ impl BloomFilter {
         let bpk = m / n;
         let k = (((bpk as f32) * LN_2) as usize).max(1);
 
-        Self {
-            inner: BitArray::with_capacity(m / 8),
-            m,
-            k,
-        }
+        Self::blocked(m, k)
     }
 
     /// Constructs a bloom filter that can hold `n` items
@@ -140,14 +270,7 @@ impl BloomFilter {
         let m = n * bpk;
         let k = (((bpk as f32) * LN_2) as usize).max(1);
 
-        // NOTE: Round up so we don't get too little bits
-        let bytes = (m as f32 / 8.0).ceil() as usize;
-
-        Self {
-            inner: BitArray::with_capacity(bytes),
-            m: bytes * 8,
-            k,
-        }
+        Self::blocked(m, k)
     }
 
     fn calculate_m(n: usize, fp_rate: f32) -> usize {
@@ -163,29 +286,63 @@ impl BloomFilter {
         ((m / 8.0).ceil() * 8.0) as usize
     }
 
-    /// Returns `true` if the hash may be contained.
+    /// Calls `f` with the bit index of every hash function, until `f` returns `false`.
     ///
-    /// Will never have a false negative.
-    #[must_use]
-    pub fn contains_hash(&self, hash: CompositeHash) -> bool {
-        let (mut h1, mut h2) = hash;
-
-        for i in 0..(self.k as u64) {
-            let idx = h1 % (self.m as u64);
-
-            // NOTE: should be in bounds because of modulo
-            #[allow(clippy::expect_used)]
-            if !self.inner.get(idx as usize) {
-                return false;
+    /// Returns `false` if `f` returned `false`.
+    fn probe<F: FnMut(usize) -> bool>(
+        layout: Layout,
+        m: usize,
+        k: usize,
+        (mut h1, mut h2): CompositeHash,
+        mut f: F,
+    ) -> bool {
+        match layout {
+            Layout::Standard => {
+                for i in 0..(k as u64) {
+                    let idx = h1 % (m as u64);
+
+                    // NOTE: should be in bounds because of modulo
+                    #[allow(clippy::cast_possible_truncation)]
+                    if !f(idx as usize) {
+                        return false;
+                    }
+
+                    h1 = h1.wrapping_add(h2);
+                    h2 = h2.wrapping_add(i);
+                }
+            }
+            Layout::Blocked { block_bits } => {
+                let block_bits = block_bits as u64;
+                let block_start = (h1 % (m as u64 / block_bits)) * block_bits;
+
+                // NOTE: The delta is odd, so for power-of-two block sizes,
+                // the first `block_bits` probes never set the same bit twice
+                let mut h = h2 & u64::from(u32::MAX);
+                let delta = (h2 >> 32) | 1;
+
+                for _ in 0..k {
+                    // NOTE: should be in bounds because of modulo
+                    #[allow(clippy::cast_possible_truncation)]
+                    if !f((block_start + h % block_bits) as usize) {
+                        return false;
+                    }
+
+                    h = h.wrapping_add(delta);
+                }
             }
-
-            h1 = h1.wrapping_add(h2);
-            h2 = h2.wrapping_add(i);
         }
 
         true
     }
 
+    /// Returns `true` if the hash may be contained.
+    ///
+    /// Will never have a false negative.
+    #[must_use]
+    pub fn contains_hash(&self, hash: CompositeHash) -> bool {
+        Self::probe(self.layout, self.m, self.k, hash, |idx| self.inner.get(idx))
+    }
+
     /// Returns `true` if the item may be contained.
     ///
     /// Will never have a false negative.
@@ -195,20 +352,13 @@ impl BloomFilter {
     }
 
     /// Adds the key to the filter
-    pub fn set_with_hash(&mut self, (mut h1, mut h2): CompositeHash) {
-        for i in 0..(self.k as u64) {
-            let idx = h1 % (self.m as u64);
-
-            self.enable_bit(idx as usize);
-
-            h1 = h1.wrapping_add(h2);
-            h2 = h2.wrapping_add(i);
-        }
-    }
+    pub fn set_with_hash(&mut self, hash: CompositeHash) {
+        let inner = &mut self.inner;
 
-    /// Sets the bit at the given index to `true`
-    fn enable_bit(&mut self, idx: usize) {
-        self.inner.set(idx, true);
+        Self::probe(self.layout, self.m, self.k, hash, |idx| {
+            inner.set(idx, true);
+            true
+        });
     }
 
     /// Gets the hash of a key
@@ -255,6 +405,142 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn bloom_serde_legacy_layout() -> crate::Result<()> {
+        let mut filter =
+            BloomFilter::from_raw(1_024, 3, Layout::Standard, vec![0; 128].into_boxed_slice());
+
+        for key in [b"item0", b"item1", b"item2"] {
+            filter.set_with_hash(BloomFilter::get_hash(key));
+        }
+
+        let bytes = filter.encode_into_vec()?;
+
+        // NOTE: Standard filters are stored without block size
+        assert_eq!(4 + 1 + 1 + 8 + 8 + 128, bytes.len());
+
+        let filter_copy = BloomFilter::decode_from(&mut &bytes[..])?;
+        assert_eq!(filter, filter_copy);
+
+        for key in [b"item0", b"item1", b"item2"] {
+            assert!(filter_copy.contains(key));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn bloom_serde_invalid_header() -> crate::Result<()> {
+        let filter = BloomFilter::with_bpk(10, 10);
+        let bytes = filter.encode_into_vec()?;
+
+        let mut invalid_type = bytes.clone();
+        *invalid_type
+            .get_mut(MAGIC_BYTES.len())
+            .expect("should exist") = 7;
+        assert!(matches!(
+            BloomFilter::decode_from(&mut &invalid_type[..]),
+            Err(DecodeError::InvalidTag(("BloomFilterType", 7))),
+        ));
+
+        // NOTE: Block size (after magic, types, m & k) does not divide m
+        let mut invalid_block_size = bytes;
+        *invalid_block_size
+            .get_mut(MAGIC_BYTES.len() + 2 + 8 + 8 + 3)
+            .expect("should exist") = 3;
+        assert!(matches!(
+            BloomFilter::decode_from(&mut &invalid_block_size[..]),
+            Err(DecodeError::InvalidContent {
+                name: "BloomFilter",
+                ..
+            }),
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn bloom_serde_invalid_size() -> crate::Result<()> {
+        let standard =
+            BloomFilter::from_raw(1_024, 3, Layout::Standard, vec![0; 128].into_boxed_slice());
+
+        for bytes in [
+            standard.encode_into_vec()?,
+            BloomFilter::with_bpk(10, 10).encode_into_vec()?,
+        ] {
+            // NOTE: m & k are stored after magic and types
+            let m_offset = MAGIC_BYTES.len() + 2;
+            let k_offset = m_offset + 8;
+
+            let mut zero_bits = bytes.clone();
+            zero_bits
+                .get_mut(m_offset..k_offset)
+                .expect("should exist")
+                .fill(0);
+
+            let mut zero_hashes = bytes.clone();
+            zero_hashes
+                .get_mut(k_offset..k_offset + 8)
+                .expect("should exist")
+                .fill(0);
+
+            let mut too_many_hashes = bytes;
+            too_many_hashes
+                .get_mut(k_offset..k_offset + 8)
+                .expect("should exist")
+                .copy_from_slice(&1_000u64.to_be_bytes());
+
+            for invalid in [zero_bits, zero_hashes, too_many_hashes] {
+                assert!(matches!(
+                    BloomFilter::decode_from(&mut &invalid[..]),
+                    Err(DecodeError::InvalidContent {
+                        name: "BloomFilter",
+                        ..
+                    }),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn bloom_blocked_single_cache_line() {
+        let mut filter = BloomFilter::with_bpk(1_000, 10);
+        assert_eq!(0, filter.len() % (CACHE_LINE_BITS / 8));
+
+        for key in (0..100u64).map(u64::to_be_bytes) {
+            let hash = BloomFilter::get_hash(&key);
+
+            let mut indexes = vec![];
+            BloomFilter::probe(filter.layout, filter.m, filter.k, hash, |idx| {
+                indexes.push(idx);
+                true
+            });
+            assert_eq!(filter.k, indexes.len());
+
+            let block = indexes.first().expect("should exist") / CACHE_LINE_BITS;
+            assert!(indexes.iter().all(|idx| idx / CACHE_LINE_BITS == block));
+
+            filter.set_with_hash(hash);
+            assert!(filter.contains_hash(hash));
+        }
+    }
+
+    #[test]
+    fn bloom_hash_count() -> crate::Result<()> {
+        let mut filter = BloomFilter::with_bpk(100, 10).with_hash_count(2);
+        assert_eq!(2, filter.k);
+
+        filter.set_with_hash(BloomFilter::get_hash(b"a"));
+
+        let filter = BloomFilter::decode_from(&mut &filter.encode_into_vec()?[..])?;
+        assert_eq!(2, filter.k);
+        assert!(filter.contains(b"a"));
+
+        Ok(())
+    }
+
     #[test]
     fn bloom_calculate_m() {
         assert_eq!(9_592, BloomFilter::calculate_m(1_000, 0.01));
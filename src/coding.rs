@@ -2,6 +2,7 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
+use crate::error::ErrorContext;
 use std::io::{Read, Write};
 
 /// Error during serialization
@@ -42,22 +43,177 @@ pub enum DecodeError {
     /// Invalid enum tag
     InvalidTag((&'static str, u8)),
 
+    /// Invalid enum tag in a block item
+    InvalidItemTag {
+        /// Name of the enum
+        name: &'static str,
+
+        /// Invalid tag
+        tag: u8,
+
+        /// Offset of the item inside the (decrypted & decompressed) block
+        offset: u64,
+    },
+
     /// Invalid block header
     InvalidTrailer,
 
-    InvalidHeader(&'static str),
+    /// Invalid header (magic bytes) of a structure
+    InvalidHeader {
+        /// Name of the structure
+        name: &'static str,
+
+        /// Expected header
+        expected: &'static [u8],
+
+        /// Header that was read
+        found: Vec<u8>,
+    },
+
+    /// A structure was decoded, but its contents are inconsistent
+    InvalidContent {
+        /// Name of the structure
+        name: &'static str,
+
+        /// Description of the inconsistency
+        reason: String,
+    },
+
+    /// Decoding a structure at a known position failed
+    At {
+        /// Name of the structure
+        name: &'static str,
+
+        /// Offset of the structure in its file (or block)
+        offset: u64,
+
+        /// Underlying error
+        source: Box<Self>,
+
+        /// Context in which the structure was read, if known
+        context: Option<Box<ErrorContext>>,
+    },
+}
+
+impl DecodeError {
+    /// Creates an error for a header that does not match the expected header.
+    pub(crate) fn invalid_header(
+        name: &'static str,
+        expected: &'static [u8],
+        found: &[u8],
+    ) -> Self {
+        Self::InvalidHeader {
+            name,
+            expected,
+            found: found.to_vec(),
+        }
+    }
+
+    /// Attaches the name and position of the structure that was being decoded.
+    pub(crate) fn at(self, name: &'static str, offset: u64) -> Self {
+        Self::At {
+            name,
+            offset,
+            source: Box::new(self),
+            context: None,
+        }
+    }
+
+    /// Attaches context to the error.
+    ///
+    /// The context is stored in the outermost position (or inside the I/O error),
+    /// so the error can still be matched as before. Errors without position
+    /// cannot carry context, so it is logged instead.
+    pub(crate) fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            Self::Io(e) => Self::Io(crate::error::ContextualIoError::attach(e, context)),
+            Self::At {
+                name,
+                offset,
+                source,
+                context: existing,
+            } => {
+                let context = match existing {
+                    Some(mut existing) => {
+                        existing.merge(context);
+                        existing
+                    }
+                    None => Box::new(context),
+                };
+
+                Self::At {
+                    name,
+                    offset,
+                    source,
+                    context: Some(context),
+                }
+            }
+            e => {
+                log::error!("{e:?} ({context})");
+                e
+            }
+        }
+    }
+
+    /// Returns the context of the error, if any.
+    #[must_use]
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::Io(e) => crate::error::ContextualIoError::context_of(e),
+            Self::At {
+                source, context, ..
+            } => context.as_deref().or_else(|| source.context()),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying error, without position information.
+    #[must_use]
+    pub fn root_cause(&self) -> &Self {
+        match self {
+            Self::At { source, .. } => source.root_cause(),
+            e => e,
+        }
+    }
+
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::InvalidTag((name, tag)) => write!(f, "invalid {name} tag: found {tag:#04x}"),
+            Self::InvalidItemTag { name, tag, offset } => {
+                write!(
+                    f,
+                    "invalid {name} tag in item at offset {offset}: found {tag:#04x}"
+                )
+            }
+            Self::InvalidHeader {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "invalid {name} header: expected {expected:02x?}, found {found:02x?}"
+            ),
+            Self::InvalidContent { name, reason } => write!(f, "invalid {name}: {reason}"),
+            Self::At {
+                name,
+                offset,
+                source,
+                ..
+            } => {
+                write!(f, "{name} at offset {offset}: ")?;
+                source.describe(f)
+            }
+            e => write!(f, "{e:?}"),
+        }
+    }
 }
 
 impl std::fmt::Display for DecodeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "DecodeError({})",
-            match self {
-                Self::Io(e) => e.to_string(),
-                e => format!("{e:?}"),
-            }
-        )
+        write!(f, "DecodeError(")?;
+        self.describe(f)?;
+        write!(f, ")")
     }
 }
 
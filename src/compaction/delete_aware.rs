@@ -0,0 +1,223 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{leveled::aggregate_key_range, Choice, CompactionStrategy, Input as CompactionInput};
+use crate::{
+    key_range::KeyRange,
+    level_manifest::{level::Level, LevelManifest},
+    segment::Segment,
+    Config,
+};
+use std::sync::Arc;
+
+/// Delete-aware compaction strategy
+///
+/// Uses the per-block tombstone counts of segments to find key ranges that
+/// are dense with deletions, and merges the segment containing them into the
+/// overlapping segments of the last level.
+///
+/// Segments are only picked by themselves from disjoint levels; in other levels,
+/// all segments that overlap with the picked segment are merged together,
+/// so no newer version of a key is left behind in the upper level.
+///
+/// Tombstones are only dropped when they are written into the last level, so only
+/// segments of the last level, and of the level right above it, are considered.
+/// Only tombstones in data blocks that actually overlap with the last level
+/// are considered reclaimable, so write bandwidth is spent where it frees up
+/// space, instead of rewriting whole levels.
+///
+/// This strategy does not manage L0, so it is best used alongside another strategy.
+#[derive(Clone)]
+pub struct Strategy {
+    /// Minimum ratio of tombstones in a data block for it to be considered
+    /// dense with deletions
+    ///
+    /// Default = 0.2
+    pub min_density: f32,
+
+    /// Target segment size (compressed)
+    ///
+    /// Default = 64 MiB
+    pub target_size: u32,
+}
+
+impl Strategy {
+    /// Creates a new delete-aware strategy with custom minimum tombstone density
+    #[must_use]
+    pub fn new(min_density: f32, target_size: u32) -> Self {
+        Self {
+            min_density,
+            target_size,
+        }
+    }
+
+    /// Returns the amount of tombstones in the data blocks of the segment
+    /// that are dense with deletions & overlap with the given key ranges.
+    fn reclaimable_tombstones(&self, segment: &Segment, key_ranges: &[&KeyRange]) -> u64 {
+        let Some(tombstone_index) = &segment.tombstone_index else {
+            return 0;
+        };
+
+        tombstone_index
+            .entries()
+            .iter()
+            .filter(|entry| {
+                #[allow(clippy::cast_precision_loss)]
+                let density = entry.tombstone_count as f32 / entry.item_count.max(1) as f32;
+                density >= self.min_density
+            })
+            .filter(|entry| {
+                key_ranges
+                    .iter()
+                    .any(|key_range| entry.key_range.overlaps_with_key_range(key_range))
+            })
+            .map(|entry| u64::from(entry.tombstone_count))
+            .sum()
+    }
+}
+
+/// Returns the segments of the level that (transitively) overlap with the given segment.
+///
+/// If the level is disjoint, that is only the segment itself. Otherwise, overlapping segments
+/// can contain different versions of the same key, so they can only be compacted together.
+fn overlapping_level_segments(level: &Level, segment: &Arc<Segment>) -> Vec<Arc<Segment>> {
+    let mut segments = vec![segment.clone()];
+
+    if level.is_disjoint {
+        return segments;
+    }
+
+    loop {
+        let key_range = aggregate_key_range(&segments);
+
+        let overlapping = level
+            .overlapping_segments(&key_range)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if overlapping.len() == segments.len() {
+            return overlapping;
+        }
+
+        segments = overlapping;
+    }
+}
+
+impl Default for Strategy {
+    fn default() -> Self {
+        Self {
+            min_density: 0.2,
+            target_size: 64 * 1_024 * 1_024,
+        }
+    }
+}
+
+impl CompactionStrategy for Strategy {
+    fn align_to_next_level(&self) -> bool {
+        true
+    }
+
+    fn choose(&self, levels: &LevelManifest, _: &Config) -> Choice {
+        let resolved_view = levels.resolved_view();
+        let last_level_index = levels.last_level_index();
+
+        // NOTE: Reclaimable tombstones per byte that needs to be rewritten
+        let mut best: Option<(f64, CompactionInput)> = None;
+
+        // NOTE: L0 is skipped, because its segments overlap, so a single
+        // segment can not be pulled down without violating recency order
+        for (curr_level_index, level) in resolved_view.iter().enumerate().skip(1) {
+            // NOTE: Level count is 255 max
+            #[allow(clippy::cast_possible_truncation)]
+            let curr_level_index = curr_level_index as u8;
+
+            // NOTE: Tombstones in the last level have nothing left to shadow,
+            // so they are evicted by rewriting the segment in place
+            let dest_level_index = (curr_level_index + 1).min(last_level_index);
+
+            // NOTE: Tombstones that are not written into the last level are kept,
+            // so compacting them would rewrite the same tombstones over and over again
+            if dest_level_index != last_level_index {
+                continue;
+            }
+
+            let next_level = resolved_view
+                .get(usize::from(dest_level_index))
+                .filter(|_| dest_level_index != curr_level_index);
+
+            for segment in level.iter() {
+                if segment.tombstone_index.is_none() {
+                    continue;
+                }
+
+                let level_segments = overlapping_level_segments(level, segment);
+                let level_key_range = aggregate_key_range(&level_segments);
+
+                let overlapping_segments = next_level
+                    .map(|next_level| {
+                        next_level
+                            .overlapping_segments(&level_key_range)
+                            .cloned()
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+
+                let reclaimable = level_segments
+                    .iter()
+                    .map(|segment| {
+                        if next_level.is_some() {
+                            let key_ranges = overlapping_segments
+                                .iter()
+                                .map(|x| &x.metadata.key_range)
+                                .collect::<Vec<_>>();
+
+                            self.reclaimable_tombstones(segment, &key_ranges)
+                        } else {
+                            self.reclaimable_tombstones(segment, &[&segment.metadata.key_range])
+                        }
+                    })
+                    .sum::<u64>();
+
+                if reclaimable == 0 {
+                    continue;
+                }
+
+                let input_segments = level_segments
+                    .into_iter()
+                    .chain(overlapping_segments)
+                    .collect::<Vec<_>>();
+
+                let job_key_range = aggregate_key_range(&input_segments);
+
+                if levels.is_busy(&[curr_level_index, dest_level_index], &job_key_range) {
+                    continue;
+                }
+
+                let rewrite_bytes = input_segments
+                    .iter()
+                    .map(|x| x.metadata.file_size)
+                    .sum::<u64>()
+                    .max(1);
+
+                #[allow(clippy::cast_precision_loss)]
+                let score = reclaimable as f64 / rewrite_bytes as f64;
+
+                if best.as_ref().is_some_and(|(best, _)| *best >= score) {
+                    continue;
+                }
+
+                best = Some((
+                    score,
+                    CompactionInput {
+                        segment_ids: input_segments.iter().map(|x| x.metadata.id).collect(),
+                        dest_level: dest_level_index,
+                        target_size: u64::from(self.target_size),
+                    },
+                ));
+            }
+        }
+
+        best.map_or(Choice::DoNothing, |(_, input)| Choice::Merge(input))
+    }
+}
@@ -0,0 +1,40 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{Choice, CompactionStrategy};
+use crate::{config::Config, level_manifest::LevelManifest, segment::meta::SegmentId};
+
+/// Drops the segments that only contain keys of a prefix
+///
+/// The segments are chosen by [`crate::Tree::remove_prefix`], and only dropped
+/// if none of them has been compacted in the meantime, so either all or none
+/// of them are dropped.
+pub struct Strategy {
+    segment_ids: Vec<SegmentId>,
+}
+
+impl Strategy {
+    /// Configures a new `DropPrefix` compaction strategy
+    #[must_use]
+    pub fn new(segment_ids: Vec<SegmentId>) -> Self {
+        Self { segment_ids }
+    }
+}
+
+impl CompactionStrategy for Strategy {
+    fn choose(&self, levels: &LevelManifest, _: &Config) -> Choice {
+        let segments = levels.get_all_segments();
+
+        let is_available = self
+            .segment_ids
+            .iter()
+            .all(|id| segments.contains_key(id) && !levels.is_hidden(*id));
+
+        if self.segment_ids.is_empty() || !is_available {
+            Choice::DoNothing
+        } else {
+            Choice::Drop(self.segment_ids.clone())
+        }
+    }
+}
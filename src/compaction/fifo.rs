@@ -176,9 +176,19 @@ mod tests {
                 range_tombstone_count: 0,
                 uncompressed_size: 0,
                 seqnos: (0, created_at as u64),
+                key_sketch: None,
+                prefix_fences: None,
+                one_level_index: false,
             },
             block_cache,
 
+            seqno_index: None,
+            tombstone_index: None,
+
+            obsolete_path: std::sync::OnceLock::new(),
+
+            seqno_offset: 0,
+
             #[cfg(feature = "bloom")]
             bloom_filter: BloomFilter::with_fp_rate(1, 0.1),
         })
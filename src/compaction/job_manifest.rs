@@ -0,0 +1,348 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    coding::{Decode, DecodeError, Encode, EncodeError},
+    file::{rewrite_atomic, MAGIC_BYTES},
+    segment::meta::SegmentId,
+    HashSet,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    io::{Cursor, Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// Manifest of an unfinished compaction job
+///
+/// Every output segment that has been completely written (and synced) is
+/// recorded, so after a crash, the job can be resumed after the last
+/// recorded output, instead of starting from scratch.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JobManifest {
+    /// Input segment IDs (sorted)
+    pub inputs: Vec<SegmentId>,
+
+    /// Level to put the created segments into
+    pub dest_level: u8,
+
+    /// Completed output segment IDs, in key order
+    pub outputs: Vec<SegmentId>,
+}
+
+impl JobManifest {
+    /// Creates a job manifest without any outputs yet.
+    #[must_use]
+    pub fn new(inputs: &[SegmentId], dest_level: u8) -> Self {
+        let mut inputs = inputs.to_vec();
+        inputs.sort_unstable();
+        inputs.dedup();
+
+        Self {
+            inputs,
+            dest_level,
+            outputs: vec![],
+        }
+    }
+
+    /// Returns `true` if the manifest belongs to a job with the same inputs & destination.
+    #[must_use]
+    pub fn matches(&self, other: &Self) -> bool {
+        self.inputs == other.inputs && self.dest_level == other.dest_level
+    }
+
+    /// Returns `true` if the job shares any input segment with the other job.
+    #[must_use]
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.inputs.iter().any(|id| other.inputs.contains(id))
+    }
+
+    /// Returns the path of the job manifest file.
+    ///
+    /// Because the inputs of in-flight jobs are disjoint, the
+    /// lowest input segment ID identifies the job.
+    fn path(&self, folder: &Path) -> PathBuf {
+        let id = self.inputs.first().copied().unwrap_or_default();
+        folder.join(id.to_string())
+    }
+
+    /// Atomically writes the job manifest into the given folder.
+    pub fn persist(&self, folder: &Path) -> crate::Result<()> {
+        if !folder.try_exists()? {
+            std::fs::create_dir_all(folder)?;
+        }
+
+        let bytes = self.encode_into_vec()?;
+        rewrite_atomic(self.path(folder), &bytes)?;
+
+        Ok(())
+    }
+
+    /// Removes the job manifest file.
+    pub fn remove(&self, folder: &Path) -> std::io::Result<()> {
+        match std::fs::remove_file(self.path(folder)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes the job manifest file, and the files of the recorded output segments.
+    pub fn discard(&self, folder: &Path, segments_folder: &Path) {
+        log::debug!("Discarding unfinished compaction job of {:?}", self.inputs);
+
+        for segment_id in &self.outputs {
+            let segment_file_path = segments_folder.join(segment_id.to_string());
+
+            match std::fs::remove_file(&segment_file_path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    log::error!(
+                        "Failed to remove segment file {}: {e:?}",
+                        segment_file_path.display()
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        if let Err(e) = self.remove(folder) {
+            log::error!("Failed to remove job manifest of {:?}: {e:?}", self.inputs);
+        }
+    }
+
+    /// Loads all job manifests from the given folder.
+    pub fn load_all(folder: &Path) -> crate::Result<Vec<Self>> {
+        let mut jobs = vec![];
+
+        if !folder.try_exists()? {
+            return Ok(jobs);
+        }
+
+        for dirent in std::fs::read_dir(folder)? {
+            let dirent = dirent?;
+            let path = dirent.path();
+
+            // NOTE: Left-over temporary files of atomic rewrites
+            if dirent
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<SegmentId>().ok())
+                .is_none()
+            {
+                log::debug!("Deleting unfinished job manifest: {}", path.display());
+                std::fs::remove_file(&path)?;
+                continue;
+            }
+
+            let mut reader = Cursor::new(std::fs::read(&path)?);
+            jobs.push(Self::decode_from(&mut reader)?);
+        }
+
+        Ok(jobs)
+    }
+
+    /// Loads all job manifests from the given folder, deleting those
+    /// whose inputs are not all part of the tree anymore (i.e. the job has already
+    /// been committed, or was superseded by another job).
+    ///
+    /// Returns the output segment IDs of the jobs that can be resumed.
+    pub fn recover(folder: &Path, segment_ids: &[SegmentId]) -> crate::Result<HashSet<SegmentId>> {
+        let mut outputs = HashSet::default();
+
+        for job in Self::load_all(folder)? {
+            if job.inputs.iter().all(|id| segment_ids.contains(id)) {
+                log::debug!("Found resumable compaction job of {:?}", job.inputs);
+                outputs.extend(job.outputs);
+            } else {
+                log::debug!("Deleting stale job manifest of {:?}", job.inputs);
+                job.remove(folder)?;
+            }
+        }
+
+        Ok(outputs)
+    }
+}
+
+impl Encode for JobManifest {
+    fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        writer.write_all(&MAGIC_BYTES)?;
+        writer.write_u8(self.dest_level)?;
+
+        for ids in [&self.inputs, &self.outputs] {
+            // NOTE: "Truncation" is OK, because there are never 4 billion segments in a tree, I hope
+            #[allow(clippy::cast_possible_truncation)]
+            writer.write_u32::<BigEndian>(ids.len() as u32)?;
+
+            for &id in ids {
+                writer.write_u64::<BigEndian>(id)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Decode for JobManifest {
+    fn decode_from<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let mut header = [0; MAGIC_BYTES.len()];
+        reader.read_exact(&mut header)?;
+
+        if header != MAGIC_BYTES {
+            return Err(DecodeError::invalid_header(
+                "JobManifest",
+                &MAGIC_BYTES,
+                &header,
+            ));
+        }
+
+        let dest_level = reader.read_u8()?;
+
+        let mut read_ids = || -> Result<Vec<SegmentId>, DecodeError> {
+            let len = reader.read_u32::<BigEndian>()?;
+
+            (0..len)
+                .map(|_| Ok(reader.read_u64::<BigEndian>()?))
+                .collect()
+        };
+
+        let inputs = read_ids()?;
+        let outputs = read_ids()?;
+
+        Ok(Self {
+            inputs,
+            dest_level,
+            outputs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn job_manifest_roundtrip() -> crate::Result<()> {
+        let folder = tempfile::tempdir()?;
+        let folder = folder.path().join("compactions");
+
+        let mut job = JobManifest::new(&[7, 3, 5, 3], 2);
+        assert_eq!(vec![3, 5, 7], job.inputs);
+
+        job.outputs = vec![10, 11];
+        job.persist(&folder)?;
+
+        assert_eq!(vec![job.clone()], JobManifest::load_all(&folder)?);
+
+        // NOTE: Segment 7 does not exist anymore, so the job has been committed
+        assert!(JobManifest::recover(&folder, &[3, 5, 10, 11])?.is_empty());
+        assert!(JobManifest::load_all(&folder)?.is_empty());
+
+        job.persist(&folder)?;
+        assert_eq!(
+            [10, 11].into_iter().collect::<HashSet<_>>(),
+            JobManifest::recover(&folder, &[3, 5, 7])?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn job_manifest_resume_compaction() -> crate::Result<()> {
+        use crate::{
+            file::{COMPACTIONS_FOLDER, SEGMENTS_FOLDER},
+            AbstractTree, Config,
+        };
+
+        const ITEM_COUNT: u64 = 10_000;
+
+        let folder = tempfile::tempdir()?;
+        let crashed_folder = tempfile::tempdir()?;
+
+        let tree = Config::new(&folder).open()?;
+
+        for seqno in 0..2 {
+            for x in 0..ITEM_COUNT {
+                tree.insert(x.to_be_bytes(), seqno.to_string(), seqno);
+            }
+            tree.flush_active_memtable(0)?;
+        }
+
+        let mut inputs = tree
+            .levels
+            .read()
+            .expect("lock is poisoned")
+            .iter()
+            .map(|x| x.metadata.id)
+            .collect::<Vec<_>>();
+        inputs.sort_unstable();
+
+        fs_extra::dir::copy(
+            &folder,
+            &crashed_folder,
+            &fs_extra::dir::CopyOptions::new().content_only(true),
+        )
+        .expect("should copy tree");
+
+        tree.major_compact(16 * 1_024, 0)?;
+
+        let (dest_level, first_output) = {
+            let levels = tree.levels.read().expect("lock is poisoned");
+            let dest_level = levels.last_level_index();
+
+            let mut outputs = levels
+                .levels
+                .get(usize::from(dest_level))
+                .expect("level should exist")
+                .segments
+                .clone();
+            assert!(outputs.len() > 1);
+
+            outputs.sort_by(|a, b| a.metadata.key_range.0.cmp(&b.metadata.key_range.0));
+            (
+                dest_level,
+                outputs.first().expect("should exist").metadata.id,
+            )
+        };
+
+        // NOTE: Simulate a crash after the first output segment has been written
+        std::fs::copy(
+            folder
+                .path()
+                .join(SEGMENTS_FOLDER)
+                .join(first_output.to_string()),
+            crashed_folder
+                .path()
+                .join(SEGMENTS_FOLDER)
+                .join(first_output.to_string()),
+        )?;
+
+        let job_folder = crashed_folder.path().join(COMPACTIONS_FOLDER);
+
+        JobManifest {
+            inputs,
+            dest_level,
+            outputs: vec![first_output],
+        }
+        .persist(&job_folder)?;
+
+        let tree = Config::new(&crashed_folder).open()?;
+        tree.major_compact(16 * 1_024, 0)?;
+
+        assert!(tree
+            .levels
+            .read()
+            .expect("lock is poisoned")
+            .iter()
+            .any(|x| x.metadata.id == first_output));
+        assert!(JobManifest::load_all(&job_folder)?.is_empty());
+
+        assert_eq!(ITEM_COUNT as usize, tree.len()?);
+
+        for x in 0..ITEM_COUNT {
+            assert_eq!(b"1", &*tree.get(x.to_be_bytes())?.expect("should exist"));
+        }
+
+        Ok(())
+    }
+}
@@ -4,7 +4,11 @@
 
 use super::{Choice, CompactionStrategy, Input as CompactionInput};
 use crate::{
-    config::Config, key_range::KeyRange, level_manifest::LevelManifest, segment::Segment, HashSet,
+    config::Config,
+    key_range::KeyRange,
+    level_manifest::{level::Level, LevelManifest},
+    segment::Segment,
+    HashSet,
 };
 use std::{ops::Deref, sync::Arc};
 
@@ -46,17 +50,20 @@ pub struct Strategy {
     pub level_ratio: u8,
 }
 
+/// Default amount of L0 segments at which they are merged into L1
+pub const DEFAULT_L0_THRESHOLD: u8 = 4;
+
 impl Default for Strategy {
     fn default() -> Self {
         Self {
-            l0_threshold: 4,
+            l0_threshold: DEFAULT_L0_THRESHOLD,
             target_size: 64 * 1_024 * 1_024,
             level_ratio: 8,
         }
     }
 }
 
-fn aggregate_key_range(segments: &[Arc<Segment>]) -> KeyRange {
+pub(super) fn aggregate_key_range(segments: &[Arc<Segment>]) -> KeyRange {
     let (mut min, mut max) = segments
         .first()
         .expect("segment should always exist")
@@ -82,19 +89,25 @@ fn desired_level_size_in_bytes(level_idx: u8, ratio: u8, target_size: u32) -> us
 }
 
 impl CompactionStrategy for Strategy {
+    fn align_to_next_level(&self) -> bool {
+        true
+    }
+
+    fn l0_threshold(&self) -> Option<usize> {
+        Some(self.l0_threshold.into())
+    }
+
     #[allow(clippy::too_many_lines)]
     fn choose(&self, levels: &LevelManifest, _: &Config) -> Choice {
         let resolved_view = levels.resolved_view();
 
-        // If there are any levels that already have a compactor working on it
-        // we can't touch those, because that could cause a race condition
+        // NOTE: If a compactor is already working on a key range of the levels,
+        // we can't touch that key range, because that could cause a race condition
         // violating the leveled compaction invariance of having a single sorted
         // run per level
         //
-        // TODO: However, this can probably improved by checking two compaction
-        // workers just don't cross key ranges
-        let busy_levels = levels.busy_levels();
-
+        // Compactions of disjoint key ranges can run at the same time, because
+        // they write disjoint segments into their destination level.
         for (curr_level_index, level) in resolved_view
             .iter()
             .enumerate()
@@ -112,10 +125,6 @@ impl CompactionStrategy for Strategy {
                 continue;
             }
 
-            if busy_levels.contains(&curr_level_index) || busy_levels.contains(&next_level_index) {
-                continue;
-            }
-
             let curr_level_bytes = level.size();
 
             let desired_bytes =
@@ -158,13 +167,31 @@ impl CompactionStrategy for Strategy {
                 // Get overlapping segments in next level
                 let key_range = aggregate_key_range(&segments_to_compact);
 
-                let next_level_overlapping_segment_ids: Vec<_> = next_level
+                let next_level_overlapping_segments: Vec<_> = next_level
                     .overlapping_segments(&key_range)
+                    .cloned()
+                    .collect();
+
+                let next_level_overlapping_segment_ids: Vec<_> = next_level_overlapping_segments
+                    .iter()
                     .map(|x| x.metadata.id)
                     .collect();
 
                 segment_ids.extend(&next_level_overlapping_segment_ids);
 
+                let job_key_range = aggregate_key_range(
+                    &level
+                        .iter()
+                        .filter(|x| segment_ids.contains(&x.metadata.id))
+                        .chain(&next_level_overlapping_segments)
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                );
+
+                if levels.is_busy(&[curr_level_index, next_level_index], &job_key_range) {
+                    continue;
+                }
+
                 let choice = CompactionInput {
                     segment_ids: {
                         let mut v = segment_ids.into_iter().collect::<Vec<_>>();
@@ -187,10 +214,7 @@ impl CompactionStrategy for Strategy {
                 return Choice::DoNothing;
             };
 
-            if first_level.len() >= self.l0_threshold.into()
-                && !busy_levels.contains(&0)
-                && !busy_levels.contains(&1)
-            {
+            if first_level.len() >= self.l0_threshold.into() {
                 let mut level = first_level.clone();
                 level.sort_by_key_range(); // TODO: disjoint levels shouldn't need sort
 
@@ -204,12 +228,28 @@ impl CompactionStrategy for Strategy {
                 // Get overlapping segments in next level
                 let key_range = aggregate_key_range(&level);
 
-                let next_level_overlapping_segment_ids: Vec<_> = next_level
+                let next_level_overlapping_segments: Vec<_> = next_level
                     .overlapping_segments(&key_range)
-                    .map(|x| x.metadata.id)
+                    .cloned()
                     .collect();
 
-                segment_ids.extend(&next_level_overlapping_segment_ids);
+                segment_ids.extend(
+                    next_level_overlapping_segments
+                        .iter()
+                        .map(|x| x.metadata.id),
+                );
+
+                let job_key_range = aggregate_key_range(
+                    &level
+                        .iter()
+                        .chain(&next_level_overlapping_segments)
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                );
+
+                if levels.is_busy(&[0, 1], &job_key_range) {
+                    return Choice::DoNothing;
+                }
 
                 let choice = CompactionInput {
                     segment_ids,
@@ -217,7 +257,7 @@ impl CompactionStrategy for Strategy {
                     target_size: u64::from(self.target_size),
                 };
 
-                if next_level_overlapping_segment_ids.is_empty() && level.is_disjoint {
+                if next_level_overlapping_segments.is_empty() && level.is_disjoint {
                     return Choice::Move(choice);
                 }
                 return Choice::Merge(choice);
@@ -226,6 +266,55 @@ impl CompactionStrategy for Strategy {
 
         Choice::DoNothing
     }
+
+    fn compaction_debt(&self, levels: &LevelManifest) -> u64 {
+        let Some(first_level) = levels.levels.first() else {
+            return 0;
+        };
+
+        let mut debt = 0;
+
+        // NOTE: Bytes that are pushed into a level by compacting the level above it
+        let mut carried_bytes = if first_level.len() >= self.l0_threshold.into() {
+            let first_level_bytes = first_level.size();
+            let next_level_bytes = levels.levels.get(1).map(Level::size).unwrap_or_default();
+
+            debt += first_level_bytes + next_level_bytes;
+            first_level_bytes
+        } else {
+            0
+        };
+
+        for (curr_level_index, pair) in levels.levels.windows(2).enumerate().skip(1) {
+            let [level, next_level] = pair else {
+                unreachable!("windows should have size 2");
+            };
+
+            // NOTE: Level count is 255 max
+            #[allow(clippy::cast_possible_truncation)]
+            let curr_level_index = curr_level_index as u8;
+
+            let curr_level_bytes = level.size() + carried_bytes;
+
+            let desired_bytes =
+                desired_level_size_in_bytes(curr_level_index, self.level_ratio, self.target_size)
+                    as u64;
+
+            let overshoot = curr_level_bytes.saturating_sub(desired_bytes);
+
+            if overshoot > 0 {
+                // NOTE: The overshoot is merged with its share of the next level
+                let next_level_share = u128::from(overshoot) * u128::from(next_level.size())
+                    / u128::from(curr_level_bytes);
+
+                debt += overshoot + u64::try_from(next_level_share).unwrap_or(u64::MAX);
+            }
+
+            carried_bytes = overshoot;
+        }
+
+        debt
+    }
 }
 
 #[cfg(test)]
@@ -301,9 +390,19 @@ mod tests {
                 range_tombstone_count: 0,
                 uncompressed_size: 0,
                 seqnos: (0, 0),
+                key_sketch: None,
+                prefix_fences: None,
+                one_level_index: false,
             },
             block_cache,
 
+            seqno_index: None,
+            tombstone_index: None,
+
+            obsolete_path: std::sync::OnceLock::new(),
+
+            seqno_offset: 0,
+
             #[cfg(feature = "bloom")]
             bloom_filter: BloomFilter::with_fp_rate(1, 0.1),
         })
@@ -377,7 +476,7 @@ mod tests {
             })
         );
 
-        levels.hide_segments(&[4]);
+        levels.start_job(&[4], None);
 
         assert_eq!(
             compactor.choose(&levels, &Config::default()),
@@ -440,7 +539,7 @@ mod tests {
             })
         );
 
-        levels.hide_segments(&[5]);
+        levels.start_job(&[5], None);
         assert_eq!(
             compactor.choose(&levels, &Config::default()),
             Choice::DoNothing
@@ -449,6 +548,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn leveled_busy_disjoint_key_range() -> crate::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let compactor = Strategy {
+            target_size: 64 * 1_024 * 1_024,
+            ..Default::default()
+        };
+
+        #[rustfmt::skip]
+        let mut levels = build_levels(tempdir.path(), vec![
+            vec![(1, "h", "t"), (2, "h", "t"), (3, "h", "t"), (4, "h", "t")],
+            vec![(5, "a", "g")],
+            vec![],
+            vec![],
+        ])?;
+
+        // NOTE: Segment 5 is being compacted, but its key range does not overlap
+        levels.start_job(&[5], None);
+
+        assert_eq!(
+            compactor.choose(&levels, &Config::default()),
+            Choice::Merge(CompactionInput {
+                dest_level: 1,
+                segment_ids: vec![1, 2, 3, 4],
+                target_size: 64 * 1_024 * 1_024
+            })
+        );
+
+        levels.show_segments(&[5]);
+        assert!(levels.compaction_jobs().is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn leveled_deeper_level_with_overlap() -> crate::Result<()> {
         let tempdir = tempfile::tempdir()?;
@@ -628,4 +761,47 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn leveled_compaction_debt() -> crate::Result<()> {
+        const MIB: u64 = 1_024 * 1_024;
+
+        let tempdir = tempfile::tempdir()?;
+        let compactor = Strategy {
+            target_size: 64 * 1_024 * 1_024,
+            level_ratio: 2,
+            ..Default::default()
+        };
+
+        #[rustfmt::skip]
+        let levels = build_levels(tempdir.path(), vec![
+            vec![],
+            vec![(1, "a", "b")],
+            vec![],
+            vec![],
+        ])?;
+        assert_eq!(0, compactor.compaction_debt(&levels));
+
+        // NOTE: L1 overshoots by one segment, which is merged with its share of L2
+        #[rustfmt::skip]
+        let levels = build_levels(tempdir.path(), vec![
+            vec![],
+            vec![(1, "a", "b"), (2, "c", "d"), (3, "e", "f")],
+            vec![(4, "a", "b"), (5, "c", "d"), (6, "e", "f")],
+            vec![],
+        ])?;
+        assert_eq!(128 * MIB, compactor.compaction_debt(&levels));
+
+        // NOTE: L0 is merged into L1 as a whole
+        #[rustfmt::skip]
+        let levels = build_levels(tempdir.path(), vec![
+            vec![(1, "a", "b"), (2, "a", "b"), (3, "a", "b"), (4, "a", "b")],
+            vec![],
+            vec![],
+            vec![],
+        ])?;
+        assert_eq!(256 * MIB, Strategy::default().compaction_debt(&levels),);
+
+        Ok(())
+    }
 }
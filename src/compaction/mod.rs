@@ -4,15 +4,21 @@
 
 //! Contains compaction strategies
 
+pub(crate) mod delete_aware;
+pub(crate) mod drop_prefix;
 pub(crate) mod fifo;
+pub(crate) mod job_manifest;
 pub(crate) mod leveled;
 pub(crate) mod maintenance;
 pub(crate) mod major;
 pub(crate) mod pulldown;
+pub(crate) mod rewrite;
 pub(crate) mod stream;
 pub(crate) mod tiered;
+pub(crate) mod warm;
 pub(crate) mod worker;
 
+pub use delete_aware::Strategy as DeleteAware;
 pub use fifo::Strategy as Fifo;
 pub use leveled::Strategy as Leveled;
 pub use tiered::Strategy as SizeTiered;
@@ -71,4 +77,27 @@ pub enum Choice {
 pub trait CompactionStrategy {
     /// Decides on what to do based on the current state of the LSM-tree's levels
     fn choose(&self, _: &LevelManifest, config: &Config) -> Choice;
+
+    /// If `true`, compaction outputs are not only cut by target size, but also at the
+    /// key boundaries of the segments in the level below the destination level
+    ///
+    /// This keeps future compactions of the created segments narrow.
+    fn align_to_next_level(&self) -> bool {
+        false
+    }
+
+    /// Estimates the amount of bytes that need to be rewritten
+    /// to bring all levels within their targets
+    ///
+    /// Strategies without level targets report no debt.
+    fn compaction_debt(&self, _: &LevelManifest) -> u64 {
+        0
+    }
+
+    /// Amount of first level (L0) segments at which the strategy compacts the first level
+    ///
+    /// Strategies that do not trigger on the first level segment count return `None`.
+    fn l0_threshold(&self) -> Option<usize> {
+        None
+    }
 }
@@ -0,0 +1,46 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{Choice, CompactionStrategy, Input as CompactionInput};
+use crate::{config::Config, level_manifest::LevelManifest};
+
+/// Key rotation
+///
+/// Rewrites a single segment that is not encrypted using the given key
+/// back into its own level, so old keys can be retired incrementally.
+pub struct Strategy {
+    key_id: u32,
+}
+
+impl Strategy {
+    /// Configures a new key rotation strategy
+    #[must_use]
+    pub fn new(key_id: u32) -> Self {
+        Self { key_id }
+    }
+}
+
+impl CompactionStrategy for Strategy {
+    fn choose(&self, levels: &LevelManifest, _: &Config) -> Choice {
+        let resolved_view = levels.resolved_view();
+
+        for (level_index, level) in resolved_view.iter().enumerate() {
+            // NOTE: Level count is 255 max
+            #[allow(clippy::cast_possible_truncation)]
+            let level_index = level_index as u8;
+
+            if let Some(segment) = level.iter().find(|x| x.key_id() != Some(self.key_id)) {
+                return Choice::Merge(CompactionInput {
+                    segment_ids: vec![segment.metadata.id],
+                    dest_level: level_index,
+
+                    // NOTE: Rewrite the segment 1:1, so the level keeps its shape
+                    target_size: u64::MAX,
+                });
+            }
+        }
+
+        Choice::DoNothing
+    }
+}
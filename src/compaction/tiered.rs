@@ -117,6 +117,35 @@ impl CompactionStrategy for Strategy {
         // the `max_memtable_size` AT ALL
         super::maintenance::Strategy.choose(levels, config)
     }
+
+    fn compaction_debt(&self, levels: &LevelManifest) -> u64 {
+        let level_count = levels.levels.len();
+
+        levels
+            .levels
+            .iter()
+            .enumerate()
+            .take(level_count.saturating_sub(1))
+            .map(|(curr_level_index, level)| {
+                // NOTE: Level count is 255 max
+                #[allow(clippy::cast_possible_truncation)]
+                let curr_level_index = curr_level_index as u8;
+
+                let curr_level_bytes = level.size();
+
+                let desired_bytes =
+                    desired_level_size_in_bytes(curr_level_index, self.level_ratio, self.base_size)
+                        as u64;
+
+                // NOTE: In tiered mode, the whole level is merged into the next one
+                if curr_level_bytes >= desired_bytes {
+                    curr_level_bytes
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
 }
 
 #[cfg(test)]
@@ -180,9 +209,19 @@ mod tests {
                 range_tombstone_count: 0,
                 uncompressed_size: size_mib * 1_024 * 1_024,
                 seqnos: (0, max_seqno),
+                key_sketch: None,
+                prefix_fences: None,
+                one_level_index: false,
             },
             block_cache,
 
+            seqno_index: None,
+            tombstone_index: None,
+
+            obsolete_path: std::sync::OnceLock::new(),
+
+            seqno_offset: 0,
+
             #[cfg(feature = "bloom")]
             bloom_filter: BloomFilter::with_fp_rate(1, 0.1),
         })
@@ -0,0 +1,200 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    block_cache::BlockCache,
+    segment::{
+        block::header::Header as BlockHeader,
+        block_index::{block_handle::KeyedBlockHandle, IndexBlock},
+        meta::SegmentId,
+        value_block::{CachePolicy, ValueBlock},
+        Segment,
+    },
+    tree::inner::TreeId,
+    InternalValue, UserKey,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Key ranges of the input data blocks of a compaction that are in the block cache
+///
+/// A data block contains the keys between the end key of the previous block
+/// (or the segment's min key) and its own end key, so the ranges are stored as
+/// `[lower, upper]`, sorted and disjoint.
+#[derive(Default)]
+pub struct HotRanges(Vec<(UserKey, UserKey)>);
+
+impl HotRanges {
+    /// Collects the key ranges of the data blocks of the given segments that are in the block cache.
+    ///
+    /// Index blocks are read without being inserted into the block cache.
+    pub fn collect(segments: &[Arc<Segment>]) -> crate::Result<Self> {
+        let mut ranges = vec![];
+
+        for segment in segments {
+            let segment_id = (segment.tree_id, segment.metadata.id).into();
+            let mut lower = segment.metadata.key_range.min().clone();
+
+            for handle in segment.block_index.top_level_index()?.iter() {
+                let index_block = segment
+                    .block_index
+                    .load_index_block(handle, CachePolicy::Read)?;
+
+                for data_handle in index_block.items.iter() {
+                    if segment
+                        .block_cache
+                        .contains_disk_block(segment_id, data_handle.offset)
+                    {
+                        ranges.push((lower.clone(), data_handle.end_key.clone()));
+                    }
+
+                    lower = data_handle.end_key.clone();
+                }
+            }
+        }
+
+        ranges.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut merged: Vec<(UserKey, UserKey)> = Vec::with_capacity(ranges.len());
+
+        for (lower, upper) in ranges {
+            if let Some(last) = merged.last_mut() {
+                if lower <= last.1 {
+                    if upper > last.1 {
+                        last.1 = upper;
+                    }
+                    continue;
+                }
+            }
+
+            merged.push((lower, upper));
+        }
+
+        Ok(Self(merged))
+    }
+
+    /// Returns `true` if no input data block was in the block cache.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns `true` if the keys in `[first_key, last_key]` overlap with any hot range.
+    fn overlaps(&self, first_key: &UserKey, last_key: &UserKey) -> bool {
+        // NOTE: Skip all ranges that end before the block starts
+        let idx = self.0.partition_point(|(_, upper)| upper < first_key);
+
+        self.0.get(idx).is_some_and(|(lower, _)| lower <= last_key)
+    }
+}
+
+/// Inserts the data blocks written by a compaction into the block cache,
+/// if they overlap with any hot range of its input segments
+pub struct CacheWarmer {
+    hot_ranges: HotRanges,
+    block_cache: Arc<BlockCache>,
+    tree_id: TreeId,
+    inserted: AtomicUsize,
+}
+
+impl CacheWarmer {
+    pub fn new(hot_ranges: HotRanges, block_cache: Arc<BlockCache>, tree_id: TreeId) -> Self {
+        Self {
+            hot_ranges,
+            block_cache,
+            tree_id,
+            inserted: AtomicUsize::default(),
+        }
+    }
+
+    /// Returns the amount of data blocks that were inserted into the block cache.
+    pub fn inserted(&self) -> usize {
+        self.inserted.load(Ordering::Relaxed)
+    }
+
+    /// Inserts a data block that was written at the given offset of a segment into the block cache,
+    /// if it overlaps with any hot range.
+    pub fn block_written(
+        &self,
+        segment_id: SegmentId,
+        offset: u64,
+        header: &BlockHeader,
+        items: &[InternalValue],
+    ) {
+        let (Some(first), Some(last)) = (items.first(), items.last()) else {
+            return;
+        };
+
+        if !self
+            .hot_ranges
+            .overlaps(&first.key.user_key, &last.key.user_key)
+        {
+            return;
+        }
+
+        let block = ValueBlock {
+            header: header.clone(),
+            items: items.into(),
+        };
+
+        self.block_cache.insert_disk_block(
+            (self.tree_id, segment_id).into(),
+            offset,
+            Arc::new(block),
+        );
+
+        self.inserted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if any of the given data blocks of a segment was inserted into the block cache.
+    pub fn contains_any(&self, segment_id: SegmentId, handles: &[KeyedBlockHandle]) -> bool {
+        let segment_id = (self.tree_id, segment_id).into();
+
+        handles.iter().any(|handle| {
+            self.block_cache
+                .contains_disk_block(segment_id, handle.offset)
+        })
+    }
+
+    /// Inserts an index block that was written at the given offset of a segment into the block cache.
+    pub fn index_block_written(&self, segment_id: SegmentId, offset: u64, block: IndexBlock) {
+        self.block_cache.insert_index_block(
+            (self.tree_id, segment_id).into(),
+            offset,
+            Arc::new(block),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    fn hot_ranges(ranges: &[(&str, &str)]) -> HotRanges {
+        HotRanges(
+            ranges
+                .iter()
+                .map(|(lower, upper)| (lower.as_bytes().into(), upper.as_bytes().into()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn hot_ranges_overlaps() {
+        let ranges = hot_ranges(&[("c", "e"), ("h", "k")]);
+
+        let key = |x: &str| UserKey::from(x.as_bytes());
+
+        assert!(!ranges.overlaps(&key("a"), &key("b")));
+        assert!(ranges.overlaps(&key("a"), &key("c")));
+        assert!(ranges.overlaps(&key("d"), &key("d")));
+        assert!(ranges.overlaps(&key("e"), &key("f")));
+        assert!(!ranges.overlaps(&key("f"), &key("g")));
+        assert!(ranges.overlaps(&key("f"), &key("i")));
+        assert!(ranges.overlaps(&key("j"), &key("z")));
+        assert!(!ranges.overlaps(&key("l"), &key("z")));
+    }
+}
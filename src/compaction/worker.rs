@@ -4,19 +4,32 @@
 
 use super::{CompactionStrategy, Input as CompactionPayload};
 use crate::{
-    compaction::{stream::CompactionStream, Choice},
-    file::SEGMENTS_FOLDER,
+    compaction::{
+        job_manifest::JobManifest,
+        stream::CompactionStream,
+        warm::{CacheWarmer, HotRanges},
+        Choice,
+    },
+    config::ConfigFlags,
+    durability::SyncTracker,
+    error::{ErrorContext, Operation},
+    file::{fsync_directory, COMPACTIONS_FOLDER, SEGMENTS_FOLDER},
     level_manifest::LevelManifest,
     merge::{BoxedIterator, Merger},
+    metrics,
+    ops_log::{OpsEvent, OpsLog},
+    read_pool::ReadPool,
     segment::{
         block_index::two_level_index::TwoLevelBlockIndex, id::GlobalSegmentId,
-        multi_writer::MultiWriter, Segment,
+        multi_writer::MultiWriter, seqno_index::SeqnoIndex, tombstone_index::TombstoneIndex,
+        trailer::SegmentFileTrailer, Segment,
     },
     stop_signal::StopSignal,
-    tree::inner::{SealedMemtables, TreeId},
+    tree::{amplification::WriteStats, inner::TreeId, level_stats::LevelStatsTracker},
     Config, HashSet,
 };
 use std::{
+    path::Path,
     sync::{atomic::AtomicU64, Arc, RwLock, RwLockWriteGuard},
     time::Instant,
 };
@@ -24,9 +37,6 @@ use std::{
 #[cfg(feature = "bloom")]
 use crate::bloom::BloomFilter;
 
-#[cfg(feature = "bloom")]
-use crate::segment::writer::BloomConstructionPolicy;
-
 /// Compaction options
 pub struct Options {
     pub tree_id: TreeId,
@@ -39,9 +49,6 @@ pub struct Options {
     /// Levels manifest.
     pub levels: Arc<RwLock<LevelManifest>>,
 
-    /// Sealed memtables (required for temporarily locking).
-    pub sealed_memtables: Arc<RwLock<SealedMemtables>>,
-
     /// Compaction strategy.
     ///
     /// The one inside `config` is NOT used.
@@ -52,6 +59,28 @@ pub struct Options {
 
     /// Evicts items that are older than this seqno
     pub eviction_seqno: u64,
+
+    /// Encrypts created segments using this key instead of the current key
+    pub key_id: Option<u32>,
+
+    /// Cumulative byte counters of the tree
+    pub write_stats: Arc<WriteStats>,
+
+    /// Per-level runtime counters of the tree
+    pub level_stats: Arc<LevelStatsTracker>,
+
+    /// Log of flushes & compactions of the tree, if enabled
+    pub ops_log: Option<Arc<OpsLog>>,
+
+    /// Tracks written segment files that have not been synced yet
+    pub sync_tracker: Arc<SyncTracker>,
+
+    /// Threads of the tree that read ahead of scans
+    pub read_pool: Arc<ReadPool>,
+
+    /// Latency histograms of the tree
+    #[cfg(feature = "metrics")]
+    pub latencies: Arc<crate::metrics::LatencyHistograms>,
 }
 
 impl Options {
@@ -59,12 +88,24 @@ impl Options {
         Self {
             tree_id: tree.id,
             segment_id_generator: tree.segment_id_counter.clone(),
-            config: tree.config.clone(),
-            sealed_memtables: tree.sealed_memtables.clone(),
+            config: {
+                // NOTE: The compression may have been changed at runtime
+                let mut config = tree.config.clone();
+                config.compression = tree.compression();
+                config
+            },
             levels: tree.levels.clone(),
             stop_signal: tree.stop_signal.clone(),
             strategy,
             eviction_seqno: 0,
+            key_id: None,
+            write_stats: tree.write_stats.clone(),
+            level_stats: tree.level_stats.clone(),
+            ops_log: tree.ops_log.clone(),
+            sync_tracker: tree.sync_tracker.clone(),
+            read_pool: tree.read_pool.clone(),
+            #[cfg(feature = "metrics")]
+            latencies: tree.latencies.clone(),
         }
     }
 }
@@ -72,7 +113,9 @@ impl Options {
 /// Runs compaction task.
 ///
 /// This will block until the compactor is fully finished.
-pub fn do_compaction(opts: &Options) -> crate::Result<()> {
+///
+/// Returns `false` if the compaction strategy chose to do nothing.
+pub fn do_compaction(opts: &Options) -> crate::Result<bool> {
     log::trace!("compactor: acquiring levels manifest lock");
     let mut original_levels = opts.levels.write().expect("lock is poisoned");
 
@@ -82,7 +125,7 @@ pub fn do_compaction(opts: &Options) -> crate::Result<()> {
     log::debug!("compactor: choice: {choice:?}");
 
     match choice {
-        Choice::Merge(payload) => merge_segments(original_levels, opts, &payload),
+        Choice::Merge(payload) => merge_segments(original_levels, opts, &payload)?,
         Choice::Move(payload) => {
             let segment_map = original_levels.get_all_segments();
 
@@ -99,7 +142,7 @@ pub fn do_compaction(opts: &Options) -> crate::Result<()> {
                             .insert(segment);
                     }
                 }
-            })
+            })?;
         }
         Choice::Drop(payload) => {
             drop_segments(
@@ -110,13 +153,14 @@ pub fn do_compaction(opts: &Options) -> crate::Result<()> {
                     .map(|x| (opts.tree_id, x).into())
                     .collect::<Vec<_>>(),
             )?;
-            Ok(())
         }
         Choice::DoNothing => {
             log::trace!("Compactor chose to do nothing");
-            Ok(())
+            return Ok(false);
         }
     }
+
+    Ok(true)
 }
 
 #[allow(clippy::too_many_lines)]
@@ -131,28 +175,41 @@ fn merge_segments(
 
     let segments_base_folder = opts.config.path.join(SEGMENTS_FOLDER);
 
-    let merge_iter = {
-        let to_merge: Vec<_> = {
-            let segments = levels.get_all_segments();
+    let mut bytes_read = 0;
 
-            payload
-                .segment_ids
-                .iter()
-                // NOTE: Throw away duplicate segment IDs
-                .collect::<HashSet<_>>()
-                .into_iter()
-                .filter_map(|x| segments.get(x))
-                .cloned()
-                .collect()
-        };
+    for (level_idx, level) in levels.levels.iter().enumerate() {
+        for segment in &level.segments {
+            if payload.segment_ids.contains(&segment.metadata.id) {
+                opts.level_stats
+                    .record_compaction_input(level_idx, segment.metadata.file_size);
+                bytes_read += segment.metadata.file_size;
+            }
+        }
+    }
+
+    let to_merge: Vec<_> = {
+        let segments = levels.get_all_segments();
+
+        payload
+            .segment_ids
+            .iter()
+            // NOTE: Throw away duplicate segment IDs
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .filter_map(|x| segments.get(x))
+            .cloned()
+            .collect()
+    };
 
+    let merge_iter = {
         let mut segment_readers: Vec<BoxedIterator<'_>> = Vec::with_capacity(to_merge.len());
 
-        for segment in to_merge {
+        for segment in &to_merge {
             let iter = Box::new(
                 segment
                     .iter()
-                    .cache_policy(crate::segment::value_block::CachePolicy::Read),
+                    .cache_policy(crate::segment::value_block::CachePolicy::Read)
+                    .read_buffer(opts.config.compaction_read_buffer_size),
             );
             segment_readers.push(iter);
         }
@@ -163,9 +220,64 @@ fn merge_segments(
 
     let last_level = levels.last_level_index();
 
-    levels.hide_segments(&payload.segment_ids);
+    // NOTE: Cut the created segments at the start keys of the segments in the next level,
+    // so every created segment overlaps with as few segments as possible, once it is compacted further
+    let boundaries = if opts.strategy.align_to_next_level() {
+        levels
+            .levels
+            .get(usize::from(payload.dest_level) + 1)
+            .filter(|level| level.is_disjoint)
+            .map(|level| {
+                let mut keys = level
+                    .iter()
+                    .map(|segment| segment.metadata.key_range.0.clone())
+                    .collect::<Vec<_>>();
+                keys.sort();
+                keys
+            })
+            .unwrap_or_default()
+    } else {
+        vec![]
+    };
+
+    levels.start_job(&payload.segment_ids, Some(payload.dest_level));
     drop(levels);
 
+    // NOTE: Cache warming is best-effort, so errors do not fail the compaction
+    let cache_warmer = if opts
+        .config
+        .flags
+        .contains(ConfigFlags::COMPACTION_CACHE_WARMING)
+    {
+        HotRanges::collect(&to_merge)
+            .map_err(|e| log::warn!("compactor: failed to collect hot key ranges: {e:?}"))
+            .ok()
+            .filter(|hot_ranges| !hot_ranges.is_empty())
+            .map(|hot_ranges| {
+                Arc::new(CacheWarmer::new(
+                    hot_ranges,
+                    opts.config.block_cache.clone(),
+                    opts.tree_id,
+                ))
+            })
+    } else {
+        None
+    };
+
+    let job_folder = opts.config.path.join(COMPACTIONS_FOLDER);
+    let (mut job, reused_segments) = resume_job(opts, payload, &job_folder, &segments_base_folder);
+    let reused_count = reused_segments.len();
+
+    // NOTE: The reused segments already contain all versions of the keys up to their last key
+    let resume_key = reused_segments
+        .last()
+        .map(|segment| segment.metadata.key_range.1.clone());
+
+    let merge_iter = merge_iter.skip_while(|item| match (item, &resume_key) {
+        (Ok(item), Some(resume_key)) => &item.key.user_key <= resume_key,
+        _ => false,
+    });
+
     // NOTE: Only evict tombstones when reaching the last level,
     // That way we don't resurrect data beneath the tombstone
     let is_last_level = payload.dest_level == last_level;
@@ -173,6 +285,12 @@ fn merge_segments(
 
     let start = Instant::now();
 
+    // NOTE: All segments of the compaction are encrypted using the same key
+    let segment_cipher = match opts.key_id {
+        Some(key_id) => opts.config.segment_cipher_with_key(key_id),
+        None => opts.config.segment_cipher(),
+    };
+
     let mut segment_writer = MultiWriter::new(
         opts.segment_id_generator.clone(),
         payload.target_size,
@@ -184,7 +302,17 @@ fn merge_segments(
             index_block_size: opts.config.index_block_size,
         },
     )?
-    .use_compression(opts.config.compression);
+    .use_compression(opts.config.compression)
+    .use_cipher(segment_cipher.clone())
+    .use_sync(opts.sync_tracker.sync_on_write())
+    .use_seqno_index(opts.config.flags.contains(ConfigFlags::SEQNO_INDEX))
+    .use_prefix_fences(opts.config.prefix_fence_len)
+    .use_one_level_index(opts.config.one_level_index_max_size)
+    .use_shortened_index_keys(opts.config.flags.contains(ConfigFlags::SHORTEN_INDEX_KEYS))
+    .use_value_checksums(opts.config.flags.contains(ConfigFlags::VALUE_CHECKSUMS))
+    .use_logical_clock(opts.config.deterministic_seed.is_some())
+    .use_cache_warmer(cache_warmer.clone())
+    .use_boundaries(boundaries);
 
     #[cfg(feature = "bloom")]
     {
@@ -192,22 +320,33 @@ fn merge_segments(
         // will still write bloom filters
 
         if opts.config.bloom_bits_per_key >= 0 {
-            // NOTE: Apply some MONKEY to have very high FPR on small levels
-            // because it's cheap
-            let bloom_policy = match payload.dest_level {
-                0 => BloomConstructionPolicy::FpRate(0.0001),
-                1 => BloomConstructionPolicy::FpRate(0.001),
-                _ => {
-                    BloomConstructionPolicy::BitsPerKey(opts.config.bloom_bits_per_key.abs() as u8)
-                }
-            };
-
-            segment_writer = segment_writer.use_bloom_policy(bloom_policy);
+            segment_writer = segment_writer
+                .use_bloom_policy(opts.config.bloom_policy(payload.dest_level))
+                .use_bloom_hash_count(opts.config.bloom_hash_count);
         }
     }
 
     for (idx, item) in merge_iter.enumerate() {
-        segment_writer.write(item?)?;
+        if let Err(e) = item
+            .and_then(|item| segment_writer.write(item))
+            .and_then(|()| {
+                let journaled = job.outputs.len() - reused_count;
+
+                journal_outputs(
+                    opts,
+                    &mut job,
+                    &job_folder,
+                    &segments_base_folder,
+                    segment_writer.results().get(journaled..),
+                )
+            })
+        {
+            log::error!("compactor: failed to write segments: {e:?}");
+            segment_writer.abort();
+            job.discard(&job_folder, &segments_base_folder);
+            abort_merge(opts, payload);
+            return Err(e.with_context(ErrorContext::new(Operation::Compaction)));
+        }
 
         if idx % 100_000 == 0 && opts.stop_signal.is_stopped() {
             log::debug!("compactor: stopping amidst compaction because of stop signal");
@@ -215,7 +354,35 @@ fn merge_segments(
         }
     }
 
-    let writer_results = segment_writer.finish()?;
+    let writer_results = match segment_writer.finish() {
+        Ok(results) => results,
+        Err(e) => {
+            log::error!("compactor: failed to finish segments: {e:?}");
+            job.discard(&job_folder, &segments_base_folder);
+            abort_merge(opts, payload);
+            return Err(e.with_context(ErrorContext::new(Operation::Compaction)));
+        }
+    };
+
+    // NOTE: Outputs that were cut while writing have already been synced when they were journaled
+    let journaled = job.outputs.len() - reused_count;
+
+    if let Err(e) = sync_outputs(
+        opts,
+        &segments_base_folder,
+        writer_results.get(journaled..).unwrap_or_default(),
+    ) {
+        log::error!("compactor: failed to sync segments: {e:?}");
+        job.discard(&job_folder, &segments_base_folder);
+        abort_merge(opts, payload);
+        return Err(e.with_context(ErrorContext::new(Operation::Compaction)));
+    }
+
+    let created_segment_ids = reused_segments
+        .iter()
+        .map(|segment| segment.metadata.id)
+        .chain(writer_results.iter().map(|trailer| trailer.metadata.id))
+        .collect::<Vec<_>>();
 
     log::debug!(
         "Compacted in {}ms ({} segments created)",
@@ -223,27 +390,116 @@ fn merge_segments(
         writer_results.len()
     );
 
+    let bytes_written = writer_results
+        .iter()
+        .map(|x| x.metadata.file_size)
+        .sum::<u64>();
+
+    opts.write_stats.record_write(bytes_written);
+
+    opts.level_stats.record_compaction_output(
+        usize::from(payload.dest_level),
+        bytes_written,
+        start.elapsed(),
+    );
+
+    #[cfg(feature = "metrics")]
+    opts.latencies.compaction.record(start.elapsed());
+
+    if let Some(ops_log) = &opts.ops_log {
+        ops_log.append(&OpsEvent::Compaction {
+            dest_level: payload.dest_level,
+            inputs: &payload.segment_ids,
+            input_bytes: bytes_read,
+            outputs: &created_segment_ids,
+            output_bytes: bytes_written,
+            duration: start.elapsed(),
+        });
+    }
+
+    if let Some(sink) = &opts.config.metrics_sink {
+        sink.counter(metrics::COMPACTIONS, 1);
+        sink.counter(metrics::COMPACTION_BYTES_READ, bytes_read);
+        sink.counter(metrics::COMPACTION_BYTES_WRITTEN, bytes_written);
+        sink.histogram(
+            metrics::COMPACTION_DURATION_MICROS,
+            metrics::micros(start.elapsed()),
+        );
+    }
+
     let created_segments = writer_results
         .into_iter()
         .map(|trailer| -> crate::Result<Arc<Segment>> {
             let segment_id = trailer.metadata.id;
             let segment_file_path = segments_base_folder.join(segment_id.to_string());
 
+            // NOTE: Blocks were encrypted for this segment's ID,
+            // so reading them back needs a cipher bound to it
+            let segment_cipher = segment_cipher.clone().map(|c| c.for_segment(segment_id));
+
             let tli_ptr = trailer.offsets.tli_ptr;
 
             #[cfg(feature = "bloom")]
             let bloom_ptr = trailer.offsets.bloom_ptr;
 
+            // NOTE: The file needs to be registered before loading the block index,
+            // because pinned index blocks are read through the descriptor table
+            //
+            // Freshly compacted data in the last level is rarely read,
+            // so it should neither take file descriptors away from the hot levels,
+            // nor displace their pages from the OS page cache
+            if is_last_level {
+                opts.config.descriptor_table.insert_cold_with_cipher(
+                    &segment_file_path,
+                    (opts.tree_id, segment_id).into(),
+                    segment_cipher.clone(),
+                );
+
+                // NOTE: Only clean pages are dropped, so if the segment file has not been
+                // synced yet (see `SyncMode`), its dirty pages stay cached until written back
+                if let Some(fd) = opts
+                    .config
+                    .descriptor_table
+                    .access(&(opts.tree_id, segment_id).into())?
+                {
+                    fd.advise(crate::fadvise::advise_dont_need);
+                }
+            } else {
+                opts.config.descriptor_table.insert_with_cipher(
+                    &segment_file_path,
+                    (opts.tree_id, segment_id).into(),
+                    segment_cipher.clone(),
+                );
+            }
+
             // NOTE: Need to allow because of false positive in Clippy
             // because of "bloom" feature
             #[allow(clippy::needless_borrows_for_generic_args)]
-            let block_index = Arc::new(TwoLevelBlockIndex::from_file(
-                &segment_file_path,
-                tli_ptr,
-                (opts.tree_id, segment_id).into(),
-                opts.config.descriptor_table.clone(),
-                opts.config.block_cache.clone(),
-            )?);
+            let block_index = Arc::new(
+                TwoLevelBlockIndex::from_file(
+                    &segment_file_path,
+                    tli_ptr,
+                    (opts.tree_id, segment_id).into(),
+                    opts.config.descriptor_table.clone(),
+                    opts.config.block_cache.clone(),
+                    segment_cipher.as_ref(),
+                    trailer.index_item_counts,
+                )?
+                .with_metrics(opts.config.metrics_sink.clone())
+                .with_read_sampler(opts.config.read_sampler.clone())
+                .with_readahead(opts.config.block_readahead)
+                .with_readahead_bytes(opts.config.readahead_bytes)
+                .with_read_pool(opts.read_pool.clone())
+                .with_pinned_index_blocks(
+                    opts.config.flags.contains(ConfigFlags::PIN_INDEX_BLOCKS)
+                        || trailer.metadata.one_level_index,
+                )?
+                .with_memory_lock(
+                    opts.config
+                        .flags
+                        .contains(ConfigFlags::MLOCK_INDEX_AND_FILTERS),
+                )?,
+            );
 
             Ok(Arc::new(Segment {
                 tree_id: opts.tree_id,
@@ -251,6 +507,21 @@ fn merge_segments(
                 descriptor_table: opts.config.descriptor_table.clone(),
                 block_cache: opts.config.block_cache.clone(),
 
+                seqno_index: SeqnoIndex::load(
+                    &segment_file_path,
+                    &trailer,
+                    segment_cipher.as_ref(),
+                )?,
+                tombstone_index: TombstoneIndex::load(
+                    &segment_file_path,
+                    &trailer,
+                    segment_cipher.as_ref(),
+                )?,
+
+                obsolete_path: std::sync::OnceLock::new(),
+
+                seqno_offset: 0,
+
                 metadata: trailer.metadata,
                 offsets: trailer.offsets,
 
@@ -259,7 +530,7 @@ fn merge_segments(
 
                 #[cfg(feature = "bloom")]
                 bloom_filter: {
-                    use crate::coding::Decode;
+                    use crate::segment::section::read_section;
                     use std::{
                         fs::File,
                         io::{Seek, SeekFrom},
@@ -269,21 +540,52 @@ fn merge_segments(
 
                     let mut reader = File::open(&segment_file_path)?;
                     reader.seek(SeekFrom::Start(bloom_ptr))?;
-                    BloomFilter::decode_from(&mut reader)?
+                    read_section::<BloomFilter, _>(
+                        &mut reader,
+                        bloom_ptr,
+                        segment_cipher.as_ref(),
+                        trailer.checksummed_sections,
+                    )?
+                    .with_memory_lock(
+                        opts.config
+                            .flags
+                            .contains(ConfigFlags::MLOCK_INDEX_AND_FILTERS),
+                    )
                 },
             }))
         })
-        .collect::<crate::Result<Vec<_>>>()?;
+        .collect::<crate::Result<Vec<_>>>();
+
+    let created_segments = match created_segments {
+        Ok(segments) => [reused_segments, segments].concat(),
+        Err(e) => {
+            log::error!("compactor: failed to load created segments: {e:?}");
+
+            for segment_id in &created_segment_ids {
+                let segment_file_path = segments_base_folder.join(segment_id.to_string());
+
+                opts.config
+                    .descriptor_table
+                    .remove((opts.tree_id, *segment_id).into());
+
+                if let Err(e) = std::fs::remove_file(&segment_file_path) {
+                    log::error!(
+                        "Failed to remove segment file {}: {e:?}",
+                        segment_file_path.display()
+                    );
+                }
+            }
+
+            job.discard(&job_folder, &segments_base_folder);
+            abort_merge(opts, payload);
+            return Err(e);
+        }
+    };
 
     // NOTE: Mind lock order L -> M -> S
     log::trace!("compactor: acquiring levels manifest write lock");
     let mut original_levels = opts.levels.write().expect("lock is poisoned");
 
-    // IMPORTANT: Write lock memtable(s), otherwise segments may get deleted while a range read is happening
-    // NOTE: Mind lock order L -> M -> S
-    log::trace!("compactor: acquiring sealed memtables write lock");
-    let sealed_memtables_guard = opts.sealed_memtables.write().expect("lock is poisoned");
-
     let swap_result = original_levels.atomic_swap(|recipe| {
         for segment in created_segments.iter().cloned() {
             log::trace!("Persisting segment {}", segment.metadata.id);
@@ -309,58 +611,164 @@ fn merge_segments(
         return Err(e);
     };
 
-    for segment in &created_segments {
-        let segment_file_path = segments_base_folder.join(segment.metadata.id.to_string());
+    // NOTE: If the application were to crash >here< it's fine
+    // The job manifest is stale, because its inputs are not referenced anymore,
+    // and will be cleaned up upon recovery
+    if let Err(e) = job.remove(&job_folder) {
+        log::error!("Failed to remove job manifest: {e:?}");
+    }
+
+    // NOTE: Files of segments that are still read by iterators or shared
+    // are deleted once their last reference is dropped
+    drop(to_merge);
+
+    original_levels.show_segments(&payload.segment_ids);
+
+    drop(original_levels);
 
-        opts.config.descriptor_table.insert(
-            &segment_file_path,
-            (opts.tree_id, segment.metadata.id).into(),
+    if let Some(cache_warmer) = &cache_warmer {
+        log::trace!(
+            "compactor: inserted {} hot blocks into block cache",
+            cache_warmer.inserted(),
         );
     }
 
-    // NOTE: Segments are registered, we can unlock the memtable(s) safely
-    drop(sealed_memtables_guard);
+    log::debug!("compactor: done");
 
-    // NOTE: If the application were to crash >here< it's fine
-    // The segments are not referenced anymore, and will be
-    // cleaned up upon recovery
-    for segment_id in &payload.segment_ids {
-        let segment_file_path = segments_base_folder.join(segment_id.to_string());
-        log::trace!("Removing old segment at {segment_file_path:?}");
-
-        if let Err(e) = std::fs::remove_file(segment_file_path) {
-            log::error!("Failed to cleanup file of deleted segment: {e:?}");
+    Ok(())
+}
+
+/// Looks for the manifest of an unfinished job with the same inputs, and loads its
+/// output segments, so the job can be resumed after the last output.
+///
+/// Manifests of other jobs that share input segments are stale, and are discarded.
+fn resume_job(
+    opts: &Options,
+    payload: &CompactionPayload,
+    job_folder: &Path,
+    segments_folder: &Path,
+) -> (JobManifest, Vec<Arc<Segment>>) {
+    let job = JobManifest::new(&payload.segment_ids, payload.dest_level);
+
+    let manifests = match JobManifest::load_all(job_folder) {
+        Ok(manifests) => manifests,
+        Err(e) => {
+            log::warn!("compactor: failed to load job manifests: {e:?}");
+            return (job, vec![]);
         }
+    };
+
+    let mut resumed = None;
+
+    for other in manifests.into_iter().filter(|other| other.intersects(&job)) {
+        if other.matches(&job) && resumed.is_none() {
+            let segments = other
+                .outputs
+                .iter()
+                .map(|&segment_id| {
+                    Segment::recover(
+                        segments_folder.join(segment_id.to_string()),
+                        segment_id,
+                        opts.tree_id,
+                        opts.config.block_cache.clone(),
+                        opts.config.descriptor_table.clone(),
+                        opts.config.cipher(),
+                        opts.config.metrics_sink.clone(),
+                        opts.config.read_sampler.clone(),
+                        opts.config.block_readahead,
+                        opts.config.readahead_bytes,
+                        opts.read_pool.clone(),
+                        opts.config.flags,
+                    )
+                    .map(Arc::new)
+                })
+                .collect::<crate::Result<Vec<_>>>();
+
+            match segments {
+                Ok(segments) => {
+                    log::info!(
+                        "compactor: resuming compaction of {:?} after {} segments",
+                        other.inputs,
+                        segments.len()
+                    );
+
+                    resumed = Some((other, segments));
+                    continue;
+                }
+                Err(e) => {
+                    log::warn!("compactor: can not resume compaction, invalid output: {e:?}");
+                }
+            }
+        }
+
+        other.discard(job_folder, segments_folder);
     }
 
-    for segment_id in &payload.segment_ids {
-        log::trace!("Closing file handles for old segment file");
+    resumed.unwrap_or((job, vec![]))
+}
+
+/// Records segments that have been completely written in the job manifest.
+///
+/// The segment files are synced first, so they can be reused after a crash.
+fn journal_outputs(
+    opts: &Options,
+    job: &mut JobManifest,
+    job_folder: &Path,
+    segments_folder: &Path,
+    trailers: Option<&[SegmentFileTrailer]>,
+) -> crate::Result<()> {
+    let Some(trailers) = trailers.filter(|x| !x.is_empty()) else {
+        return Ok(());
+    };
+
+    sync_outputs(opts, segments_folder, trailers)?;
 
-        opts.config
-            .descriptor_table
-            .remove((opts.tree_id, *segment_id).into());
+    for trailer in trailers {
+        job.outputs.push(trailer.metadata.id);
     }
 
-    original_levels.show_segments(&payload.segment_ids);
+    job.persist(job_folder)
+}
 
-    drop(original_levels);
+/// Syncs output segments (and their folder), if the segment writer has not synced them already.
+///
+/// The input segments are deleted once the compaction is committed, so the outputs
+/// need to be durable before that, whatever the sync mode.
+fn sync_outputs(
+    opts: &Options,
+    segments_folder: &Path,
+    trailers: &[SegmentFileTrailer],
+) -> crate::Result<()> {
+    if trailers.is_empty() {
+        return Ok(());
+    }
 
-    log::debug!("compactor: done");
+    if !opts.sync_tracker.sync_on_write() {
+        for trailer in trailers {
+            let segment_file_path = segments_folder.join(trailer.metadata.id.to_string());
+            fail_point!(crate::failpoints::FSYNC);
+            std::fs::File::open(segment_file_path)?.sync_all()?;
+        }
+    }
+
+    fsync_directory(segments_folder)?;
 
     Ok(())
 }
 
+/// Makes the input segments of a failed merge visible again, so they can be
+/// picked up by a later compaction
+fn abort_merge(opts: &Options, payload: &CompactionPayload) {
+    log::trace!("compactor: acquiring levels manifest write lock");
+    let mut levels = opts.levels.write().expect("lock is poisoned");
+    levels.show_segments(&payload.segment_ids);
+}
+
 fn drop_segments(
     mut original_levels: RwLockWriteGuard<'_, LevelManifest>,
     opts: &Options,
     segment_ids: &[GlobalSegmentId],
 ) -> crate::Result<()> {
-    let segments_base_folder = opts.config.path.join(SEGMENTS_FOLDER);
-
-    // IMPORTANT: Write lock memtable, otherwise segments may get deleted while a range read is happening
-    log::trace!("compaction: acquiring sealed memtables write lock");
-    let memtable_lock = opts.sealed_memtables.write().expect("lock is poisoned");
-
     // IMPORTANT: Write the segment with the removed segments first
     // Otherwise the folder is deleted, but the segment is still referenced!
     original_levels.atomic_swap(|recipe| {
@@ -374,26 +782,17 @@ fn drop_segments(
         }
     })?;
 
-    drop(memtable_lock);
     drop(original_levels);
 
-    // NOTE: If the application were to crash >here< it's fine
-    // The segments are not referenced anymore, and will be
-    // cleaned up upon recovery
-    for key in segment_ids {
-        let segment_id = key.segment_id();
-
-        let segment_file_path = segments_base_folder.join(segment_id.to_string());
-        log::trace!("Removing old segment at {segment_file_path:?}");
-
-        if let Err(e) = std::fs::remove_file(segment_file_path) {
-            log::error!("Failed to cleanup file of deleted segment: {e:?}");
-        }
-    }
+    if let Some(ops_log) = &opts.ops_log {
+        let segment_ids = segment_ids
+            .iter()
+            .map(GlobalSegmentId::segment_id)
+            .collect::<Vec<_>>();
 
-    for key in segment_ids {
-        log::trace!("Closing file handles for segment data file");
-        opts.config.descriptor_table.remove(*key);
+        ops_log.append(&OpsEvent::Drop {
+            segment_ids: &segment_ids,
+        });
     }
 
     log::trace!("Dropped {} segments", segment_ids.len());
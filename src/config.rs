@@ -3,14 +3,23 @@
 // (found in the LICENSE-* files in the repository)
 
 use crate::{
+    blob_tree::SharedValueLog,
+    compaction::leveled::DEFAULT_L0_THRESHOLD,
     descriptor_table::FileDescriptorTable,
+    durability::SyncMode,
+    encryption::{Cipher, SegmentCipher},
+    metrics::{MetricsSink, ReadSampleSink, ReadSampler},
     path::absolute_path,
-    segment::meta::{CompressionType, TableType},
+    segment::{
+        meta::{CompressionType, TableType},
+        writer::BloomConstructionPolicy,
+    },
     BlobTree, BlockCache, Tree,
 };
 use std::{
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 use value_log::BlobCache;
 
@@ -47,6 +56,55 @@ impl TryFrom<u8> for TreeType {
 
 const DEFAULT_FILE_FOLDER: &str = ".lsm.data";
 
+/// Persisted settings that were explicitly configured
+///
+/// When recovering a tree, settings that were not explicitly configured are restored,
+/// explicitly configured settings that are incompatible with the tree need to match
+/// the settings the tree was created with.
+#[derive(Clone, Copy, Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ExplicitSettings {
+    pub compression: bool,
+    pub blob_compression: bool,
+    pub data_block_size: bool,
+    pub index_block_size: bool,
+    pub bloom_bits_per_key: bool,
+    pub blob_separation_threshold: bool,
+}
+
+bitflags::bitflags! {
+    /// Optional features of a tree, see the [`Config`] setters of the same name
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+    pub struct ConfigFlags: u16 {
+        /// Segments are written with a seqno index
+        const SEQNO_INDEX = 1;
+
+        /// Top-level block indexes are loaded on first access instead of on recovery
+        const LAZY_BLOCK_INDEX = 1 << 1;
+
+        /// All index blocks of every segment are pinned in memory
+        const PIN_INDEX_BLOCKS = 1 << 2;
+
+        /// Weak deletes check the single-delete contract
+        const WEAK_TOMBSTONE_CHECKS = 1 << 3;
+
+        /// Blocks of compaction outputs that replace cached blocks are loaded into the block cache
+        const COMPACTION_CACHE_WARMING = 1 << 4;
+
+        /// The block index stores shortened separator keys
+        const SHORTEN_INDEX_KEYS = 1 << 5;
+
+        /// Block indexes & bloom filters are locked into RAM
+        const MLOCK_INDEX_AND_FILTERS = 1 << 6;
+
+        /// Flushed segments are read back & verified before they are registered
+        const VERIFY_FLUSHES = 1 << 7;
+
+        /// Data blocks store a checksum of every value
+        const VALUE_CHECKSUMS = 1 << 8;
+    }
+}
+
 #[derive(Clone)]
 /// Tree configuration builder
 pub struct Config {
@@ -83,6 +141,14 @@ pub struct Config {
     #[doc(hidden)]
     pub bloom_bits_per_key: i8,
 
+    /// Bloom filter policies per level, overriding `bloom_bits_per_key`
+    #[cfg_attr(not(feature = "bloom"), allow(dead_code))]
+    pub(crate) bloom_level_policies: Vec<BloomConstructionPolicy>,
+
+    /// Number of bloom filter hash functions, overriding the number derived from the bits per key
+    #[cfg_attr(not(feature = "bloom"), allow(dead_code))]
+    pub(crate) bloom_hash_count: Option<u8>,
+
     /// Block cache to use
     #[doc(hidden)]
     pub block_cache: Arc<BlockCache>,
@@ -99,9 +165,95 @@ pub struct Config {
     #[doc(hidden)]
     pub blob_file_separation_threshold: u32,
 
+    /// Maximum size in bytes of a single blob of a streamed value
+    pub(crate) blob_chunk_size: u32,
+
+    /// Maximum size in bytes of a streamed value
+    pub(crate) max_streamed_value_size: u64,
+
+    /// Value log that is shared with other blob trees
+    #[doc(hidden)]
+    pub shared_value_log: Option<SharedValueLog>,
+
     /// Descriptor table to use
     #[doc(hidden)]
     pub descriptor_table: Arc<FileDescriptorTable>,
+
+    /// Disk space in bytes that is reserved for flushes and compactions
+    pub reserved_headroom: u64,
+
+    /// Block cipher used for encryption at rest
+    #[cfg(feature = "encryption")]
+    pub(crate) cipher: Option<Cipher>,
+
+    /// Sink that receives metrics events
+    #[doc(hidden)]
+    pub metrics_sink: Option<Arc<dyn MetricsSink>>,
+
+    /// Sampler of point reads
+    pub(crate) read_sampler: Option<ReadSampler>,
+
+    /// Maximum size of the operations log in bytes (0 = disabled)
+    #[doc(hidden)]
+    pub ops_log_max_size: u64,
+
+    /// Maximum key size in bytes that is accepted by fallible inserts
+    #[doc(hidden)]
+    pub max_key_size: u16,
+
+    /// Maximum value size in bytes that is accepted by fallible inserts
+    #[doc(hidden)]
+    pub max_value_size: u32,
+
+    /// Amount of data blocks that are prefetched ahead of forward scans (0 = disabled)
+    #[doc(hidden)]
+    pub block_readahead: usize,
+
+    /// Amount of bytes that forward scans request per I/O on block cache misses (0 = disabled)
+    pub(crate) readahead_bytes: usize,
+
+    /// Size of the read buffer of every input segment of a compaction (0 = disabled)
+    pub(crate) compaction_read_buffer_size: usize,
+
+    /// Controls when segment files are fsynced
+    #[doc(hidden)]
+    pub sync_mode: SyncMode,
+
+    /// Maximum amount of expensive scans that can run concurrently (0 = unlimited)
+    pub(crate) max_concurrent_scans: usize,
+
+    /// How long an expensive scan waits for a running expensive scan to finish
+    pub(crate) scan_queue_timeout: Duration,
+
+    /// Estimated amount of bytes from which on a scan is limited
+    pub(crate) scan_throttle_threshold: u64,
+
+    /// Maximum index size of segments that keep their whole index in memory (0 = disabled)
+    pub(crate) one_level_index_max_size: u64,
+
+    /// Length of the key prefixes that are stored in the segment metadata (0 = disabled)
+    pub(crate) prefix_fence_len: u8,
+
+    /// Seed of the deterministic mode, if enabled
+    pub(crate) deterministic_seed: Option<u64>,
+
+    /// Amount of threads that recover segments when opening the tree
+    pub(crate) recovery_threads: usize,
+
+    /// Amount of threads that read ahead of scans (0 = disabled)
+    pub(crate) read_ahead_threads: usize,
+
+    /// Amount of L0 segments at which the L0 pressure starts to rise above 0.0
+    pub(crate) l0_slowdown_threshold: usize,
+
+    /// Amount of L0 segments at which the L0 pressure reaches 1.0
+    pub(crate) l0_stop_threshold: usize,
+
+    /// Optional features that are enabled
+    pub(crate) flags: ConfigFlags,
+
+    /// Persisted settings that were explicitly configured
+    pub(crate) explicit: ExplicitSettings,
 }
 
 impl Default for Config {
@@ -119,10 +271,50 @@ impl Default for Config {
             compression: CompressionType::None,
             blob_compression: CompressionType::None,
             bloom_bits_per_key: 10,
+            bloom_level_policies: Vec::new(),
+            bloom_hash_count: None,
 
             blob_cache: Arc::new(BlobCache::with_capacity_bytes(/* 16 MiB */ 16 * 1_024 * 1_024)),
             blob_file_target_size: /* 64 MiB */ 64 * 1_024 * 1_024,
             blob_file_separation_threshold: /* 4 KiB */ 4 * 1_024,
+            blob_chunk_size: /* 64 MiB */ 64 * 1_024 * 1_024,
+            max_streamed_value_size: u64::MAX,
+            shared_value_log: None,
+
+            reserved_headroom: 0,
+
+            #[cfg(feature = "encryption")]
+            cipher: None,
+
+            metrics_sink: None,
+            read_sampler: None,
+
+            ops_log_max_size: 0,
+
+            max_key_size: u16::MAX,
+            max_value_size: u32::MAX,
+
+            block_readahead: 0,
+            readahead_bytes: 0,
+
+            compaction_read_buffer_size: 0,
+
+            sync_mode: SyncMode::Always,
+
+            prefix_fence_len: 0,
+            one_level_index_max_size: 0,
+            max_concurrent_scans: 0,
+            scan_queue_timeout: Duration::from_secs(10),
+            scan_throttle_threshold: /* 64 MiB */ 64 * 1_024 * 1_024,
+
+            deterministic_seed: None,
+            recovery_threads: std::thread::available_parallelism().map_or(1, usize::from),
+            read_ahead_threads: 4,
+            l0_slowdown_threshold: DEFAULT_L0_THRESHOLD.into(),
+            l0_stop_threshold: 2 * usize::from(DEFAULT_L0_THRESHOLD),
+
+            flags: ConfigFlags::empty(),
+            explicit: ExplicitSettings::default(),
         }
     }
 }
@@ -141,6 +333,10 @@ impl Config {
     ///
     /// Use -1 to disable bloom filters even in L0, L1, L2.
     ///
+    /// The bits per key are persisted when the tree is created, and used when the tree
+    /// is reopened without configuring them. Because every segment stores its own filter,
+    /// different bits per key can be configured when reopening the tree.
+    ///
     /// Defaults to 10 bits.
     ///
     /// # Panics
@@ -152,28 +348,120 @@ impl Config {
         assert!(bits >= -1, "invalid bits_per_key value");
 
         self.bloom_bits_per_key = bits;
+        self.explicit.bloom_bits_per_key = true;
         self
     }
 
+    /// Sets the bloom filter policy of every level, starting at L0.
+    ///
+    /// Levels deeper than the given list use its last policy, so a single
+    /// policy applies to all levels. Flushed segments use the L0 policy.
+    ///
+    /// Memory-constrained deployments may want fewer bits per key, while
+    /// read-latency-sensitive deployments may want lower false positive rates.
+    ///
+    /// Unlike `bloom_bits_per_key`, the policies are not persisted in the tree:
+    /// every segment keeps the bloom filter it was written with,
+    /// so a changed policy only applies to segments that are written afterwards.
+    ///
+    /// By default, L0 and L1 use a false positive rate of 0.01% and 0.1%,
+    /// and deeper levels use `bloom_bits_per_key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a false positive rate is not in (0.0, 1.0).
+    #[must_use]
+    #[cfg(feature = "bloom")]
+    pub fn bloom_level_policies(mut self, policies: Vec<BloomConstructionPolicy>) -> Self {
+        for policy in &policies {
+            if let BloomConstructionPolicy::FpRate(fpr) = policy {
+                assert!(
+                    *fpr > 0.0 && *fpr < 1.0,
+                    "invalid bloom filter false positive rate"
+                );
+            }
+        }
+
+        self.bloom_level_policies = policies;
+        self
+    }
+
+    /// Sets the number of hash functions (bits set per key) of bloom filters.
+    ///
+    /// Bloom filters are blocked, so all bits of a key are in the same cache line,
+    /// and every hash function costs a bit test, but no additional cache miss.
+    /// Fewer hash functions make lookups cheaper, but raise the false positive rate.
+    ///
+    /// Every bloom filter stores its number of hash functions,
+    /// so a changed number only applies to segments that are written afterwards.
+    ///
+    /// Defaults to `bits per key * ln(2)`, which minimizes the false positive rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0.
+    #[must_use]
+    #[cfg(feature = "bloom")]
+    pub fn bloom_hash_functions(mut self, k: u8) -> Self {
+        assert!(k > 0, "bloom filter needs at least one hash function");
+
+        self.bloom_hash_count = Some(k);
+        self
+    }
+
+    /// Returns the bloom filter policy for segments written into the given level.
+    #[cfg(feature = "bloom")]
+    pub(crate) fn bloom_policy(&self, level: u8) -> BloomConstructionPolicy {
+        if let Some(policy) = self
+            .bloom_level_policies
+            .get(usize::from(level))
+            .or_else(|| self.bloom_level_policies.last())
+        {
+            return *policy;
+        }
+
+        // NOTE: Apply some MONKEY to have very high FPR on small levels
+        // because it's cheap
+        match level {
+            0 => BloomConstructionPolicy::FpRate(0.0001),
+            1 => BloomConstructionPolicy::FpRate(0.001),
+            _ => BloomConstructionPolicy::BitsPerKey(self.bloom_bits_per_key.unsigned_abs()),
+        }
+    }
+
     /// Sets the compression method.
     ///
     /// Using some compression is recommended.
     ///
+    /// The compression is persisted when the tree is created, and used when the tree
+    /// is reopened without configuring a compression. Because every segment records its
+    /// own compression, a different compression can be configured when reopening the tree,
+    /// which is then used for new segments.
+    ///
     /// Default = None
     #[must_use]
     pub fn compression(mut self, compression: CompressionType) -> Self {
         self.compression = compression;
+        self.explicit.compression = true;
         self
     }
 
-    /// Sets the compression method.
+    /// Sets the compression method of blobs in the value log.
     ///
-    /// Using some compression is recommended.
+    /// This is independent of the block compression set by [`Config::compression`],
+    /// so large values can use a heavier compression (e.g. a high `Miniz` level),
+    /// while index & data blocks keep using a fast codec.
+    ///
+    /// The blob compression is persisted when the tree is created, and cannot
+    /// be changed afterwards.
+    ///
+    /// This option has no effect when not used for opening a blob tree.
     ///
     /// Default = None
     #[must_use]
     pub fn blob_compression(mut self, compression: CompressionType) -> Self {
         self.blob_compression = compression;
+        self.explicit.blob_compression = true;
         self
     }
 
@@ -204,6 +492,10 @@ impl Config {
     /// For scan heavy workloads (range, prefix), use 16 - 64 KiB
     /// which also increases compression efficiency.
     ///
+    /// The data block size is persisted when the tree is created, and used when the tree
+    /// is reopened without configuring it. Because every segment stores its own block layout,
+    /// a different block size can be configured when reopening the tree.
+    ///
     /// # Panics
     ///
     /// Panics if the block size is smaller than 1 KiB or larger than 512 KiB.
@@ -213,6 +505,7 @@ impl Config {
         assert!(block_size <= 512 * 1_024);
 
         self.data_block_size = block_size;
+        self.explicit.data_block_size = true;
 
         self
     }
@@ -227,6 +520,10 @@ impl Config {
     /// For scan heavy workloads (range, prefix), use 16 - 64 KiB
     /// which also increases compression efficiency.
     ///
+    /// The index block size is persisted when the tree is created, and used when the tree
+    /// is reopened without configuring it. Because every segment stores its own block layout,
+    /// a different block size can be configured when reopening the tree.
+    ///
     /// # Panics
     ///
     /// Panics if the block size is smaller than 1 KiB or larger than 512 KiB.
@@ -236,6 +533,7 @@ impl Config {
         assert!(block_size <= 512 * 1_024);
 
         self.index_block_size = block_size;
+        self.explicit.index_block_size = true;
 
         self
     }
@@ -245,19 +543,22 @@ impl Config {
     /// You can create a global [`BlockCache`] and share it between multiple
     /// trees to cap global cache memory usage.
     ///
-    /// Defaults to a block cache with 8 MiB of capacity *per tree*.
+    /// Defaults to a block cache with 16 MiB of capacity *per tree*.
     #[must_use]
     pub fn block_cache(mut self, block_cache: Arc<BlockCache>) -> Self {
         self.block_cache = block_cache;
         self
     }
 
-    /// Sets the block cache.
+    /// Sets the blob cache.
+    ///
+    /// The blob cache stores large values (keyed by their value handle) separately
+    /// from the block cache, so large values do not evict index & data blocks.
     ///
     /// You can create a global [`BlobCache`] and share it between multiple
     /// trees and their value logs to cap global cache memory usage.
     ///
-    /// Defaults to a block cache with 8 MiB of capacity *per tree*.
+    /// Defaults to a blob cache with 16 MiB of capacity *per tree*.
     ///
     /// This option has no effect when not used for opening a blob tree.
     #[must_use]
@@ -266,6 +567,35 @@ impl Config {
         self
     }
 
+    /// Gives the tree a dedicated blob cache with the given capacity.
+    ///
+    /// Setting the capacity to 0 effectively disables blob caching,
+    /// so every blob read goes to the value log.
+    ///
+    /// This option has no effect when not used for opening a blob tree.
+    #[must_use]
+    pub fn blob_cache_capacity(mut self, bytes: u64) -> Self {
+        self.blob_cache = Arc::new(BlobCache::with_capacity_bytes(bytes));
+        self
+    }
+
+    /// Stores blobs in a value log that is shared with other blob trees.
+    ///
+    /// Each tree keeps track of the blob files it has written, and garbage collection
+    /// of a tree only rewrites its own blob files.
+    /// The blob file settings of the shared value log take precedence
+    /// over the blob settings of this config.
+    ///
+    /// All trees sharing a value log need to be opened before running garbage
+    /// collection, otherwise blobs of unopened trees are considered stale.
+    ///
+    /// This option has no effect when not used for opening a blob tree.
+    #[must_use]
+    pub fn shared_value_log(mut self, vlog: SharedValueLog) -> Self {
+        self.shared_value_log = Some(vlog);
+        self
+    }
+
     /// Sets the target size of blob files.
     ///
     /// Smaller blob files allow more granular garbage collection
@@ -285,15 +615,545 @@ impl Config {
 
     /// Sets the key-value separation threshold in bytes.
     ///
+    /// Values smaller than the threshold are stored inline in segments,
+    /// larger values are written to the value log.
+    ///
     /// Smaller value will reduce compaction overhead and thus write amplification,
     /// at the cost of lower read performance.
     ///
+    /// The threshold is persisted when the tree is created, and cannot
+    /// be changed afterwards.
+    ///
     /// Defaults to 4KiB.
     ///
     /// This option has no effect when not used for opening a blob tree.
     #[must_use]
-    pub fn blob_file_separation_threshold(mut self, bytes: u32) -> Self {
+    pub fn blob_separation_threshold(mut self, bytes: u32) -> Self {
         self.blob_file_separation_threshold = bytes;
+        self.explicit.blob_separation_threshold = true;
+        self
+    }
+
+    /// Sets the chunk size in bytes of streamed values.
+    ///
+    /// Streamed values (see [`BlobTree::insert_streaming`]) that reach the chunk size
+    /// are split into multiple blobs, which are reassembled on read, or read one by one
+    /// (see [`BlobTree::get_streaming`]). This lifts the 4 GiB limit of a single blob,
+    /// and bounds the memory used while streaming a value.
+    ///
+    /// At most one chunk of a value is buffered while it is streamed.
+    ///
+    /// Defaults to 64 MiB.
+    ///
+    /// This option has no effect when not used for opening a blob tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is 0.
+    #[must_use]
+    pub fn blob_chunk_size(mut self, bytes: u32) -> Self {
+        assert!(bytes > 0);
+
+        self.blob_chunk_size = bytes;
+        self
+    }
+
+    /// Sets the maximum size in bytes of a streamed value.
+    ///
+    /// Defaults to 2^64 - 1 bytes (unlimited).
+    ///
+    /// This option has no effect when not used for opening a blob tree.
+    #[must_use]
+    pub fn max_streamed_value_size(mut self, bytes: u64) -> Self {
+        self.max_streamed_value_size = bytes;
+        self
+    }
+
+    /// Alias for [`Config::blob_separation_threshold`]
+    #[must_use]
+    #[doc(hidden)]
+    pub fn blob_file_separation_threshold(self, bytes: u32) -> Self {
+        self.blob_separation_threshold(bytes)
+    }
+
+    /// Sets the maximum key size in bytes that is accepted by [`Tree::try_insert`].
+    ///
+    /// Defaults to 65535 bytes, which is the maximum supported key size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is 0.
+    #[must_use]
+    pub fn max_key_size(mut self, bytes: u16) -> Self {
+        assert!(bytes > 0);
+
+        self.max_key_size = bytes;
+        self
+    }
+
+    /// Sets the maximum value size in bytes that is accepted by [`Tree::try_insert`].
+    ///
+    /// Defaults to 2^32 - 1 bytes, which is the maximum supported value size.
+    #[must_use]
+    pub fn max_value_size(mut self, bytes: u32) -> Self {
+        self.max_value_size = bytes;
+        self
+    }
+
+    /// Sets the amount of disk space in bytes that is reserved up front.
+    ///
+    /// When the disk runs full, the reservation is released, so flushes and compactions
+    /// have enough room to complete and reclaim space. The reservation is restored
+    /// after the next flush or compaction, once there is enough space again.
+    ///
+    /// Defaults to 0 (disabled).
+    #[must_use]
+    pub fn reserved_headroom(mut self, bytes: u64) -> Self {
+        self.reserved_headroom = bytes;
+        self
+    }
+
+    /// Sets the block cipher that is used to encrypt segments at rest.
+    ///
+    /// Every block of new segments is encrypted using the cipher's current key.
+    /// Existing segments are decrypted using the key they were written with.
+    ///
+    /// Once a tree contains encrypted segments, it cannot be opened without the cipher.
+    ///
+    /// Blob files are not encrypted, so blob trees cannot be opened with a cipher.
+    #[must_use]
+    #[cfg(feature = "encryption")]
+    pub fn block_cipher(mut self, cipher: Arc<dyn crate::BlockCipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    /// Returns the configured block cipher
+    #[cfg(feature = "encryption")]
+    pub(crate) fn cipher(&self) -> Option<&Cipher> {
+        self.cipher.as_ref()
+    }
+
+    /// Returns the configured block cipher, which is always `None`
+    /// without the `encryption` feature
+    #[cfg(not(feature = "encryption"))]
+    #[allow(clippy::unused_self)]
+    pub(crate) fn cipher(&self) -> Option<&Cipher> {
+        None
+    }
+
+    /// Returns the cipher that should be used for writing new segments
+    #[cfg(feature = "encryption")]
+    pub(crate) fn segment_cipher(&self) -> Option<SegmentCipher> {
+        self.cipher.as_ref().map(SegmentCipher::for_new_segment)
+    }
+
+    /// Returns the cipher that should be used for writing new segments
+    #[cfg(not(feature = "encryption"))]
+    #[allow(clippy::unused_self)]
+    pub(crate) fn segment_cipher(&self) -> Option<SegmentCipher> {
+        None
+    }
+
+    /// Returns the cipher that should be used for rewriting segments using the given key
+    #[cfg(feature = "encryption")]
+    pub(crate) fn segment_cipher_with_key(&self, key_id: u32) -> Option<SegmentCipher> {
+        self.cipher
+            .as_ref()
+            .map(|cipher| SegmentCipher::new(cipher.clone(), key_id))
+    }
+
+    /// Returns the cipher that should be used for rewriting segments using the given key
+    #[cfg(not(feature = "encryption"))]
+    #[allow(clippy::unused_self)]
+    pub(crate) fn segment_cipher_with_key(&self, _: u32) -> Option<SegmentCipher> {
+        None
+    }
+
+    /// Sets the sink that receives metrics events of the tree.
+    ///
+    /// See [`crate::metrics`] for the emitted metrics.
+    #[must_use]
+    pub fn metrics_sink(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.metrics_sink = Some(sink);
+        self
+    }
+
+    /// Records a fraction (0.0 - 1.0) of the point reads that reach a data block
+    /// into the given sink, which receives the key, segment, block offset,
+    /// and whether the block was found in the block cache.
+    ///
+    /// Keys are sampled by their hash, so all reads of a sampled key are recorded,
+    /// which allows simulating other block cache sizes (and working set analyses)
+    /// offline, using a small fraction of keys.
+    ///
+    /// Defaults to no sampling.
+    #[must_use]
+    pub fn read_sampling(mut self, sink: Arc<dyn ReadSampleSink>, rate: f64) -> Self {
+        self.read_sampler = Some(ReadSampler::new(sink, rate));
+        self
+    }
+
+    /// Sets the amount of data blocks that are prefetched ahead of forward scans.
+    ///
+    /// Once a scan moves on to the next data block of a segment, the read-ahead threads
+    /// of the tree (see [`Config::read_ahead_threads`]) load and decompress the following
+    /// blocks into the block cache, so cold sequential scans do not have to wait for every block read.
+    ///
+    /// Short scans that stay inside a single block never start the prefetcher.
+    /// Without read-ahead threads, no blocks are prefetched.
+    ///
+    /// Defaults to 0 (disabled).
+    #[must_use]
+    pub fn block_readahead(mut self, depth: usize) -> Self {
+        self.block_readahead = depth;
+        self
+    }
+
+    /// Sets the amount of bytes that forward scans request per I/O, when
+    /// a data block is not in the block cache.
+    ///
+    /// The following data blocks that fit into the request are then read from memory,
+    /// instead of issuing an I/O per block.
+    /// Fast local disks (e.g. `NVMe`) work well with small values, while network
+    /// file systems with high latency benefit from requesting megabytes at a time.
+    ///
+    /// Blocks that are read ahead are inserted into the block cache once the scan reaches them.
+    ///
+    /// Defaults to 0 (one I/O per data block).
+    #[must_use]
+    pub fn readahead_bytes(mut self, bytes: usize) -> Self {
+        self.readahead_bytes = bytes;
+        self
+    }
+
+    /// Sets the size of the read buffer that is used for every input segment of a compaction.
+    ///
+    /// Compactions stream their input segments block by block through the buffer,
+    /// bypassing the block cache, so the memory usage of a compaction is bounded by
+    /// the amount of input segments times the buffer (and block) size, no matter how
+    /// large the segments are.
+    /// Larger buffers result in fewer, larger reads.
+    ///
+    /// Every input segment is opened once more for the buffered reads, so compactions
+    /// use file descriptors that are not counted by the file descriptor table.
+    ///
+    /// If set to 0, input blocks are read through the file descriptor table (and block cache) instead.
+    ///
+    /// Defaults to 0 (disabled).
+    #[must_use]
+    pub fn compaction_read_buffer_size(mut self, bytes: usize) -> Self {
+        self.compaction_read_buffer_size = bytes;
+        self
+    }
+
+    /// If `true`, compactions carry hot key ranges forward in the block cache.
+    ///
+    /// The key ranges of input data blocks that are in the block cache when the
+    /// compaction starts are looked up in the output segments, and the output data blocks
+    /// of these key ranges are loaded into the block cache, so a compaction does not
+    /// turn a hot key range cold.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn compaction_cache_warming(mut self, enabled: bool) -> Self {
+        self.flags
+            .set(ConfigFlags::COMPACTION_CACHE_WARMING, enabled);
+        self
+    }
+
+    /// Sets the sync mode, which controls when written segment files
+    /// (and the segments folder) are fsynced after flushes and compactions.
+    ///
+    /// See [`SyncMode`] for the durability implications.
+    ///
+    /// Defaults to [`SyncMode::Always`].
+    #[must_use]
+    pub fn sync_mode(mut self, mode: SyncMode) -> Self {
+        self.sync_mode = mode;
+        self
+    }
+
+    /// If `true`, flushed and compacted segments are written with a seqno index,
+    /// which maps the seqno range of every data block to the block's offset.
+    ///
+    /// This allows [`Tree::changes_since`] to skip all data blocks that only
+    /// contain older items, instead of scanning and filtering whole segments,
+    /// at the cost of 24 bytes per data block.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn seqno_index(mut self, enabled: bool) -> Self {
+        self.flags.set(ConfigFlags::SEQNO_INDEX, enabled);
+        self
+    }
+
+    /// Limits the amount of expensive scans that can run concurrently,
+    /// so a burst of analytical queries can not starve point reads of
+    /// file descriptors and disk bandwidth.
+    ///
+    /// A scan (including range and prefix scans) is expensive if it is estimated to read at least
+    /// [`Config::scan_throttle_threshold`] bytes of segment files.
+    ///
+    /// An expensive scan runs until its iterator is dropped. Excess expensive scans wait for
+    /// a running expensive scan to finish, for at most `timeout`, after which their
+    /// iterator only yields [`crate::Error::ScanQueueTimeout`].
+    /// Reads of the first or last item are never limited.
+    ///
+    /// Defaults to 0 (unlimited).
+    #[must_use]
+    pub fn max_concurrent_scans(mut self, max: usize, timeout: Duration) -> Self {
+        self.max_concurrent_scans = max;
+        self.scan_queue_timeout = timeout;
+        self
+    }
+
+    /// Sets the amount of bytes a scan is estimated to read from segment files,
+    /// from which on it is limited by [`Config::max_concurrent_scans`].
+    ///
+    /// The estimate is based on the top-level index of every segment
+    /// that overlaps the scanned range, so it does not cause any I/O.
+    ///
+    /// Defaults to 64 MiB.
+    #[must_use]
+    pub fn scan_throttle_threshold(mut self, bytes: u64) -> Self {
+        self.scan_throttle_threshold = bytes;
+        self
+    }
+
+    /// Segments whose index blocks (the partitions of the two-level index,
+    /// see [`Config::index_block_size`]) are at most `max_size` bytes in total keep
+    /// their whole index in memory, so reads skip the block cache lookup of index blocks.
+    ///
+    /// Larger segments keep using a two-level index, so only the top-level index
+    /// is held in memory. The choice is made per segment when it is written,
+    /// and stored in its metadata.
+    ///
+    /// Defaults to 0 (two-level index for every segment).
+    #[must_use]
+    pub fn one_level_index_max_size(mut self, max_size: u64) -> Self {
+        self.one_level_index_max_size = max_size;
+        self
+    }
+
+    /// If `true`, the block index of flushed and compacted segments stores the shortest
+    /// key that separates every data block from the next one (e.g. `b` between `abc` and `bcd`),
+    /// instead of the last key of every data block.
+    ///
+    /// This shrinks index blocks for long keys, so more of the index fits into the block cache.
+    /// Keys are compared bytewise, so any key between two data blocks is a valid separator.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn shorten_index_keys(mut self, enabled: bool) -> Self {
+        self.flags.set(ConfigFlags::SHORTEN_INDEX_KEYS, enabled);
+        self
+    }
+
+    /// If `true`, flushed and compacted segments store a checksum (xxh3) of every value
+    /// next to it, which is verified whenever the value is read.
+    ///
+    /// Data block checksums are not verified by regular reads (only by [`crate::AbstractTree::verify`]),
+    /// so value checksums detect corruption of single values on every read, and pinpoint
+    /// the corrupted item inside an otherwise readable block, at the cost of 8 bytes per value.
+    /// Reads fail with [`crate::Error::Decode`] when a value does not match its checksum.
+    ///
+    /// Segments written without value checksums stay readable.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn value_checksums(mut self, enabled: bool) -> Self {
+        self.flags.set(ConfigFlags::VALUE_CHECKSUMS, enabled);
+        self
+    }
+
+    /// Stores the distinct key prefixes (the first `prefix_len` bytes of every key)
+    /// of flushed and compacted segments in their metadata.
+    ///
+    /// Range and prefix scans then skip segments that contain none of the scanned
+    /// prefixes, even if their key range overlaps the scan (e.g. when keys are spread
+    /// over a few skewed prefixes, like `tenant_id:`), without loading any block.
+    /// Segments with more than 1024 distinct prefixes do not store any prefixes.
+    ///
+    /// Defaults to 0 (disabled).
+    #[must_use]
+    pub fn prefix_fence_len(mut self, prefix_len: u8) -> Self {
+        self.prefix_fence_len = prefix_len;
+        self
+    }
+
+    /// Enables the deterministic mode, for reproducible tests.
+    ///
+    /// The tree ID is derived from the given seed, segments get their segment ID
+    /// as creation timestamp instead of the wall clock time, and parallel compactions
+    /// run on a single thread, so performing the same operations results in
+    /// byte-identical trees across runs.
+    ///
+    /// Because segment timestamps are not actual times anymore,
+    /// this should not be used with time-based compaction strategies (e.g. FIFO TTL).
+    ///
+    /// Defaults to disabled.
+    #[must_use]
+    pub fn deterministic(mut self, seed: u64) -> Self {
+        self.deterministic_seed = Some(seed);
+        self
+    }
+
+    /// If `true`, every weak delete (see [`crate::AbstractTree::remove_weak`]) checks
+    /// that the key was written at most once since it was last deleted.
+    ///
+    /// A weak tombstone only cancels out the single version below it, so
+    /// weakly deleting a key that was overwritten silently resurrects its older
+    /// versions once they are compacted.
+    /// Violations are logged as errors, and counted (see [`crate::Tree::weak_tombstone_violations`]).
+    ///
+    /// Each check reads all versions of the key, so this is meant for debugging only.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn weak_tombstone_checks(mut self, enabled: bool) -> Self {
+        self.flags.set(ConfigFlags::WEAK_TOMBSTONE_CHECKS, enabled);
+        self
+    }
+
+    /// Sets the amount of threads that recover segments (metadata, block index
+    /// and bloom filter) when opening the tree.
+    ///
+    /// Defaults to the available parallelism.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is 0.
+    #[must_use]
+    pub fn recovery_threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "recovery needs at least one thread");
+
+        self.recovery_threads = threads;
+        self
+    }
+
+    /// Sets the amount of background threads that read ahead of scans,
+    /// such as the blob reads of blob tree scans and data block prefetching
+    /// (see [`Config::block_readahead`]).
+    ///
+    /// The threads are owned by the tree: they are started once a scan first needs them,
+    /// and stopped when the tree is dropped.
+    /// If set to 0, scans do all reads on their own thread.
+    ///
+    /// Defaults to 4.
+    #[must_use]
+    pub fn read_ahead_threads(mut self, threads: usize) -> Self {
+        self.read_ahead_threads = threads;
+        self
+    }
+
+    /// If `true`, the top-level block index of a segment is not loaded
+    /// when the tree is opened, but on the first read of the segment.
+    ///
+    /// This reduces open time and memory usage of trees with many
+    /// rarely accessed segments, at the cost of a slower first read
+    /// per segment. Index corruption is then only detected on that read.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn lazy_block_index(mut self, enabled: bool) -> Self {
+        self.flags.set(ConfigFlags::LAZY_BLOCK_INDEX, enabled);
+        self
+    }
+
+    /// If `true`, all index blocks of every segment are loaded when the segment
+    /// is opened or written, and kept in memory outside of the block cache.
+    ///
+    /// Point reads & range scans then never need to load index blocks from disk,
+    /// which bounds their worst-case I/O to the data blocks, at the cost of memory
+    /// proportional to the total size of all block indexes.
+    /// Segments are not loaded lazily then, see [`Config::lazy_block_index`].
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn pin_index_blocks(mut self, enabled: bool) -> Self {
+        self.flags.set(ConfigFlags::PIN_INDEX_BLOCKS, enabled);
+        self
+    }
+
+    /// If `true`, the block indexes and bloom filters of every segment are locked into RAM (`mlock`),
+    /// so memory pressure (e.g. from large scans filling the page cache) can not page them out
+    /// and cause latency spikes.
+    ///
+    /// This implies [`Config::pin_index_blocks`], because index blocks in the block cache
+    /// can not stay locked.
+    ///
+    /// Locking is best-effort: memory that the OS refuses to lock
+    /// (e.g. because of `RLIMIT_MEMLOCK`) stays unlocked.
+    /// Requires the `mlock` feature, otherwise this has no effect.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn mlock_index_and_filters(mut self, enabled: bool) -> Self {
+        self.flags
+            .set(ConfigFlags::MLOCK_INDEX_AND_FILTERS, enabled);
+        self
+    }
+
+    /// If `true`, a checksum of the items is computed while flushing a memtable,
+    /// and the written segment is read back and compared against it,
+    /// before the segment is added to the tree.
+    ///
+    /// This catches silent corruption on the write path (e.g. bad RAM),
+    /// at the cost of reading every flushed segment once more.
+    /// The segment is read right after it was written, so it is usually served
+    /// from the OS page cache; corruption that only happens on the way to
+    /// (or on) the disk, such as faulty disk firmware, is not detected.
+    /// On mismatch, the flush fails with a corruption error (see [`crate::Error::is_corruption`]),
+    /// and the memtable stays sealed, so it can be flushed again.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn verify_flushes(mut self, enabled: bool) -> Self {
+        self.flags.set(ConfigFlags::VERIFY_FLUSHES, enabled);
+        self
+    }
+
+    /// Sets the amount of first level (L0) segments at which the L0 pressure
+    /// (see [`crate::Tree::l0_pressure`]) starts to rise above 0.0, and at which it reaches 1.0.
+    ///
+    /// The tree does not throttle writes on its own, the thresholds only serve
+    /// embedders that do their own admission control (e.g. slowing down producers),
+    /// before reads suffer from too many overlapping L0 segments.
+    ///
+    /// The first level is compacted once it reaches the L0 threshold of the compaction strategy
+    /// (see [`crate::compaction::CompactionStrategy::l0_threshold`]), so the slowdown threshold
+    /// should not be below it; L0 segments beyond the compaction trigger mean
+    /// that compaction does not keep up with flushes.
+    ///
+    /// Defaults to the L0 threshold of the default leveled strategy (4) and twice that (8),
+    /// so the pressure reaches 1.0 once a whole compaction trigger worth of segments is backlogged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stop threshold is not larger than the slowdown threshold.
+    #[must_use]
+    pub fn l0_pressure_thresholds(mut self, slowdown: usize, stop: usize) -> Self {
+        assert!(
+            stop > slowdown,
+            "stop threshold should be larger than slowdown threshold"
+        );
+
+        self.l0_slowdown_threshold = slowdown;
+        self.l0_stop_threshold = stop;
+        self
+    }
+
+    /// Enables the operations log.
+    ///
+    /// Flushes & compactions (inputs, outputs, sizes, durations) are appended
+    /// to a JSONL file in the tree folder, which is rotated once it reaches
+    /// the given size, keeping one previous file.
+    ///
+    /// Defaults to 0 (disabled).
+    #[must_use]
+    pub fn ops_log(mut self, max_file_size: u64) -> Self {
+        self.ops_log_max_size = max_file_size;
         self
     }
 
@@ -317,7 +1177,7 @@ impl Config {
     ///
     /// # Errors
     ///
-    /// Will return `Err` if an IO error occurs.
+    /// Will return `Err` if an IO error occurs, or a block cipher is configured.
     pub fn open_as_blob_tree(mut self) -> crate::Result<BlobTree> {
         self.tree_type = TreeType::Blob;
         BlobTree::open(self)
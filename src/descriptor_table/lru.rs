@@ -27,6 +27,12 @@ impl<T: Clone + Eq + PartialEq> LruList<T> {
         self.0.push_back(item);
     }
 
+    /// Moves the item to the least recently used position.
+    pub fn demote(&mut self, item: T) {
+        self.remove(&item);
+        self.0.push_front(item);
+    }
+
     pub fn get_least_recently_used(&mut self) -> Option<T> {
         let front = self.0.pop_front()?;
         self.0.push_back(front.clone());
@@ -4,18 +4,20 @@
 
 mod lru;
 
-use crate::{segment::id::GlobalSegmentId, HashMap};
+use crate::{encryption::SegmentCipher, segment::id::GlobalSegmentId, HashMap};
 use lru::LruList;
 use std::{
     fs::File,
     io::BufReader,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, AtomicUsize},
-        Arc, Mutex, RwLock, RwLockWriteGuard,
+        Arc, Mutex, RwLock,
     },
 };
 
+use std::io::{Read, Seek, SeekFrom};
+
 pub struct FileGuard(Arc<FileDescriptorWrapper>);
 
 impl std::ops::Deref for FileGuard {
@@ -34,14 +36,191 @@ impl Drop for FileGuard {
     }
 }
 
+/// Reads from a shared file descriptor using positional reads (`pread`)
+///
+/// Because `pread` does not touch the file cursor, any number of readers
+/// can use the same descriptor concurrently.
+#[cfg(unix)]
+pub struct PositionalReader<'a> {
+    file: &'a File,
+    offset: u64,
+}
+
+#[cfg(unix)]
+impl Read for PositionalReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        use std::os::unix::fs::FileExt;
+
+        let n = self.file.read_at(buf, self.offset)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(unix)]
+impl Seek for PositionalReader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let new_offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => self.offset.checked_add_signed(delta),
+            SeekFrom::End(delta) => self.file.metadata()?.len().checked_add_signed(delta),
+        };
+
+        let Some(new_offset) = new_offset else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            ));
+        };
+
+        self.offset = new_offset;
+        Ok(new_offset)
+    }
+}
+
+/// Reader over a segment file
+///
+/// Not buffered, as each read is a single block or section, which is
+/// read with as few (positional) reads as possible.
+#[cfg(unix)]
+pub type SegmentFileReader<'a> = PositionalReader<'a>;
+
+/// Buffered reader over a segment file
+#[cfg(not(unix))]
+pub type SegmentFileReader<'a> = LockedReader<'a>;
+
+/// Exclusive access to a (non-shareable) file descriptor
+#[cfg(not(unix))]
+pub struct LockedReader<'a>(std::sync::MutexGuard<'a, BufReader<File>>);
+
+#[cfg(not(unix))]
+impl<'a> std::io::Read for LockedReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+#[cfg(not(unix))]
+impl<'a> std::io::Seek for LockedReader<'a> {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+/// Buffered reader over a segment file, using its own file descriptor
+///
+/// Used for sequential scans of many blocks (e.g. compaction): Reading a block at
+/// the current position does not seek, so the read buffer is kept between blocks.
+pub struct SequentialReader {
+    reader: BufReader<File>,
+    pos: u64,
+}
+
+impl Read for SequentialReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for SequentialReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        // NOTE: Seeking a BufReader discards its buffer, even if the position does not change
+        if pos == SeekFrom::Start(self.pos) {
+            return Ok(self.pos);
+        }
+
+        // NOTE: Skipping forward (e.g. over blocks that are cached) keeps the
+        // buffer, if the target position is still buffered
+        if let SeekFrom::Start(target) = pos {
+            if let Some(delta) = target
+                .checked_sub(self.pos)
+                .and_then(|delta| i64::try_from(delta).ok())
+            {
+                self.reader.seek_relative(delta)?;
+                self.pos = target;
+                return Ok(self.pos);
+            }
+        }
+
+        self.pos = self.reader.seek(pos)?;
+        Ok(self.pos)
+    }
+}
+
 pub struct FileDescriptorWrapper {
-    pub file: Mutex<BufReader<File>>,
+    #[cfg(unix)]
+    file: File,
+
+    #[cfg(not(unix))]
+    file: Mutex<BufReader<File>>,
+
+    /// Cipher to decrypt blocks of the file with
+    pub cipher: Option<SegmentCipher>,
+
     is_used: AtomicBool,
 }
 
+impl FileDescriptorWrapper {
+    fn open(
+        path: &Path,
+        cipher: Option<SegmentCipher>,
+        is_used: bool,
+        cold: bool,
+    ) -> crate::Result<Self> {
+        let file = File::open(path)?;
+
+        if cold {
+            crate::fadvise::advise_no_reuse(&file);
+        }
+
+        #[cfg(not(unix))]
+        let file = Mutex::new(BufReader::new(file));
+
+        Ok(Self {
+            file,
+            cipher,
+            is_used: AtomicBool::new(is_used),
+        })
+    }
+
+    /// Gives the OS a page cache hint for the file, see [`crate::fadvise`]
+    pub fn advise(&self, advise: fn(&File)) {
+        #[cfg(unix)]
+        advise(&self.file);
+
+        #[cfg(not(unix))]
+        advise(self.file.lock().expect("lock is poisoned").get_ref());
+    }
+
+    /// Returns a reader over the file
+    ///
+    /// On Unix, reads are positional, so the reader does not
+    /// block other readers of the same file descriptor.
+    #[cfg(unix)]
+    pub fn reader(&self) -> SegmentFileReader<'_> {
+        PositionalReader {
+            file: &self.file,
+            offset: 0,
+        }
+    }
+
+    /// Returns a reader over the file
+    #[cfg(not(unix))]
+    pub fn reader(&self) -> SegmentFileReader<'_> {
+        LockedReader(self.file.lock().expect("lock is poisoned"))
+    }
+}
+
 pub struct FileHandle {
     descriptors: RwLock<Vec<Arc<FileDescriptorWrapper>>>,
     path: PathBuf,
+    cipher: Option<SegmentCipher>,
+
+    /// If `true`, the file is kept at the least recently used position,
+    /// so its descriptors are closed before those of other files
+    cold: bool,
 }
 
 // TODO: FileDescriptorTable should wrap Arc<Inner>
@@ -84,6 +263,18 @@ impl FileDescriptorTable {
         }
     }
 
+    /// Number of file descriptors that are opened per file
+    ///
+    /// On Unix, positional reads allow sharing a single descriptor
+    /// between all concurrent readers of a segment.
+    fn descriptors_per_file(&self) -> usize {
+        if cfg!(unix) {
+            1
+        } else {
+            self.concurrency
+        }
+    }
+
     /// Number of segments
     pub fn len(&self) -> usize {
         self.inner.read().expect("lock is poisoned").table.len()
@@ -118,24 +309,41 @@ impl FileDescriptorTable {
 
             let lock = self.inner.write().expect("lock is poisoned");
             let mut lru = lock.lru.lock().expect("lock is poisoned");
-            lru.refresh(*id);
+
+            if lock.table.get(id).is_some_and(|item| item.cold) {
+                lru.demote(*id);
+            } else {
+                lru.refresh(*id);
+            }
+
+            let descriptor_count = self.descriptors_per_file();
 
             let fd = {
                 let item = lock.table.get(id).expect("should exist");
                 let mut fd_lock = item.descriptors.write().expect("lock is poisoned");
 
-                for _ in 0..(self.concurrency - 1) {
-                    let fd = Arc::new(FileDescriptorWrapper {
-                        file: Mutex::new(BufReader::new(File::open(&item.path)?)),
-                        is_used: AtomicBool::default(),
-                    });
-                    fd_lock.push(fd.clone());
+                // NOTE: Another thread may have opened the file in the meantime
+                #[cfg(unix)]
+                if let Some(fd) = fd_lock.first() {
+                    return Ok(Some(FileGuard(fd.clone())));
                 }
 
-                let fd = Arc::new(FileDescriptorWrapper {
-                    file: Mutex::new(BufReader::new(File::open(&item.path)?)),
-                    is_used: AtomicBool::new(true),
-                });
+                for _ in 0..(descriptor_count - 1) {
+                    let fd = Arc::new(FileDescriptorWrapper::open(
+                        &item.path,
+                        item.cipher.clone(),
+                        false,
+                        item.cold,
+                    )?);
+                    fd_lock.push(fd);
+                }
+
+                let fd = Arc::new(FileDescriptorWrapper::open(
+                    &item.path,
+                    item.cipher.clone(),
+                    true,
+                    item.cold,
+                )?);
                 fd_lock.push(fd.clone());
 
                 fd
@@ -143,8 +351,8 @@ impl FileDescriptorTable {
 
             let mut size_now = lock
                 .size
-                .fetch_add(self.concurrency, std::sync::atomic::Ordering::AcqRel)
-                + self.concurrency;
+                .fetch_add(descriptor_count, std::sync::atomic::Ordering::AcqRel)
+                + descriptor_count;
 
             while size_now > self.limit {
                 if let Some(oldest) = lru.get_least_recently_used() {
@@ -167,6 +375,13 @@ impl FileDescriptorTable {
 
             Ok(Some(FileGuard(fd)))
         } else {
+            // NOTE: Positional reads do not move the file cursor,
+            // so the single descriptor can be shared by all readers
+            #[cfg(unix)]
+            if let Some(fd) = fd_array.first() {
+                return Ok(Some(FileGuard(fd.clone())));
+            }
+
             loop {
                 for shard in &*fd_array {
                     if shard.is_used.compare_exchange(
@@ -185,24 +400,135 @@ impl FileDescriptorTable {
     }
 
     fn inner_insert(
-        mut lock: RwLockWriteGuard<'_, FileDescriptorTableInner>,
+        &self,
         path: PathBuf,
         id: GlobalSegmentId,
+        cipher: Option<SegmentCipher>,
+        cold: bool,
     ) {
+        let mut lock = self.inner.write().expect("lock is poisoned");
+
         lock.table.insert(
             id,
             FileHandle {
                 descriptors: RwLock::new(vec![]),
                 path,
+                cipher,
+                cold,
             },
         );
 
-        lock.lru.lock().expect("lock is poisoned").refresh(id);
+        let mut lru = lock.lru.lock().expect("lock is poisoned");
+
+        if cold {
+            lru.demote(id);
+        } else {
+            lru.refresh(id);
+        }
+
+        drop(lru);
+        drop(lock);
     }
 
     pub fn insert<P: Into<PathBuf>>(&self, path: P, id: GlobalSegmentId) {
-        let lock = self.inner.write().expect("lock is poisoned");
-        Self::inner_insert(lock, path.into(), id);
+        self.insert_with_cipher(path, id, None);
+    }
+
+    /// Registers a file whose blocks need to be decrypted using the given cipher
+    pub fn insert_with_cipher<P: Into<PathBuf>>(
+        &self,
+        path: P,
+        id: GlobalSegmentId,
+        cipher: Option<SegmentCipher>,
+    ) {
+        self.inner_insert(path.into(), id, cipher, false);
+    }
+
+    /// Registers a file that is rarely read (e.g. a segment in the last level),
+    /// whose descriptors are closed before those of any other file.
+    ///
+    /// Reads of the file are hinted not to be reused by the OS page cache (see the `fadvise` feature).
+    ///
+    /// The cold status is not persisted, so it needs to be set again after recovery.
+    pub fn insert_cold_with_cipher<P: Into<PathBuf>>(
+        &self,
+        path: P,
+        id: GlobalSegmentId,
+        cipher: Option<SegmentCipher>,
+    ) {
+        self.inner_insert(path.into(), id, cipher, true);
+    }
+
+    /// Marks a registered file as rarely read (or not), see [`FileDescriptorTable::insert_cold_with_cipher`].
+    ///
+    /// The cold status is not persisted, so it needs to be set again after recovery.
+    pub fn set_cold(&self, id: GlobalSegmentId, cold: bool) {
+        let mut lock = self.inner.write().expect("lock is poisoned");
+
+        let Some(item) = lock.table.get_mut(&id) else {
+            return;
+        };
+
+        if item.cold == cold {
+            return;
+        }
+        item.cold = cold;
+
+        // NOTE: Descriptors that are already open do not have the hint yet
+        if cold {
+            for fd in &*item.descriptors.read().expect("lock is poisoned") {
+                fd.advise(crate::fadvise::advise_no_reuse);
+            }
+        }
+
+        let mut lru = lock.lru.lock().expect("lock is poisoned");
+
+        if cold {
+            lru.demote(id);
+        } else {
+            lru.refresh(id);
+        }
+
+        drop(lru);
+        drop(lock);
+    }
+
+    /// Returns the ID of the key the file is encrypted with, if encrypted
+    pub fn key_id(&self, id: &GlobalSegmentId) -> Option<u32> {
+        let lock = self.inner.read().expect("lock is poisoned");
+
+        lock.table
+            .get(id)
+            .and_then(|item| item.cipher.as_ref())
+            .map(SegmentCipher::key_id)
+    }
+
+    /// Opens a new file descriptor for sequentially reading the given file,
+    /// using a read buffer of the given size.
+    ///
+    /// The descriptor is not part of the table, and is closed once the reader is dropped.
+    pub fn open_sequential(
+        &self,
+        id: &GlobalSegmentId,
+        buffer_size: usize,
+    ) -> crate::Result<Option<(SequentialReader, Option<SegmentCipher>)>> {
+        let Some((path, cipher)) = self
+            .inner
+            .read()
+            .expect("lock is poisoned")
+            .table
+            .get(id)
+            .map(|item| (item.path.clone(), item.cipher.clone()))
+        else {
+            return Ok(None);
+        };
+
+        let reader = SequentialReader {
+            reader: BufReader::with_capacity(buffer_size, File::open(path)?),
+            pos: 0,
+        };
+
+        Ok(Some((reader, cipher)))
     }
 
     pub fn remove(&self, id: GlobalSegmentId) {
@@ -224,6 +550,42 @@ mod tests {
     use super::*;
     use test_log::test;
 
+    #[test]
+    #[cfg(unix)]
+    fn descriptor_table_single_fd_positional_reads() -> crate::Result<()> {
+        use std::io::{Read, Seek, SeekFrom, Write};
+
+        let folder = tempfile::tempdir()?;
+        let path = folder.path().join("1");
+
+        let mut file = File::create(&path)?;
+        file.write_all(b"abcdefgh")?;
+        file.sync_all()?;
+        drop(file);
+
+        let table = FileDescriptorTable::new(10, 4);
+        table.insert(&path, (0, 1).into());
+
+        let a = table.access(&(0, 1).into())?.expect("should exist");
+        let b = table.access(&(0, 1).into())?.expect("should exist");
+        assert_eq!(1, table.size());
+
+        let mut reader_a = a.reader();
+        let mut reader_b = b.reader();
+
+        reader_a.seek(SeekFrom::Start(4))?;
+        reader_b.seek(SeekFrom::Start(2))?;
+
+        let mut buf = [0; 2];
+        reader_a.read_exact(&mut buf)?;
+        assert_eq!(b"ef", &buf);
+
+        reader_b.read_exact(&mut buf)?;
+        assert_eq!(b"cd", &buf);
+
+        Ok(())
+    }
+
     #[test]
     fn descriptor_table_limit() -> crate::Result<()> {
         let folder = tempfile::tempdir()?;
@@ -276,4 +638,72 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn descriptor_table_cold_files_closed_first() -> crate::Result<()> {
+        let folder = tempfile::tempdir()?;
+        let path = folder.path();
+
+        File::create(path.join("1"))?;
+        File::create(path.join("2"))?;
+        File::create(path.join("3"))?;
+
+        let table = FileDescriptorTable::new(2, 1);
+
+        let is_open = |id: GlobalSegmentId| {
+            let lock = table.inner.read().expect("lock is poisoned");
+            let item = lock.table.get(&id).expect("should exist");
+            let descriptors = item.descriptors.read().expect("lock is poisoned");
+            !descriptors.is_empty()
+        };
+
+        table.insert(path.join("1"), (0, 1).into());
+        table.insert(path.join("2"), (0, 2).into());
+        table.insert_cold_with_cipher(path.join("3"), (0, 3).into(), None);
+
+        let _ = table.access(&(0, 1).into())?;
+        let _ = table.access(&(0, 3).into())?;
+        assert_eq!(2, table.size());
+
+        // NOTE: The cold file was accessed more recently, but is still closed first
+        let _ = table.access(&(0, 2).into())?;
+        assert_eq!(2, table.size());
+
+        assert!(is_open((0, 1).into()));
+        assert!(is_open((0, 2).into()));
+        assert!(!is_open((0, 3).into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn descriptor_table_set_cold() -> crate::Result<()> {
+        let folder = tempfile::tempdir()?;
+        let path = folder.path();
+
+        File::create(path.join("1"))?;
+        File::create(path.join("2"))?;
+        File::create(path.join("3"))?;
+
+        let table = FileDescriptorTable::new(2, 1);
+
+        table.insert(path.join("1"), (0, 1).into());
+        table.insert(path.join("2"), (0, 2).into());
+        table.insert(path.join("3"), (0, 3).into());
+
+        // NOTE: E.g. the segment was recovered in the last level
+        table.set_cold((0, 3).into(), true);
+
+        let is_cold = |id: GlobalSegmentId| {
+            let lock = table.inner.read().expect("lock is poisoned");
+            lock.table.get(&id).expect("should exist").cold
+        };
+        assert!(is_cold((0, 3).into()));
+        assert!(!is_cold((0, 1).into()));
+
+        table.set_cold((0, 3).into(), false);
+        assert!(!is_cold((0, 3).into()));
+
+        Ok(())
+    }
 }
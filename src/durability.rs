@@ -0,0 +1,393 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::file::fsync_directory;
+use std::{
+    path::PathBuf,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Controls when segment files (and their folder) are fsynced
+///
+/// Segment files are always synced before they are added to the tree,
+/// so a crash never leaves the tree pointing at torn segment files.
+/// Relaxing the sync mode defers syncing a written segment to that point (or to
+/// when the sync mode requires it), so the segments of a flush or compaction
+/// are synced together, instead of one by one while they are written.
+/// Use [`crate::Tree::sync`] to explicitly sync all written segments.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum SyncMode {
+    /// Every segment is synced once it is written (by a flush or compaction)
+    #[default]
+    Always,
+
+    /// Written segments are synced once at least the given amount of bytes
+    /// has been written since the last sync
+    Bytes(u64),
+
+    /// Written segments are synced once the given interval has passed since the last sync
+    ///
+    /// Unsynced segments are synced by a background thread, so the interval is also
+    /// honored if no more segments are written.
+    Interval(Duration),
+
+    /// Segments are only synced before they are added to the tree,
+    /// or if [`crate::Tree::sync`] is called
+    Never,
+}
+
+struct PendingSync {
+    /// Segment files that have been written, but not synced yet, with their size
+    files: Vec<(PathBuf, u64)>,
+
+    /// Size of the unsynced files
+    bytes: u64,
+
+    last_sync: Instant,
+
+    /// If `true`, the tracker was dropped, so the interval sync thread stops
+    is_closed: bool,
+}
+
+impl PendingSync {
+    /// Takes all unsynced files, so they can be synced without holding the lock.
+    fn take(&mut self) -> Vec<(PathBuf, u64)> {
+        self.bytes = 0;
+        self.last_sync = Instant::now();
+        std::mem::take(&mut self.files)
+    }
+
+    /// Adds files back that failed to be synced, so they are synced again later.
+    fn restore(&mut self, files: Vec<(PathBuf, u64)>) {
+        self.bytes += files.iter().map(|(_, bytes)| bytes).sum::<u64>();
+        self.files.extend(files);
+    }
+}
+
+/// Keeps track of unsynced segment files, and syncs them according to the sync mode
+///
+/// In [`SyncMode::Interval`], a background thread syncs unsynced files once the interval has passed.
+///
+/// Files are synced without holding the lock of the tracker, so writers are never
+/// blocked by a sync of other files.
+pub struct SyncTracker {
+    mode: SyncMode,
+    pending: Arc<Mutex<PendingSync>>,
+
+    /// Wakes up the interval sync thread when the tracker is dropped
+    closed: Arc<Condvar>,
+}
+
+impl Drop for SyncTracker {
+    fn drop(&mut self) {
+        self.lock_pending().is_closed = true;
+        self.closed.notify_all();
+    }
+}
+
+impl SyncTracker {
+    pub fn new(mode: SyncMode) -> Self {
+        let pending = Arc::new(Mutex::new(PendingSync {
+            files: vec![],
+            bytes: 0,
+            last_sync: Instant::now(),
+            is_closed: false,
+        }));
+        let closed = Arc::new(Condvar::new());
+
+        if let SyncMode::Interval(interval) = mode {
+            let pending = pending.clone();
+            let closed = closed.clone();
+
+            let result = std::thread::Builder::new()
+                .name("lsm-interval-sync".into())
+                .spawn(move || Self::run_interval_syncs(&pending, &closed, interval));
+
+            if let Err(e) = result {
+                log::warn!("Failed to spawn interval sync thread, syncing on write only: {e:?}");
+            }
+        }
+
+        Self {
+            mode,
+            pending,
+            closed,
+        }
+    }
+
+    /// Syncs unsynced files whenever the interval has passed, until the tracker is dropped.
+    fn run_interval_syncs(pending: &Mutex<PendingSync>, closed: &Condvar, interval: Duration) {
+        let mut guard = Self::lock(pending);
+
+        while !guard.is_closed {
+            let timeout = interval.saturating_sub(guard.last_sync.elapsed());
+
+            if !timeout.is_zero() {
+                guard = closed
+                    .wait_timeout(guard, timeout)
+                    .expect("lock is poisoned")
+                    .0;
+                continue;
+            }
+
+            // NOTE: If syncing fails, the files are retried after the next interval, instead of spinning
+            let files = guard.take();
+
+            if files.is_empty() {
+                continue;
+            }
+
+            drop(guard);
+
+            let result = Self::sync_files(files.iter().map(|(path, _)| path));
+
+            guard = Self::lock(pending);
+
+            if let Err(e) = result {
+                log::error!("Failed to sync segment files: {e:?}");
+                guard.restore(files);
+            }
+        }
+
+        drop(guard);
+    }
+
+    /// Returns `true` if segment writers should sync the segment themselves.
+    pub fn sync_on_write(&self) -> bool {
+        self.mode == SyncMode::Always
+    }
+
+    /// Registers a written segment file, syncing all unsynced files if the sync mode requires it.
+    pub fn record(&self, path: PathBuf, bytes: u64) -> std::io::Result<()> {
+        if self.sync_on_write() {
+            return Ok(());
+        }
+
+        let mut pending = self.lock_pending();
+        pending.files.push((path, bytes));
+        pending.bytes += bytes;
+
+        let is_due = match self.mode {
+            SyncMode::Bytes(threshold) => pending.bytes >= threshold,
+            SyncMode::Interval(interval) => pending.last_sync.elapsed() >= interval,
+            SyncMode::Always | SyncMode::Never => false,
+        };
+
+        if !is_due {
+            return Ok(());
+        }
+
+        let files = pending.take();
+        drop(pending);
+
+        self.sync_taken(files)
+    }
+
+    /// Syncs all unsynced files.
+    pub fn sync(&self) -> std::io::Result<()> {
+        let files = self.lock_pending().take();
+        self.sync_taken(files)
+    }
+
+    /// Syncs the given segment files, before they are added to the tree.
+    ///
+    /// The files are synced whatever the sync mode, unless the segment writers
+    /// have already synced them.
+    pub fn sync_before_commit(&self, paths: &[PathBuf]) -> std::io::Result<()> {
+        if self.sync_on_write() || paths.is_empty() {
+            return Ok(());
+        }
+
+        // NOTE: The files are synced, even if they are not pending anymore, because
+        // they may be in the middle of being synced by another thread
+        Self::sync_files(paths)?;
+
+        let mut pending = self.lock_pending();
+
+        let mut synced_bytes = 0;
+        pending.files.retain(|(path, bytes)| {
+            let is_synced = paths.contains(path);
+            if is_synced {
+                synced_bytes += bytes;
+            }
+            !is_synced
+        });
+        pending.bytes -= synced_bytes;
+
+        drop(pending);
+
+        Ok(())
+    }
+
+    fn lock_pending(&self) -> MutexGuard<'_, PendingSync> {
+        Self::lock(&self.pending)
+    }
+
+    fn lock(pending: &Mutex<PendingSync>) -> MutexGuard<'_, PendingSync> {
+        pending.lock().expect("lock is poisoned")
+    }
+
+    /// Syncs files that were taken from the tracker, adding them back if syncing fails.
+    fn sync_taken(&self, files: Vec<(PathBuf, u64)>) -> std::io::Result<()> {
+        if files.is_empty() {
+            return Ok(());
+        }
+
+        if let Err(e) = Self::sync_files(files.iter().map(|(path, _)| path)) {
+            self.lock_pending().restore(files);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    fn sync_files<'a, I: IntoIterator<Item = &'a PathBuf>>(paths: I) -> std::io::Result<()> {
+        let mut folders = Vec::with_capacity(1);
+        let mut count = 0;
+
+        for path in paths {
+            fail_point!(crate::failpoints::FSYNC);
+
+            match std::fs::File::open(path) {
+                Ok(file) => file.sync_all()?,
+
+                // NOTE: Segment may have already been compacted away
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+
+                Err(e) => return Err(e),
+            }
+
+            if let Some(folder) = path.parent() {
+                if !folders.iter().any(|x: &PathBuf| x == folder) {
+                    folders.push(folder.to_path_buf());
+                }
+            }
+
+            count += 1;
+        }
+
+        // IMPORTANT: fsync folders on Unix
+        for folder in &folders {
+            fsync_directory(folder)?;
+        }
+
+        log::trace!("Synced {count} segment files");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn sync_tracker_bytes() -> std::io::Result<()> {
+        let folder = tempfile::tempdir()?;
+        let tracker = SyncTracker::new(SyncMode::Bytes(100));
+
+        for idx in 0..3 {
+            let path = folder.path().join(idx.to_string());
+            std::fs::write(&path, [0; 40])?;
+            tracker.record(path, 40)?;
+        }
+
+        // NOTE: 120 bytes were written, so the files were synced
+        let pending = tracker.pending.lock().expect("lock is poisoned");
+        assert!(pending.files.is_empty());
+        assert_eq!(0, pending.bytes);
+
+        Ok(())
+    }
+
+    #[test]
+    fn sync_tracker_never() -> std::io::Result<()> {
+        let folder = tempfile::tempdir()?;
+        let tracker = SyncTracker::new(SyncMode::Never);
+
+        let path = folder.path().join("0");
+        std::fs::write(&path, [0; 40])?;
+        tracker.record(path, u64::MAX / 2)?;
+
+        assert_eq!(
+            1,
+            tracker
+                .pending
+                .lock()
+                .expect("lock is poisoned")
+                .files
+                .len()
+        );
+
+        tracker.sync()?;
+
+        assert!(tracker
+            .pending
+            .lock()
+            .expect("lock is poisoned")
+            .files
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn sync_tracker_sync_before_commit() -> std::io::Result<()> {
+        let folder = tempfile::tempdir()?;
+        let tracker = SyncTracker::new(SyncMode::Never);
+
+        let paths = (0..2)
+            .map(|idx| folder.path().join(idx.to_string()))
+            .collect::<Vec<_>>();
+
+        for path in &paths {
+            std::fs::write(path, [0; 40])?;
+            tracker.record(path.clone(), 40)?;
+        }
+
+        tracker.sync_before_commit(&paths[..1])?;
+
+        let pending = tracker.pending.lock().expect("lock is poisoned");
+        assert_eq!(
+            vec![(paths[1].clone(), 40)],
+            pending.files,
+            "only the committed file should be synced",
+        );
+        assert_eq!(40, pending.bytes);
+
+        Ok(())
+    }
+
+    #[test]
+    fn sync_tracker_interval_without_writes() -> std::io::Result<()> {
+        let folder = tempfile::tempdir()?;
+        let tracker = SyncTracker::new(SyncMode::Interval(Duration::from_millis(10)));
+
+        let path = folder.path().join("0");
+        std::fs::write(&path, [0; 40])?;
+        tracker.record(path, 40)?;
+
+        // NOTE: No more segments are written, but the files are synced anyway
+        let start = Instant::now();
+
+        while !tracker
+            .pending
+            .lock()
+            .expect("lock is poisoned")
+            .files
+            .is_empty()
+        {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "files were not synced"
+            );
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        Ok(())
+    }
+}
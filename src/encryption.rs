@@ -0,0 +1,320 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::segment::meta::SegmentId;
+
+#[cfg(feature = "encryption")]
+use std::sync::Arc;
+
+/// Block cipher of a tree, see [`crate::Config::block_cipher`]
+#[cfg(feature = "encryption")]
+pub type Cipher = Arc<dyn BlockCipher>;
+
+/// Without the `encryption` feature, no block cipher can be configured
+#[cfg(not(feature = "encryption"))]
+#[derive(Clone)]
+pub enum Cipher {}
+
+/// Position of an encrypted block
+///
+/// The segment ID is the ID the segment was written with, so it does not change
+/// if the segment file is linked into another tree (see [`crate::Tree::absorb`]).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BlockContext {
+    /// ID of the segment the block was written into
+    pub segment_id: SegmentId,
+
+    /// Offset of the block in the segment file
+    pub offset: u64,
+}
+
+/// Pluggable cipher that is used to encrypt segment blocks at rest
+///
+/// Every block (data, index, bloom filter, metadata) is passed through the cipher
+/// after compression when writing, and before decompression when reading.
+///
+/// The ID of the key that was used is recorded per segment, so the
+/// cipher can hold multiple keys at once.
+///
+/// Ciphers should authenticate the [`BlockContext`] of a block (e.g. as associated data),
+/// so a block that is copied to another position or segment fails to decrypt.
+#[cfg(feature = "encryption")]
+pub trait BlockCipher: Send + Sync {
+    /// Returns the ID of the key that should be used for new segments
+    fn current_key_id(&self) -> u32;
+
+    /// Encrypts a block using the given key, binding it to its position
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the key is unknown or encryption fails.
+    fn encrypt(
+        &self,
+        key_id: u32,
+        context: BlockContext,
+        plaintext: &[u8],
+    ) -> std::io::Result<Vec<u8>>;
+
+    /// Decrypts a block using the given key
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the key is unknown, the block was encrypted
+    /// for another position, or decryption fails.
+    fn decrypt(
+        &self,
+        key_id: u32,
+        context: BlockContext,
+        ciphertext: &[u8],
+    ) -> std::io::Result<Vec<u8>>;
+}
+
+/// A block cipher bound to the key of a specific segment
+#[cfg(feature = "encryption")]
+#[derive(Clone)]
+pub struct SegmentCipher {
+    cipher: Arc<dyn BlockCipher>,
+    key_id: u32,
+    segment_id: SegmentId,
+}
+
+#[cfg(feature = "encryption")]
+impl std::fmt::Debug for SegmentCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "SegmentCipher(key_id={}, segment_id={})",
+            self.key_id, self.segment_id
+        )
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl SegmentCipher {
+    /// Binds the cipher to the given key
+    ///
+    /// The cipher still needs to be bound to its segment, see [`SegmentCipher::for_segment`].
+    #[must_use]
+    pub fn new(cipher: Arc<dyn BlockCipher>, key_id: u32) -> Self {
+        Self {
+            cipher,
+            key_id,
+            segment_id: 0,
+        }
+    }
+
+    /// Binds the cipher to the segment (by the ID it was written with)
+    #[must_use]
+    pub fn for_segment(mut self, segment_id: SegmentId) -> Self {
+        self.segment_id = segment_id;
+        self
+    }
+
+    /// Binds the cipher to its current key, used for writing new segments
+    #[must_use]
+    pub fn for_new_segment(cipher: &Arc<dyn BlockCipher>) -> Self {
+        Self::new(cipher.clone(), cipher.current_key_id())
+    }
+
+    /// Returns the cipher for an existing segment that was written using `key_id`,
+    /// with the given segment ID
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the segment is encrypted, but no cipher is configured.
+    pub fn for_existing_segment(
+        cipher: Option<&Cipher>,
+        key_id: Option<u32>,
+        segment_id: SegmentId,
+    ) -> crate::Result<Option<Self>> {
+        match (cipher, key_id) {
+            (_, None) => Ok(None),
+            (Some(cipher), Some(key_id)) => Ok(Some(
+                Self::new(cipher.clone(), key_id).for_segment(segment_id),
+            )),
+            (None, Some(key_id)) => Err(crate::Error::MissingCipher(key_id)),
+        }
+    }
+
+    /// Returns the key ID
+    #[must_use]
+    pub fn key_id(&self) -> u32 {
+        self.key_id
+    }
+
+    /// Returns the ID of the segment the cipher is bound to
+    #[must_use]
+    pub fn segment_id(&self) -> SegmentId {
+        self.segment_id
+    }
+
+    /// Encrypts the block at the given offset
+    pub fn encrypt(&self, offset: u64, plaintext: &[u8]) -> std::io::Result<Vec<u8>> {
+        self.cipher
+            .encrypt(self.key_id, self.context(offset), plaintext)
+    }
+
+    /// Decrypts the block at the given offset
+    pub fn decrypt(&self, offset: u64, ciphertext: &[u8]) -> std::io::Result<Vec<u8>> {
+        self.cipher
+            .decrypt(self.key_id, self.context(offset), ciphertext)
+    }
+
+    fn context(&self, offset: u64) -> BlockContext {
+        BlockContext {
+            segment_id: self.segment_id,
+            offset,
+        }
+    }
+}
+
+/// Without the `encryption` feature, segments are never encrypted,
+/// so no segment cipher can exist
+#[cfg(not(feature = "encryption"))]
+#[derive(Clone, Debug)]
+pub enum SegmentCipher {}
+
+// NOTE: The types are uninhabited, so the methods can never be called
+#[cfg(not(feature = "encryption"))]
+#[allow(clippy::uninhabited_references)]
+impl SegmentCipher {
+    /// Returns the cipher for an existing segment that was written using `key_id`
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the segment is encrypted.
+    pub fn for_existing_segment(
+        cipher: Option<&Cipher>,
+        key_id: Option<u32>,
+        _: SegmentId,
+    ) -> crate::Result<Option<Self>> {
+        match (cipher, key_id) {
+            (_, None) => Ok(None),
+            (Some(cipher), Some(_)) => match *cipher {},
+            (None, Some(key_id)) => Err(crate::Error::MissingCipher(key_id)),
+        }
+    }
+
+    /// Binds the cipher to the segment (by the ID it was written with)
+    #[must_use]
+    pub fn for_segment(self, _: SegmentId) -> Self {
+        match self {}
+    }
+
+    /// Returns the key ID
+    #[must_use]
+    pub fn key_id(&self) -> u32 {
+        match *self {}
+    }
+
+    /// Returns the ID of the segment the cipher is bound to
+    #[must_use]
+    pub fn segment_id(&self) -> SegmentId {
+        match *self {}
+    }
+
+    /// Encrypts the block at the given offset
+    pub fn encrypt(&self, _: u64, _: &[u8]) -> std::io::Result<Vec<u8>> {
+        match *self {}
+    }
+
+    /// Decrypts the block at the given offset
+    pub fn decrypt(&self, _: u64, _: &[u8]) -> std::io::Result<Vec<u8>> {
+        match *self {}
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use test_log::test;
+
+    /// XORs bytes with the key ID, and appends the block context as tag, good enough for testing
+    #[cfg(feature = "encryption")]
+    pub struct XorCipher;
+
+    #[cfg(feature = "encryption")]
+    impl XorCipher {
+        fn tag(context: BlockContext) -> Vec<u8> {
+            let mut tag = context.segment_id.to_be_bytes().to_vec();
+            tag.extend_from_slice(&context.offset.to_be_bytes());
+            tag
+        }
+    }
+
+    #[cfg(feature = "encryption")]
+    impl BlockCipher for XorCipher {
+        fn current_key_id(&self) -> u32 {
+            7
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        fn encrypt(
+            &self,
+            key_id: u32,
+            context: BlockContext,
+            plaintext: &[u8],
+        ) -> std::io::Result<Vec<u8>> {
+            let mut ciphertext = plaintext
+                .iter()
+                .map(|b| b ^ key_id as u8)
+                .collect::<Vec<_>>();
+            ciphertext.extend(Self::tag(context));
+            Ok(ciphertext)
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        fn decrypt(
+            &self,
+            key_id: u32,
+            context: BlockContext,
+            ciphertext: &[u8],
+        ) -> std::io::Result<Vec<u8>> {
+            let tag = Self::tag(context);
+
+            let Some(ciphertext) = ciphertext.strip_suffix(tag.as_slice()) else {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "block was encrypted for another position",
+                ));
+            };
+
+            Ok(ciphertext.iter().map(|b| b ^ key_id as u8).collect())
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "encryption")]
+    fn encryption_bound_to_block_position() -> crate::Result<()> {
+        let cipher: Cipher = std::sync::Arc::new(XorCipher);
+
+        let segment_cipher = SegmentCipher::for_existing_segment(Some(&cipher), Some(7), 3)?
+            .expect("should be encrypted");
+
+        let ciphertext = segment_cipher.encrypt(100, b"abc")?;
+        assert_eq!(b"abc", &*segment_cipher.decrypt(100, &ciphertext)?);
+
+        // NOTE: A block that is copied to another offset, or segment, can not be decrypted
+        assert!(segment_cipher.decrypt(101, &ciphertext).is_err());
+        assert!(segment_cipher
+            .clone()
+            .for_segment(4)
+            .decrypt(100, &ciphertext)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn encryption_missing_cipher() {
+        assert!(matches!(
+            SegmentCipher::for_existing_segment(None, Some(3), 0),
+            Err(crate::Error::MissingCipher(3))
+        ));
+        assert!(matches!(
+            SegmentCipher::for_existing_segment(None, None, 0),
+            Ok(None)
+        ));
+    }
+}
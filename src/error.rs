@@ -5,8 +5,93 @@
 use crate::{
     coding::{DecodeError, EncodeError},
     version::Version,
-    Checksum, CompressionType,
+    Checksum, CompressionType, SegmentId,
 };
+use std::path::PathBuf;
+
+/// Operation during which an error occurred
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operation {
+    /// Memtable flush
+    Flush,
+
+    /// Compaction
+    Compaction,
+
+    /// Point read or scan
+    Read,
+}
+
+/// Context of an error, describing where it occurred
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[allow(clippy::module_name_repetitions)]
+pub struct ErrorContext {
+    /// Operation that failed
+    pub operation: Option<Operation>,
+
+    /// Affected file
+    pub path: Option<PathBuf>,
+
+    /// Affected segment
+    pub segment_id: Option<SegmentId>,
+
+    /// Offset of the affected block in its segment file
+    pub block_offset: Option<u64>,
+}
+
+impl ErrorContext {
+    pub(crate) fn new(operation: Operation) -> Self {
+        Self {
+            operation: Some(operation),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn with_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub(crate) fn with_segment_id(mut self, segment_id: SegmentId) -> Self {
+        self.segment_id = Some(segment_id);
+        self
+    }
+
+    pub(crate) fn with_block_offset(mut self, offset: u64) -> Self {
+        self.block_offset = Some(offset);
+        self
+    }
+
+    /// Fills in missing fields from the given (outer) context,
+    /// replacing the operation by the outer operation
+    pub(crate) fn merge(&mut self, outer: Self) {
+        self.operation = outer.operation.or(self.operation);
+        self.path = self.path.take().or(outer.path);
+        self.segment_id = self.segment_id.or(outer.segment_id);
+        self.block_offset = self.block_offset.or(outer.block_offset);
+    }
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+
+        if let Some(operation) = self.operation {
+            parts.push(format!("operation={operation:?}"));
+        }
+        if let Some(segment_id) = self.segment_id {
+            parts.push(format!("segment={segment_id}"));
+        }
+        if let Some(offset) = self.block_offset {
+            parts.push(format!("block_offset={offset}"));
+        }
+        if let Some(path) = &self.path {
+            parts.push(format!("path={}", path.display()));
+        }
+
+        write!(f, "{}", parts.join(", "))
+    }
+}
 
 /// Represents errors that can occur in the LSM-tree
 #[derive(Debug)]
@@ -34,6 +119,136 @@ pub enum Error {
 
     /// Value log errors
     ValueLog(value_log::Error),
+
+    /// The disk ran out of space
+    ///
+    /// The failed flush or compaction has been rolled back,
+    /// so the tree is still in a consistent state.
+    DiskFull(std::io::Error),
+
+    /// Segment is encrypted (using the given key ID), but no block cipher is configured
+    MissingCipher(u32),
+
+    /// The configured setting (by name) does not match the setting the tree was created with
+    ConfigMismatch(&'static str),
+
+    /// The tree on disk is not the tree that was expected (by ID)
+    ///
+    /// This happens if the folder of a tree was replaced by a different tree.
+    TreeIdMismatch,
+
+    /// Key is empty
+    EmptyKey,
+
+    /// Key (of the given size) exceeds the maximum key size
+    KeyTooLarge(usize),
+
+    /// Value (of the given size) exceeds the maximum value size
+    ValueTooLarge(usize),
+
+    /// An expensive scan could not start before the queue timeout expired,
+    /// because the maximum amount of concurrent expensive scans was running
+    ScanQueueTimeout,
+}
+
+impl Error {
+    /// Attaches context to the error.
+    ///
+    /// I/O errors carry the context inside the [`std::io::Error`] (keeping its kind),
+    /// and decoding errors carry it next to their position, so the variant of the error
+    /// never changes. Other errors cannot carry context, so their context is logged instead.
+    ///
+    /// If the error already has context, missing fields are filled in,
+    /// and the operation is replaced by the (outer) operation.
+    pub(crate) fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            Self::Io(e) => Self::Io(ContextualIoError::attach(e, context)),
+            Self::DiskFull(e) => Self::DiskFull(ContextualIoError::attach(e, context)),
+            Self::Encode(EncodeError::Io(e)) => {
+                Self::Encode(EncodeError::Io(ContextualIoError::attach(e, context)))
+            }
+            Self::Decode(e) => Self::Decode(e.with_context(context)),
+            e => {
+                log::error!("{e:?} ({context})");
+                e
+            }
+        }
+    }
+
+    /// Attaches the name and file offset of the structure that was being decoded,
+    /// if the error is a decoding error.
+    pub(crate) fn at(self, name: &'static str, offset: u64) -> Self {
+        match self {
+            Self::Decode(e) => Self::Decode(e.at(name, offset)),
+            e => e,
+        }
+    }
+
+    /// Returns the context of the error, if any.
+    ///
+    /// Only I/O and decoding errors carry context,
+    /// the context of other errors is logged when it is attached.
+    #[must_use]
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::Io(e) | Self::DiskFull(e) | Self::Encode(EncodeError::Io(e)) => {
+                ContextualIoError::context_of(e)
+            }
+            Self::Decode(e) => e.context(),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the error was caused by corrupted data.
+    #[must_use]
+    pub fn is_corruption(&self) -> bool {
+        matches!(
+            self,
+            Self::Decode(_) | Self::Decompress(_) | Self::InvalidChecksum(_) | Self::Unrecoverable
+        )
+    }
+
+    /// Returns `true` if the error was caused by an I/O error (including a full disk).
+    #[must_use]
+    pub fn is_io(&self) -> bool {
+        matches!(self, Self::Io(_) | Self::DiskFull(_))
+    }
+
+    /// Returns `true` if the disk ran out of space.
+    #[must_use]
+    pub fn is_disk_full(&self) -> bool {
+        match self {
+            Self::DiskFull(_) => true,
+            Self::Io(e) | Self::Encode(EncodeError::Io(e)) => is_disk_full(e),
+            _ => false,
+        }
+    }
+}
+
+/// Returns `true` if the I/O error was caused by the disk running out of space
+pub fn is_disk_full(e: &std::io::Error) -> bool {
+    // NOTE: ErrorKind::StorageFull is not stable in our MSRV
+    #[cfg(unix)]
+    const DISK_FULL_CODES: &[i32] = &[/* ENOSPC */ 28];
+
+    #[cfg(windows)]
+    const DISK_FULL_CODES: &[i32] = &[
+        /* ERROR_HANDLE_DISK_FULL */ 39, /* ERROR_DISK_FULL */ 112,
+    ];
+
+    #[cfg(not(any(unix, windows)))]
+    const DISK_FULL_CODES: &[i32] = &[];
+
+    // NOTE: Context hides the OS error code of the wrapped error
+    if let Some(e) = e
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<ContextualIoError>())
+    {
+        return is_disk_full(&e.source);
+    }
+
+    e.raw_os_error()
+        .is_some_and(|code| DISK_FULL_CODES.contains(&code))
 }
 
 impl std::fmt::Display for Error {
@@ -42,17 +257,83 @@ impl std::fmt::Display for Error {
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) | Self::DiskFull(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Payload of an I/O error that has context attached to it
+#[derive(Debug)]
+pub struct ContextualIoError {
+    context: ErrorContext,
+    source: std::io::Error,
+}
+
+impl ContextualIoError {
+    /// Attaches context to an I/O error, keeping its kind.
+    ///
+    /// If the error already has context, missing fields are filled in,
+    /// and the operation is replaced by the (outer) operation.
+    pub(crate) fn attach(mut error: std::io::Error, context: ErrorContext) -> std::io::Error {
+        if let Some(existing) = error
+            .get_mut()
+            .and_then(|e| e.downcast_mut::<Self>())
+            .map(|e| &mut e.context)
+        {
+            existing.merge(context);
+            return error;
+        }
+
+        std::io::Error::new(
+            error.kind(),
+            Self {
+                context,
+                source: error,
+            },
+        )
+    }
+
+    /// Returns the context attached to an I/O error, if any.
+    pub(crate) fn context_of(error: &std::io::Error) -> Option<&ErrorContext> {
+        error
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<Self>())
+            .map(|e| &e.context)
+    }
+}
+
+impl std::fmt::Display for ContextualIoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.source, self.context)
+    }
+}
+
+impl std::error::Error for ContextualIoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
 
 impl From<std::io::Error> for Error {
     fn from(value: std::io::Error) -> Self {
-        Self::Io(value)
+        if is_disk_full(&value) {
+            Self::DiskFull(value)
+        } else {
+            Self::Io(value)
+        }
     }
 }
 
 impl From<EncodeError> for Error {
     fn from(value: EncodeError) -> Self {
-        Self::Encode(value)
+        match value {
+            EncodeError::Io(e) if is_disk_full(&e) => Self::DiskFull(e),
+            EncodeError::Io(e) => Self::Encode(EncodeError::Io(e)),
+        }
     }
 }
 
@@ -70,3 +351,73 @@ impl From<value_log::Error> for Error {
 
 /// Tree result
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn error_context_merge() {
+        let err = Error::DiskFull(std::io::Error::from_raw_os_error(28))
+            .with_context(
+                ErrorContext::new(Operation::Read)
+                    .with_segment_id(4)
+                    .with_block_offset(128),
+            )
+            .with_context(ErrorContext::new(Operation::Compaction).with_segment_id(5));
+
+        assert!(err.is_disk_full());
+        assert!(err.is_io());
+        assert!(!err.is_corruption());
+        assert!(matches!(err, Error::DiskFull(_)));
+
+        assert_eq!(
+            Some(&ErrorContext {
+                operation: Some(Operation::Compaction),
+                path: None,
+                segment_id: Some(4),
+                block_offset: Some(128),
+            }),
+            err.context(),
+        );
+
+        let io_error = std::error::Error::source(&err)
+            .and_then(|e| e.downcast_ref::<std::io::Error>())
+            .expect("should have I/O error as source");
+        assert_eq!(
+            std::io::Error::from_raw_os_error(28).kind(),
+            io_error.kind()
+        );
+        assert!(io_error
+            .to_string()
+            .ends_with(" (operation=Compaction, segment=4, block_offset=128)"));
+
+        let os_error = std::error::Error::source(io_error)
+            .and_then(|e| e.downcast_ref::<std::io::Error>())
+            .expect("should have OS error as source");
+        assert_eq!(Some(28), os_error.raw_os_error());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn error_disk_full_with_context() {
+        let io_error = ContextualIoError::attach(
+            std::io::Error::from_raw_os_error(28),
+            ErrorContext::new(Operation::Flush),
+        );
+        assert!(is_disk_full(&io_error));
+
+        let err = Error::from(io_error);
+        assert!(matches!(err, Error::DiskFull(_)));
+        assert!(err.is_disk_full());
+
+        // NOTE: Errors that were wrapped before being classified
+        let err = Error::Io(ContextualIoError::attach(
+            std::io::Error::from_raw_os_error(28),
+            ErrorContext::new(Operation::Compaction),
+        ));
+        assert!(err.is_disk_full());
+        assert!(!Error::Io(std::io::Error::from_raw_os_error(2)).is_disk_full());
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Page cache hints for files that are rarely read
+//!
+//! Hints are best-effort: if the OS does not support or refuses a hint, it is ignored.
+//! Hints are only given on Linux, with the `fadvise` feature.
+
+use std::fs::File;
+
+/// Hints that data read through the file descriptor will not be reused (`POSIX_FADV_NOREUSE`),
+/// so reading the file does not displace the cached pages of other files.
+#[cfg(all(feature = "fadvise", target_os = "linux"))]
+pub fn advise_no_reuse(file: &File) {
+    advise(file, rustix::fs::Advice::NoReuse);
+}
+
+/// Hints that the (written and synced) pages of the file should be dropped from the page cache
+/// (`POSIX_FADV_DONTNEED`), so freshly written data does not displace the cached pages of other files.
+#[cfg(all(feature = "fadvise", target_os = "linux"))]
+pub fn advise_dont_need(file: &File) {
+    advise(file, rustix::fs::Advice::DontNeed);
+}
+
+#[cfg(all(feature = "fadvise", target_os = "linux"))]
+fn advise(file: &File, advice: rustix::fs::Advice) {
+    if let Err(e) = rustix::fs::fadvise(file, 0, None, advice) {
+        log::debug!("Failed to give page cache hint {advice:?}: {e:?}");
+    }
+}
+
+/// Does nothing, because page cache hints require the `fadvise` feature (on Linux).
+#[cfg(not(all(feature = "fadvise", target_os = "linux")))]
+pub fn advise_no_reuse(_: &File) {}
+
+/// Does nothing, because page cache hints require the `fadvise` feature (on Linux).
+#[cfg(not(all(feature = "fadvise", target_os = "linux")))]
+pub fn advise_dont_need(_: &File) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn fadvise_best_effort() -> crate::Result<()> {
+        let file = tempfile::tempfile()?;
+
+        // NOTE: Hints never fail, even if the OS does not support them
+        advise_no_reuse(&file);
+        advise_dont_need(&file);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Fault injection points, for simulating crashes and I/O errors
+//!
+//! Fail points are placed in the critical I/O paths of the tree. When a fail point
+//! is enabled, evaluating it either returns an I/O error or panics, so the recovery
+//! behaviour of the tree (and systems built on top of it) can be tested deterministically.
+//!
+//! The fail point configuration is global to the process.
+//!
+//! Only available with the `failpoints` feature.
+
+use std::sync::Mutex;
+
+/// Evaluated before a data block is written into a segment file
+pub const SEGMENT_WRITE: &str = "segment_write";
+
+/// Evaluated before a new level manifest is committed to disk
+pub const MANIFEST_COMMIT: &str = "manifest_commit";
+
+/// Evaluated before a file or directory is fsync'ed
+pub const FSYNC: &str = "fsync";
+
+/// Evaluated before a block is read from a segment file
+pub const BLOCK_READ: &str = "block_read";
+
+/// What happens when an enabled fail point is hit
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Action {
+    /// Returns an I/O error of the given kind
+    Error(std::io::ErrorKind),
+
+    /// Panics, simulating a crash of the process
+    Panic,
+}
+
+struct FailPoint {
+    name: &'static str,
+    action: Action,
+
+    /// Amount of hits to let pass before triggering
+    skip: usize,
+}
+
+static FAIL_POINTS: Mutex<Vec<FailPoint>> = Mutex::new(Vec::new());
+
+/// Enables a fail point, triggering its action on every hit.
+pub fn enable(name: &'static str, action: Action) {
+    enable_after(name, 0, action);
+}
+
+/// Enables a fail point, letting the first `skip` hits pass,
+/// then triggering its action on every subsequent hit.
+///
+/// # Panics
+///
+/// Panics if a lock is poisoned.
+#[allow(clippy::expect_used)]
+pub fn enable_after(name: &'static str, skip: usize, action: Action) {
+    let mut fail_points = FAIL_POINTS.lock().expect("lock is poisoned");
+    fail_points.retain(|x| x.name != name);
+    fail_points.push(FailPoint { name, action, skip });
+}
+
+/// Disables a fail point.
+///
+/// # Panics
+///
+/// Panics if a lock is poisoned.
+#[allow(clippy::expect_used)]
+pub fn disable(name: &'static str) {
+    let mut fail_points = FAIL_POINTS.lock().expect("lock is poisoned");
+    fail_points.retain(|x| x.name != name);
+}
+
+/// Disables all fail points.
+///
+/// # Panics
+///
+/// Panics if a lock is poisoned.
+#[allow(clippy::expect_used)]
+pub fn disable_all() {
+    FAIL_POINTS.lock().expect("lock is poisoned").clear();
+}
+
+/// Evaluates a fail point.
+///
+/// # Errors
+///
+/// Will return `Err` if the fail point is enabled with [`Action::Error`].
+///
+/// # Panics
+///
+/// Panics if the fail point is enabled with [`Action::Panic`].
+#[allow(clippy::expect_used)]
+pub fn eval(name: &'static str) -> std::io::Result<()> {
+    let mut fail_points = FAIL_POINTS.lock().expect("lock is poisoned");
+
+    let Some(fail_point) = fail_points.iter_mut().find(|x| x.name == name) else {
+        return Ok(());
+    };
+
+    if fail_point.skip > 0 {
+        fail_point.skip -= 1;
+        return Ok(());
+    }
+
+    let action = fail_point.action.clone();
+
+    // NOTE: The lock is released before panicking, so it is not poisoned
+    drop(fail_points);
+
+    match action {
+        Action::Error(kind) => Err(std::io::Error::new(
+            kind,
+            format!("fail point {name:?} triggered"),
+        )),
+        Action::Panic => panic!("fail point {name:?} triggered"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn failpoints_enable_after() {
+        const NAME: &str = "test_enable_after";
+
+        assert!(eval(NAME).is_ok());
+
+        enable_after(NAME, 2, Action::Error(std::io::ErrorKind::Other));
+        assert!(eval(NAME).is_ok());
+        assert!(eval(NAME).is_ok());
+        assert_eq!(
+            std::io::ErrorKind::Other,
+            eval(NAME).expect_err("should fail").kind()
+        );
+        assert!(eval(NAME).is_err());
+
+        disable(NAME);
+        assert!(eval(NAME).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "fail point \"test_panic\" triggered")]
+    fn failpoints_panic() {
+        const NAME: &str = "test_panic";
+
+        enable(NAME, Action::Panic);
+        let _ = eval(NAME);
+    }
+}
@@ -8,8 +8,35 @@ pub const MAGIC_BYTES: [u8; 4] = [b'L', b'S', b'M', 2];
 
 pub const MANIFEST_FILE: &str = "manifest";
 pub const SEGMENTS_FOLDER: &str = "segments";
+pub const COMPACTIONS_FOLDER: &str = "compactions";
 pub const LEVELS_MANIFEST_FILE: &str = "levels";
 pub const BLOBS_FOLDER: &str = "blobs";
+pub const CHUNKS_FOLDER: &str = "chunks";
+pub const HEADROOM_FILE: &str = "headroom";
+pub const OWNED_BLOB_FILES_FILE: &str = "blob_files";
+pub const SHARED_TREES_FILE: &str = "trees";
+pub const OPS_LOG_FILE: &str = "ops.log";
+pub const ROTATED_OPS_LOG_FILE: &str = "ops.log.1";
+
+/// Reserves disk space by writing a file of the given size
+///
+/// The file can later be deleted to free up space when the disk is full.
+pub fn reserve_headroom<P: AsRef<Path>>(path: P, bytes: u64) -> std::io::Result<()> {
+    let path = path.as_ref();
+
+    if path.try_exists()? && std::fs::metadata(path)?.len() >= bytes {
+        return Ok(());
+    }
+
+    let result = write_zeroes(path, bytes);
+
+    if result.is_err() {
+        // NOTE: Don't leave a half-written reservation lying around
+        let _ = std::fs::remove_file(path);
+    }
+
+    result
+}
 
 /// Atomically rewrites a file
 pub fn rewrite_atomic<P: AsRef<Path>>(path: P, content: &[u8]) -> std::io::Result<()> {
@@ -26,6 +53,8 @@ pub fn rewrite_atomic<P: AsRef<Path>>(path: P, content: &[u8]) -> std::io::Resul
     // TODO: not sure why it fails on Windows...
     #[cfg(not(target_os = "windows"))]
     {
+        fail_point!(crate::failpoints::FSYNC);
+
         let file = std::fs::File::open(path)?;
         file.sync_all()?;
     }
@@ -33,8 +62,30 @@ pub fn rewrite_atomic<P: AsRef<Path>>(path: P, content: &[u8]) -> std::io::Resul
     Ok(())
 }
 
+/// Writes a file that consists of `bytes` zeroes
+fn write_zeroes(path: &Path, bytes: u64) -> std::io::Result<()> {
+    // NOTE: Need to actually write the data, a sparse file
+    // created using set_len would not reserve any disk space
+    let mut file = std::fs::File::create(path)?;
+    let chunk = vec![0; 64 * 1_024];
+    let mut remaining = bytes;
+
+    while remaining > 0 {
+        // NOTE: Truncation is OK because the chunk is smaller than u32
+        #[allow(clippy::cast_possible_truncation)]
+        let len = remaining.min(chunk.len() as u64) as usize;
+
+        file.write_all(chunk.get(..len).unwrap_or_default())?;
+        remaining -= len as u64;
+    }
+
+    file.sync_all()
+}
+
 #[cfg(not(target_os = "windows"))]
 pub fn fsync_directory<P: AsRef<Path>>(path: P) -> std::io::Result<()> {
+    fail_point!(crate::failpoints::FSYNC);
+
     let file = std::fs::File::open(path)?;
     debug_assert!(file.metadata()?.is_dir());
     file.sync_all()
@@ -53,6 +104,21 @@ mod tests {
     use std::io::Write;
     use test_log::test;
 
+    #[test]
+    fn reserve_headroom_file() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(HEADROOM_FILE);
+
+        reserve_headroom(&path, 100_000)?;
+        assert_eq!(100_000, std::fs::metadata(&path)?.len());
+
+        // NOTE: Reserving again is a no-op
+        reserve_headroom(&path, 1_000)?;
+        assert_eq!(100_000, std::fs::metadata(&path)?.len());
+
+        Ok(())
+    }
+
     #[test]
     fn atomic_rewrite() -> crate::Result<()> {
         let dir = tempfile::tempdir()?;
@@ -0,0 +1,202 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::coding::{Decode, DecodeError, Encode, EncodeError};
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+/// Number of hash bits that select a register
+const PRECISION: u8 = 10;
+
+/// Number of registers (1 byte each)
+const REGISTER_COUNT: usize = 1 << PRECISION;
+
+/// `HyperLogLog` sketch that estimates the number of distinct keys
+///
+/// The sketch uses 1 KiB, with a standard error of about 3%.
+/// Sketches can be merged, which allows estimating the number of distinct keys
+/// of a set of segments (e.g. a level or the whole tree), and the overlap between segments.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HyperLogLog {
+    registers: Box<[u8]>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self {
+            registers: vec![0; REGISTER_COUNT].into_boxed_slice(),
+        }
+    }
+}
+
+impl HyperLogLog {
+    /// Hashes a key.
+    #[must_use]
+    pub fn get_hash(key: &[u8]) -> u64 {
+        xxhash_rust::xxh3::xxh3_64(key)
+    }
+
+    /// Adds a key to the sketch.
+    pub fn insert(&mut self, key: &[u8]) {
+        self.insert_hash(Self::get_hash(key));
+    }
+
+    /// Adds a key hash to the sketch.
+    pub fn insert_hash(&mut self, hash: u64) {
+        // NOTE: Truncation is OK because the index only has PRECISION bits
+        #[allow(clippy::cast_possible_truncation)]
+        let idx = (hash >> (64 - PRECISION)) as usize;
+
+        // NOTE: Set the lowest bit that is not used for the index,
+        // so the rank is capped if all remaining bits are zero
+        let rest = (hash << PRECISION) | (1 << (PRECISION - 1));
+
+        // NOTE: Truncation is OK because the rank is at most 64 - PRECISION + 1
+        #[allow(clippy::cast_possible_truncation)]
+        let rank = rest.leading_zeros() as u8 + 1;
+
+        if let Some(register) = self.registers.get_mut(idx) {
+            *register = (*register).max(rank);
+        }
+    }
+
+    /// Merges another sketch into this one, so it estimates the union of both key sets.
+    pub fn merge(&mut self, other: &Self) {
+        for (a, b) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *a = (*a).max(*b);
+        }
+    }
+
+    /// Estimates the number of distinct keys.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn estimate(&self) -> u64 {
+        let m = REGISTER_COUNT as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+
+        let (sum, zeros) = self
+            .registers
+            .iter()
+            .fold((0.0, 0usize), |(sum, zeros), &rank| {
+                (
+                    sum + 1.0 / f64::from(1u32 << rank.min(31)),
+                    zeros + usize::from(rank == 0),
+                )
+            });
+
+        let estimate = alpha * m * m / sum;
+
+        // NOTE: Small range correction (linear counting)
+        if estimate <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+
+    /// Estimates the number of distinct keys that are contained in both sketches.
+    #[must_use]
+    pub fn intersection_estimate(&self, other: &Self) -> u64 {
+        let mut union = self.clone();
+        union.merge(other);
+
+        (self.estimate() + other.estimate()).saturating_sub(union.estimate())
+    }
+}
+
+impl Encode for HyperLogLog {
+    fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        writer.write_u8(PRECISION)?;
+        writer.write_all(&self.registers)?;
+        Ok(())
+    }
+}
+
+impl Decode for HyperLogLog {
+    fn decode_from<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let precision = reader.read_u8()?;
+
+        if precision != PRECISION {
+            return Err(DecodeError::invalid_header(
+                "HyperLogLog",
+                &[PRECISION],
+                &[precision],
+            ));
+        }
+
+        let mut registers = vec![0; REGISTER_COUNT].into_boxed_slice();
+        reader.read_exact(&mut registers)?;
+
+        Ok(Self { registers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    fn sketch(keys: std::ops::Range<u64>) -> HyperLogLog {
+        let mut hll = HyperLogLog::default();
+        for key in keys {
+            hll.insert(&key.to_be_bytes());
+        }
+        hll
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn hyperloglog_estimate() {
+        assert_eq!(0, HyperLogLog::default().estimate());
+
+        for n in [10, 1_000, 100_000] {
+            let estimate = sketch(0..n).estimate() as f64;
+            let error = (estimate - n as f64).abs() / n as f64;
+            assert!(error < 0.1, "estimate {estimate} is too far off {n}");
+        }
+    }
+
+    #[test]
+    fn hyperloglog_duplicates() {
+        let mut hll = sketch(0..1_000);
+        let before = hll.estimate();
+
+        for key in 0u64..1_000 {
+            hll.insert(&key.to_be_bytes());
+        }
+
+        assert_eq!(before, hll.estimate());
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn hyperloglog_merge_intersection() {
+        let a = sketch(0..10_000);
+        let b = sketch(5_000..15_000);
+
+        let mut union = a.clone();
+        union.merge(&b);
+
+        let estimate = union.estimate() as f64;
+        assert!((estimate - 15_000.0).abs() / 15_000.0 < 0.1);
+
+        let estimate = a.intersection_estimate(&b) as f64;
+        assert!((estimate - 5_000.0).abs() / 5_000.0 < 0.3);
+
+        assert!(a.intersection_estimate(&sketch(20_000..30_000)) < 3_000);
+    }
+
+    #[test]
+    fn hyperloglog_roundtrip() -> crate::Result<()> {
+        let before = sketch(0..1_000);
+
+        let buf = before.encode_into_vec()?;
+        let after = HyperLogLog::decode_from(&mut buf.as_slice())?;
+        assert_eq!(before, after);
+
+        Ok(())
+    }
+}
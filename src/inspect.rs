@@ -0,0 +1,203 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    coding::Decode,
+    encryption::Cipher,
+    file::{LEVELS_MANIFEST_FILE, MANIFEST_FILE, SEGMENTS_FOLDER},
+    level_manifest::LevelManifest,
+    manifest::Manifest,
+    segment::{meta::Metadata, trailer::SegmentFileTrailer},
+    KeyRange, SeqNo, TreeType, Uuid, Version,
+};
+use std::path::Path;
+
+/// Reads the shape of a tree on disk, without opening it
+///
+/// Only the manifests and the metadata of each segment are read,
+/// block indexes and bloom filters are not loaded, and nothing is written.
+///
+/// # Errors
+///
+/// Will return `Err` if an IO error occurs, the tree is corrupted or encrypted.
+pub fn inspect<P: AsRef<Path>>(path: P) -> crate::Result<TreeInspection> {
+    inspect_tree(path.as_ref(), None)
+}
+
+/// Reads the shape of a (possibly encrypted) tree on disk, without opening it
+///
+/// See [`inspect`].
+///
+/// # Errors
+///
+/// Will return `Err` if an IO error occurs, the tree is corrupted,
+/// or it is encrypted, but no cipher is given.
+#[cfg(feature = "encryption")]
+pub fn inspect_with_cipher<P: AsRef<Path>>(
+    path: P,
+    cipher: Option<&Cipher>,
+) -> crate::Result<TreeInspection> {
+    inspect_tree(path.as_ref(), cipher)
+}
+
+fn inspect_tree(path: &Path, cipher: Option<&Cipher>) -> crate::Result<TreeInspection> {
+    log::debug!("Inspecting tree at {}", path.display());
+
+    let bytes = std::fs::read(path.join(MANIFEST_FILE))?;
+    let manifest = Manifest::decode_from(&mut bytes.as_slice())?;
+
+    let level_manifest = LevelManifest::load_level_manifest(path.join(LEVELS_MANIFEST_FILE))?;
+    let segment_folder = path.join(SEGMENTS_FOLDER);
+
+    let levels = level_manifest
+        .into_iter()
+        .map(|segment_ids| {
+            let segments = segment_ids
+                .into_iter()
+                .map(|segment_id| {
+                    let segment_file_path = segment_folder.join(segment_id.to_string());
+                    let trailer = SegmentFileTrailer::from_file(&segment_file_path, cipher)?;
+
+                    // NOTE: The segment ID is taken from the level manifest, see Segment::recover
+                    let mut metadata = trailer.metadata;
+                    metadata.id = segment_id;
+
+                    Ok(metadata)
+                })
+                .collect::<crate::Result<Vec<_>>>()?;
+
+            Ok(LevelInspection { segments })
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+
+    Ok(TreeInspection {
+        version: manifest.version,
+        tree_type: manifest.tree_type,
+        id: manifest.uuid,
+        levels,
+    })
+}
+
+/// Shape of a tree on disk
+///
+/// See [`inspect`].
+#[allow(clippy::module_name_repetitions)]
+pub struct TreeInspection {
+    /// Disk format version of the tree
+    pub version: Version,
+
+    /// Type of the tree
+    pub tree_type: TreeType,
+
+    /// Unique identifier of the tree
+    ///
+    /// Trees created by older versions do not have an identifier.
+    pub id: Option<Uuid>,
+
+    /// Levels of the tree, from L0 downwards
+    pub levels: Vec<LevelInspection>,
+}
+
+impl TreeInspection {
+    fn segments(&self) -> impl Iterator<Item = &Metadata> {
+        self.levels.iter().flat_map(|level| &level.segments)
+    }
+
+    /// Returns the amount of disk segments in the tree.
+    #[must_use]
+    pub fn segment_count(&self) -> usize {
+        self.levels.iter().map(|level| level.segments.len()).sum()
+    }
+
+    /// Returns the size of all disk segments in bytes.
+    ///
+    /// Blob files of key-value separated trees are not included.
+    #[must_use]
+    pub fn disk_space(&self) -> u64 {
+        self.levels.iter().map(LevelInspection::disk_space).sum()
+    }
+
+    /// Returns the amount of items (including tombstones and older versions) in all disk segments.
+    #[must_use]
+    pub fn item_count(&self) -> u64 {
+        self.levels.iter().map(LevelInspection::item_count).sum()
+    }
+
+    /// Returns the amount of tombstones in all disk segments.
+    #[must_use]
+    pub fn tombstone_count(&self) -> u64 {
+        self.levels
+            .iter()
+            .map(LevelInspection::tombstone_count)
+            .sum()
+    }
+
+    /// Returns the smallest and largest key in all disk segments,
+    /// or `None` if there are no segments.
+    #[must_use]
+    pub fn key_range(&self) -> Option<KeyRange> {
+        key_range_of(self.segments())
+    }
+
+    /// Returns the lowest and highest sequence number in all disk segments,
+    /// or `None` if there are no segments.
+    #[must_use]
+    pub fn seqno_range(&self) -> Option<(SeqNo, SeqNo)> {
+        seqno_range_of(self.segments())
+    }
+}
+
+/// Shape of a single level of a tree on disk
+///
+/// See [`inspect`].
+pub struct LevelInspection {
+    /// Metadata of the segments in the level
+    pub segments: Vec<Metadata>,
+}
+
+impl LevelInspection {
+    /// Returns the size of all segments in the level in bytes.
+    #[must_use]
+    pub fn disk_space(&self) -> u64 {
+        self.segments.iter().map(|x| x.file_size).sum()
+    }
+
+    /// Returns the amount of items (including tombstones and older versions) in the level.
+    #[must_use]
+    pub fn item_count(&self) -> u64 {
+        self.segments.iter().map(|x| x.item_count).sum()
+    }
+
+    /// Returns the amount of tombstones in the level.
+    #[must_use]
+    pub fn tombstone_count(&self) -> u64 {
+        self.segments.iter().map(|x| x.tombstone_count).sum()
+    }
+
+    /// Returns the smallest and largest key in the level,
+    /// or `None` if the level is empty.
+    #[must_use]
+    pub fn key_range(&self) -> Option<KeyRange> {
+        key_range_of(self.segments.iter())
+    }
+
+    /// Returns the lowest and highest sequence number in the level,
+    /// or `None` if the level is empty.
+    #[must_use]
+    pub fn seqno_range(&self) -> Option<(SeqNo, SeqNo)> {
+        seqno_range_of(self.segments.iter())
+    }
+}
+
+fn key_range_of<'a>(segments: impl Iterator<Item = &'a Metadata>) -> Option<KeyRange> {
+    segments
+        .map(|x| x.key_range.clone())
+        .reduce(|a, b| a.union(&b))
+}
+
+fn seqno_range_of<'a>(segments: impl Iterator<Item = &'a Metadata>) -> Option<(SeqNo, SeqNo)> {
+    segments
+        .map(|x| x.seqnos)
+        .reduce(|(min, max), (lo, hi)| (min.min(lo), max.max(hi)))
+}
@@ -13,6 +13,9 @@ use std::{
 };
 use varint_rs::{VarintReader, VarintWriter};
 
+/// Bits of the encoded value type tag that are reserved for item flags
+const TAG_FLAGS_MASK: u8 = 0xF0;
+
 #[derive(Clone, PartialEq, Eq)]
 #[allow(clippy::module_name_repetitions)]
 pub struct InternalKey {
@@ -56,13 +59,18 @@ impl InternalKey {
     pub fn is_tombstone(&self) -> bool {
         self.value_type == ValueType::Tombstone || self.value_type == ValueType::WeakTombstone
     }
-}
 
-impl Encode for InternalKey {
-    fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+    /// Encodes the key, setting the given item flags in its value type tag.
+    pub(crate) fn encode_with_tag_flags<W: Write>(
+        &self,
+        writer: &mut W,
+        flags: u8,
+    ) -> Result<(), EncodeError> {
+        debug_assert_eq!(0, flags & !TAG_FLAGS_MASK, "invalid item flags");
+
         writer.write_u64_varint(self.seqno)?;
 
-        writer.write_u8(u8::from(self.value_type))?;
+        writer.write_u8(u8::from(self.value_type) | flags)?;
 
         // NOTE: Truncation is okay and actually needed
         #[allow(clippy::cast_possible_truncation)]
@@ -71,22 +79,44 @@ impl Encode for InternalKey {
 
         Ok(())
     }
-}
 
-impl Decode for InternalKey {
-    fn decode_from<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+    /// Decodes the key, returning the item flags that were set in its value type tag.
+    pub(crate) fn decode_with_tag_flags<R: Read>(
+        reader: &mut R,
+    ) -> Result<(Self, u8), DecodeError> {
         let seqno = reader.read_u64_varint()?;
 
-        let value_type = reader.read_u8()?;
-        let value_type = value_type
+        let tag = reader.read_u8()?;
+        let value_type = (tag & !TAG_FLAGS_MASK)
             .try_into()
-            .map_err(|()| DecodeError::InvalidTag(("ValueType", value_type)))?;
+            .map_err(|()| DecodeError::InvalidTag(("ValueType", tag)))?;
 
         let key_len = reader.read_u16_varint()?;
         let mut key = vec![0; key_len.into()];
         reader.read_exact(&mut key)?;
 
-        Ok(Self::new(key, seqno, value_type))
+        Ok((Self::new(key, seqno, value_type), tag & TAG_FLAGS_MASK))
+    }
+}
+
+impl Encode for InternalKey {
+    fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        self.encode_with_tag_flags(writer, 0)
+    }
+}
+
+impl Decode for InternalKey {
+    fn decode_from<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let (key, flags) = Self::decode_with_tag_flags(reader)?;
+
+        if flags != 0 {
+            return Err(DecodeError::InvalidTag((
+                "ValueType",
+                u8::from(key.value_type) | flags,
+            )));
+        }
+
+        Ok(key)
     }
 }
 
@@ -104,3 +134,58 @@ impl Ord for InternalKey {
         (&self.user_key, Reverse(self.seqno)).cmp(&(&other.user_key, Reverse(other.seqno)))
     }
 }
+
+/// Borrowed form of an [`InternalKey`], which allows searching
+/// the memtable without allocating a user key
+pub trait InternalKeyRef {
+    fn user_key(&self) -> &[u8];
+
+    fn seqno(&self) -> SeqNo;
+}
+
+impl InternalKeyRef for InternalKey {
+    fn user_key(&self) -> &[u8] {
+        &self.user_key
+    }
+
+    fn seqno(&self) -> SeqNo {
+        self.seqno
+    }
+}
+
+impl InternalKeyRef for (&[u8], SeqNo) {
+    fn user_key(&self) -> &[u8] {
+        self.0
+    }
+
+    fn seqno(&self) -> SeqNo {
+        self.1
+    }
+}
+
+impl<'a> std::borrow::Borrow<dyn InternalKeyRef + 'a> for InternalKey {
+    fn borrow(&self) -> &(dyn InternalKeyRef + 'a) {
+        self
+    }
+}
+
+impl PartialEq for dyn InternalKeyRef + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for dyn InternalKeyRef + '_ {}
+
+impl PartialOrd for dyn InternalKeyRef + '_ {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// NOTE: Needs to be consistent with the ordering of InternalKey
+impl Ord for dyn InternalKeyRef + '_ {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.user_key(), Reverse(self.seqno())).cmp(&(other.user_key(), Reverse(other.seqno())))
+    }
+}
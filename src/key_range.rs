@@ -9,13 +9,48 @@ use crate::{
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::{
     io::{Read, Write},
-    ops::{Bound, Deref},
+    ops::{Bound, Deref, RangeBounds, RangeInclusive},
 };
 
 /// A key range in the format of [min, max] (inclusive on both sides)
+///
+/// Can be used as range bounds, e.g. to scan through a key range:
+///
+/// ```
+/// # use lsm_tree::KeyRange;
+/// let a = KeyRange::from("a"..="f");
+/// let b = KeyRange::from("d"..="k");
+///
+/// assert!(a.contains_key("c"));
+/// assert_eq!(Some(KeyRange::from("d"..="f")), a.intersect(&b));
+/// assert_eq!(KeyRange::from("a"..="k"), a.union(&b));
+/// ```
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct KeyRange((UserKey, UserKey));
 
+impl<K: Into<UserKey>> From<RangeInclusive<K>> for KeyRange {
+    fn from(range: RangeInclusive<K>) -> Self {
+        let (min, max) = range.into_inner();
+        Self::new((min.into(), max.into()))
+    }
+}
+
+impl From<(UserKey, UserKey)> for KeyRange {
+    fn from(range: (UserKey, UserKey)) -> Self {
+        Self::new(range)
+    }
+}
+
+impl RangeBounds<UserKey> for KeyRange {
+    fn start_bound(&self) -> Bound<&UserKey> {
+        Bound::Included(&self.0 .0)
+    }
+
+    fn end_bound(&self) -> Bound<&UserKey> {
+        Bound::Included(&self.0 .1)
+    }
+}
+
 impl std::ops::Deref for KeyRange {
     type Target = (UserKey, UserKey);
 
@@ -25,11 +60,55 @@ impl std::ops::Deref for KeyRange {
 }
 
 impl KeyRange {
+    /// Creates a key range from its (inclusive) minimum and maximum key.
+    #[must_use]
     pub fn new(range: (UserKey, UserKey)) -> Self {
         Self(range)
     }
 
+    /// Returns the smallest key of the range.
+    #[must_use]
+    pub fn min(&self) -> &UserKey {
+        &self.0 .0
+    }
+
+    /// Returns the largest key of the range.
+    #[must_use]
+    pub fn max(&self) -> &UserKey {
+        &self.0 .1
+    }
+
+    /// Returns `true` if the other key range lies completely inside this key range.
+    #[must_use]
+    pub fn contains(&self, other: &Self) -> bool {
+        self.min() <= other.min() && other.max() <= self.max()
+    }
+
+    /// Returns the key range that is covered by both key ranges,
+    /// or `None` if they do not overlap.
+    #[must_use]
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        if !self.overlaps_with_key_range(other) {
+            return None;
+        }
+
+        Some(Self::new((
+            self.min().max(other.min()).clone(),
+            self.max().min(other.max()).clone(),
+        )))
+    }
+
+    /// Returns the smallest key range that covers both key ranges.
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        Self::new((
+            self.min().min(other.min()).clone(),
+            self.max().max(other.max()).clone(),
+        ))
+    }
+
     /// Returns `true` if the list of key ranges is disjoint
+    #[must_use]
     pub fn is_disjoint(ranges: &[&Self]) -> bool {
         for (idx, a) in ranges.iter().enumerate() {
             for b in ranges.iter().skip(idx + 1) {
@@ -42,52 +121,38 @@ impl KeyRange {
         true
     }
 
+    /// Returns `true` if the key lies inside the key range.
+    #[must_use]
     pub fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> bool {
         let key = key.as_ref();
         let (start, end) = &self.0;
         key >= *start && key <= *end
     }
 
+    /// Returns `true` if the key ranges share at least one key.
+    #[must_use]
     pub fn overlaps_with_key_range(&self, other: &Self) -> bool {
         let (start1, end1) = &self.0;
         let (start2, end2) = &other.0;
         end1 >= start2 && start1 <= end2
     }
 
+    /// Returns `true` if the key range shares at least one key with the given bounds.
+    #[must_use]
     pub fn overlaps_with_bounds(&self, bounds: &(Bound<UserKey>, Bound<UserKey>)) -> bool {
         let (lo, hi) = bounds;
         let (my_lo, my_hi) = &self.0;
 
-        if *lo == Bound::Unbounded && *hi == Bound::Unbounded {
-            return true;
-        }
-
-        if *hi == Bound::Unbounded {
-            return match lo {
-                Bound::Included(key) => key <= my_hi,
-                Bound::Excluded(key) => key < my_hi,
-                Bound::Unbounded => panic!("Invalid key range check"),
-            };
-        }
-
-        if *lo == Bound::Unbounded {
-            return match hi {
-                Bound::Included(key) => key >= my_lo,
-                Bound::Excluded(key) => key > my_lo,
-                Bound::Unbounded => panic!("Invalid key range check"),
-            };
-        }
-
         let lo_included = match lo {
             Bound::Included(key) => key <= my_hi,
             Bound::Excluded(key) => key < my_hi,
-            Bound::Unbounded => panic!("Invalid key range check"),
+            Bound::Unbounded => true,
         };
 
         let hi_included = match hi {
             Bound::Included(key) => key >= my_lo,
             Bound::Excluded(key) => key > my_lo,
-            Bound::Unbounded => panic!("Invalid key range check"),
+            Bound::Unbounded => true,
         };
 
         lo_included && hi_included
@@ -233,4 +298,50 @@ mod tests {
             assert!(key_range.overlaps_with_bounds(&bounds));
         }
     }
+
+    mod set_operations {
+        use super::*;
+
+        #[test]
+        fn key_range_from_range_inclusive() {
+            assert_eq!(string_key_range("a", "f"), KeyRange::from("a"..="f"));
+            assert_eq!(
+                string_key_range("a", "f"),
+                KeyRange::from(b"a".as_slice()..=b"f".as_slice())
+            );
+        }
+
+        #[test]
+        fn key_range_contains() {
+            let a = string_key_range("a", "f");
+            assert!(a.contains(&string_key_range("a", "f")));
+            assert!(a.contains(&string_key_range("b", "c")));
+            assert!(!a.contains(&string_key_range("b", "g")));
+            assert!(!string_key_range("b", "c").contains(&a));
+        }
+
+        #[test]
+        fn key_range_intersect() {
+            let a = string_key_range("a", "f");
+            assert_eq!(
+                Some(string_key_range("c", "f")),
+                a.intersect(&string_key_range("c", "z"))
+            );
+            assert_eq!(
+                Some(string_key_range("f", "f")),
+                a.intersect(&string_key_range("f", "z"))
+            );
+            assert_eq!(None, a.intersect(&string_key_range("g", "z")));
+        }
+
+        #[test]
+        fn key_range_union() {
+            let a = string_key_range("a", "f");
+            assert_eq!(
+                string_key_range("a", "z"),
+                a.union(&string_key_range("x", "z"))
+            );
+            assert_eq!(a, a.union(&string_key_range("b", "c")));
+        }
+    }
 }
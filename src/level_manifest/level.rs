@@ -2,9 +2,27 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use crate::{key_range::KeyRange, segment::meta::SegmentId, Segment};
+use crate::{hyperloglog::HyperLogLog, key_range::KeyRange, segment::meta::SegmentId, Segment};
 use std::sync::Arc;
 
+/// Estimates the number of distinct keys of the given segments, by merging their key sketches
+///
+/// Segments without a key sketch are counted by their key count.
+pub fn distinct_key_estimate<'a, I: IntoIterator<Item = &'a Arc<Segment>>>(segments: I) -> u64 {
+    let mut sketch = HyperLogLog::default();
+    let mut unsketched_key_count = 0;
+
+    for segment in segments {
+        if let Some(key_sketch) = &segment.metadata.key_sketch {
+            sketch.merge(key_sketch);
+        } else {
+            unsketched_key_count += segment.metadata.key_count;
+        }
+    }
+
+    sketch.estimate() + unsketched_key_count
+}
+
 /// Level of an LSM-tree
 #[derive(Clone, Debug)]
 pub struct Level {
@@ -106,6 +124,36 @@ impl Level {
         self.segments.iter().map(|x| x.metadata.file_size).sum()
     }
 
+    /// Estimates the number of distinct keys in the level.
+    pub fn distinct_key_estimate(&self) -> u64 {
+        distinct_key_estimate(&self.segments)
+    }
+
+    /// Returns the number of tombstones in the level.
+    pub fn tombstone_count(&self) -> u64 {
+        self.segments
+            .iter()
+            .map(|x| x.metadata.tombstone_count)
+            .sum()
+    }
+
+    /// Returns the ratio of tombstones to items in the level,
+    /// or 0.0 if the level is empty.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn tombstone_ratio(&self) -> f64 {
+        let item_count = self
+            .segments
+            .iter()
+            .map(|x| x.metadata.item_count)
+            .sum::<u64>();
+
+        if item_count == 0 {
+            return 0.0;
+        }
+
+        self.tombstone_count() as f64 / item_count as f64
+    }
+
     /// Checks if the level is disjoint and caches the result in `is_disjoint`.
     fn set_disjoint_flag(&mut self) {
         let ranges = self
@@ -135,14 +183,14 @@ impl Level {
     /// # Panics
     ///
     /// Panics if the level is not disjoint.
-    pub fn get_segment_containing_key<K: AsRef<[u8]>>(&self, key: K) -> Option<Arc<Segment>> {
+    pub fn get_segment_containing_key<K: AsRef<[u8]>>(&self, key: K) -> Option<&Arc<Segment>> {
         assert!(self.is_disjoint, "level is not disjoint");
 
         let idx = self
             .segments
             .partition_point(|x| &*x.metadata.key_range.1 < key.as_ref());
 
-        self.segments.get(idx).cloned()
+        self.segments.get(idx)
     }
 }
 
@@ -204,9 +252,19 @@ mod tests {
                 range_tombstone_count: 0,
                 uncompressed_size: 0,
                 seqnos: (0, 0),
+                key_sketch: None,
+                prefix_fences: None,
+                one_level_index: false,
             },
             block_cache,
 
+            seqno_index: None,
+            tombstone_index: None,
+
+            obsolete_path: std::sync::OnceLock::new(),
+
+            seqno_offset: 0,
+
             #[cfg(feature = "bloom")]
             bloom_filter: BloomFilter::with_fp_rate(1, 0.1),
         })
@@ -7,9 +7,10 @@ pub(crate) mod level;
 
 use crate::{
     coding::{DecodeError, Encode, EncodeError},
-    file::{rewrite_atomic, MAGIC_BYTES},
+    file::{rewrite_atomic, MAGIC_BYTES, SEGMENTS_FOLDER},
+    key_range::KeyRange,
     segment::{meta::SegmentId, Segment},
-    HashMap, HashSet,
+    HashMap, HashSet, SeqNo, UserKey,
 };
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use iter::LevelManifestIterator;
@@ -17,11 +18,38 @@ use level::Level;
 use std::{
     io::{Cursor, Read, Write},
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Weak},
 };
 
 pub type HiddenSet = HashSet<SegmentId>;
 
+/// In-flight compaction job
+///
+/// The input segments of a job are hidden until the job is finished.
+#[derive(Clone, Debug)]
+pub struct CompactionJob {
+    /// Input segment IDs
+    pub segment_ids: Vec<SegmentId>,
+
+    /// Levels the job reads from or writes into
+    pub levels: Vec<u8>,
+
+    /// Key range of the input segments
+    pub key_range: KeyRange,
+}
+
+/// References to segment files that are shared outside of the levels
+///
+/// A segment file that is removed from the levels (e.g. by compaction)
+/// is only deleted once no reader (e.g. a long-running iterator, or acquired
+/// [`crate::SegmentFiles`]) holds on to the segment anymore.
+#[derive(Debug, Default)]
+struct SegmentRefs {
+    /// Segments that are not part of the levels anymore, but whose files are kept
+    /// until the last reader drops the segment, with their file sizes
+    obsolete: Vec<(Weak<Segment>, u64)>,
+}
+
 /// Represents the levels of a log-structured merge tree.
 pub struct LevelManifest {
     /// Path of level manifest file
@@ -36,6 +64,12 @@ pub struct LevelManifest {
     /// While consuming segments (because of compaction) they will not appear in the list of segments
     /// as to not cause conflicts between multiple compaction threads (compacting the same segments)
     hidden_set: HiddenSet,
+
+    /// Registry of in-flight compaction jobs, which own the hidden segments
+    jobs: Vec<CompactionJob>,
+
+    /// Shared references to segment files
+    segment_refs: SegmentRefs,
 }
 
 impl std::fmt::Display for LevelManifest {
@@ -119,6 +153,8 @@ impl LevelManifest {
                 10,
                 xxhash_rust::xxh3::Xxh3Builder::new(),
             ),
+            jobs: Vec::new(),
+            segment_refs: SegmentRefs::default(),
         };
         Self::write_to_disk(path, &levels.levels)?;
 
@@ -129,14 +165,46 @@ impl LevelManifest {
         path: P,
     ) -> crate::Result<Vec<Vec<SegmentId>>> {
         let mut level_manifest = Cursor::new(std::fs::read(&path)?);
+        Self::read_levels(&mut level_manifest)
+    }
 
+    /// Loads the seqno offsets of segments that were linked by [`crate::Tree::absorb`],
+    /// see [`Segment::seqno_offset`].
+    pub(crate) fn load_seqno_offsets<P: AsRef<Path>>(
+        path: P,
+    ) -> crate::Result<HashMap<SegmentId, SeqNo>> {
+        let bytes = std::fs::read(&path)?;
+        let mut level_manifest = Cursor::new(bytes.as_slice());
+        Self::read_levels(&mut level_manifest)?;
+
+        let mut seqno_offsets = HashMap::default();
+
+        // NOTE: Level manifests without seqno offsets end after the levels
+        if level_manifest.position() == bytes.len() as u64 {
+            return Ok(seqno_offsets);
+        }
+
+        let count = level_manifest.read_u32::<BigEndian>()?;
+
+        for _ in 0..count {
+            let segment_id = level_manifest.read_u64::<BigEndian>()?;
+            let seqno_offset = level_manifest.read_u64::<BigEndian>()?;
+            seqno_offsets.insert(segment_id, seqno_offset);
+        }
+
+        Ok(seqno_offsets)
+    }
+
+    fn read_levels<R: Read>(level_manifest: &mut R) -> crate::Result<Vec<Vec<SegmentId>>> {
         // Check header
         let mut magic = [0u8; MAGIC_BYTES.len()];
         level_manifest.read_exact(&mut magic)?;
 
         if magic != MAGIC_BYTES {
-            return Err(crate::Error::Decode(DecodeError::InvalidHeader(
+            return Err(crate::Error::Decode(DecodeError::invalid_header(
                 "LevelManifest",
+                &MAGIC_BYTES,
+                &magic,
             )));
         }
 
@@ -205,11 +273,16 @@ impl LevelManifest {
                 10,
                 xxhash_rust::xxh3::Xxh3Builder::new(),
             ),
+            jobs: Vec::new(),
+            segment_refs: SegmentRefs::default(),
             path: path.as_ref().to_path_buf(),
         })
     }
 
-    pub(crate) fn write_to_disk<P: AsRef<Path>>(path: P, levels: &Vec<Level>) -> crate::Result<()> {
+    pub(crate) fn write_to_disk<P: AsRef<Path>, L: Encode>(
+        path: P,
+        levels: &L,
+    ) -> crate::Result<()> {
         let path = path.as_ref();
 
         log::trace!("Writing level manifest to {path:?}",);
@@ -223,6 +296,7 @@ impl LevelManifest {
         //
         // a) truncating is not an option, because for a short moment, the file is empty
         // b) just overwriting corrupts the file content
+        fail_point!(crate::failpoints::MANIFEST_COMMIT);
         rewrite_atomic(path, &serialized)?;
 
         Ok(())
@@ -239,7 +313,36 @@ impl LevelManifest {
         f(&mut working_copy);
 
         Self::write_to_disk(&self.path, &working_copy)?;
-        self.levels = working_copy;
+
+        let live_segment_ids = working_copy
+            .iter()
+            .flat_map(|level| level.iter())
+            .map(|segment| segment.metadata.id)
+            .collect::<HashSet<_>>();
+
+        let old_levels = std::mem::replace(&mut self.levels, working_copy);
+
+        // NOTE: Readers may still hold on to removed segments,
+        // so their files are only deleted once the last reference is dropped
+        let segments_folder = self.path.with_file_name(SEGMENTS_FOLDER);
+
+        self.segment_refs
+            .obsolete
+            .retain(|(segment, _)| segment.strong_count() > 0);
+
+        for segment in old_levels
+            .iter()
+            .flat_map(|level| level.iter())
+            .filter(|segment| !live_segment_ids.contains(&segment.metadata.id))
+        {
+            let segment_file_path = segments_folder.join(segment.metadata.id.to_string());
+
+            if segment.obsolete_path.set(segment_file_path).is_ok() {
+                self.segment_refs
+                    .obsolete
+                    .push((Arc::downgrade(segment), segment.metadata.file_size));
+            }
+        }
 
         log::trace!("Swapped level manifest to:\n{self}");
 
@@ -286,6 +389,27 @@ impl LevelManifest {
         self.levels.iter().all(|x| x.is_disjoint)
     }
 
+    /// Returns `true` if no two segments of the tree overlap, across all levels.
+    ///
+    /// Unlike [`LevelManifest::is_disjoint`], which checks every level by itself,
+    /// this allows reading all segments as if they were in a single level.
+    pub(crate) fn is_disjoint_across_levels(&self) -> bool {
+        if !self.is_disjoint() {
+            return false;
+        }
+
+        let mut key_ranges = self
+            .iter()
+            .map(|segment| &segment.metadata.key_range)
+            .collect::<Vec<_>>();
+        key_ranges.sort_by(|a, b| a.0.cmp(&b.0));
+
+        key_ranges.windows(2).all(|pair| match pair {
+            [a, b] => !a.overlaps_with_key_range(b),
+            _ => true,
+        })
+    }
+
     /// Returns `true` if there are no segments
     #[must_use]
     pub fn is_empty(&self) -> bool {
@@ -377,20 +501,123 @@ impl LevelManifest {
         output
     }
 
+    /// Returns the in-flight compaction jobs.
+    #[must_use]
+    pub fn compaction_jobs(&self) -> &[CompactionJob] {
+        &self.jobs
+    }
+
+    /// Returns `true` if an in-flight compaction job touches any of the given levels
+    /// in the given key range.
+    ///
+    /// A compaction that does not conflict with any in-flight job can run concurrently,
+    /// because the jobs write disjoint key ranges into their destination levels.
+    #[must_use]
+    pub fn is_busy(&self, levels: &[u8], key_range: &KeyRange) -> bool {
+        self.jobs.iter().any(|job| {
+            job.levels.iter().any(|idx| levels.contains(idx))
+                && job.key_range.overlaps_with_key_range(key_range)
+        })
+    }
+
+    /// Returns `true` if the segment is currently being compacted.
+    #[must_use]
+    pub fn is_hidden(&self, segment_id: SegmentId) -> bool {
+        self.hidden_set.contains(&segment_id)
+    }
+
+    /// Returns the size of the files of segments that are not part of the levels anymore,
+    /// but are kept because they are still read or referenced.
+    #[must_use]
+    pub fn obsolete_segment_bytes(&self) -> u64 {
+        self.segment_refs
+            .obsolete
+            .iter()
+            .filter(|(segment, _)| segment.strong_count() > 0)
+            .map(|(_, file_size)| file_size)
+            .sum()
+    }
+
     pub(crate) fn show_segments(&mut self, keys: &[SegmentId]) {
         for key in keys {
             self.hidden_set.remove(key);
         }
+
+        self.jobs
+            .retain(|job| !job.segment_ids.iter().any(|id| keys.contains(id)));
     }
 
-    pub(crate) fn hide_segments(&mut self, keys: &[SegmentId]) {
+    /// Hides the input segments of a compaction job, and registers the job,
+    /// until the segments are shown again.
+    pub(crate) fn start_job(&mut self, keys: &[SegmentId], dest_level: Option<u8>) {
+        let mut levels = vec![];
+        let mut key_range: Option<(UserKey, UserKey)> = None;
+
+        for (idx, level) in self.levels.iter().enumerate() {
+            for segment in level.iter().filter(|x| keys.contains(&x.metadata.id)) {
+                // NOTE: Level count is u8
+                #[allow(clippy::cast_possible_truncation)]
+                levels.push(idx as u8);
+
+                let (min, max) = &*segment.metadata.key_range;
+
+                key_range = Some(match key_range {
+                    Some((lo, hi)) => (lo.min(min.clone()), hi.max(max.clone())),
+                    None => (min.clone(), max.clone()),
+                });
+            }
+        }
+
+        levels.extend(dest_level);
+        levels.sort_unstable();
+        levels.dedup();
+
         for key in keys {
             self.hidden_set.insert(*key);
         }
+
+        if let Some(key_range) = key_range {
+            self.jobs.push(CompactionJob {
+                segment_ids: keys.to_vec(),
+                levels,
+                key_range: KeyRange::new(key_range),
+            });
+        }
     }
 }
 
 impl Encode for Vec<Level> {
+    fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        self.iter()
+            .map(|level| level.ids().collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+            .encode_into(writer)?;
+
+        let seqno_offsets = self
+            .iter()
+            .flat_map(|level| level.iter())
+            .filter(|segment| segment.seqno_offset > 0)
+            .map(|segment| (segment.metadata.id, segment.seqno_offset))
+            .collect::<Vec<_>>();
+
+        // NOTE: The seqno offsets are appended after the levels, and only if there are any,
+        // so level manifests of trees that never absorbed a tree do not change
+        if !seqno_offsets.is_empty() {
+            // NOTE: "Truncation" is OK, because there are never 4 billion segments in a tree
+            #[allow(clippy::cast_possible_truncation)]
+            writer.write_u32::<BigEndian>(seqno_offsets.len() as u32)?;
+
+            for (segment_id, seqno_offset) in seqno_offsets {
+                writer.write_u64::<BigEndian>(segment_id)?;
+                writer.write_u64::<BigEndian>(seqno_offset)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Encode for Vec<Vec<SegmentId>> {
     fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
         // Write header
         writer.write_all(&MAGIC_BYTES)?;
@@ -402,10 +629,10 @@ impl Encode for Vec<Level> {
         for level in self {
             // NOTE: "Truncation" is OK, because there are never 4 billion segments in a tree, I hope
             #[allow(clippy::cast_possible_truncation)]
-            writer.write_u32::<BigEndian>(level.segments.len() as u32)?;
+            writer.write_u32::<BigEndian>(level.len() as u32)?;
 
-            for segment in &level.segments {
-                writer.write_u64::<BigEndian>(segment.metadata.id)?;
+            for &segment_id in level {
+                writer.write_u64::<BigEndian>(segment_id)?;
             }
         }
 
@@ -416,7 +643,11 @@ impl Encode for Vec<Level> {
 #[cfg(test)]
 #[allow(clippy::expect_used)]
 mod tests {
-    use crate::{coding::Encode, level_manifest::LevelManifest, AbstractTree};
+    use crate::{
+        coding::Encode,
+        level_manifest::{LevelManifest, SegmentRefs},
+        AbstractTree,
+    };
     use std::collections::HashSet;
     use test_log::test;
 
@@ -466,6 +697,8 @@ mod tests {
     fn level_manifest_raw_empty() -> crate::Result<()> {
         let levels = LevelManifest {
             hidden_set: HashSet::default(),
+            jobs: Vec::new(),
+            segment_refs: SegmentRefs::default(),
             levels: Vec::default(),
             path: "a".into(),
         };
@@ -103,6 +103,16 @@
 pub(crate) type HashMap<K, V> = std::collections::HashMap<K, V, xxhash_rust::xxh3::Xxh3Builder>;
 pub(crate) type HashSet<K> = std::collections::HashSet<K, xxhash_rust::xxh3::Xxh3Builder>;
 
+/// Evaluates a fail point, returning early with an I/O error if it is enabled
+///
+/// Compiles to nothing without the `failpoints` feature.
+macro_rules! fail_point {
+    ($name:expr) => {
+        #[cfg(feature = "failpoints")]
+        crate::failpoints::eval($name)?;
+    };
+}
+
 macro_rules! fail_iter {
     ($e:expr) => {
         match $e {
@@ -129,17 +139,31 @@ pub mod bloom;
 pub mod coding;
 pub mod compaction;
 mod config;
+mod durability;
 
 #[doc(hidden)]
 pub mod descriptor_table;
 
 mod either;
+
+#[doc(hidden)]
+pub mod encryption;
+
 mod error;
 // mod export;
 
+mod fadvise;
+
+#[cfg(feature = "failpoints")]
+pub mod failpoints;
+
 #[doc(hidden)]
 pub mod file;
 
+mod hyperloglog;
+
+mod inspect;
+
 mod key;
 mod key_range;
 
@@ -147,12 +171,17 @@ mod key_range;
 pub mod level_manifest;
 
 mod manifest;
+mod memory_tree;
 mod memtable;
 
+pub mod metrics;
+
 #[doc(hidden)]
 pub mod merge;
 
+mod mlock;
 mod mvcc_stream;
+mod ops_log;
 mod path;
 
 #[doc(hidden)]
@@ -161,6 +190,8 @@ pub mod range;
 #[doc(hidden)]
 pub mod segment;
 
+mod read_options;
+mod read_pool;
 mod seqno;
 mod snapshot;
 
@@ -169,6 +200,7 @@ pub mod stop_signal;
 
 mod time;
 mod tree;
+mod uuid;
 mod value;
 mod version;
 
@@ -183,24 +215,43 @@ pub use {
     value::InternalValue,
 };
 
+#[cfg(feature = "encryption")]
+pub use {
+    encryption::{BlockCipher, BlockContext},
+    inspect::inspect_with_cipher,
+};
+
 pub use {
     block_cache::BlockCache,
     coding::{DecodeError, EncodeError},
     config::{Config, TreeType},
-    error::{Error, Result},
+    durability::SyncMode,
+    error::{Error, ErrorContext, Operation, Result},
+    hyperloglog::HyperLogLog,
+    inspect::{inspect, LevelInspection, TreeInspection},
+    key_range::KeyRange,
+    memory_tree::MemoryTree,
     memtable::Memtable,
     r#abstract::AbstractTree,
-    segment::{meta::CompressionType, Segment},
+    read_options::ReadOptions,
+    segment::{meta::CompressionType, writer::BloomConstructionPolicy, Segment},
     seqno::SequenceNumberCounter,
     snapshot::Snapshot,
-    tree::Tree,
+    tree::{
+        AmplificationReport, LevelStats, ParRange, ScanOrder, SegmentFiles, Tree, TuningAdvice,
+        TuningReport,
+    },
+    uuid::Uuid,
     value::{SeqNo, UserKey, UserValue, ValueType},
     version::Version,
 };
 
 pub use any_tree::AnyTree;
 
-pub use blob_tree::BlobTree;
+pub use blob_tree::{
+    AgeStrategy, BlobFileStats, BlobTree, FragmentationReport, SharedValueLog,
+    StreamingValueReader, StreamingValueWriter,
+};
 
 pub use value_log::{
     BlobCache, GcReport, GcStrategy, Slice, SpaceAmpStrategy, StaleThresholdStrategy,
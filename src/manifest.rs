@@ -6,16 +6,161 @@ use crate::{
     coding::{Decode, DecodeError, Encode, EncodeError},
     file::MAGIC_BYTES,
     segment::meta::TableType,
-    TreeType, Version,
+    uuid::Uuid,
+    CompressionType, Config, TreeType, Version,
 };
-use byteorder::{ReadBytesExt, WriteBytesExt};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::io::Write;
 
+/// Format version of manifests written by older versions, which end after the level count
+const LEGACY_FORMAT_VERSION: u8 = 0;
+
+/// Format version of the manifest, which follows the level count
+///
+/// Version 1 persists all tree settings.
+const FORMAT_VERSION: u8 = 1;
+
+/// Restores a persisted setting, unless it was explicitly configured
+fn restore_default<T>(explicit: bool, configured: &mut T, persisted: Option<T>) {
+    if let (false, Some(persisted)) = (explicit, persisted) {
+        *configured = persisted;
+    }
+}
+
+/// Restores a persisted setting, or checks it against the configured one,
+/// if it was explicitly configured
+fn restore_setting<T: Copy + PartialEq + std::fmt::Debug>(
+    name: &'static str,
+    explicit: bool,
+    configured: &mut T,
+    persisted: Option<T>,
+) -> crate::Result<()> {
+    let Some(persisted) = persisted else {
+        return Ok(());
+    };
+
+    if explicit && *configured != persisted {
+        log::error!(
+            "Configured {name} ({configured:?}) does not match the {name} the tree was created with ({persisted:?})"
+        );
+        return Err(crate::Error::ConfigMismatch(name));
+    }
+
+    *configured = persisted;
+
+    Ok(())
+}
+
 pub struct Manifest {
     pub(crate) version: Version,
     pub(crate) tree_type: TreeType,
     pub(crate) table_type: TableType,
     pub(crate) level_count: u8,
+
+    /// Key-value separation threshold that was used when creating the tree
+    ///
+    /// Manifests written by older versions do not contain the threshold.
+    pub(crate) blob_separation_threshold: Option<u32>,
+
+    /// Compression that is used for blobs in the value log
+    ///
+    /// Manifests written by older versions do not contain the blob compression.
+    pub(crate) blob_compression: Option<CompressionType>,
+
+    /// Compression that is used for blocks
+    ///
+    /// Manifests written by older versions do not contain the following settings.
+    pub(crate) compression: Option<CompressionType>,
+
+    /// Data block size
+    pub(crate) data_block_size: Option<u32>,
+
+    /// Index block size
+    pub(crate) index_block_size: Option<u32>,
+
+    /// Bloom filter bits per key
+    pub(crate) bloom_bits_per_key: Option<i8>,
+
+    /// Unique identifier of the tree
+    ///
+    /// Manifests written by older versions do not contain an identifier.
+    pub(crate) uuid: Option<Uuid>,
+}
+
+impl Manifest {
+    /// Creates the manifest of a new tree, persisting its config
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            version: Version::V2,
+            level_count: config.level_count,
+            tree_type: config.tree_type,
+            table_type: TableType::Block,
+            blob_separation_threshold: Some(config.blob_file_separation_threshold),
+            blob_compression: Some(config.blob_compression),
+            compression: Some(config.compression),
+            data_block_size: Some(config.data_block_size),
+            index_block_size: Some(config.index_block_size),
+            bloom_bits_per_key: Some(config.bloom_bits_per_key),
+            uuid: Some(
+                config
+                    .deterministic_seed
+                    .map_or_else(Uuid::new_v4, Uuid::from_seed),
+            ),
+        }
+    }
+
+    /// Restores the persisted settings into the config
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an explicitly configured blob separation threshold
+    /// or blob compression does not match the setting the tree was created with.
+    pub(crate) fn restore_config(&self, config: &mut Config) -> crate::Result<()> {
+        config.level_count = self.level_count;
+        config.table_type = self.table_type;
+        config.tree_type = self.tree_type;
+
+        let explicit = config.explicit;
+
+        // NOTE: Every segment records its own compression, block layout and filter,
+        // so these settings may be changed, the persisted settings are only used as defaults
+        restore_default(
+            explicit.compression,
+            &mut config.compression,
+            self.compression,
+        );
+        restore_default(
+            explicit.data_block_size,
+            &mut config.data_block_size,
+            self.data_block_size,
+        );
+        restore_default(
+            explicit.index_block_size,
+            &mut config.index_block_size,
+            self.index_block_size,
+        );
+        restore_default(
+            explicit.bloom_bits_per_key,
+            &mut config.bloom_bits_per_key,
+            self.bloom_bits_per_key,
+        );
+
+        restore_setting(
+            "blob compression",
+            explicit.blob_compression,
+            &mut config.blob_compression,
+            self.blob_compression,
+        )?;
+
+        restore_setting(
+            "blob separation threshold",
+            explicit.blob_separation_threshold,
+            &mut config.blob_file_separation_threshold,
+            self.blob_separation_threshold,
+        )?;
+
+        Ok(())
+    }
 }
 
 impl Encode for Manifest {
@@ -24,6 +169,39 @@ impl Encode for Manifest {
         writer.write_u8(self.tree_type.into())?;
         writer.write_u8(self.table_type.into())?;
         writer.write_u8(self.level_count)?;
+
+        // NOTE: Manifests without settings are written in the legacy format
+        let (
+            Some(threshold),
+            Some(blob_compression),
+            Some(compression),
+            Some(data_block_size),
+            Some(index_block_size),
+            Some(bloom_bits_per_key),
+            Some(uuid),
+        ) = (
+            self.blob_separation_threshold,
+            self.blob_compression,
+            self.compression,
+            self.data_block_size,
+            self.index_block_size,
+            self.bloom_bits_per_key,
+            self.uuid,
+        )
+        else {
+            return Ok(());
+        };
+
+        writer.write_u8(FORMAT_VERSION)?;
+
+        writer.write_u32::<BigEndian>(threshold)?;
+        blob_compression.encode_into(writer)?;
+        compression.encode_into(writer)?;
+        writer.write_u32::<BigEndian>(data_block_size)?;
+        writer.write_u32::<BigEndian>(index_block_size)?;
+        writer.write_i8(bloom_bits_per_key)?;
+        uuid.encode_into(writer)?;
+
         Ok(())
     }
 }
@@ -34,7 +212,11 @@ impl Decode for Manifest {
         reader.read_exact(&mut header)?;
 
         if header != MAGIC_BYTES {
-            return Err(crate::DecodeError::InvalidHeader("Manifest"));
+            return Err(crate::DecodeError::invalid_header(
+                "Manifest",
+                &MAGIC_BYTES,
+                &header,
+            ));
         }
 
         let version = *header.get(3).expect("header must be size 4");
@@ -44,9 +226,45 @@ impl Decode for Manifest {
         let table_type = reader.read_u8()?;
         let level_count = reader.read_u8()?;
 
+        // NOTE: Manifests written by older versions end after the level count
+        let format_version = match reader.read_u8() {
+            Ok(format_version) => format_version,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => LEGACY_FORMAT_VERSION,
+            Err(e) => return Err(e.into()),
+        };
+
+        let (
+            blob_separation_threshold,
+            blob_compression,
+            compression,
+            data_block_size,
+            index_block_size,
+            bloom_bits_per_key,
+            uuid,
+        ) = match format_version {
+            LEGACY_FORMAT_VERSION => (None, None, None, None, None, None, None),
+            FORMAT_VERSION => (
+                Some(reader.read_u32::<BigEndian>()?),
+                Some(CompressionType::decode_from(reader)?),
+                Some(CompressionType::decode_from(reader)?),
+                Some(reader.read_u32::<BigEndian>()?),
+                Some(reader.read_u32::<BigEndian>()?),
+                Some(reader.read_i8()?),
+                Some(Uuid::decode_from(reader)?),
+            ),
+            _ => return Err(DecodeError::InvalidVersion),
+        };
+
         Ok(Self {
             version,
             level_count,
+            blob_separation_threshold,
+            blob_compression,
+            compression,
+            data_block_size,
+            index_block_size,
+            bloom_bits_per_key,
+            uuid,
             tree_type: tree_type
                 .try_into()
                 .map_err(|()| DecodeError::InvalidTag(("TreeType", tree_type)))?,
@@ -56,3 +274,123 @@ impl Decode for Manifest {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn manifest_roundtrip() -> crate::Result<()> {
+        let before = Manifest {
+            version: Version::V2,
+            tree_type: TreeType::Blob,
+            table_type: TableType::Block,
+            level_count: 7,
+            blob_separation_threshold: Some(1_024),
+            blob_compression: Some(CompressionType::None),
+            compression: Some(CompressionType::None),
+            data_block_size: Some(8_192),
+            index_block_size: Some(2_048),
+            bloom_bits_per_key: Some(-1),
+            uuid: Some(Uuid::new_v4()),
+        };
+
+        let bytes = before.encode_into_vec()?;
+        let after = Manifest::decode_from(&mut bytes.as_slice())?;
+
+        assert_eq!(7, after.level_count);
+        assert_eq!(TreeType::Blob, after.tree_type);
+        assert_eq!(Some(1_024), after.blob_separation_threshold);
+        assert_eq!(Some(CompressionType::None), after.blob_compression);
+        assert_eq!(Some(CompressionType::None), after.compression);
+        assert_eq!(Some(8_192), after.data_block_size);
+        assert_eq!(Some(2_048), after.index_block_size);
+        assert_eq!(Some(-1), after.bloom_bits_per_key);
+        assert_eq!(before.uuid, after.uuid);
+
+        Ok(())
+    }
+
+    #[test]
+    fn manifest_without_separation_threshold() -> crate::Result<()> {
+        let before = Manifest {
+            version: Version::V2,
+            tree_type: TreeType::Standard,
+            table_type: TableType::Block,
+            level_count: 7,
+            blob_separation_threshold: None,
+            blob_compression: None,
+            compression: None,
+            data_block_size: None,
+            index_block_size: None,
+            bloom_bits_per_key: None,
+            uuid: None,
+        };
+
+        let bytes = before.encode_into_vec()?;
+        let after = Manifest::decode_from(&mut bytes.as_slice())?;
+
+        assert_eq!(None, after.blob_separation_threshold);
+        assert_eq!(None, after.blob_compression);
+        assert_eq!(None, after.compression);
+        assert_eq!(None, after.uuid);
+
+        Ok(())
+    }
+
+    #[test]
+    fn manifest_short_read() -> crate::Result<()> {
+        let mut bytes = Manifest::new(&Config::default()).encode_into_vec()?;
+        bytes.pop();
+
+        assert!(matches!(
+            Manifest::decode_from(&mut bytes.as_slice()),
+            Err(DecodeError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn manifest_unknown_format_version() -> crate::Result<()> {
+        let mut bytes = Manifest::new(&Config::default()).encode_into_vec()?;
+        bytes[MAGIC_BYTES.len() + 3] = FORMAT_VERSION + 1;
+
+        assert!(matches!(
+            Manifest::decode_from(&mut bytes.as_slice()),
+            Err(DecodeError::InvalidVersion)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn manifest_restore_config() -> crate::Result<()> {
+        let manifest = Manifest::new(&Config::default().data_block_size(8_192));
+
+        let mut config = Config::default();
+        manifest.restore_config(&mut config)?;
+        assert_eq!(8_192, config.data_block_size);
+
+        let mut config = Config::default().data_block_size(8_192);
+        manifest.restore_config(&mut config)?;
+        assert_eq!(8_192, config.data_block_size);
+
+        let mut config = Config::default().data_block_size(16_384);
+        manifest.restore_config(&mut config)?;
+        assert_eq!(16_384, config.data_block_size);
+
+        let manifest = Manifest::new(&Config::default().index_block_size(8_192));
+
+        let mut config = Config::default();
+        manifest.restore_config(&mut config)?;
+        assert_eq!(8_192, config.index_block_size);
+
+        let mut config = Config::default().index_block_size(16_384);
+        manifest.restore_config(&mut config)?;
+        assert_eq!(16_384, config.index_block_size);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,382 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    compaction::CompactionStrategy,
+    config::{Config, TreeType},
+    memtable::Memtable,
+    range::to_owned_bounds,
+    segment::block::ItemSize,
+    tree::inner::MemtableId,
+    value::InternalValue,
+    AbstractTree, KvPair, Segment, SegmentId, SeqNo, Snapshot, UserKey, UserValue, ValueType,
+};
+use std::{
+    collections::BTreeMap,
+    ops::{Bound, RangeBounds},
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
+};
+
+type BoxedIter<T> = Box<dyn DoubleEndedIterator<Item = crate::Result<T>> + 'static>;
+
+/// All versions of a key, by seqno
+type Versions = BTreeMap<SeqNo, (ValueType, UserValue)>;
+
+/// All versions of all keys
+type Items = BTreeMap<UserKey, Versions>;
+
+#[allow(clippy::module_name_repetitions)]
+pub struct MemoryTreeInner {
+    config: Config,
+
+    items: RwLock<Items>,
+
+    /// Approximate size of all items in bytes
+    size: AtomicU32,
+
+    /// Only used to hand out write locks, see [`AbstractTree::lock_active_memtable`]
+    ///
+    /// Items are never stored in it.
+    memtable: RwLock<Memtable>,
+
+    segment_id_counter: AtomicU64,
+}
+
+/// In-memory reference implementation of [`AbstractTree`]
+///
+/// Every version of every item is kept in a `BTreeMap`, and reads are resolved
+/// by looking up the latest version below the snapshot seqno - without any memtables,
+/// segments or compactions involved.
+///
+/// It is intended as a drop-in for unit tests of applications built on [`AbstractTree`],
+/// and as an executable specification for differential testing against [`crate::Tree`].
+///
+/// Nothing is persisted, so flushes & compactions are no-ops.
+/// Weak tombstones behave like regular tombstones, because versions are never
+/// merged away.
+///
+/// # Examples
+///
+/// ```
+/// use lsm_tree::{AbstractTree, MemoryTree};
+///
+/// let tree = MemoryTree::new();
+///
+/// tree.insert("a", "abc", 0);
+/// let snapshot = tree.snapshot(1);
+///
+/// tree.remove("a", 1);
+///
+/// assert!(!tree.contains_key("a")?);
+/// assert!(snapshot.contains_key("a")?);
+/// #
+/// # Ok::<(), lsm_tree::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct MemoryTree(Arc<MemoryTreeInner>);
+
+impl Default for MemoryTree {
+    fn default() -> Self {
+        Self::with_config(Config::default())
+    }
+}
+
+impl MemoryTree {
+    /// Creates an empty in-memory tree.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty in-memory tree that reports the given config.
+    ///
+    /// The config is only returned by [`AbstractTree::tree_config`], no files are ever created.
+    #[must_use]
+    pub fn with_config(config: Config) -> Self {
+        Self(Arc::new(MemoryTreeInner {
+            config,
+            items: RwLock::default(),
+            size: AtomicU32::default(),
+            memtable: RwLock::default(),
+            segment_id_counter: AtomicU64::default(),
+        }))
+    }
+
+    /// Read-locks the items
+    fn read_lock_items(&self) -> RwLockReadGuard<'_, Items> {
+        self.0.items.read().expect("lock is poisoned")
+    }
+
+    /// Write-locks the items for exclusive access
+    fn lock_items(&self) -> RwLockWriteGuard<'_, Items> {
+        self.0.items.write().expect("lock is poisoned")
+    }
+
+    fn append(&self, item: InternalValue) -> (u32, u32) {
+        // NOTE: We know values are limited to 32-bit length
+        #[allow(clippy::cast_possible_truncation)]
+        let item_size = item.size() as u32;
+
+        let size_before = self.0.size.fetch_add(item_size, Ordering::AcqRel);
+
+        self.lock_items()
+            .entry(item.key.user_key)
+            .or_default()
+            .insert(item.key.seqno, (item.key.value_type, item.value));
+
+        (item_size, size_before.saturating_add(item_size))
+    }
+
+    /// Returns the latest version that is visible at the given seqno (exclusive).
+    fn visible_version(
+        versions: &Versions,
+        seqno: Option<SeqNo>,
+    ) -> Option<(SeqNo, ValueType, UserValue)> {
+        let version = match seqno {
+            Some(seqno) => versions.range(..seqno).next_back(),
+            None => versions.iter().next_back(),
+        };
+
+        version.map(|(seqno, (value_type, value))| (*seqno, *value_type, value.clone()))
+    }
+
+    fn create_range<K: AsRef<[u8]>, R: RangeBounds<K>>(
+        &self,
+        range: &R,
+        seqno: Option<SeqNo>,
+        index: Option<Arc<Memtable>>,
+    ) -> BoxedIter<KvPair> {
+        let bounds = to_owned_bounds(range);
+
+        let mut visible = self
+            .read_lock_items()
+            .iter()
+            .filter(|(key, _)| bounds.contains(*key))
+            .filter_map(|(key, versions)| {
+                Self::visible_version(versions, seqno).map(|version| (key.clone(), version))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        // NOTE: Items of the index are not filtered by seqno, and shadow
+        // older versions of the tree, like in [`crate::Tree`]
+        if let Some(index) = index {
+            for item in index.iter().filter(|x| bounds.contains(&x.key.user_key)) {
+                let is_newer = visible
+                    .get(&item.key.user_key)
+                    .map_or(true, |(seqno, _, _)| item.key.seqno > *seqno);
+
+                if is_newer {
+                    visible.insert(
+                        item.key.user_key,
+                        (item.key.seqno, item.key.value_type, item.value),
+                    );
+                }
+            }
+        }
+
+        Box::new(
+            visible
+                .into_iter()
+                .filter(|(_, (_, value_type, _))| *value_type == ValueType::Value)
+                .map(|(key, (_, _, value))| Ok((key, value)))
+                .collect::<Vec<_>>()
+                .into_iter(),
+        )
+    }
+}
+
+impl AbstractTree for MemoryTree {
+    fn verify(&self) -> crate::Result<usize> {
+        Ok(0)
+    }
+
+    fn flush_memtable(
+        &self,
+        _: SegmentId,
+        _: &Arc<Memtable>,
+        _: SeqNo,
+    ) -> crate::Result<Option<Arc<Segment>>> {
+        Ok(None)
+    }
+
+    /// Adds all items of the segments to the tree.
+    fn register_segments(&self, segments: &[Arc<Segment>]) -> crate::Result<()> {
+        for segment in segments {
+            for item in segment.iter() {
+                self.append(item?);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn lock_active_memtable(&self) -> RwLockWriteGuard<'_, Memtable> {
+        self.0.memtable.write().expect("lock is poisoned")
+    }
+
+    /// Adds all items of the memtable to the tree.
+    fn set_active_memtable(&self, memtable: Memtable) {
+        for item in memtable.iter() {
+            self.append(item);
+        }
+    }
+
+    fn sealed_memtable_count(&self) -> usize {
+        0
+    }
+
+    /// Adds all items of the memtable to the tree.
+    fn add_sealed_memtable(&self, _: MemtableId, memtable: Arc<Memtable>) {
+        for item in memtable.iter() {
+            self.append(item);
+        }
+    }
+
+    fn compact(&self, _: Arc<dyn CompactionStrategy>, _: SeqNo) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn get_next_segment_id(&self) -> SegmentId {
+        self.0.segment_id_counter.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn tree_config(&self) -> &Config {
+        &self.0.config
+    }
+
+    fn active_memtable_size(&self) -> u32 {
+        self.0.size.load(Ordering::Acquire)
+    }
+
+    fn tree_type(&self) -> TreeType {
+        TreeType::Standard
+    }
+
+    fn rotate_memtable(&self) -> Option<(MemtableId, Arc<Memtable>)> {
+        None
+    }
+
+    fn segment_count(&self) -> usize {
+        0
+    }
+
+    fn first_level_segment_count(&self) -> usize {
+        0
+    }
+
+    fn is_first_level_disjoint(&self) -> bool {
+        true
+    }
+
+    fn approximate_len(&self) -> usize {
+        self.read_lock_items().values().map(BTreeMap::len).sum()
+    }
+
+    fn disk_space(&self) -> u64 {
+        0
+    }
+
+    fn get_highest_memtable_seqno(&self) -> Option<SeqNo> {
+        self.read_lock_items()
+            .values()
+            .filter_map(|versions| versions.keys().next_back().copied())
+            .max()
+    }
+
+    fn get_highest_persisted_seqno(&self) -> Option<SeqNo> {
+        None
+    }
+
+    fn keys(&self) -> BoxedIter<UserKey> {
+        Box::new(self.iter().map(|x| x.map(|(k, _)| k)))
+    }
+
+    fn values(&self) -> BoxedIter<UserValue> {
+        Box::new(self.iter().map(|x| x.map(|(_, v)| v)))
+    }
+
+    fn keys_with_seqno(&self, seqno: SeqNo, index: Option<Arc<Memtable>>) -> BoxedIter<UserKey> {
+        Box::new(
+            self.iter_with_seqno(seqno, index)
+                .map(|x| x.map(|(k, _)| k)),
+        )
+    }
+
+    fn values_with_seqno(
+        &self,
+        seqno: SeqNo,
+        index: Option<Arc<Memtable>>,
+    ) -> BoxedIter<UserValue> {
+        Box::new(
+            self.iter_with_seqno(seqno, index)
+                .map(|x| x.map(|(_, v)| v)),
+        )
+    }
+
+    fn range_bounds(
+        &self,
+        bounds: (Bound<UserKey>, Bound<UserKey>),
+        seqno: Option<SeqNo>,
+        index: Option<Arc<Memtable>>,
+    ) -> BoxedIter<KvPair> {
+        self.create_range(&bounds, seqno, index)
+    }
+
+    fn get_bytes(&self, key: &[u8], seqno: Option<SeqNo>) -> crate::Result<Option<UserValue>> {
+        let items = self.read_lock_items();
+
+        Ok(items
+            .get(key)
+            .and_then(|versions| Self::visible_version(versions, seqno))
+            .filter(|(_, value_type, _)| *value_type == ValueType::Value)
+            .map(|(_, _, value)| value))
+    }
+
+    fn get_many_bytes(
+        &self,
+        keys: &[&[u8]],
+        seqno: SeqNo,
+    ) -> crate::Result<Vec<Option<UserValue>>> {
+        let items = self.read_lock_items();
+
+        Ok(keys
+            .iter()
+            .map(|key| {
+                items
+                    .get(*key)
+                    .and_then(|versions| Self::visible_version(versions, Some(seqno)))
+                    .filter(|(_, value_type, _)| *value_type == ValueType::Value)
+                    .map(|(_, _, value)| value)
+            })
+            .collect())
+    }
+
+    fn snapshot(&self, seqno: SeqNo) -> Snapshot {
+        Snapshot::new(self.clone(), seqno)
+    }
+
+    fn insert_bytes(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        seqno: SeqNo,
+        r#type: ValueType,
+    ) -> (u32, u32) {
+        self.append(InternalValue::from_components(key, value, seqno, r#type))
+    }
+
+    fn raw_insert_bytes_with_lock(
+        &self,
+        _: &RwLockWriteGuard<'_, Memtable>,
+        key: &[u8],
+        value: &[u8],
+        seqno: SeqNo,
+        r#type: ValueType,
+    ) -> (u32, u32) {
+        self.append(InternalValue::from_components(key, value, seqno, r#type))
+    }
+}
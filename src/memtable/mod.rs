@@ -2,12 +2,13 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use crate::key::InternalKey;
+use crate::key::{InternalKey, InternalKeyRef};
+use crate::key_range::KeyRange;
 use crate::mvcc_stream::MvccStream;
 use crate::segment::block::ItemSize;
-use crate::value::{InternalValue, SeqNo, UserValue, ValueType};
+use crate::value::{InternalValue, SeqNo, UserKey, UserValue, ValueType};
 use crossbeam_skiplist::SkipMap;
-use std::ops::RangeBounds;
+use std::ops::{Bound, RangeBounds};
 use std::sync::atomic::AtomicU32;
 
 struct DoubleEndedWrapper<I>(I);
@@ -71,6 +72,17 @@ impl Memtable {
         })
     }
 
+    /// Returns `true` if any item's key is inside the given key range.
+    pub(crate) fn overlaps_key_range(&self, key_range: &KeyRange) -> bool {
+        let (min, max) = &**key_range;
+
+        // NOTE: See range.rs for the bounds explanation
+        let lo = InternalKey::new(min.clone(), SeqNo::MAX, ValueType::Tombstone);
+        let hi = InternalKey::new(max.clone(), 0, ValueType::Value);
+
+        self.items.range(lo..=hi).next().is_some()
+    }
+
     /// Returns the item by key if it exists.
     ///
     /// The item with the highest seqno will be returned, if `seqno` is None.
@@ -94,11 +106,14 @@ impl Memtable {
         // abcdef -> 6
         // abcdef -> 5
         //
-        let lower_bound = InternalKey::new(prefix, SeqNo::MAX, ValueType::Value);
+        //
+        // NOTE: The lower bound is borrowed, so point reads do not allocate a key
+        let lower_bound = (prefix, SeqNo::MAX);
+        let lower_bound: &dyn InternalKeyRef = &lower_bound;
 
         let iter = self
             .items
-            .range(lower_bound..)
+            .range::<dyn InternalKeyRef, _>((Bound::Included(lower_bound), Bound::Unbounded))
             .take_while(|entry| {
                 let key = entry.key();
                 &*key.user_key == prefix
@@ -130,10 +145,14 @@ impl Memtable {
         let iter = DoubleEndedWrapper(iter);
 
         // NOTE: We need to unwrap the return value again... memtables are not fallible, so it cannot panic
+        //
+        // NOTE: Bind the item, so the iterator (which borrows the lower bound) is dropped first
         #[allow(clippy::expect_used)]
-        MvccStream::new(iter)
+        let item = MvccStream::new(iter)
             .next()
-            .map(|x| x.expect("cannot fail"))
+            .map(|x| x.expect("cannot fail"));
+
+        item
     }
 
     /// Gets approximate size of memtable in bytes.
@@ -170,6 +189,43 @@ impl Memtable {
         (item_size, size_before + item_size)
     }
 
+    /// Returns the user keys that split the memtable into (at most) the given amount
+    /// of key ranges, which contain roughly the same amount of items.
+    pub(crate) fn split_keys(&self, partitions: usize) -> Vec<UserKey> {
+        let len = self.len();
+
+        let mut split_keys: Vec<UserKey> = Vec::with_capacity(partitions.saturating_sub(1));
+
+        let Some(first) = self.items.front() else {
+            return split_keys;
+        };
+        let first_key = first.key().user_key.clone();
+
+        let mut next_split = 1;
+
+        for (idx, entry) in self.items.iter().enumerate() {
+            if next_split >= partitions {
+                break;
+            }
+
+            if idx * partitions < next_split * len {
+                continue;
+            }
+
+            // NOTE: All versions of a key end up in the same key range,
+            // so keys that occur multiple times may result in fewer key ranges
+            let user_key = &entry.key().user_key;
+
+            if *user_key != first_key && split_keys.last() != Some(user_key) {
+                split_keys.push(user_key.clone());
+            }
+
+            next_split += 1;
+        }
+
+        split_keys
+    }
+
     /// Returns the highest sequence number in the memtable.
     pub fn get_highest_seqno(&self) -> Option<SeqNo> {
         self.items
@@ -381,4 +437,33 @@ mod tests {
             memtable.get("abc", Some(50))
         );
     }
+
+    #[test]
+    fn memtable_split_keys() {
+        let memtable = Memtable::default();
+
+        for key in 0u64..100 {
+            for seqno in 0..2 {
+                memtable.insert(InternalValue::from_components(
+                    key.to_be_bytes(),
+                    *b"",
+                    seqno,
+                    ValueType::Value,
+                ));
+            }
+        }
+
+        let split_keys = memtable.split_keys(4);
+        assert_eq!(
+            vec![
+                UserKey::from(25u64.to_be_bytes()),
+                UserKey::from(50u64.to_be_bytes()),
+                UserKey::from(75u64.to_be_bytes()),
+            ],
+            split_keys
+        );
+
+        assert!(memtable.split_keys(1).is_empty());
+        assert!(Memtable::default().split_keys(4).is_empty());
+    }
 }
@@ -0,0 +1,259 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+    time::Duration,
+};
+
+/// Every power of two is split into this many linear sub-buckets,
+/// which bounds the relative error of recorded values to 1/16
+const SUB_BUCKET_BITS: u32 = 4;
+const SUB_BUCKET_COUNT: usize = 1 << SUB_BUCKET_BITS;
+
+/// Covers the whole u64 range
+const BUCKET_COUNT: usize = (64 - SUB_BUCKET_BITS as usize + 1) * SUB_BUCKET_COUNT;
+
+/// Returns the bucket a value (in nanoseconds) falls into
+fn bucket_index(value: u64) -> usize {
+    // NOTE: Exponents and sub-bucket indexes are small, so the casts cannot truncate
+    #[allow(clippy::cast_possible_truncation)]
+    let idx = if value < SUB_BUCKET_COUNT as u64 {
+        value as usize
+    } else {
+        let exp = value.ilog2();
+        let sub = (value >> (exp - SUB_BUCKET_BITS)) as usize - SUB_BUCKET_COUNT;
+
+        (exp - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKET_COUNT + sub
+    };
+
+    idx
+}
+
+/// Returns the highest value (in nanoseconds) of a bucket
+fn bucket_upper_bound(idx: usize) -> u64 {
+    if idx < SUB_BUCKET_COUNT {
+        return idx as u64;
+    }
+
+    // NOTE: idx < BUCKET_COUNT, so the exponent fits in u32
+    #[allow(clippy::cast_possible_truncation)]
+    let exp = (idx / SUB_BUCKET_COUNT) as u32 + SUB_BUCKET_BITS - 1;
+    let sub = (idx % SUB_BUCKET_COUNT + SUB_BUCKET_COUNT) as u64;
+    let width = 1u64 << (exp - SUB_BUCKET_BITS);
+
+    (sub << (exp - SUB_BUCKET_BITS)).saturating_add(width - 1)
+}
+
+/// Converts a duration to nanoseconds, saturating at `u64::MAX`
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+/// Lock-free latency histogram with log-linear (HDR-style) buckets
+///
+/// Latencies are recorded with nanosecond resolution, and a relative error of
+/// at most 6.25%, so percentiles stay meaningful from nanoseconds up to hours.
+pub struct Histogram {
+    buckets: Box<[AtomicU64]>,
+    count: AtomicU64,
+    sum: AtomicU64,
+    max: AtomicU64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: (0..BUCKET_COUNT).map(|_| AtomicU64::default()).collect(),
+            count: AtomicU64::default(),
+            sum: AtomicU64::default(),
+            max: AtomicU64::default(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Histogram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Histogram")
+            .field("count", &self.count())
+            .field("mean", &self.mean())
+            .field("p50", &self.percentile(50.0))
+            .field("p99", &self.percentile(99.0))
+            .field("max", &self.max())
+            .finish()
+    }
+}
+
+impl Histogram {
+    /// Records a latency.
+    pub fn record(&self, latency: Duration) {
+        let value = nanos(latency);
+
+        if let Some(bucket) = self.buckets.get(bucket_index(value)) {
+            bucket.fetch_add(1, Relaxed);
+        }
+
+        self.count.fetch_add(1, Relaxed);
+        self.sum.fetch_add(value, Relaxed);
+        self.max.fetch_max(value, Relaxed);
+    }
+
+    /// Returns the amount of recorded latencies.
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.count.load(Relaxed)
+    }
+
+    /// Returns the mean latency.
+    #[must_use]
+    pub fn mean(&self) -> Duration {
+        let count = self.count();
+
+        if count == 0 {
+            return Duration::ZERO;
+        }
+
+        Duration::from_nanos(self.sum.load(Relaxed) / count)
+    }
+
+    /// Returns the highest recorded latency.
+    #[must_use]
+    pub fn max(&self) -> Duration {
+        Duration::from_nanos(self.max.load(Relaxed))
+    }
+
+    /// Returns the latency at the given percentile (0.0 - 100.0).
+    ///
+    /// The result is the upper bound of the bucket the percentile falls into.
+    #[must_use]
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        let count = self.count();
+
+        if count == 0 {
+            return Duration::ZERO;
+        }
+
+        // NOTE: The rank is clamped to [1, count]
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_precision_loss,
+            clippy::cast_sign_loss
+        )]
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0 * count as f64).ceil() as u64).max(1);
+
+        let mut seen = 0;
+
+        for (idx, bucket) in self.buckets.iter().enumerate() {
+            seen += bucket.load(Relaxed);
+
+            if seen >= rank {
+                let value = bucket_upper_bound(idx).min(self.max.load(Relaxed));
+                return Duration::from_nanos(value);
+            }
+        }
+
+        self.max()
+    }
+
+    /// Clears all recorded latencies.
+    pub fn reset(&self) {
+        for bucket in &*self.buckets {
+            bucket.store(0, Relaxed);
+        }
+
+        self.count.store(0, Relaxed);
+        self.sum.store(0, Relaxed);
+        self.max.store(0, Relaxed);
+    }
+}
+
+/// Latency histograms of a tree
+#[derive(Debug, Default)]
+pub struct LatencyHistograms {
+    /// Point reads
+    pub get: Histogram,
+
+    /// Single steps of range, prefix & full scans (in either direction)
+    pub range_next: Histogram,
+
+    /// Memtable flushes
+    pub flush: Histogram,
+
+    /// Compactions (merges)
+    pub compaction: Histogram,
+}
+
+/// Records the latency of every step of an iterator
+pub struct TimedIter<I> {
+    inner: I,
+    latencies: std::sync::Arc<LatencyHistograms>,
+}
+
+impl<I> TimedIter<I> {
+    pub fn new(inner: I, latencies: std::sync::Arc<LatencyHistograms>) -> Self {
+        Self { inner, latencies }
+    }
+}
+
+impl<I: Iterator> Iterator for TimedIter<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = std::time::Instant::now();
+        let item = self.inner.next();
+        self.latencies.range_next.record(start.elapsed());
+        item
+    }
+}
+
+impl<I: DoubleEndedIterator> DoubleEndedIterator for TimedIter<I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let start = std::time::Instant::now();
+        let item = self.inner.next_back();
+        self.latencies.range_next.record(start.elapsed());
+        item
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn histogram_bucket_index_roundtrip() {
+        for value in [0, 1, 15, 16, 17, 31, 32, 1_000, 123_456_789, u64::MAX] {
+            let idx = bucket_index(value);
+            assert!(idx < BUCKET_COUNT);
+            assert!(bucket_upper_bound(idx) >= value);
+
+            if idx > 0 {
+                assert!(bucket_upper_bound(idx - 1) < value);
+            }
+        }
+    }
+
+    #[test]
+    fn histogram_percentiles() {
+        let histogram = Histogram::default();
+        assert_eq!(Duration::ZERO, histogram.percentile(99.0));
+
+        for micros in 1..=100 {
+            histogram.record(Duration::from_micros(micros));
+        }
+
+        assert_eq!(100, histogram.count());
+        assert_eq!(Duration::from_micros(100), histogram.max());
+
+        let p50 = histogram.percentile(50.0);
+        assert!(p50 >= Duration::from_micros(50));
+        assert!(p50 <= Duration::from_micros(54));
+
+        assert_eq!(Duration::from_micros(100), histogram.percentile(100.0));
+
+        histogram.reset();
+        assert_eq!(0, histogram.count());
+        assert_eq!(Duration::ZERO, histogram.max());
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Pluggable metrics
+//!
+//! Implement [`MetricsSink`] to forward tree events to a metrics backend
+//! (Prometheus, `StatsD`, ...) and register it using [`crate::Config::metrics_sink`].
+//!
+//! The constants in this module are the names of all emitted metrics.
+//!
+//! With the `metrics` feature, trees additionally record latency histograms,
+//! see `Tree::latencies`.
+//!
+//! Point reads can be sampled for offline cache simulations, see [`crate::Config::read_sampling`].
+
+#[cfg(feature = "metrics")]
+mod histogram;
+
+mod read_sampler;
+
+pub use read_sampler::{ReadSample, ReadSampleSink};
+
+pub(crate) use read_sampler::ReadSampler;
+
+#[cfg(feature = "metrics")]
+pub use histogram::{Histogram, LatencyHistograms};
+
+#[cfg(feature = "metrics")]
+pub(crate) use histogram::TimedIter;
+
+/// Receives metrics events of a tree
+///
+/// Callbacks are invoked inline (e.g. on every write), so they should be cheap,
+/// for example atomic increments.
+pub trait MetricsSink: Send + Sync {
+    /// Increments the counter `name` by `value`
+    fn counter(&self, name: &'static str, value: u64);
+
+    /// Sets the gauge `name` to `value`
+    fn gauge(&self, name: &'static str, value: u64);
+
+    /// Records `value` in the histogram `name`
+    fn histogram(&self, name: &'static str, value: u64);
+}
+
+/// Counter of memtable flushes
+pub const FLUSHES: &str = "lsm_tree.flushes";
+
+/// Counter of bytes written to disk segments by flushes
+pub const FLUSH_BYTES_WRITTEN: &str = "lsm_tree.flush.bytes_written";
+
+/// Histogram of flush durations in microseconds
+pub const FLUSH_DURATION_MICROS: &str = "lsm_tree.flush.duration_us";
+
+/// Counter of compactions (merges) that created new segments
+pub const COMPACTIONS: &str = "lsm_tree.compactions";
+
+/// Counter of bytes read from disk segments by compactions
+pub const COMPACTION_BYTES_READ: &str = "lsm_tree.compaction.bytes_read";
+
+/// Counter of bytes written to disk segments by compactions
+pub const COMPACTION_BYTES_WRITTEN: &str = "lsm_tree.compaction.bytes_written";
+
+/// Histogram of compaction durations in microseconds
+pub const COMPACTION_DURATION_MICROS: &str = "lsm_tree.compaction.duration_us";
+
+/// Gauge of the amount of disk segments
+pub const SEGMENTS: &str = "lsm_tree.segments";
+
+/// Gauge of the amount of disk segments in the first level (L0)
+pub const FIRST_LEVEL_SEGMENTS: &str = "lsm_tree.segments.l0";
+
+/// Gauge of bytes of removed segment files that are kept, because old readers still use them
+pub const OBSOLETE_SEGMENT_BYTES: &str = "lsm_tree.segments.obsolete_bytes";
+
+/// Counter of data & index blocks that were found in the block cache
+pub const BLOCK_CACHE_HITS: &str = "lsm_tree.block_cache.hits";
+
+/// Counter of data & index blocks that had to be loaded from disk
+pub const BLOCK_CACHE_MISSES: &str = "lsm_tree.block_cache.misses";
+
+/// Counter of writes that had to wait for the active memtable
+/// (e.g. because it was being rotated)
+pub const WRITE_STALLS: &str = "lsm_tree.write.stalls";
+
+/// Histogram of write stall durations in microseconds
+pub const WRITE_STALL_DURATION_MICROS: &str = "lsm_tree.write.stall_duration_us";
+
+/// Counter of bytes (keys + values) written into the tree
+pub const BYTES_WRITTEN: &str = "lsm_tree.write.bytes";
+
+/// Counter of bytes (values) returned by point reads
+pub const BYTES_READ: &str = "lsm_tree.read.bytes";
+
+/// Converts a duration to microseconds for histograms
+// NOTE: Truncation is fine, 2^64 microseconds are more than 500000 years
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn micros(duration: std::time::Duration) -> u64 {
+    duration.as_micros() as u64
+}
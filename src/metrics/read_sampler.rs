@@ -0,0 +1,141 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{segment::meta::SegmentId, UserKey};
+use std::sync::Arc;
+
+/// A sampled point read of a data block
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReadSample {
+    /// Key that was read
+    pub key: UserKey,
+
+    /// Segment that was read
+    pub segment_id: SegmentId,
+
+    /// File offset of the data block that was read
+    pub block_offset: u64,
+
+    /// `true` if the data block was found in the block cache
+    pub cache_hit: bool,
+}
+
+/// Receives sampled point reads, see [`crate::Config::read_sampling`]
+///
+/// Samples are recorded inline on the read path, so the sink should be cheap,
+/// for example pushing into a channel or buffer.
+pub trait ReadSampleSink: Send + Sync {
+    /// Records a sampled point read
+    fn record(&self, sample: ReadSample);
+}
+
+impl<F: Fn(ReadSample) + Send + Sync> ReadSampleSink for F {
+    fn record(&self, sample: ReadSample) {
+        self(sample);
+    }
+}
+
+/// Decides which point reads are sampled, and forwards them to the sink
+///
+/// Keys are sampled by their hash, so either all or no reads of a key are recorded,
+/// which keeps the reuse distances of the sampled keys intact
+/// (like in spatially hashed cache simulations, e.g. SHARDS).
+#[derive(Clone)]
+pub struct ReadSampler {
+    sink: Arc<dyn ReadSampleSink>,
+
+    /// Keys whose hash is below the threshold are sampled
+    threshold: u64,
+}
+
+impl ReadSampler {
+    /// Creates a sampler that samples the given fraction (0.0 - 1.0) of keys.
+    pub fn new(sink: Arc<dyn ReadSampleSink>, rate: f64) -> Self {
+        // NOTE: Saturating cast, rates >= 1.0 sample every key
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let threshold = (rate.clamp(0.0, 1.0) * u64::MAX as f64) as u64;
+
+        Self { sink, threshold }
+    }
+
+    /// Returns `true` if reads of the key are sampled.
+    fn is_sampled(&self, key: &[u8]) -> bool {
+        self.threshold == u64::MAX || xxhash_rust::xxh3::xxh3_64(key) < self.threshold
+    }
+
+    /// Records the read, if its key is sampled.
+    pub fn sample(&self, key: &[u8], segment_id: SegmentId, block_offset: u64, cache_hit: bool) {
+        if self.is_sampled(key) {
+            self.sink.record(ReadSample {
+                key: key.into(),
+                segment_id,
+                block_offset,
+                cache_hit,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use test_log::test;
+
+    #[test]
+    fn read_sampler_rate() {
+        let samples = Arc::new(Mutex::new(vec![]));
+
+        let sink = {
+            let samples = samples.clone();
+            move |sample: ReadSample| samples.lock().expect("lock is poisoned").push(sample)
+        };
+        let sampler = ReadSampler::new(Arc::new(sink), 0.1);
+
+        for x in 0..10_000u64 {
+            sampler.sample(&x.to_be_bytes(), 0, 0, false);
+        }
+
+        let count = samples.lock().expect("lock is poisoned").len();
+        assert!((800..1_200).contains(&count), "sampled {count} keys");
+
+        // NOTE: Sampling is deterministic per key
+        let samples = Arc::new(Mutex::new(vec![]));
+        let sink = {
+            let samples = samples.clone();
+            move |sample: ReadSample| samples.lock().expect("lock is poisoned").push(sample)
+        };
+        let sampler = ReadSampler::new(Arc::new(sink), 0.5);
+
+        for _ in 0..2 {
+            for x in 0..100u64 {
+                sampler.sample(&x.to_be_bytes(), 0, 0, false);
+            }
+        }
+
+        let samples = samples.lock().expect("lock is poisoned");
+        let (first, second) = samples.split_at(samples.len() / 2);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn read_sampler_bounds() {
+        let samples = Arc::new(Mutex::new(0));
+
+        let sink = {
+            let samples = samples.clone();
+            move |_: ReadSample| *samples.lock().expect("lock is poisoned") += 1
+        };
+
+        ReadSampler::new(Arc::new(sink.clone()), 0.0).sample(b"a", 0, 0, false);
+        assert_eq!(0, *samples.lock().expect("lock is poisoned"));
+
+        ReadSampler::new(Arc::new(sink), 1.0).sample(b"a", 0, 0, false);
+        assert_eq!(1, *samples.lock().expect("lock is poisoned"));
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+#[cfg(feature = "mlock")]
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// Lock counts of all locked pages, by page address
+///
+/// The OS does not count locks, and locks whole pages, so small regions of different
+/// owners may share a locked page. A page is only unlocked once every region on it is unlocked.
+#[cfg(feature = "mlock")]
+static LOCKED_PAGES: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+#[cfg(feature = "mlock")]
+fn locked_pages() -> MutexGuard<'static, BTreeMap<usize, usize>> {
+    // NOTE: The counts are never left half-updated, so a poisoned lock can still be used
+    LOCKED_PAGES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Memory regions that are locked into RAM, so they are never paged out
+///
+/// Locking is best-effort: if the OS refuses to lock memory (e.g. because of `RLIMIT_MEMLOCK`),
+/// the memory simply stays unlocked. Without the `mlock` feature, nothing is locked.
+///
+/// Memory is locked in whole pages, which are reference counted across all memory locks,
+/// so unlocking a region (when dropped) never unlocks memory of another region on the same page.
+#[derive(Default)]
+pub struct MemoryLock {
+    /// Locked pages, as address of the first page & page count
+    #[cfg(feature = "mlock")]
+    pages: Vec<(usize, usize)>,
+
+    /// Amount of bytes that were locked
+    locked_bytes: usize,
+}
+
+impl MemoryLock {
+    /// Tries to lock the memory of the given slice.
+    #[cfg(feature = "mlock")]
+    pub fn lock<T>(&mut self, data: &[T]) {
+        let size = std::mem::size_of_val(data);
+
+        if size == 0 {
+            return;
+        }
+
+        let page_size = region::page::size();
+        let first_page = region::page::floor(data.as_ptr()) as usize;
+        let page_count = (data.as_ptr() as usize + size - first_page).div_ceil(page_size);
+
+        let mut locked_pages = locked_pages();
+
+        // NOTE: Pages that are locked already do not need another syscall
+        let is_locked =
+            (0..page_count).all(|idx| locked_pages.contains_key(&(first_page + idx * page_size)));
+
+        if !is_locked {
+            match region::lock(data.as_ptr(), size) {
+                // NOTE: Pages are unlocked once their lock count drops to 0, see Drop
+                Ok(guard) => std::mem::forget(guard),
+                Err(e) => {
+                    log::debug!("Failed to lock {size} bytes of memory: {e:?}");
+                    return;
+                }
+            }
+        }
+
+        for idx in 0..page_count {
+            *locked_pages
+                .entry(first_page + idx * page_size)
+                .or_default() += 1;
+        }
+
+        drop(locked_pages);
+
+        self.pages.push((first_page, page_count));
+        self.locked_bytes += size;
+    }
+
+    /// Does nothing, because locking memory requires the `mlock` feature.
+    #[cfg(not(feature = "mlock"))]
+    #[allow(clippy::unused_self, clippy::needless_pass_by_ref_mut)]
+    pub fn lock<T>(&mut self, _: &[T]) {}
+
+    /// Returns the amount of bytes that were locked.
+    #[cfg(test)]
+    #[must_use]
+    pub fn locked_bytes(&self) -> usize {
+        self.locked_bytes
+    }
+}
+
+#[cfg(feature = "mlock")]
+impl Drop for MemoryLock {
+    fn drop(&mut self) {
+        if self.pages.is_empty() {
+            return;
+        }
+
+        let page_size = region::page::size();
+        let mut locked_pages = locked_pages();
+
+        for &(first_page, page_count) in &self.pages {
+            for idx in 0..page_count {
+                let page = first_page + idx * page_size;
+
+                let Entry::Occupied(mut entry) = locked_pages.entry(page) else {
+                    continue;
+                };
+
+                *entry.get_mut() -= 1;
+
+                if *entry.get() == 0 {
+                    entry.remove();
+
+                    if let Err(e) = region::unlock(page as *const u8, page_size) {
+                        log::debug!("Failed to unlock memory page: {e:?}");
+                    }
+                }
+            }
+        }
+
+        drop(locked_pages);
+    }
+}
+
+impl std::fmt::Debug for MemoryLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MemoryLock({} bytes)", self.locked_bytes)
+    }
+}
+
+// NOTE: Whether memory is locked does not change the value it holds
+impl PartialEq for MemoryLock {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for MemoryLock {}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn memory_lock_empty() {
+        let mut memory_lock = MemoryLock::default();
+        memory_lock.lock::<u8>(&[]);
+        assert_eq!(0, memory_lock.locked_bytes());
+    }
+
+    #[test]
+    #[cfg(feature = "mlock")]
+    fn memory_lock_best_effort() {
+        let data = vec![0_u8; 4_096];
+
+        let mut memory_lock = MemoryLock::default();
+        memory_lock.lock(&data);
+
+        // NOTE: The OS may refuse to lock memory, which is not an error
+        assert!(memory_lock.locked_bytes() == 0 || memory_lock.locked_bytes() == data.len());
+    }
+
+    #[test]
+    #[cfg(feature = "mlock")]
+    fn memory_lock_shared_page() {
+        let page_size = region::page::size();
+        let data = vec![0_u8; 2 * page_size];
+
+        // NOTE: Use a page that is not shared with memory of other tests
+        let page = region::page::ceil(data.as_ptr()) as usize;
+        let offset = page - data.as_ptr() as usize;
+        let (left, right) = data
+            .get(offset..offset + 64)
+            .expect("should be in bounds")
+            .split_at(32);
+
+        let mut left_lock = MemoryLock::default();
+        left_lock.lock(left);
+
+        // NOTE: The OS may refuse to lock memory, which is not an error
+        if left_lock.locked_bytes() == 0 {
+            return;
+        }
+
+        let mut right_lock = MemoryLock::default();
+        right_lock.lock(right);
+        assert_eq!(Some(&2), locked_pages().get(&page));
+
+        drop(left_lock);
+        assert_eq!(Some(&1), locked_pages().get(&page));
+
+        drop(right_lock);
+        assert_eq!(None, locked_pages().get(&page));
+    }
+}
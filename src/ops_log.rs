@@ -0,0 +1,236 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Operations log, see [`crate::Config::ops_log`]
+//!
+//! Every line is a single JSON object, e.g.:
+//!
+//! ```json
+//! {"ts_ms":"1700000000000","event":"flush","segment_id":"1","items":"10","bytes":"1234","duration_us":"5"}
+//! ```
+//!
+//! Like in [`crate::Tree::manifest_json`], 64-bit and 128-bit integers are encoded as decimal strings,
+//! because many JSON parsers read numbers as doubles, which only represent integers up to 2^53 exactly.
+
+use crate::{
+    file::{OPS_LOG_FILE, ROTATED_OPS_LOG_FILE},
+    time::unix_timestamp,
+    SegmentId,
+};
+use std::{
+    fmt::Write as _,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+/// Maintenance event that is recorded in the operations log
+pub enum OpsEvent<'a> {
+    /// A memtable was flushed into a segment
+    Flush {
+        segment_id: SegmentId,
+        item_count: u64,
+        bytes: u64,
+        duration: Duration,
+    },
+
+    /// Segments were merged into new segments
+    Compaction {
+        dest_level: u8,
+        inputs: &'a [SegmentId],
+        input_bytes: u64,
+        outputs: &'a [SegmentId],
+        output_bytes: u64,
+        duration: Duration,
+    },
+
+    /// Segments were dropped without rewriting them
+    Drop { segment_ids: &'a [SegmentId] },
+}
+
+fn write_ids(line: &mut String, ids: &[SegmentId]) {
+    line.push('[');
+
+    for (idx, id) in ids.iter().enumerate() {
+        if idx > 0 {
+            line.push(',');
+        }
+        let _ = write!(line, "\"{id}\"");
+    }
+
+    line.push(']');
+}
+
+impl OpsEvent<'_> {
+    /// Encodes the event as a single JSON line
+    fn to_json_line(&self, timestamp: Duration) -> String {
+        let mut line = format!("{{\"ts_ms\":\"{}\"", timestamp.as_millis());
+
+        // NOTE: Writing into a String cannot fail
+        match self {
+            Self::Flush {
+                segment_id,
+                item_count,
+                bytes,
+                duration,
+            } => {
+                let _ = write!(
+                    line,
+                    ",\"event\":\"flush\",\"segment_id\":\"{segment_id}\",\"items\":\"{item_count}\",\"bytes\":\"{bytes}\",\"duration_us\":\"{}\"",
+                    duration.as_micros(),
+                );
+            }
+            Self::Compaction {
+                dest_level,
+                inputs,
+                input_bytes,
+                outputs,
+                output_bytes,
+                duration,
+            } => {
+                let _ = write!(
+                    line,
+                    ",\"event\":\"compaction\",\"dest_level\":{dest_level},\"inputs\":"
+                );
+                write_ids(&mut line, inputs);
+                let _ = write!(line, ",\"input_bytes\":\"{input_bytes}\",\"outputs\":");
+                write_ids(&mut line, outputs);
+                let _ = write!(
+                    line,
+                    ",\"output_bytes\":\"{output_bytes}\",\"duration_us\":\"{}\"",
+                    duration.as_micros(),
+                );
+            }
+            Self::Drop { segment_ids } => {
+                line.push_str(",\"event\":\"drop\",\"segment_ids\":");
+                write_ids(&mut line, segment_ids);
+            }
+        }
+
+        line.push_str("}\n");
+        line
+    }
+}
+
+/// Append-only JSONL log of flushes & compactions, stored in the tree folder
+///
+/// Once the log exceeds its maximum size, it is rotated, keeping
+/// the previous log file, so at most 2x the maximum size is used.
+pub struct OpsLog {
+    folder: PathBuf,
+    max_size: u64,
+
+    /// Open log file and its current size
+    file: Mutex<Option<(File, u64)>>,
+}
+
+impl OpsLog {
+    /// Creates the operations log of a tree, if enabled
+    pub fn from_config(config: &crate::Config) -> Option<Self> {
+        (config.ops_log_max_size > 0).then(|| Self::new(&config.path, config.ops_log_max_size))
+    }
+
+    pub fn new<P: AsRef<Path>>(folder: P, max_size: u64) -> Self {
+        Self {
+            folder: folder.as_ref().into(),
+            max_size,
+            file: Mutex::default(),
+        }
+    }
+
+    /// Appends an event to the log
+    ///
+    /// The log is a debugging aid, so failing to write it
+    /// does not fail the operation that is recorded.
+    pub fn append(&self, event: &OpsEvent<'_>) {
+        let line = event.to_json_line(unix_timestamp());
+
+        if let Err(e) = self.append_line(&line) {
+            log::warn!("Failed to write operations log: {e:?}");
+        }
+    }
+
+    fn append_line(&self, line: &str) -> std::io::Result<()> {
+        let mut lock = self.file.lock().expect("lock is poisoned");
+
+        let path = self.folder.join(OPS_LOG_FILE);
+
+        if let Some((_, size)) = &*lock {
+            if *size > 0 && *size + line.len() as u64 > self.max_size {
+                log::debug!("Rotating operations log at {}", path.display());
+
+                *lock = None;
+                std::fs::rename(&path, self.folder.join(ROTATED_OPS_LOG_FILE))?;
+            }
+        }
+
+        if lock.is_none() {
+            let file = OpenOptions::new().create(true).append(true).open(&path)?;
+            let size = file.metadata()?.len();
+            *lock = Some((file, size));
+        }
+
+        if let Some((file, size)) = &mut *lock {
+            file.write_all(line.as_bytes())?;
+            *size += line.len() as u64;
+        }
+        drop(lock);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn ops_log_json_lines() {
+        let line = OpsEvent::Compaction {
+            dest_level: 1,
+            inputs: &[1, 2],
+            input_bytes: 100,
+            outputs: &[3],
+            output_bytes: 90,
+            duration: Duration::from_micros(5),
+        }
+        .to_json_line(Duration::from_millis(7));
+
+        assert_eq!(
+            "{\"ts_ms\":\"7\",\"event\":\"compaction\",\"dest_level\":1,\"inputs\":[\"1\",\"2\"],\"input_bytes\":\"100\",\"outputs\":[\"3\"],\"output_bytes\":\"90\",\"duration_us\":\"5\"}\n",
+            line,
+        );
+
+        let line = OpsEvent::Drop { segment_ids: &[] }.to_json_line(Duration::ZERO);
+        assert_eq!(
+            "{\"ts_ms\":\"0\",\"event\":\"drop\",\"segment_ids\":[]}\n",
+            line
+        );
+    }
+
+    #[test]
+    fn ops_log_rotation() -> crate::Result<()> {
+        let folder = tempfile::tempdir()?;
+
+        let log = OpsLog::new(&folder, 150);
+
+        for segment_id in 0..3 {
+            log.append(&OpsEvent::Drop {
+                segment_ids: &[segment_id],
+            });
+        }
+
+        let current = std::fs::read_to_string(folder.path().join(OPS_LOG_FILE))?;
+        let rotated = std::fs::read_to_string(folder.path().join(ROTATED_OPS_LOG_FILE))?;
+
+        assert_eq!(1, current.lines().count());
+        assert_eq!(2, rotated.lines().count());
+        assert!(current.contains("\"segment_ids\":[\"2\"]"));
+
+        Ok(())
+    }
+}
@@ -8,20 +8,66 @@ use crate::{
     memtable::Memtable,
     merge::{BoxedIterator, Merger},
     mvcc_stream::MvccStream,
-    segment::{multi_reader::MultiReader, range::Range as RangeReader},
+    read_options::ReadOptions,
+    segment::{level_reader::LevelReader, Segment},
     tree::inner::SealedMemtables,
-    value::{SeqNo, UserKey},
-    KvPair,
+    value::{InternalValue, SeqNo, UserKey},
 };
 use guardian::ArcRwLockReadGuardian;
 use self_cell::self_cell;
-use std::{collections::VecDeque, ops::Bound, sync::Arc};
+use std::{
+    ops::{Bound, Range, RangeBounds},
+    sync::Arc,
+};
 
 #[must_use]
 pub fn seqno_filter(item_seqno: SeqNo, seqno: SeqNo) -> bool {
     item_seqno < seqno
 }
 
+/// Converts a borrowed range into owned bounds
+#[must_use]
+pub fn to_owned_bounds<K: AsRef<[u8]>, R: RangeBounds<K>>(
+    range: &R,
+) -> (Bound<UserKey>, Bound<UserKey>) {
+    use std::ops::Bound::{Excluded, Included, Unbounded};
+
+    let lo = match range.start_bound() {
+        Included(x) => Included(x.as_ref().into()),
+        Excluded(x) => Excluded(x.as_ref().into()),
+        Unbounded => Unbounded,
+    };
+
+    let hi = match range.end_bound() {
+        Included(x) => Included(x.as_ref().into()),
+        Excluded(x) => Excluded(x.as_ref().into()),
+        Unbounded => Unbounded,
+    };
+
+    (lo, hi)
+}
+
+/// Returns `true` if no key can fall into the given bounds (e.g. `b..a` or `a..a`).
+#[must_use]
+pub fn is_empty_range(bounds: &(Bound<UserKey>, Bound<UserKey>)) -> bool {
+    use std::ops::Bound::{Excluded, Included};
+
+    match bounds {
+        (Included(lo), Included(hi)) => lo > hi,
+        (Included(lo) | Excluded(lo), Included(hi) | Excluded(hi)) => lo >= hi,
+        _ => false,
+    }
+}
+
+/// Returns the key, if the bounds only contain a single key (e.g. `a..=a`).
+#[must_use]
+pub fn single_key_range(bounds: &(Bound<UserKey>, Bound<UserKey>)) -> Option<&UserKey> {
+    match bounds {
+        (Bound::Included(lo), Bound::Included(hi)) if lo == hi => Some(lo),
+        _ => None,
+    }
+}
+
 #[must_use]
 #[allow(clippy::module_name_repetitions)]
 pub fn prefix_to_range(prefix: &[u8]) -> (Bound<UserKey>, Bound<UserKey>) {
@@ -47,13 +93,69 @@ pub fn prefix_to_range(prefix: &[u8]) -> (Bound<UserKey>, Bound<UserKey>) {
     (Included(prefix.into()), Unbounded)
 }
 
+/// Converts user key bounds into bounds over internal keys,
+/// so all versions of the bounding keys are included (or excluded)
+#[must_use]
+pub fn to_internal_bounds(
+    bounds: &(Bound<UserKey>, Bound<UserKey>),
+) -> (Bound<InternalKey>, Bound<InternalKey>) {
+    let lo = match &bounds.0 {
+        // NOTE: See memtable.rs for range explanation
+        Bound::Included(key) => Bound::Included(InternalKey::new(
+            key.clone(),
+            SeqNo::MAX,
+            crate::value::ValueType::Tombstone,
+        )),
+        Bound::Excluded(key) => Bound::Excluded(InternalKey::new(
+            key.clone(),
+            0,
+            crate::value::ValueType::Tombstone,
+        )),
+        Bound::Unbounded => Bound::Unbounded,
+    };
+
+    let hi = match &bounds.1 {
+        // NOTE: See memtable.rs for range explanation, this is the reverse case
+        // where we need to go all the way to the last seqno of an item
+        //
+        // Example: We search for (Unbounded..Excluded(abdef))
+        //
+        // key -> seqno
+        //
+        // a   -> 7 <<< This is the lowest key that matches the range
+        // abc -> 5
+        // abc -> 4
+        // abc -> 3 <<< This is the highest key that matches the range
+        // abcdef -> 6
+        // abcdef -> 5
+        //
+        Bound::Included(key) => Bound::Included(InternalKey::new(
+            key.clone(),
+            0,
+            crate::value::ValueType::Value,
+        )),
+        Bound::Excluded(key) => Bound::Excluded(InternalKey::new(
+            key.clone(),
+            SeqNo::MAX,
+            crate::value::ValueType::Value,
+        )),
+        Bound::Unbounded => Bound::Unbounded,
+    };
+
+    (lo, hi)
+}
+
 pub struct MemtableLockGuard {
     pub(crate) active: ArcRwLockReadGuardian<Memtable>,
     pub(crate) sealed: ArcRwLockReadGuardian<SealedMemtables>,
     pub(crate) ephemeral: Option<Arc<Memtable>>,
+
+    /// Segments the iterator reads from, so their files are not deleted
+    /// by compaction while the iterator is alive
+    pub(crate) segments: Vec<Arc<Segment>>,
 }
 
-type BoxedMerge<'a> = Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'a>;
+type BoxedMerge<'a> = Box<dyn DoubleEndedIterator<Item = crate::Result<InternalValue>> + 'a>;
 
 self_cell!(
     pub struct TreeIter {
@@ -65,7 +167,7 @@ self_cell!(
 );
 
 impl Iterator for TreeIter {
-    type Item = crate::Result<KvPair>;
+    type Item = crate::Result<InternalValue>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.with_dependent_mut(|_, iter| iter.next())
@@ -81,88 +183,106 @@ impl DoubleEndedIterator for TreeIter {
 fn collect_disjoint_tree_with_range(
     level_manifest: &LevelManifest,
     bounds: &(Bound<UserKey>, Bound<UserKey>),
-) -> MultiReader<RangeReader> {
-    // TODO: bench... can probably be optimized by not linearly filtering, but using binary search etc.
-    // TODO: binary-filter per level and collect instead of sorting and whatever
-    let mut segments: Vec<_> = level_manifest
-        .iter()
-        .filter(|x| x.check_key_range_overlap(bounds))
-        .collect();
-
+    options: &ReadOptions,
+) -> LevelReader {
+    let mut segments: Vec<_> = level_manifest.iter().cloned().collect();
     segments.sort_by(|a, b| a.metadata.key_range.0.cmp(&b.metadata.key_range.0));
 
-    let readers: VecDeque<_> = segments
-        .into_iter()
-        .map(|x| x.range(bounds.clone()))
-        .collect::<VecDeque<_>>();
-
-    MultiReader::new(readers)
+    LevelReader::new(&segments, bounds.clone()).with_options(options.clone())
 }
 
 impl TreeIter {
     #[must_use]
-    #[allow(clippy::too_many_lines)]
     pub fn create_range(
         guard: MemtableLockGuard,
         bounds: (Bound<UserKey>, Bound<UserKey>),
         seqno: Option<SeqNo>,
         level_manifest: ArcRwLockReadGuardian<LevelManifest>,
     ) -> Self {
-        Self::new(guard, |lock| {
-            let lo = match &bounds.0 {
-                // NOTE: See memtable.rs for range explanation
-                Bound::Included(key) => Bound::Included(InternalKey::new(
-                    key.clone(),
-                    SeqNo::MAX,
-                    crate::value::ValueType::Tombstone,
-                )),
-                Bound::Excluded(key) => Bound::Excluded(InternalKey::new(
-                    key.clone(),
-                    0,
-                    crate::value::ValueType::Tombstone,
-                )),
-                Bound::Unbounded => Bound::Unbounded,
-            };
+        Self::create(
+            guard,
+            bounds,
+            seqno.map(|seqno| 0..seqno),
+            false,
+            &ReadOptions::default(),
+            level_manifest,
+        )
+    }
 
-            let hi = match &bounds.1 {
-                // NOTE: See memtable.rs for range explanation, this is the reverse case
-                // where we need to go all the way to the last seqno of an item
-                //
-                // Example: We search for (Unbounded..Excluded(abdef))
-                //
-                // key -> seqno
-                //
-                // a   -> 7 <<< This is the lowest key that matches the range
-                // abc -> 5
-                // abc -> 4
-                // abc -> 3 <<< This is the highest key that matches the range
-                // abcdef -> 6
-                // abcdef -> 5
-                //
-                Bound::Included(key) => Bound::Included(InternalKey::new(
-                    key.clone(),
-                    0,
-                    crate::value::ValueType::Value,
-                )),
-                Bound::Excluded(key) => Bound::Excluded(InternalKey::new(
-                    key.clone(),
-                    SeqNo::MAX,
-                    crate::value::ValueType::Value,
-                )),
-                Bound::Unbounded => Bound::Unbounded,
-            };
+    /// Creates a range over the latest visible versions of items,
+    /// reading segments as configured by the given options.
+    ///
+    /// The pagination cursor narrows the bounds, and the limit caps the readahead of each
+    /// segment reader, but the limit itself and the key-only option are not applied here.
+    #[must_use]
+    pub fn create_range_with_options(
+        guard: MemtableLockGuard,
+        bounds: (Bound<UserKey>, Bound<UserKey>),
+        options: &ReadOptions,
+        level_manifest: ArcRwLockReadGuardian<LevelManifest>,
+    ) -> Self {
+        Self::create(
+            guard,
+            options.apply_start_after(bounds),
+            options.seqno.map(|seqno| 0..seqno),
+            false,
+            options,
+            level_manifest,
+        )
+    }
 
-            let range = (lo, hi);
+    /// Creates a range over the latest version of every key that was written
+    /// in the given seqno window, including tombstones.
+    #[must_use]
+    pub fn create_range_window(
+        guard: MemtableLockGuard,
+        bounds: (Bound<UserKey>, Bound<UserKey>),
+        seqnos: Range<SeqNo>,
+        level_manifest: ArcRwLockReadGuardian<LevelManifest>,
+    ) -> Self {
+        Self::create(
+            guard,
+            bounds,
+            Some(seqnos),
+            true,
+            &ReadOptions::default(),
+            level_manifest,
+        )
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn create(
+        mut guard: MemtableLockGuard,
+        bounds: (Bound<UserKey>, Bound<UserKey>),
+        seqnos: Option<Range<SeqNo>>,
+        keep_tombstones: bool,
+        options: &ReadOptions,
+        level_manifest: ArcRwLockReadGuardian<LevelManifest>,
+    ) -> Self {
+        // NOTE: Fast path for probing empty ranges, which can not contain any item
+        if is_empty_range(&bounds) {
+            return Self::new(guard, |_| Box::new(std::iter::empty()));
+        }
+
+        // NOTE: Only segments that overlap with the range may be read
+        guard.segments = level_manifest
+            .iter()
+            .filter(|segment| segment.metadata.key_range.overlaps_with_bounds(&bounds))
+            .cloned()
+            .collect();
+
+        Self::new(guard, |lock| {
+            let range = to_internal_bounds(&bounds);
 
             let mut iters: Vec<BoxedIterator<'_>> = Vec::new();
 
-            // NOTE: Optimize disjoint trees (e.g. timeseries) to only use a single MultiReader.
-            if level_manifest.is_disjoint() {
-                let reader = collect_disjoint_tree_with_range(&level_manifest, &bounds);
+            // NOTE: Optimize disjoint trees (e.g. timeseries) to only use a single LevelReader.
+            if level_manifest.is_disjoint_across_levels() {
+                let reader = collect_disjoint_tree_with_range(&level_manifest, &bounds, options);
 
-                if let Some(seqno) = seqno {
+                if let Some(seqnos) = seqnos.clone() {
                     iters.push(Box::new(reader.filter(move |item| match item {
-                        Ok(item) => seqno_filter(item.key.seqno, seqno),
+                        Ok(item) => seqnos.contains(&item.key.seqno),
                         Err(_) => true,
                     })));
                 } else {
@@ -172,39 +292,30 @@ impl TreeIter {
                 for level in &level_manifest.levels {
                     if level.is_disjoint {
                         let mut level = level.clone();
-
-                        let mut readers: VecDeque<BoxedIterator<'_>> = VecDeque::new();
-
                         level.sort_by_key_range();
 
-                        // TODO: can probably be optimized by using binary search per disjoint level to filter segments
-                        for segment in &level.segments {
-                            if segment.check_key_range_overlap(&bounds) {
-                                let range = segment.range(bounds.clone());
-                                readers.push_back(Box::new(range));
-                            }
-                        }
-
-                        if !readers.is_empty() {
-                            let multi_reader = MultiReader::new(readers);
+                        // NOTE: Segment readers are only opened once the scan reaches them
+                        let reader = LevelReader::new(&level.segments, bounds.clone())
+                            .with_options(options.clone());
 
-                            if let Some(seqno) = seqno {
-                                iters.push(Box::new(multi_reader.filter(move |item| match item {
-                                    Ok(item) => seqno_filter(item.key.seqno, seqno),
+                        if reader.remaining_segments() > 0 {
+                            if let Some(seqnos) = seqnos.clone() {
+                                iters.push(Box::new(reader.filter(move |item| match item {
+                                    Ok(item) => seqnos.contains(&item.key.seqno),
                                     Err(_) => true,
                                 })));
                             } else {
-                                iters.push(Box::new(multi_reader));
+                                iters.push(Box::new(reader));
                             }
                         }
                     } else {
                         for segment in &level.segments {
                             if segment.check_key_range_overlap(&bounds) {
-                                let reader = segment.range(bounds.clone());
+                                let reader = segment.range_with_options(bounds.clone(), options);
 
-                                if let Some(seqno) = seqno {
+                                if let Some(seqnos) = seqnos.clone() {
                                     iters.push(Box::new(reader.filter(move |item| match item {
-                                        Ok(item) => seqno_filter(item.key.seqno, seqno),
+                                        Ok(item) => seqnos.contains(&item.key.seqno),
                                         Err(_) => true,
                                     })));
                                 } else {
@@ -222,9 +333,9 @@ impl TreeIter {
             for (_, memtable) in lock.sealed.iter() {
                 let iter = memtable.range(range.clone());
 
-                if let Some(seqno) = seqno {
+                if let Some(seqnos) = seqnos.clone() {
                     iters.push(Box::new(
-                        iter.filter(move |item| seqno_filter(item.key.seqno, seqno))
+                        iter.filter(move |item| seqnos.contains(&item.key.seqno))
                             .map(Ok),
                     ));
                 } else {
@@ -236,9 +347,9 @@ impl TreeIter {
             {
                 let iter = lock.active.range(range.clone());
 
-                if let Some(seqno) = seqno {
+                if let Some(seqnos) = seqnos {
                     iters.push(Box::new(
-                        iter.filter(move |item| seqno_filter(item.key.seqno, seqno))
+                        iter.filter(move |item| seqnos.contains(&item.key.seqno))
                             .map(Ok),
                     ));
                 } else {
@@ -255,16 +366,10 @@ impl TreeIter {
             let merged = Merger::new(iters);
             let iter = MvccStream::new(merged);
 
-            Box::new(
-                iter.filter(|x| match x {
-                    Ok(value) => !value.key.is_tombstone(),
-                    Err(_) => true,
-                })
-                .map(|item| match item {
-                    Ok(kv) => Ok((kv.key.user_key, kv.value)),
-                    Err(e) => Err(e),
-                }),
-            )
+            Box::new(iter.filter(move |x| match x {
+                Ok(value) => keep_tombstones || !value.key.is_tombstone(),
+                Err(_) => true,
+            }))
         })
     }
 }
@@ -295,6 +400,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn range_is_empty() {
+        let key = |key: &str| Slice::from(key.as_bytes());
+
+        assert!(is_empty_range(&(Included(key("b")), Included(key("a")))));
+        assert!(is_empty_range(&(Included(key("a")), Excluded(key("a")))));
+        assert!(is_empty_range(&(Excluded(key("a")), Included(key("a")))));
+        assert!(is_empty_range(&(Excluded(key("a")), Excluded(key("a")))));
+
+        assert!(!is_empty_range(&(Included(key("a")), Included(key("a")))));
+        assert!(!is_empty_range(&(Excluded(key("a")), Excluded(key("b")))));
+        assert!(!is_empty_range(&(Included(key("b")), Unbounded)));
+        assert!(!is_empty_range(&(Unbounded, Excluded(key("a")))));
+    }
+
+    #[test]
+    fn range_single_key() {
+        let key = |key: &str| Slice::from(key.as_bytes());
+
+        assert_eq!(
+            Some(&key("a")),
+            single_key_range(&(Included(key("a")), Included(key("a"))))
+        );
+        assert_eq!(
+            None,
+            single_key_range(&(Included(key("a")), Included(key("b"))))
+        );
+        assert_eq!(
+            None,
+            single_key_range(&(Included(key("a")), Excluded(key("a"))))
+        );
+    }
+
     #[test]
     fn prefix_to_range_basic() {
         test_prefix(b"abc", Excluded(b"abd"));
@@ -0,0 +1,284 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    segment::{value_block::CachePolicy, Segment},
+    SeqNo, UserKey,
+};
+use std::ops::Bound;
+
+/// Options for full, range and prefix scans
+///
+/// # Examples
+///
+/// ```
+/// # let folder = tempfile::tempdir()?;
+/// use lsm_tree::{AbstractTree, Config, ReadOptions};
+///
+/// let tree = Config::new(folder).open()?;
+///
+/// tree.insert("a", "abc", 0);
+/// tree.insert("b", "abc", 1);
+/// tree.insert("c", "abc", 2);
+///
+/// let options = ReadOptions::default().seqno(2).limit(1);
+/// assert_eq!(1, tree.iter_with_options(&options).count());
+/// #
+/// # Ok::<(), lsm_tree::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReadOptions {
+    pub(crate) seqno: Option<SeqNo>,
+    pub(crate) fill_cache: bool,
+    pub(crate) readahead: Option<usize>,
+    pub(crate) readahead_bytes: Option<usize>,
+    pub(crate) key_only: bool,
+    pub(crate) limit: Option<usize>,
+    pub(crate) start_after: Option<UserKey>,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            seqno: None,
+            fill_cache: true,
+            readahead: None,
+            readahead_bytes: None,
+            key_only: false,
+            limit: None,
+            start_after: None,
+        }
+    }
+}
+
+impl ReadOptions {
+    /// Only reads items with a sequence number lower than `seqno`.
+    ///
+    /// Defaults to reading the latest state of the tree.
+    #[must_use]
+    pub fn seqno(mut self, seqno: SeqNo) -> Self {
+        self.seqno = Some(seqno);
+        self
+    }
+
+    /// If `false`, blocks that are loaded from disk are not inserted into the block cache,
+    /// so a large scan does not evict the working set of other reads.
+    ///
+    /// Defaults to `true`.
+    #[must_use]
+    pub fn fill_cache(mut self, enabled: bool) -> Self {
+        self.fill_cache = enabled;
+        self
+    }
+
+    /// Sets the amount of data blocks that are prefetched ahead of forward scans.
+    ///
+    /// Defaults to [`crate::Config::block_readahead`].
+    #[must_use]
+    pub fn readahead(mut self, depth: usize) -> Self {
+        self.readahead = Some(depth);
+        self
+    }
+
+    /// Sets the amount of bytes that forward scans request per I/O,
+    /// when a data block is not in the block cache.
+    ///
+    /// Defaults to [`crate::Config::readahead_bytes`].
+    #[must_use]
+    pub fn readahead_bytes(mut self, bytes: usize) -> Self {
+        self.readahead_bytes = Some(bytes);
+        self
+    }
+
+    /// If `true`, only keys are read, and all returned values are empty.
+    ///
+    /// In a key-value separated tree, values are then not read from blob files.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn key_only(mut self, enabled: bool) -> Self {
+        self.key_only = enabled;
+        self
+    }
+
+    /// Returns at most `n` items, counted across both ends of the iterator.
+    ///
+    /// The limit caps the readahead of every segment reader, so a single reader does not
+    /// prefetch more data blocks than needed to satisfy it. It does not bound the total amount
+    /// of bytes read: every segment reader that is opened by the merge still reads its first block.
+    ///
+    /// Defaults to no limit.
+    #[must_use]
+    pub fn limit(mut self, n: usize) -> Self {
+        self.limit = Some(n);
+        self
+    }
+
+    /// Only returns items with a key strictly greater than `key`.
+    ///
+    /// Used for pagination, by passing the last key of the previous page.
+    /// The cursor is pushed down into the block indexes, so blocks before it are never read.
+    ///
+    /// Defaults to no cursor.
+    #[must_use]
+    pub fn start_after<K: Into<UserKey>>(mut self, key: K) -> Self {
+        self.start_after = Some(key.into());
+        self
+    }
+
+    /// Narrows the lower bound of the given bounds to the pagination cursor.
+    pub(crate) fn apply_start_after(
+        &self,
+        bounds: (Bound<UserKey>, Bound<UserKey>),
+    ) -> (Bound<UserKey>, Bound<UserKey>) {
+        let Some(cursor) = &self.start_after else {
+            return bounds;
+        };
+
+        let lo = match bounds.0 {
+            Bound::Included(lo) if lo > *cursor => Bound::Included(lo),
+            Bound::Excluded(lo) if lo >= *cursor => Bound::Excluded(lo),
+            _ => Bound::Excluded(cursor.clone()),
+        };
+
+        (lo, bounds.1)
+    }
+
+    /// Returns the readahead depth for a scan over the given segment.
+    ///
+    /// With a limit, the depth is capped to the amount of data blocks
+    /// of the segment that (on average) hold the limited amount of items.
+    pub(crate) fn limit_capped_readahead(&self, segment: &Segment) -> usize {
+        let readahead = self.readahead.unwrap_or(segment.block_index.readahead);
+
+        let Some(limit) = self.limit else {
+            return readahead;
+        };
+
+        let items_per_block =
+            segment.metadata.item_count / u64::from(segment.metadata.data_block_count).max(1);
+
+        // NOTE: Truncation is OK because the value is capped by `limit`
+        #[allow(clippy::cast_possible_truncation)]
+        let blocks = (limit as u64).div_ceil(items_per_block.max(1)) as usize;
+
+        // NOTE: The first block is read anyway, readahead only covers the following ones
+        readahead.min(blocks.saturating_sub(1))
+    }
+
+    pub(crate) fn cache_policy(&self) -> CachePolicy {
+        if self.fill_cache {
+            CachePolicy::Write
+        } else {
+            CachePolicy::Read
+        }
+    }
+
+    /// Caps the given iterator to the configured limit.
+    pub(crate) fn limited<I: DoubleEndedIterator>(&self, iter: I) -> Limited<I> {
+        Limited {
+            inner: iter,
+            remaining: self.limit.unwrap_or(usize::MAX),
+        }
+    }
+}
+
+/// Iterator that yields at most a fixed amount of items,
+/// counted across both ends
+pub struct Limited<I> {
+    inner: I,
+    remaining: usize,
+}
+
+impl<I: Iterator> Iterator for Limited<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let item = self.inner.next()?;
+        self.remaining -= 1;
+        Some(item)
+    }
+}
+
+impl<I: DoubleEndedIterator> DoubleEndedIterator for Limited<I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let item = self.inner.next_back()?;
+        self.remaining -= 1;
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn read_options_limited_both_ends() {
+        let options = ReadOptions::default().limit(3);
+
+        let mut iter = options.limited(0..10);
+        assert_eq!(Some(0), iter.next());
+        assert_eq!(Some(9), iter.next_back());
+        assert_eq!(Some(1), iter.next());
+        assert_eq!(None, iter.next_back());
+        assert_eq!(None, iter.next());
+    }
+
+    #[test]
+    fn read_options_start_after() {
+        let bounds = |lo: Bound<&str>| {
+            let lo = match lo {
+                Bound::Included(key) => Bound::Included(UserKey::from(key.as_bytes())),
+                Bound::Excluded(key) => Bound::Excluded(UserKey::from(key.as_bytes())),
+                Bound::Unbounded => Bound::Unbounded,
+            };
+            (lo, Bound::Unbounded)
+        };
+
+        let options = ReadOptions::default().start_after("c");
+
+        assert_eq!(
+            bounds(Bound::Excluded("c")),
+            options.apply_start_after(bounds(Bound::Unbounded))
+        );
+        assert_eq!(
+            bounds(Bound::Excluded("c")),
+            options.apply_start_after(bounds(Bound::Included("a")))
+        );
+        assert_eq!(
+            bounds(Bound::Excluded("c")),
+            options.apply_start_after(bounds(Bound::Included("c")))
+        );
+        assert_eq!(
+            bounds(Bound::Excluded("c")),
+            options.apply_start_after(bounds(Bound::Excluded("c")))
+        );
+        assert_eq!(
+            bounds(Bound::Included("d")),
+            options.apply_start_after(bounds(Bound::Included("d")))
+        );
+        assert_eq!(
+            bounds(Bound::Excluded("d")),
+            options.apply_start_after(bounds(Bound::Excluded("d")))
+        );
+    }
+
+    #[test]
+    fn read_options_cache_policy() {
+        assert_eq!(CachePolicy::Write, ReadOptions::default().cache_policy());
+        assert_eq!(
+            CachePolicy::Read,
+            ReadOptions::default().fill_cache(false).cache_policy()
+        );
+    }
+}
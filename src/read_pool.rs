@@ -0,0 +1,160 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use std::{
+    panic::AssertUnwindSafe,
+    sync::{mpsc, Arc, Mutex, OnceLock},
+    thread::JoinHandle,
+};
+
+/// Work that is done ahead of a scan
+pub type Job = Box<dyn FnOnce() + Send + 'static>;
+
+struct Workers {
+    sender: mpsc::Sender<Job>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+/// Threads that read ahead of the scans of a tree
+///
+/// The threads are only started once the first job is submitted,
+/// and are joined when the pool (and thus the tree) is dropped.
+///
+/// A pool without threads (or whose threads could not be started)
+/// hands every job back to the caller, which then does the work itself.
+pub struct ReadPool {
+    thread_count: usize,
+    workers: OnceLock<Option<Workers>>,
+}
+
+impl ReadPool {
+    /// Creates a pool with the given amount of threads (0 = disabled).
+    pub fn new(thread_count: usize) -> Self {
+        Self {
+            thread_count,
+            workers: OnceLock::new(),
+        }
+    }
+
+    fn start(thread_count: usize) -> Option<Workers> {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let mut handles = Vec::with_capacity(thread_count);
+
+        for _ in 0..thread_count {
+            let receiver = receiver.clone();
+
+            let spawned = std::thread::Builder::new()
+                .name("lsm-read-ahead".into())
+                .spawn(move || loop {
+                    let job = {
+                        let Ok(receiver) = receiver.lock() else {
+                            return;
+                        };
+                        receiver.recv()
+                    };
+
+                    // NOTE: The sender is dropped together with the pool
+                    let Ok(job) = job else {
+                        return;
+                    };
+
+                    // NOTE: A failed job drops its result channel, which the submitter
+                    // reports as an error, so the thread is kept alive for other jobs
+                    if std::panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                        log::error!("Read-ahead job panicked");
+                    }
+                });
+
+            match spawned {
+                Ok(handle) => handles.push(handle),
+                Err(e) => log::warn!("Failed to start read-ahead thread: {e:?}"),
+            }
+        }
+
+        (!handles.is_empty()).then_some(Workers { sender, handles })
+    }
+
+    /// Submits a job to the pool.
+    ///
+    /// Returns the job if the pool has no threads, so the caller can run it itself.
+    pub fn spawn(&self, job: Job) -> Result<(), Job> {
+        if self.thread_count == 0 {
+            return Err(job);
+        }
+
+        let Some(workers) = self.workers.get_or_init(|| Self::start(self.thread_count)) else {
+            return Err(job);
+        };
+
+        workers.sender.send(job).map_err(|mpsc::SendError(job)| job)
+    }
+
+    /// Returns `true` if jobs may be run by the pool.
+    pub fn is_enabled(&self) -> bool {
+        self.thread_count > 0 && !matches!(self.workers.get(), Some(None))
+    }
+}
+
+impl Drop for ReadPool {
+    fn drop(&mut self) {
+        let Some(Some(workers)) = self.workers.take() else {
+            return;
+        };
+
+        // NOTE: Closing the queue stops the threads once they are done with their current job
+        drop(workers.sender);
+
+        for handle in workers.handles {
+            if handle.join().is_err() {
+                log::error!("Read-ahead thread panicked");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn read_pool_runs_jobs() {
+        let pool = ReadPool::new(2);
+        let (sender, receiver) = mpsc::channel();
+
+        for x in 0..10 {
+            let sender = sender.clone();
+            assert!(pool
+                .spawn(Box::new(move || sender.send(x).expect("should send")))
+                .is_ok());
+        }
+        drop(sender);
+
+        let mut results = receiver.iter().collect::<Vec<_>>();
+        results.sort_unstable();
+        assert_eq!((0..10).collect::<Vec<_>>(), results);
+    }
+
+    #[test]
+    fn read_pool_disabled() {
+        let pool = ReadPool::new(0);
+        assert!(!pool.is_enabled());
+        assert!(pool.spawn(Box::new(|| {})).is_err());
+    }
+
+    #[test]
+    fn read_pool_survives_panicking_job() {
+        let pool = ReadPool::new(1);
+        assert!(pool.spawn(Box::new(|| panic!("job failed"))).is_ok());
+
+        let (sender, receiver) = mpsc::channel();
+        assert!(pool
+            .spawn(Box::new(move || sender.send(1).expect("should send")))
+            .is_ok());
+        assert_eq!(Ok(1), receiver.recv());
+    }
+}
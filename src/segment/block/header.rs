@@ -78,7 +78,7 @@ impl Decode for Header {
         reader.read_exact(&mut magic)?;
 
         if magic != MAGIC_BYTES {
-            return Err(DecodeError::InvalidHeader("Block"));
+            return Err(DecodeError::invalid_header("Block", &MAGIC_BYTES, &magic));
         }
 
         let compression = CompressionType::decode_from(reader)?;
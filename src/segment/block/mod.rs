@@ -6,7 +6,10 @@ pub mod checksum;
 pub mod header;
 
 use super::meta::CompressionType;
-use crate::coding::{Decode, Encode};
+use crate::{
+    coding::{Decode, DecodeError, Encode, EncodeError},
+    encryption::SegmentCipher,
+};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use checksum::Checksum;
 use header::Header as BlockHeader;
@@ -23,6 +26,9 @@ impl<T: ItemSize> ItemSize for [T] {
     }
 }
 
+/// Decodes a single item of a block
+pub type ItemDecoder<T> = fn(&mut Cursor<Vec<u8>>) -> Result<T, DecodeError>;
+
 /// A disk-based block
 ///
 /// A block is split into its header and a blob of data.
@@ -40,13 +46,53 @@ pub struct Block<T: Clone + Encode + Decode + ItemSize> {
 
 impl<T: Clone + Encode + Decode + ItemSize> Block<T> {
     pub fn from_reader<R: Read>(reader: &mut R) -> crate::Result<Self> {
+        Self::from_reader_with_cipher(reader, 0, None)
+    }
+
+    /// Reads a block that is stored at the given offset of its file
+    ///
+    /// Encrypted blocks are bound to their offset, so it needs to be known to decrypt them.
+    pub fn from_reader_with_cipher<R: Read>(
+        reader: &mut R,
+        offset: u64,
+        cipher: Option<&SegmentCipher>,
+    ) -> crate::Result<Self> {
+        Self::from_reader_inner(reader, offset, cipher, false, T::decode_from)
+    }
+
+    fn from_reader_inner<R: Read>(
+        reader: &mut R,
+        offset: u64,
+        cipher: Option<&SegmentCipher>,
+        verify_checksum: bool,
+        decode_item: ItemDecoder<T>,
+    ) -> crate::Result<Self> {
+        fail_point!(crate::failpoints::BLOCK_READ);
+
         // Read block header
-        let header = BlockHeader::decode_from(reader)?;
+        //
+        // NOTE: The header has a fixed size, so it is read at once, which keeps
+        // unbuffered (positional) readers at one read for the header
+        let mut header = [0u8; BlockHeader::serialized_len()];
+        reader.read_exact(&mut header)?;
+        let header = BlockHeader::decode_from(&mut &header[..])?;
         log::trace!("Got block header: {header:?}");
 
         let mut bytes = vec![0u8; header.data_length as usize];
         reader.read_exact(&mut bytes)?;
 
+        if verify_checksum {
+            let checksum = Checksum::from_bytes(&bytes);
+
+            if checksum != header.checksum {
+                return Err(crate::Error::InvalidChecksum((checksum, header.checksum)));
+            }
+        }
+
+        if let Some(cipher) = cipher {
+            bytes = cipher.decrypt(offset, &bytes)?;
+        }
+
         let bytes = match header.compression {
             super::meta::CompressionType::None => bytes,
 
@@ -66,10 +112,24 @@ impl<T: Clone + Encode + Decode + ItemSize> Block<T> {
         // Read number of items
         let item_count = bytes.read_u32::<BigEndian>()? as usize;
 
+        // NOTE: The item count may be corrupted, so do not trust it
+        // further than the block could hold (every item takes at least one byte)
+        let max_item_count = bytes.get_ref().len();
+
         // Deserialize each value
-        let mut items = Vec::with_capacity(item_count);
+        let mut items = Vec::with_capacity(item_count.min(max_item_count));
         for _ in 0..item_count {
-            items.push(T::decode_from(&mut bytes)?);
+            let offset = bytes.position();
+
+            // NOTE: Attach the item position, so corrupted items can be located
+            let item = decode_item(&mut bytes).map_err(|e| match e {
+                DecodeError::InvalidTag((name, tag)) => {
+                    DecodeError::InvalidItemTag { name, tag, offset }
+                }
+                e => e.at("BlockItem", offset),
+            })?;
+
+            items.push(item);
         }
 
         Ok(Self {
@@ -81,9 +141,46 @@ impl<T: Clone + Encode + Decode + ItemSize> Block<T> {
     pub fn from_file<R: std::io::Read + std::io::Seek>(
         reader: &mut R,
         offset: u64,
+    ) -> crate::Result<Self> {
+        Self::from_file_with_cipher(reader, offset, None)
+    }
+
+    pub fn from_file_with_cipher<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+        offset: u64,
+        cipher: Option<&SegmentCipher>,
     ) -> crate::Result<Self> {
         reader.seek(std::io::SeekFrom::Start(offset))?;
-        Self::from_reader(reader)
+        Self::from_reader_with_cipher(reader, offset, cipher).map_err(|e| e.at("Block", offset))
+    }
+
+    /// Reads a block, checking its integrity using the checksum in its header
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the checksum does not match.
+    pub fn from_file_checked<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+        offset: u64,
+        cipher: Option<&SegmentCipher>,
+    ) -> crate::Result<Self> {
+        Self::from_file_checked_with(reader, offset, cipher, T::decode_from)
+    }
+
+    /// Reads a block like [`Block::from_file_checked`], decoding its items with the given function
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the checksum does not match.
+    pub fn from_file_checked_with<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+        offset: u64,
+        cipher: Option<&SegmentCipher>,
+        decode_item: ItemDecoder<T>,
+    ) -> crate::Result<Self> {
+        reader.seek(std::io::SeekFrom::Start(offset))?;
+        Self::from_reader_inner(reader, offset, cipher, true, decode_item)
+            .map_err(|e| e.at("Block", offset))
     }
 
     pub fn to_bytes_compressed(
@@ -91,7 +188,45 @@ impl<T: Clone + Encode + Decode + ItemSize> Block<T> {
         previous_block_offset: u64,
         compression: CompressionType,
     ) -> crate::Result<(BlockHeader, Vec<u8>)> {
-        let packed = Self::pack_items(items, compression)?;
+        Self::to_bytes_with_cipher(items, previous_block_offset, compression, None, 0)
+    }
+
+    /// Serializes and compresses the items, then encrypts them if a cipher is given
+    ///
+    /// Encrypted blocks are bound to the offset they are written at.
+    /// The checksum is calculated over the bytes that end up on disk.
+    pub fn to_bytes_with_cipher(
+        items: &[T],
+        previous_block_offset: u64,
+        compression: CompressionType,
+        cipher: Option<&SegmentCipher>,
+        offset: u64,
+    ) -> crate::Result<(BlockHeader, Vec<u8>)> {
+        Self::to_bytes_with_encoder(
+            items,
+            previous_block_offset,
+            compression,
+            cipher,
+            offset,
+            Encode::encode_into,
+        )
+    }
+
+    /// Same as [`Block::to_bytes_with_cipher`], but serializes every item using the given function
+    pub fn to_bytes_with_encoder<F: Fn(&T, &mut Vec<u8>) -> Result<(), EncodeError>>(
+        items: &[T],
+        previous_block_offset: u64,
+        compression: CompressionType,
+        cipher: Option<&SegmentCipher>,
+        offset: u64,
+        encode: F,
+    ) -> crate::Result<(BlockHeader, Vec<u8>)> {
+        let mut packed = Self::pack_items(items, compression, encode)?;
+
+        if let Some(cipher) = cipher {
+            packed = cipher.encrypt(offset, &packed)?;
+        }
+
         let checksum = Checksum::from_bytes(&packed);
 
         let header = BlockHeader {
@@ -111,7 +246,11 @@ impl<T: Clone + Encode + Decode + ItemSize> Block<T> {
         Ok((header, packed))
     }
 
-    fn pack_items(items: &[T], compression: CompressionType) -> crate::Result<Vec<u8>> {
+    fn pack_items<F: Fn(&T, &mut Vec<u8>) -> Result<(), EncodeError>>(
+        items: &[T],
+        compression: CompressionType,
+        encode: F,
+    ) -> crate::Result<Vec<u8>> {
         let mut buf = Vec::with_capacity(u16::MAX.into());
 
         // NOTE: There cannot be 4 billion items in a block
@@ -120,7 +259,7 @@ impl<T: Clone + Encode + Decode + ItemSize> Block<T> {
 
         // Serialize each value
         for value in items {
-            value.encode_into(&mut buf)?;
+            encode(value, &mut buf)?;
         }
 
         Ok(match compression {
@@ -218,4 +357,146 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn disk_block_deserialization_failure_value_type() -> crate::Result<()> {
+        let item1 =
+            InternalValue::from_components(vec![1, 2, 3], vec![4, 5, 6], 42, ValueType::Value);
+        let item2 =
+            InternalValue::from_components(vec![7, 8, 9], vec![10, 11, 12], 43, ValueType::Value);
+
+        let items = vec![item1, item2];
+
+        let mut serialized = Vec::new();
+
+        let (header, data) = ValueBlock::to_bytes_compressed(&items, 0, CompressionType::None)?;
+
+        header.encode_into(&mut serialized)?;
+        serialized.write_all(&data)?;
+
+        // NOTE: Item count (4 bytes) + first item (10 bytes) + seqno of second item (1 byte)
+        if let Some(byte) = serialized.get_mut(BlockHeader::serialized_len() + 15) {
+            *byte = 0xFF;
+        }
+
+        assert!(matches!(
+            ValueBlock::from_reader(&mut Cursor::new(serialized)),
+            Err(crate::Error::Decode(DecodeError::InvalidItemTag {
+                name: "ValueType",
+                tag: 0xFF,
+                offset: 14,
+            }))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn disk_block_deserialization_failure_item_count() -> crate::Result<()> {
+        let items = vec![InternalValue::from_components(
+            vec![1, 2, 3],
+            vec![4, 5, 6],
+            42,
+            ValueType::Value,
+        )];
+
+        let mut serialized = Vec::new();
+
+        let (header, data) = ValueBlock::to_bytes_compressed(&items, 0, CompressionType::None)?;
+
+        header.encode_into(&mut serialized)?;
+        serialized.write_all(&data)?;
+
+        // NOTE: The item count is not trusted to preallocate the items
+        for byte in serialized
+            .iter_mut()
+            .skip(BlockHeader::serialized_len())
+            .take(4)
+        {
+            *byte = 0xFF;
+        }
+
+        assert!(ValueBlock::from_reader(&mut Cursor::new(serialized)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn disk_block_checked_read_detects_corruption() -> crate::Result<()> {
+        let items = vec![InternalValue::from_components(
+            vec![1, 2, 3],
+            vec![4, 5, 6],
+            42,
+            ValueType::Value,
+        )];
+
+        let mut serialized = Vec::new();
+
+        let (header, data) = ValueBlock::to_bytes_compressed(&items, 0, CompressionType::None)?;
+
+        header.encode_into(&mut serialized)?;
+        serialized.write_all(&data)?;
+
+        let block = ValueBlock::from_file_checked(&mut Cursor::new(serialized.clone()), 0, None)?;
+        assert_eq!(1, block.items.len());
+
+        if let Some(byte) = serialized.last_mut() {
+            *byte ^= 0xFF;
+        }
+
+        assert!(matches!(
+            ValueBlock::from_file_checked(&mut Cursor::new(serialized), 0, None),
+            Err(crate::Error::InvalidChecksum(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn disk_block_deserialization_failure_magic() -> crate::Result<()> {
+        let items = vec![InternalValue::from_components(
+            vec![1, 2, 3],
+            vec![4, 5, 6],
+            42,
+            ValueType::Value,
+        )];
+
+        // NOTE: Block is preceded by some other data
+        let mut serialized = vec![0; 7];
+
+        let (header, data) = ValueBlock::to_bytes_compressed(&items, 0, CompressionType::None)?;
+
+        header.encode_into(&mut serialized)?;
+        serialized.write_all(&data)?;
+
+        if let Some(byte) = serialized.get_mut(7) {
+            *byte = b'X';
+        }
+
+        let Err(crate::Error::Decode(e)) = ValueBlock::from_file(&mut Cursor::new(serialized), 7)
+        else {
+            panic!("should fail to decode");
+        };
+
+        assert!(matches!(
+            &e,
+            DecodeError::At {
+                name: "Block",
+                offset: 7,
+                source,
+                ..
+            } if matches!(
+                &**source,
+                DecodeError::InvalidHeader { name: "Block", expected, found }
+                    if *expected == crate::file::MAGIC_BYTES && found.first() == Some(&b'X')
+            )
+        ));
+
+        assert_eq!(
+            "DecodeError(Block at offset 7: invalid Block header: expected [4c, 53, 4d, 02], found [58, 53, 4d, 02])",
+            e.to_string(),
+        );
+
+        Ok(())
+    }
 }
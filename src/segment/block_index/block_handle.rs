@@ -20,21 +20,42 @@ pub struct KeyedBlockHandle {
 
     /// Position of block in file
     pub offset: u64,
+
+    /// Amount of items in the data block (or all data blocks of the index block)
+    ///
+    /// Always 0 in segments whose index entries do not store item counts.
+    pub item_count: u32,
 }
 
 impl KeyedBlockHandle {
     #[must_use]
-    pub fn new<K: Into<Slice>>(end_key: K, offset: u64) -> Self {
+    pub fn new<K: Into<Slice>>(end_key: K, offset: u64, item_count: u32) -> Self {
         Self {
             end_key: end_key.into(),
             offset,
+            item_count,
         }
     }
+
+    /// Decodes an index entry of a segment whose index entries do not store item counts.
+    pub fn decode_without_item_count<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let offset = reader.read_u64_varint()?;
+
+        let key_len = reader.read_u16_varint()?;
+        let mut key = vec![0; key_len.into()];
+        reader.read_exact(&mut key)?;
+
+        Ok(Self {
+            offset,
+            end_key: Slice::from(key),
+            item_count: 0,
+        })
+    }
 }
 
 impl ItemSize for KeyedBlockHandle {
     fn size(&self) -> usize {
-        std::mem::size_of::<u64>() + self.end_key.len()
+        std::mem::size_of::<u64>() + std::mem::size_of::<u32>() + self.end_key.len()
     }
 }
 
@@ -72,6 +93,8 @@ impl Encode for KeyedBlockHandle {
         writer.write_u16_varint(self.end_key.len() as u16)?;
         writer.write_all(&self.end_key)?;
 
+        writer.write_u32_varint(self.item_count)?;
+
         Ok(())
     }
 }
@@ -81,29 +104,41 @@ impl Decode for KeyedBlockHandle {
     where
         Self: Sized,
     {
-        let offset = reader.read_u64_varint()?;
-
-        let key_len = reader.read_u16_varint()?;
-        let mut key = vec![0; key_len.into()];
-        reader.read_exact(&mut key)?;
-
-        Ok(Self {
-            offset,
-            end_key: Slice::from(key),
-        })
+        let mut handle = Self::decode_without_item_count(reader)?;
+        handle.item_count = reader.read_u32_varint()?;
+        Ok(handle)
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::expect_used)]
 mod tests {
     use super::*;
 
     #[test]
     fn index_block_size() {
         let items = [
-            KeyedBlockHandle::new("abcd", 5),
-            KeyedBlockHandle::new("efghij", 10),
+            KeyedBlockHandle::new("abcd", 5, 1),
+            KeyedBlockHandle::new("efghij", 10, 1),
         ];
-        assert_eq!(26, items.size());
+        assert_eq!(34, items.size());
+    }
+
+    #[test]
+    fn index_entry_without_item_count() -> crate::Result<()> {
+        let handle = KeyedBlockHandle::new("abcd", 5, 7);
+
+        let bytes = handle.encode_into_vec()?;
+        let decoded = KeyedBlockHandle::decode_from(&mut &bytes[..])?;
+        assert_eq!(7, decoded.item_count);
+
+        // NOTE: Older segments end the index entry after the key
+        let legacy = bytes.get(..bytes.len() - 1).expect("should be in bounds");
+        let decoded = KeyedBlockHandle::decode_without_item_count(&mut &legacy[..])?;
+        assert_eq!(handle, decoded);
+        assert_eq!(&*handle.end_key, &*decoded.end_key);
+        assert_eq!(0, decoded.item_count);
+
+        Ok(())
     }
 }
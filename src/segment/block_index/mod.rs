@@ -8,10 +8,40 @@ pub mod two_level_index;
 pub mod writer;
 
 use super::{block::Block, value_block::CachePolicy};
+use crate::encryption::SegmentCipher;
 use block_handle::KeyedBlockHandle;
+use std::io::{Read, Seek};
 
 pub type IndexBlock = Block<KeyedBlockHandle>;
 
+impl IndexBlock {
+    /// Reads an index block, checking its integrity
+    ///
+    /// If `item_counts` is `false`, the segment was written by an older version,
+    /// whose index entries do not store the item count of their block.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the block is corrupted.
+    pub fn from_segment_file<R: Read + Seek>(
+        reader: &mut R,
+        offset: u64,
+        cipher: Option<&SegmentCipher>,
+        item_counts: bool,
+    ) -> crate::Result<Self> {
+        if item_counts {
+            Self::from_file_checked(reader, offset, cipher)
+        } else {
+            Self::from_file_checked_with(
+                reader,
+                offset,
+                cipher,
+                KeyedBlockHandle::decode_without_item_count,
+            )
+        }
+    }
+}
+
 impl BlockIndex for [KeyedBlockHandle] {
     fn get_lowest_block_containing_key(
         &self,
@@ -84,6 +114,7 @@ mod tests {
         KeyedBlockHandle {
             end_key: end_key.into(),
             offset,
+            item_count: 0,
         }
     }
 
@@ -3,7 +3,10 @@
 // (found in the LICENSE-* files in the repository)
 
 use super::{block_handle::KeyedBlockHandle, BlockIndex};
-use crate::segment::{block_index::IndexBlock, value_block::CachePolicy};
+use crate::{
+    encryption::SegmentCipher,
+    segment::{block_index::IndexBlock, value_block::CachePolicy},
+};
 use std::{fs::File, path::Path};
 
 /// The block index stores references to the positions of blocks on a file and their size
@@ -36,13 +39,26 @@ impl TopLevelIndex {
     }
 
     /// Loads a top-level index from disk
-    pub fn from_file<P: AsRef<Path>>(path: P, offset: u64) -> crate::Result<Self> {
+    ///
+    /// See [`IndexBlock::from_segment_file`] for `item_counts`.
+    pub fn from_file<P: AsRef<Path>>(
+        path: P,
+        offset: u64,
+        cipher: Option<&SegmentCipher>,
+        item_counts: bool,
+    ) -> crate::Result<Self> {
         let path = path.as_ref();
         log::trace!("reading TLI from {path:?}, offset={offset}");
 
         let mut file = File::open(path)?;
 
-        let items = IndexBlock::from_file(&mut file, offset)?.items;
+        // NOTE: Check the TLI, so index corruption is detected on recovery
+        let items = IndexBlock::from_segment_file(&mut file, offset, cipher, item_counts)
+            .map_err(|e| {
+                log::error!("Failed to load TLI of {}: {e:?}", path.display());
+                e
+            })?
+            .items;
         log::trace!("loaded TLI ({path:?}): {items:?}");
 
         debug_assert!(!items.is_empty());
@@ -60,6 +76,10 @@ impl TopLevelIndex {
         self.len() == 0
     }
 
+    pub(crate) fn handles(&self) -> &[KeyedBlockHandle] {
+        &self.0
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &KeyedBlockHandle> {
         self.0.iter()
     }
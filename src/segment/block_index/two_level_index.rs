@@ -8,8 +8,22 @@ use super::{
     top_level::TopLevelIndex,
     BlockIndex, IndexBlock,
 };
-use crate::{block_cache::BlockCache, descriptor_table::FileDescriptorTable};
-use std::{path::Path, sync::Arc};
+use crate::{
+    block_cache::BlockCache,
+    descriptor_table::FileDescriptorTable,
+    encryption::SegmentCipher,
+    error::{ErrorContext, Operation},
+    metrics::{MetricsSink, ReadSampler, BLOCK_CACHE_HITS, BLOCK_CACHE_MISSES},
+    mlock::MemoryLock,
+    read_pool::ReadPool,
+};
+use std::{
+    path::Path,
+    sync::{Arc, OnceLock},
+};
+
+/// Index blocks that are pinned in memory, by offset
+type PinnedIndexBlocks = Box<[(u64, Arc<IndexBlock>)]>;
 
 /// Allows reading index blocks - just a wrapper around a block cache
 #[allow(clippy::module_name_repetitions)]
@@ -38,10 +52,17 @@ pub struct TwoLevelBlockIndex {
     /// Segment ID
     segment_id: GlobalSegmentId,
 
-    /// Level-0 index. Is read-only and always fully loaded.
+    /// Level-0 index. Is read-only and fully loaded, either on recovery,
+    /// or on first access (see [`TwoLevelBlockIndex::from_file_lazy`]).
     ///
     /// This index points to index blocks inside the level-1 index.
-    pub(crate) top_level_index: TopLevelIndex,
+    top_level_index: OnceLock<TopLevelIndex>,
+
+    /// File offset of the level-0 index
+    tli_ptr: u64,
+
+    /// If `true`, index entries store the item count of their block
+    pub(crate) item_counts: bool,
 
     /// Level-1 index. This index is only partially loaded into memory, decreasing memory usage, compared to a fully loaded one.
     ///
@@ -50,9 +71,71 @@ pub struct TwoLevelBlockIndex {
     /// To find a reference to a segment block, first the level-0 index needs to be checked,
     /// then the corresponding index block needs to be loaded, which contains the wanted disk block handle.
     index_block_fetcher: IndexBlockFetcher,
+
+    /// Index blocks that are pinned in memory, sorted by offset
+    ///
+    /// Pinned index blocks bypass the block cache, so they are never evicted.
+    pinned_index_blocks: Option<PinnedIndexBlocks>,
+
+    /// Lock of the top-level index & pinned index blocks, if they are locked into RAM
+    memory_lock: MemoryLock,
+
+    /// Sink that receives block cache hits & misses
+    pub(crate) metrics: Option<Arc<dyn MetricsSink>>,
+
+    /// Sampler of point reads
+    pub(crate) read_sampler: Option<ReadSampler>,
+
+    /// Amount of data blocks that are prefetched ahead of forward scans
+    pub(crate) readahead: usize,
+
+    /// Amount of bytes that forward scans request per I/O on block cache misses
+    pub(crate) readahead_bytes: usize,
+
+    /// Threads of the tree that prefetch data blocks ahead of forward scans
+    pub(crate) read_pool: Option<Arc<ReadPool>>,
 }
 
 impl TwoLevelBlockIndex {
+    /// Returns the level-0 index, loading it on first access.
+    pub(crate) fn top_level_index(&self) -> crate::Result<&TopLevelIndex> {
+        if let Some(tli) = self.top_level_index.get() {
+            return Ok(tli);
+        }
+
+        log::trace!("lazily loading TLI of segment {:?}", self.segment_id);
+
+        // NOTE: The segment file is registered in the descriptor table for as long as the segment exists
+        #[allow(clippy::expect_used)]
+        let file_guard = self
+            .descriptor_table
+            .access(&self.segment_id)?
+            .expect("should acquire file handle");
+
+        let items = IndexBlock::from_segment_file(
+            &mut file_guard.reader(),
+            self.tli_ptr,
+            file_guard.cipher.as_ref(),
+            self.item_counts,
+        )
+        .map_err(|e| {
+            log::error!("Failed to load TLI of segment {:?}: {e:?}", self.segment_id);
+            e.with_context(
+                ErrorContext::new(Operation::Read)
+                    .with_segment_id(self.segment_id.segment_id())
+                    .with_block_offset(self.tli_ptr),
+            )
+        })?
+        .items;
+
+        drop(file_guard);
+
+        // NOTE: If another thread raced us, its TLI is kept, which is identical
+        Ok(self
+            .top_level_index
+            .get_or_init(|| TopLevelIndex::from_boxed_slice(items)))
+    }
+
     /// Gets the lowest block handle that may contain the given item
     pub fn get_lowest_data_block_handle_containing_item(
         &self,
@@ -60,7 +143,7 @@ impl TwoLevelBlockIndex {
         cache_policy: CachePolicy,
     ) -> crate::Result<Option<KeyedBlockHandle>> {
         let Some(index_block_handle) = self
-            .top_level_index
+            .top_level_index()?
             .get_lowest_block_containing_key(key, cache_policy)
             .expect("cannot fail")
         else {
@@ -76,6 +159,40 @@ impl TwoLevelBlockIndex {
             .cloned())
     }
 
+    /// Gets the lowest block handle that may contain the given item,
+    /// without doing any disk I/O
+    ///
+    /// Returns `None` if the level-0 index or the needed index block is not in memory.
+    // NOTE: Lookups in loaded index blocks cannot fail
+    #[allow(clippy::expect_used)]
+    pub fn get_cached_lowest_data_block_handle_containing_item(
+        &self,
+        key: &[u8],
+    ) -> Option<Option<KeyedBlockHandle>> {
+        let Some(index_block_handle) = self
+            .top_level_index
+            .get()?
+            .get_lowest_block_containing_key(key, CachePolicy::Read)
+            .expect("cannot fail")
+        else {
+            return Some(None);
+        };
+
+        let offset = index_block_handle.offset;
+
+        let index_block = self
+            .get_pinned_index_block(offset)
+            .or_else(|| self.index_block_fetcher.get(self.segment_id, offset))?;
+
+        Some(
+            index_block
+                .items
+                .get_lowest_block_containing_key(key, CachePolicy::Read)
+                .expect("cannot fail")
+                .cloned(),
+        )
+    }
+
     /// Gets the last block handle that may contain the given item
     pub fn get_last_data_block_handle_containing_item(
         &self,
@@ -83,7 +200,7 @@ impl TwoLevelBlockIndex {
         cache_policy: CachePolicy,
     ) -> crate::Result<Option<KeyedBlockHandle>> {
         let Some(index_block_handle) = self
-            .top_level_index
+            .top_level_index()?
             .get_last_block_containing_key(key, cache_policy)
             .expect("cannot fail")
         else {
@@ -104,7 +221,7 @@ impl TwoLevelBlockIndex {
         cache_policy: CachePolicy,
     ) -> crate::Result<KeyedBlockHandle> {
         let index_block_handle = self
-            .top_level_index
+            .top_level_index()?
             .get_last_block_handle(cache_policy)
             .expect("cannot fail");
 
@@ -117,6 +234,12 @@ impl TwoLevelBlockIndex {
             .clone())
     }
 
+    fn get_pinned_index_block(&self, offset: u64) -> Option<Arc<IndexBlock>> {
+        let pinned = self.pinned_index_blocks.as_ref()?;
+        let idx = pinned.binary_search_by_key(&offset, |(x, _)| *x).ok()?;
+        pinned.get(idx).map(|(_, block)| block.clone())
+    }
+
     /// Loads an index block from disk
     pub fn load_index_block(
         &self,
@@ -125,24 +248,38 @@ impl TwoLevelBlockIndex {
     ) -> crate::Result<Arc<IndexBlock>> {
         log::trace!("loading index block {:?}/{block_handle:?}", self.segment_id);
 
+        if let Some(block) = self.get_pinned_index_block(block_handle.offset) {
+            return Ok(block);
+        }
+
         if let Some(block) = self
             .index_block_fetcher
             .get(self.segment_id, block_handle.offset)
         {
             // Cache hit: Copy from block
 
+            if let Some(sink) = &self.metrics {
+                sink.counter(BLOCK_CACHE_HITS, 1);
+            }
+
             Ok(block)
         } else {
             // Cache miss: load from disk
 
+            if let Some(sink) = &self.metrics {
+                sink.counter(BLOCK_CACHE_MISSES, 1);
+            }
+
             let file_guard = self
                 .descriptor_table
                 .access(&self.segment_id)?
                 .expect("should acquire file handle");
 
-            let block = IndexBlock::from_file(
-                &mut *file_guard.file.lock().expect("lock is poisoned"),
+            let block = IndexBlock::from_segment_file(
+                &mut file_guard.reader(),
                 block_handle.offset,
+                file_guard.cipher.as_ref(),
+                self.item_counts,
             )
             .map_err(|e| {
                 log::error!(
@@ -150,7 +287,11 @@ impl TwoLevelBlockIndex {
                     self.segment_id,
                     block_handle.offset
                 );
-                e
+                e.with_context(
+                    ErrorContext::new(Operation::Read)
+                        .with_segment_id(self.segment_id.segment_id())
+                        .with_block_offset(block_handle.offset),
+                )
             })?;
             // TODO: ^ inspect_err instead: 1.76
 
@@ -179,7 +320,16 @@ impl TwoLevelBlockIndex {
             descriptor_table: Arc::new(FileDescriptorTable::new(512, 1)),
             segment_id,
             index_block_fetcher: index_block_index,
-            top_level_index: TopLevelIndex::from_boxed_slice(Box::default()),
+            pinned_index_blocks: None,
+            memory_lock: MemoryLock::default(),
+            top_level_index: OnceLock::from(TopLevelIndex::from_boxed_slice(Box::default())),
+            tli_ptr: 0,
+            item_counts: true,
+            metrics: None,
+            read_sampler: None,
+            readahead: 0,
+            readahead_bytes: 0,
+            read_pool: None,
         }
     }
 
@@ -189,17 +339,166 @@ impl TwoLevelBlockIndex {
         segment_id: GlobalSegmentId,
         descriptor_table: Arc<FileDescriptorTable>,
         block_cache: Arc<BlockCache>,
+        cipher: Option<&SegmentCipher>,
+        item_counts: bool,
     ) -> crate::Result<Self> {
         let file_path = file_path.as_ref();
         log::trace!("Reading block index from {file_path:?}");
 
-        let top_level_index = TopLevelIndex::from_file(file_path, offset)?;
+        let top_level_index = TopLevelIndex::from_file(file_path, offset, cipher, item_counts)?;
 
         Ok(Self {
             descriptor_table,
             segment_id,
-            top_level_index,
+            top_level_index: OnceLock::from(top_level_index),
+            tli_ptr: offset,
+            item_counts,
             index_block_fetcher: IndexBlockFetcher(block_cache),
+            pinned_index_blocks: None,
+            memory_lock: MemoryLock::default(),
+            metrics: None,
+            read_sampler: None,
+            readahead: 0,
+            readahead_bytes: 0,
+            read_pool: None,
         })
     }
+
+    /// Creates a block index that loads its top-level index on first access,
+    /// through the segment's file descriptor.
+    ///
+    /// The top-level index is not checked for corruption until then.
+    #[must_use]
+    pub fn from_file_lazy(
+        offset: u64,
+        segment_id: GlobalSegmentId,
+        descriptor_table: Arc<FileDescriptorTable>,
+        block_cache: Arc<BlockCache>,
+        item_counts: bool,
+    ) -> Self {
+        Self {
+            descriptor_table,
+            segment_id,
+            top_level_index: OnceLock::new(),
+            tli_ptr: offset,
+            item_counts,
+            index_block_fetcher: IndexBlockFetcher(block_cache),
+            pinned_index_blocks: None,
+            memory_lock: MemoryLock::default(),
+            metrics: None,
+            read_sampler: None,
+            readahead: 0,
+            readahead_bytes: 0,
+            read_pool: None,
+        }
+    }
+
+    /// Returns `true` if the top-level index is loaded into memory.
+    #[must_use]
+    pub fn is_loaded(&self) -> bool {
+        self.top_level_index.get().is_some()
+    }
+
+    /// Sets the sink that receives block cache hits & misses of the segment
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: Option<Arc<dyn MetricsSink>>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Sets the sampler of point reads of the segment
+    #[must_use]
+    pub fn with_read_sampler(mut self, read_sampler: Option<ReadSampler>) -> Self {
+        self.read_sampler = read_sampler;
+        self
+    }
+
+    /// Sets the amount of data blocks that are prefetched ahead of forward scans
+    #[must_use]
+    pub fn with_readahead(mut self, depth: usize) -> Self {
+        self.readahead = depth;
+        self
+    }
+
+    /// Sets the amount of bytes that forward scans request per I/O on block cache misses
+    #[must_use]
+    pub fn with_readahead_bytes(mut self, bytes: usize) -> Self {
+        self.readahead_bytes = bytes;
+        self
+    }
+
+    /// Sets the threads that prefetch data blocks ahead of forward scans
+    #[must_use]
+    pub fn with_read_pool(mut self, read_pool: Arc<ReadPool>) -> Self {
+        self.read_pool = Some(read_pool);
+        self
+    }
+
+    /// Loads all index blocks and pins them in memory, if enabled.
+    ///
+    /// This also loads the top-level index, if it is loaded lazily.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn with_pinned_index_blocks(mut self, enabled: bool) -> crate::Result<Self> {
+        if !enabled {
+            return Ok(self);
+        }
+
+        let mut pinned = self
+            .top_level_index()?
+            .iter()
+            .map(|handle| {
+                let block = self.load_index_block(handle, CachePolicy::Read)?;
+                Ok((handle.offset, block))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        pinned.sort_by_key(|(offset, _)| *offset);
+
+        self.pinned_index_blocks = Some(pinned.into_boxed_slice());
+
+        Ok(self)
+    }
+
+    /// Tries to lock the top-level index and all index blocks into RAM, if enabled.
+    ///
+    /// Index blocks that are not pinned yet are loaded and pinned, so no index block
+    /// of the segment is read through the block cache, where it could not stay locked.
+    /// Locking is best-effort, see [`crate::Config::mlock_index_and_filters`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn with_memory_lock(mut self, enabled: bool) -> crate::Result<Self> {
+        fn lock_handles(memory_lock: &mut MemoryLock, handles: &[KeyedBlockHandle]) {
+            memory_lock.lock(handles);
+
+            for handle in handles {
+                memory_lock.lock::<u8>(&handle.end_key);
+            }
+        }
+
+        if !enabled {
+            return Ok(self);
+        }
+
+        if self.pinned_index_blocks.is_none() {
+            self = self.with_pinned_index_blocks(true)?;
+        }
+
+        let mut memory_lock = MemoryLock::default();
+
+        if let Some(top_level_index) = self.top_level_index.get() {
+            lock_handles(&mut memory_lock, top_level_index.handles());
+        }
+
+        for (_, block) in self.pinned_index_blocks.iter().flat_map(|x| x.iter()) {
+            lock_handles(&mut memory_lock, &block.items);
+        }
+
+        self.memory_lock = memory_lock;
+        Ok(self)
+    }
 }
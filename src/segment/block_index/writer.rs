@@ -5,44 +5,89 @@
 use super::{IndexBlock, KeyedBlockHandle};
 use crate::{
     coding::Encode,
-    segment::{block::header::Header as BlockHeader, meta::CompressionType},
+    compaction::warm::CacheWarmer,
+    encryption::SegmentCipher,
+    segment::{
+        block::header::Header as BlockHeader,
+        meta::{CompressionType, SegmentId},
+    },
     value::UserKey,
 };
 use std::{
     fs::File,
     io::{BufWriter, Seek, Write},
+    sync::Arc,
 };
 
-pub struct Writer {
-    file_pos: u64,
+/// Returns the shortest key `s` with `lo <= s < hi`, if `lo < hi`, otherwise `lo`
+///
+/// Keys are compared bytewise, so the separator of two adjacent data blocks
+/// (the last key of the first block, and the first key of the second block)
+/// can be used as end key of the first block in the block index.
+#[must_use]
+pub fn shortest_separator(lo: &[u8], hi: &[u8]) -> UserKey {
+    let common_len = lo.iter().zip(hi).take_while(|(a, b)| a == b).count();
+
+    // NOTE: If `lo` is a prefix of `hi`, it is the shortest separator
+    let (Some(&lo_byte), Some(&hi_byte)) = (lo.get(common_len), hi.get(common_len)) else {
+        return lo.into();
+    };
+
+    if lo_byte > hi_byte {
+        return lo.into();
+    }
+
+    // NOTE: The first differing byte of `hi` is larger than the one of `lo`, so its
+    // prefix up to that byte is larger than `lo`, and smaller than `hi`, if `hi` is longer
+    if hi.len() > common_len + 1 {
+        return hi.get(..=common_len).unwrap_or(hi).into();
+    }
+
+    if lo_byte + 1 < hi_byte {
+        let mut separator = lo.get(..=common_len).unwrap_or(lo).to_vec();
+
+        if let Some(last) = separator.last_mut() {
+            *last += 1;
+        }
 
-    prev_pos: (u64, u64),
+        return separator.into();
+    }
 
-    write_buffer: Vec<u8>,
+    lo.into()
+}
 
+pub struct Writer {
     block_size: u32,
     compression: CompressionType,
+    cipher: Option<SegmentCipher>,
 
     buffer_size: u32,
 
     block_handles: Vec<KeyedBlockHandle>,
-    tli_pointers: Vec<KeyedBlockHandle>,
+
+    /// Sealed index blocks
+    ///
+    /// They are serialized once the index is written, because
+    /// encrypted blocks are bound to their final position in the file.
+    index_blocks: Vec<Vec<KeyedBlockHandle>>,
 
     pub block_count: usize,
+
+    /// Inserts index blocks that point to hot data blocks into the block cache
+    cache_warmer: Option<(Arc<CacheWarmer>, SegmentId)>,
 }
 
 impl Writer {
     pub fn new(block_size: u32) -> crate::Result<Self> {
         Ok(Self {
-            file_pos: 0,
-            prev_pos: (0, 0),
-            write_buffer: Vec::with_capacity(block_size as usize),
             buffer_size: 0,
             block_size,
             compression: CompressionType::None,
+            cipher: None,
             block_handles: Vec::with_capacity(1_000),
-            tli_pointers: Vec::with_capacity(1_000),
+            index_blocks: Vec::new(),
             block_count: 0,
+            cache_warmer: None,
         })
     }
 
@@ -52,47 +97,38 @@ impl Writer {
         self
     }
 
-    fn write_block(&mut self) -> crate::Result<()> {
-        // Write to file
-        let (header, data) = IndexBlock::to_bytes_compressed(
-            &self.block_handles,
-            self.prev_pos.0,
-            self.compression,
-        )?;
-
-        header.encode_into(&mut self.write_buffer)?;
-        self.write_buffer.write_all(&data)?;
-
-        let bytes_written = (BlockHeader::serialized_len() + data.len()) as u64;
-
-        // NOTE: Expect is fine, because the chunk is not empty
-        #[allow(clippy::expect_used)]
-        let last = self
-            .block_handles
-            .last()
-            .expect("Chunk should not be empty");
+    #[must_use]
+    pub fn use_cipher(mut self, cipher: Option<SegmentCipher>) -> Self {
+        self.cipher = cipher;
+        self
+    }
 
-        let index_block_handle = KeyedBlockHandle {
-            end_key: last.end_key.clone(),
-            offset: self.file_pos,
-        };
+    #[must_use]
+    pub fn use_cache_warmer(
+        mut self,
+        cache_warmer: Option<Arc<CacheWarmer>>,
+        segment_id: SegmentId,
+    ) -> Self {
+        self.cache_warmer = cache_warmer.map(|cache_warmer| (cache_warmer, segment_id));
+        self
+    }
 
-        self.tli_pointers.push(index_block_handle);
+    fn seal_block(&mut self) {
+        self.index_blocks.push(std::mem::replace(
+            &mut self.block_handles,
+            Vec::with_capacity(1_000),
+        ));
 
         self.buffer_size = 0;
-        self.file_pos += bytes_written;
-
-        self.prev_pos.0 = self.prev_pos.1;
-        self.prev_pos.1 += bytes_written;
-
         self.block_count += 1;
-
-        self.block_handles.clear();
-
-        Ok(())
     }
 
-    pub fn register_block(&mut self, start_key: UserKey, offset: u64) -> crate::Result<()> {
+    pub fn register_block(
+        &mut self,
+        start_key: UserKey,
+        offset: u64,
+        item_count: u32,
+    ) -> crate::Result<()> {
         // NOTE: Truncation is OK, because a key is bound by 65535 bytes, so can never exceed u32s
         #[allow(clippy::cast_possible_truncation)]
         let block_handle_size = (start_key.len() + std::mem::size_of::<KeyedBlockHandle>()) as u32;
@@ -100,6 +136,7 @@ impl Writer {
         let block_handle = KeyedBlockHandle {
             end_key: start_key,
             offset,
+            item_count,
         };
 
         self.block_handles.push(block_handle);
@@ -107,41 +144,102 @@ impl Writer {
         self.buffer_size += block_handle_size;
 
         if self.buffer_size >= self.block_size {
-            self.write_block()?;
+            self.seal_block();
         }
 
         Ok(())
     }
 
-    fn write_top_level_index(
+    /// Writes the index blocks, returning the pointers to them
+    fn write_index_blocks(
         &mut self,
         block_file_writer: &mut BufWriter<File>,
-        file_offset: u64,
-    ) -> crate::Result<u64> {
-        block_file_writer.write_all(&self.write_buffer)?;
-        let tli_ptr = block_file_writer.stream_position()?;
+        index_block_ptr: u64,
+    ) -> crate::Result<Vec<KeyedBlockHandle>> {
+        let mut tli_pointers = Vec::with_capacity(self.index_blocks.len());
+
+        // NOTE: Offsets of previous blocks are relative to the first index block
+        let mut prev_pos = (0, 0);
+
+        for block_handles in self.index_blocks.drain(..) {
+            let offset = index_block_ptr + prev_pos.1;
+
+            let (header, data) = IndexBlock::to_bytes_with_cipher(
+                &block_handles,
+                prev_pos.0,
+                self.compression,
+                self.cipher.as_ref(),
+                offset,
+            )?;
+
+            header.encode_into(block_file_writer)?;
+            block_file_writer.write_all(&data)?;
+
+            let bytes_written = (BlockHeader::serialized_len() + data.len()) as u64;
+
+            // NOTE: Expect is fine, because the chunk is not empty
+            #[allow(clippy::expect_used)]
+            let last = block_handles.last().expect("Chunk should not be empty");
+
+            let item_count = block_handles
+                .iter()
+                .fold(0_u32, |acc, handle| acc.saturating_add(handle.item_count));
+
+            tli_pointers.push(KeyedBlockHandle {
+                end_key: last.end_key.clone(),
+                offset,
+                item_count,
+            });
+
+            if let Some((cache_warmer, segment_id)) = &self.cache_warmer {
+                if cache_warmer.contains_any(*segment_id, &block_handles) {
+                    cache_warmer.index_block_written(
+                        *segment_id,
+                        offset,
+                        IndexBlock {
+                            header,
+                            items: block_handles.into(),
+                        },
+                    );
+                }
+            }
+
+            prev_pos.0 = prev_pos.1;
+            prev_pos.1 += bytes_written;
+        }
 
         log::trace!("Concatted index blocks onto blocks file");
 
-        for item in &mut self.tli_pointers {
-            item.offset += file_offset;
-        }
+        Ok(tli_pointers)
+    }
+
+    fn write_top_level_index(
+        &self,
+        block_file_writer: &mut BufWriter<File>,
+        tli_pointers: &[KeyedBlockHandle],
+    ) -> crate::Result<u64> {
+        let tli_ptr = block_file_writer.stream_position()?;
 
         // Write to file
-        let (header, data) =
-            IndexBlock::to_bytes_compressed(&self.tli_pointers, 0, self.compression)?;
+        let (header, data) = IndexBlock::to_bytes_with_cipher(
+            tli_pointers,
+            0,
+            self.compression,
+            self.cipher.as_ref(),
+            tli_ptr,
+        )?;
 
         header.encode_into(block_file_writer)?;
         block_file_writer.write_all(&data)?;
 
         let bytes_written = BlockHeader::serialized_len() + data.len();
 
+        // NOTE: The segment writer fsyncs the whole file, once it is finished
         block_file_writer.flush()?;
-        block_file_writer.get_mut().sync_all()?;
 
         log::trace!(
             "Written top level index, with {} pointers ({} bytes)",
-            self.tli_pointers.len(),
+            tli_pointers.len(),
             bytes_written,
         );
 
@@ -151,12 +249,62 @@ impl Writer {
     /// Returns the offset in the file to TLI
     pub fn finish(&mut self, block_file_writer: &mut BufWriter<File>) -> crate::Result<u64> {
         if self.buffer_size > 0 {
-            self.write_block()?;
+            self.seal_block();
         }
 
         let index_block_ptr = block_file_writer.stream_position()?;
-        let tli_ptr = self.write_top_level_index(block_file_writer, index_block_ptr)?;
+        let tli_pointers = self.write_index_blocks(block_file_writer, index_block_ptr)?;
 
-        Ok(tli_ptr)
+        self.write_top_level_index(block_file_writer, &tli_pointers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn shortest_separator_basic() {
+        assert_eq!(b"abd", &*shortest_separator(b"abc123", b"abdxyz"));
+        assert_eq!(b"b", &*shortest_separator(b"abc", b"bcd"));
+        assert_eq!(b"abc", &*shortest_separator(b"abc", b"abcdef"));
+    }
+
+    #[test]
+    fn shortest_separator_increment() {
+        assert_eq!(b"abc", &*shortest_separator(b"abb123", b"abd"));
+        assert_eq!(b"abc123", &*shortest_separator(b"abc123", b"abd"));
+        assert_eq!(&[0, 255, 1], &*shortest_separator(&[0, 255, 1], &[1]));
+    }
+
+    #[test]
+    fn shortest_separator_not_smaller() {
+        assert_eq!(b"abc", &*shortest_separator(b"abc", b"abc"));
+        assert_eq!(b"abd", &*shortest_separator(b"abd", b"abc"));
+    }
+
+    #[test]
+    fn shortest_separator_bounds() {
+        let keys: Vec<Vec<u8>> = (0..1_000u64)
+            .step_by(7)
+            .map(|x| x.to_be_bytes().to_vec())
+            .chain([
+                b"a".to_vec(),
+                b"ab".to_vec(),
+                b"abc".to_vec(),
+                b"b".to_vec(),
+            ])
+            .collect();
+
+        for lo in &keys {
+            for hi in &keys {
+                if lo < hi {
+                    let separator = shortest_separator(lo, hi);
+                    assert!(lo.as_slice() <= &*separator);
+                    assert!(&*separator < hi.as_slice());
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,175 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{
+    block::header::Header as BlockHeader,
+    block_index::{block_handle::KeyedBlockHandle, top_level::TopLevelIndex, IndexBlock},
+    file_offsets::FileOffsets,
+    meta::Metadata,
+    trailer::SegmentFileTrailer,
+    value_block::ValueBlock,
+};
+use crate::{
+    encryption::{Cipher, SegmentCipher},
+    InternalValue,
+};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+/// Parses a segment file, without needing a tree
+///
+/// Only the trailer, metadata and block index are read eagerly,
+/// data blocks can be decoded using [`SegmentInspector::blocks`].
+///
+/// # Errors
+///
+/// Will return `Err` if an IO error occurs, the segment is corrupted or encrypted.
+pub fn inspect<P: AsRef<Path>>(path: P) -> crate::Result<SegmentInspector> {
+    inspect_with_cipher(path, None)
+}
+
+/// Parses a (possibly encrypted) segment file, without needing a tree
+///
+/// See [`inspect`].
+///
+/// # Errors
+///
+/// Will return `Err` if an IO error occurs, the segment is corrupted,
+/// or it is encrypted, but no cipher is given.
+pub fn inspect_with_cipher<P: AsRef<Path>>(
+    path: P,
+    cipher: Option<&Cipher>,
+) -> crate::Result<SegmentInspector> {
+    let path = path.as_ref();
+    log::debug!("Inspecting segment file {}", path.display());
+
+    let trailer = SegmentFileTrailer::from_file(path, cipher)?;
+    let cipher = trailer.cipher(cipher)?;
+
+    let top_level_index = TopLevelIndex::from_file(
+        path,
+        trailer.offsets.tli_ptr,
+        cipher.as_ref(),
+        trailer.index_item_counts,
+    )?;
+
+    let mut file = BufReader::new(File::open(path)?);
+    // NOTE: The file may be corrupted, so do not trust the block count
+    // further than the index section could hold (every handle takes at least one byte)
+    let index_len = trailer
+        .offsets
+        .tli_ptr
+        .saturating_sub(trailer.offsets.index_block_ptr);
+    let index_len = usize::try_from(index_len).unwrap_or(usize::MAX);
+
+    let mut block_handles =
+        Vec::with_capacity((trailer.metadata.data_block_count as usize).min(index_len));
+
+    for handle in top_level_index.iter() {
+        let index_block = IndexBlock::from_segment_file(
+            &mut file,
+            handle.offset,
+            cipher.as_ref(),
+            trailer.index_item_counts,
+        )?;
+        block_handles.extend(index_block.items.iter().cloned());
+    }
+
+    Ok(SegmentInspector {
+        path: path.into(),
+        metadata: trailer.metadata,
+        offsets: trailer.offsets,
+        key_id: trailer.key_id,
+        block_handles,
+        cipher,
+    })
+}
+
+/// Parsed view of a segment file
+///
+/// See [`inspect`].
+#[allow(clippy::module_name_repetitions)]
+pub struct SegmentInspector {
+    path: PathBuf,
+    cipher: Option<SegmentCipher>,
+
+    /// Segment metadata
+    pub metadata: Metadata,
+
+    /// Positions of the sections in the file
+    pub offsets: FileOffsets,
+
+    /// ID of the key the segment is encrypted with, if encrypted
+    pub key_id: Option<u32>,
+
+    /// Data block handles of the block index, in key order
+    pub block_handles: Vec<KeyedBlockHandle>,
+}
+
+impl SegmentInspector {
+    /// Returns an iterator that decodes every data block, in key order.
+    ///
+    /// The checksum of every block is verified, a corrupted block
+    /// yields an error without ending the iteration.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the segment file cannot be opened.
+    pub fn blocks(&self) -> crate::Result<InspectedBlocks<'_>> {
+        let file = BufReader::new(File::open(&self.path)?);
+
+        Ok(InspectedBlocks {
+            file,
+            cipher: self.cipher.as_ref(),
+            handles: self.block_handles.iter(),
+        })
+    }
+}
+
+/// Decoded data block of a segment
+#[derive(Debug)]
+pub struct InspectedBlock {
+    /// Handle of the block in the block index
+    pub handle: KeyedBlockHandle,
+
+    /// Block header
+    pub header: BlockHeader,
+
+    /// Decoded items of the block
+    pub items: Box<[InternalValue]>,
+}
+
+/// Iterator over the decoded data blocks of a segment
+///
+/// See [`SegmentInspector::blocks`].
+pub struct InspectedBlocks<'a> {
+    file: BufReader<File>,
+    cipher: Option<&'a SegmentCipher>,
+    handles: std::slice::Iter<'a, KeyedBlockHandle>,
+}
+
+impl Iterator for InspectedBlocks<'_> {
+    type Item = crate::Result<InspectedBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let handle = self.handles.next()?;
+
+        Some(
+            ValueBlock::from_file_checked(&mut self.file, handle.offset, self.cipher).map(
+                |block| InspectedBlock {
+                    handle: handle.clone(),
+                    header: block.header,
+                    items: block.items,
+                },
+            ),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.handles.size_hint()
+    }
+}
@@ -0,0 +1,254 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{range::Range, Segment};
+use crate::{range::is_empty_range, read_options::ReadOptions, InternalValue, UserKey};
+use std::{ops::Bound, sync::Arc};
+
+/// Reads through a disjoint, sorted run of segments
+///
+/// In contrast to [`super::multi_reader::MultiReader`], segment readers are only
+/// created once the scan reaches the segment, and dropped once it has been read
+/// through, so at most two readers (one per direction) exist at any time.
+pub struct LevelReader {
+    segments: Vec<Arc<Segment>>,
+    range: (Bound<UserKey>, Bound<UserKey>),
+
+    options: Option<ReadOptions>,
+
+    /// Index of the lowest unread segment
+    lo: usize,
+
+    /// Index after the highest unread segment
+    hi: usize,
+
+    lo_reader: Option<Range>,
+    hi_reader: Option<Range>,
+}
+
+impl LevelReader {
+    /// Creates a reader over the segments that overlap with the given range.
+    ///
+    /// The segments need to be disjoint and sorted by key range, so segments
+    /// outside the range can be skipped using binary search.
+    #[must_use]
+    pub fn new(segments: &[Arc<Segment>], range: (Bound<UserKey>, Bound<UserKey>)) -> Self {
+        let lo = segments.partition_point(|segment| {
+            let (_, max) = &*segment.metadata.key_range;
+
+            match &range.0 {
+                Bound::Included(key) => max < key,
+                Bound::Excluded(key) => max <= key,
+                Bound::Unbounded => false,
+            }
+        });
+
+        let hi = segments.partition_point(|segment| {
+            let (min, _) = &*segment.metadata.key_range;
+
+            match &range.1 {
+                Bound::Included(key) => min <= key,
+                Bound::Excluded(key) => min < key,
+                Bound::Unbounded => true,
+            }
+        });
+
+        // NOTE: An empty range (e.g. `a..a`) may still fall inside a segment's key range
+        let hi = if is_empty_range(&range) { lo } else { hi };
+
+        // NOTE: Segments whose prefix fences do not overlap with the range are skipped as well
+        let segments = segments
+            .get(lo..hi.max(lo))
+            .unwrap_or_default()
+            .iter()
+            .filter(|segment| segment.check_key_range_overlap(&range))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Self {
+            hi: segments.len(),
+            segments,
+            range,
+            options: None,
+            lo: 0,
+            lo_reader: None,
+            hi_reader: None,
+        }
+    }
+
+    /// Configures the segment readers using the given read options
+    #[must_use]
+    pub fn with_options(mut self, options: ReadOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+
+    /// Returns the amount of segments that have not been read through yet.
+    #[must_use]
+    pub fn remaining_segments(&self) -> usize {
+        self.hi.saturating_sub(self.lo)
+    }
+
+    fn open_reader(&self, idx: usize) -> Option<Range> {
+        self.segments.get(idx).map(|segment| match &self.options {
+            Some(options) => segment.range_with_options(self.range.clone(), options),
+            None => segment.range(self.range.clone()),
+        })
+    }
+}
+
+impl Iterator for LevelReader {
+    type Item = crate::Result<InternalValue>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.lo >= self.hi {
+                return None;
+            }
+
+            // NOTE: If the backwards reader already sits on the last remaining segment,
+            // continue using it, so no item is returned twice
+            if self.lo_reader.is_none() && self.hi - self.lo == 1 && self.hi_reader.is_some() {
+                std::mem::swap(&mut self.lo_reader, &mut self.hi_reader);
+            }
+
+            if self.lo_reader.is_none() {
+                self.lo_reader = self.open_reader(self.lo);
+            }
+
+            if let Some(item) = self.lo_reader.as_mut()?.next() {
+                return Some(item);
+            }
+
+            // NOTE: Segment is read through, drop its reader and move on to the next segment
+            self.lo_reader = None;
+            self.lo += 1;
+        }
+    }
+}
+
+impl DoubleEndedIterator for LevelReader {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.lo >= self.hi {
+                return None;
+            }
+
+            // NOTE: If the forwards reader already sits on the last remaining segment,
+            // continue using it, so no item is returned twice
+            if self.hi_reader.is_none() && self.hi - self.lo == 1 && self.lo_reader.is_some() {
+                std::mem::swap(&mut self.lo_reader, &mut self.hi_reader);
+            }
+
+            if self.hi_reader.is_none() {
+                self.hi_reader = self.open_reader(self.hi - 1);
+            }
+
+            if let Some(item) = self.hi_reader.as_mut()?.next_back() {
+                return Some(item);
+            }
+
+            // NOTE: Segment is read through, drop its reader and move on to the previous segment
+            self.hi_reader = None;
+            self.hi -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::{AbstractTree, Slice};
+    use test_log::test;
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn level_reader_skip_segments() -> crate::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let tree = crate::Config::new(&tempdir).open()?;
+
+        let ids = [
+            ["a", "b", "c"],
+            ["d", "e", "f"],
+            ["g", "h", "i"],
+            ["j", "k", "l"],
+        ];
+
+        for batch in ids {
+            for id in batch {
+                tree.insert(id, vec![], 0);
+            }
+            tree.flush_active_memtable(0)?;
+        }
+
+        let segments = tree
+            .levels
+            .read()
+            .expect("lock is poisoned")
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let reader = LevelReader::new(&segments, (Bound::Unbounded, Bound::Unbounded));
+        assert_eq!(4, reader.remaining_segments());
+        assert_eq!(12, reader.flatten().count());
+
+        let reader = LevelReader::new(
+            &segments,
+            (Bound::Excluded("c".into()), Bound::Included("g".into())),
+        );
+        assert_eq!(2, reader.remaining_segments());
+
+        let mut iter = reader.flatten();
+        assert_eq!(Slice::from(*b"d"), iter.next().unwrap().key.user_key);
+        assert_eq!(Slice::from(*b"g"), iter.next_back().unwrap().key.user_key);
+        assert_eq!(Slice::from(*b"e"), iter.next().unwrap().key.user_key);
+        assert_eq!(Slice::from(*b"f"), iter.next_back().unwrap().key.user_key);
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+
+        let reader = LevelReader::new(&segments, (Bound::Included("x".into()), Bound::Unbounded));
+        assert_eq!(0, reader.remaining_segments());
+
+        let reader = LevelReader::new(
+            &segments,
+            (Bound::Included("e".into()), Bound::Excluded("e".into())),
+        );
+        assert_eq!(0, reader.remaining_segments());
+
+        Ok(())
+    }
+
+    #[allow(clippy::unwrap_used)]
+    #[test]
+    fn level_reader_single_segment_both_directions() -> crate::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let tree = crate::Config::new(&tempdir).open()?;
+
+        for id in ["a", "b", "c", "d"] {
+            tree.insert(id, vec![], 0);
+        }
+        tree.flush_active_memtable(0)?;
+
+        let segments = tree
+            .levels
+            .read()
+            .expect("lock is poisoned")
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut iter = LevelReader::new(&segments, (Bound::Unbounded, Bound::Unbounded)).flatten();
+
+        assert_eq!(Slice::from(*b"a"), iter.next().unwrap().key.user_key);
+        assert_eq!(Slice::from(*b"d"), iter.next_back().unwrap().key.user_key);
+        assert_eq!(Slice::from(*b"b"), iter.next().unwrap().key.user_key);
+        assert_eq!(Slice::from(*b"c"), iter.next_back().unwrap().key.user_key);
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+
+        Ok(())
+    }
+}
@@ -5,10 +5,11 @@
 mod compression;
 mod table_type;
 
-use super::writer::Writer;
+use super::{prefix_fences::PrefixFences, writer::Writer};
 use crate::{
     coding::{Decode, DecodeError, Encode, EncodeError},
     file::MAGIC_BYTES,
+    hyperloglog::HyperLogLog,
     key_range::KeyRange,
     time::unix_timestamp,
     value::SeqNo,
@@ -22,6 +23,15 @@ pub use {compression::CompressionType, table_type::TableType};
 
 pub type SegmentId = u64;
 
+/// Header of the segment metadata, the last byte is the metadata format version
+///
+/// Version 2 (the header of all other files) ends after the key range,
+/// version 3 always stores the key sketch, prefix fences and index layout.
+const METADATA_MAGIC_BYTES: [u8; 4] = [b'L', b'S', b'M', 3];
+
+/// Metadata format version of segments written by older versions
+const LEGACY_METADATA_VERSION: u8 = MAGIC_BYTES[3];
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Metadata {
     /// Segment ID
@@ -75,12 +85,29 @@ pub struct Metadata {
 
     /// Key range
     pub key_range: KeyRange,
+
+    /// Sketch of the segment's keys, used to estimate distinct keys across segments
+    ///
+    /// Segments written by older versions do not have a key sketch.
+    pub key_sketch: Option<HyperLogLog>,
+
+    /// Distinct key prefixes of the segment, used to skip the segment in range scans
+    ///
+    /// Only stored if prefix fences are enabled, and the segment does not
+    /// contain too many distinct prefixes.
+    pub prefix_fences: Option<PrefixFences>,
+
+    /// If `true`, the index is small enough to be kept in memory as a whole,
+    /// so point reads skip the block cache lookup of index blocks
+    ///
+    /// Segments written by older versions always use a two-level index.
+    pub one_level_index: bool,
 }
 
 impl Encode for Metadata {
     fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
         // Write header
-        writer.write_all(&MAGIC_BYTES)?;
+        writer.write_all(&METADATA_MAGIC_BYTES)?;
 
         writer.write_u64::<BigEndian>(self.id)?;
 
@@ -109,18 +136,55 @@ impl Encode for Metadata {
 
         self.key_range.encode_into(writer)?;
 
+        if let Some(key_sketch) = &self.key_sketch {
+            writer.write_u8(1)?;
+            key_sketch.encode_into(writer)?;
+        } else {
+            writer.write_u8(0)?;
+        }
+
+        if let Some(prefix_fences) = &self.prefix_fences {
+            writer.write_u8(1)?;
+            prefix_fences.encode_into(writer)?;
+        } else {
+            writer.write_u8(0)?;
+        }
+
+        writer.write_u8(u8::from(self.one_level_index))?;
+
         Ok(())
     }
 }
 
+/// Position of the sections of a segment file, that the metadata is checked against
+#[derive(Copy, Clone, Debug)]
+pub struct SegmentFileLayout {
+    /// Offset of the first index block, which directly follows the data blocks
+    pub index_block_ptr: u64,
+
+    /// Actual length of the segment file
+    pub file_len: u64,
+}
+
 impl Decode for Metadata {
     fn decode_from<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
         // Check header
-        let mut magic = [0u8; MAGIC_BYTES.len()];
+        let mut magic = [0u8; METADATA_MAGIC_BYTES.len()];
         reader.read_exact(&mut magic)?;
 
-        if magic != MAGIC_BYTES {
-            return Err(DecodeError::InvalidHeader("SegmentMetadata"));
+        if magic[..3] != METADATA_MAGIC_BYTES[..3] {
+            return Err(DecodeError::invalid_header(
+                "SegmentMetadata",
+                &METADATA_MAGIC_BYTES,
+                &magic,
+            ));
+        }
+
+        // NOTE: The last byte of the magic is the format version
+        let version = magic[3];
+
+        if version != METADATA_MAGIC_BYTES[3] && version != LEGACY_METADATA_VERSION {
+            return Err(DecodeError::InvalidVersion);
         }
 
         let id = reader.read_u64::<BigEndian>()?;
@@ -152,6 +216,32 @@ impl Decode for Metadata {
 
         let key_range = KeyRange::decode_from(reader)?;
 
+        // NOTE: Older segments end after the key range, so they
+        // have no key sketch, no prefix fences, and a two-level index
+        let (key_sketch, prefix_fences, one_level_index) = if version == LEGACY_METADATA_VERSION {
+            (None, None, false)
+        } else {
+            let key_sketch = match reader.read_u8()? {
+                0 => None,
+                1 => Some(HyperLogLog::decode_from(reader)?),
+                tag => return Err(DecodeError::InvalidTag(("KeySketch", tag))),
+            };
+
+            let prefix_fences = match reader.read_u8()? {
+                0 => None,
+                1 => Some(PrefixFences::decode_from(reader)?),
+                tag => return Err(DecodeError::InvalidTag(("PrefixFences", tag))),
+            };
+
+            let one_level_index = match reader.read_u8()? {
+                0 => false,
+                1 => true,
+                tag => return Err(DecodeError::InvalidTag(("IndexLayout", tag))),
+            };
+
+            (key_sketch, prefix_fences, one_level_index)
+        };
+
         Ok(Self {
             id,
             created_at,
@@ -176,6 +266,12 @@ impl Decode for Metadata {
             seqnos: (seqno_min, seqno_max),
 
             key_range,
+
+            key_sketch,
+
+            prefix_fences,
+
+            one_level_index,
         })
     }
 }
@@ -190,9 +286,13 @@ impl Metadata {
 
             // NOTE: Using seconds is not granular enough
             // But because millis already returns u128, might as well use micros :)
-            created_at: unix_timestamp().as_micros(),
+            created_at: if writer.logical_clock {
+                u128::from(id)
+            } else {
+                unix_timestamp().as_micros()
+            },
 
-            compression: CompressionType::None,
+            compression: writer.compression,
             table_type: TableType::Block,
 
             // NOTE: Truncation is OK - even with the smallest block size (1 KiB), 4 billion blocks would be 4 TB
@@ -232,14 +332,87 @@ impl Metadata {
 
             // TODO: #2 https://github.com/fjall-rs/lsm-tree/issues/2
             range_tombstone_count: 0,
+
+            key_sketch: Some(writer.meta.key_sketch.clone()),
+
+            prefix_fences: writer.meta.prefix_fences.clone(),
+
+            one_level_index: writer.meta.one_level_index,
         })
     }
 
+    /// Checks that the metadata is internally consistent.
+    ///
+    /// If the segment file is known (the position of its index blocks, and its length),
+    /// the data size is checked against it.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the metadata is inconsistent.
+    pub fn validate(&self, file: Option<SegmentFileLayout>) -> Result<(), DecodeError> {
+        let invalid = |reason: String| {
+            Err(DecodeError::InvalidContent {
+                name: "SegmentMetadata",
+                reason,
+            })
+        };
+
+        let (lo_seqno, hi_seqno) = self.seqnos;
+        if lo_seqno > hi_seqno {
+            return invalid(format!("seqno range is inverted: {lo_seqno} > {hi_seqno}"));
+        }
+
+        let (min_key, max_key) = &*self.key_range;
+        if min_key > max_key {
+            return invalid(format!(
+                "key range is inverted: {:?} > {:?}",
+                &**min_key, &**max_key,
+            ));
+        }
+
+        if self.tombstone_count > self.item_count {
+            return invalid(format!(
+                "tombstone count exceeds item count: {} > {}",
+                self.tombstone_count, self.item_count,
+            ));
+        }
+
+        if self.key_count > self.item_count {
+            return invalid(format!(
+                "key count exceeds item count: {} > {}",
+                self.key_count, self.item_count,
+            ));
+        }
+
+        if let Some(SegmentFileLayout {
+            index_block_ptr,
+            file_len,
+        }) = file
+        {
+            // NOTE: The data blocks are directly followed by the index blocks
+            if self.file_size != index_block_ptr {
+                return invalid(format!(
+                    "data size does not match start of index blocks: {} != {index_block_ptr}",
+                    self.file_size,
+                ));
+            }
+
+            if index_block_ptr > file_len {
+                return invalid(format!(
+                    "index blocks start beyond end of file: {index_block_ptr} > {file_len}",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Reads and parses a Segment metadata file
     pub fn from_disk<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
         let file_content = std::fs::read(path)?;
         let mut cursor = Cursor::new(file_content);
         let meta = Self::decode_from(&mut cursor)?;
+        meta.validate(None)?;
         Ok(meta)
     }
 }
@@ -269,6 +442,45 @@ mod tests {
             range_tombstone_count: 0,
             uncompressed_size: 0,
             seqnos: (0, 5),
+            key_sketch: None,
+            prefix_fences: None,
+            one_level_index: false,
+        };
+
+        let bytes = metadata.encode_into_vec()?;
+        let mut cursor = Cursor::new(bytes);
+        let metadata_copy = Metadata::decode_from(&mut cursor)?;
+
+        assert_eq!(metadata, metadata_copy);
+
+        Ok(())
+    }
+
+    #[test]
+    fn segment_metadata_serde_round_trip_key_sketch() -> crate::Result<()> {
+        let mut key_sketch = HyperLogLog::default();
+        key_sketch.insert(b"abc");
+
+        let metadata = Metadata {
+            data_block_count: 0,
+            index_block_count: 0,
+            data_block_size: 4_096,
+            index_block_size: 4_096,
+            created_at: 5,
+            id: 632_632,
+            file_size: 1,
+            compression: CompressionType::None,
+            table_type: TableType::Block,
+            item_count: 1,
+            key_count: 1,
+            key_range: KeyRange::new((vec![2].into(), vec![5].into())),
+            tombstone_count: 0,
+            range_tombstone_count: 0,
+            uncompressed_size: 0,
+            seqnos: (0, 5),
+            key_sketch: Some(key_sketch),
+            prefix_fences: None,
+            one_level_index: false,
         };
 
         let bytes = metadata.encode_into_vec()?;
@@ -279,4 +491,203 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn segment_metadata_serde_round_trip_prefix_fences() -> crate::Result<()> {
+        let mut prefix_fences = PrefixFences::new(2);
+        prefix_fences.push(b"a:1");
+        prefix_fences.push(b"c:1");
+
+        let metadata = Metadata {
+            data_block_count: 0,
+            index_block_count: 0,
+            data_block_size: 4_096,
+            index_block_size: 4_096,
+            created_at: 5,
+            id: 632_632,
+            file_size: 1,
+            compression: CompressionType::None,
+            table_type: TableType::Block,
+            item_count: 2,
+            key_count: 2,
+            key_range: KeyRange::new((b"a:1".to_vec().into(), b"c:1".to_vec().into())),
+            tombstone_count: 0,
+            range_tombstone_count: 0,
+            uncompressed_size: 0,
+            seqnos: (0, 5),
+            key_sketch: None,
+            prefix_fences: Some(prefix_fences),
+            one_level_index: true,
+        };
+
+        let bytes = metadata.encode_into_vec()?;
+        let mut cursor = Cursor::new(bytes);
+        let metadata_copy = Metadata::decode_from(&mut cursor)?;
+
+        assert_eq!(metadata, metadata_copy);
+
+        Ok(())
+    }
+
+    #[test]
+    fn segment_metadata_validate() {
+        let metadata = Metadata {
+            data_block_count: 1,
+            index_block_count: 1,
+            data_block_size: 4_096,
+            index_block_size: 4_096,
+            created_at: 5,
+            id: 632_632,
+            file_size: 100,
+            compression: CompressionType::None,
+            table_type: TableType::Block,
+            item_count: 3,
+            key_count: 2,
+            key_range: KeyRange::new((vec![2].into(), vec![5].into())),
+            tombstone_count: 1,
+            range_tombstone_count: 0,
+            uncompressed_size: 100,
+            seqnos: (0, 5),
+            key_sketch: None,
+            prefix_fences: None,
+            one_level_index: false,
+        };
+        assert!(metadata.validate(None).is_ok());
+
+        let layout = |index_block_ptr, file_len| {
+            Some(SegmentFileLayout {
+                index_block_ptr,
+                file_len,
+            })
+        };
+        assert!(metadata.validate(layout(100, 150)).is_ok());
+        assert!(metadata.validate(layout(100, 100)).is_ok());
+        assert!(metadata.validate(layout(120, 150)).is_err());
+        assert!(metadata.validate(layout(99, 150)).is_err());
+        assert!(metadata.validate(layout(100, 99)).is_err());
+
+        let invalid = [
+            Metadata {
+                seqnos: (5, 0),
+                ..metadata.clone()
+            },
+            Metadata {
+                key_range: KeyRange::new((vec![5].into(), vec![2].into())),
+                ..metadata.clone()
+            },
+            Metadata {
+                tombstone_count: 4,
+                ..metadata.clone()
+            },
+            Metadata {
+                key_count: 4,
+                ..metadata.clone()
+            },
+        ];
+
+        for metadata in invalid {
+            assert!(matches!(
+                metadata.validate(None),
+                Err(DecodeError::InvalidContent {
+                    name: "SegmentMetadata",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn segment_metadata_invalid_version() -> crate::Result<()> {
+        let metadata = Metadata {
+            data_block_count: 0,
+            index_block_count: 0,
+            data_block_size: 4_096,
+            index_block_size: 4_096,
+            created_at: 5,
+            id: 632_632,
+            file_size: 1,
+            compression: CompressionType::None,
+            table_type: TableType::Block,
+            item_count: 0,
+            key_count: 0,
+            key_range: KeyRange::new((vec![2].into(), vec![5].into())),
+            tombstone_count: 0,
+            range_tombstone_count: 0,
+            uncompressed_size: 0,
+            seqnos: (0, 5),
+            key_sketch: None,
+            prefix_fences: None,
+            one_level_index: false,
+        };
+
+        let mut bytes = metadata.encode_into_vec()?;
+        bytes[3] = 1;
+
+        let mut cursor = Cursor::new(bytes);
+        assert!(matches!(
+            Metadata::decode_from(&mut cursor),
+            Err(DecodeError::InvalidVersion)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn segment_metadata_legacy_version() -> crate::Result<()> {
+        let mut key_sketch = HyperLogLog::default();
+        key_sketch.insert(b"abc");
+
+        let metadata = Metadata {
+            data_block_count: 0,
+            index_block_count: 0,
+            data_block_size: 4_096,
+            index_block_size: 4_096,
+            created_at: 5,
+            id: 632_632,
+            file_size: 1,
+            compression: CompressionType::None,
+            table_type: TableType::Block,
+            item_count: 1,
+            key_count: 1,
+            key_range: KeyRange::new((vec![2].into(), vec![5].into())),
+            tombstone_count: 0,
+            range_tombstone_count: 0,
+            uncompressed_size: 0,
+            seqnos: (0, 5),
+            key_sketch: Some(key_sketch),
+            prefix_fences: None,
+            one_level_index: true,
+        };
+
+        // NOTE: Older segments end after the key range, and are followed by the trailer
+        let mut bytes = Metadata {
+            key_sketch: None,
+            prefix_fences: None,
+            one_level_index: false,
+            ..metadata.clone()
+        }
+        .encode_into_vec()?;
+        bytes.truncate(bytes.len() - 3);
+        bytes[3] = LEGACY_METADATA_VERSION;
+        bytes.extend_from_slice(&[1, 1, 1]);
+
+        let mut cursor = Cursor::new(bytes);
+        let metadata_copy = Metadata::decode_from(&mut cursor)?;
+
+        assert_eq!(None, metadata_copy.key_sketch);
+        assert_eq!(None, metadata_copy.prefix_fences);
+        assert!(!metadata_copy.one_level_index);
+
+        // NOTE: Current segments always store all fields
+        let mut bytes = metadata.encode_into_vec()?;
+        bytes.truncate(bytes.len() - 1);
+
+        let mut cursor = Cursor::new(bytes);
+        assert!(matches!(
+            Metadata::decode_from(&mut cursor),
+            Err(DecodeError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+
+        Ok(())
+    }
 }
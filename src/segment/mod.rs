@@ -6,11 +6,18 @@ pub mod block;
 pub mod block_index;
 pub mod file_offsets;
 pub mod id;
+pub mod inspect;
+pub mod level_reader;
 pub mod meta;
 pub mod multi_reader;
 pub mod multi_writer;
+pub mod prefetch;
+pub mod prefix_fences;
 pub mod range;
 pub mod reader;
+pub mod section;
+pub mod seqno_index;
+pub mod tombstone_index;
 pub mod trailer;
 pub mod value_block;
 pub mod value_block_consumer;
@@ -18,22 +25,47 @@ pub mod writer;
 
 use crate::{
     block_cache::BlockCache,
+    config::ConfigFlags,
     descriptor_table::FileDescriptorTable,
+    encryption::Cipher,
+    error::ErrorContext,
+    metrics::{MetricsSink, ReadSampler, BLOCK_CACHE_HITS},
     mvcc_stream::MvccStream,
+    read_options::ReadOptions,
+    read_pool::ReadPool,
     segment::{reader::Reader, value_block_consumer::ValueBlockConsumer},
     tree::inner::TreeId,
     value::{InternalValue, SeqNo, UserKey},
     ValueType,
 };
 use block::checksum::Checksum;
-use block_index::two_level_index::TwoLevelBlockIndex;
+use block_index::{block_handle::KeyedBlockHandle, two_level_index::TwoLevelBlockIndex};
 use file_offsets::FileOffsets;
+use id::GlobalSegmentId;
+use meta::SegmentId;
 use range::Range;
-use std::{ops::Bound, path::Path, sync::Arc};
+use seqno_index::SeqnoIndex;
+use std::{
+    ops::{Bound, RangeBounds},
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
+use tombstone_index::TombstoneIndex;
+
+pub use inspect::{inspect, inspect_with_cipher};
 
 #[cfg(feature = "bloom")]
 use crate::bloom::{BloomFilter, CompositeHash};
 
+/// Result of a point read that is only served from memory, see [`Segment::get_cached`]
+pub(crate) enum CachedRead {
+    /// The needed blocks are in memory, and the item was looked up
+    Hit(Option<InternalValue>),
+
+    /// Some needed block is not in memory, so the segment needs to be read from disk
+    Miss,
+}
+
 /// Disk segment (a.k.a. `SSTable`, `SST`, `sorted string table`) that is located on disk
 ///
 /// A segment is an immutable list of key-value pairs, split into compressed blocks.
@@ -65,6 +97,23 @@ pub struct Segment {
     #[doc(hidden)]
     pub block_cache: Arc<BlockCache>,
 
+    /// Seqno index, if the segment was written with one
+    pub(crate) seqno_index: Option<SeqnoIndex>,
+
+    /// Tombstone index, if the segment contains tombstones
+    pub(crate) tombstone_index: Option<TombstoneIndex>,
+
+    /// Path of the segment file, set once the segment has been removed from the tree
+    ///
+    /// The file is deleted when the last reference to the segment is dropped.
+    pub(crate) obsolete_path: OnceLock<PathBuf>,
+
+    /// Offset that is added to the seqno of every item read from the segment file
+    ///
+    /// Segments that were linked into a tree by [`crate::Tree::absorb`] keep the seqnos of the
+    /// absorbed tree in their file, so the offset is stored in the level manifest instead.
+    pub(crate) seqno_offset: SeqNo,
+
     /// Bloom filter
     #[cfg(feature = "bloom")]
     #[doc(hidden)]
@@ -77,9 +126,36 @@ impl std::fmt::Debug for Segment {
     }
 }
 
+impl Drop for Segment {
+    fn drop(&mut self) {
+        // NOTE: The blocks cannot be read anymore, so they should not stay pinned
+        self.block_cache
+            .unpin_segment((self.tree_id, self.metadata.id).into());
+
+        let Some(segment_file_path) = self.obsolete_path.get() else {
+            return;
+        };
+
+        // NOTE: If the application were to crash >here< it's fine
+        // The segment is not referenced anymore, and will be
+        // cleaned up upon recovery
+        log::trace!("Removing old segment at {}", segment_file_path.display());
+
+        if let Err(e) = std::fs::remove_file(segment_file_path) {
+            log::error!("Failed to cleanup file of deleted segment: {e:?}");
+        }
+
+        log::trace!("Closing file handles for old segment file");
+        self.descriptor_table
+            .remove((self.tree_id, self.metadata.id).into());
+    }
+}
+
 impl Segment {
     pub(crate) fn verify(&self) -> crate::Result<usize> {
+        use block::header::Header as BlockHeader;
         use block_index::IndexBlock;
+        use std::io::{Read, Seek, SeekFrom};
         use value_block::ValueBlock;
 
         let mut data_block_count = 0;
@@ -90,12 +166,18 @@ impl Segment {
             .access(&(self.tree_id, self.metadata.id).into())?
             .expect("should have gotten file");
 
-        let mut file = guard.file.lock().expect("lock is poisoned");
+        let cipher = guard.cipher.as_ref();
+        let mut file = guard.reader();
 
         // NOTE: TODO: because of 1.74.0
         #[allow(clippy::explicit_iter_loop)]
-        for handle in self.block_index.top_level_index.iter() {
-            let block = match IndexBlock::from_file(&mut *file, handle.offset) {
+        for handle in self.block_index.top_level_index()?.iter() {
+            let block = match IndexBlock::from_segment_file(
+                &mut file,
+                handle.offset,
+                cipher,
+                self.block_index.item_counts,
+            ) {
                 Ok(v) => v,
                 Err(e) => {
                     log::error!(
@@ -107,7 +189,11 @@ impl Segment {
             };
 
             for handle in &*block.items {
-                let value_block = match ValueBlock::from_file(&mut *file, handle.offset) {
+                let value_block = match ValueBlock::from_file_with_cipher(
+                    &mut file,
+                    handle.offset,
+                    cipher,
+                ) {
                     Ok(v) => v,
                     Err(e) => {
                         log::error!(
@@ -119,12 +205,17 @@ impl Segment {
                     }
                 };
 
-                let (_, data) = ValueBlock::to_bytes_compressed(
-                    &value_block.items,
-                    value_block.header.previous_block_offset,
-                    value_block.header.compression,
-                )?;
-                let actual_checksum = Checksum::from_bytes(&data);
+                // NOTE: The checksum is calculated over the (possibly encrypted) bytes on disk
+                let actual_checksum = {
+                    file.seek(SeekFrom::Start(
+                        handle.offset + BlockHeader::serialized_len() as u64,
+                    ))?;
+
+                    let mut data = vec![0; value_block.header.data_length as usize];
+                    file.read_exact(&mut data)?;
+
+                    Checksum::from_bytes(&data)
+                };
 
                 if value_block.header.checksum != actual_checksum {
                     log::error!("{handle:?} is corrupted, invalid checksum value");
@@ -148,62 +239,296 @@ impl Segment {
     }
 
     /// Tries to recover a segment from a file.
+    ///
+    /// The segment ID is taken from the level manifest, and may differ from the ID
+    /// stored in the segment file, if the file was linked from another tree.
+    ///
+    /// Errors carry the path of the segment file in their [`ErrorContext`].
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn recover<P: AsRef<Path>>(
         file_path: P,
+        segment_id: SegmentId,
         tree_id: TreeId,
         block_cache: Arc<BlockCache>,
         descriptor_table: Arc<FileDescriptorTable>,
+        cipher: Option<&Cipher>,
+        metrics: Option<Arc<dyn MetricsSink>>,
+        read_sampler: Option<ReadSampler>,
+        readahead: usize,
+        readahead_bytes: usize,
+        read_pool: Arc<ReadPool>,
+        flags: ConfigFlags,
     ) -> crate::Result<Self> {
-        use trailer::SegmentFileTrailer;
-
         let file_path = file_path.as_ref();
 
+        Self::recover_file(
+            file_path,
+            segment_id,
+            tree_id,
+            block_cache,
+            descriptor_table,
+            cipher,
+            metrics,
+            read_sampler,
+            readahead,
+            readahead_bytes,
+            read_pool,
+            flags,
+        )
+        .map_err(|e| {
+            e.with_context(
+                ErrorContext::default()
+                    .with_path(file_path)
+                    .with_segment_id(segment_id),
+            )
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn recover_file(
+        file_path: &Path,
+        segment_id: SegmentId,
+        tree_id: TreeId,
+        block_cache: Arc<BlockCache>,
+        descriptor_table: Arc<FileDescriptorTable>,
+        cipher: Option<&Cipher>,
+        metrics: Option<Arc<dyn MetricsSink>>,
+        read_sampler: Option<ReadSampler>,
+        readahead: usize,
+        readahead_bytes: usize,
+        read_pool: Arc<ReadPool>,
+        flags: ConfigFlags,
+    ) -> crate::Result<Self> {
+        use trailer::SegmentFileTrailer;
+
         log::debug!("Recovering segment from file {file_path:?}");
-        let trailer = SegmentFileTrailer::from_file(file_path)?;
+        let trailer = SegmentFileTrailer::from_file(file_path, cipher)?;
+        let cipher = trailer.cipher(cipher)?;
+
+        // NOTE: The file needs to be registered before loading the block index,
+        // because pinned index blocks are read through the descriptor table
+        descriptor_table.insert_with_cipher(
+            file_path,
+            (tree_id, segment_id).into(),
+            cipher.clone(),
+        );
 
         log::debug!(
             "Creating block index, with tli_ptr={}",
             trailer.offsets.tli_ptr
         );
-        let block_index = TwoLevelBlockIndex::from_file(
-            file_path,
-            trailer.offsets.tli_ptr,
-            (tree_id, trailer.metadata.id).into(),
-            descriptor_table.clone(),
-            block_cache.clone(),
-        )?;
+        let block_index = if flags.contains(ConfigFlags::LAZY_BLOCK_INDEX) {
+            TwoLevelBlockIndex::from_file_lazy(
+                trailer.offsets.tli_ptr,
+                (tree_id, segment_id).into(),
+                descriptor_table.clone(),
+                block_cache.clone(),
+                trailer.index_item_counts,
+            )
+        } else {
+            TwoLevelBlockIndex::from_file(
+                file_path,
+                trailer.offsets.tli_ptr,
+                (tree_id, segment_id).into(),
+                descriptor_table.clone(),
+                block_cache.clone(),
+                cipher.as_ref(),
+                trailer.index_item_counts,
+            )?
+        }
+        .with_metrics(metrics)
+        .with_read_sampler(read_sampler)
+        .with_readahead(readahead)
+        .with_readahead_bytes(readahead_bytes)
+        .with_read_pool(read_pool)
+        .with_pinned_index_blocks(
+            flags.contains(ConfigFlags::PIN_INDEX_BLOCKS) || trailer.metadata.one_level_index,
+        )?
+        .with_memory_lock(flags.contains(ConfigFlags::MLOCK_INDEX_AND_FILTERS))?;
 
         #[cfg(feature = "bloom")]
         let bloom_ptr = trailer.offsets.bloom_ptr;
 
+        #[cfg(feature = "bloom")]
+        let bloom_filter = {
+            use section::read_section;
+            use std::{
+                fs::File,
+                io::{Seek, SeekFrom},
+            };
+
+            assert!(bloom_ptr > 0, "can not find bloom filter block");
+
+            let mut reader = File::open(file_path)?;
+            reader.seek(SeekFrom::Start(bloom_ptr))?;
+            read_section::<BloomFilter, _>(
+                &mut reader,
+                bloom_ptr,
+                cipher.as_ref(),
+                trailer.checksummed_sections,
+            )?
+            .with_memory_lock(flags.contains(ConfigFlags::MLOCK_INDEX_AND_FILTERS))
+        };
+
+        let seqno_index = SeqnoIndex::load(file_path, &trailer, cipher.as_ref())?;
+        let tombstone_index = TombstoneIndex::load(file_path, &trailer, cipher.as_ref())?;
+
+        let mut metadata = trailer.metadata;
+        metadata.id = segment_id;
+
         Ok(Self {
             tree_id,
 
             descriptor_table,
-            metadata: trailer.metadata,
+            metadata,
             offsets: trailer.offsets,
 
             block_index: Arc::new(block_index),
             block_cache,
 
-            // TODO: as Bloom method
-            #[cfg(feature = "bloom")]
-            bloom_filter: {
-                use crate::coding::Decode;
-                use std::{
-                    fs::File,
-                    io::{Seek, SeekFrom},
-                };
+            seqno_index,
+            tombstone_index,
 
-                assert!(bloom_ptr > 0, "can not find bloom filter block");
+            obsolete_path: OnceLock::new(),
+            seqno_offset: 0,
 
-                let mut reader = File::open(file_path)?;
-                reader.seek(SeekFrom::Start(bloom_ptr))?;
-                BloomFilter::decode_from(&mut reader)?
-            },
+            #[cfg(feature = "bloom")]
+            bloom_filter,
         })
     }
 
+    /// Adds an offset to the seqno of every item read from the segment, see [`Segment::seqno_offset`].
+    pub(crate) fn with_seqno_offset(mut self, seqno_offset: SeqNo) -> Self {
+        self.seqno_offset = seqno_offset;
+        self.metadata.seqnos.0 = self.metadata.seqnos.0.saturating_add(seqno_offset);
+        self.metadata.seqnos.1 = self.metadata.seqnos.1.saturating_add(seqno_offset);
+        self
+    }
+
+    /// Translates the seqno of a read into the seqnos stored in the segment file.
+    fn stored_seqno(&self, seqno: SeqNo) -> SeqNo {
+        seqno.saturating_sub(self.seqno_offset)
+    }
+
+    /// Translates an item read from the segment file into the seqnos of the tree.
+    fn shift_seqno(&self, mut item: InternalValue) -> InternalValue {
+        item.key.seqno += self.seqno_offset;
+        item
+    }
+
+    /// Counts the items of the segment in the given range.
+    ///
+    /// Blocks that are fully covered by the range are counted using the item counts
+    /// of their index entries, so only the data blocks at the edges of the range are loaded.
+    ///
+    /// Every item is counted, so this only returns the amount of visible items,
+    /// if the segment contains neither tombstones nor older versions of keys.
+    pub(crate) fn range_len(
+        &self,
+        bounds: &(Bound<UserKey>, Bound<UserKey>),
+    ) -> crate::Result<usize> {
+        use value_block::CachePolicy;
+
+        fn count(range: Range) -> crate::Result<usize> {
+            let mut count = 0;
+
+            for item in range {
+                let _ = item?;
+                count += 1;
+            }
+
+            Ok(count)
+        }
+
+        let (min, max) = &*self.metadata.key_range;
+
+        // NOTE: Item count is bounded by memory
+        #[allow(clippy::cast_possible_truncation)]
+        if bounds.contains(min) && bounds.contains(max) {
+            return Ok(self.metadata.item_count as usize);
+        }
+
+        if !self.block_index.item_counts {
+            return count(self.range(bounds.clone()));
+        }
+
+        let below = |key: &UserKey| match &bounds.0 {
+            Bound::Included(lo) => key < lo,
+            Bound::Excluded(lo) => key <= lo,
+            Bound::Unbounded => false,
+        };
+
+        let above = |key: &UserKey| match &bounds.1 {
+            Bound::Included(hi) => key > hi,
+            Bound::Excluded(hi) => key >= hi,
+            Bound::Unbounded => false,
+        };
+
+        // NOTE: A block only contains keys between the end key of its previous block
+        // (or the segment's min key) and its own end key
+        let covers = |prev_end: Option<&UserKey>, handle: &KeyedBlockHandle| {
+            handle.item_count < u32::MAX
+                && bounds.contains(prev_end.unwrap_or(min))
+                && bounds.contains(&handle.end_key)
+        };
+
+        // NOTE: Covered blocks are contiguous, so only the end key of the block
+        // before the first covered block & the end key of the last covered block are needed
+        let mut covered: Option<(Option<UserKey>, UserKey, u64)> = None;
+
+        let mut cover = |prev_end: Option<&UserKey>, handle: &KeyedBlockHandle| {
+            let item_count = u64::from(handle.item_count);
+
+            covered = Some(match covered.take() {
+                Some((first, _, sum)) => (first, handle.end_key.clone(), sum + item_count),
+                None => (prev_end.cloned(), handle.end_key.clone(), item_count),
+            });
+        };
+
+        let mut prev_end: Option<&UserKey> = None;
+
+        // NOTE: TODO: because of 1.74.0
+        #[allow(clippy::explicit_iter_loop)]
+        for tli_handle in self.block_index.top_level_index()?.iter() {
+            if above(prev_end.unwrap_or(min)) {
+                break;
+            }
+
+            if covers(prev_end, tli_handle) {
+                cover(prev_end, tli_handle);
+            } else if !below(&tli_handle.end_key) {
+                let index_block = self
+                    .block_index
+                    .load_index_block(tli_handle, CachePolicy::Write)?;
+
+                let mut prev_end = prev_end.cloned();
+
+                for handle in &*index_block.items {
+                    if covers(prev_end.as_ref(), handle) {
+                        cover(prev_end.as_ref(), handle);
+                    }
+                    prev_end = Some(handle.end_key.clone());
+                }
+            }
+
+            prev_end = Some(&tli_handle.end_key);
+        }
+
+        let Some((first, last, item_count)) = covered else {
+            return count(self.range(bounds.clone()));
+        };
+
+        let head = match first {
+            Some(prev_end) => count(self.range((bounds.0.clone(), Bound::Included(prev_end))))?,
+            None => 0,
+        };
+        let tail = count(self.range((Bound::Excluded(last), bounds.1.clone())))?;
+
+        // NOTE: Item count is bounded by memory
+        #[allow(clippy::cast_possible_truncation)]
+        Ok(head + item_count as usize + tail)
+    }
+
     #[cfg(feature = "bloom")]
     #[must_use]
     /// Gets the bloom filter size
@@ -211,6 +536,12 @@ impl Segment {
         self.bloom_filter.len()
     }
 
+    /// Returns `true` if the bloom filter rules out the key
+    #[cfg(feature = "bloom")]
+    pub(crate) fn bloom_filter_excludes(&self, hash: CompositeHash) -> bool {
+        !self.bloom_filter.contains_hash(hash)
+    }
+
     #[cfg(feature = "bloom")]
     pub fn get_with_hash<K: AsRef<[u8]>>(
         &self,
@@ -237,14 +568,27 @@ impl Segment {
         self.point_read(key, seqno)
     }
 
-    fn point_read<K: AsRef<[u8]>>(
+    pub(crate) fn point_read<K: AsRef<[u8]>>(
         &self,
         key: K,
         seqno: Option<SeqNo>,
+    ) -> crate::Result<Option<InternalValue>> {
+        self.point_read_shared(key.as_ref(), seqno, &mut None)
+    }
+
+    /// Like [`Segment::point_read`], but remembers the data block the key starts in,
+    /// so a following read of a key in the same data block does not load it again.
+    ///
+    /// The caller is expected to have checked the seqno, key range (and bloom filter) already.
+    pub(crate) fn point_read_shared(
+        &self,
+        key: &[u8],
+        seqno: Option<SeqNo>,
+        last_block: &mut Option<(u64, Arc<value_block::ValueBlock>)>,
     ) -> crate::Result<Option<InternalValue>> {
         use value_block::{CachePolicy, ValueBlock};
 
-        let key = key.as_ref();
+        let seqno = seqno.map(|seqno| self.stored_seqno(seqno));
 
         let Some(first_block_handle) = self
             .block_index
@@ -253,17 +597,31 @@ impl Segment {
             return Ok(None);
         };
 
-        let Some(block) = ValueBlock::load_by_block_handle(
-            &self.descriptor_table,
-            &self.block_cache,
-            (self.tree_id, self.metadata.id).into(),
-            first_block_handle.offset,
-            CachePolicy::Write,
-        )?
-        else {
-            return Ok(None);
+        let (block, cache_hit) = match last_block {
+            Some((offset, block)) if *offset == first_block_handle.offset => (block.clone(), true),
+            _ => {
+                let Some((block, cache_hit)) = ValueBlock::load_by_block_handle_traced(
+                    &self.descriptor_table,
+                    &self.block_cache,
+                    (self.tree_id, self.metadata.id).into(),
+                    first_block_handle.offset,
+                    CachePolicy::Write,
+                    self.block_index.metrics.as_deref(),
+                )?
+                else {
+                    return Ok(None);
+                };
+
+                *last_block = Some((first_block_handle.offset, block.clone()));
+
+                (block, cache_hit)
+            }
         };
 
+        if let Some(sampler) = &self.block_index.read_sampler {
+            sampler.sample(key, self.metadata.id, first_block_handle.offset, cache_hit);
+        }
+
         if seqno.is_none() {
             // NOTE: Fastpath for non-seqno reads (which are most common)
             // This avoids setting up a rather expensive block iterator
@@ -277,7 +635,7 @@ impl Segment {
             if latest.key.value_type == ValueType::WeakTombstone {
                 // NOTE: Continue in slow path
             } else {
-                return Ok(Some(latest));
+                return Ok(Some(self.shift_seqno(latest)));
             }
         }
 
@@ -289,6 +647,7 @@ impl Segment {
             first_block_handle.offset,
             None,
         );
+        reader.metrics.clone_from(&self.block_index.metrics);
         reader.lo_block_size = block.header.data_length.into();
         reader.lo_block_items = Some(ValueBlockConsumer::with_bounds(
             block,
@@ -337,7 +696,79 @@ impl Segment {
             return Ok(None);
         }
 
-        Ok(Some(entry))
+        Ok(Some(self.shift_seqno(entry)))
+    }
+
+    /// Retrieves the latest version of an item (that is visible at the given seqno)
+    /// from the segment, without doing any disk I/O.
+    ///
+    /// The caller is expected to have checked the seqno, key range (and bloom filter) already.
+    ///
+    /// Returns [`CachedRead::Miss`] if the block index or data block is not in memory,
+    /// or the item cannot be resolved from a single data block.
+    pub(crate) fn get_cached(&self, key: &[u8], seqno: Option<SeqNo>) -> CachedRead {
+        let seqno = seqno.map(|seqno| self.stored_seqno(seqno));
+
+        let Some(block_handle) = self
+            .block_index
+            .get_cached_lowest_data_block_handle_containing_item(key)
+        else {
+            return CachedRead::Miss;
+        };
+
+        let Some(block_handle) = block_handle else {
+            return CachedRead::Hit(None);
+        };
+
+        let Some(block) = self
+            .block_cache
+            .get_disk_block((self.tree_id, self.metadata.id).into(), block_handle.offset)
+        else {
+            return CachedRead::Miss;
+        };
+
+        if let Some(sink) = &self.block_index.metrics {
+            sink.counter(BLOCK_CACHE_HITS, 1);
+        }
+
+        let idx = block
+            .items
+            .partition_point(|item| &*item.key.user_key < key);
+
+        let visible = block
+            .items
+            .iter()
+            .skip(idx)
+            .take_while(|item| &*item.key.user_key == key)
+            .find(|item| seqno.map_or(true, |seqno| item.key.seqno < seqno));
+
+        let item = match visible {
+            // NOTE: Older versions may continue in the next block, see Segment::point_read
+            None if block
+                .items
+                .last()
+                .is_some_and(|item| &*item.key.user_key == key) =>
+            {
+                return CachedRead::Miss;
+            }
+
+            None => None,
+
+            // NOTE: Weak tombstones may need to look at older versions in the next block,
+            // see Segment::point_read
+            Some(item) if item.key.value_type == ValueType::WeakTombstone => {
+                return CachedRead::Miss;
+            }
+
+            Some(item) => Some(self.shift_seqno(item.clone())),
+        };
+
+        // NOTE: Misses are sampled by the point read that follows them
+        if let Some(sampler) = &self.block_index.read_sampler {
+            sampler.sample(key, self.metadata.id, block_handle.offset, true);
+        }
+
+        CachedRead::Hit(item)
     }
 
     // NOTE: Clippy false positive
@@ -405,6 +836,90 @@ impl Segment {
             self.block_index.clone(),
             range,
         )
+        .seqno_offset(self.seqno_offset)
+    }
+
+    /// Creates a range over the segment, configured by the given read options.
+    pub(crate) fn range_with_options(
+        &self,
+        range: (Bound<UserKey>, Bound<UserKey>),
+        options: &ReadOptions,
+    ) -> Range {
+        let range = self
+            .range(range)
+            .cache_policy(options.cache_policy())
+            .readahead(options.limit_capped_readahead(self));
+
+        match options.readahead_bytes {
+            Some(bytes) => range.readahead_bytes(bytes),
+            None => range,
+        }
+    }
+
+    /// Returns all items with a seqno >= `seqno`, in key order.
+    ///
+    /// If the segment has a seqno index, only the data blocks that contain
+    /// such items are loaded, otherwise the whole segment is scanned.
+    pub(crate) fn changes_since(
+        &self,
+        seqno: SeqNo,
+    ) -> Box<dyn Iterator<Item = crate::Result<InternalValue>>> {
+        use value_block::{CachePolicy, ValueBlock};
+
+        if self.metadata.seqnos.1 < seqno {
+            return Box::new(std::iter::empty());
+        }
+
+        let Some(seqno_index) = &self.seqno_index else {
+            return Box::new(
+                self.iter()
+                    .filter(move |item| item.as_ref().map_or(true, |x| x.key.seqno >= seqno)),
+            );
+        };
+
+        let descriptor_table = self.descriptor_table.clone();
+        let block_cache = self.block_cache.clone();
+        let metrics = self.block_index.metrics.clone();
+        let segment_id = (self.tree_id, self.metadata.id).into();
+
+        let seqno_offset = self.seqno_offset;
+        let seqno = self.stored_seqno(seqno);
+
+        // NOTE: Collected, because the returned iterator cannot borrow the segment
+        #[allow(clippy::needless_collect)]
+        let offsets = seqno_index.blocks_since(seqno).collect::<Vec<_>>();
+
+        Box::new(
+            offsets
+                .into_iter()
+                .map(move |offset| {
+                    ValueBlock::load_by_block_handle(
+                        &descriptor_table,
+                        &block_cache,
+                        segment_id,
+                        offset,
+                        CachePolicy::Read,
+                        metrics.as_deref(),
+                    )
+                })
+                .flat_map(move |block| {
+                    let items: Vec<crate::Result<InternalValue>> = match block {
+                        Ok(Some(block)) => block
+                            .items
+                            .iter()
+                            .filter(|item| item.key.seqno >= seqno)
+                            .cloned()
+                            .map(|mut item| {
+                                item.key.seqno += seqno_offset;
+                                Ok(item)
+                            })
+                            .collect(),
+                        Ok(None) => vec![],
+                        Err(e) => vec![Err(e)],
+                    };
+                    items
+                }),
+        )
     }
 
     /// Returns the highest sequence number in the segment.
@@ -413,6 +928,178 @@ impl Segment {
         self.metadata.seqnos.1
     }
 
+    /// Returns the ID of the key the segment is encrypted with, if encrypted.
+    #[must_use]
+    pub fn key_id(&self) -> Option<u32> {
+        self.descriptor_table
+            .key_id(&(self.tree_id, self.metadata.id).into())
+    }
+
+    /// Opens the segment file and loads all index blocks into the block cache,
+    /// and optionally the first data block.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub(crate) fn prewarm(&self, load_first_data_block: bool) -> crate::Result<()> {
+        use value_block::{CachePolicy, ValueBlock};
+
+        let mut first_data_block = None;
+
+        for handle in self.block_index.top_level_index()?.iter() {
+            let index_block = self
+                .block_index
+                .load_index_block(handle, CachePolicy::Write)?;
+
+            if first_data_block.is_none() {
+                first_data_block = index_block.items.first().map(|x| x.offset);
+            }
+        }
+
+        if load_first_data_block {
+            if let Some(offset) = first_data_block {
+                ValueBlock::load_by_block_handle(
+                    &self.descriptor_table,
+                    &self.block_cache,
+                    (self.tree_id, self.metadata.id).into(),
+                    offset,
+                    CachePolicy::Write,
+                    self.block_index.metrics.as_deref(),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loads the index & data blocks that may contain keys of the given range
+    /// into the block cache, and pins them, so they are not evicted.
+    ///
+    /// The pinned blocks are appended to `pinned` as (segment ID, offset, weight),
+    /// even if an error occurs, so the caller can release them.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub(crate) fn pin_range(
+        &self,
+        bounds: &(Bound<UserKey>, Bound<UserKey>),
+        pinned: &mut Vec<(GlobalSegmentId, u64, u64)>,
+    ) -> crate::Result<()> {
+        use value_block::{CachePolicy, ValueBlock};
+
+        let segment_id = (self.tree_id, self.metadata.id).into();
+
+        if !self.check_key_range_overlap(bounds) {
+            return Ok(());
+        }
+
+        // NOTE: A block contains the keys after the end key of the previous block,
+        // up to (and including) its own end key
+        let mut lower: Option<&UserKey> = None;
+
+        for handle in self.block_index.top_level_index()?.iter() {
+            let overlaps = block_overlaps(lower, &handle.end_key, bounds);
+            let index_lower = lower;
+            lower = Some(&handle.end_key);
+
+            if !overlaps {
+                continue;
+            }
+
+            let index_block = self
+                .block_index
+                .load_index_block(handle, CachePolicy::Read)?;
+
+            self.block_cache.pin(
+                segment_id,
+                handle.offset,
+                index_block.header.uncompressed_length.into(),
+            );
+            self.block_cache
+                .insert_index_block(segment_id, handle.offset, index_block.clone());
+            pinned.push((
+                segment_id,
+                handle.offset,
+                index_block.header.uncompressed_length.into(),
+            ));
+
+            let mut lower = index_lower;
+
+            for data_handle in index_block.items.iter() {
+                let overlaps = block_overlaps(lower, &data_handle.end_key, bounds);
+                lower = Some(&data_handle.end_key);
+
+                if !overlaps {
+                    continue;
+                }
+
+                let Some(block) = ValueBlock::load_by_block_handle(
+                    &self.descriptor_table,
+                    &self.block_cache,
+                    segment_id,
+                    data_handle.offset,
+                    CachePolicy::Read,
+                    self.block_index.metrics.as_deref(),
+                )?
+                else {
+                    continue;
+                };
+
+                self.block_cache.pin(
+                    segment_id,
+                    data_handle.offset,
+                    block.header.uncompressed_length.into(),
+                );
+                self.block_cache
+                    .insert_disk_block(segment_id, data_handle.offset, block.clone());
+                pinned.push((
+                    segment_id,
+                    data_handle.offset,
+                    block.header.uncompressed_length.into(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Estimates the amount of bytes of the segment file that are read
+    /// when scanning the given range.
+    ///
+    /// The estimate is the share of top-level index entries that may contain
+    /// keys of the range, applied to the file size.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub(crate) fn estimate_range_size(
+        &self,
+        bounds: &(Bound<UserKey>, Bound<UserKey>),
+    ) -> crate::Result<u64> {
+        if !self.check_key_range_overlap(bounds) {
+            return Ok(0);
+        }
+
+        let tli = self.block_index.top_level_index()?;
+
+        // NOTE: A block contains the keys after the end key of the previous block,
+        // up to (and including) its own end key
+        let mut lower: Option<&UserKey> = None;
+        let mut overlapping: u64 = 0;
+
+        for handle in tli.iter() {
+            if block_overlaps(lower, &handle.end_key, bounds) {
+                overlapping += 1;
+            }
+            lower = Some(&handle.end_key);
+        }
+
+        let total = (tli.len() as u64).max(1);
+
+        Ok(self.metadata.file_size / total * overlapping)
+    }
+
     /// Returns the amount of tombstone markers in the `Segment`.
     #[must_use]
     pub fn tombstone_count(&self) -> u64 {
@@ -420,10 +1107,49 @@ impl Segment {
     }
 
     /// Checks if a key range is (partially or fully) contained in this segment.
+    ///
+    /// If the segment has prefix fences, the range also needs to overlap with any of its prefixes.
+    /// If the range only contains a single key, the key also needs to pass the bloom filter.
     pub(crate) fn check_key_range_overlap(
         &self,
         bounds: &(Bound<UserKey>, Bound<UserKey>),
     ) -> bool {
-        self.metadata.key_range.overlaps_with_bounds(bounds)
+        if !self.metadata.key_range.overlaps_with_bounds(bounds) {
+            return false;
+        }
+
+        if let Some(prefix_fences) = &self.metadata.prefix_fences {
+            if !prefix_fences.may_overlap(bounds) {
+                return false;
+            }
+        }
+
+        #[cfg(feature = "bloom")]
+        if let Some(key) = crate::range::single_key_range(bounds) {
+            return self.bloom_filter.contains(key);
+        }
+
+        true
     }
 }
+
+/// Returns `true` if a block that contains the keys in `(lower, end_key]`
+/// may contain keys of the given range.
+fn block_overlaps(
+    lower: Option<&UserKey>,
+    end_key: &UserKey,
+    bounds: &(Bound<UserKey>, Bound<UserKey>),
+) -> bool {
+    let above_lo = match &bounds.0 {
+        Bound::Included(key) => end_key >= key,
+        Bound::Excluded(key) => end_key > key,
+        Bound::Unbounded => true,
+    };
+
+    let below_hi = match (&bounds.1, lower) {
+        (Bound::Included(key) | Bound::Excluded(key), Some(lower)) => lower < key,
+        _ => true,
+    };
+
+    above_lo && below_hi
+}
@@ -6,7 +6,10 @@ use super::{
     trailer::SegmentFileTrailer,
     writer::{Options, Writer},
 };
-use crate::{value::InternalValue, CompressionType};
+use crate::{
+    compaction::warm::CacheWarmer, encryption::SegmentCipher, value::InternalValue,
+    CompressionType, UserKey,
+};
 use std::sync::{atomic::AtomicU64, Arc};
 
 #[cfg(feature = "bloom")]
@@ -15,7 +18,7 @@ use super::writer::BloomConstructionPolicy;
 /// Like `Writer` but will rotate to a new segment, once a segment grows larger than `target_size`
 ///
 /// This results in a sorted "run" of segments
-#[allow(clippy::module_name_repetitions)]
+#[allow(clippy::module_name_repetitions, clippy::struct_excessive_bools)]
 pub struct MultiWriter {
     /// Target size of segments in bytes
     ///
@@ -33,8 +36,35 @@ pub struct MultiWriter {
 
     pub compression: CompressionType,
 
+    cipher: Option<SegmentCipher>,
+
+    sync: bool,
+
+    seqno_index: bool,
+
+    shorten_index_keys: bool,
+
+    value_checksums: bool,
+
+    logical_clock: bool,
+
+    cache_warmer: Option<Arc<CacheWarmer>>,
+
+    prefix_fence_len: u8,
+
+    one_level_index_max_size: u64,
+
+    /// Sorted keys at which a new segment is started, in addition to the target size
+    boundaries: Vec<UserKey>,
+
+    /// Index of the next boundary that has not been crossed yet
+    next_boundary: usize,
+
     #[cfg(feature = "bloom")]
     bloom_policy: BloomConstructionPolicy,
+
+    #[cfg(feature = "bloom")]
+    bloom_hash_count: Option<u8>,
 }
 
 impl MultiWriter {
@@ -65,8 +95,32 @@ impl MultiWriter {
 
             compression: CompressionType::None,
 
+            cipher: None,
+
+            sync: true,
+
+            seqno_index: false,
+
+            shorten_index_keys: false,
+
+            value_checksums: false,
+
+            logical_clock: false,
+
+            cache_warmer: None,
+
+            prefix_fence_len: 0,
+
+            one_level_index_max_size: 0,
+
+            boundaries: vec![],
+            next_boundary: 0,
+
             #[cfg(feature = "bloom")]
             bloom_policy: BloomConstructionPolicy::default(),
+
+            #[cfg(feature = "bloom")]
+            bloom_hash_count: None,
         })
     }
 
@@ -77,6 +131,78 @@ impl MultiWriter {
         self
     }
 
+    #[must_use]
+    pub fn use_cipher(mut self, cipher: Option<SegmentCipher>) -> Self {
+        self.cipher.clone_from(&cipher);
+        self.writer = self.writer.use_cipher(cipher);
+        self
+    }
+
+    #[must_use]
+    pub fn use_sync(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self.writer = self.writer.use_sync(sync);
+        self
+    }
+
+    #[must_use]
+    pub fn use_seqno_index(mut self, enabled: bool) -> Self {
+        self.seqno_index = enabled;
+        self.writer = self.writer.use_seqno_index(enabled);
+        self
+    }
+
+    #[must_use]
+    pub fn use_prefix_fences(mut self, prefix_len: u8) -> Self {
+        self.prefix_fence_len = prefix_len;
+        self.writer = self.writer.use_prefix_fences(prefix_len);
+        self
+    }
+
+    #[must_use]
+    pub fn use_one_level_index(mut self, max_size: u64) -> Self {
+        self.one_level_index_max_size = max_size;
+        self.writer = self.writer.use_one_level_index(max_size);
+        self
+    }
+
+    #[must_use]
+    pub fn use_shortened_index_keys(mut self, enabled: bool) -> Self {
+        self.shorten_index_keys = enabled;
+        self.writer = self.writer.use_shortened_index_keys(enabled);
+        self
+    }
+
+    #[must_use]
+    pub fn use_value_checksums(mut self, enabled: bool) -> Self {
+        self.value_checksums = enabled;
+        self.writer = self.writer.use_value_checksums(enabled);
+        self
+    }
+
+    #[must_use]
+    pub fn use_logical_clock(mut self, enabled: bool) -> Self {
+        self.logical_clock = enabled;
+        self.writer = self.writer.use_logical_clock(enabled);
+        self
+    }
+
+    /// Sets the cache warmer that data blocks are passed to, once they are written.
+    #[must_use]
+    pub(crate) fn use_cache_warmer(mut self, cache_warmer: Option<Arc<CacheWarmer>>) -> Self {
+        self.cache_warmer.clone_from(&cache_warmer);
+        self.writer = self.writer.use_cache_warmer(cache_warmer);
+        self
+    }
+
+    /// Sets sorted keys at which a new segment is started, in addition to the target size.
+    #[must_use]
+    pub fn use_boundaries(mut self, boundaries: Vec<UserKey>) -> Self {
+        self.boundaries = boundaries;
+        self.next_boundary = 0;
+        self
+    }
+
     #[must_use]
     #[cfg(feature = "bloom")]
     pub fn use_bloom_policy(mut self, bloom_policy: BloomConstructionPolicy) -> Self {
@@ -85,6 +211,14 @@ impl MultiWriter {
         self
     }
 
+    #[must_use]
+    #[cfg(feature = "bloom")]
+    pub fn use_bloom_hash_count(mut self, bloom_hash_count: Option<u8>) -> Self {
+        self.bloom_hash_count = bloom_hash_count;
+        self.writer = self.writer.use_bloom_hash_count(bloom_hash_count);
+        self
+    }
+
     fn get_next_segment_id(&mut self) -> u64 {
         self.current_segment_id = self
             .segment_id_generator
@@ -108,43 +242,191 @@ impl MultiWriter {
             data_block_size: self.opts.data_block_size,
             index_block_size: self.opts.index_block_size,
         })?
-        .use_compression(self.compression);
+        .use_compression(self.compression)
+        .use_cipher(self.cipher.clone())
+        .use_sync(self.sync)
+        .use_seqno_index(self.seqno_index)
+        .use_prefix_fences(self.prefix_fence_len)
+        .use_one_level_index(self.one_level_index_max_size)
+        .use_shortened_index_keys(self.shorten_index_keys)
+        .use_value_checksums(self.value_checksums)
+        .use_logical_clock(self.logical_clock)
+        .use_cache_warmer(self.cache_warmer.clone());
 
         #[cfg(feature = "bloom")]
         {
-            new_writer = new_writer.use_bloom_policy(self.bloom_policy);
+            new_writer = new_writer
+                .use_bloom_policy(self.bloom_policy)
+                .use_bloom_hash_count(self.bloom_hash_count);
         }
 
         let mut old_writer = std::mem::replace(&mut self.writer, new_writer);
 
-        if old_writer.meta.item_count > 0 {
-            // NOTE: if-check checks for item count
-            self.results
-                .push(old_writer.finish()?.expect("writer should emit result"));
+        // NOTE: Items may still be buffered in the current block,
+        // so check the key count, which is updated on every write
+        if old_writer.meta.key_count > 0 {
+            match old_writer.finish() {
+                Ok(result) => self.results.extend(result),
+                Err(e) => {
+                    old_writer.abort();
+                    return Err(e);
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// Discards all segments written so far, removing their files
+    pub fn abort(self) {
+        for trailer in &self.results {
+            let segment_file_path = self.opts.folder.join(trailer.metadata.id.to_string());
+            log::debug!(
+                "Removing partial segment file at {}",
+                segment_file_path.display()
+            );
+
+            if let Err(e) = std::fs::remove_file(&segment_file_path) {
+                log::error!(
+                    "Failed to remove partial segment file {}: {e:?}",
+                    segment_file_path.display()
+                );
+            }
+        }
+
+        self.writer.abort();
+    }
+
+    /// Returns `true` if the key crosses the next key boundary(s).
+    fn crosses_boundary(&mut self, key: &UserKey) -> bool {
+        let mut crossed = false;
+
+        while let Some(boundary) = self.boundaries.get(self.next_boundary) {
+            if key < boundary {
+                break;
+            }
+
+            self.next_boundary += 1;
+            crossed = true;
+        }
+
+        crossed
+    }
+
     /// Writes an item
     pub fn write(&mut self, item: InternalValue) -> crate::Result<()> {
-        self.writer.write(item)?;
+        // NOTE: All versions of a key are kept in the same segment,
+        // because only the first version of a key can cross a boundary
+        if self.crosses_boundary(&item.key.user_key) && self.writer.meta.key_count > 0 {
+            self.rotate()?;
+        }
 
-        if self.writer.meta.file_pos >= self.target_size {
+        // NOTE: Only rotate when a new key starts, so every key is fully
+        // contained in a single segment (see `boundaries` above)
+        if self.writer.meta.file_pos >= self.target_size
+            && self.writer.meta.last_key.as_ref() != Some(&item.key.user_key)
+        {
             self.rotate()?;
         }
 
+        self.writer.write(item)?;
+
         Ok(())
     }
 
+    /// Returns the metadata of the segments that have been completely written so far
+    #[must_use]
+    pub fn results(&self) -> &[SegmentFileTrailer] {
+        &self.results
+    }
+
     /// Finishes the last segment, making sure all data is written durably
     ///
     /// Returns the metadata of created segments
+    ///
+    /// If finishing fails, all written segment files are removed.
     pub fn finish(mut self) -> crate::Result<Vec<SegmentFileTrailer>> {
-        if let Some(last_writer_result) = self.writer.finish()? {
-            self.results.push(last_writer_result);
+        match self.writer.finish() {
+            Ok(Some(last_writer_result)) => {
+                self.results.push(last_writer_result);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                self.abort();
+                return Err(e);
+            }
         }
 
         Ok(self.results)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ValueType;
+    use test_log::test;
+
+    #[test]
+    fn multi_writer_boundaries() -> crate::Result<()> {
+        let folder = tempfile::tempdir()?;
+
+        let mut writer = MultiWriter::new(
+            Arc::default(),
+            u64::MAX,
+            Options {
+                folder: folder.path().into(),
+                evict_tombstones: false,
+                segment_id: 0,
+                data_block_size: 4_096,
+                index_block_size: 4_096,
+            },
+        )?
+        .use_boundaries(vec![
+            UserKey::from(25u64.to_be_bytes()),
+            UserKey::from(26u64.to_be_bytes()),
+            UserKey::from(50u64.to_be_bytes()),
+            UserKey::from(200u64.to_be_bytes()),
+        ]);
+
+        for key in 0u64..100 {
+            for seqno in [1, 0] {
+                writer.write(InternalValue::from_components(
+                    key.to_be_bytes(),
+                    *b"",
+                    seqno,
+                    ValueType::Value,
+                ))?;
+            }
+        }
+
+        let trailers = writer.finish()?;
+
+        assert_eq!(
+            vec![
+                (
+                    UserKey::from(0u64.to_be_bytes()),
+                    UserKey::from(24u64.to_be_bytes())
+                ),
+                (
+                    UserKey::from(25u64.to_be_bytes()),
+                    UserKey::from(25u64.to_be_bytes())
+                ),
+                (
+                    UserKey::from(26u64.to_be_bytes()),
+                    UserKey::from(49u64.to_be_bytes())
+                ),
+                (
+                    UserKey::from(50u64.to_be_bytes()),
+                    UserKey::from(99u64.to_be_bytes())
+                ),
+            ],
+            trailers
+                .iter()
+                .map(|trailer| (*trailer.metadata.key_range).clone())
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{
+    block::header::Header as BlockHeader,
+    id::GlobalSegmentId,
+    value_block::ValueBlock,
+};
+use crate::{descriptor_table::FileDescriptorTable, read_pool::ReadPool, BlockCache};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+/// Segment whose blocks are being prefetched
+struct Source {
+    descriptor_table: Arc<FileDescriptorTable>,
+    block_cache: Arc<BlockCache>,
+    segment_id: GlobalSegmentId,
+
+    /// Blocks at or after `end` are never prefetched
+    end: u64,
+
+    /// Incremented by every request, so outdated requests can be skipped
+    generation: AtomicU64,
+}
+
+impl Source {
+    fn is_current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::Acquire) == generation
+    }
+}
+
+/// Request to load the given amount of data blocks, starting at the given offset
+struct Request {
+    source: Arc<Source>,
+    generation: u64,
+    offset: u64,
+    blocks: usize,
+}
+
+impl Request {
+    fn run(mut self) {
+        let source = &self.source;
+
+        if !source.is_current(self.generation) {
+            return;
+        }
+
+        // NOTE: The segment may have been dropped since the request was sent,
+        // which removes its file from the descriptor table, so there is nothing to prefetch
+        let Ok(Some(file_guard)) = source.descriptor_table.access(&source.segment_id) else {
+            return;
+        };
+
+        // NOTE: Stop once the scan has moved past the request, or the prefetcher was dropped
+        while self.blocks > 0 && self.offset < source.end && source.is_current(self.generation) {
+            let block = if let Some(block) = source
+                .block_cache
+                .get_disk_block(source.segment_id, self.offset)
+            {
+                block
+            } else {
+                // NOTE: Errors are not fatal, the scan will load the block itself
+                // and report the error
+                let Ok(block) = ValueBlock::from_file_with_cipher(
+                    &mut file_guard.reader(),
+                    self.offset,
+                    file_guard.cipher.as_ref(),
+                ) else {
+                    break;
+                };
+
+                let block = Arc::new(block);
+
+                source
+                    .block_cache
+                    .insert_disk_block(source.segment_id, self.offset, block.clone());
+
+                block
+            };
+
+            self.offset +=
+                BlockHeader::serialized_len() as u64 + u64::from(block.header.data_length);
+            self.blocks -= 1;
+        }
+    }
+}
+
+/// Loads data blocks ahead of a forward scan into the block cache
+///
+/// Blocks are loaded (and decompressed) by the read-ahead threads of the tree,
+/// so the scan can decode block N while blocks N+1..N+k are being read from disk,
+/// without starting a thread per segment.
+///
+/// Outstanding requests are abandoned once the prefetcher is dropped.
+pub struct Prefetcher {
+    source: Arc<Source>,
+    depth: usize,
+    pool: Arc<ReadPool>,
+}
+
+impl Prefetcher {
+    /// Creates a prefetcher for a segment.
+    ///
+    /// Blocks at or after `end` are never prefetched.
+    ///
+    /// Returns `None` if the tree has no read-ahead threads.
+    pub fn start(
+        pool: Arc<ReadPool>,
+        descriptor_table: Arc<FileDescriptorTable>,
+        block_cache: Arc<BlockCache>,
+        segment_id: GlobalSegmentId,
+        end: u64,
+        depth: usize,
+    ) -> Option<Self> {
+        if !pool.is_enabled() {
+            return None;
+        }
+
+        Some(Self {
+            source: Arc::new(Source {
+                descriptor_table,
+                block_cache,
+                segment_id,
+                end,
+                generation: AtomicU64::default(),
+            }),
+            depth,
+            pool,
+        })
+    }
+
+    /// Requests the next blocks, starting at the given offset, to be loaded.
+    ///
+    /// Supersedes any previous request of the prefetcher.
+    pub fn prefetch(&self, offset: u64) {
+        let generation = self.source.generation.fetch_add(1, Ordering::AcqRel) + 1;
+
+        let request = Request {
+            source: self.source.clone(),
+            generation,
+            offset,
+            blocks: self.depth,
+        };
+
+        // NOTE: If the pool has no threads, the scan simply reads blocks itself
+        let _ = self.pool.spawn(Box::new(move || request.run()));
+    }
+}
+
+impl Drop for Prefetcher {
+    fn drop(&mut self) {
+        // NOTE: Invalidate queued requests
+        self.source.generation.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::AbstractTree;
+    use std::time::{Duration, Instant};
+    use test_log::test;
+
+    #[test]
+    fn prefetch_loads_blocks_into_cache() -> crate::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let tree = crate::Config::new(&tempdir).data_block_size(1_024).open()?;
+
+        for x in 0u64..1_000 {
+            tree.insert(x.to_be_bytes(), vec![0; 100], 0);
+        }
+        tree.flush_active_memtable(0)?;
+
+        let segment = tree
+            .levels
+            .read()
+            .expect("lock is poisoned")
+            .iter()
+            .next()
+            .cloned()
+            .expect("should have segment");
+
+        let segment_id = (segment.tree_id, segment.metadata.id).into();
+
+        let prefetcher = Prefetcher::start(
+            Arc::new(ReadPool::new(1)),
+            segment.descriptor_table.clone(),
+            segment.block_cache.clone(),
+            segment_id,
+            segment.offsets.index_block_ptr,
+            4,
+        )
+        .expect("should start");
+
+        let block_count = segment.block_cache.len();
+        prefetcher.prefetch(0);
+
+        let start = Instant::now();
+
+        while segment.block_cache.len() < block_count + 4 {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "blocks were not prefetched"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        assert!(segment.block_cache.get_disk_block(segment_id, 0).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn prefetch_skips_removed_segment() -> crate::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let tree = crate::Config::new(&tempdir).data_block_size(1_024).open()?;
+
+        for x in 0u64..1_000 {
+            tree.insert(x.to_be_bytes(), vec![0; 100], 0);
+        }
+        tree.flush_active_memtable(0)?;
+
+        let segment = tree
+            .levels
+            .read()
+            .expect("lock is poisoned")
+            .iter()
+            .next()
+            .cloned()
+            .expect("should have segment");
+
+        let segment_id = (segment.tree_id, segment.metadata.id).into();
+
+        let prefetcher = Prefetcher::start(
+            Arc::new(ReadPool::new(1)),
+            segment.descriptor_table.clone(),
+            segment.block_cache.clone(),
+            segment_id,
+            segment.offsets.index_block_ptr,
+            4,
+        )
+        .expect("should start");
+
+        // NOTE: Same as dropping the last reference of a deleted segment
+        segment.descriptor_table.remove(segment_id);
+
+        let block_count = segment.block_cache.len();
+        prefetcher.prefetch(0);
+
+        std::thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(block_count, segment.block_cache.len());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{
+    coding::{Decode, DecodeError, Encode, EncodeError},
+    range::prefix_to_range,
+    UserKey,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    io::{Read, Write},
+    ops::Bound,
+};
+
+/// Maximum amount of distinct prefixes that are stored per segment
+///
+/// Segments with more distinct prefixes do not store any prefix fences,
+/// so the segment metadata stays small.
+pub const MAX_PREFIX_FENCES: usize = 1_024;
+
+/// Distinct key prefixes (the first N bytes of every key) of a segment
+///
+/// Keys that are shorter than the prefix length are stored as a whole.
+/// Range & prefix scans can skip a segment if none of its prefixes falls into
+/// the scanned range, even if the segment's key range overlaps with it
+/// (e.g. a segment that contains `a:*` and `c:*` does not need to be read for `b:*`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrefixFences {
+    prefix_len: u8,
+
+    /// Sorted, distinct prefixes
+    prefixes: Vec<UserKey>,
+}
+
+impl PrefixFences {
+    /// Creates an empty set of prefix fences.
+    #[must_use]
+    pub fn new(prefix_len: u8) -> Self {
+        Self {
+            prefix_len,
+            prefixes: vec![],
+        }
+    }
+
+    /// Returns the prefix length.
+    #[must_use]
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns the amount of distinct prefixes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    /// Returns `true` if there are no prefixes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+
+    /// Registers a key.
+    ///
+    /// Keys need to be pushed in sorted order.
+    ///
+    /// Returns `false` if there are more than [`MAX_PREFIX_FENCES`] distinct prefixes.
+    pub fn push(&mut self, key: &[u8]) -> bool {
+        let prefix = key.get(..self.prefix_len.into()).unwrap_or(key);
+
+        if self.prefixes.last().is_some_and(|last| &**last == prefix) {
+            return true;
+        }
+
+        if self.prefixes.len() >= MAX_PREFIX_FENCES {
+            return false;
+        }
+
+        self.prefixes.push(prefix.into());
+        true
+    }
+
+    /// Returns `true` if the segment may contain keys in the given range.
+    #[must_use]
+    pub fn may_overlap(&self, bounds: &(Bound<UserKey>, Bound<UserKey>)) -> bool {
+        let full_len = usize::from(self.prefix_len);
+
+        // NOTE: Every prefix covers its own key range ([prefix, successor of prefix)
+        // for full prefixes, [key, key] for short keys), and those ranges are sorted & disjoint,
+        // so we can skip all prefixes that end before the range starts
+        let idx = match &bounds.0 {
+            Bound::Included(lo) | Bound::Excluded(lo) => {
+                let lo_included = matches!(bounds.0, Bound::Included(_));
+
+                self.prefixes.partition_point(|prefix| {
+                    if prefix.len() < full_len {
+                        return if lo_included {
+                            prefix < lo
+                        } else {
+                            prefix <= lo
+                        };
+                    }
+
+                    match prefix_to_range(prefix).1 {
+                        Bound::Excluded(end) => end <= *lo,
+                        _ => false,
+                    }
+                })
+            }
+            Bound::Unbounded => 0,
+        };
+
+        self.prefixes
+            .get(idx)
+            .is_some_and(|prefix| match &bounds.1 {
+                Bound::Included(hi) => prefix <= hi,
+                Bound::Excluded(hi) => prefix < hi,
+                Bound::Unbounded => true,
+            })
+    }
+}
+
+impl Encode for PrefixFences {
+    fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        writer.write_u8(self.prefix_len)?;
+
+        // NOTE: Truncation is OK because there are at most MAX_PREFIX_FENCES prefixes
+        #[allow(clippy::cast_possible_truncation)]
+        writer.write_u32::<BigEndian>(self.prefixes.len() as u32)?;
+
+        for prefix in &self.prefixes {
+            // NOTE: Prefixes are at most u8::MAX bytes, or (if shorter) a whole key (u16)
+            #[allow(clippy::cast_possible_truncation)]
+            writer.write_u16::<BigEndian>(prefix.len() as u16)?;
+            writer.write_all(prefix)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Decode for PrefixFences {
+    fn decode_from<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let prefix_len = reader.read_u8()?;
+
+        let len = reader.read_u32::<BigEndian>()? as usize;
+        let mut prefixes = Vec::with_capacity(len.min(MAX_PREFIX_FENCES));
+
+        for _ in 0..len {
+            let prefix_size = reader.read_u16::<BigEndian>()?;
+            let mut prefix = vec![0; prefix_size.into()];
+            reader.read_exact(&mut prefix)?;
+            prefixes.push(prefix.into());
+        }
+
+        Ok(Self {
+            prefix_len,
+            prefixes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use test_log::test;
+    use Bound::{Excluded, Included, Unbounded};
+
+    fn fences(prefix_len: u8, keys: &[&str]) -> PrefixFences {
+        let mut fences = PrefixFences::new(prefix_len);
+        for key in keys {
+            assert!(fences.push(key.as_bytes()));
+        }
+        fences
+    }
+
+    fn key(key: &str) -> UserKey {
+        key.as_bytes().into()
+    }
+
+    #[test]
+    fn prefix_fences_push_dedup() {
+        let fences = fences(2, &["a", "a:1", "a:2", "b:1", "c:1", "c:2"]);
+        assert_eq!(4, fences.len());
+    }
+
+    #[test]
+    fn prefix_fences_push_limit() {
+        let mut fences = PrefixFences::new(4);
+
+        for x in 0..MAX_PREFIX_FENCES as u32 {
+            assert!(fences.push(&x.to_be_bytes()));
+        }
+        assert!(!fences.push(&u32::MAX.to_be_bytes()));
+    }
+
+    #[test]
+    fn prefix_fences_prefix_scan() {
+        let fences = fences(2, &["a:1", "a:2", "c:1", "c:2"]);
+
+        assert!(fences.may_overlap(&prefix_to_range(b"a:")));
+        assert!(fences.may_overlap(&prefix_to_range(b"a:1")));
+        assert!(fences.may_overlap(&prefix_to_range(b"a:3")));
+        assert!(!fences.may_overlap(&prefix_to_range(b"b:")));
+        assert!(!fences.may_overlap(&prefix_to_range(b"b")));
+        assert!(fences.may_overlap(&prefix_to_range(b"c")));
+        assert!(!fences.may_overlap(&prefix_to_range(b"d")));
+        assert!(fences.may_overlap(&prefix_to_range(b"")));
+    }
+
+    #[test]
+    fn prefix_fences_range() {
+        let fences = fences(2, &["a", "a:1", "c:1"]);
+
+        assert!(fences.may_overlap(&(Included(key("a")), Included(key("a")))));
+        assert!(!fences.may_overlap(&(Excluded(key("a")), Excluded(key("a:")))));
+        assert!(!fences.may_overlap(&(Included(key("a;")), Excluded(key("c:")))));
+        assert!(fences.may_overlap(&(Included(key("a;")), Included(key("c:")))));
+        assert!(fences.may_overlap(&(Included(key("b")), Unbounded)));
+        assert!(fences.may_overlap(&(Unbounded, Excluded(key("a:")))));
+        assert!(!fences.may_overlap(&(Unbounded, Excluded(key("a")))));
+        assert!(!fences.may_overlap(&(Included(key("c;")), Unbounded)));
+    }
+
+    #[test]
+    fn prefix_fences_serde_round_trip() -> crate::Result<()> {
+        let fences = fences(2, &["a", "a:1", "c:1"]);
+
+        let bytes = fences.encode_into_vec()?;
+        let mut cursor = Cursor::new(bytes);
+        assert_eq!(fences, PrefixFences::decode_from(&mut cursor)?);
+
+        Ok(())
+    }
+}
@@ -9,6 +9,7 @@ use super::value_block::CachePolicy;
 use crate::block_cache::BlockCache;
 use crate::descriptor_table::FileDescriptorTable;
 use crate::value::InternalValue;
+use crate::value::SeqNo;
 use crate::value::UserKey;
 use crate::Slice;
 use std::ops::Bound;
@@ -25,6 +26,8 @@ pub struct Range {
     reader: Reader,
 
     cache_policy: CachePolicy,
+
+    seqno_offset: SeqNo,
 }
 
 impl Range {
@@ -36,7 +39,7 @@ impl Range {
         block_index: Arc<TwoLevelBlockIndex>,
         range: (Bound<UserKey>, Bound<UserKey>),
     ) -> Self {
-        let reader = Reader::new(
+        let mut reader = Reader::new(
             data_block_boundary,
             descriptor_table,
             segment_id,
@@ -44,6 +47,10 @@ impl Range {
             0,
             None,
         );
+        reader.metrics.clone_from(&block_index.metrics);
+        reader.readahead = block_index.readahead;
+        reader.readahead_bytes = block_index.readahead_bytes;
+        reader.read_pool.clone_from(&block_index.read_pool);
 
         Self {
             is_initialized: false,
@@ -54,13 +61,46 @@ impl Range {
             range,
 
             cache_policy: CachePolicy::Write,
+
+            seqno_offset: 0,
         }
     }
 
+    /// Sets the offset that is added to the seqno of every item
+    #[must_use]
+    pub(crate) fn seqno_offset(mut self, seqno_offset: SeqNo) -> Self {
+        self.seqno_offset = seqno_offset;
+        self
+    }
+
     /// Sets the cache policy
     #[must_use]
     pub fn cache_policy(mut self, policy: CachePolicy) -> Self {
         self.cache_policy = policy;
+        self.reader.cache_policy = policy;
+        self
+    }
+
+    /// Sets the amount of data blocks that are prefetched ahead of forward scans
+    #[must_use]
+    pub fn readahead(mut self, depth: usize) -> Self {
+        self.reader.readahead = depth;
+        self
+    }
+
+    /// Sets the amount of bytes that forward scans request per I/O on block cache misses (0 = disabled)
+    #[must_use]
+    pub fn readahead_bytes(mut self, bytes: usize) -> Self {
+        self.reader.readahead_bytes = bytes;
+        self
+    }
+
+    /// Sets the size of a dedicated read buffer for forward scans (0 = disabled)
+    ///
+    /// Data blocks are then read sequentially through the buffer, bypassing the block cache.
+    #[must_use]
+    pub fn read_buffer(mut self, bytes: usize) -> Self {
+        self.reader.read_buffer_size = bytes;
         self
     }
 
@@ -72,7 +112,7 @@ impl Range {
             Bound::Included(start) | Bound::Excluded(start) => {
                 if let Some(lower_bound) = self
                     .block_index
-                    .get_lowest_data_block_handle_containing_item(start, CachePolicy::Write)?
+                    .get_lowest_data_block_handle_containing_item(start, self.cache_policy)?
                 {
                     self.reader.lo_block_offset = lower_bound.offset;
                 }
@@ -87,7 +127,7 @@ impl Range {
             Bound::Unbounded => {
                 let upper_bound = self
                     .block_index
-                    .get_last_data_block_handle(CachePolicy::Write)?;
+                    .get_last_data_block_handle(self.cache_policy)?;
 
                 self.reader.hi_block_offset = Some(upper_bound.offset);
 
@@ -96,7 +136,7 @@ impl Range {
             Bound::Included(end) | Bound::Excluded(end) => {
                 if let Some(upper_bound) = self
                     .block_index
-                    .get_last_data_block_handle_containing_item(end, CachePolicy::Write)?
+                    .get_last_data_block_handle_containing_item(end, self.cache_policy)?
                 {
                     self.reader.hi_block_offset = Some(upper_bound.offset);
                 }
@@ -132,7 +172,7 @@ impl Iterator for Range {
             let entry_result = self.reader.next()?;
 
             match entry_result {
-                Ok(entry) => {
+                Ok(mut entry) => {
                     match self.range.start_bound() {
                         Bound::Included(start) => {
                             if entry.key.user_key < *start {
@@ -165,6 +205,8 @@ impl Iterator for Range {
                         Bound::Unbounded => {}
                     }
 
+                    entry.key.seqno += self.seqno_offset;
+
                     return Some(Ok(entry));
                 }
                 Err(error) => return Some(Err(error)),
@@ -185,7 +227,7 @@ impl DoubleEndedIterator for Range {
             let entry_result = self.reader.next_back()?;
 
             match entry_result {
-                Ok(entry) => {
+                Ok(mut entry) => {
                     match self.range.start_bound() {
                         Bound::Included(start) => {
                             if entry.key.user_key < *start {
@@ -218,6 +260,8 @@ impl DoubleEndedIterator for Range {
                         Bound::Unbounded => {}
                     }
 
+                    entry.key.seqno += self.seqno_offset;
+
                     return Some(Ok(entry));
                 }
                 Err(error) => return Some(Err(error)),
@@ -291,6 +335,8 @@ mod tests {
             (0, 0).into(),
             table.clone(),
             block_cache.clone(),
+            None,
+            trailer.index_item_counts,
         )?);
 
         let iter = Range::new(
@@ -390,6 +436,8 @@ mod tests {
             (0, 0).into(),
             table.clone(),
             block_cache.clone(),
+            None,
+            trailer.index_item_counts,
         )?);
 
         {
@@ -590,6 +638,8 @@ mod tests {
                 (0, 0).into(),
                 table.clone(),
                 block_cache.clone(),
+                None,
+                trailer.index_item_counts,
             )?);
 
             let ranges: Vec<(Bound<u64>, Bound<u64>)> = vec![
@@ -693,6 +743,8 @@ mod tests {
             (0, 0).into(),
             table.clone(),
             block_cache.clone(),
+            None,
+            trailer.index_item_counts,
         )?);
 
         for (i, &start_char) in chars.iter().enumerate() {
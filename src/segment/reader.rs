@@ -3,11 +3,18 @@
 // (found in the LICENSE-* files in the repository)
 
 use super::{
+    prefetch::Prefetcher,
     value_block::{CachePolicy, ValueBlock},
     value_block_consumer::ValueBlockConsumer,
 };
 use crate::{
-    descriptor_table::FileDescriptorTable, segment::block::header::Header, value::InternalValue,
+    descriptor_table::{FileDescriptorTable, SequentialReader},
+    encryption::SegmentCipher,
+    error::{ErrorContext, Operation},
+    metrics::{MetricsSink, BLOCK_CACHE_HITS, BLOCK_CACHE_MISSES},
+    read_pool::ReadPool,
+    segment::block::header::Header,
+    value::InternalValue,
     BlockCache, GlobalSegmentId, UserKey,
 };
 use std::sync::Arc;
@@ -32,7 +39,29 @@ pub struct Reader {
     start_key: Option<UserKey>,
     end_key: Option<UserKey>,
 
-    cache_policy: CachePolicy,
+    pub(crate) cache_policy: CachePolicy,
+
+    /// Sink that receives block cache hits & misses
+    pub(crate) metrics: Option<Arc<dyn MetricsSink>>,
+
+    /// Amount of data blocks to prefetch ahead of forward scans (0 = disabled)
+    pub(crate) readahead: usize,
+
+    /// Threads of the tree that prefetch data blocks
+    pub(crate) read_pool: Option<Arc<ReadPool>>,
+
+    /// Started once the scan moves on to its second data block
+    prefetcher: Option<Prefetcher>,
+
+    /// Size of the dedicated read buffer of forward scans (0 = disabled)
+    pub(crate) read_buffer_size: usize,
+
+    /// Amount of bytes that forward scans request per I/O on block cache misses (0 = disabled)
+    pub(crate) readahead_bytes: usize,
+
+    /// Opened once a forward scan loads its first data block from disk,
+    /// if a read buffer or readahead is configured
+    sequential: Option<(SequentialReader, Option<SegmentCipher>)>,
 }
 
 impl Reader {
@@ -66,6 +95,16 @@ impl Reader {
 
             start_key: None,
             end_key: None,
+
+            metrics: None,
+
+            readahead: 0,
+            read_pool: None,
+            prefetcher: None,
+
+            read_buffer_size: 0,
+            readahead_bytes: 0,
+            sequential: None,
         }
     }
 
@@ -96,6 +135,7 @@ impl Reader {
             self.segment_id,
             offset,
             self.cache_policy,
+            self.metrics.as_deref(),
         )?;
 
         // Truncate as many items as possible
@@ -108,8 +148,119 @@ impl Reader {
         })
     }
 
+    /// Loads a data block of the forward direction.
+    ///
+    /// If a read buffer is configured, consecutive blocks are read through a
+    /// dedicated, sequential reader, bypassing the block cache, so at most one
+    /// decoded block (and the read buffer) is held in memory at any time.
+    ///
+    /// If readahead is configured, blocks that are not cached are read through a
+    /// sequential reader as well, so the following blocks are read in the same I/O.
+    fn load_lo_data_block(
+        &mut self,
+        offset: u64,
+    ) -> crate::Result<Option<(u64, u64, ValueBlockConsumer)>> {
+        let bypass_cache = self.read_buffer_size > 0;
+
+        if !bypass_cache && self.readahead_bytes > 0 {
+            if let Some(block) = self.block_cache.get_disk_block(self.segment_id, offset) {
+                if let Some(sink) = &self.metrics {
+                    sink.counter(BLOCK_CACHE_HITS, 1);
+                }
+
+                return Ok(Some((
+                    block.header.data_length.into(),
+                    block.header.previous_block_offset,
+                    ValueBlockConsumer::with_bounds(block, &self.start_key, &self.end_key),
+                )));
+            }
+
+            if let Some(sink) = &self.metrics {
+                sink.counter(BLOCK_CACHE_MISSES, 1);
+            }
+        }
+
+        if self.sequential.is_none() {
+            let buffer_size = if bypass_cache {
+                self.read_buffer_size
+            } else {
+                self.readahead_bytes
+            };
+
+            if buffer_size > 0 {
+                self.sequential = self
+                    .descriptor_table
+                    .open_sequential(&self.segment_id, buffer_size)?;
+            }
+        }
+
+        let Some((reader, cipher)) = &mut self.sequential else {
+            return self.load_data_block(offset);
+        };
+
+        let block =
+            ValueBlock::from_file_with_cipher(reader, offset, cipher.as_ref()).map_err(|e| {
+                log::error!(
+                    "Failed to load value block {:?}/{offset:?}: {e:?}",
+                    self.segment_id
+                );
+                e.with_context(
+                    ErrorContext::new(Operation::Read)
+                        .with_segment_id(self.segment_id.segment_id())
+                        .with_block_offset(offset),
+                )
+            })?;
+        let block = Arc::new(block);
+
+        if !bypass_cache && self.cache_policy == CachePolicy::Write {
+            self.block_cache
+                .insert_disk_block(self.segment_id, offset, block.clone());
+        }
+
+        Ok(Some((
+            block.header.data_length.into(),
+            block.header.previous_block_offset,
+            ValueBlockConsumer::with_bounds(block, &self.start_key, &self.end_key),
+        )))
+    }
+
+    /// Requests the blocks following the block at the given offset to be prefetched
+    fn prefetch_after(&mut self, offset: u64, size: u64) {
+        if self.readahead == 0 || self.cache_policy != CachePolicy::Write {
+            return;
+        }
+
+        if self.prefetcher.is_none() {
+            // NOTE: The last block of the range is still prefetched
+            let end = self
+                .hi_block_offset
+                .map_or(self.data_block_boundary, |hi| hi + 1)
+                .min(self.data_block_boundary);
+
+            self.prefetcher = self.read_pool.clone().and_then(|read_pool| {
+                Prefetcher::start(
+                    read_pool,
+                    self.descriptor_table.clone(),
+                    self.block_cache.clone(),
+                    self.segment_id,
+                    end,
+                    self.readahead,
+                )
+            });
+
+            // NOTE: Do not retry if the tree has no prefetch threads
+            if self.prefetcher.is_none() {
+                self.readahead = 0;
+            }
+        }
+
+        if let Some(prefetcher) = &self.prefetcher {
+            prefetcher.prefetch(offset + Header::serialized_len() as u64 + size);
+        }
+    }
+
     fn initialize_lo(&mut self) -> crate::Result<()> {
-        if let Some((size, _, items)) = self.load_data_block(self.lo_block_offset)? {
+        if let Some((size, _, items)) = self.load_lo_data_block(self.lo_block_offset)? {
             self.lo_block_items = Some(items);
             self.lo_block_size = size;
         }
@@ -175,12 +326,14 @@ impl Iterator for Reader {
             }
         }
 
-        match fail_iter!(self.load_data_block(next_block_offset)) {
+        match fail_iter!(self.load_lo_data_block(next_block_offset)) {
             Some((size, _, items)) => {
                 self.lo_block_items = Some(items);
                 self.lo_block_size = size;
                 self.lo_block_offset = next_block_offset;
 
+                self.prefetch_after(next_block_offset, size);
+
                 // We just loaded the block
                 self.lo_block_items.as_mut()?.next().map(Ok)
             }
@@ -0,0 +1,210 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::block::checksum::Checksum;
+use crate::{
+    coding::{Decode, DecodeError, Encode, EncodeError},
+    encryption::SegmentCipher,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+/// Writes a section (bloom filter, metadata) of a segment file
+///
+/// The section is framed by its length and checksum, so corruption
+/// is detected when loading the section:
+///
+/// \[ length \]
+/// \[checksum\]
+/// \[ payload \]
+///
+/// If encrypted, the payload is the ciphertext (bound to the offset of the section),
+/// and the checksum is calculated over the ciphertext.
+pub fn write_section<T: Encode, W: Write>(
+    writer: &mut W,
+    offset: u64,
+    item: &T,
+    cipher: Option<&SegmentCipher>,
+) -> Result<(), EncodeError> {
+    let mut payload = item.encode_into_vec()?;
+
+    if let Some(cipher) = cipher {
+        payload = cipher.encrypt(offset, &payload)?;
+    }
+
+    // NOTE: Truncation is OK because sections are never larger than 4 GiB
+    #[allow(clippy::cast_possible_truncation)]
+    writer.write_u32::<BigEndian>(payload.len() as u32)?;
+    writer.write_u64::<BigEndian>(*Checksum::from_bytes(&payload))?;
+    writer.write_all(&payload)?;
+
+    Ok(())
+}
+
+/// Reads a section (bloom filter, metadata) of a segment file
+///
+/// Segments written by older versions do not checksum their sections,
+/// which is signalled by `checksummed` being `false`.
+///
+/// # Errors
+///
+/// Will return `Err` if the section is corrupted.
+pub fn read_section<T: Decode, R: Read>(
+    reader: &mut R,
+    offset: u64,
+    cipher: Option<&SegmentCipher>,
+    checksummed: bool,
+) -> crate::Result<T> {
+    if !checksummed {
+        return read_legacy_section(reader, offset, cipher);
+    }
+
+    let len = reader.read_u32::<BigEndian>()?;
+    let expected_checksum = Checksum::from_raw(reader.read_u64::<BigEndian>()?);
+
+    let mut payload = vec![0; len as usize];
+    reader.read_exact(&mut payload)?;
+
+    let got_checksum = Checksum::from_bytes(&payload);
+
+    if got_checksum != expected_checksum {
+        log::error!(
+            "Checksum mismatch in segment section: got={}, expected={}",
+            *got_checksum,
+            *expected_checksum,
+        );
+        return Err(crate::Error::InvalidChecksum((
+            got_checksum,
+            expected_checksum,
+        )));
+    }
+
+    if let Some(cipher) = cipher {
+        payload = cipher.decrypt(offset, &payload)?;
+    }
+
+    let mut payload = payload.as_slice();
+    let item = T::decode_from(&mut payload)?;
+
+    if !payload.is_empty() {
+        return Err(crate::Error::Decode(DecodeError::InvalidContent {
+            name: "SegmentSection",
+            reason: format!("{} trailing bytes", payload.len()),
+        }));
+    }
+
+    Ok(item)
+}
+
+/// Reads a section without length framing or checksum
+fn read_legacy_section<T: Decode, R: Read>(
+    reader: &mut R,
+    offset: u64,
+    cipher: Option<&SegmentCipher>,
+) -> crate::Result<T> {
+    let Some(cipher) = cipher else {
+        return Ok(T::decode_from(reader)?);
+    };
+
+    let len = reader.read_u32::<BigEndian>()?;
+
+    let mut ciphertext = vec![0; len as usize];
+    reader.read_exact(&mut ciphertext)?;
+
+    let plaintext = cipher.decrypt(offset, &ciphertext)?;
+    Ok(T::decode_from(&mut plaintext.as_slice())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::segment::file_offsets::FileOffsets;
+    use test_log::test;
+
+    fn offsets() -> FileOffsets {
+        FileOffsets {
+            bloom_ptr: 15,
+            index_block_ptr: 14,
+            metadata_ptr: 17,
+            pfx_ptr: 18,
+            range_filter_ptr: 13,
+            range_tombstones_ptr: 5,
+            tli_ptr: 4,
+        }
+    }
+
+    #[test]
+    fn section_roundtrip() -> crate::Result<()> {
+        let before = offsets();
+
+        let mut buf = vec![];
+        write_section(&mut buf, 0, &before, None)?;
+
+        let after: FileOffsets = read_section(&mut buf.as_slice(), 0, None, true)?;
+        assert_eq!(before, after);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "encryption")]
+    fn section_roundtrip_encrypted() -> crate::Result<()> {
+        use crate::encryption::{tests::XorCipher, BlockCipher};
+        use std::sync::Arc;
+
+        let cipher: Arc<dyn BlockCipher> = Arc::new(XorCipher);
+        let cipher = SegmentCipher::for_new_segment(&cipher);
+        assert_eq!(7, cipher.key_id());
+
+        let before = offsets();
+
+        let mut buf = vec![];
+        write_section(&mut buf, 0, &before, Some(&cipher))?;
+        assert_ne!(buf.get(12..), Some(&*before.encode_into_vec()?));
+
+        let after: FileOffsets = read_section(&mut buf.as_slice(), 0, Some(&cipher), true)?;
+        assert_eq!(before, after);
+
+        // NOTE: The section is bound to its position
+        assert!(
+            read_section::<FileOffsets, _>(&mut buf.as_slice(), 1, Some(&cipher), true).is_err()
+        );
+
+        let other_segment = cipher.clone().for_segment(1);
+        assert!(
+            read_section::<FileOffsets, _>(&mut buf.as_slice(), 0, Some(&other_segment), true)
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn section_detect_corruption() -> crate::Result<()> {
+        let mut buf = vec![];
+        write_section(&mut buf, 0, &offsets(), None)?;
+
+        if let Some(byte) = buf.last_mut() {
+            *byte ^= 0xFF;
+        }
+
+        assert!(matches!(
+            read_section::<FileOffsets, _>(&mut buf.as_slice(), 0, None, true),
+            Err(crate::Error::InvalidChecksum(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn section_legacy() -> crate::Result<()> {
+        let before = offsets();
+        let buf = before.encode_into_vec()?;
+
+        let after: FileOffsets = read_section(&mut buf.as_slice(), 0, None, false)?;
+        assert_eq!(before, after);
+
+        Ok(())
+    }
+}
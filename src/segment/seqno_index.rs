@@ -0,0 +1,154 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{section::read_section, trailer::SegmentFileTrailer};
+use crate::{
+    coding::{Decode, DecodeError, Encode, EncodeError},
+    encryption::SegmentCipher,
+    value::SeqNo,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// Seqno range of a single data block
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SeqnoIndexEntry {
+    /// Offset of the data block in the segment file
+    pub offset: u64,
+
+    /// Lowest seqno in the data block
+    pub lo: SeqNo,
+
+    /// Highest seqno in the data block
+    pub hi: SeqNo,
+}
+
+/// Secondary index that maps the seqno ranges of a segment's data blocks to their offsets
+///
+/// Because a segment is sorted by key, not by seqno, every data block can contain
+/// any seqno. The index allows skipping all data blocks that only contain items older
+/// than a given seqno, without loading them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SeqnoIndex {
+    entries: Vec<SeqnoIndexEntry>,
+}
+
+impl SeqnoIndex {
+    /// Loads the seqno index of a segment file, if it was written with one.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        trailer: &SegmentFileTrailer,
+        cipher: Option<&SegmentCipher>,
+    ) -> crate::Result<Option<Self>> {
+        let ptr = trailer.seqno_index_ptr;
+
+        if ptr == 0 {
+            return Ok(None);
+        }
+
+        let mut reader = File::open(path)?;
+        reader.seek(SeekFrom::Start(ptr))?;
+
+        read_section(&mut reader, ptr, cipher, trailer.checksummed_sections)
+            .map(Some)
+            .map_err(|e| e.at("SeqnoIndex", ptr))
+    }
+
+    /// Registers a data block.
+    pub fn push(&mut self, offset: u64, lo: SeqNo, hi: SeqNo) {
+        self.entries.push(SeqnoIndexEntry { offset, lo, hi });
+    }
+
+    /// Returns the amount of indexed data blocks.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no data blocks are indexed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the offsets of all data blocks that contain items with seqno >= `seqno`,
+    /// in file order.
+    pub fn blocks_since(&self, seqno: SeqNo) -> impl Iterator<Item = u64> + '_ {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.hi >= seqno)
+            .map(|entry| entry.offset)
+    }
+}
+
+impl Encode for SeqnoIndex {
+    fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        // NOTE: Truncation is OK because a segment never has more than u32::MAX data blocks
+        #[allow(clippy::cast_possible_truncation)]
+        writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
+
+        for entry in &self.entries {
+            writer.write_u64::<BigEndian>(entry.offset)?;
+            writer.write_u64::<BigEndian>(entry.lo)?;
+            writer.write_u64::<BigEndian>(entry.hi)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Decode for SeqnoIndex {
+    fn decode_from<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let len = reader.read_u32::<BigEndian>()? as usize;
+
+        let mut entries = Vec::with_capacity(len);
+
+        for _ in 0..len {
+            let offset = reader.read_u64::<BigEndian>()?;
+            let lo = reader.read_u64::<BigEndian>()?;
+            let hi = reader.read_u64::<BigEndian>()?;
+
+            entries.push(SeqnoIndexEntry { offset, lo, hi });
+        }
+
+        Ok(Self { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn seqno_index_roundtrip() -> crate::Result<()> {
+        let mut before = SeqnoIndex::default();
+        before.push(0, 5, 10);
+        before.push(100, 0, 3);
+        before.push(250, 2, 20);
+
+        let buf = before.encode_into_vec()?;
+        let after = SeqnoIndex::decode_from(&mut buf.as_slice())?;
+        assert_eq!(before, after);
+
+        Ok(())
+    }
+
+    #[test]
+    fn seqno_index_blocks_since() {
+        let mut index = SeqnoIndex::default();
+        index.push(0, 5, 10);
+        index.push(100, 0, 3);
+        index.push(250, 2, 20);
+
+        assert_eq!(vec![0, 100, 250], index.blocks_since(0).collect::<Vec<_>>());
+        assert_eq!(vec![0, 250], index.blocks_since(4).collect::<Vec<_>>());
+        assert_eq!(vec![250], index.blocks_since(11).collect::<Vec<_>>());
+        assert!(index.blocks_since(21).next().is_none());
+    }
+}
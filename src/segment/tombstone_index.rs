@@ -0,0 +1,214 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{section::read_section, trailer::SegmentFileTrailer};
+use crate::{
+    coding::{Decode, DecodeError, Encode, EncodeError},
+    encryption::SegmentCipher,
+    key_range::KeyRange,
+    UserKey,
+};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// Tombstone statistics of a single data block
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TombstoneIndexEntry {
+    /// Offset of the data block in the segment file
+    pub offset: u64,
+
+    /// Key range of the data block
+    pub key_range: KeyRange,
+
+    /// Amount of items in the data block
+    pub item_count: u32,
+
+    /// Amount of tombstones in the data block
+    pub tombstone_count: u32,
+}
+
+/// Secondary index that records the tombstone count of the data blocks of a segment
+///
+/// Only data blocks that contain tombstones are recorded. The index allows
+/// finding out which key ranges of a segment are dense with deletions,
+/// without loading any data block.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TombstoneIndex {
+    entries: Vec<TombstoneIndexEntry>,
+}
+
+impl TombstoneIndex {
+    /// Loads the tombstone index of a segment file, if it has one.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        trailer: &SegmentFileTrailer,
+        cipher: Option<&SegmentCipher>,
+    ) -> crate::Result<Option<Self>> {
+        let ptr = trailer.tombstone_index_ptr;
+
+        if ptr == 0 {
+            return Ok(None);
+        }
+
+        let mut reader = File::open(path)?;
+        reader.seek(SeekFrom::Start(ptr))?;
+
+        read_section(&mut reader, ptr, cipher, trailer.checksummed_sections)
+            .map(Some)
+            .map_err(|e| e.at("TombstoneIndex", ptr))
+    }
+
+    /// Registers a data block, if it contains any tombstones.
+    pub fn push(
+        &mut self,
+        offset: u64,
+        key_range: (UserKey, UserKey),
+        item_count: u32,
+        tombstone_count: u32,
+    ) {
+        if tombstone_count > 0 {
+            self.entries.push(TombstoneIndexEntry {
+                offset,
+                key_range: KeyRange::new(key_range),
+                item_count,
+                tombstone_count,
+            });
+        }
+    }
+
+    /// Returns the amount of indexed data blocks.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no data blocks are indexed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the indexed data blocks, in key order.
+    #[must_use]
+    pub fn entries(&self) -> &[TombstoneIndexEntry] {
+        &self.entries
+    }
+
+    /// Sums up the item & tombstone counts of all data blocks that
+    /// overlap with any of the given key ranges.
+    pub fn count_overlapping<'a, I: IntoIterator<Item = &'a KeyRange>>(
+        &self,
+        key_ranges: I,
+    ) -> (u64, u64) {
+        let key_ranges = key_ranges.into_iter().collect::<Vec<_>>();
+
+        self.entries
+            .iter()
+            .filter(|entry| {
+                key_ranges
+                    .iter()
+                    .any(|key_range| entry.key_range.overlaps_with_key_range(key_range))
+            })
+            .fold((0, 0), |(items, tombstones), entry| {
+                (
+                    items + u64::from(entry.item_count),
+                    tombstones + u64::from(entry.tombstone_count),
+                )
+            })
+    }
+}
+
+impl Encode for TombstoneIndex {
+    fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        // NOTE: Truncation is OK because a segment never has more than u32::MAX data blocks
+        #[allow(clippy::cast_possible_truncation)]
+        writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
+
+        for entry in &self.entries {
+            writer.write_u64::<BigEndian>(entry.offset)?;
+            writer.write_u32::<BigEndian>(entry.item_count)?;
+            writer.write_u32::<BigEndian>(entry.tombstone_count)?;
+            entry.key_range.encode_into(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Decode for TombstoneIndex {
+    fn decode_from<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        let len = reader.read_u32::<BigEndian>()? as usize;
+
+        let mut entries = Vec::with_capacity(len);
+
+        for _ in 0..len {
+            let offset = reader.read_u64::<BigEndian>()?;
+            let item_count = reader.read_u32::<BigEndian>()?;
+            let tombstone_count = reader.read_u32::<BigEndian>()?;
+            let key_range = KeyRange::decode_from(reader)?;
+
+            entries.push(TombstoneIndexEntry {
+                offset,
+                key_range,
+                item_count,
+                tombstone_count,
+            });
+        }
+
+        Ok(Self { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    fn key_range(lo: &str, hi: &str) -> (UserKey, UserKey) {
+        (lo.as_bytes().into(), hi.as_bytes().into())
+    }
+
+    #[test]
+    fn tombstone_index_roundtrip() -> crate::Result<()> {
+        let mut before = TombstoneIndex::default();
+        before.push(0, key_range("a", "c"), 10, 5);
+        before.push(100, key_range("d", "f"), 10, 0);
+        before.push(250, key_range("g", "k"), 20, 1);
+        assert_eq!(2, before.len());
+
+        let buf = before.encode_into_vec()?;
+        let after = TombstoneIndex::decode_from(&mut buf.as_slice())?;
+        assert_eq!(before, after);
+
+        Ok(())
+    }
+
+    #[test]
+    fn tombstone_index_count_overlapping() {
+        let mut index = TombstoneIndex::default();
+        index.push(0, key_range("a", "c"), 10, 5);
+        index.push(250, key_range("g", "k"), 20, 1);
+
+        assert_eq!((0, 0), index.count_overlapping([]));
+        assert_eq!(
+            (10, 5),
+            index.count_overlapping([&KeyRange::new(key_range("b", "d"))])
+        );
+        assert_eq!(
+            (30, 6),
+            index.count_overlapping([
+                &KeyRange::new(key_range("b", "d")),
+                &KeyRange::new(key_range("k", "z")),
+            ])
+        );
+        assert_eq!(
+            (0, 0),
+            index.count_overlapping([&KeyRange::new(key_range("d", "f"))])
+        );
+    }
+}
@@ -2,11 +2,17 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use super::{file_offsets::FileOffsets, meta::Metadata};
+use super::{
+    file_offsets::FileOffsets,
+    meta::{Metadata, SegmentFileLayout, SegmentId},
+    section::read_section,
+};
 use crate::{
     coding::{Decode, DecodeError, Encode, EncodeError},
+    encryption::{Cipher, SegmentCipher},
     file::MAGIC_BYTES,
 };
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::{
     fs::File,
     io::{BufReader, Read, Seek, Write},
@@ -23,18 +29,105 @@ pub struct SegmentFileTrailer {
 
     #[doc(hidden)]
     pub offsets: FileOffsets,
+
+    /// ID of the key the segment is encrypted with, if encrypted
+    #[doc(hidden)]
+    pub key_id: Option<u32>,
+
+    /// ID the segment was written with, which its encrypted blocks are bound to
+    ///
+    /// The ID of a segment can change (see [`crate::Tree::absorb`]), so it is stored separately.
+    #[doc(hidden)]
+    pub cipher_segment_id: SegmentId,
+
+    /// Whether the bloom filter & metadata sections are framed by length and checksum
+    ///
+    /// Segments written by older versions do not have section checksums.
+    #[doc(hidden)]
+    pub checksummed_sections: bool,
+
+    /// Offset of the seqno index section (0 = no seqno index)
+    #[doc(hidden)]
+    pub seqno_index_ptr: u64,
+
+    /// Offset of the tombstone index section (0 = no tombstone index)
+    #[doc(hidden)]
+    pub tombstone_index_ptr: u64,
+
+    /// Whether the index entries store the item count of their block
+    ///
+    /// Segments written by older versions do not have item counts.
+    #[doc(hidden)]
+    pub index_item_counts: bool,
 }
 
 impl SegmentFileTrailer {
-    pub fn from_file<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+    /// Size of the encryption key ID marker
+    const KEY_ID_LEN: usize = std::mem::size_of::<u8>() + std::mem::size_of::<u32>();
+
+    /// Size of the format flags
+    const FLAGS_LEN: usize = std::mem::size_of::<u8>();
+
+    /// Format flag that marks segments with checksummed sections
+    const FLAG_CHECKSUMMED_SECTIONS: u8 = 1;
+
+    /// Format flag that marks segments whose index entries store item counts
+    const FLAG_INDEX_ITEM_COUNTS: u8 = 1 << 1;
+
+    /// Size of the seqno index pointer
+    const SEQNO_INDEX_PTR_LEN: usize = std::mem::size_of::<u64>();
+
+    /// Size of the tombstone index pointer
+    const TOMBSTONE_INDEX_PTR_LEN: usize = std::mem::size_of::<u64>();
+
+    /// Size of the segment ID the encrypted blocks are bound to
+    const CIPHER_SEGMENT_ID_LEN: usize = std::mem::size_of::<SegmentId>();
+
+    pub fn from_file<P: AsRef<Path>>(path: P, cipher: Option<&Cipher>) -> crate::Result<Self> {
         let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
         let mut reader = BufReader::new(file);
         reader.seek(std::io::SeekFrom::End(-(TRAILER_SIZE as i64)))?;
 
         // Parse pointers
         let offsets = FileOffsets::decode_from(&mut reader)?;
 
-        let remaining_padding = TRAILER_SIZE - FileOffsets::serialized_len() - MAGIC_BYTES.len();
+        // NOTE: Unencrypted segments are padded with zeroes, so they read as "no key"
+        let key_id = match reader.read_u8()? {
+            0 => {
+                reader.read_u32::<BigEndian>()?;
+                None
+            }
+            1 => Some(reader.read_u32::<BigEndian>()?),
+            tag => {
+                return Err(crate::Error::Decode(DecodeError::InvalidTag((
+                    "SegmentKeyId",
+                    tag,
+                ))));
+            }
+        };
+
+        // NOTE: Older segments are padded with zeroes, so they read as "no flags"
+        let flags = reader.read_u8()?;
+        let checksummed_sections = flags & Self::FLAG_CHECKSUMMED_SECTIONS > 0;
+        let index_item_counts = flags & Self::FLAG_INDEX_ITEM_COUNTS > 0;
+
+        // NOTE: Older segments are padded with zeroes, so they read as "no seqno index"
+        let seqno_index_ptr = reader.read_u64::<BigEndian>()?;
+
+        // NOTE: Older segments are padded with zeroes, so they read as "no tombstone index"
+        let tombstone_index_ptr = reader.read_u64::<BigEndian>()?;
+
+        let cipher_segment_id = reader.read_u64::<BigEndian>()?;
+
+        let remaining_padding = TRAILER_SIZE
+            - FileOffsets::serialized_len()
+            - Self::KEY_ID_LEN
+            - Self::FLAGS_LEN
+            - Self::SEQNO_INDEX_PTR_LEN
+            - Self::TOMBSTONE_INDEX_PTR_LEN
+            - Self::CIPHER_SEGMENT_ID_LEN
+            - MAGIC_BYTES.len();
         reader.seek_relative(remaining_padding as i64)?;
 
         // Check trailer magic
@@ -42,18 +135,53 @@ impl SegmentFileTrailer {
         reader.read_exact(&mut magic)?;
 
         if magic != MAGIC_BYTES {
-            return Err(crate::Error::Decode(DecodeError::InvalidHeader(
+            return Err(crate::Error::Decode(DecodeError::invalid_header(
                 "SegmentTrailer",
+                &MAGIC_BYTES,
+                &magic,
             )));
         }
 
         log::trace!("Trailer offsets: {offsets:#?}");
 
+        let cipher = SegmentCipher::for_existing_segment(cipher, key_id, cipher_segment_id)?;
+
         // Jump to metadata and parse
         reader.seek(std::io::SeekFrom::Start(offsets.metadata_ptr))?;
-        let metadata = Metadata::decode_from(&mut reader)?;
+        let metadata: Metadata = read_section(
+            &mut reader,
+            offsets.metadata_ptr,
+            cipher.as_ref(),
+            checksummed_sections,
+        )
+        .map_err(|e| e.at("SegmentMetadata", offsets.metadata_ptr))?;
+
+        metadata
+            .validate(Some(SegmentFileLayout {
+                index_block_ptr: offsets.index_block_ptr,
+                file_len,
+            }))
+            .map_err(|e| crate::Error::Decode(e.at("SegmentMetadata", offsets.metadata_ptr)))?;
+
+        Ok(Self {
+            metadata,
+            offsets,
+            key_id,
+            cipher_segment_id,
+            checksummed_sections,
+            seqno_index_ptr,
+            tombstone_index_ptr,
+            index_item_counts,
+        })
+    }
 
-        Ok(Self { metadata, offsets })
+    /// Returns the cipher to read the segment with
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the segment is encrypted, but no cipher is configured.
+    pub fn cipher(&self, cipher: Option<&Cipher>) -> crate::Result<Option<SegmentCipher>> {
+        SegmentCipher::for_existing_segment(cipher, self.key_id, self.cipher_segment_id)
     }
 }
 
@@ -63,6 +191,27 @@ impl Encode for SegmentFileTrailer {
 
         self.offsets.encode_into(&mut v)?;
 
+        if let Some(key_id) = self.key_id {
+            v.write_u8(1)?;
+            v.write_u32::<BigEndian>(key_id)?;
+        } else {
+            v.write_u8(0)?;
+            v.write_u32::<BigEndian>(0)?;
+        }
+
+        let mut flags = 0;
+        if self.checksummed_sections {
+            flags |= Self::FLAG_CHECKSUMMED_SECTIONS;
+        }
+        if self.index_item_counts {
+            flags |= Self::FLAG_INDEX_ITEM_COUNTS;
+        }
+        v.write_u8(flags)?;
+
+        v.write_u64::<BigEndian>(self.seqno_index_ptr)?;
+        v.write_u64::<BigEndian>(self.tombstone_index_ptr)?;
+        v.write_u64::<BigEndian>(self.cipher_segment_id)?;
+
         // Pad with remaining bytes
         v.resize(TRAILER_SIZE - MAGIC_BYTES.len(), 0);
 
@@ -3,7 +3,13 @@
 // (found in the LICENSE-* files in the repository)
 
 use super::{block::Block, id::GlobalSegmentId};
-use crate::{descriptor_table::FileDescriptorTable, value::InternalValue, BlockCache};
+use crate::{
+    descriptor_table::FileDescriptorTable,
+    error::{ErrorContext, Operation},
+    metrics::{MetricsSink, BLOCK_CACHE_HITS, BLOCK_CACHE_MISSES},
+    value::InternalValue,
+    BlockCache,
+};
 use std::sync::Arc;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -38,28 +44,63 @@ impl ValueBlock {
         segment_id: GlobalSegmentId,
         offset: u64,
         cache_policy: CachePolicy,
+        metrics: Option<&dyn MetricsSink>,
     ) -> crate::Result<Option<Arc<Self>>> {
+        Self::load_by_block_handle_traced(
+            descriptor_table,
+            block_cache,
+            segment_id,
+            offset,
+            cache_policy,
+            metrics,
+        )
+        .map(|block| block.map(|(block, _)| block))
+    }
+
+    /// Same as [`ValueBlock::load_by_block_handle`], but also returns
+    /// `true` if the block was found in the block cache.
+    pub fn load_by_block_handle_traced(
+        descriptor_table: &FileDescriptorTable,
+        block_cache: &BlockCache,
+        segment_id: GlobalSegmentId,
+        offset: u64,
+        cache_policy: CachePolicy,
+        metrics: Option<&dyn MetricsSink>,
+    ) -> crate::Result<Option<(Arc<Self>, bool)>> {
         Ok(
             if let Some(block) = block_cache.get_disk_block(segment_id, offset) {
                 // Cache hit: Copy from block
 
-                Some(block)
+                if let Some(sink) = metrics {
+                    sink.counter(BLOCK_CACHE_HITS, 1);
+                }
+
+                Some((block, true))
             } else {
                 // Cache miss: load from disk
 
+                if let Some(sink) = metrics {
+                    sink.counter(BLOCK_CACHE_MISSES, 1);
+                }
+
                 log::trace!("loading value block from disk: {segment_id:?}/{offset:?}");
 
                 let file_guard = descriptor_table
                     .access(&segment_id)?
                     .expect("should acquire file handle");
 
-                let block = Self::from_file(
-                    &mut *file_guard.file.lock().expect("lock is poisoned"),
+                let block = Self::from_file_with_cipher(
+                    &mut file_guard.reader(),
                     offset,
+                    file_guard.cipher.as_ref(),
                 )
                 .map_err(|e| {
                     log::error!("Failed to load value block {segment_id:?}/{offset:?}: {e:?}");
-                    e
+                    e.with_context(
+                        ErrorContext::new(Operation::Read)
+                            .with_segment_id(segment_id.segment_id())
+                            .with_block_offset(offset),
+                    )
                 })?;
                 // TODO: ^ inspect_err instead: 1.76
 
@@ -71,7 +112,7 @@ impl ValueBlock {
                     block_cache.insert_disk_block(segment_id, offset, block.clone());
                 }
 
-                Some(block)
+                Some((block, false))
             },
         )
     }
@@ -2,7 +2,7 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
-use crate::{SeqNo, UserKey};
+use crate::{hyperloglog::HyperLogLog, segment::prefix_fences::PrefixFences, SeqNo, UserKey};
 
 pub struct Metadata {
     /// Written data block count
@@ -37,6 +37,17 @@ pub struct Metadata {
 
     /// Highest encountered seqno
     pub highest_seqno: SeqNo,
+
+    /// Sketch of the written (unique) keys
+    pub key_sketch: HyperLogLog,
+
+    /// Distinct prefixes of the written keys, if prefix fences are enabled
+    ///
+    /// Dropped if there are too many distinct prefixes.
+    pub prefix_fences: Option<PrefixFences>,
+
+    /// Whether the written index is small enough to be kept in memory as a whole
+    pub one_level_index: bool,
 }
 
 impl Default for Metadata {
@@ -56,6 +67,12 @@ impl Default for Metadata {
 
             lowest_seqno: SeqNo::MAX,
             highest_seqno: 0,
+
+            key_sketch: HyperLogLog::default(),
+
+            prefix_fences: None,
+
+            one_level_index: false,
         }
     }
 }
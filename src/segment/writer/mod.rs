@@ -6,34 +6,60 @@ mod meta;
 
 use super::{
     block::header::Header as BlockHeader,
-    block_index::writer::Writer as IndexWriter,
+    block_index::writer::{shortest_separator, Writer as IndexWriter},
     file_offsets::FileOffsets,
     meta::{CompressionType, Metadata},
+    prefix_fences::PrefixFences,
+    section::write_section,
+    seqno_index::SeqnoIndex,
+    tombstone_index::TombstoneIndex,
     trailer::SegmentFileTrailer,
     value_block::ValueBlock,
 };
 use crate::{
     coding::Encode,
+    compaction::warm::CacheWarmer,
+    encryption::SegmentCipher,
     file::fsync_directory,
     segment::block::ItemSize,
-    value::{InternalValue, UserKey},
+    value::{InternalValue, SeqNo, UserKey},
     SegmentId,
 };
 use std::{
     fs::File,
     io::{BufWriter, Seek, Write},
     path::PathBuf,
+    sync::Arc,
 };
 
 #[cfg(feature = "bloom")]
 use crate::bloom::BloomFilter;
 
 /// Serializes and compresses values into blocks and writes them to disk as segment
+#[allow(clippy::struct_excessive_bools)]
 pub struct Writer {
     pub(crate) opts: Options,
 
     /// Compression to use
-    compression: CompressionType,
+    pub(crate) compression: CompressionType,
+
+    /// Cipher to encrypt blocks with
+    cipher: Option<SegmentCipher>,
+
+    /// If `true`, the segment file is fsynced once it is finished
+    sync: bool,
+
+    /// If `true`, index entries store the shortest separator between adjacent data blocks
+    shorten_index_keys: bool,
+
+    /// If `true`, every value is followed by its checksum
+    value_checksums: bool,
+
+    /// If `true`, the segment ID is used as creation timestamp
+    pub(crate) logical_clock: bool,
+
+    /// Inserts hot data blocks into the block cache, as they are written
+    cache_warmer: Option<Arc<CacheWarmer>>,
 
     /// Segment file
     segment_file_path: PathBuf,
@@ -55,9 +81,26 @@ pub struct Writer {
 
     current_key: Option<UserKey>,
 
+    /// Seqno ranges of the written data blocks, if a seqno index is written
+    seqno_index: Option<SeqnoIndex>,
+
+    /// Tombstone counts of the written data blocks that contain tombstones
+    tombstone_index: TombstoneIndex,
+
+    /// Maximum size of the index blocks that are kept in memory as a whole (0 = disabled)
+    one_level_index_max_size: u64,
+
+    /// Last key, offset & item count of the previous data block, which is registered
+    /// in the index once the first key of the next data block is known
+    pending_index_entry: Option<(UserKey, u64, u32)>,
+
     #[cfg(feature = "bloom")]
     bloom_policy: BloomConstructionPolicy,
 
+    /// Number of bloom filter hash functions, if not derived from the bloom policy
+    #[cfg(feature = "bloom")]
+    bloom_hash_count: Option<u8>,
+
     /// Hashes for bloom filter
     ///
     /// using enhanced double hashing, so we got two u64s
@@ -65,14 +108,19 @@ pub struct Writer {
     bloom_hash_buffer: Vec<(u64, u64)>,
 }
 
-#[derive(Copy, Clone, Debug)]
-#[cfg(feature = "bloom")]
+/// Decides how large the bloom filter of a segment is
+///
+/// The bloom filter stores its own parameters, so every segment keeps the
+/// policy it was written with.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum BloomConstructionPolicy {
+    /// Uses a fixed amount of bits per key
     BitsPerKey(u8),
+
+    /// Sizes the filter to reach the given false positive rate
     FpRate(f32),
 }
 
-#[cfg(feature = "bloom")]
 impl Default for BloomConstructionPolicy {
     fn default() -> Self {
         Self::BitsPerKey(10)
@@ -115,6 +163,12 @@ impl Writer {
             meta: meta::Metadata::default(),
 
             compression: CompressionType::None,
+            cipher: None,
+            sync: true,
+            shorten_index_keys: false,
+            value_checksums: false,
+            logical_clock: false,
+            cache_warmer: None,
 
             segment_file_path,
 
@@ -128,9 +182,20 @@ impl Writer {
 
             current_key: None,
 
+            seqno_index: None,
+
+            tombstone_index: TombstoneIndex::default(),
+
+            one_level_index_max_size: 0,
+
+            pending_index_entry: None,
+
             #[cfg(feature = "bloom")]
             bloom_policy: BloomConstructionPolicy::default(),
 
+            #[cfg(feature = "bloom")]
+            bloom_hash_count: None,
+
             #[cfg(feature = "bloom")]
             bloom_hash_buffer: Vec::with_capacity(10_000),
         })
@@ -143,6 +208,78 @@ impl Writer {
         self
     }
 
+    #[must_use]
+    pub(crate) fn use_cipher(mut self, cipher: Option<SegmentCipher>) -> Self {
+        let cipher = cipher.map(|cipher| cipher.for_segment(self.opts.segment_id));
+        self.index_writer = self.index_writer.use_cipher(cipher.clone());
+        self.cipher = cipher;
+        self
+    }
+
+    #[must_use]
+    pub(crate) fn use_sync(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    /// If enabled, a seqno index is written, which maps the seqno range
+    /// of every data block to its offset.
+    #[must_use]
+    pub(crate) fn use_seqno_index(mut self, enabled: bool) -> Self {
+        self.seqno_index = enabled.then(SeqnoIndex::default);
+        self
+    }
+
+    /// If enabled (> 0), the distinct prefixes of the given length of all written keys
+    /// are stored in the segment metadata.
+    #[must_use]
+    pub(crate) fn use_prefix_fences(mut self, prefix_len: u8) -> Self {
+        self.meta.prefix_fences = (prefix_len > 0).then(|| PrefixFences::new(prefix_len));
+        self
+    }
+
+    /// If the index blocks of the segment are at most `max_size` bytes,
+    /// the segment is marked to keep its whole index in memory.
+    #[must_use]
+    pub(crate) fn use_one_level_index(mut self, max_size: u64) -> Self {
+        self.one_level_index_max_size = max_size;
+        self
+    }
+
+    /// If enabled, the block index stores the shortest key that separates every data block
+    /// from the next one, instead of the last key of the data block.
+    #[must_use]
+    pub(crate) fn use_shortened_index_keys(mut self, enabled: bool) -> Self {
+        self.shorten_index_keys = enabled;
+        self
+    }
+
+    /// If enabled, a checksum of every value is stored in its data block item,
+    /// so corruption of single values is detected when they are read.
+    #[must_use]
+    pub(crate) fn use_value_checksums(mut self, enabled: bool) -> Self {
+        self.value_checksums = enabled;
+        self
+    }
+
+    /// If enabled, the segment ID is used as creation timestamp of the segment,
+    /// instead of the wall clock time.
+    #[must_use]
+    pub(crate) fn use_logical_clock(mut self, enabled: bool) -> Self {
+        self.logical_clock = enabled;
+        self
+    }
+
+    /// Sets the cache warmer that data blocks are passed to, once they are written.
+    #[must_use]
+    pub(crate) fn use_cache_warmer(mut self, cache_warmer: Option<Arc<CacheWarmer>>) -> Self {
+        self.index_writer = self
+            .index_writer
+            .use_cache_warmer(cache_warmer.clone(), self.opts.segment_id);
+        self.cache_warmer = cache_warmer;
+        self
+    }
+
     #[must_use]
     #[cfg(feature = "bloom")]
     pub(crate) fn use_bloom_policy(mut self, bloom_policy: BloomConstructionPolicy) -> Self {
@@ -150,6 +287,13 @@ impl Writer {
         self
     }
 
+    #[must_use]
+    #[cfg(feature = "bloom")]
+    pub(crate) fn use_bloom_hash_count(mut self, bloom_hash_count: Option<u8>) -> Self {
+        self.bloom_hash_count = bloom_hash_count;
+        self
+    }
+
     /// Writes a compressed block to disk.
     ///
     /// This is triggered when a `Writer::write` causes the buffer to grow to the configured `block_size`.
@@ -160,9 +304,70 @@ impl Writer {
             return Ok(());
         };
 
+        fail_point!(crate::failpoints::SEGMENT_WRITE);
+
+        if let Some((last_key, offset, item_count)) = self.pending_index_entry.take() {
+            let first_key = self
+                .chunk
+                .first()
+                .map_or(&last.key.user_key, |x| &x.key.user_key);
+
+            self.index_writer.register_block(
+                shortest_separator(&last_key, first_key),
+                offset,
+                item_count,
+            )?;
+        }
+
+        if let Some(seqno_index) = &mut self.seqno_index {
+            let (lo, hi) = self.chunk.iter().fold((SeqNo::MAX, 0), |(lo, hi), item| {
+                (lo.min(item.key.seqno), hi.max(item.key.seqno))
+            });
+
+            seqno_index.push(self.meta.file_pos, lo, hi);
+        }
+
+        let first_key = self
+            .chunk
+            .first()
+            .map_or_else(|| last.key.user_key.clone(), |x| x.key.user_key.clone());
+
+        // NOTE: Truncation is OK because a data block never has more than u32::MAX items
+        #[allow(clippy::cast_possible_truncation)]
+        let item_count = self.chunk.len() as u32;
+
+        let tombstone_count = self.chunk.iter().filter(|x| x.is_tombstone()).count();
+
+        if tombstone_count > 0 {
+            // NOTE: Truncation is OK because a data block never has more than u32::MAX items
+            #[allow(clippy::cast_possible_truncation)]
+            self.tombstone_index.push(
+                self.meta.file_pos,
+                (first_key, last.key.user_key.clone()),
+                item_count,
+                tombstone_count as u32,
+            );
+        }
+
         // Write to file
-        let (header, data) =
-            ValueBlock::to_bytes_compressed(&self.chunk, self.prev_pos.0, self.compression)?;
+        let (header, data) = if self.value_checksums {
+            ValueBlock::to_bytes_with_encoder(
+                &self.chunk,
+                self.prev_pos.0,
+                self.compression,
+                self.cipher.as_ref(),
+                self.meta.file_pos,
+                InternalValue::encode_with_value_checksum,
+            )?
+        } else {
+            ValueBlock::to_bytes_with_cipher(
+                &self.chunk,
+                self.prev_pos.0,
+                self.compression,
+                self.cipher.as_ref(),
+                self.meta.file_pos,
+            )?
+        };
 
         self.meta.uncompressed_size += u64::from(header.uncompressed_length);
 
@@ -171,8 +376,27 @@ impl Writer {
 
         let bytes_written = (BlockHeader::serialized_len() + data.len()) as u64;
 
-        self.index_writer
-            .register_block(last.key.user_key.clone(), self.meta.file_pos)?;
+        if let Some(cache_warmer) = &self.cache_warmer {
+            cache_warmer.block_written(
+                self.opts.segment_id,
+                self.meta.file_pos,
+                &header,
+                &self.chunk,
+            );
+        }
+
+        if self.shorten_index_keys {
+            // NOTE: The index entry is registered once the next data block is written
+            // (or the segment is finished), so it can be shortened
+            self.pending_index_entry =
+                Some((last.key.user_key.clone(), self.meta.file_pos, item_count));
+        } else {
+            self.index_writer.register_block(
+                last.key.user_key.clone(),
+                self.meta.file_pos,
+                item_count,
+            )?;
+        }
 
         // Adjust metadata
         self.meta.file_pos += bytes_written;
@@ -205,6 +429,14 @@ impl Writer {
 
         if Some(&item.key.user_key) != self.current_key.as_ref() {
             self.meta.key_count += 1;
+            self.meta.key_sketch.insert(&item.key.user_key);
+
+            if let Some(prefix_fences) = &mut self.meta.prefix_fences {
+                if !prefix_fences.push(&item.key.user_key) {
+                    log::trace!("Too many distinct prefixes, not writing prefix fences");
+                    self.meta.prefix_fences = None;
+                }
+            }
             self.current_key = Some(item.key.user_key.clone());
 
             // IMPORTANT: Do not buffer *every* item's key
@@ -242,12 +474,71 @@ impl Writer {
         Ok(())
     }
 
+    /// Discards the segment, removing the partially written segment file
+    pub(crate) fn abort(self) {
+        let segment_file_path = self.segment_file_path.clone();
+
+        // NOTE: Close the file before deleting it
+        drop(self);
+
+        log::debug!(
+            "Removing partial segment file at {}",
+            segment_file_path.display()
+        );
+
+        if let Err(e) = std::fs::remove_file(&segment_file_path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::error!(
+                    "Failed to remove partial segment file {}: {e:?}",
+                    segment_file_path.display()
+                );
+            }
+        }
+    }
+
+    /// Writes the bloom filter of all written keys, returning its position
+    #[cfg(feature = "bloom")]
+    fn write_bloom_filter(&mut self) -> crate::Result<u64> {
+        let bloom_ptr = self.block_writer.stream_position()?;
+
+        let n = self.bloom_hash_buffer.len();
+        log::trace!(
+            "Writing bloom filter with {n} hashes: {:?}",
+            self.bloom_policy
+        );
+
+        let mut filter = self.bloom_policy.build(n);
+
+        if let Some(k) = self.bloom_hash_count {
+            filter = filter.with_hash_count(k.into());
+        }
+
+        for hash in std::mem::take(&mut self.bloom_hash_buffer) {
+            filter.set_with_hash(hash);
+        }
+
+        write_section(
+            &mut self.block_writer,
+            bloom_ptr,
+            &filter,
+            self.cipher.as_ref(),
+        )?;
+
+        Ok(bloom_ptr)
+    }
+
     // TODO: should take mut self to avoid double finish
 
     /// Finishes the segment, making sure all data is written durably
     pub fn finish(&mut self) -> crate::Result<Option<SegmentFileTrailer>> {
         self.spill_block()?;
 
+        // NOTE: The last data block keeps its last key, because there is no next block
+        if let Some((last_key, offset, item_count)) = self.pending_index_entry.take() {
+            self.index_writer
+                .register_block(last_key, offset, item_count)?;
+        }
+
         // No items written! Just delete segment file and return nothing
         if self.meta.item_count == 0 {
             std::fs::remove_file(&self.segment_file_path)?;
@@ -262,33 +553,46 @@ impl Writer {
         log::trace!("tli_ptr={tli_ptr}");
 
         self.meta.index_block_count = self.index_writer.block_count;
+        self.meta.one_level_index = tli_ptr - index_block_ptr <= self.one_level_index_max_size;
 
         // Write bloom filter
         #[cfg(feature = "bloom")]
-        let bloom_ptr = {
-            let bloom_ptr = self.block_writer.stream_position()?;
-
-            let n = self.bloom_hash_buffer.len();
-            log::trace!(
-                "Writing bloom filter with {n} hashes: {:?}",
-                self.bloom_policy
-            );
-
-            let mut filter = self.bloom_policy.build(n);
-
-            for hash in std::mem::take(&mut self.bloom_hash_buffer) {
-                filter.set_with_hash(hash);
-            }
-
-            filter.encode_into(&mut self.block_writer)?;
-
-            bloom_ptr
-        };
+        let bloom_ptr = self.write_bloom_filter()?;
 
         #[cfg(not(feature = "bloom"))]
         let bloom_ptr = 0;
         log::trace!("bloom_ptr={bloom_ptr}");
 
+        // Write seqno index
+        let seqno_index_ptr = if let Some(seqno_index) = &self.seqno_index {
+            let seqno_index_ptr = self.block_writer.stream_position()?;
+            write_section(
+                &mut self.block_writer,
+                seqno_index_ptr,
+                seqno_index,
+                self.cipher.as_ref(),
+            )?;
+            seqno_index_ptr
+        } else {
+            0
+        };
+        log::trace!("seqno_index_ptr={seqno_index_ptr}");
+
+        // Write tombstone index
+        let tombstone_index_ptr = if self.tombstone_index.is_empty() {
+            0
+        } else {
+            let tombstone_index_ptr = self.block_writer.stream_position()?;
+            write_section(
+                &mut self.block_writer,
+                tombstone_index_ptr,
+                &self.tombstone_index,
+                self.cipher.as_ref(),
+            )?;
+            tombstone_index_ptr
+        };
+        log::trace!("tombstone_index_ptr={tombstone_index_ptr}");
+
         // TODO: #46 https://github.com/fjall-rs/lsm-tree/issues/46 - Write range filter
         let rf_ptr = 0;
         log::trace!("rf_ptr={rf_ptr}");
@@ -305,7 +609,12 @@ impl Writer {
         let metadata_ptr = self.block_writer.stream_position()?;
 
         let metadata = Metadata::from_writer(self.opts.segment_id, self)?;
-        metadata.encode_into(&mut self.block_writer)?;
+        write_section(
+            &mut self.block_writer,
+            metadata_ptr,
+            &metadata,
+            self.cipher.as_ref(),
+        )?;
 
         // Bundle all the file offsets
         let offsets = FileOffsets {
@@ -319,15 +628,29 @@ impl Writer {
         };
 
         // Write trailer
-        let trailer = SegmentFileTrailer { metadata, offsets };
+        let trailer = SegmentFileTrailer {
+            metadata,
+            offsets,
+            key_id: self.cipher.as_ref().map(SegmentCipher::key_id),
+            cipher_segment_id: self.opts.segment_id,
+            checksummed_sections: true,
+            seqno_index_ptr,
+            tombstone_index_ptr,
+            index_item_counts: true,
+        };
         trailer.encode_into(&mut self.block_writer)?;
 
         // Finally, flush & fsync the blocks file
         self.block_writer.flush()?;
-        self.block_writer.get_mut().sync_all()?;
 
-        // IMPORTANT: fsync folder on Unix
-        fsync_directory(&self.opts.folder)?;
+        // NOTE: If syncing is deferred, the tree syncs the file later, depending on its sync mode
+        if self.sync {
+            fail_point!(crate::failpoints::FSYNC);
+            self.block_writer.get_mut().sync_all()?;
+
+            // IMPORTANT: fsync folder on Unix
+            fsync_directory(&self.opts.folder)?;
+        }
 
         log::debug!(
             "Written {} items in {} blocks into new segment file, written {} MB of data blocks",
@@ -392,7 +715,12 @@ mod tests {
         // the TLI length fits into u32 as well
         #[allow(clippy::cast_possible_truncation)]
         {
-            let tli = TopLevelIndex::from_file(&segment_file_path, trailer.offsets.tli_ptr)?;
+            let tli = TopLevelIndex::from_file(
+                &segment_file_path,
+                trailer.offsets.tli_ptr,
+                None,
+                trailer.index_item_counts,
+            )?;
             assert_eq!(tli.len() as u32, trailer.metadata.index_block_count);
         }
 
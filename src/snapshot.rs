@@ -3,8 +3,10 @@
 // (found in the LICENSE-* files in the repository)
 
 use crate::{
+    any_tree::SnapshotTree,
+    tree::gc_watermark::{GuardedIter, SnapshotGuard},
     value::{SeqNo, UserKey, UserValue},
-    AbstractTree, AnyTree, KvPair,
+    AbstractTree, KvPair, ReadOptions,
 };
 use std::ops::RangeBounds;
 
@@ -13,20 +15,39 @@ use std::ops::RangeBounds;
 /// As long as the snapshot is open, old versions of objects will not be evicted as to
 /// keep the snapshot consistent. Thus, snapshots should only be kept around for as little as possible.
 ///
+/// The snapshot, and all iterators created from it, are registered in the tree
+/// and hold back its [GC watermark](crate::Tree::gc_watermark) until they are dropped.
+///
 /// Snapshots do not persist across restarts.
 #[derive(Clone)]
 pub struct Snapshot {
-    tree: AnyTree,
+    tree: SnapshotTree,
 
     #[doc(hidden)]
     pub seqno: SeqNo,
+
+    guard: Option<SnapshotGuard>,
 }
 
 impl Snapshot {
     /// Creates a snapshot
-    pub(crate) fn new(tree: AnyTree, seqno: SeqNo) -> Self {
+    pub(crate) fn new<T: Into<SnapshotTree>>(tree: T, seqno: SeqNo) -> Self {
         log::trace!("Opening snapshot with seqno: {seqno}");
-        Self { tree, seqno }
+
+        let tree = tree.into();
+
+        let guard = match &tree {
+            SnapshotTree::Standard(tree) => Some(tree.snapshots.register(seqno)),
+            SnapshotTree::Blob(tree) => Some(tree.index.snapshots.register(seqno)),
+            SnapshotTree::Memory(_) => None,
+        };
+
+        Self { tree, seqno, guard }
+    }
+
+    /// Keeps the snapshot registered for as long as the given iterator is alive.
+    fn guarded<I>(&self, iter: I) -> GuardedIter<I> {
+        GuardedIter::new(iter, self.guard.clone())
     }
 
     /// Retrieves an item from the snapshot.
@@ -55,6 +76,42 @@ impl Snapshot {
         self.tree.get_with_seqno(key, self.seqno)
     }
 
+    /// Retrieves a batch of items from the snapshot.
+    ///
+    /// The values are returned in the order of the keys.
+    ///
+    /// All keys are read from the same view of the tree, so the answers are consistent
+    /// with each other, even if the tree is written to, flushed or compacted in the meantime.
+    /// Keys that are stored in the same data block share a single block load.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let folder = tempfile::tempdir()?;
+    /// use lsm_tree::{AbstractTree, Config, Tree};
+    ///
+    /// let tree = Config::new(folder).open()?;
+    ///
+    /// tree.insert("a", "abc", 0);
+    /// tree.insert("b", "def", 1);
+    /// let snapshot = tree.snapshot(2);
+    ///
+    /// tree.remove("a", 2);
+    ///
+    /// let values = snapshot.get_many(&["b", "c", "a"])?;
+    /// assert_eq!(vec![Some("def".as_bytes().into()), None, Some("abc".as_bytes().into())], values);
+    /// #
+    /// # Ok::<(), lsm_tree::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn get_many<K: AsRef<[u8]>>(&self, keys: &[K]) -> crate::Result<Vec<Option<UserValue>>> {
+        let keys = keys.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+        self.tree.get_many_bytes(&keys, self.seqno)
+    }
+
     /// Returns an iterator that scans through the entire snapshot.
     ///
     /// Avoid using this function, or limit it as otherwise it may scan a lot of items.
@@ -79,7 +136,7 @@ impl Snapshot {
     /// ```
     #[must_use]
     pub fn iter(&self) -> impl DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static {
-        self.tree.iter_with_seqno(self.seqno, None)
+        self.guarded(self.tree.iter_with_seqno(self.seqno, None))
     }
 
     /// Returns an iterator that scans through the entire snapshot, returning keys only.
@@ -106,7 +163,7 @@ impl Snapshot {
     /// ```
     #[must_use]
     pub fn keys(&self) -> impl DoubleEndedIterator<Item = crate::Result<UserKey>> + 'static {
-        self.tree.keys_with_seqno(self.seqno, None)
+        self.guarded(self.tree.keys_with_seqno(self.seqno, None))
     }
 
     /// Returns an iterator that scans through the entire snapshot, returning values only.
@@ -133,7 +190,7 @@ impl Snapshot {
     /// ```
     #[must_use]
     pub fn values(&self) -> impl DoubleEndedIterator<Item = crate::Result<UserValue>> + 'static {
-        self.tree.values_with_seqno(self.seqno, None)
+        self.guarded(self.tree.values_with_seqno(self.seqno, None))
     }
 
     /// Returns an iterator over a range of items in the snapshot.
@@ -162,7 +219,7 @@ impl Snapshot {
         &self,
         range: R,
     ) -> impl DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static {
-        self.tree.range_with_seqno(range, self.seqno, None)
+        self.guarded(self.tree.range_with_seqno(range, self.seqno, None))
     }
 
     /// Returns an iterator over a prefixed set of items in the snapshot.
@@ -191,7 +248,75 @@ impl Snapshot {
         &self,
         prefix: K,
     ) -> impl DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static {
-        self.tree.prefix_with_seqno(prefix, self.seqno, None)
+        self.guarded(self.tree.prefix_with_seqno(prefix, self.seqno, None))
+    }
+
+    /// Returns the given options, bounded to the snapshot's sequence number.
+    ///
+    /// If the options have a lower sequence number, that one is used instead.
+    fn bounded_options(&self, options: &ReadOptions) -> ReadOptions {
+        let seqno = options
+            .seqno
+            .map_or(self.seqno, |seqno| seqno.min(self.seqno));
+        options.clone().seqno(seqno)
+    }
+
+    /// Returns an iterator that scans through the entire snapshot,
+    /// configured by the given [`ReadOptions`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let folder = tempfile::tempdir()?;
+    /// use lsm_tree::{AbstractTree, Config, ReadOptions, Tree};
+    ///
+    /// let tree = Config::new(folder).open()?;
+    ///
+    /// tree.insert("a", "abc", 0);
+    /// tree.insert("f", "abc", 1);
+    /// let snapshot = tree.snapshot(2);
+    ///
+    /// tree.insert("g", "abc", 2);
+    ///
+    /// let options = ReadOptions::default().limit(1);
+    /// assert_eq!(1, snapshot.iter_with_options(&options).count());
+    /// #
+    /// # Ok::<(), lsm_tree::Error>(())
+    /// ```
+    #[must_use]
+    pub fn iter_with_options(
+        &self,
+        options: &ReadOptions,
+    ) -> impl DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static {
+        self.guarded(self.tree.iter_with_options(&self.bounded_options(options)))
+    }
+
+    /// Returns an iterator over a range of items in the snapshot,
+    /// configured by the given [`ReadOptions`].
+    #[must_use]
+    pub fn range_with_options<K: AsRef<[u8]>, R: RangeBounds<K>>(
+        &self,
+        range: R,
+        options: &ReadOptions,
+    ) -> impl DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static {
+        self.guarded(
+            self.tree
+                .range_with_options(range, &self.bounded_options(options)),
+        )
+    }
+
+    /// Returns an iterator over a prefixed set of items in the snapshot,
+    /// configured by the given [`ReadOptions`].
+    #[must_use]
+    pub fn prefix_with_options<K: AsRef<[u8]>>(
+        &self,
+        prefix: K,
+        options: &ReadOptions,
+    ) -> impl DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static {
+        self.guarded(
+            self.tree
+                .prefix_with_options(prefix, &self.bounded_options(options)),
+        )
     }
 
     /// Returns the first key-value pair in the snapshot.
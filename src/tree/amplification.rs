@@ -0,0 +1,168 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::level_manifest::LevelManifest;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+/// Cumulative byte counters of a tree, used for write amplification
+#[derive(Debug, Default)]
+pub struct WriteStats {
+    /// Bytes written into memtables
+    ingested_bytes: AtomicU64,
+
+    /// Bytes written into disk segments by flushes & compactions
+    written_bytes: AtomicU64,
+}
+
+impl WriteStats {
+    pub fn record_ingest(&self, bytes: u64) {
+        self.ingested_bytes.fetch_add(bytes, Relaxed);
+    }
+
+    pub fn record_write(&self, bytes: u64) {
+        self.written_bytes.fetch_add(bytes, Relaxed);
+    }
+}
+
+/// Read, space and write amplification of a tree
+///
+/// See [`crate::Tree::amplification`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[allow(clippy::module_name_repetitions)]
+pub struct AmplificationReport {
+    /// Amount of sorted runs (memtables, L0 segments and deeper levels)
+    /// a point read has to consult in the worst case
+    pub read_amp: usize,
+
+    /// Size of all disk segments in bytes
+    pub total_bytes: u64,
+
+    /// Estimated size of live data (not shadowed by newer versions or tombstones) in bytes
+    pub live_bytes: u64,
+
+    /// Bytes written into the tree since it was opened
+    pub ingested_bytes: u64,
+
+    /// Bytes written into disk segments since the tree was opened
+    pub written_bytes: u64,
+}
+
+impl AmplificationReport {
+    pub(crate) fn new(levels: &LevelManifest, memtable_count: usize, stats: &WriteStats) -> Self {
+        let read_amp = memtable_count
+            + levels
+                .levels
+                .iter()
+                .map(|level| {
+                    if level.is_disjoint {
+                        usize::from(!level.is_empty())
+                    } else {
+                        level.len()
+                    }
+                })
+                .sum::<usize>();
+
+        let total_bytes = levels.size();
+        let total_items = levels.iter().map(|x| x.metadata.item_count).sum::<u64>();
+
+        // NOTE: Assume newer versions in upper runs shadow keys of
+        // the largest sorted run, so the largest run approximates the live key count
+        let live_items = levels
+            .levels
+            .iter()
+            .flat_map(|level| {
+                let live_keys = level.segments.iter().map(|x| {
+                    x.metadata
+                        .key_count
+                        .saturating_sub(x.metadata.tombstone_count)
+                });
+
+                if level.is_disjoint {
+                    vec![live_keys.sum::<u64>()]
+                } else {
+                    live_keys.collect()
+                }
+            })
+            .max()
+            .unwrap_or_default();
+
+        let live_bytes = if total_items == 0 {
+            0
+        } else {
+            // NOTE: live_items <= total_items, so the result fits in u64
+            #[allow(clippy::cast_possible_truncation)]
+            let live_bytes =
+                (u128::from(total_bytes) * u128::from(live_items) / u128::from(total_items)) as u64;
+
+            live_bytes
+        };
+
+        Self {
+            read_amp,
+            total_bytes,
+            live_bytes,
+            ingested_bytes: stats.ingested_bytes.load(Relaxed),
+            written_bytes: stats.written_bytes.load(Relaxed),
+        }
+    }
+
+    /// Returns the estimated space amplification.
+    ///
+    /// A value of 1.0 means there is no shadowed data.
+    /// Returns 0.0 if there is no live data.
+    #[must_use]
+    pub fn space_amp(&self) -> f32 {
+        if self.live_bytes == 0 {
+            return 0.0;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let space_amp = self.total_bytes as f32 / self.live_bytes as f32;
+
+        space_amp
+    }
+
+    /// Returns the cumulative write amplification since the tree was opened.
+    ///
+    /// Returns 0.0 if nothing has been written yet.
+    #[must_use]
+    pub fn write_amp(&self) -> f32 {
+        if self.ingested_bytes == 0 {
+            return 0.0;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let write_amp = self.written_bytes as f32 / self.ingested_bytes as f32;
+
+        write_amp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn amplification_report_ratios() {
+        let report = AmplificationReport {
+            read_amp: 3,
+            total_bytes: 300,
+            live_bytes: 100,
+            ingested_bytes: 100,
+            written_bytes: 500,
+        };
+
+        assert!((report.space_amp() - 3.0).abs() < f32::EPSILON);
+        assert!((report.write_amp() - 5.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn amplification_report_empty() {
+        let report = AmplificationReport::default();
+
+        assert!(report.space_amp().abs() < f32::EPSILON);
+        assert!(report.write_amp().abs() < f32::EPSILON);
+    }
+}
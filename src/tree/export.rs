@@ -0,0 +1,187 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! Columnar export format
+//!
+//! The stream starts with [`MAGIC`], followed by batches of up to [`MAX_BATCH_ROWS`] rows.
+//!
+//! Every batch consists of (all integers are big endian):
+//!
+//! - row count (u32)
+//! - key lengths (u16 per row), followed by all key bytes
+//! - value lengths (u32 per row), followed by all value bytes
+//! - seqnos (u64 per row)
+//!
+//! The stream ends with an empty batch (row count 0), followed by the total row count (u64).
+
+use crate::InternalValue;
+use byteorder::{BigEndian, WriteBytesExt};
+use std::io::Write;
+
+/// Magic bytes of the columnar export format
+pub const MAGIC: &[u8; 8] = b"LSMTCOL1";
+
+/// Maximum amount of rows per batch
+pub const MAX_BATCH_ROWS: usize = 1_024;
+
+/// Batches are also cut once their values exceed this size, to bound memory usage
+const MAX_BATCH_BYTES: usize = 4 * 1_024 * 1_024;
+
+/// Buffers a single batch of rows, column by column
+#[derive(Default)]
+struct Batch {
+    key_lengths: Vec<u8>,
+    keys: Vec<u8>,
+    value_lengths: Vec<u8>,
+    values: Vec<u8>,
+    seqnos: Vec<u8>,
+    rows: u32,
+}
+
+impl Batch {
+    fn push(&mut self, item: &InternalValue) -> std::io::Result<()> {
+        let key = &item.key.user_key;
+
+        // NOTE: We know keys are limited to 16-bit length
+        #[allow(clippy::cast_possible_truncation)]
+        self.key_lengths.write_u16::<BigEndian>(key.len() as u16)?;
+        self.keys.extend_from_slice(key);
+
+        // NOTE: We know values are limited to 32-bit length
+        #[allow(clippy::cast_possible_truncation)]
+        self.value_lengths
+            .write_u32::<BigEndian>(item.value.len() as u32)?;
+        self.values.extend_from_slice(&item.value);
+
+        self.seqnos.write_u64::<BigEndian>(item.key.seqno)?;
+        self.rows += 1;
+
+        Ok(())
+    }
+
+    fn is_full(&self) -> bool {
+        self.rows as usize >= MAX_BATCH_ROWS
+            || self.keys.len() + self.values.len() >= MAX_BATCH_BYTES
+    }
+
+    fn write_to<W: Write>(&mut self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_u32::<BigEndian>(self.rows)?;
+
+        for column in [
+            &mut self.key_lengths,
+            &mut self.keys,
+            &mut self.value_lengths,
+            &mut self.values,
+            &mut self.seqnos,
+        ] {
+            writer.write_all(column)?;
+            column.clear();
+        }
+
+        self.rows = 0;
+
+        Ok(())
+    }
+}
+
+/// Writes the items into the writer, one batch at a time
+///
+/// Returns the amount of exported items.
+pub fn write_columnar<W: Write>(
+    items: impl Iterator<Item = crate::Result<InternalValue>>,
+    mut writer: W,
+) -> crate::Result<u64> {
+    writer.write_all(MAGIC)?;
+
+    let mut batch = Batch::default();
+    let mut row_count = 0;
+
+    for item in items {
+        batch.push(&item?)?;
+        row_count += 1;
+
+        if batch.is_full() {
+            batch.write_to(&mut writer)?;
+        }
+    }
+
+    if batch.rows > 0 {
+        batch.write_to(&mut writer)?;
+    }
+
+    // NOTE: Terminate with an empty batch
+    batch.write_to(&mut writer)?;
+    writer.write_u64::<BigEndian>(row_count)?;
+
+    writer.flush()?;
+
+    Ok(row_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ValueType;
+    use byteorder::ReadBytesExt;
+    use std::io::{Cursor, Read};
+    use test_log::test;
+
+    #[test]
+    fn export_columnar_batches() -> crate::Result<()> {
+        let items = (0..(MAX_BATCH_ROWS as u64 + 1)).map(|x| {
+            Ok(InternalValue::from_components(
+                x.to_be_bytes(),
+                "abc",
+                x,
+                ValueType::Value,
+            ))
+        });
+
+        let mut buf = vec![];
+        assert_eq!(MAX_BATCH_ROWS as u64 + 1, write_columnar(items, &mut buf)?);
+
+        let mut reader = Cursor::new(buf);
+
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        assert_eq!(MAGIC, &magic);
+
+        let mut batch_rows = vec![];
+
+        loop {
+            let rows = reader.read_u32::<BigEndian>()?;
+
+            if rows == 0 {
+                break;
+            }
+
+            batch_rows.push(rows);
+
+            let key_lengths = (0..rows)
+                .map(|_| reader.read_u16::<BigEndian>())
+                .collect::<std::io::Result<Vec<_>>>()?;
+            assert!(key_lengths.iter().all(|&x| x == 8));
+
+            let mut keys = vec![0; key_lengths.iter().map(|&x| usize::from(x)).sum()];
+            reader.read_exact(&mut keys)?;
+
+            let value_lengths = (0..rows)
+                .map(|_| reader.read_u32::<BigEndian>())
+                .collect::<std::io::Result<Vec<_>>>()?;
+
+            let mut values = vec![0; value_lengths.iter().map(|&x| x as usize).sum()];
+            reader.read_exact(&mut values)?;
+            assert_eq!(b"abc".repeat(rows as usize), values);
+
+            for _ in 0..rows {
+                reader.read_u64::<BigEndian>()?;
+            }
+        }
+
+        assert_eq!(vec![MAX_BATCH_ROWS as u32, 1], batch_rows);
+        assert_eq!(MAX_BATCH_ROWS as u64 + 1, reader.read_u64::<BigEndian>()?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::Tree;
+use crate::{
+    error::ErrorContext,
+    segment::{block::checksum::Checksum, value_block::CachePolicy},
+    InternalValue, Segment,
+};
+use xxhash_rust::xxh3::Xxh3;
+
+/// Rolling checksum over a stream of items
+///
+/// Used to compare the items that were written into a segment
+/// with the items that can be read back from it.
+pub struct ItemStreamChecksum {
+    hasher: Xxh3,
+    item_count: u64,
+}
+
+impl ItemStreamChecksum {
+    pub fn new() -> Self {
+        Self {
+            hasher: Xxh3::new(),
+            item_count: 0,
+        }
+    }
+
+    pub fn update(&mut self, item: &InternalValue) {
+        // NOTE: Lengths are hashed as well, so (a, bc) and (ab, c) do not collide
+        self.hasher
+            .update(&(item.key.user_key.len() as u64).to_be_bytes());
+        self.hasher.update(&item.key.user_key);
+        self.hasher.update(&item.key.seqno.to_be_bytes());
+        self.hasher.update(&[u8::from(item.key.value_type)]);
+        self.hasher.update(&(item.value.len() as u64).to_be_bytes());
+        self.hasher.update(&item.value);
+
+        self.item_count += 1;
+    }
+
+    pub fn item_count(&self) -> u64 {
+        self.item_count
+    }
+
+    pub fn checksum(&self) -> Checksum {
+        Checksum::from_raw(self.hasher.digest())
+    }
+}
+
+impl Tree {
+    /// Re-reads a freshly written segment, and compares its items with the checksum
+    /// of the items that were written into it.
+    ///
+    /// On mismatch, the segment file is deleted, so the segment is never registered.
+    ///
+    /// NOTE: The file was just written, so it is most likely read back from the OS page cache,
+    /// not from the disk. This detects corruption that happened before the data reached the kernel
+    /// (e.g. while encoding, or in the write buffers), but not corruption on the way to the disk.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or the checksums do not match.
+    pub(crate) fn verify_flushed_segment(
+        &self,
+        segment: &Segment,
+        expected: &ItemStreamChecksum,
+        context: ErrorContext,
+    ) -> crate::Result<()> {
+        let segment_id = segment.metadata.id;
+
+        let mut actual = ItemStreamChecksum::new();
+
+        // NOTE: Do not pollute the block cache with the whole segment
+        for item in segment.iter().cache_policy(CachePolicy::Read) {
+            let item = item.map_err(|e| e.with_context(context.clone()))?;
+            actual.update(&item);
+        }
+
+        let (got, expected_checksum) = (actual.checksum(), expected.checksum());
+
+        if got == expected_checksum && actual.item_count() == expected.item_count() {
+            return Ok(());
+        }
+
+        log::error!(
+            "flush: segment {segment_id} does not match the flushed items (read back {} items with checksum {got:?}, expected {} items with checksum {expected_checksum:?})",
+            actual.item_count(),
+            expected.item_count(),
+        );
+
+        let path = self
+            .config
+            .path
+            .join(crate::file::SEGMENTS_FOLDER)
+            .join(segment_id.to_string());
+
+        self.config
+            .descriptor_table
+            .remove((segment.tree_id, segment_id).into());
+
+        if let Err(e) = std::fs::remove_file(&path) {
+            log::warn!(
+                "Failed to remove corrupted segment file {}: {e:?}",
+                path.display()
+            );
+        }
+
+        Err(crate::Error::InvalidChecksum((got, expected_checksum)).with_context(context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ValueType;
+    use test_log::test;
+
+    #[test]
+    fn item_stream_checksum() {
+        let items = [
+            InternalValue::from_components("a", "bc", 0, ValueType::Value),
+            InternalValue::from_components("b", "", 1, ValueType::Tombstone),
+        ];
+
+        let mut a = ItemStreamChecksum::new();
+        let mut b = ItemStreamChecksum::new();
+
+        for item in &items {
+            a.update(item);
+            b.update(item);
+        }
+        assert_eq!(a.checksum(), b.checksum());
+        assert_eq!(2, a.item_count());
+
+        // NOTE: Key & value boundaries are part of the checksum
+        let mut c = ItemStreamChecksum::new();
+        c.update(&InternalValue::from_components(
+            "ab",
+            "c",
+            0,
+            ValueType::Value,
+        ));
+        c.update(&InternalValue::from_components(
+            "b",
+            "",
+            1,
+            ValueType::Tombstone,
+        ));
+        assert_ne!(a.checksum(), c.checksum());
+
+        let mut d = ItemStreamChecksum::new();
+        d.update(&InternalValue::from_components(
+            "a",
+            "bc",
+            0,
+            ValueType::Value,
+        ));
+        d.update(&InternalValue::from_components(
+            "b",
+            "",
+            2,
+            ValueType::Tombstone,
+        ));
+        assert_ne!(a.checksum(), d.checksum());
+    }
+}
@@ -0,0 +1,204 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::Tree;
+use crate::{compaction::CompactionStrategy, segment::Segment, AbstractTree, SeqNo};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// Keeps track of open snapshots and the external GC floor,
+/// which hold back garbage collection of old versions
+#[derive(Default)]
+pub struct SnapshotRegistry {
+    /// Amount of open snapshots per sequence number
+    snapshots: Mutex<BTreeMap<SeqNo, usize>>,
+
+    /// Lowest sequence number that is still needed outside of the tree
+    floor: Mutex<Option<SeqNo>>,
+}
+
+impl SnapshotRegistry {
+    /// Locks the open snapshots
+    fn lock_snapshots(&self) -> MutexGuard<'_, BTreeMap<SeqNo, usize>> {
+        self.snapshots.lock().expect("lock is poisoned")
+    }
+
+    /// Locks the external GC floor
+    fn lock_floor(&self) -> MutexGuard<'_, Option<SeqNo>> {
+        self.floor.lock().expect("lock is poisoned")
+    }
+
+    /// Registers a snapshot, which stays registered until the returned guard is dropped.
+    pub fn register(self: &Arc<Self>, seqno: SeqNo) -> SnapshotGuard {
+        *self.lock_snapshots().entry(seqno).or_default() += 1;
+
+        SnapshotGuard {
+            registry: self.clone(),
+            seqno,
+        }
+    }
+
+    fn unregister(&self, seqno: SeqNo) {
+        let mut snapshots = self.lock_snapshots();
+
+        if let Some(count) = snapshots.get_mut(&seqno) {
+            *count -= 1;
+
+            if *count == 0 {
+                snapshots.remove(&seqno);
+            }
+        }
+    }
+
+    /// Returns the lowest sequence number of all open snapshots.
+    pub fn lowest(&self) -> Option<SeqNo> {
+        self.lock_snapshots().keys().next().copied()
+    }
+
+    /// Returns the amount of open snapshots.
+    pub fn count(&self) -> usize {
+        self.lock_snapshots().values().sum()
+    }
+
+    /// Returns the external GC floor, if set.
+    pub fn floor(&self) -> Option<SeqNo> {
+        *self.lock_floor()
+    }
+
+    /// Sets or removes the external GC floor.
+    pub fn set_floor(&self, floor: Option<SeqNo>) {
+        *self.lock_floor() = floor;
+    }
+}
+
+/// Keeps a snapshot registered for as long as it is alive
+///
+/// Cloning the guard registers the snapshot once more.
+pub struct SnapshotGuard {
+    registry: Arc<SnapshotRegistry>,
+    seqno: SeqNo,
+}
+
+impl Clone for SnapshotGuard {
+    fn clone(&self) -> Self {
+        self.registry.register(self.seqno)
+    }
+}
+
+impl Drop for SnapshotGuard {
+    fn drop(&mut self) {
+        self.registry.unregister(self.seqno);
+    }
+}
+
+/// Iterator that keeps the snapshot it was created from registered
+/// until it is dropped
+pub struct GuardedIter<I> {
+    inner: I,
+
+    #[allow(unused)]
+    guard: Option<SnapshotGuard>,
+}
+
+impl<I> GuardedIter<I> {
+    pub fn new(inner: I, guard: Option<SnapshotGuard>) -> Self {
+        Self { inner, guard }
+    }
+}
+
+impl<I: Iterator> Iterator for GuardedIter<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<I: DoubleEndedIterator> DoubleEndedIterator for GuardedIter<I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl Tree {
+    /// Returns the amount of open snapshots (including iterators created from snapshots).
+    #[must_use]
+    pub fn open_snapshot_count(&self) -> usize {
+        self.snapshots.count()
+    }
+
+    /// Sets the lowest sequence number that is still needed outside of the tree
+    /// (e.g. the replication progress of a follower).
+    ///
+    /// Versions with a sequence number >= `seqno` are never garbage-collected
+    /// by flushes and compactions that use the [GC watermark](Tree::gc_watermark).
+    pub fn set_gc_floor(&self, seqno: SeqNo) {
+        self.snapshots.set_floor(Some(seqno));
+    }
+
+    /// Removes the external GC floor, see [`Tree::set_gc_floor`].
+    pub fn clear_gc_floor(&self) {
+        self.snapshots.set_floor(None);
+    }
+
+    /// Returns the highest sequence number that flushes and compactions
+    /// may use as GC threshold without affecting any reader.
+    ///
+    /// The watermark is computed from:
+    ///
+    /// - the highest sequence number of the tree, so only versions that are shadowed are dropped,
+    /// - the external floor set using [`Tree::set_gc_floor`],
+    /// - the open snapshots (and iterators created from them).
+    ///
+    /// Garbage collection drops any shadowed version below the threshold, which includes
+    /// versions that an open snapshot may still read, so as long as a snapshot is open,
+    /// the watermark is 0 and no version is garbage-collected.
+    #[must_use]
+    pub fn gc_watermark(&self) -> SeqNo {
+        let mut watermark = self.get_highest_seqno().map_or(0, |seqno| seqno + 1);
+
+        if let Some(floor) = self.snapshots.floor() {
+            watermark = watermark.min(floor);
+        }
+
+        if let Some(lowest) = self.snapshots.lowest() {
+            log::trace!("GC watermark is held back by snapshot with seqno {lowest}");
+            watermark = 0;
+        }
+
+        watermark
+    }
+
+    /// Synchronously flushes the active memtable to a disk segment,
+    /// using the [GC watermark](Tree::gc_watermark) as GC threshold.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn flush_active_memtable_auto(&self) -> crate::Result<Option<Arc<Segment>>> {
+        self.flush_active_memtable(self.gc_watermark())
+    }
+
+    /// Performs major compaction, using the [GC watermark](Tree::gc_watermark) as GC threshold.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    #[doc(hidden)]
+    pub fn major_compact_auto(&self, target_size: u64) -> crate::Result<()> {
+        self.major_compact(target_size, self.gc_watermark())
+    }
+
+    /// Performs compaction using the given strategy,
+    /// using the [GC watermark](Tree::gc_watermark) as GC threshold.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn compact_auto(&self, strategy: Arc<dyn CompactionStrategy>) -> crate::Result<()> {
+        self.compact(strategy, self.gc_watermark())
+    }
+}
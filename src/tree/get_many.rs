@@ -0,0 +1,79 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::{ignore_tombstone_value, Tree};
+use crate::{segment::value_block::ValueBlock, InternalValue, SegmentId, SeqNo};
+use std::sync::Arc;
+
+/// The data block the previous key of a batch started in, per segment
+pub type LastBlocks = crate::HashMap<SegmentId, Option<(u64, Arc<ValueBlock>)>>;
+
+impl Tree {
+    /// Retrieves the items of a batch of keys, as visible at the given seqno.
+    ///
+    /// Tombstones are evicted, so deleted keys are returned as `None`.
+    ///
+    /// The memtables and levels are read from top to bottom, like a single point read.
+    /// No lock is held while reading segments, so the batch does not block flushes and compactions.
+    /// Keys are read in sorted order, so keys that share a data block only load it once.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn get_many_internal_entries(
+        &self,
+        keys: &[&[u8]],
+        seqno: SeqNo,
+    ) -> crate::Result<Vec<Option<InternalValue>>> {
+        let mut items = vec![None; keys.len()];
+
+        let mut order = keys.iter().copied().enumerate().collect::<Vec<_>>();
+        order.sort_by_key(|&(_, key)| key);
+
+        // NOTE: The active memtable cannot be cloned, but reading it does not do any I/O
+        let active_memtable = self.read_lock_active_memtable();
+        let mut resolved = order
+            .iter()
+            .map(|&(_, key)| active_memtable.get(key, Some(seqno)))
+            .collect::<Vec<_>>();
+        drop(active_memtable);
+
+        let sealed_memtables = self
+            .read_lock_sealed_memtables()
+            .iter()
+            .map(|(_, memtable)| memtable.clone())
+            .collect::<Vec<_>>();
+
+        for (&(_, key), item) in order.iter().zip(&mut resolved) {
+            if item.is_none() {
+                *item = sealed_memtables
+                    .iter()
+                    .rev()
+                    .find_map(|memtable| memtable.get(key, Some(seqno)));
+            }
+        }
+
+        let levels = self.read_lock_levels().levels.clone();
+
+        let mut last_blocks = LastBlocks::default();
+
+        for ((idx, key), item) in order.into_iter().zip(resolved) {
+            let item = match item {
+                Some(item) => Some(item),
+                None => self.get_internal_entry_from_levels(
+                    &levels,
+                    key,
+                    Some(seqno),
+                    Some(&mut last_blocks),
+                )?,
+            };
+
+            if let Some(slot) = items.get_mut(idx) {
+                *slot = item.and_then(ignore_tombstone_value);
+            }
+        }
+
+        Ok(items)
+    }
+}
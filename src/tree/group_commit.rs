@@ -0,0 +1,244 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{value::InternalValue, SeqNo, SequenceNumberCounter};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{atomic::Ordering::Release, Condvar, Mutex, MutexGuard},
+};
+
+/// Write that is waiting to be committed by a group leader
+struct PendingWrite {
+    ticket: u64,
+
+    /// Item to write, its seqno is assigned by the group leader
+    item: InternalValue,
+}
+
+#[derive(Default)]
+struct State {
+    /// Ticket of the next write, used to hand results back to their writer
+    next_ticket: u64,
+
+    pending: Vec<PendingWrite>,
+
+    /// If `true`, some writer is currently committing a group
+    has_leader: bool,
+
+    /// Seqnos & resulting memtable sizes of committed writes, until picked up by their writer
+    committed: HashMap<u64, (SeqNo, u32)>,
+
+    /// Tickets of writes whose group leader panicked while committing them
+    abandoned: HashSet<u64>,
+}
+
+/// Hands leadership back if the leader panics while committing its group
+///
+/// Otherwise, the other writers would wait for a leader that never returns.
+struct LeaderGuard<'a> {
+    group_commit: &'a GroupCommit,
+    tickets: Vec<u64>,
+}
+
+impl Drop for LeaderGuard<'_> {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            return;
+        }
+
+        let mut state = self
+            .group_commit
+            .state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        state.abandoned.extend(self.tickets.drain(..));
+        state.has_leader = false;
+        drop(state);
+
+        self.group_commit.committed.notify_all();
+    }
+}
+
+/// Batches concurrent writes (group commit)
+///
+/// Writers enqueue their write; the first writer that finds no active leader
+/// becomes the leader, and commits all enqueued writes at once:
+/// it allocates a contiguous block of seqnos with a single atomic operation
+/// and inserts the whole group into the memtable using a single lock acquisition.
+/// The other writers of the group wait until their write was committed.
+#[derive(Default)]
+pub struct GroupCommit {
+    state: Mutex<State>,
+    committed: Condvar,
+}
+
+impl GroupCommit {
+    /// Enqueues a write, and waits until it is committed.
+    ///
+    /// The item is built (and its key & value validated) by the writer itself,
+    /// so the leader only assigns seqnos, and a bad write can not fail the whole group.
+    /// The seqno of the item is ignored.
+    ///
+    /// The `commit` function inserts a group of writes, returning the new memtable size.
+    ///
+    /// Returns the seqno assigned to the write and the memtable size after its group was committed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the leader that committed the write's group panicked.
+    pub fn write<F: FnOnce(Vec<InternalValue>) -> u32>(
+        &self,
+        item: InternalValue,
+        seqno_counter: &SequenceNumberCounter,
+        commit: F,
+    ) -> (SeqNo, u32) {
+        let mut state = self.lock_state();
+
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+
+        state.pending.push(PendingWrite { ticket, item });
+
+        loop {
+            if let Some(result) = state.committed.remove(&ticket) {
+                return result;
+            }
+
+            assert!(
+                !state.abandoned.remove(&ticket),
+                "group commit leader panicked",
+            );
+
+            if state.has_leader {
+                state = self.committed.wait(state).expect("lock is poisoned");
+                continue;
+            }
+
+            // NOTE: Become the leader, our own write is part of the group
+            state.has_leader = true;
+            let group = std::mem::take(&mut state.pending);
+            drop(state);
+
+            let base_seqno = seqno_counter.fetch_add(group.len() as u64, Release);
+
+            let mut own_seqno = base_seqno;
+            let mut tickets = Vec::with_capacity(group.len());
+            let mut items = Vec::with_capacity(group.len());
+
+            for (seqno, mut write) in (base_seqno..).zip(group) {
+                if write.ticket == ticket {
+                    own_seqno = seqno;
+                } else {
+                    tickets.push((write.ticket, seqno));
+                }
+
+                write.item.key.seqno = seqno;
+                items.push(write.item);
+            }
+
+            let guard = LeaderGuard {
+                group_commit: self,
+                tickets: tickets.iter().map(|&(ticket, _)| ticket).collect(),
+            };
+
+            let memtable_size = commit(items);
+            drop(guard);
+
+            state = self.lock_state();
+
+            for (ticket, seqno) in tickets {
+                state.committed.insert(ticket, (seqno, memtable_size));
+            }
+            state.has_leader = false;
+            drop(state);
+
+            self.committed.notify_all();
+
+            return (own_seqno, memtable_size);
+        }
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("lock is poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ValueType;
+    use std::sync::Arc;
+    use test_log::test;
+
+    #[test]
+    fn group_commit_assigns_unique_seqnos() {
+        let group_commit = Arc::new(GroupCommit::default());
+        let counter = SequenceNumberCounter::default();
+        let inserted = Arc::new(Mutex::new(vec![]));
+
+        let threads = (0..8)
+            .map(|_| {
+                let group_commit = group_commit.clone();
+                let counter = counter.clone();
+                let inserted = inserted.clone();
+
+                std::thread::spawn(move || {
+                    (0..100)
+                        .map(|_| {
+                            group_commit
+                                .write(
+                                    InternalValue::from_components("a", "b", 0, ValueType::Value),
+                                    &counter,
+                                    |items| {
+                                        let mut inserted =
+                                            inserted.lock().expect("lock is poisoned");
+                                        inserted.extend(items.into_iter().map(|x| x.key.seqno));
+                                        0
+                                    },
+                                )
+                                .0
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut seqnos = threads
+            .into_iter()
+            .flat_map(|x| x.join().expect("should join"))
+            .collect::<Vec<_>>();
+        seqnos.sort_unstable();
+
+        let mut inserted = inserted.lock().expect("lock is poisoned").clone();
+        inserted.sort_unstable();
+
+        assert_eq!((0..800).collect::<Vec<_>>(), seqnos);
+        assert_eq!(seqnos, inserted);
+        assert_eq!(800, counter.get());
+    }
+
+    #[test]
+    fn group_commit_leader_panic() {
+        let group_commit = GroupCommit::default();
+        let counter = SequenceNumberCounter::default();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            group_commit.write(
+                InternalValue::from_components("a", "b", 0, ValueType::Value),
+                &counter,
+                |_| panic!("commit failed"),
+            )
+        }));
+        assert!(result.is_err());
+
+        // NOTE: Leadership was handed back, so the next write does not block forever
+        let (seqno, _) = group_commit.write(
+            InternalValue::from_components("a", "b", 0, ValueType::Value),
+            &counter,
+            |_| 0,
+        );
+        assert_eq!(1, seqno);
+    }
+}
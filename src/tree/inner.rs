@@ -2,11 +2,27 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
+use super::{
+    amplification::WriteStats,
+    gc_watermark::SnapshotRegistry,
+    group_commit::GroupCommit,
+    level_stats::LevelStatsTracker,
+    pin::{release, PinnedRange},
+    scan_limiter::ScanLimiter,
+};
 use crate::{
-    config::Config, file::LEVELS_MANIFEST_FILE, level_manifest::LevelManifest, memtable::Memtable,
-    segment::meta::SegmentId, stop_signal::StopSignal,
+    config::Config,
+    durability::SyncTracker,
+    file::LEVELS_MANIFEST_FILE,
+    level_manifest::LevelManifest,
+    memtable::Memtable,
+    ops_log::OpsLog,
+    read_pool::ReadPool,
+    segment::meta::{CompressionType, SegmentId},
+    stop_signal::StopSignal,
+    uuid::Uuid,
 };
-use std::sync::{atomic::AtomicU64, Arc, RwLock};
+use std::sync::{atomic::AtomicU64, Arc, Mutex, RwLock};
 
 /// Unique tree ID
 ///
@@ -22,24 +38,69 @@ pub type MemtableId = u64;
 ///
 /// Memtable IDs are monotonically increasing, so we don't really
 /// need a search tree; also there are only a handful of them at most.
+///
+/// Memtables are claimed by the flush that writes them, so
+/// no memtable is flushed (into the same segment file) twice.
 #[derive(Default)]
-pub struct SealedMemtables(Vec<(MemtableId, Arc<Memtable>)>);
+pub struct SealedMemtables {
+    memtables: Vec<(MemtableId, Arc<Memtable>)>,
+
+    /// IDs of the memtables that are being flushed
+    flushing: Vec<MemtableId>,
+}
 
 impl SealedMemtables {
     pub fn add(&mut self, id: MemtableId, memtable: Arc<Memtable>) {
-        self.0.push((id, memtable));
+        self.memtables.push((id, memtable));
     }
 
     pub fn remove(&mut self, id_to_remove: MemtableId) {
-        self.0.retain(|(id, _)| *id != id_to_remove);
+        self.memtables.retain(|(id, _)| *id != id_to_remove);
+        self.flushing.retain(|id| *id != id_to_remove);
     }
 
     pub fn iter(&self) -> impl DoubleEndedIterator<Item = &(MemtableId, Arc<Memtable>)> {
-        self.0.iter()
+        self.memtables.iter()
     }
 
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.memtables.len()
+    }
+
+    /// Claims a sealed memtable for flushing
+    ///
+    /// Returns `false` if the memtable is already being flushed.
+    pub fn claim(&mut self, id: MemtableId) -> bool {
+        if self.flushing.contains(&id) {
+            return false;
+        }
+        if self.memtables.iter().any(|(x, _)| *x == id) {
+            self.flushing.push(id);
+        }
+        true
+    }
+
+    /// Claims the oldest run of consecutive memtables that are not being flushed
+    ///
+    /// The run is consecutive, so a segment that is written from it (using the newest ID)
+    /// does not contain items that are older than the items of memtables that are flushed separately.
+    pub fn claim_oldest_run(&mut self) -> Vec<(MemtableId, Arc<Memtable>)> {
+        let run = self
+            .memtables
+            .iter()
+            .skip_while(|(id, _)| self.flushing.contains(id))
+            .take_while(|(id, _)| !self.flushing.contains(id))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        self.flushing.extend(run.iter().map(|(id, _)| *id));
+
+        run
+    }
+
+    /// Releases the claim of memtables whose flush failed, so they can be flushed again
+    pub fn release(&mut self, ids: &[MemtableId]) {
+        self.flushing.retain(|id| !ids.contains(id));
     }
 }
 
@@ -71,24 +132,81 @@ pub struct TreeInner {
     /// Tree configuration
     pub config: Config,
 
+    /// Compression of new segments, which may differ from the configured compression
+    pub(crate) compression: RwLock<CompressionType>,
+
     /// Compaction may take a while; setting the signal to `true`
     /// will interrupt the compaction and kill the worker.
     pub(crate) stop_signal: StopSignal,
+
+    /// Cumulative byte counters for write amplification
+    pub(crate) write_stats: Arc<WriteStats>,
+
+    /// Per-level runtime counters
+    pub(crate) level_stats: Arc<LevelStatsTracker>,
+
+    /// Log of flushes & compactions, if enabled
+    pub(crate) ops_log: Option<Arc<OpsLog>>,
+
+    /// Batches concurrent grouped writes
+    pub(crate) group_commit: GroupCommit,
+
+    /// Amount of detected weak tombstone violations
+    pub(crate) weak_tombstone_violations: AtomicU64,
+
+    /// Open snapshots & external GC floor
+    pub(crate) snapshots: Arc<SnapshotRegistry>,
+
+    /// Key ranges whose blocks are pinned in the block cache
+    pub(crate) pinned_ranges: Mutex<Vec<PinnedRange>>,
+
+    /// Running expensive scans, if their concurrency is limited
+    pub(crate) scan_limiter: Arc<ScanLimiter>,
+
+    /// Unique identifier of the tree, persisted in its manifest
+    pub(crate) uuid: Option<Uuid>,
+
+    /// Tracks written segment files that have not been synced yet
+    pub(crate) sync_tracker: Arc<SyncTracker>,
+
+    /// Threads that read ahead of scans
+    pub(crate) read_pool: Arc<ReadPool>,
+
+    /// Latency histograms
+    #[cfg(feature = "metrics")]
+    pub(crate) latencies: Arc<crate::metrics::LatencyHistograms>,
 }
 
 impl TreeInner {
-    pub(crate) fn create_new(config: Config) -> crate::Result<Self> {
+    pub(crate) fn create_new(config: Config, uuid: Option<Uuid>) -> crate::Result<Self> {
         let levels =
             LevelManifest::create_new(config.level_count, config.path.join(LEVELS_MANIFEST_FILE))?;
 
+        let level_stats = Arc::new(LevelStatsTracker::new(config.level_count));
+        let ops_log = OpsLog::from_config(&config).map(Arc::new);
+
         Ok(Self {
             id: get_next_tree_id(),
             segment_id_counter: Arc::new(AtomicU64::default()),
+            compression: RwLock::new(config.compression),
+            sync_tracker: Arc::new(SyncTracker::new(config.sync_mode)),
+            read_pool: Arc::new(ReadPool::new(config.read_ahead_threads)),
             config,
             active_memtable: Arc::default(),
             sealed_memtables: Arc::default(),
             levels: Arc::new(RwLock::new(levels)),
             stop_signal: StopSignal::default(),
+            write_stats: Arc::default(),
+            level_stats,
+            ops_log,
+            group_commit: GroupCommit::default(),
+            weak_tombstone_violations: AtomicU64::default(),
+            snapshots: Arc::default(),
+            pinned_ranges: Mutex::default(),
+            scan_limiter: Arc::default(),
+            uuid,
+            #[cfg(feature = "metrics")]
+            latencies: Arc::default(),
         })
     }
 
@@ -104,5 +222,12 @@ impl Drop for TreeInner {
 
         log::trace!("Sending stop signal to compactors");
         self.stop_signal.send();
+
+        // NOTE: The block cache may be shared with other trees, so release our pins
+        if let Ok(pinned_ranges) = self.pinned_ranges.get_mut() {
+            for range in pinned_ranges.drain(..) {
+                release(&self.config.block_cache, &range.blocks);
+            }
+        }
     }
 }
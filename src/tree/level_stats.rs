@@ -0,0 +1,181 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+    time::Duration,
+};
+
+/// Runtime statistics of a single level
+///
+/// See [`crate::Tree::level_stats`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[allow(clippy::module_name_repetitions)]
+pub struct LevelStats {
+    /// Level index
+    pub level: u8,
+
+    /// Point reads that were answered by a segment in this level
+    pub reads_served: u64,
+
+    /// Segments in this level that were skipped by point reads because of their bloom filter
+    pub bloom_negatives: u64,
+
+    /// Segments in this level whose bloom filter let a point read through,
+    /// but that did not contain the key
+    pub bloom_false_positives: u64,
+
+    /// Highest amount of segments in this level, observed after flushes & compactions
+    pub peak_segment_count: u64,
+
+    /// Bytes (keys + values) of items that point reads returned from this level
+    pub bytes_read: u64,
+
+    /// Bytes of segments in this level that were read by compactions
+    pub compaction_bytes_in: u64,
+
+    /// Bytes of segments that compactions wrote into this level
+    pub compaction_bytes_out: u64,
+
+    /// Time spent on compactions into this level
+    pub compaction_time: Duration,
+}
+
+#[derive(Default)]
+struct LevelCounters {
+    reads_served: AtomicU64,
+    bloom_negatives: AtomicU64,
+    bloom_false_positives: AtomicU64,
+    peak_segment_count: AtomicU64,
+    bytes_read: AtomicU64,
+    compaction_bytes_in: AtomicU64,
+    compaction_bytes_out: AtomicU64,
+    compaction_time_micros: AtomicU64,
+}
+
+/// Per-level runtime counters of a tree
+///
+/// Counters are kept in memory only, so they start at zero when the tree is opened.
+pub struct LevelStatsTracker(Box<[LevelCounters]>);
+
+impl LevelStatsTracker {
+    pub fn new(level_count: u8) -> Self {
+        Self((0..level_count).map(|_| LevelCounters::default()).collect())
+    }
+
+    fn level(&self, level: usize) -> Option<&LevelCounters> {
+        self.0.get(level)
+    }
+
+    pub fn record_read(&self, level: usize, bytes: u64) {
+        if let Some(counters) = self.level(level) {
+            counters.reads_served.fetch_add(1, Relaxed);
+            counters.bytes_read.fetch_add(bytes, Relaxed);
+        }
+    }
+
+    #[cfg(feature = "bloom")]
+    pub fn record_bloom_negative(&self, level: usize) {
+        if let Some(counters) = self.level(level) {
+            counters.bloom_negatives.fetch_add(1, Relaxed);
+        }
+    }
+
+    #[cfg(feature = "bloom")]
+    pub fn record_bloom_false_positive(&self, level: usize) {
+        if let Some(counters) = self.level(level) {
+            counters.bloom_false_positives.fetch_add(1, Relaxed);
+        }
+    }
+
+    pub fn record_segment_counts<I: IntoIterator<Item = usize>>(&self, segment_counts: I) {
+        for (counters, count) in self.0.iter().zip(segment_counts) {
+            counters.peak_segment_count.fetch_max(count as u64, Relaxed);
+        }
+    }
+
+    pub fn record_compaction_input(&self, level: usize, bytes: u64) {
+        if let Some(counters) = self.level(level) {
+            counters.compaction_bytes_in.fetch_add(bytes, Relaxed);
+        }
+    }
+
+    pub fn record_compaction_output(&self, level: usize, bytes: u64, duration: Duration) {
+        if let Some(counters) = self.level(level) {
+            counters.compaction_bytes_out.fetch_add(bytes, Relaxed);
+            counters
+                .compaction_time_micros
+                .fetch_add(crate::metrics::micros(duration), Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<LevelStats> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(idx, counters)| LevelStats {
+                // NOTE: Level count is u8
+                #[allow(clippy::cast_possible_truncation)]
+                level: idx as u8,
+
+                reads_served: counters.reads_served.load(Relaxed),
+                bloom_negatives: counters.bloom_negatives.load(Relaxed),
+                bloom_false_positives: counters.bloom_false_positives.load(Relaxed),
+                peak_segment_count: counters.peak_segment_count.load(Relaxed),
+                bytes_read: counters.bytes_read.load(Relaxed),
+                compaction_bytes_in: counters.compaction_bytes_in.load(Relaxed),
+                compaction_bytes_out: counters.compaction_bytes_out.load(Relaxed),
+                compaction_time: Duration::from_micros(
+                    counters.compaction_time_micros.load(Relaxed),
+                ),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn level_stats_tracker() {
+        let tracker = LevelStatsTracker::new(3);
+
+        tracker.record_read(1, 10);
+        tracker.record_read(1, 5);
+        #[cfg(feature = "bloom")]
+        {
+            tracker.record_bloom_negative(0);
+            tracker.record_bloom_false_positive(0);
+        }
+        tracker.record_segment_counts([4, 1]);
+        tracker.record_segment_counts([2, 3]);
+        tracker.record_compaction_input(0, 100);
+        tracker.record_compaction_output(1, 90, Duration::from_millis(2));
+
+        // NOTE: Out of range levels are ignored
+        tracker.record_read(7, 10);
+
+        let stats = tracker.snapshot();
+        assert_eq!(3, stats.len());
+
+        let l0 = stats.first().expect("should exist");
+        assert_eq!(0, l0.level);
+        #[cfg(feature = "bloom")]
+        {
+            assert_eq!(1, l0.bloom_negatives);
+            assert_eq!(1, l0.bloom_false_positives);
+        }
+        assert_eq!(4, l0.peak_segment_count);
+        assert_eq!(100, l0.compaction_bytes_in);
+
+        let l1 = stats.get(1).expect("should exist");
+        assert_eq!(2, l1.reads_served);
+        assert_eq!(15, l1.bytes_read);
+        assert_eq!(3, l1.peak_segment_count);
+        assert_eq!(90, l1.compaction_bytes_out);
+        assert_eq!(Duration::from_millis(2), l1.compaction_time);
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+//! JSON description of the level manifest
+//!
+//! The output is a single JSON object:
+//!
+//! ```json
+//! {
+//!   "levels": [
+//!     {
+//!       "level": 0,
+//!       "disjoint": true,
+//!       "size": "1234",
+//!       "segments": [
+//!         {
+//!           "id": "1",
+//!           "file_size": "1234",
+//!           "uncompressed_size": "2345",
+//!           "items": "10",
+//!           "tombstones": "0",
+//!           "data_blocks": 1,
+//!           "key_range": ["61", "7a"],
+//!           "seqnos": ["0", "9"],
+//!           "created_at": "1700000000000000",
+//!           "compacting": false
+//!         }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Keys are encoded as lowercase hex strings, because they may be arbitrary bytes.
+//! 64-bit and 128-bit integers are encoded as decimal strings, because many JSON parsers
+//! read numbers as doubles, which only represent integers up to 2^53 exactly.
+//! The creation timestamp is given in microseconds since the Unix epoch.
+//! New fields may be added in the future, but existing fields are not changed.
+
+use crate::level_manifest::LevelManifest;
+use std::fmt::Write as _;
+
+fn write_hex(out: &mut String, bytes: &[u8]) {
+    out.push('"');
+
+    for byte in bytes {
+        let _ = write!(out, "{byte:02x}");
+    }
+
+    out.push('"');
+}
+
+/// Encodes the level manifest as JSON
+pub fn to_json(levels: &LevelManifest) -> String {
+    let mut out = String::from("{\"levels\":[");
+
+    // NOTE: Writing into a String cannot fail
+    for (idx, level) in levels.levels.iter().enumerate() {
+        if idx > 0 {
+            out.push(',');
+        }
+
+        let _ = write!(
+            out,
+            "{{\"level\":{idx},\"disjoint\":{},\"size\":\"{}\",\"segments\":[",
+            level.is_disjoint,
+            level.size(),
+        );
+
+        for (idx, segment) in level.iter().enumerate() {
+            let meta = &segment.metadata;
+
+            if idx > 0 {
+                out.push(',');
+            }
+
+            let _ = write!(
+                out,
+                "{{\"id\":\"{}\",\"file_size\":\"{}\",\"uncompressed_size\":\"{}\",\"items\":\"{}\",\"tombstones\":\"{}\",\"data_blocks\":{},\"key_range\":[",
+                meta.id,
+                meta.file_size,
+                meta.uncompressed_size,
+                meta.item_count,
+                meta.tombstone_count,
+                meta.data_block_count,
+            );
+
+            write_hex(&mut out, &meta.key_range.0);
+            out.push(',');
+            write_hex(&mut out, &meta.key_range.1);
+
+            let _ = write!(
+                out,
+                "],\"seqnos\":[\"{}\",\"{}\"],\"created_at\":\"{}\",\"compacting\":{}}}",
+                meta.seqnos.0,
+                meta.seqnos.1,
+                meta.created_at,
+                levels.is_hidden(meta.id),
+            );
+        }
+
+        out.push_str("]}");
+    }
+
+    out.push_str("]}");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn manifest_json_hex() {
+        let mut out = String::new();
+        write_hex(&mut out, &[0, 15, 255]);
+        assert_eq!("\"000fff\"", out);
+    }
+
+    #[test]
+    fn manifest_json_empty() -> crate::Result<()> {
+        let folder = tempfile::tempdir()?;
+        let levels = LevelManifest::create_new(2, folder.path().join("levels"))?;
+
+        assert_eq!(
+            "{\"levels\":[{\"level\":0,\"disjoint\":true,\"size\":\"0\",\"segments\":[]},{\"level\":1,\"disjoint\":true,\"size\":\"0\",\"segments\":[]}]}",
+            to_json(&levels),
+        );
+
+        Ok(())
+    }
+}
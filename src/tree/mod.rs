@@ -2,31 +2,80 @@
 // This source code is licensed under both the Apache 2.0 and MIT License
 // (found in the LICENSE-* files in the repository)
 
+pub mod amplification;
+mod export;
+pub mod flush_verify;
+pub mod gc_watermark;
+pub mod get_many;
+pub mod group_commit;
 pub mod inner;
+pub mod level_stats;
+mod manifest_json;
+mod par_range;
+pub mod pin;
+pub mod scan_limiter;
+pub mod segment_files;
+pub mod seqno_order;
+mod summary;
+pub mod tuning;
+mod weak_tombstone;
 
 use crate::{
     coding::{Decode, Encode},
     compaction::{stream::CompactionStream, CompactionStrategy},
-    config::Config,
-    descriptor_table::FileDescriptorTable,
-    level_manifest::LevelManifest,
+    config::{Config, ConfigFlags},
+    durability::SyncTracker,
+    error::{ErrorContext, Operation},
+    inspect::LevelInspection,
+    level_manifest::{
+        level::{distinct_key_estimate, Level},
+        LevelManifest,
+    },
     manifest::Manifest,
     memtable::Memtable,
-    range::{prefix_to_range, MemtableLockGuard, TreeIter},
-    segment::{block_index::two_level_index::TwoLevelBlockIndex, meta::TableType, Segment},
+    merge::{BoxedIterator, Merger},
+    metrics,
+    mvcc_stream::MvccStream,
+    ops_log::{OpsEvent, OpsLog},
+    range::{prefix_to_range, to_internal_bounds, to_owned_bounds, MemtableLockGuard, TreeIter},
+    read_options::ReadOptions,
+    read_pool::ReadPool,
+    segment::{
+        block_index::two_level_index::TwoLevelBlockIndex, seqno_index::SeqnoIndex,
+        tombstone_index::TombstoneIndex, CachedRead, Segment,
+    },
     stop_signal::StopSignal,
+    uuid::Uuid,
     value::InternalValue,
     version::Version,
-    AbstractTree, BlockCache, KvPair, SegmentId, SeqNo, Snapshot, UserKey, UserValue, ValueType,
+    AbstractTree, CompressionType, HashSet, KvPair, SegmentId, SeqNo, SequenceNumberCounter,
+    Snapshot, UserKey, UserValue, ValueType,
 };
+use flush_verify::ItemStreamChecksum;
+use get_many::LastBlocks;
 use inner::{MemtableId, SealedMemtables, TreeId, TreeInner};
+use level_stats::LevelStatsTracker;
+use smallvec::SmallVec;
 use std::{
     io::Cursor,
-    ops::RangeBounds,
-    path::Path,
-    sync::{atomic::AtomicU64, Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    ops::{Bound, RangeBounds},
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicU64, Arc, Condvar, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::{Duration, Instant},
 };
 
+pub use amplification::AmplificationReport;
+pub use level_stats::LevelStats;
+pub use par_range::{ParRange, ScanOrder};
+pub use segment_files::SegmentFiles;
+pub use tuning::{TuningAdvice, TuningReport};
+
+/// Amount of keys that are read at once by [`Tree::remove_prefix`], before writing their tombstones
+const REMOVE_PREFIX_CHUNK_SIZE: usize = 1_000;
+
+/// Maximum time [`Tree::remove_prefix`] waits for a running compaction, before checking the segments again
+const REMOVE_PREFIX_MAX_BACKOFF: Duration = Duration::from_millis(100);
+
 fn ignore_tombstone_value(item: InternalValue) -> Option<InternalValue> {
     if item.is_tombstone() {
         None
@@ -35,6 +84,37 @@ fn ignore_tombstone_value(item: InternalValue) -> Option<InternalValue> {
     }
 }
 
+/// Maps the L0 segment count linearly from the slowdown threshold (0.0) to the stop threshold (1.0).
+fn l0_pressure(segment_count: usize, slowdown: usize, stop: usize) -> f32 {
+    let range = stop.saturating_sub(slowdown).max(1);
+    let excess = segment_count.saturating_sub(slowdown).min(range);
+
+    // NOTE: Segment counts are small, so the precision loss does not matter
+    #[allow(clippy::cast_precision_loss)]
+    let pressure = excess as f32 / range as f32;
+
+    pressure
+}
+
+/// Progress of the compaction threads of [`Tree::compact_parallel`]
+#[derive(Default)]
+struct CompactionProgress {
+    /// Amount of threads that are currently running a compaction
+    running: usize,
+
+    /// Amount of compactions that have finished (or failed)
+    finished: u64,
+}
+
+/// Folder and key range of a tree that is created by [`Tree::fork_ranges`]
+struct ForkTarget<'a> {
+    /// Folder of the new tree
+    path: &'a Path,
+
+    /// Key range of the items that are copied into the new tree
+    range: (Bound<UserKey>, Bound<UserKey>),
+}
+
 /// A log-structured merge tree (LSM-tree/LSMT)
 #[derive(Clone)]
 pub struct Tree(#[doc(hidden)] pub Arc<TreeInner>);
@@ -116,72 +196,34 @@ impl AbstractTree for Tree {
         Box::new(self.create_iter(None, None).map(|x| x.map(|(_, v)| v)))
     }
 
+    fn first_key_value(&self) -> crate::Result<Option<KvPair>> {
+        // NOTE: Only reads a single item, so it is not throttled like expensive scans
+        self.create_iter(None, None).next().transpose()
+    }
+
+    fn last_key_value(&self) -> crate::Result<Option<KvPair>> {
+        // NOTE: Only reads a single item, so it is not throttled like expensive scans
+        self.create_iter(None, None).next_back().transpose()
+    }
+
     fn flush_memtable(
         &self,
         segment_id: SegmentId,
         memtable: &Arc<Memtable>,
         seqno_threshold: SeqNo,
     ) -> crate::Result<Option<Arc<Segment>>> {
-        use crate::{
-            file::SEGMENTS_FOLDER,
-            segment::writer::{Options, Writer},
-        };
-
-        let folder = self.config.path.join(SEGMENTS_FOLDER);
-        log::debug!("writing segment to {folder:?}");
-
-        let mut segment_writer = Writer::new(Options {
-            segment_id,
-            folder,
-            evict_tombstones: false,
-            data_block_size: self.config.data_block_size,
-            index_block_size: self.config.index_block_size,
-        })?
-        .use_compression(self.config.compression);
-
-        #[cfg(feature = "bloom")]
-        {
-            if self.config.bloom_bits_per_key >= 0 {
-                segment_writer = segment_writer.use_bloom_policy(
-                    crate::segment::writer::BloomConstructionPolicy::FpRate(0.0001),
-                );
-            }
-        }
-
-        let iter = memtable.iter().map(Ok);
-        let compaction_filter = CompactionStream::new(iter, seqno_threshold);
-
-        for item in compaction_filter {
-            segment_writer.write(item?)?;
+        // NOTE: The memtable may already be flushed by a coalesced flush,
+        // which also registers its segment
+        if !self.lock_sealed_memtables().claim(segment_id) {
+            log::debug!("flush: memtable {segment_id} is already being flushed");
+            return Ok(None);
         }
 
-        self.consume_writer(segment_id, segment_writer)
+        self.flush_claimed_memtable(segment_id, memtable, seqno_threshold)
     }
 
     fn register_segments(&self, segments: &[Arc<Segment>]) -> crate::Result<()> {
-        // NOTE: Mind lock order L -> M -> S
-        log::trace!("flush: acquiring levels manifest write lock");
-        let mut original_levels = self.levels.write().expect("lock is poisoned");
-
-        // NOTE: Mind lock order L -> M -> S
-        log::trace!("flush: acquiring sealed memtables write lock");
-        let mut sealed_memtables = self.sealed_memtables.write().expect("lock is poisoned");
-
-        original_levels.atomic_swap(|recipe| {
-            for segment in segments.iter().cloned() {
-                recipe
-                    .first_mut()
-                    .expect("first level should exist")
-                    .insert(segment);
-            }
-        })?;
-
-        for segment in segments {
-            log::trace!("releasing sealed memtable {}", segment.metadata.id);
-            sealed_memtables.remove(segment.metadata.id);
-        }
-
-        Ok(())
+        self.register_flushed_segments(segments, segments.iter().map(|segment| segment.metadata.id))
     }
 
     fn lock_active_memtable(&self) -> RwLockWriteGuard<'_, Memtable> {
@@ -203,15 +245,12 @@ impl AbstractTree for Tree {
         strategy: Arc<dyn CompactionStrategy>,
         seqno_threshold: SeqNo,
     ) -> crate::Result<()> {
-        use crate::compaction::worker::{do_compaction, Options};
+        use crate::compaction::worker::Options;
 
         let mut opts = Options::from_tree(self, strategy);
         opts.eviction_seqno = seqno_threshold;
-        do_compaction(&opts)?;
 
-        log::debug!("lsm-tree: compaction run over");
-
-        Ok(())
+        self.run_compaction(&opts).map(|_| ())
     }
 
     fn get_next_segment_id(&self) -> SegmentId {
@@ -237,25 +276,7 @@ impl AbstractTree for Tree {
     }
 
     fn rotate_memtable(&self) -> Option<(MemtableId, Arc<Memtable>)> {
-        log::trace!("rotate: acquiring active memtable write lock");
-        let mut active_memtable = self.lock_active_memtable();
-
-        log::trace!("rotate: acquiring sealed memtables write lock");
-        let mut sealed_memtables = self.lock_sealed_memtables();
-
-        if active_memtable.is_empty() {
-            return None;
-        }
-
-        let yanked_memtable = std::mem::take(&mut *active_memtable);
-        let yanked_memtable = Arc::new(yanked_memtable);
-
-        let tmp_memtable_id = self.get_next_segment_id();
-        sealed_memtables.add(tmp_memtable_id, yanked_memtable.clone());
-
-        log::trace!("rotate: added memtable id={tmp_memtable_id} to sealed memtables");
-
-        Some((tmp_memtable_id, yanked_memtable))
+        self.rotate_memtable_inner(false)
     }
 
     fn segment_count(&self) -> usize {
@@ -320,87 +341,105 @@ impl AbstractTree for Tree {
         Snapshot::new(Standard(self.clone()), seqno)
     }
 
-    fn get_with_seqno<K: AsRef<[u8]>>(
-        &self,
-        key: K,
-        seqno: SeqNo,
-    ) -> crate::Result<Option<UserValue>> {
-        Ok(self
-            .get_internal_entry(key, true, Some(seqno))?
-            .map(|x| x.value))
-    }
+    fn get_bytes(&self, key: &[u8], seqno: Option<SeqNo>) -> crate::Result<Option<UserValue>> {
+        #[cfg(feature = "metrics")]
+        let start = Instant::now();
+
+        let value = self.get_internal_entry(key, true, seqno)?.map(|x| x.value);
 
-    fn get<K: AsRef<[u8]>>(&self, key: K) -> crate::Result<Option<UserValue>> {
-        Ok(self.get_internal_entry(key, true, None)?.map(|x| x.value))
+        #[cfg(feature = "metrics")]
+        self.latencies.get.record(start.elapsed());
+
+        self.emit_read(value.as_ref());
+
+        Ok(value)
     }
 
-    fn iter_with_seqno(
+    fn get_many_bytes(
         &self,
+        keys: &[&[u8]],
         seqno: SeqNo,
-        index: Option<Arc<Memtable>>,
-    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
-        self.range_with_seqno::<UserKey, _>(.., seqno, index)
+    ) -> crate::Result<Vec<Option<UserValue>>> {
+        let values = self
+            .get_many_internal_entries(keys, seqno)?
+            .into_iter()
+            .map(|item| item.map(|item| item.value))
+            .collect::<Vec<_>>();
+
+        for value in &values {
+            self.emit_read(value.as_ref());
+        }
+
+        Ok(values)
     }
 
-    fn range_with_seqno<K: AsRef<[u8]>, R: RangeBounds<K>>(
+    fn range_bounds(
         &self,
-        range: R,
-        seqno: SeqNo,
+        bounds: (Bound<UserKey>, Bound<UserKey>),
+        seqno: Option<SeqNo>,
         index: Option<Arc<Memtable>>,
     ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
-        Box::new(self.create_range(&range, Some(seqno), index))
+        self.throttle_scan(&bounds, || self.create_range(&bounds, seqno, index))
     }
 
-    fn prefix_with_seqno<K: AsRef<[u8]>>(
+    fn range_bounds_with_options(
         &self,
-        prefix: K,
-        seqno: SeqNo,
+        bounds: (Bound<UserKey>, Bound<UserKey>),
+        options: &ReadOptions,
         index: Option<Arc<Memtable>>,
     ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
-        Box::new(self.create_prefix(prefix, Some(seqno), index))
-    }
+        let key_only = options.key_only;
+
+        self.throttle_scan(&bounds.clone(), || {
+            let iter = self
+                .create_range_with_options(bounds, options, index)
+                .map(move |item| {
+                    item.map(|(key, value)| {
+                        if key_only {
+                            (key, UserValue::from(&[][..]))
+                        } else {
+                            (key, value)
+                        }
+                    })
+                });
 
-    fn range<K: AsRef<[u8]>, R: RangeBounds<K>>(
-        &self,
-        range: R,
-    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
-        Box::new(self.create_range(&range, None, None))
+            options.limited(iter)
+        })
     }
 
-    fn prefix<K: AsRef<[u8]>>(
+    fn insert_bytes(
         &self,
-        prefix: K,
-    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static> {
-        Box::new(self.create_prefix(prefix, None, None))
-    }
+        key: &[u8],
+        value: &[u8],
+        seqno: SeqNo,
+        r#type: ValueType,
+    ) -> (u32, u32) {
+        if r#type == ValueType::WeakTombstone
+            && self
+                .config
+                .flags
+                .contains(ConfigFlags::WEAK_TOMBSTONE_CHECKS)
+        {
+            if let Err(e) = self.check_weak_tombstone(key) {
+                log::warn!("Failed to check weak tombstone of key {key:?}: {e:?}");
+            }
+        }
 
-    fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V, seqno: SeqNo) -> (u32, u32) {
-        let value =
-            InternalValue::from_components(key.as_ref(), value.as_ref(), seqno, ValueType::Value);
+        let value = InternalValue::from_components(key, value, seqno, r#type);
         self.append_entry(value)
     }
 
-    fn raw_insert_with_lock<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+    fn raw_insert_bytes_with_lock(
         &self,
         lock: &RwLockWriteGuard<'_, Memtable>,
-        key: K,
-        value: V,
+        key: &[u8],
+        value: &[u8],
         seqno: SeqNo,
         r#type: ValueType,
     ) -> (u32, u32) {
-        let value = InternalValue::from_components(key.as_ref(), value.as_ref(), seqno, r#type);
+        let value = InternalValue::from_components(key, value, seqno, r#type);
         lock.insert(value)
     }
-
-    fn remove<K: AsRef<[u8]>>(&self, key: K, seqno: SeqNo) -> (u32, u32) {
-        let value = InternalValue::new_tombstone(key.as_ref(), seqno);
-        self.append_entry(value)
-    }
-
-    fn remove_weak<K: AsRef<[u8]>>(&self, key: K, seqno: SeqNo) -> (u32, u32) {
-        let value = InternalValue::new_weak_tombstone(key.as_ref(), seqno);
-        self.append_entry(value)
-    }
 }
 
 impl Tree {
@@ -432,174 +471,2408 @@ impl Tree {
             Self::create_new(config)
         }?;
 
+        tree.reserve_headroom();
+
         Ok(tree)
     }
 
-    pub(crate) fn read_lock_active_memtable(&self) -> RwLockReadGuard<'_, Memtable> {
-        self.active_memtable.read().expect("lock is poisoned")
+    /// Tries to (re-)reserve the configured headroom on disk
+    ///
+    /// Failing to do so is not fatal, it will be retried after the next flush or compaction.
+    fn reserve_headroom(&self) {
+        use crate::file::{reserve_headroom, HEADROOM_FILE};
+
+        if self.config.reserved_headroom == 0 {
+            return;
+        }
+
+        let path = self.config.path.join(HEADROOM_FILE);
+
+        if let Err(e) = reserve_headroom(&path, self.config.reserved_headroom) {
+            log::warn!(
+                "Failed to reserve disk headroom at {}: {e:?}",
+                path.display()
+            );
+        }
     }
 
-    // TODO: Expose as public function, however:
-    // TODO: Right now this is somewhat unsafe to expose as
-    // major compaction needs ALL segments, right now it just takes as many
-    // as it can, which may make the LSM inconsistent.
-    // TODO: There should also be a function to partially compact levels and individual segments
+    /// Runs a single compaction, returning `false` if the strategy chose to do nothing.
+    fn run_compaction(&self, opts: &crate::compaction::worker::Options) -> crate::Result<bool> {
+        use crate::compaction::worker::do_compaction;
 
-    /// Performs major compaction, blocking the caller until it's done.
+        let did_work = match do_compaction(opts) {
+            Err(e) if e.is_disk_full() && self.release_headroom() => {
+                log::warn!("Disk is full, released reserved headroom to complete compaction");
+                do_compaction(opts)?
+            }
+            result => result?,
+        };
+
+        self.reserve_headroom();
+
+        self.emit_segment_gauges(&self.read_lock_levels());
+
+        log::debug!("lsm-tree: compaction run over");
+
+        Ok(did_work)
+    }
+
+    /// Performs compaction using multiple threads, blocking the caller until it's done.
+    ///
+    /// Every thread repeatedly consults the compaction strategy.
+    /// Segments that are being compacted are hidden from the strategy, so compactions of
+    /// non-overlapping segments (e.g. disjoint key ranges of the same levels) run at the same time.
+    /// If the strategy chooses to do nothing while other compactions are running, the thread
+    /// waits for one of them to finish, and consults the strategy again; compaction is done
+    /// once the strategy chooses to do nothing while no compaction is running.
     ///
     /// # Errors
     ///
     /// Will return `Err` if an IO error occurs.
-    #[doc(hidden)]
-    pub fn major_compact(&self, target_size: u64, seqno_threshold: SeqNo) -> crate::Result<()> {
-        log::info!("Starting major compaction");
-        let strategy = Arc::new(crate::compaction::major::Strategy::new(target_size));
-        self.compact(strategy, seqno_threshold)
-    }
-
-    pub(crate) fn consume_writer(
+    ///
+    /// # Panics
+    ///
+    /// Panics if a compaction thread panics, or a lock is poisoned.
+    // NOTE: Takes the strategy like `AbstractTree::compact` does
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn compact_parallel(
         &self,
-        segment_id: SegmentId,
-        mut writer: crate::segment::writer::Writer,
-    ) -> crate::Result<Option<Arc<Segment>>> {
-        #[cfg(feature = "bloom")]
-        use crate::bloom::BloomFilter;
-
-        let segment_folder = writer.opts.folder.clone();
-        let segment_file_path = segment_folder.join(segment_id.to_string());
+        strategy: Arc<dyn CompactionStrategy + Send + Sync>,
+        seqno_threshold: SeqNo,
+        threads: usize,
+    ) -> crate::Result<()> {
+        use crate::compaction::worker::Options;
 
-        let Some(trailer) = writer.finish()? else {
-            return Ok(None);
+        // NOTE: The order in which concurrent compactions allocate segment IDs
+        // is not reproducible, so the deterministic mode compacts sequentially
+        let threads = if self.config.deterministic_seed.is_some() {
+            1
+        } else {
+            threads
         };
 
-        log::debug!("Finalized segment write at {segment_folder:?}");
+        log::debug!("compaction: compacting using {threads} threads");
 
-        let block_index = Arc::new(TwoLevelBlockIndex::from_file(
-            &segment_file_path,
-            trailer.offsets.tli_ptr,
-            (self.id, segment_id).into(),
-            self.config.descriptor_table.clone(),
-            self.config.block_cache.clone(),
-        )?);
+        let progress = Mutex::new(CompactionProgress::default());
+        let progressed = Condvar::new();
 
-        #[cfg(feature = "bloom")]
-        let bloom_ptr = trailer.offsets.bloom_ptr;
+        let results = std::thread::scope(|scope| -> crate::Result<Vec<_>> {
+            let mut handles = Vec::with_capacity(threads);
 
-        let created_segment: Arc<_> = Segment {
-            tree_id: self.id,
+            for _ in 0..threads.max(1) {
+                let strategy = strategy.clone();
+                let progress = &progress;
+                let progressed = &progressed;
 
-            metadata: trailer.metadata,
-            offsets: trailer.offsets,
+                let handle = std::thread::Builder::new()
+                    .name("lsm-compaction".into())
+                    .spawn_scoped(scope, move || -> crate::Result<()> {
+                        let mut opts = Options::from_tree(self, strategy);
+                        opts.eviction_seqno = seqno_threshold;
 
-            descriptor_table: self.config.descriptor_table.clone(),
-            block_index,
-            block_cache: self.config.block_cache.clone(),
+                        while !opts.stop_signal.is_stopped() {
+                            let seen = {
+                                let mut progress = progress.lock().expect("lock is poisoned");
+                                progress.running += 1;
+                                progress.finished
+                            };
 
-            // TODO: as Bloom method
-            #[cfg(feature = "bloom")]
-            bloom_filter: {
-                use crate::coding::Decode;
-                use std::io::Seek;
+                            let result = self.run_compaction(&opts);
 
-                assert!(bloom_ptr > 0, "can not find bloom filter block");
+                            let mut progress = progress.lock().expect("lock is poisoned");
+                            progress.running -= 1;
 
-                let mut reader = std::fs::File::open(&segment_file_path)?;
-                reader.seek(std::io::SeekFrom::Start(bloom_ptr))?;
-                BloomFilter::decode_from(&mut reader)?
-            },
-        }
-        .into();
+                            if !matches!(result, Ok(false)) {
+                                progress.finished += 1;
+                            }
+                            progressed.notify_all();
 
-        self.config.descriptor_table.insert(
-            segment_file_path,
-            (self.id, created_segment.metadata.id).into(),
-        );
+                            if result? {
+                                continue;
+                            }
 
-        log::debug!("Flushed segment to {segment_folder:?}");
+                            // NOTE: The strategy may have chosen to do nothing only because
+                            // the segments it wants to compact are busy, so wait until
+                            // another compaction finishes, and try again
+                            while progress.running > 0 && progress.finished == seen {
+                                progress = progressed.wait(progress).expect("lock is poisoned");
+                            }
 
-        Ok(Some(created_segment))
-    }
+                            if progress.finished == seen {
+                                break;
+                            }
+                        }
 
-    /// Synchronously flushes the active memtable to a disk segment.
-    ///
-    /// The function may not return a result, if, during concurrent workloads, the memtable
-    /// ends up being empty before the flush thread is set up.
-    ///
-    /// The result will contain the disk segment's path, relative to the tree's base path.
-    ///
-    /// # Errors
-    ///
-    /// Will return `Err` if an IO error occurs.
-    #[doc(hidden)]
-    pub fn flush_active_memtable(
-        &self,
-        seqno_threshold: SeqNo,
-    ) -> crate::Result<Option<Arc<Segment>>> {
-        log::debug!("flush: flushing active memtable");
+                        Ok(())
+                    })?;
 
-        let Some((segment_id, yanked_memtable)) = self.rotate_memtable() else {
-            return Ok(None);
-        };
+                handles.push(handle);
+            }
 
-        let Some(segment) = self.flush_memtable(segment_id, &yanked_memtable, seqno_threshold)?
-        else {
-            return Ok(None);
-        };
-        self.register_segments(&[segment.clone()])?;
+            Ok(handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+                })
+                .collect())
+        })?;
 
-        Ok(Some(segment))
+        results.into_iter().collect()
     }
 
-    /// Returns `true` if there are some segments that are being compacted.
-    #[doc(hidden)]
-    #[must_use]
-    pub fn is_compacting(&self) -> bool {
-        let levels = self.levels.read().expect("lock is poisoned");
-        levels.is_compacting()
-    }
+    /// Deletes the headroom reservation, returning `true` if space was freed up
+    fn release_headroom(&self) -> bool {
+        use crate::file::HEADROOM_FILE;
 
-    /// Write-locks the sealed memtables for exclusive access
-    fn lock_sealed_memtables(&self) -> RwLockWriteGuard<'_, SealedMemtables> {
-        self.sealed_memtables.write().expect("lock is poisoned")
-    }
+        if self.config.reserved_headroom == 0 {
+            return false;
+        }
 
-    /// Used for [`BlobTree`] lookup
-    pub(crate) fn get_internal_entry_with_lock<K: AsRef<[u8]>>(
-        &self,
-        memtable_lock: &RwLockWriteGuard<'_, Memtable>,
-        key: K,
-        evict_tombstone: bool,
-        seqno: Option<SeqNo>,
-    ) -> crate::Result<Option<InternalValue>> {
-        if let Some(entry) = memtable_lock.get(&key, seqno) {
-            if evict_tombstone {
-                return Ok(ignore_tombstone_value(entry));
-            }
-            return Ok(Some(entry));
-        };
+        let path = self.config.path.join(HEADROOM_FILE);
 
-        // Now look in sealed memtables
-        if let Some(entry) = self.get_internal_entry_from_sealed_memtables(&key, seqno) {
-            if evict_tombstone {
-                return Ok(ignore_tombstone_value(entry));
+        match std::fs::remove_file(&path) {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!(
+                    "Failed to release disk headroom at {}: {e:?}",
+                    path.display()
+                );
+                false
             }
-            return Ok(Some(entry));
         }
-
-        self.get_internal_entry_from_segments(key, evict_tombstone, seqno)
     }
 
-    fn get_internal_entry_from_sealed_memtables<K: AsRef<[u8]>>(
-        &self,
-        key: K,
-        seqno: Option<SeqNo>,
-    ) -> Option<InternalValue> {
-        let memtable_lock = self.sealed_memtables.read().expect("lock is poisoned");
+    /// Records a finished flush in the metrics & operations log
+    pub(crate) fn record_flush(&self, segment: Option<&Arc<Segment>>, duration: Duration) {
+        #[cfg(feature = "metrics")]
+        self.latencies.flush.record(duration);
 
-        for (_, memtable) in memtable_lock.iter().rev() {
-            if let Some(entry) = memtable.get(&key, seqno) {
-                return Some(entry);
-            }
+        if let Some(sink) = &self.config.metrics_sink {
+            sink.histogram(metrics::FLUSH_DURATION_MICROS, metrics::micros(duration));
+        }
+
+        if let (Some(ops_log), Some(segment)) = (&self.ops_log, segment) {
+            ops_log.append(&OpsEvent::Flush {
+                segment_id: segment.metadata.id,
+                item_count: segment.metadata.item_count,
+                bytes: segment.metadata.file_size,
+                duration,
+            });
+        }
+    }
+
+    /// Emits the segment count gauges, and tracks the peak segment count of every level
+    fn emit_segment_gauges(&self, levels: &LevelManifest) {
+        self.level_stats
+            .record_segment_counts(levels.levels.iter().map(Level::len));
+
+        if let Some(sink) = &self.config.metrics_sink {
+            sink.gauge(metrics::SEGMENTS, levels.len() as u64);
+            sink.gauge(
+                metrics::FIRST_LEVEL_SEGMENTS,
+                levels.first_level_segment_count() as u64,
+            );
+            sink.gauge(
+                metrics::OBSOLETE_SEGMENT_BYTES,
+                levels.obsolete_segment_bytes(),
+            );
+        }
+    }
+
+    /// Emits the amount of bytes returned by a point read
+    pub(crate) fn emit_read(&self, value: Option<&UserValue>) {
+        if let (Some(sink), Some(value)) = (&self.config.metrics_sink, value) {
+            sink.counter(metrics::BYTES_READ, value.len() as u64);
+        }
+    }
+
+    pub(crate) fn read_lock_active_memtable(&self) -> RwLockReadGuard<'_, Memtable> {
+        self.active_memtable.read().expect("lock is poisoned")
+    }
+
+    /// Read-locks the level manifest
+    pub(crate) fn read_lock_levels(&self) -> RwLockReadGuard<'_, LevelManifest> {
+        self.levels.read().expect("lock is poisoned")
+    }
+
+    /// Read-locks the sealed memtables
+    pub(crate) fn read_lock_sealed_memtables(&self) -> RwLockReadGuard<'_, SealedMemtables> {
+        self.sealed_memtables.read().expect("lock is poisoned")
+    }
+
+    // TODO: Expose as public function, however:
+    // TODO: Right now this is somewhat unsafe to expose as
+    // major compaction needs ALL segments, right now it just takes as many
+    // as it can, which may make the LSM inconsistent.
+    // TODO: There should also be a function to partially compact levels and individual segments
+
+    /// Performs major compaction, blocking the caller until it's done.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    #[doc(hidden)]
+    pub fn major_compact(&self, target_size: u64, seqno_threshold: SeqNo) -> crate::Result<()> {
+        log::info!("Starting major compaction");
+        let strategy = Arc::new(crate::compaction::major::Strategy::new(target_size));
+        self.compact(strategy, seqno_threshold)
+    }
+
+    /// Removes all items whose key starts with the given prefix.
+    ///
+    /// Disk segments that only contain keys of the prefix are dropped as a whole,
+    /// using only their metadata, so their space is reclaimed immediately, without reading them.
+    /// Only the keys of the prefix that are stored in the memtables, or in segments
+    /// that also contain other keys (or newer items, see below), get a tombstone.
+    ///
+    /// So the cost is proportional to the amount of keys of the prefix that are stored
+    /// in memtables and partially covered segments, not to the total amount of keys of the prefix.
+    /// The keys are read in chunks of at most 1'000 keys, so memory usage does not grow with the
+    /// size of the prefix. If any tombstones were written and segments are dropped, the active
+    /// memtable is flushed first, so the tombstones are persisted before the segments are gone.
+    ///
+    /// If a segment that would be dropped is compacted at the same time, the segments are
+    /// checked again after a short (increasing) delay, and only the keys of segments that have been
+    /// written in the meantime get a tombstone.
+    ///
+    /// Items written with a seqno >= `seqno` are kept: keys whose latest version
+    /// was written at or above `seqno` do not get a tombstone, and segments that
+    /// contain such items are not dropped.
+    ///
+    /// ###### Caution
+    ///
+    /// Dropped segments are not visible to snapshots anymore, so snapshots
+    /// taken before the deletion may not see the removed items either.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    pub fn remove_prefix<K: AsRef<[u8]>>(&self, prefix: K, seqno: SeqNo) -> crate::Result<()> {
+        use crate::compaction::worker::Options;
+
+        let prefix = prefix.as_ref();
+        let bounds = prefix_to_range(prefix);
+
+        // NOTE: Segments whose keys already got a tombstone
+        let mut checked_segments = HashSet::default();
+        let mut backoff = Duration::from_millis(1);
+
+        loop {
+            let (covered, partial): (Vec<_>, Vec<_>) = {
+                let levels = self.levels.read().expect("lock is poisoned");
+
+                levels
+                    .iter()
+                    .filter(|segment| segment.metadata.key_range.overlaps_with_bounds(&bounds))
+                    .cloned()
+                    .partition(|segment| {
+                        let (min, max) = &*segment.metadata.key_range;
+
+                        min.starts_with(prefix)
+                            && max.starts_with(prefix)
+                            && segment.metadata.seqnos.1 < seqno
+                            && !levels.is_hidden(segment.metadata.id)
+                    })
+            };
+
+            // NOTE: Keys that are only stored in covered segments vanish with them,
+            // all other keys need a tombstone, so their older versions do not become visible
+            let tombstone_count = if checked_segments.is_empty() {
+                self.write_prefix_tombstones(&bounds, &partial, seqno)?
+            } else {
+                // NOTE: When retrying, only the keys of segments that have been written
+                // in the meantime (by the compaction that got in the way) need a tombstone,
+                // the keys of the other segments already got one in a previous iteration
+                let mut tombstone_count = 0;
+
+                for segment in partial
+                    .iter()
+                    .filter(|x| !checked_segments.contains(&x.metadata.id))
+                {
+                    let (min, max) = &*segment.metadata.key_range;
+
+                    let lo = if min.starts_with(prefix) {
+                        Bound::Included(min.clone())
+                    } else {
+                        bounds.0.clone()
+                    };
+                    let hi = if max.starts_with(prefix) {
+                        Bound::Included(max.clone())
+                    } else {
+                        bounds.1.clone()
+                    };
+
+                    tombstone_count += self.write_prefix_tombstones(&(lo, hi), &partial, seqno)?;
+                }
+
+                tombstone_count
+            };
+
+            checked_segments.extend(partial.iter().map(|x| x.metadata.id));
+
+            log::debug!(
+                "Wrote {tombstone_count} tombstones for prefix {prefix:?}, dropping {} segments",
+                covered.len(),
+            );
+
+            if covered.is_empty() {
+                return Ok(());
+            }
+
+            // IMPORTANT: The tombstones need to be persisted before dropping segments,
+            // otherwise older versions of their keys in other segments become visible again
+            if tombstone_count > 0 {
+                // NOTE: The flushed tombstones do not need to be checked again
+                if let Some(segment) = self.flush_active_memtable(0)? {
+                    checked_segments.insert(segment.metadata.id);
+                }
+            }
+
+            let segment_ids = covered.iter().map(|x| x.metadata.id).collect();
+            let strategy = Arc::new(crate::compaction::drop_prefix::Strategy::new(segment_ids));
+
+            if self.run_compaction(&Options::from_tree(self, strategy))? {
+                return Ok(());
+            }
+
+            // NOTE: A covered segment is being compacted, or has been compacted in the meantime,
+            // so give the compaction some time to finish, before checking the segments again
+            log::debug!(
+                "Covered segments of prefix {prefix:?} are being compacted, retrying in {backoff:?}"
+            );
+            std::thread::sleep(backoff);
+            backoff = (backoff * 2).min(REMOVE_PREFIX_MAX_BACKOFF);
+        }
+    }
+
+    /// Writes a tombstone for every key in the given bounds that is stored in
+    /// the memtables or the given segments, and was last written below `seqno`
+    ///
+    /// Returns the amount of written tombstones.
+    #[allow(clippy::significant_drop_tightening)]
+    fn write_prefix_tombstones(
+        &self,
+        bounds: &(Bound<UserKey>, Bound<UserKey>),
+        segments: &[Arc<Segment>],
+        seqno: SeqNo,
+    ) -> crate::Result<usize> {
+        let options = ReadOptions {
+            key_only: true,
+            ..Default::default()
+        };
+
+        let (mut lo, hi) = bounds.clone();
+        let mut tombstone_count = 0;
+
+        loop {
+            let range = (lo.clone(), hi.clone());
+
+            // NOTE: Collect a chunk first, so the memtable is not written to while iterating
+            let chunk = {
+                // NOTE: Mind lock order M -> S
+                let active = self.active_memtable.read().expect("lock is poisoned");
+                let sealed = self.sealed_memtables.read().expect("lock is poisoned");
+
+                let internal_range = to_internal_bounds(&range);
+
+                let mut iters: Vec<BoxedIterator<'_>> = Vec::with_capacity(segments.len() + 1);
+
+                for segment in segments {
+                    if segment.check_key_range_overlap(&range) {
+                        iters.push(Box::new(
+                            segment.range_with_options(range.clone(), &options),
+                        ));
+                    }
+                }
+
+                for (_, memtable) in sealed.iter() {
+                    iters.push(Box::new(memtable.range(internal_range.clone()).map(Ok)));
+                }
+
+                iters.push(Box::new(active.range(internal_range).map(Ok)));
+
+                MvccStream::new(Merger::new(iters))
+                    .take(REMOVE_PREFIX_CHUNK_SIZE)
+                    .collect::<crate::Result<Vec<_>>>()?
+            };
+
+            let Some(last) = chunk.last() else {
+                break;
+            };
+            let is_last_chunk = chunk.len() < REMOVE_PREFIX_CHUNK_SIZE;
+            lo = Bound::Excluded(last.key.user_key.clone());
+
+            // NOTE: Keys that have a newer version than `seqno` are kept, and must not get a
+            // tombstone, because it would collide with or shadow an item written at `seqno`
+            for item in chunk
+                .into_iter()
+                .filter(|item| !item.is_tombstone() && item.key.seqno < seqno)
+            {
+                self.remove(item.key.user_key, seqno);
+                tombstone_count += 1;
+            }
+
+            if is_last_chunk {
+                break;
+            }
+        }
+
+        Ok(tombstone_count)
+    }
+
+    /// Re-encrypts all segments that are not encrypted using the given key,
+    /// blocking the caller until it's done.
+    ///
+    /// Segments are rewritten one by one, so the tree stays fully available
+    /// during key rotation. Once this returns `0`, the old keys are not needed
+    /// anymore to read the tree.
+    ///
+    /// Returns the amount of segments that could not be rewritten yet
+    /// (e.g. because they are currently being compacted), in which case
+    /// the rotation should be retried later.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, or no block cipher is configured.
+    pub fn rewrite_with_key(&self, key_id: u32, seqno_threshold: SeqNo) -> crate::Result<usize> {
+        use crate::compaction::worker::Options;
+
+        if self.config.cipher().is_none() {
+            return Err(crate::Error::MissingCipher(key_id));
+        }
+
+        log::info!("Rewriting segments using key {key_id}");
+
+        let strategy = Arc::new(crate::compaction::rewrite::Strategy::new(key_id));
+
+        let mut opts = Options::from_tree(self, strategy);
+        opts.eviction_seqno = seqno_threshold;
+        opts.key_id = Some(key_id);
+
+        let mut pending = self.count_segments_without_key(key_id);
+
+        while pending > 0 {
+            self.run_compaction(&opts)?;
+
+            let remaining = self.count_segments_without_key(key_id);
+
+            // NOTE: The remaining segments are busy in another compaction
+            if remaining >= pending {
+                log::debug!("{remaining} segments could not be rewritten using key {key_id}");
+                return Ok(remaining);
+            }
+
+            pending = remaining;
+        }
+
+        log::info!("All segments are encrypted using key {key_id}");
+
+        Ok(0)
+    }
+
+    fn count_segments_without_key(&self, key_id: u32) -> usize {
+        self.read_lock_levels()
+            .iter()
+            .filter(|x| x.key_id() != Some(key_id))
+            .count()
+    }
+
+    pub(crate) fn consume_writer(
+        &self,
+        segment_id: SegmentId,
+        writer: crate::segment::writer::Writer,
+    ) -> crate::Result<Option<Arc<Segment>>> {
+        let segment = self.finish_segment_writer(segment_id, writer)?;
+
+        if let (Some(segment), Some(sink)) = (&segment, &self.config.metrics_sink) {
+            sink.counter(metrics::FLUSHES, 1);
+            sink.counter(metrics::FLUSH_BYTES_WRITTEN, segment.metadata.file_size);
+        }
+
+        Ok(segment)
+    }
+
+    /// Finishes a segment writer, and loads the written segment, without recording it as a flush.
+    fn finish_segment_writer(
+        &self,
+        segment_id: SegmentId,
+        mut writer: crate::segment::writer::Writer,
+    ) -> crate::Result<Option<Arc<Segment>>> {
+        #[cfg(feature = "bloom")]
+        use crate::bloom::BloomFilter;
+
+        let segment_folder = writer.opts.folder.clone();
+        let segment_file_path = segment_folder.join(segment_id.to_string());
+
+        let trailer = match writer.finish() {
+            Ok(Some(trailer)) => trailer,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                log::error!("Failed to finish segment {segment_id}: {e:?}");
+                writer.abort();
+                return Err(e);
+            }
+        };
+
+        log::debug!("Finalized segment write at {segment_folder:?}");
+
+        self.sync_tracker
+            .record(segment_file_path.clone(), trailer.metadata.file_size)?;
+
+        self.write_stats.record_write(trailer.metadata.file_size);
+
+        let cipher = trailer.cipher(self.config.cipher())?;
+
+        // NOTE: The file needs to be registered before loading the block index,
+        // because pinned index blocks are read through the descriptor table
+        self.config.descriptor_table.insert_with_cipher(
+            &segment_file_path,
+            (self.id, segment_id).into(),
+            cipher.clone(),
+        );
+
+        let block_index = Arc::new(
+            TwoLevelBlockIndex::from_file(
+                &segment_file_path,
+                trailer.offsets.tli_ptr,
+                (self.id, segment_id).into(),
+                self.config.descriptor_table.clone(),
+                self.config.block_cache.clone(),
+                cipher.as_ref(),
+                trailer.index_item_counts,
+            )?
+            .with_metrics(self.config.metrics_sink.clone())
+            .with_read_sampler(self.config.read_sampler.clone())
+            .with_readahead(self.config.block_readahead)
+            .with_readahead_bytes(self.config.readahead_bytes)
+            .with_read_pool(self.read_pool.clone())
+            .with_pinned_index_blocks(
+                self.config.flags.contains(ConfigFlags::PIN_INDEX_BLOCKS)
+                    || trailer.metadata.one_level_index,
+            )?
+            .with_memory_lock(
+                self.config
+                    .flags
+                    .contains(ConfigFlags::MLOCK_INDEX_AND_FILTERS),
+            )?,
+        );
+
+        #[cfg(feature = "bloom")]
+        let bloom_ptr = trailer.offsets.bloom_ptr;
+
+        let seqno_index = SeqnoIndex::load(&segment_file_path, &trailer, cipher.as_ref())?;
+        let tombstone_index = TombstoneIndex::load(&segment_file_path, &trailer, cipher.as_ref())?;
+
+        let created_segment: Arc<_> = Segment {
+            tree_id: self.id,
+
+            seqno_index,
+            tombstone_index,
+
+            obsolete_path: std::sync::OnceLock::new(),
+
+            seqno_offset: 0,
+
+            metadata: trailer.metadata,
+            offsets: trailer.offsets,
+
+            descriptor_table: self.config.descriptor_table.clone(),
+            block_index,
+            block_cache: self.config.block_cache.clone(),
+
+            // TODO: as Bloom method
+            #[cfg(feature = "bloom")]
+            bloom_filter: {
+                use crate::segment::section::read_section;
+                use std::io::Seek;
+
+                assert!(bloom_ptr > 0, "can not find bloom filter block");
+
+                let mut reader = std::fs::File::open(&segment_file_path)?;
+                reader.seek(std::io::SeekFrom::Start(bloom_ptr))?;
+                read_section::<BloomFilter, _>(
+                    &mut reader,
+                    bloom_ptr,
+                    cipher.as_ref(),
+                    trailer.checksummed_sections,
+                )?
+                .with_memory_lock(
+                    self.config
+                        .flags
+                        .contains(ConfigFlags::MLOCK_INDEX_AND_FILTERS),
+                )
+            },
+        }
+        .into();
+
+        log::debug!("Flushed segment to {segment_folder:?}");
+
+        Ok(Some(created_segment))
+    }
+
+    /// Seals the active memtable, optionally claiming it for flushing,
+    /// so it is not picked up by a coalesced flush.
+    fn rotate_memtable_inner(&self, claim: bool) -> Option<(MemtableId, Arc<Memtable>)> {
+        log::trace!("rotate: acquiring active memtable write lock");
+        let mut active_memtable = self.lock_active_memtable();
+
+        log::trace!("rotate: acquiring sealed memtables write lock");
+        let mut sealed_memtables = self.lock_sealed_memtables();
+
+        if active_memtable.is_empty() {
+            return None;
+        }
+
+        let yanked_memtable = std::mem::take(&mut *active_memtable);
+        let yanked_memtable = Arc::new(yanked_memtable);
+
+        let tmp_memtable_id = self.get_next_segment_id();
+        sealed_memtables.add(tmp_memtable_id, yanked_memtable.clone());
+
+        if claim {
+            sealed_memtables.claim(tmp_memtable_id);
+        }
+
+        log::trace!("rotate: added memtable id={tmp_memtable_id} to sealed memtables");
+
+        Some((tmp_memtable_id, yanked_memtable))
+    }
+
+    /// Flushes a memtable that has been claimed by the caller.
+    ///
+    /// If the flush fails, the claim is released, so the memtable can be flushed again.
+    fn flush_claimed_memtable(
+        &self,
+        segment_id: SegmentId,
+        memtable: &Arc<Memtable>,
+        seqno_threshold: SeqNo,
+    ) -> crate::Result<Option<Arc<Segment>>> {
+        let result = match self.flush_items(segment_id, memtable.iter().map(Ok), seqno_threshold) {
+            Err(e) if e.is_disk_full() && self.release_headroom() => {
+                log::warn!("Disk is full, released reserved headroom to complete flush");
+                self.flush_items(segment_id, memtable.iter().map(Ok), seqno_threshold)
+            }
+            result => result,
+        };
+
+        if result.is_err() {
+            self.lock_sealed_memtables().release(&[segment_id]);
+        }
+        let segment = result?;
+
+        self.reserve_headroom();
+
+        Ok(segment)
+    }
+
+    /// Flushes all sealed memtables into a single segment.
+    ///
+    /// Under bursty writes, multiple memtables may be sealed before they can be flushed.
+    /// Instead of writing one small segment per memtable, their items are merged
+    /// (in one sorted pass) into one segment, which keeps the amount of segments in
+    /// the first level low.
+    ///
+    /// Sealed memtables that are already being flushed (by another flush) are skipped.
+    /// Only the oldest run of consecutive memtables that are not being flushed is merged,
+    /// so the segment does not contain items that are older than those of a separately
+    /// flushed memtable with a lower ID.
+    ///
+    /// Returns the new segment, or `None` if there was no sealed memtable
+    /// that is not being flushed, or all items were evicted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn flush_sealed_memtables(
+        &self,
+        seqno_threshold: SeqNo,
+    ) -> crate::Result<Option<Arc<Segment>>> {
+        use crate::merge::{BoxedIterator, Merger};
+
+        // NOTE: The memtables are claimed, so concurrent flushes do not write them again
+        let memtables = self.lock_sealed_memtables().claim_oldest_run();
+
+        // NOTE: The segment takes the ID of the newest memtable
+        let Some(&(segment_id, _)) = memtables.last() else {
+            return Ok(None);
+        };
+
+        log::debug!(
+            "flush: coalescing {} sealed memtables into segment {segment_id}",
+            memtables.len()
+        );
+
+        let iters = memtables
+            .iter()
+            .map(|(_, memtable)| Box::new(memtable.iter().map(Ok)) as BoxedIterator<'_>)
+            .collect();
+
+        let result = self.flush_items(segment_id, Merger::new(iters), seqno_threshold);
+
+        if result.is_err() {
+            self.lock_sealed_memtables().release(
+                &memtables
+                    .iter()
+                    .map(|(memtable_id, _)| *memtable_id)
+                    .collect::<Vec<_>>(),
+            );
+        }
+        let segment = result?;
+
+        self.register_flushed_segments(
+            &segment.iter().cloned().collect::<Vec<_>>(),
+            memtables.iter().map(|(memtable_id, _)| *memtable_id),
+        )?;
+
+        Ok(segment)
+    }
+
+    /// Synchronously flushes the active memtable into multiple segments, which are written in parallel.
+    ///
+    /// See [`Tree::flush_memtable_parallel`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn flush_active_memtable_parallel(
+        &self,
+        seqno_threshold: SeqNo,
+        threads: usize,
+    ) -> crate::Result<Vec<Arc<Segment>>> {
+        log::debug!("flush: flushing active memtable using {threads} threads");
+
+        let Some((segment_id, yanked_memtable)) = self.rotate_memtable_inner(true) else {
+            return Ok(vec![]);
+        };
+
+        let result =
+            self.flush_memtable_parallel(segment_id, &yanked_memtable, seqno_threshold, threads);
+
+        if result.is_err() {
+            self.lock_sealed_memtables().release(&[segment_id]);
+        }
+        let segments = result?;
+
+        self.register_flushed_segments(&segments, [segment_id])?;
+
+        Ok(segments)
+    }
+
+    /// Flushes a (large) memtable into multiple segments, which are written in parallel.
+    ///
+    /// The memtable is partitioned into (at most) `threads` key ranges of roughly equal size,
+    /// and each key range is written into its own segment by a separate thread, so flushing
+    /// a very large memtable does not take as long.
+    /// The resulting segments are disjoint, and can be registered using a single
+    /// level manifest update.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    #[doc(hidden)]
+    pub fn flush_memtable_parallel(
+        &self,
+        segment_id: SegmentId,
+        memtable: &Arc<Memtable>,
+        seqno_threshold: SeqNo,
+        threads: usize,
+    ) -> crate::Result<Vec<Arc<Segment>>> {
+        use crate::key::InternalKey;
+
+        let split_keys = memtable.split_keys(threads);
+
+        let mut ranges = Vec::with_capacity(split_keys.len() + 1);
+        let mut lo = Bound::Unbounded;
+
+        for key in split_keys {
+            // NOTE: The lowest internal key of a user key has the highest seqno
+            let bound = InternalKey::new(key, SeqNo::MAX, ValueType::Value);
+            ranges.push((lo, Bound::Excluded(bound.clone())));
+            lo = Bound::Included(bound);
+        }
+        ranges.push((lo, Bound::Unbounded));
+
+        log::debug!(
+            "flush: writing memtable {segment_id} into {} partitions",
+            ranges.len()
+        );
+
+        // NOTE: The first partition uses the memtable ID, like a regular flush
+        let segment_ids = std::iter::once(segment_id)
+            .chain(std::iter::repeat_with(|| self.get_next_segment_id()));
+
+        let results = std::thread::scope(|scope| -> crate::Result<Vec<_>> {
+            let mut handles = Vec::with_capacity(ranges.len());
+
+            for (range, segment_id) in ranges.into_iter().zip(segment_ids) {
+                let handle = std::thread::Builder::new()
+                    .name("lsm-flush".into())
+                    .spawn_scoped(scope, move || {
+                        self.flush_items(segment_id, memtable.range(range).map(Ok), seqno_threshold)
+                    })?;
+
+                handles.push(handle);
+            }
+
+            Ok(handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+                })
+                .collect())
+        })?;
+
+        let mut segments = Vec::with_capacity(results.len());
+        let mut error = None;
+
+        for result in results {
+            match result {
+                Ok(segment) => segments.extend(segment),
+                Err(e) => error = error.or(Some(e)),
+            }
+        }
+
+        if let Some(e) = error {
+            // NOTE: The segments of the other partitions are never registered, so remove them
+            for segment in segments {
+                let path = self
+                    .config
+                    .path
+                    .join(crate::file::SEGMENTS_FOLDER)
+                    .join(segment.metadata.id.to_string());
+
+                if let Err(e) = std::fs::remove_file(&path) {
+                    log::warn!(
+                        "Failed to remove unregistered segment file {}: {e:?}",
+                        path.display()
+                    );
+                }
+            }
+
+            return Err(e);
+        }
+
+        Ok(segments)
+    }
+
+    /// Writes (flushed) items into a new segment, evicting items according to the seqno threshold.
+    fn flush_items<I: Iterator<Item = crate::Result<InternalValue>>>(
+        &self,
+        segment_id: SegmentId,
+        items: I,
+        seqno_threshold: SeqNo,
+    ) -> crate::Result<Option<Arc<Segment>>> {
+        use crate::file::SEGMENTS_FOLDER;
+
+        let start = Instant::now();
+
+        let folder = self.config.path.join(SEGMENTS_FOLDER);
+        log::debug!("writing segment to {}", folder.display());
+
+        let context = ErrorContext::new(Operation::Flush)
+            .with_segment_id(segment_id)
+            .with_path(folder.join(segment_id.to_string()));
+
+        let mut segment_writer = self
+            .create_segment_writer(segment_id, folder, 0)
+            .map_err(|e| e.with_context(context.clone()))?;
+
+        let compaction_filter = CompactionStream::new(items, seqno_threshold);
+
+        let mut checksum = self
+            .config
+            .flags
+            .contains(ConfigFlags::VERIFY_FLUSHES)
+            .then(ItemStreamChecksum::new);
+
+        for item in compaction_filter {
+            if let Err(e) = item.and_then(|item| {
+                if let Some(checksum) = &mut checksum {
+                    checksum.update(&item);
+                }
+                segment_writer.write(item)
+            }) {
+                log::error!("flush: failed to write segment {segment_id}: {e:?}");
+                segment_writer.abort();
+                return Err(e.with_context(context));
+            }
+        }
+
+        let segment = self
+            .consume_writer(segment_id, segment_writer)
+            .map_err(|e| e.with_context(context.clone()))?;
+
+        if let (Some(segment), Some(checksum)) = (&segment, &checksum) {
+            self.verify_flushed_segment(segment, checksum, context)?;
+        }
+
+        self.record_flush(segment.as_ref(), start.elapsed());
+
+        Ok(segment)
+    }
+
+    /// Adds flushed segments to the first level, and releases the sealed memtables they were flushed from.
+    fn register_flushed_segments<I: IntoIterator<Item = MemtableId>>(
+        &self,
+        segments: &[Arc<Segment>],
+        memtable_ids: I,
+    ) -> crate::Result<()> {
+        self.sync_new_segments(segments)?;
+
+        // NOTE: Mind lock order L -> M -> S
+        log::trace!("flush: acquiring levels manifest write lock");
+        let mut original_levels = self.lock_levels();
+
+        // NOTE: Mind lock order L -> M -> S
+        log::trace!("flush: acquiring sealed memtables write lock");
+        let mut sealed_memtables = self.lock_sealed_memtables();
+
+        // NOTE: All items may have been evicted, so there may be no segment to add
+        if !segments.is_empty() {
+            original_levels.atomic_swap(|recipe| {
+                if let Some(first_level) = recipe.first_mut() {
+                    for segment in segments.iter().cloned() {
+                        first_level.insert(segment);
+                    }
+                }
+            })?;
+        }
+
+        for memtable_id in memtable_ids {
+            log::trace!("releasing sealed memtable {memtable_id}");
+            sealed_memtables.remove(memtable_id);
+        }
+        drop(sealed_memtables);
+
+        self.emit_segment_gauges(&original_levels);
+        drop(original_levels);
+
+        Ok(())
+    }
+
+    /// Syncs newly written segment files, before they are added to the tree.
+    fn sync_new_segments(&self, segments: &[Arc<Segment>]) -> crate::Result<()> {
+        use crate::file::SEGMENTS_FOLDER;
+
+        let folder = self.config.path.join(SEGMENTS_FOLDER);
+
+        let paths = segments
+            .iter()
+            .map(|segment| folder.join(segment.metadata.id.to_string()))
+            .collect::<Vec<_>>();
+
+        // IMPORTANT: Whatever the sync mode, segments need to be durable before the levels
+        // manifest refers to them, otherwise a crash leaves the tree pointing at torn files
+        self.sync_tracker.sync_before_commit(&paths)?;
+
+        Ok(())
+    }
+
+    /// Synchronously flushes the active memtable to a disk segment.
+    ///
+    /// The function may not return a result, if, during concurrent workloads, the memtable
+    /// ends up being empty before the flush thread is set up.
+    ///
+    /// The result will contain the disk segment's path, relative to the tree's base path.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    #[doc(hidden)]
+    pub fn flush_active_memtable(
+        &self,
+        seqno_threshold: SeqNo,
+    ) -> crate::Result<Option<Arc<Segment>>> {
+        log::debug!("flush: flushing active memtable");
+
+        let Some((segment_id, yanked_memtable)) = self.rotate_memtable_inner(true) else {
+            return Ok(None);
+        };
+
+        let Some(segment) =
+            self.flush_claimed_memtable(segment_id, &yanked_memtable, seqno_threshold)?
+        else {
+            // NOTE: All items were evicted, so only the memtable is released
+            self.register_flushed_segments(&[], [segment_id])?;
+            return Ok(None);
+        };
+        self.register_segments(&[segment.clone()])?;
+
+        Ok(Some(segment))
+    }
+
+    /// Returns the current read, space & write amplification of the tree.
+    ///
+    /// Space amplification is estimated from the item & key counts of the
+    /// disk segments, assuming the largest level holds the live data.
+    /// Write amplification is cumulative since the tree was opened.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn amplification(&self) -> AmplificationReport {
+        // NOTE: Mind lock order L -> M -> S
+        let levels = self.read_lock_levels();
+
+        // NOTE: The active memtable always needs to be consulted
+        let memtable_count = 1 + self.read_lock_sealed_memtables().len();
+
+        AmplificationReport::new(&levels, memtable_count, &self.write_stats)
+    }
+
+    /// Returns the size of segment files that have been removed from the tree
+    /// (e.g. by compaction), but are kept because old readers still use them.
+    ///
+    /// The files are deleted once the last reader is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn obsolete_segment_bytes(&self) -> u64 {
+        self.read_lock_levels().obsolete_segment_bytes()
+    }
+
+    /// Estimates the amount of bytes that compaction needs to rewrite
+    /// to bring all levels within the targets of the given strategy.
+    ///
+    /// A growing debt means compaction does not keep up with writes,
+    /// which can be used to throttle writers before write stalls occur.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn compaction_debt(&self, strategy: &dyn CompactionStrategy) -> u64 {
+        strategy.compaction_debt(&self.read_lock_levels())
+    }
+
+    /// Returns how close the first level (L0) is to its stop threshold,
+    /// from 0.0 (at or below the slowdown threshold) to 1.0 (at or above the stop threshold).
+    ///
+    /// See [`Config::l0_pressure_thresholds`].
+    #[must_use]
+    pub fn l0_pressure(&self) -> f32 {
+        l0_pressure(
+            self.first_level_segment_count(),
+            self.config.l0_slowdown_threshold,
+            self.config.l0_stop_threshold,
+        )
+    }
+
+    /// Returns the amount of first level (L0) segments at which the L0 pressure starts to rise above 0.0.
+    ///
+    /// See [`Config::l0_pressure_thresholds`].
+    #[must_use]
+    pub fn l0_slowdown_threshold(&self) -> usize {
+        self.config.l0_slowdown_threshold
+    }
+
+    /// Returns the amount of first level (L0) segments at which the L0 pressure reaches 1.0.
+    ///
+    /// See [`Config::l0_pressure_thresholds`].
+    #[must_use]
+    pub fn l0_stop_threshold(&self) -> usize {
+        self.config.l0_stop_threshold
+    }
+
+    /// Returns the latency histograms of the tree.
+    ///
+    /// Latencies are recorded since the tree was opened.
+    #[cfg(feature = "metrics")]
+    #[must_use]
+    pub fn latencies(&self) -> &crate::metrics::LatencyHistograms {
+        &self.latencies
+    }
+
+    /// Exports the latest versions of all items in the given range as a columnar stream
+    /// of key, value & seqno columns into the writer.
+    ///
+    /// The range is scanned in batches of up to 1,024 items, so the range
+    /// is never fully buffered in memory.
+    /// Every batch starts with its row count (u32), followed by the key lengths (u16)
+    /// and key bytes, the value lengths (u32) and value bytes, and the seqnos (u64).
+    /// The stream is prefixed by the magic bytes `LSMTCOL1`, and terminated by an
+    /// empty batch, followed by the total row count (u64). All integers are big endian.
+    ///
+    /// Returns the amount of exported items.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn export_range<K: AsRef<[u8]>, R: RangeBounds<K>, W: std::io::Write>(
+        &self,
+        range: R,
+        writer: W,
+    ) -> crate::Result<u64> {
+        export::write_columnar(self.create_internal_range(&range, None, None), writer)
+    }
+
+    /// Returns all items (including tombstones) that were written with a seqno >= `seqno`.
+    ///
+    /// The items are not returned in key or seqno order: every segment and memtable
+    /// is read one after another, in key order.
+    ///
+    /// If the segments were written with a seqno index (see [`Config::seqno_index`]),
+    /// only the data blocks that contain such items are read, otherwise every segment
+    /// that contains newer items is scanned fully.
+    ///
+    /// The segments and memtables are captured when the iterator is created.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    pub fn changes_since(
+        &self,
+        seqno: SeqNo,
+    ) -> impl Iterator<Item = crate::Result<InternalValue>> {
+        // NOTE: Mind lock order L -> M -> S
+        let levels = self.read_lock_levels();
+        let active_memtable = self.read_lock_active_memtable();
+        let sealed_memtables = self.read_lock_sealed_memtables();
+
+        let memtable_items = sealed_memtables
+            .iter()
+            .flat_map(|(_, memtable)| memtable.iter())
+            .chain(active_memtable.iter())
+            .filter(|item| item.key.seqno >= seqno)
+            .map(Ok)
+            .collect::<Vec<_>>();
+
+        // NOTE: Collected, so the locks can be released before reading any segment
+        #[allow(clippy::needless_collect)]
+        let segments = levels.iter().cloned().collect::<Vec<_>>();
+
+        drop(sealed_memtables);
+        drop(active_memtable);
+        drop(levels);
+
+        segments
+            .into_iter()
+            .flat_map(move |segment| segment.changes_since(seqno))
+            .chain(memtable_items)
+    }
+
+    /// Returns the latest version of every key in the given range that was written
+    /// in the given seqno window, in key order.
+    ///
+    /// Versions written before or after the window are ignored, so a key that was updated
+    /// inside the window is returned with the last value it was given inside the window.
+    /// Tombstones are returned as well, so deletions inside the window can be observed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let folder = tempfile::tempdir()?;
+    /// use lsm_tree::{AbstractTree, Config, Tree};
+    ///
+    /// let tree = Config::new(folder).open()?;
+    ///
+    /// tree.insert("a", "abc", 0);
+    /// tree.insert("b", "abc", 1);
+    /// tree.remove("a", 2);
+    /// tree.insert("c", "abc", 3);
+    ///
+    /// let changes = tree.range_window::<&str, _>(.., 1..3).collect::<Result<Vec<_>, _>>()?;
+    /// assert_eq!(2, changes.len());
+    /// assert!(changes[0].is_tombstone());
+    /// #
+    /// # Ok::<(), lsm_tree::Error>(())
+    /// ```
+    pub fn range_window<K: AsRef<[u8]>, R: RangeBounds<K>>(
+        &self,
+        range: R,
+        seqnos: std::ops::Range<SeqNo>,
+    ) -> impl DoubleEndedIterator<Item = crate::Result<InternalValue>> + 'static {
+        let bounds = to_owned_bounds(&range);
+
+        // NOTE: Mind lock order L -> M -> S
+        let level_manifest_lock =
+            guardian::ArcRwLockReadGuardian::take(self.levels.clone()).expect("lock is poisoned");
+
+        let active = guardian::ArcRwLockReadGuardian::take(self.active_memtable.clone())
+            .expect("lock is poisoned");
+
+        let sealed = guardian::ArcRwLockReadGuardian::take(self.sealed_memtables.clone())
+            .expect("lock is poisoned");
+
+        TreeIter::create_range_window(
+            MemtableLockGuard {
+                active,
+                sealed,
+                ephemeral: None,
+                segments: vec![],
+            },
+            bounds,
+            seqnos,
+            level_manifest_lock,
+        )
+    }
+
+    /// Returns the latest version of every key with the given prefix that was written
+    /// in the given seqno window, in key order.
+    ///
+    /// See [`Tree::range_window`].
+    pub fn prefix_window<K: AsRef<[u8]>>(
+        &self,
+        prefix: K,
+        seqnos: std::ops::Range<SeqNo>,
+    ) -> impl DoubleEndedIterator<Item = crate::Result<InternalValue>> + 'static {
+        self.range_window(prefix_to_range(prefix.as_ref()), seqnos)
+    }
+
+    /// Scans the given range using multiple threads.
+    ///
+    /// The range is split into `threads` partitions of roughly equal size
+    /// (at index block boundaries of the overlapping segments), which are scanned in parallel.
+    /// Depending on `order`, the items are returned in key order, or as soon as they are read.
+    ///
+    /// All partitions read the same snapshot of the tree, taken when the scan is started.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a scan thread cannot be started.
+    pub fn par_range<K: AsRef<[u8]>, R: RangeBounds<K>>(
+        &self,
+        range: R,
+        threads: usize,
+        order: ScanOrder,
+    ) -> crate::Result<ParRange> {
+        use std::{
+            collections::VecDeque,
+            ops::Bound::{Excluded, Included, Unbounded},
+            sync::mpsc::sync_channel,
+        };
+
+        let bounds: (Bound<UserKey>, Bound<UserKey>) = (
+            match range.start_bound() {
+                Included(x) => Included(x.as_ref().into()),
+                Excluded(x) => Excluded(x.as_ref().into()),
+                Unbounded => Unbounded,
+            },
+            match range.end_bound() {
+                Included(x) => Included(x.as_ref().into()),
+                Excluded(x) => Excluded(x.as_ref().into()),
+                Unbounded => Unbounded,
+            },
+        );
+
+        // NOTE: Mind lock order L -> M -> S
+        //
+        // The partitions and the snapshot seqno are taken from the same view of the tree,
+        // so items that are written after this point are not visible to the scan
+        let (partitions, seqno) = {
+            let levels = self.read_lock_levels();
+            let active_memtable = self.read_lock_active_memtable();
+            let sealed_memtables = self.read_lock_sealed_memtables();
+
+            let seqno = sealed_memtables
+                .iter()
+                .map(|(_, memtable)| memtable.get_highest_seqno())
+                .chain(std::iter::once(active_memtable.get_highest_seqno()))
+                .chain(
+                    levels
+                        .iter()
+                        .map(|segment| Some(segment.get_highest_seqno())),
+                )
+                .max()
+                .flatten()
+                .map(|seqno| seqno + 1);
+
+            // NOTE: Newer writes are not visible at `seqno`,
+            // and the segments cannot change while the levels are locked
+            drop(sealed_memtables);
+            drop(active_memtable);
+
+            let partitions = par_range::partition_range(&levels, &bounds, threads.max(1));
+            drop(levels);
+
+            (partitions, seqno)
+        };
+
+        let mut receivers = VecDeque::with_capacity(partitions.len());
+        let mut shared_sender = None;
+
+        for partition in partitions {
+            let sender = match order {
+                ScanOrder::Sorted => {
+                    let (sender, receiver) = sync_channel(par_range::CHANNEL_CAPACITY);
+                    receivers.push_back(receiver);
+                    sender
+                }
+                ScanOrder::Unsorted => shared_sender
+                    .get_or_insert_with(|| {
+                        let (sender, receiver) = sync_channel(par_range::CHANNEL_CAPACITY);
+                        receivers.push_back(receiver);
+                        sender
+                    })
+                    .clone(),
+            };
+
+            let tree = self.clone();
+
+            std::thread::Builder::new()
+                .name("lsm-par-range".into())
+                .spawn(move || {
+                    for item in tree.create_internal_range(&partition, seqno, None) {
+                        let item = item.map(|item| (item.key.user_key, item.value));
+
+                        // NOTE: The scan was dropped, so stop reading
+                        if sender.send(item).is_err() {
+                            return;
+                        }
+                    }
+                })?;
+        }
+
+        Ok(ParRange { receivers })
+    }
+
+    /// Syncs all segment files that have been written by flushes and compactions,
+    /// but have not been synced yet (see [`crate::SyncMode`]).
+    ///
+    /// With [`crate::SyncMode::Always`], every segment is already synced once it is written,
+    /// so this is a no-op.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn sync(&self) -> crate::Result<()> {
+        self.sync_tracker.sync()?;
+        Ok(())
+    }
+
+    /// Opens the segment files of the given levels in the background, and loads
+    /// their index blocks (and optionally their first data blocks) into the block cache,
+    /// so the first queries after opening the tree do not pay the cold-start cost.
+    ///
+    /// The returned handle can be joined to wait for prewarming to finish,
+    /// or dropped to let it run in the background.
+    /// Segments that are compacted away during prewarming are skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the background thread could not be spawned.
+    pub fn prewarm<R: RangeBounds<usize>>(
+        &self,
+        levels: R,
+        load_first_data_block: bool,
+    ) -> crate::Result<std::thread::JoinHandle<crate::Result<()>>> {
+        let segments = self
+            .read_lock_levels()
+            .levels
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| levels.contains(idx))
+            .flat_map(|(_, level)| level.segments.iter().cloned())
+            .collect::<Vec<_>>();
+
+        log::debug!("Prewarming {} segments", segments.len());
+
+        // NOTE: The segment file is only deleted once the last reference to the segment
+        // is dropped, so the level manifest lock does not need to be held while reading
+        let handle = std::thread::Builder::new()
+            .name("lsm-prewarm".into())
+            .spawn(move || {
+                for segment in segments {
+                    if segment.obsolete_path.get().is_some() {
+                        continue;
+                    }
+
+                    segment.prewarm(load_first_data_block)?;
+                }
+
+                Ok(())
+            })?;
+
+        Ok(handle)
+    }
+
+    /// Returns the unique identifier of the tree.
+    ///
+    /// The ID is generated when the tree is created, and persisted in its manifest.
+    /// Trees created by older versions do not have an ID.
+    #[must_use]
+    pub fn id(&self) -> Option<Uuid> {
+        self.uuid
+    }
+
+    /// Checks that the tree folder still contains this tree, by comparing the ID
+    /// stored in its manifest, so no segment files of a different tree are linked.
+    fn verify_id(&self) -> crate::Result<()> {
+        use crate::file::MANIFEST_FILE;
+
+        let bytes = std::fs::read(self.config.path.join(MANIFEST_FILE))?;
+        let manifest = Manifest::decode_from(&mut bytes.as_slice())?;
+
+        if manifest.uuid != self.uuid {
+            log::error!(
+                "Tree at {} has ID {:?}, expected {:?}",
+                self.config.path.display(),
+                manifest.uuid,
+                self.uuid,
+            );
+            return Err(crate::Error::TreeIdMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Creates a fork of the tree in the given folder.
+    ///
+    /// The fork starts out with the same data as the tree, but both trees are
+    /// independent afterwards: writes to one tree are not visible in the other.
+    ///
+    /// Segments are immutable, so instead of copying them, they are hard linked
+    /// into the new folder (falling back to copying, if the file system does not
+    /// support hard links). Unflushed items are copied into the fork's active memtable.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, the folder already contains a tree,
+    /// or the tree's folder was replaced by a different tree (see [`Tree::id`]).
+    pub fn fork<P: AsRef<Path>>(&self, path: P) -> crate::Result<Self> {
+        let mut forks = self.fork_ranges(&[ForkTarget {
+            path: path.as_ref(),
+            range: (Bound::Unbounded, Bound::Unbounded),
+        }])?;
+
+        Ok(forks.remove(0))
+    }
+
+    /// Splits the tree at the given key into two new trees.
+    ///
+    /// The left tree (created in `left_path`) contains all keys lower than `key`,
+    /// the right tree (created in `right_path`) contains all keys starting from `key`.
+    ///
+    /// Like [`Tree::fork`], segments that lie fully on one side of the key are
+    /// hard linked; only segments that contain the split key are rewritten.
+    /// The tree itself is not modified, and all trees are independent afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, a folder already contains a tree,
+    /// or the tree's folder was replaced by a different tree (see [`Tree::id`]).
+    pub fn split<K: Into<UserKey>, P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        key: K,
+        left_path: P,
+        right_path: Q,
+    ) -> crate::Result<(Self, Self)> {
+        let key = key.into();
+
+        let mut trees = self.fork_ranges(&[
+            ForkTarget {
+                path: left_path.as_ref(),
+                range: (Bound::Unbounded, Bound::Excluded(key.clone())),
+            },
+            ForkTarget {
+                path: right_path.as_ref(),
+                range: (Bound::Included(key), Bound::Unbounded),
+            },
+        ])?;
+
+        // NOTE: One tree is created per target
+        #[allow(clippy::expect_used)]
+        let (left, right) = {
+            let right = trees.pop().expect("should have right tree");
+            let left = trees.pop().expect("should have left tree");
+            (left, right)
+        };
+
+        Ok((left, right))
+    }
+
+    /// Creates new trees in the given folders, each containing the tree's items in the given key range.
+    ///
+    /// All trees are created from the same consistent view of the tree.
+    fn fork_ranges(&self, targets: &[ForkTarget<'_>]) -> crate::Result<Vec<Self>> {
+        use crate::{
+            file::{fsync_directory, LEVELS_MANIFEST_FILE, MANIFEST_FILE, SEGMENTS_FOLDER},
+            path::absolute_path,
+        };
+        use std::fs::{create_dir_all, File};
+
+        self.verify_id()?;
+
+        let mut configs = Vec::with_capacity(targets.len());
+
+        for target in targets {
+            let mut config = self.config.clone();
+            config.path = absolute_path(target.path);
+
+            if config.path.join(MANIFEST_FILE).try_exists()? {
+                return Err(crate::Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    "folder already contains a tree",
+                )));
+            }
+
+            create_dir_all(config.path.join(SEGMENTS_FOLDER))?;
+
+            configs.push(config);
+        }
+
+        // NOTE: Only snapshot the segments and unflushed items while holding the locks,
+        // so writes, flushes and compactions are not blocked while segments are linked
+        // or rewritten; the acquired segment files are not deleted in the meantime
+        let (segment_files, levels, memtable_items) = {
+            // NOTE: Mind lock order L -> M -> S
+            // Holding all locks guarantees that no memtable is flushed
+            // and no segment is replaced in the meantime
+            let levels = self.read_lock_levels();
+            let segment_files = self.acquire_segment_files_locked(&levels);
+            let active_memtable = self.read_lock_active_memtable();
+            let sealed_memtables = self.read_lock_sealed_memtables();
+
+            let memtable_items = targets
+                .iter()
+                .map(|target| {
+                    sealed_memtables
+                        .iter()
+                        .flat_map(|(_, memtable)| memtable.iter())
+                        .chain(active_memtable.iter())
+                        .filter(|item| target.range.contains(&item.key.user_key))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            drop(sealed_memtables);
+            drop(active_memtable);
+
+            (
+                segment_files,
+                levels
+                    .levels
+                    .iter()
+                    .map(|level| level.iter().cloned().collect::<Vec<_>>())
+                    .collect::<Vec<_>>(),
+                memtable_items,
+            )
+        };
+
+        let source_folder_path = self.config.path.join(SEGMENTS_FOLDER);
+
+        for (config, target) in configs.iter().zip(targets) {
+            log::debug!(
+                "Forking LSM-tree at {} to {}",
+                self.config.path.display(),
+                config.path.display()
+            );
+
+            let level_ids = self.fork_segments(
+                &levels,
+                &target.range,
+                &source_folder_path,
+                &config.path.join(SEGMENTS_FOLDER),
+            )?;
+
+            LevelManifest::write_to_disk(config.path.join(LEVELS_MANIFEST_FILE), &level_ids)?;
+        }
+
+        drop(levels);
+        drop(segment_files);
+
+        let mut trees = Vec::with_capacity(configs.len());
+
+        for (config, items) in configs.into_iter().zip(memtable_items) {
+            let path = config.path.clone();
+            let segment_folder_path = path.join(SEGMENTS_FOLDER);
+
+            // NOTE: Lastly, fsync manifest
+            // -> the tree is fully initialized
+            let mut file = File::create(path.join(MANIFEST_FILE))?;
+            Manifest::new(&config).encode_into(&mut file)?;
+            file.sync_all()?;
+
+            // IMPORTANT: fsync folders on Unix
+            fsync_directory(&segment_folder_path)?;
+            fsync_directory(&path)?;
+
+            let tree = Self::open(config)?;
+            tree.append_entries(items);
+
+            trees.push(tree);
+        }
+
+        Ok(trees)
+    }
+
+    /// Hard links (or copies) the segments that are fully in the given key range into the given folder,
+    /// and rewrites the segments that straddle a range boundary.
+    ///
+    /// Returns the segment IDs of the forked levels.
+    fn fork_segments(
+        &self,
+        levels: &[Vec<Arc<Segment>>],
+        range: &(Bound<UserKey>, Bound<UserKey>),
+        source_folder_path: &Path,
+        segment_folder_path: &Path,
+    ) -> crate::Result<Vec<Vec<SegmentId>>> {
+        let mut level_ids = Vec::with_capacity(levels.len());
+
+        for (level_idx, level) in levels.iter().enumerate() {
+            let mut ids = Vec::with_capacity(level.len());
+
+            for segment in level {
+                let (min, max) = &*segment.metadata.key_range;
+
+                // NOTE: The level manifest of the fork only stores segment IDs, so segments
+                // with a seqno offset (see `Tree::absorb`) are rewritten with adjusted seqnos
+                if range.contains(min) && range.contains(max) && segment.seqno_offset == 0 {
+                    let file_name = segment.metadata.id.to_string();
+                    let src = source_folder_path.join(&file_name);
+                    let dst = segment_folder_path.join(&file_name);
+
+                    if let Err(e) = std::fs::hard_link(&src, &dst) {
+                        log::debug!(
+                            "Failed to hard link {}, copying instead: {e:?}",
+                            src.display()
+                        );
+                        std::fs::copy(&src, &dst)?;
+                    }
+
+                    ids.push(segment.metadata.id);
+                } else if segment.check_key_range_overlap(range) {
+                    // NOTE: Segment straddles a range boundary (or has a seqno offset),
+                    // so only its items in the range are written into a new segment
+                    //
+                    // There are far less than 256 levels
+                    #[allow(clippy::cast_possible_truncation)]
+                    if let Some(id) = self.rewrite_segment_range(
+                        segment,
+                        range,
+                        segment_folder_path,
+                        level_idx as u8,
+                    )? {
+                        ids.push(id);
+                    }
+                }
+            }
+
+            level_ids.push(ids);
+        }
+
+        Ok(level_ids)
+    }
+
+    /// Writes the items of a segment that are in the given key range into a new segment in the given folder.
+    ///
+    /// The new segment replaces the old one in the given level, so it uses that level's bloom filter policy.
+    ///
+    /// Returns the ID of the new segment, or `None` if no items are in the range.
+    fn rewrite_segment_range(
+        &self,
+        segment: &Segment,
+        range: &(Bound<UserKey>, Bound<UserKey>),
+        folder: &Path,
+        level: u8,
+    ) -> crate::Result<Option<SegmentId>> {
+        // NOTE: Allocate the ID from this tree, so it cannot collide with any linked segment
+        let segment_id = self.get_next_segment_id();
+
+        // NOTE: The new tree does not know about unsynced files, so sync right away
+        let mut segment_writer = self
+            .create_segment_writer(segment_id, folder.to_path_buf(), level)?
+            .use_sync(true);
+
+        for item in segment.range(range.clone()) {
+            if let Err(e) = item.and_then(|item| segment_writer.write(item)) {
+                segment_writer.abort();
+                return Err(e);
+            }
+        }
+
+        match segment_writer.finish() {
+            Ok(trailer) => Ok(trailer.map(|_| segment_id)),
+            Err(e) => {
+                segment_writer.abort();
+                Err(e)
+            }
+        }
+    }
+
+    /// Ingests the data of another tree into this tree.
+    ///
+    /// The seqnos of the other tree's items are increased by `seqno_offset`, so they
+    /// can be ordered after the items of this tree, and are not visible to snapshots (and reads at a seqno)
+    /// that are lower than `seqno_offset`. Afterwards, the seqno counter of this tree
+    /// needs to be advanced past `seqno_offset` plus the highest seqno of the other tree.
+    ///
+    /// The segments of the other tree are hard linked (under a new segment ID) without rewriting them,
+    /// and only copied if they cannot be linked (e.g. because both trees are on different file systems).
+    /// Their seqno offset is stored in the level manifest, and added to the seqnos of their items when
+    /// they are read, until they are rewritten by a compaction.
+    /// All ingested segments are added to the first level, and unflushed items
+    /// are copied into the active memtable.
+    ///
+    /// The other tree is not modified, and needs to use the same encryption settings as this tree.
+    /// Keys of the other tree should not be written into this tree while it is absorbed.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs, the other tree is this tree, a seqno of the other tree
+    /// overflows when adding `seqno_offset`, or the other tree's folder was replaced by a different tree (see [`Tree::id`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    pub fn absorb(&self, other: &Self, seqno_offset: SeqNo) -> crate::Result<()> {
+        log::debug!(
+            "Absorbing LSM-tree at {} into {}",
+            other.config.path.display(),
+            self.config.path.display()
+        );
+
+        if self.id == other.id {
+            return Err(crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "tree can not absorb itself",
+            )));
+        }
+
+        other.verify_id()?;
+
+        // NOTE: Only snapshot the other tree's segments and unflushed items while holding its locks,
+        // so its writes, flushes and compactions are not blocked while segments are linked or rewritten;
+        // the acquired segment files are not deleted in the meantime
+        //
+        // The locks of both trees are never held at the same time, so two trees
+        // that absorb each other concurrently cannot deadlock
+        let (other_segment_files, memtable_items) = {
+            // NOTE: Mind lock order L -> M -> S
+            let levels = other.read_lock_levels();
+            let segment_files = other.acquire_segment_files_locked(&levels);
+            let active_memtable = other.read_lock_active_memtable();
+            let sealed_memtables = other.read_lock_sealed_memtables();
+
+            let memtable_items = sealed_memtables
+                .iter()
+                .flat_map(|(_, memtable)| memtable.iter())
+                .chain(active_memtable.iter())
+                .collect::<Vec<_>>();
+
+            drop(sealed_memtables);
+            drop(active_memtable);
+
+            drop(levels);
+
+            (segment_files, memtable_items)
+        };
+        let other_segments = other_segment_files.segments();
+
+        let memtable_items = memtable_items
+            .into_iter()
+            .map(|item| Self::shift_seqno(item, seqno_offset))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let mut segment_ids = Vec::with_capacity(other_segments.len());
+
+        let segments =
+            match self.absorb_segments(other, other_segments, seqno_offset, &mut segment_ids) {
+                Ok(segments) => segments,
+                Err(e) => {
+                    self.delete_absorbed_segments(&segment_ids);
+                    return Err(e);
+                }
+            };
+
+        if let Err(e) = self.sync_new_segments(&segments) {
+            self.delete_absorbed_segments(&segment_ids);
+            return Err(e);
+        }
+
+        let mut levels = self.lock_levels();
+
+        let result = levels.atomic_swap(|recipe| {
+            if let Some(first_level) = recipe.first_mut() {
+                for segment in segments.iter().cloned() {
+                    first_level.insert(segment);
+                }
+            }
+        });
+
+        if let Err(e) = result {
+            drop(levels);
+            self.delete_absorbed_segments(&segment_ids);
+            return Err(e);
+        }
+
+        self.emit_segment_gauges(&levels);
+        drop(levels);
+
+        self.append_entries(memtable_items);
+
+        Ok(())
+    }
+
+    /// Increases the seqno of an absorbed item by the given offset.
+    fn shift_seqno(mut item: InternalValue, seqno_offset: SeqNo) -> crate::Result<InternalValue> {
+        item.key.seqno = item.key.seqno.checked_add(seqno_offset).ok_or_else(|| {
+            crate::Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "seqno offset overflows seqno of absorbed item",
+            ))
+        })?;
+
+        Ok(item)
+    }
+
+    /// Links the segments of an absorbed tree into this tree's segment folder.
+    ///
+    /// The segment files are never rewritten, instead `seqno_offset` is added to
+    /// the seqno offset of each segment, which is stored in the level manifest.
+    ///
+    /// The IDs of all segment files that are created are pushed into `segment_ids`,
+    /// so they can be deleted if absorbing fails.
+    fn absorb_segments(
+        &self,
+        other: &Self,
+        other_segments: &[Arc<Segment>],
+        seqno_offset: SeqNo,
+        segment_ids: &mut Vec<SegmentId>,
+    ) -> crate::Result<Vec<Arc<Segment>>> {
+        use crate::file::SEGMENTS_FOLDER;
+
+        let segment_folder_path = self.config.path.join(SEGMENTS_FOLDER);
+        let other_folder_path = other.config.path.join(SEGMENTS_FOLDER);
+
+        let mut segments = Vec::with_capacity(other_segments.len());
+
+        for segment in other_segments {
+            let segment_id = self.get_next_segment_id();
+            segment_ids.push(segment_id);
+
+            // NOTE: The segment may already have been absorbed with a seqno offset itself
+            let segment_seqno_offset = segment
+                .seqno_offset
+                .checked_add(seqno_offset)
+                .filter(|_| {
+                    segment
+                        .metadata
+                        .seqnos
+                        .1
+                        .checked_add(seqno_offset)
+                        .is_some()
+                })
+                .ok_or_else(|| {
+                    crate::Error::Io(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "seqno offset overflows seqno of absorbed segment",
+                    ))
+                })?;
+
+            let src = other_folder_path.join(segment.metadata.id.to_string());
+            let dst = segment_folder_path.join(segment_id.to_string());
+
+            if let Err(e) = std::fs::hard_link(&src, &dst) {
+                log::debug!(
+                    "Failed to hard link {}, copying instead: {e:?}",
+                    src.display()
+                );
+                std::fs::copy(&src, &dst)?;
+            }
+
+            let segment = Segment::recover(
+                &dst,
+                segment_id,
+                self.id,
+                self.config.block_cache.clone(),
+                self.config.descriptor_table.clone(),
+                self.config.cipher(),
+                self.config.metrics_sink.clone(),
+                self.config.read_sampler.clone(),
+                self.config.block_readahead,
+                self.config.readahead_bytes,
+                self.read_pool.clone(),
+                self.config.flags,
+            )?
+            .with_seqno_offset(segment_seqno_offset);
+
+            segments.push(Arc::new(segment));
+        }
+
+        Ok(segments)
+    }
+
+    /// Deletes the segment files that were created by a failed [`Tree::absorb`].
+    fn delete_absorbed_segments(&self, segment_ids: &[SegmentId]) {
+        use crate::file::SEGMENTS_FOLDER;
+
+        let segment_folder_path = self.config.path.join(SEGMENTS_FOLDER);
+
+        for &segment_id in segment_ids {
+            self.config
+                .descriptor_table
+                .remove((self.id, segment_id).into());
+
+            let path = segment_folder_path.join(segment_id.to_string());
+
+            if let Err(e) = std::fs::remove_file(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!(
+                        "Failed to delete orphaned segment file {}: {e:?}",
+                        path.display()
+                    );
+                }
+            }
+        }
+    }
+
+    /// Creates a writer for a new segment in the given level, using the tree's configuration.
+    #[cfg_attr(not(feature = "bloom"), allow(unused_mut, unused_variables))]
+    fn create_segment_writer(
+        &self,
+        segment_id: SegmentId,
+        folder: PathBuf,
+        level: u8,
+    ) -> crate::Result<crate::segment::writer::Writer> {
+        use crate::segment::writer::{Options, Writer};
+
+        let mut segment_writer = Writer::new(Options {
+            segment_id,
+            folder,
+            evict_tombstones: false,
+            data_block_size: self.config.data_block_size,
+            index_block_size: self.config.index_block_size,
+        })?
+        .use_compression(self.compression())
+        .use_cipher(self.config.segment_cipher())
+        .use_sync(self.sync_tracker.sync_on_write())
+        .use_seqno_index(self.config.flags.contains(ConfigFlags::SEQNO_INDEX))
+        .use_prefix_fences(self.config.prefix_fence_len)
+        .use_one_level_index(self.config.one_level_index_max_size)
+        .use_shortened_index_keys(self.config.flags.contains(ConfigFlags::SHORTEN_INDEX_KEYS))
+        .use_value_checksums(self.config.flags.contains(ConfigFlags::VALUE_CHECKSUMS))
+        .use_logical_clock(self.config.deterministic_seed.is_some());
+
+        #[cfg(feature = "bloom")]
+        {
+            if self.config.bloom_bits_per_key >= 0 {
+                segment_writer = segment_writer
+                    .use_bloom_policy(self.config.bloom_policy(level))
+                    .use_bloom_hash_count(self.config.bloom_hash_count);
+            }
+        }
+
+        Ok(segment_writer)
+    }
+
+    /// Inserts many key-value pairs at once.
+    ///
+    /// The items are assigned a contiguous block of seqnos (in iteration order),
+    /// which is allocated from the counter using a single atomic operation,
+    /// and are inserted into the memtable using a single lock acquisition.
+    ///
+    /// All items are checked before any of them is inserted, so
+    /// either the whole batch is inserted, or nothing is.
+    ///
+    /// Returns the new size of the memtable.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if any key is empty, or any key or value
+    /// exceeds the configured maximum sizes.
+    pub fn insert_many<K: Into<UserKey>, V: Into<UserValue>, I: IntoIterator<Item = (K, V)>>(
+        &self,
+        items: I,
+        seqno: &SequenceNumberCounter,
+    ) -> crate::Result<u32> {
+        let items = items
+            .into_iter()
+            .map(|(key, value)| {
+                let key = key.into();
+                let value = value.into();
+                self.check_kv_size(&key, value.len())?;
+                Ok((key, value))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let base_seqno = seqno.fetch_add(items.len() as u64, std::sync::atomic::Ordering::Release);
+
+        let (_, memtable_size) =
+            self.append_entries((base_seqno..).zip(items).map(|(seqno, (key, value))| {
+                InternalValue::from_components(key, value, seqno, ValueType::Value)
+            }));
+
+        Ok(memtable_size)
+    }
+
+    /// Inserts a key-value pair, using group commit.
+    ///
+    /// Concurrent grouped writes are batched: one writer allocates the seqnos
+    /// of the whole group from the counter at once, and inserts the group into
+    /// the memtable using a single lock acquisition, while the other writers wait.
+    /// This improves throughput when many threads insert at the same time.
+    ///
+    /// Returns the seqno assigned to the item and the new size of the memtable.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the key is empty, or the key or value
+    /// exceed the configured maximum sizes.
+    pub fn insert_grouped<K: Into<UserKey>, V: Into<UserValue>>(
+        &self,
+        key: K,
+        value: V,
+        seqno: &SequenceNumberCounter,
+    ) -> crate::Result<(SeqNo, u32)> {
+        let key = key.into();
+        let value = value.into();
+        self.check_kv_size(&key, value.len())?;
+
+        // NOTE: The seqno is assigned by the group leader
+        let item = InternalValue::from_components(key, value, 0, ValueType::Value);
+
+        Ok(self
+            .group_commit
+            .write(item, seqno, |items| self.append_entries(items).1))
+    }
+
+    /// Removes an item, using group commit.
+    ///
+    /// See [`Tree::insert_grouped`].
+    ///
+    /// Returns the seqno assigned to the tombstone and the new size of the memtable.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the key is empty, or exceeds the configured maximum key size.
+    pub fn remove_grouped<K: Into<UserKey>>(
+        &self,
+        key: K,
+        seqno: &SequenceNumberCounter,
+    ) -> crate::Result<(SeqNo, u32)> {
+        let key = key.into();
+        self.check_kv_size(&key, 0)?;
+
+        // NOTE: The seqno is assigned by the group leader
+        let item = InternalValue::new_tombstone(key, 0);
+
+        Ok(self
+            .group_commit
+            .write(item, seqno, |items| self.append_entries(items).1))
+    }
+
+    /// Inserts a key-value pair, checking the key & value sizes first.
+    ///
+    /// Unlike [`AbstractTree::insert`], which panics on empty keys or oversized
+    /// keys & values, this returns an error, so untrusted input cannot abort the process.
+    ///
+    /// Returns the added item's size and new size of the memtable.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the key is empty, or the key or value
+    /// exceed the configured maximum sizes.
+    pub fn try_insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+        &self,
+        key: K,
+        value: V,
+        seqno: SeqNo,
+    ) -> crate::Result<(u32, u32)> {
+        self.check_kv_size(key.as_ref(), value.as_ref().len())?;
+        Ok(self.insert(key, value, seqno))
+    }
+
+    /// Checks the key & value sizes against the configured maximum sizes
+    pub(crate) fn check_kv_size(&self, key: &[u8], value_len: usize) -> crate::Result<()> {
+        if key.is_empty() {
+            return Err(crate::Error::EmptyKey);
+        }
+
+        if key.len() > usize::from(self.config.max_key_size) {
+            return Err(crate::Error::KeyTooLarge(key.len()));
+        }
+
+        // NOTE: u32 always fits into usize on 32-bit and 64-bit platforms
+        if value_len > self.config.max_value_size as usize {
+            return Err(crate::Error::ValueTooLarge(value_len));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the compression that is used for new segments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn compression(&self) -> CompressionType {
+        *self.compression.read().expect("lock is poisoned")
+    }
+
+    /// Sets the compression that is used for subsequent flushes & compactions.
+    ///
+    /// Existing segments are not rewritten, because every segment
+    /// records its own compression, segments of different compressions can be mixed.
+    ///
+    /// The compression is not persisted, so once the tree is reopened, it uses the configured
+    /// compression again (or the compression the tree was created with, see [`Config::compression`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    pub fn set_compression(&self, compression: CompressionType) {
+        log::debug!("Setting compression of tree {} to {compression:?}", self.id);
+        *self.compression.write().expect("lock is poisoned") = compression;
+    }
+
+    /// Estimates the number of distinct keys in the tree's segments (not including memtables).
+    ///
+    /// The estimate is computed from the key sketches of the segments,
+    /// so it is cheap, but may be off by a few percent.
+    /// Segments written by older versions are counted by their key count.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn distinct_key_estimate(&self) -> u64 {
+        distinct_key_estimate(self.read_lock_levels().iter())
+    }
+
+    /// Estimates the number of distinct keys in every level.
+    ///
+    /// See [`Tree::distinct_key_estimate`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn level_distinct_key_estimates(&self) -> Vec<u64> {
+        self.read_lock_levels()
+            .levels
+            .iter()
+            .map(Level::distinct_key_estimate)
+            .collect()
+    }
+
+    /// Returns the number of tombstones in the tree's segments (not including memtables).
+    ///
+    /// The count is taken from the segment metadata, so it is cheap to compute,
+    /// and can be used to decide when to run a cleanup compaction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn tombstone_count(&self) -> u64 {
+        self.read_lock_levels()
+            .iter()
+            .map(|x| x.metadata.tombstone_count)
+            .sum()
+    }
+
+    /// Returns the number of tombstones in every level.
+    ///
+    /// See [`Tree::tombstone_count`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn level_tombstone_counts(&self) -> Vec<u64> {
+        self.read_lock_levels()
+            .levels
+            .iter()
+            .map(Level::tombstone_count)
+            .collect()
+    }
+
+    /// Returns the ratio of tombstones to items in every level,
+    /// which is 0.0 for empty levels.
+    ///
+    /// A high ratio indicates a delete-heavy level, which may be worth compacting.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn level_tombstone_ratios(&self) -> Vec<f64> {
+        self.read_lock_levels()
+            .levels
+            .iter()
+            .map(Level::tombstone_ratio)
+            .collect()
+    }
+
+    /// Returns a human-readable, multi-line description of the tree's shape:
+    /// levels, segment counts & sizes, memtables, block cache usage & hits, and amplification.
+    ///
+    /// The format is meant for humans (e.g. REPLs or debug endpoints) and may change,
+    /// use [`Tree::manifest_json`] for a machine-readable description.
+    #[must_use]
+    pub fn summary(&self) -> String {
+        summary::summarize(self)
+    }
+
+    /// Returns a JSON description of the levels and their segments,
+    /// including key ranges, seqno ranges and sizes.
+    ///
+    /// The format is stable and independent of internal data structures,
+    /// so it can be consumed by external tooling, dashboards or support bundles.
+    /// Keys are encoded as hex strings, and 64-bit or larger integers as decimal strings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn manifest_json(&self) -> String {
+        manifest_json::to_json(&self.read_lock_levels())
+    }
+
+    /// Writes the JSON description of the levels into the given writer.
+    ///
+    /// See [`Tree::manifest_json`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn write_manifest_json<W: std::io::Write>(&self, mut writer: W) -> crate::Result<()> {
+        writer.write_all(self.manifest_json().as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns runtime statistics of every level, counted since the tree was opened.
+    #[must_use]
+    pub fn level_stats(&self) -> Vec<LevelStats> {
+        self.level_stats.snapshot()
+    }
+
+    /// Returns the metadata of all segments whose key range overlaps with the given range,
+    /// grouped by level.
+    ///
+    /// The returned list contains an entry for every level, even if no segment of the level overlaps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let folder = tempfile::tempdir()?;
+    /// use lsm_tree::{AbstractTree, Config};
+    ///
+    /// let tree = Config::new(folder).open()?;
+    /// tree.insert("a", "abc", 0);
+    /// tree.insert("c", "abc", 1);
+    /// tree.flush_active_memtable(0)?;
+    ///
+    /// let levels = tree.segments_overlapping("b".."d");
+    /// assert_eq!(1, levels.iter().map(|level| level.segments.len()).sum::<usize>());
+    ///
+    /// let levels = tree.segments_overlapping("d"..);
+    /// assert!(levels.iter().all(|level| level.segments.is_empty()));
+    /// #
+    /// # Ok::<(), lsm_tree::Error>(())
+    /// ```
+    #[must_use]
+    pub fn segments_overlapping<K: AsRef<[u8]>, R: RangeBounds<K>>(
+        &self,
+        range: R,
+    ) -> Vec<LevelInspection> {
+        let bounds = to_owned_bounds(&range);
+
+        self.read_lock_levels()
+            .levels
+            .iter()
+            .map(|level| LevelInspection {
+                segments: level
+                    .iter()
+                    .filter(|segment| segment.metadata.key_range.overlaps_with_bounds(&bounds))
+                    .map(|segment| segment.metadata.clone())
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Returns `true` if there are some segments that are being compacted.
+    #[doc(hidden)]
+    #[must_use]
+    pub fn is_compacting(&self) -> bool {
+        let levels = self.levels.read().expect("lock is poisoned");
+        levels.is_compacting()
+    }
+
+    /// Write-locks the sealed memtables for exclusive access
+    fn lock_sealed_memtables(&self) -> RwLockWriteGuard<'_, SealedMemtables> {
+        self.sealed_memtables.write().expect("lock is poisoned")
+    }
+
+    /// Write-locks the level manifest for exclusive access
+    fn lock_levels(&self) -> RwLockWriteGuard<'_, LevelManifest> {
+        self.levels.write().expect("lock is poisoned")
+    }
+
+    /// Used for [`BlobTree`] lookup
+    pub(crate) fn get_internal_entry_with_lock<K: AsRef<[u8]>>(
+        &self,
+        memtable_lock: &RwLockWriteGuard<'_, Memtable>,
+        key: K,
+        evict_tombstone: bool,
+        seqno: Option<SeqNo>,
+    ) -> crate::Result<Option<InternalValue>> {
+        if let Some(entry) = memtable_lock.get(&key, seqno) {
+            if evict_tombstone {
+                return Ok(ignore_tombstone_value(entry));
+            }
+            return Ok(Some(entry));
+        };
+
+        // Now look in sealed memtables
+        if let Some(entry) = self.get_internal_entry_from_sealed_memtables(&key, seqno) {
+            if evict_tombstone {
+                return Ok(ignore_tombstone_value(entry));
+            }
+            return Ok(Some(entry));
+        }
+
+        self.get_internal_entry_from_segments(key, evict_tombstone, seqno)
+    }
+
+    fn get_internal_entry_from_sealed_memtables<K: AsRef<[u8]>>(
+        &self,
+        key: K,
+        seqno: Option<SeqNo>,
+    ) -> Option<InternalValue> {
+        let memtable_lock = self.sealed_memtables.read().expect("lock is poisoned");
+
+        for (_, memtable) in memtable_lock.iter().rev() {
+            if let Some(entry) = memtable.get(&key, seqno) {
+                return Some(entry);
+            }
         }
 
         None
@@ -610,49 +2883,194 @@ impl Tree {
         key: K,
         evict_tombstone: bool,
         seqno: Option<SeqNo>,
+    ) -> crate::Result<Option<InternalValue>> {
+        let level_manifest = self.levels.read().expect("lock is poisoned");
+
+        let item =
+            self.get_internal_entry_from_levels(&level_manifest.levels, key.as_ref(), seqno, None)?;
+
+        drop(level_manifest);
+
+        if evict_tombstone {
+            return Ok(item.and_then(ignore_tombstone_value));
+        }
+        Ok(item)
+    }
+
+    /// Looks up the item of a key in the segments of the given levels.
+    ///
+    /// If `last_blocks` is given, the data block every segment read started in is remembered,
+    /// so reading a following key that starts in the same block does not load it again.
+    fn get_internal_entry_from_levels(
+        &self,
+        levels: &[Level],
+        key: &[u8],
+        seqno: Option<SeqNo>,
+        mut last_blocks: Option<&mut LastBlocks>,
     ) -> crate::Result<Option<InternalValue>> {
         // NOTE: Create key hash for hash sharing
         // https://fjall-rs.github.io/post/bloom-filter-hash-sharing/
         #[cfg(feature = "bloom")]
-        let key_hash = crate::bloom::BloomFilter::get_hash(key.as_ref());
+        let key_hash = crate::bloom::BloomFilter::get_hash(key);
 
-        let level_manifest = self.levels.read().expect("lock is poisoned");
+        let record_read = |level_idx: usize, item: &InternalValue| {
+            self.level_stats.record_read(
+                level_idx,
+                (item.key.user_key.len() + item.value.len()) as u64,
+            );
+        };
 
-        for level in &level_manifest.levels {
-            // NOTE: Based on benchmarking, binary search is only worth it after ~4 segments
-            if level.is_disjoint && level.len() >= 5 {
-                if let Some(segment) = level.get_segment_containing_key(&key) {
-                    #[cfg(not(feature = "bloom"))]
-                    let maybe_item = segment.get(&key, seqno)?;
-                    #[cfg(feature = "bloom")]
-                    let maybe_item = segment.get_with_hash(&key, seqno, key_hash)?;
+        // NOTE: A key that is not visible at the read seqno may still exist with a higher seqno,
+        // so a miss is only a bloom filter false positive if every version in the segment is visible
+        #[cfg(feature = "bloom")]
+        let record_bloom_miss = |level_idx: usize, segment: &Segment| {
+            if !seqno.is_some_and(|seqno| segment.metadata.seqnos.1 >= seqno) {
+                self.level_stats.record_bloom_false_positive(level_idx);
+            }
+        };
 
-                    if let Some(item) = maybe_item {
-                        if evict_tombstone {
-                            return Ok(ignore_tombstone_value(item));
-                        }
-                        return Ok(Some(item));
-                    }
+        // NOTE: Reads a segment whose bloom filter (if any) was already probed
+        let mut read_segment = |level_idx: usize, segment: &Segment| {
+            let maybe_item = match last_blocks.as_deref_mut() {
+                Some(last_blocks) => {
+                    let last_block = last_blocks.entry(segment.metadata.id).or_default();
+                    segment.point_read_shared(key, seqno, last_block)?
                 }
+                None => segment.point_read(key, seqno)?,
+            };
+
+            if let Some(item) = &maybe_item {
+                record_read(level_idx, item);
             } else {
-                // NOTE: Fallback to linear search
-                for segment in &level.segments {
-                    #[cfg(not(feature = "bloom"))]
-                    let maybe_item = segment.get(&key, seqno)?;
-                    #[cfg(feature = "bloom")]
-                    let maybe_item = segment.get_with_hash(&key, seqno, key_hash)?;
+                // NOTE: The bloom filter was consulted, but the key was not found
+                #[cfg(feature = "bloom")]
+                record_bloom_miss(level_idx, segment);
+            }
 
-                    if let Some(item) = maybe_item {
-                        if evict_tombstone {
-                            return Ok(ignore_tombstone_value(item));
-                        }
-                        return Ok(Some(item));
+            Ok::<_, crate::Error>(maybe_item)
+        };
+
+        // NOTE: Candidate segments, in read order (newest data first)
+        let candidates = levels.iter().enumerate().flat_map(|(level_idx, level)| {
+            // NOTE: Based on benchmarking, binary search is only worth it after ~4 segments
+            let segments = if level.is_disjoint && level.len() >= 5 {
+                level
+                    .get_segment_containing_key(key)
+                    .map(std::slice::from_ref)
+                    .unwrap_or_default()
+            } else {
+                &level.segments
+            };
+
+            segments
+                .iter()
+                .filter(|segment| segment.metadata.key_range.contains_key(key))
+                .filter(|segment| !seqno.is_some_and(|seqno| segment.metadata.seqnos.0 >= seqno))
+                .map(move |segment| (level_idx, segment))
+        });
+
+        // NOTE: Without a block cache, nothing can be answered from cache
+        let read_cached = self.config.block_cache.capacity() > 0;
+
+        // Phase 1: Try to answer the read from cached blocks only
+        //
+        // A cached item in a deeper level may be returned before segments
+        // in upper levels were read from disk, as long as none of those
+        // segments can contain a newer version of the key.
+        //
+        // Segments that could not be resolved from cache are read in phase 2,
+        // so every bloom filter is probed at most once.
+        let mut unresolved = SmallVec::<[(usize, &Segment); 8]>::new();
+
+        // NOTE: Highest seqno of all segments that could not be resolved from cache
+        let mut unresolved_seqno: Option<SeqNo> = None;
+
+        // NOTE: Item of a cached segment that an unresolved segment may contain a newer version of
+        let mut shadowed = None;
+
+        for (level_idx, segment) in candidates {
+            #[cfg(feature = "bloom")]
+            if segment.bloom_filter_excludes(key_hash) {
+                self.level_stats.record_bloom_negative(level_idx);
+                continue;
+            }
+
+            if !read_cached {
+                if let Some(item) = read_segment(level_idx, segment)? {
+                    return Ok(Some(item));
+                }
+                continue;
+            }
+
+            match segment.get_cached(key, seqno) {
+                CachedRead::Hit(None) => {
+                    #[cfg(feature = "bloom")]
+                    record_bloom_miss(level_idx, segment);
+                }
+                CachedRead::Hit(Some(item)) => {
+                    if unresolved_seqno.is_some_and(|seqno| seqno >= item.key.seqno) {
+                        // NOTE: An unresolved segment may contain a newer version
+                        shadowed = Some((level_idx, item));
+                        break;
                     }
+
+                    record_read(level_idx, &item);
+                    return Ok(Some(item));
                 }
+                CachedRead::Miss => {
+                    let seqno = segment.get_highest_seqno();
+                    unresolved_seqno = Some(unresolved_seqno.map_or(seqno, |x| x.max(seqno)));
+                    unresolved.push((level_idx, segment));
+                }
+            }
+        }
+
+        // Phase 2: Read unresolved segments in order, which may involve disk I/O
+        for (level_idx, segment) in unresolved {
+            if let Some(item) = read_segment(level_idx, segment)? {
+                return Ok(Some(item));
             }
         }
 
-        Ok(None)
+        Ok(shadowed.map(|(level_idx, item)| {
+            record_read(level_idx, &item);
+            item
+        }))
+    }
+
+    /// Retrieves an item, and passes a borrowed view of its value to the callback.
+    ///
+    /// This is a shorthand for readers that only need to inspect the value.
+    /// It reads the same way as [`AbstractTree::get`]: values are reference counted,
+    /// so neither method copies the value out of the memtable or cached data block.
+    ///
+    /// Returns the callback's result, or `None` if the key does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let folder = tempfile::tempdir()?;
+    /// use lsm_tree::{AbstractTree, Config, Tree};
+    ///
+    /// let tree = Config::new(folder).open()?;
+    /// tree.insert("a", "my_value", 0);
+    ///
+    /// let len = tree.get_with("a", <[u8]>::len)?;
+    /// assert_eq!(Some(8), len);
+    /// #
+    /// # Ok::<(), lsm_tree::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn get_with<K: AsRef<[u8]>, R, F: FnOnce(&[u8]) -> R>(
+        &self,
+        key: K,
+        f: F,
+    ) -> crate::Result<Option<R>> {
+        let value = self.get_bytes(key.as_ref(), None)?;
+        Ok(value.map(|value| f(&value)))
     }
 
     #[doc(hidden)]
@@ -703,22 +3121,57 @@ impl Tree {
         seqno: Option<SeqNo>,
         ephemeral: Option<Arc<Memtable>>,
     ) -> impl DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static {
-        use std::ops::Bound::{self, Excluded, Included, Unbounded};
+        let iter = self
+            .create_internal_range(range, seqno, ephemeral)
+            .map(|item| item.map(|kv| (kv.key.user_key, kv.value)));
 
-        let lo: Bound<UserKey> = match range.start_bound() {
-            Included(x) => Included(x.as_ref().into()),
-            Excluded(x) => Excluded(x.as_ref().into()),
-            Unbounded => Unbounded,
-        };
+        #[cfg(feature = "metrics")]
+        let iter = crate::metrics::TimedIter::new(iter, self.latencies.clone());
+
+        iter
+    }
+
+    #[doc(hidden)]
+    #[must_use]
+    pub fn create_range_with_options(
+        &self,
+        bounds: (Bound<UserKey>, Bound<UserKey>),
+        options: &ReadOptions,
+        ephemeral: Option<Arc<Memtable>>,
+    ) -> impl DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static {
+        let iter = self
+            .create_internal_range_with_options(bounds, options, ephemeral)
+            .map(|item| item.map(|kv| (kv.key.user_key, kv.value)));
+
+        #[cfg(feature = "metrics")]
+        let iter = crate::metrics::TimedIter::new(iter, self.latencies.clone());
+
+        iter
+    }
 
-        let hi: Bound<UserKey> = match range.end_bound() {
-            Included(x) => Included(x.as_ref().into()),
-            Excluded(x) => Excluded(x.as_ref().into()),
-            Unbounded => Unbounded,
+    /// Creates a range over the latest visible (non-tombstone) versions of items
+    pub(crate) fn create_internal_range<'a, K: AsRef<[u8]> + 'a, R: RangeBounds<K> + 'a>(
+        &'a self,
+        range: &'a R,
+        seqno: Option<SeqNo>,
+        ephemeral: Option<Arc<Memtable>>,
+    ) -> impl DoubleEndedIterator<Item = crate::Result<InternalValue>> + 'static {
+        let options = ReadOptions {
+            seqno,
+            ..Default::default()
         };
 
-        let bounds: (Bound<UserKey>, Bound<UserKey>) = (lo, hi);
+        self.create_internal_range_with_options(to_owned_bounds(range), &options, ephemeral)
+    }
 
+    /// Creates a range over the latest visible (non-tombstone) versions of items,
+    /// reading segments as configured by the given options
+    fn create_internal_range_with_options(
+        &self,
+        bounds: (Bound<UserKey>, Bound<UserKey>),
+        options: &ReadOptions,
+        ephemeral: Option<Arc<Memtable>>,
+    ) -> TreeIter {
         // NOTE: Mind lock order L -> M -> S
         let level_manifest_lock =
             guardian::ArcRwLockReadGuardian::take(self.levels.clone()).expect("lock is poisoned");
@@ -729,14 +3182,15 @@ impl Tree {
         let sealed = guardian::ArcRwLockReadGuardian::take(self.sealed_memtables.clone())
             .expect("lock is poisoned");
 
-        TreeIter::create_range(
+        TreeIter::create_range_with_options(
             MemtableLockGuard {
                 active,
                 sealed,
                 ephemeral,
+                segments: vec![],
             },
             bounds,
-            seqno,
+            options,
             level_manifest_lock,
         )
     }
@@ -752,14 +3206,229 @@ impl Tree {
         self.create_range(&range, seqno, ephemeral)
     }
 
+    /// Counts the items in the given range.
+    ///
+    /// Disk segments that fall completely inside the range are counted using their
+    /// metadata without any I/O, if no other segment or memtable overlaps them, and they
+    /// contain neither tombstones nor older versions of keys.
+    /// If such a segment is only partially covered by the range, the data blocks it fully
+    /// covers are counted using their item counts, so only the data blocks at the edges
+    /// of the range are loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let folder = tempfile::tempdir()?;
+    /// use lsm_tree::{AbstractTree, Config, Tree};
+    ///
+    /// let tree = Config::new(folder).open()?;
+    ///
+    /// tree.insert("a", "abc", 0);
+    /// tree.insert("f", "abc", 1);
+    /// tree.insert("g", "abc", 2);
+    /// assert_eq!(2, tree.range_len("a"..="f")?);
+    /// #
+    /// # Ok::<(), lsm_tree::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn range_len<K: AsRef<[u8]>, R: RangeBounds<K>>(&self, range: R) -> crate::Result<usize> {
+        let bounds = to_owned_bounds(&range);
+
+        let mut count = 0;
+        let mut lo = Some(bounds.0.clone());
+
+        // NOTE: Countable segments are disjoint, so we only need to scan the gaps between them
+        for segment in self.get_countable_segments(&bounds) {
+            let (min, max) = &*segment.metadata.key_range;
+
+            // NOTE: Only the first segment may start before the range
+            if let Some(lo) = lo.take().filter(|_| bounds.contains(min)) {
+                count += self.count_keys((lo, Bound::Excluded(min.clone())))?;
+            }
+
+            count += segment.range_len(&bounds)?;
+
+            // NOTE: Only the last segment may end after the range
+            lo = bounds.contains(max).then(|| Bound::Excluded(max.clone()));
+        }
+
+        if let Some(lo) = lo {
+            count += self.count_keys((lo, bounds.1))?;
+        }
+
+        Ok(count)
+    }
+
+    /// Counts the items with the given prefix.
+    ///
+    /// See [`Tree::range_len`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let folder = tempfile::tempdir()?;
+    /// use lsm_tree::{AbstractTree, Config, Tree};
+    ///
+    /// let tree = Config::new(folder).open()?;
+    ///
+    /// tree.insert("a", "abc", 0);
+    /// tree.insert("ab", "abc", 1);
+    /// tree.insert("abc", "abc", 2);
+    /// assert_eq!(2, tree.prefix_len("ab")?);
+    /// #
+    /// # Ok::<(), lsm_tree::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn prefix_len<K: AsRef<[u8]>>(&self, prefix: K) -> crate::Result<usize> {
+        self.range_len(prefix_to_range(prefix.as_ref()))
+    }
+
+    /// Counts the latest visible items in the given range, by scanning them.
+    fn count_keys(&self, bounds: (Bound<UserKey>, Bound<UserKey>)) -> crate::Result<usize> {
+        let mut count = 0;
+
+        for item in self.create_internal_range(&bounds, None, None) {
+            let _ = item?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Returns the segments overlapping the range that can be counted using their metadata,
+    /// sorted by key.
+    ///
+    /// A segment can be counted, if it contains every item of its key range exactly once:
+    /// no tombstones, no older versions, and no other segment or memtable overlapping it.
+    fn get_countable_segments(
+        &self,
+        bounds: &(Bound<UserKey>, Bound<UserKey>),
+    ) -> Vec<Arc<Segment>> {
+        // NOTE: Mind lock order L -> M -> S
+        let levels = self.read_lock_levels();
+        let active = self.read_lock_active_memtable();
+        let sealed = self.read_lock_sealed_memtables();
+
+        let mut segments = levels.iter().cloned().collect::<Vec<_>>();
+        drop(levels);
+
+        segments.sort_by(|a, b| a.metadata.key_range.0.cmp(&b.metadata.key_range.0));
+
+        let mut countable = vec![];
+
+        // NOTE: Highest max key of all segments before the current one
+        let mut prev_max: Option<&UserKey> = None;
+
+        for (idx, segment) in segments.iter().enumerate() {
+            let key_range = &segment.metadata.key_range;
+            let (min, max) = &**key_range;
+
+            let overlaps_prev = prev_max.is_some_and(|prev_max| prev_max >= min);
+            let overlaps_next = segments
+                .get(idx + 1)
+                .is_some_and(|next| next.metadata.key_range.0 <= *max);
+
+            prev_max = prev_max.max(Some(max));
+
+            if overlaps_prev || overlaps_next {
+                continue;
+            }
+
+            if segment.metadata.tombstone_count > 0
+                || segment.metadata.item_count != segment.metadata.key_count
+            {
+                continue;
+            }
+
+            if !key_range.overlaps_with_bounds(bounds) {
+                continue;
+            }
+
+            if active.overlaps_key_range(key_range)
+                || sealed
+                    .iter()
+                    .any(|(_, memtable)| memtable.overlaps_key_range(key_range))
+            {
+                continue;
+            }
+
+            countable.push(segment.clone());
+        }
+
+        drop(sealed);
+        drop(active);
+
+        countable
+    }
+
     /// Adds an item to the active memtable.
     ///
     /// Returns the added item's size and new size of the memtable.
     #[doc(hidden)]
     #[must_use]
     pub fn append_entry(&self, value: InternalValue) -> (u32, u32) {
-        let memtable_lock = self.active_memtable.read().expect("lock is poisoned");
-        memtable_lock.insert(value)
+        let (item_size, memtable_size) = self.lock_active_memtable_for_writes().insert(value);
+
+        self.write_stats.record_ingest(item_size.into());
+
+        if let Some(sink) = &self.config.metrics_sink {
+            sink.counter(metrics::BYTES_WRITTEN, item_size.into());
+        }
+
+        (item_size, memtable_size)
+    }
+
+    /// Adds items to the active memtable, acquiring the memtable lock only once.
+    ///
+    /// Returns the added items' total size and new size of the memtable.
+    #[doc(hidden)]
+    pub fn append_entries<I: IntoIterator<Item = InternalValue>>(&self, values: I) -> (u64, u32) {
+        let memtable_lock = self.lock_active_memtable_for_writes();
+
+        let mut total_size = 0;
+        let mut memtable_size = memtable_lock.size();
+
+        for value in values {
+            let (item_size, size) = memtable_lock.insert(value);
+            total_size += u64::from(item_size);
+            memtable_size = size;
+        }
+        drop(memtable_lock);
+
+        self.write_stats.record_ingest(total_size);
+
+        if let Some(sink) = &self.config.metrics_sink {
+            sink.counter(metrics::BYTES_WRITTEN, total_size);
+        }
+
+        (total_size, memtable_size)
+    }
+
+    /// Read locks the active memtable for writing into it, recording write stalls
+    fn lock_active_memtable_for_writes(&self) -> RwLockReadGuard<'_, Memtable> {
+        if let Ok(lock) = self.active_memtable.try_read() {
+            return lock;
+        }
+
+        // NOTE: The active memtable is write locked (e.g. being rotated), so the write stalls
+        let start = Instant::now();
+        let lock = self.read_lock_active_memtable();
+
+        if let Some(sink) = &self.config.metrics_sink {
+            sink.counter(metrics::WRITE_STALLS, 1);
+            sink.histogram(
+                metrics::WRITE_STALL_DURATION_MICROS,
+                metrics::micros(start.elapsed()),
+            );
+        }
+
+        lock
     }
 
     /// Recovers previous state, by loading the level manifest and segments.
@@ -768,7 +3437,10 @@ impl Tree {
     ///
     /// Returns error, if an IO error occurred.
     fn recover(mut config: Config) -> crate::Result<Self> {
-        use crate::file::MANIFEST_FILE;
+        use crate::{
+            compaction::job_manifest::JobManifest,
+            file::{COMPACTIONS_FOLDER, MANIFEST_FILE},
+        };
         use inner::get_next_tree_id;
 
         log::info!("Recovering LSM-tree at {:?}", config.path);
@@ -782,34 +3454,48 @@ impl Tree {
         }
 
         // IMPORTANT: Restore persisted config
-        config.level_count = manifest.level_count;
-        config.table_type = manifest.table_type;
-        config.tree_type = manifest.tree_type;
+        manifest.restore_config(&mut config)?;
 
         let tree_id = get_next_tree_id();
+        let read_pool = Arc::new(ReadPool::new(config.read_ahead_threads));
 
-        let mut levels = Self::recover_levels(
-            &config.path,
-            tree_id,
-            &config.block_cache,
-            &config.descriptor_table,
-        )?;
+        let mut levels = Self::recover_levels(&config, tree_id, &read_pool)?;
         levels.sort_levels();
 
+        // NOTE: Outputs of unfinished compactions may have higher IDs than all registered segments
+        let resumable_segment_ids = JobManifest::load_all(&config.path.join(COMPACTIONS_FOLDER))?
+            .into_iter()
+            .flat_map(|job| job.outputs);
+
         let highest_segment_id = levels
             .iter()
             .map(|x| x.metadata.id)
+            .chain(resumable_segment_ids)
             .max()
             .unwrap_or_default();
 
         let inner = TreeInner {
             id: tree_id,
             segment_id_counter: Arc::new(AtomicU64::new(highest_segment_id + 1)),
+            level_stats: Arc::new(LevelStatsTracker::new(levels.depth())),
             active_memtable: Arc::default(),
             sealed_memtables: Arc::default(),
             levels: Arc::new(RwLock::new(levels)),
             stop_signal: StopSignal::default(),
+            ops_log: OpsLog::from_config(&config).map(Arc::new),
+            compression: RwLock::new(config.compression),
+            sync_tracker: Arc::new(SyncTracker::new(config.sync_mode)),
+            read_pool,
             config,
+            write_stats: Arc::default(),
+            group_commit: group_commit::GroupCommit::default(),
+            weak_tombstone_violations: AtomicU64::default(),
+            snapshots: Arc::default(),
+            pinned_ranges: Mutex::default(),
+            scan_limiter: Arc::default(),
+            uuid: manifest.uuid,
+            #[cfg(feature = "metrics")]
+            latencies: Arc::default(),
         };
 
         Ok(Self(Arc::new(inner)))
@@ -833,45 +3519,44 @@ impl Tree {
 
         // NOTE: Lastly, fsync version marker, which contains the version
         // -> the LSM is fully initialized
+        let manifest = Manifest::new(&config);
+
         let mut file = File::create(manifest_path)?;
-        Manifest {
-            version: Version::V2,
-            level_count: config.level_count,
-            tree_type: config.tree_type,
-            table_type: TableType::Block,
-        }
-        .encode_into(&mut file)?;
+        manifest.encode_into(&mut file)?;
         file.sync_all()?;
 
         // IMPORTANT: fsync folders on Unix
         fsync_directory(&segment_folder_path)?;
         fsync_directory(&path)?;
 
-        let inner = TreeInner::create_new(config)?;
+        let inner = TreeInner::create_new(config, manifest.uuid)?;
         Ok(Self(Arc::new(inner)))
     }
 
     /// Recovers the level manifest, loading all segments from disk.
-    fn recover_levels<P: AsRef<Path>>(
-        tree_path: P,
+    fn recover_levels(
+        config: &Config,
         tree_id: TreeId,
-        block_cache: &Arc<BlockCache>,
-        descriptor_table: &Arc<FileDescriptorTable>,
+        read_pool: &Arc<ReadPool>,
     ) -> crate::Result<LevelManifest> {
         use crate::{
+            compaction::job_manifest::JobManifest,
             file::fsync_directory,
-            file::{LEVELS_MANIFEST_FILE, SEGMENTS_FOLDER},
+            file::{COMPACTIONS_FOLDER, LEVELS_MANIFEST_FILE, SEGMENTS_FOLDER},
             SegmentId,
         };
 
-        let tree_path = tree_path.as_ref();
+        let tree_path = &config.path;
         log::debug!("Recovering disk segments from {tree_path:?}");
 
         let level_manifest_path = tree_path.join(LEVELS_MANIFEST_FILE);
 
         let segment_ids_to_recover = LevelManifest::recover_ids(&level_manifest_path)?;
+        let seqno_offsets = LevelManifest::load_seqno_offsets(&level_manifest_path)?;
 
-        let mut segments = vec![];
+        // NOTE: Outputs of unfinished compactions are kept, so the compactions can be resumed
+        let resumable_segment_ids =
+            JobManifest::recover(&tree_path.join(COMPACTIONS_FOLDER), &segment_ids_to_recover)?;
 
         let segment_base_folder = tree_path.join(SEGMENTS_FOLDER);
 
@@ -880,6 +3565,8 @@ impl Tree {
             fsync_directory(&segment_base_folder)?;
         }
 
+        let mut segment_files = vec![];
+
         for dirent in std::fs::read_dir(&segment_base_folder)? {
             let dirent = dirent?;
 
@@ -899,31 +3586,51 @@ impl Tree {
                 continue;
             }
 
-            log::debug!("Recovering segment from {segment_file_path:?}");
-
             let segment_id = segment_file_name.parse::<SegmentId>().map_err(|e| {
                 log::error!("invalid segment file name {segment_file_name:?}: {e:?}");
                 crate::Error::Unrecoverable
             })?;
 
             if segment_ids_to_recover.contains(&segment_id) {
-                let segment = Segment::recover(
-                    &segment_file_path,
-                    tree_id,
-                    block_cache.clone(),
-                    descriptor_table.clone(),
-                )?;
-
-                descriptor_table.insert(&segment_file_path, (tree_id, segment.metadata.id).into());
-
-                segments.push(Arc::new(segment));
-                log::debug!("Recovered segment from {segment_file_path:?}");
+                segment_files.push((segment_id, segment_file_path));
+            } else if resumable_segment_ids.contains(&segment_id) {
+                log::debug!(
+                    "Keeping output of unfinished compaction: {}",
+                    segment_file_path.display()
+                );
             } else {
                 log::debug!("Deleting unfinished segment: {segment_file_path:?}",);
                 std::fs::remove_file(&segment_file_path)?;
             }
         }
 
+        let recover_segment = |(segment_id, segment_file_path): &(SegmentId, PathBuf)| {
+            log::debug!("Recovering segment from {}", segment_file_path.display());
+
+            let segment = Segment::recover(
+                segment_file_path,
+                *segment_id,
+                tree_id,
+                config.block_cache.clone(),
+                config.descriptor_table.clone(),
+                config.cipher(),
+                config.metrics_sink.clone(),
+                config.read_sampler.clone(),
+                config.block_readahead,
+                config.readahead_bytes,
+                read_pool.clone(),
+                config.flags,
+            )?
+            .with_seqno_offset(seqno_offsets.get(segment_id).copied().unwrap_or_default());
+
+            log::debug!("Recovered segment from {}", segment_file_path.display());
+
+            Ok::<_, crate::Error>(Arc::new(segment))
+        };
+
+        let threads = config.recovery_threads.clamp(1, segment_files.len().max(1));
+        let segments = Self::recover_segment_files(&segment_files, threads, recover_segment)?;
+
         if segments.len() < segment_ids_to_recover.len() {
             log::error!("Expected segments: {segment_ids_to_recover:?}");
             return Err(crate::Error::Unrecoverable);
@@ -931,6 +3638,69 @@ impl Tree {
 
         log::debug!("Recovered {} segments", segments.len());
 
-        LevelManifest::recover(&level_manifest_path, segments)
+        let levels = LevelManifest::recover(&level_manifest_path, segments)?;
+
+        // NOTE: The cold status of segment files is not persisted, so recompute it
+        // the same way compactions do, see `FileDescriptorTable::insert_cold_with_cipher`
+        if let Some(last_level) = levels.levels.last() {
+            for segment in &last_level.segments {
+                config
+                    .descriptor_table
+                    .set_cold((tree_id, segment.metadata.id).into(), true);
+            }
+        }
+
+        Ok(levels)
+    }
+
+    /// Recovers the given segment files, spreading them over the given amount of threads.
+    fn recover_segment_files<F>(
+        segment_files: &[(SegmentId, PathBuf)],
+        threads: usize,
+        recover_segment: F,
+    ) -> crate::Result<Vec<Arc<Segment>>>
+    where
+        F: Fn(&(SegmentId, PathBuf)) -> crate::Result<Arc<Segment>> + Sync,
+    {
+        if threads == 1 {
+            return segment_files.iter().map(recover_segment).collect();
+        }
+
+        log::debug!(
+            "Recovering {} segments using {threads} threads",
+            segment_files.len()
+        );
+
+        let chunk_size = segment_files.len().div_ceil(threads);
+        let recover_segment = &recover_segment;
+
+        std::thread::scope(|scope| -> crate::Result<Vec<_>> {
+            let mut handles = Vec::with_capacity(threads);
+
+            for chunk in segment_files.chunks(chunk_size) {
+                let handle = std::thread::Builder::new()
+                    .name("lsm-recovery".into())
+                    .spawn_scoped(scope, move || {
+                        chunk
+                            .iter()
+                            .map(recover_segment)
+                            .collect::<crate::Result<Vec<_>>>()
+                    })?;
+
+                handles.push(handle);
+            }
+
+            let mut segments = Vec::with_capacity(segment_files.len());
+
+            for handle in handles {
+                segments.extend(
+                    handle
+                        .join()
+                        .unwrap_or_else(|e| std::panic::resume_unwind(e))?,
+                );
+            }
+
+            Ok(segments)
+        })
     }
 }
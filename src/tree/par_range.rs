@@ -0,0 +1,140 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::{level_manifest::LevelManifest, KvPair, UserKey};
+use std::{
+    collections::VecDeque,
+    ops::{Bound, RangeBounds},
+    sync::mpsc::Receiver,
+};
+
+/// Maximum amount of items buffered per partition, before its scan thread is paused
+pub const CHANNEL_CAPACITY: usize = 1_024;
+
+/// Order in which the items of a parallel scan are returned
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ScanOrder {
+    /// Items are returned in key order
+    ///
+    /// Partitions are still scanned in parallel, but the results of
+    /// later partitions are buffered until the previous partitions are consumed.
+    Sorted,
+
+    /// Items are returned as soon as any partition produces them
+    Unsorted,
+}
+
+/// Iterator over the results of a parallel range scan
+///
+/// See [`crate::Tree::par_range`].
+#[allow(clippy::module_name_repetitions)]
+pub struct ParRange {
+    pub(crate) receivers: VecDeque<Receiver<crate::Result<KvPair>>>,
+}
+
+impl Iterator for ParRange {
+    type Item = crate::Result<KvPair>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Ok(item) = self.receivers.front()?.recv() {
+                return Some(item);
+            }
+
+            // NOTE: Partition is scanned through, continue with the next one
+            self.receivers.pop_front();
+        }
+    }
+}
+
+/// Splits the range into (at most) the given amount of partitions of roughly equal size
+///
+/// Partitions are cut at index block boundaries of the segments that overlap with the range,
+/// so every partition covers about the same amount of data blocks.
+pub fn partition_range(
+    levels: &LevelManifest,
+    bounds: &(Bound<UserKey>, Bound<UserKey>),
+    partitions: usize,
+) -> Vec<(Bound<UserKey>, Bound<UserKey>)> {
+    let mut keys = levels
+        .iter()
+        .filter(|segment| segment.check_key_range_overlap(bounds))
+        .flat_map(|segment| {
+            // NOTE: Partitioning is only a heuristic, if the TLI can not be loaded,
+            // the scan itself will return the error
+            segment
+                .block_index
+                .top_level_index()
+                .map(|tli| {
+                    tli.iter()
+                        .map(|handle| handle.end_key.clone())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        })
+        .filter(|key| bounds.contains(key))
+        .collect::<Vec<_>>();
+
+    keys.sort();
+    keys.dedup();
+
+    let mut split_keys = (1..partitions)
+        .filter_map(|idx| keys.get(idx * keys.len() / partitions).cloned())
+        .collect::<Vec<_>>();
+    split_keys.dedup();
+
+    let mut ranges = Vec::with_capacity(split_keys.len() + 1);
+    let mut lo = bounds.0.clone();
+
+    for key in split_keys {
+        ranges.push((lo, Bound::Included(key.clone())));
+        lo = Bound::Excluded(key);
+    }
+
+    ranges.push((lo, bounds.1.clone()));
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbstractTree;
+    use test_log::test;
+
+    #[test]
+    fn par_range_partition_range() -> crate::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let tree = crate::Config::new(&tempdir)
+            .data_block_size(1_024)
+            .index_block_size(1_024)
+            .open()?;
+
+        for x in 0u64..10_000 {
+            tree.insert(x.to_be_bytes(), "abc", 0);
+        }
+        tree.flush_active_memtable(0)?;
+
+        let levels = tree.levels.read().expect("lock is poisoned");
+
+        let unbounded = (Bound::Unbounded, Bound::Unbounded);
+
+        let ranges = partition_range(&levels, &unbounded, 4);
+        assert_eq!(4, ranges.len());
+        assert_eq!(Some(&Bound::Unbounded), ranges.first().map(|(lo, _)| lo));
+        assert_eq!(Some(&Bound::Unbounded), ranges.last().map(|(_, hi)| hi));
+
+        for window in ranges.windows(2) {
+            if let [(_, Bound::Included(a)), (Bound::Excluded(b), _)] = window {
+                assert_eq!(a, b);
+            } else {
+                panic!("partitions should be adjacent");
+            }
+        }
+
+        assert_eq!(1, partition_range(&levels, &unbounded, 1).len());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::Tree;
+use crate::{
+    block_cache::BlockCache, range::to_owned_bounds, segment::id::GlobalSegmentId, UserKey,
+};
+use std::{
+    collections::HashMap,
+    ops::{Bound, RangeBounds},
+    sync::MutexGuard,
+};
+
+/// A key range whose blocks are pinned in the block cache
+pub struct PinnedRange {
+    bounds: (Bound<UserKey>, Bound<UserKey>),
+
+    /// Pinned blocks as (segment ID, offset, weight)
+    pub(crate) blocks: Vec<(GlobalSegmentId, u64, u64)>,
+}
+
+/// Releases the pins of the given blocks.
+pub fn release(block_cache: &BlockCache, blocks: &[(GlobalSegmentId, u64, u64)]) {
+    for &(segment_id, offset, _) in blocks {
+        block_cache.unpin(segment_id, offset);
+    }
+}
+
+impl Tree {
+    /// Loads the blocks of the given key range into the block cache,
+    /// and pins them, so they are exempt from eviction (e.g. for a small, hot configuration prefix).
+    ///
+    /// Only blocks of the disk segments that exist at the time of pinning are pinned.
+    /// After flushes & compactions, the range can be pinned again, which releases
+    /// the blocks that were previously pinned for the same range.
+    /// Blocks of segments that are dropped are unpinned automatically.
+    ///
+    /// Pinned blocks count towards the block cache capacity, so only small ranges should be pinned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an IO error occurs.
+    pub fn pin_range<K: AsRef<[u8]>, R: RangeBounds<K>>(&self, range: R) -> crate::Result<()> {
+        let bounds = to_owned_bounds(&range);
+
+        let mut blocks = vec![];
+
+        {
+            // NOTE: Hold the level manifest lock so no segment file
+            // can be deleted by a compaction while it is read
+            let levels = self.read_lock_levels();
+
+            for segment in levels.iter() {
+                if let Err(e) = segment.pin_range(&bounds, &mut blocks) {
+                    release(&self.config.block_cache, &blocks);
+                    return Err(e);
+                }
+            }
+        }
+
+        log::debug!("Pinned {} blocks of range {bounds:?}", blocks.len());
+
+        self.unpin_range_bounds(&bounds);
+
+        self.lock_pinned_ranges()
+            .push(PinnedRange { bounds, blocks });
+
+        Ok(())
+    }
+
+    /// Releases the blocks that were pinned for the given range, see [`Tree::pin_range`].
+    ///
+    /// The range needs to be the same as the one that was pinned.
+    ///
+    /// Returns `true` if the range was pinned.
+    pub fn unpin_range<K: AsRef<[u8]>, R: RangeBounds<K>>(&self, range: R) -> bool {
+        self.unpin_range_bounds(&to_owned_bounds(&range))
+    }
+
+    /// Locks the pinned ranges
+    fn lock_pinned_ranges(&self) -> MutexGuard<'_, Vec<PinnedRange>> {
+        self.pinned_ranges.lock().expect("lock is poisoned")
+    }
+
+    fn unpin_range_bounds(&self, bounds: &(Bound<UserKey>, Bound<UserKey>)) -> bool {
+        let mut pinned_ranges = self.lock_pinned_ranges();
+
+        let Some(idx) = pinned_ranges.iter().position(|x| &x.bounds == bounds) else {
+            return false;
+        };
+        let range = pinned_ranges.remove(idx);
+        drop(pinned_ranges);
+
+        release(&self.config.block_cache, &range.blocks);
+
+        true
+    }
+
+    /// Returns the amount of bytes of blocks that are pinned by this tree.
+    ///
+    /// Blocks that are pinned by multiple overlapping ranges are only counted once.
+    /// Blocks of segments that have been dropped (e.g. after compaction) are not counted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn pinned_bytes(&self) -> u64 {
+        let pinned_ranges = self.lock_pinned_ranges();
+
+        pinned_ranges
+            .iter()
+            .flat_map(|range| range.blocks.iter())
+            .filter(|&&(segment_id, offset, _)| {
+                self.config.block_cache.is_pinned(segment_id, offset)
+            })
+            .map(|&(segment_id, offset, weight)| ((segment_id, offset), weight))
+            .collect::<HashMap<_, _>>()
+            .values()
+            .sum()
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::Tree;
+use crate::{KvPair, UserKey};
+use std::{
+    ops::Bound,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Limits the amount of expensive scans that can run concurrently
+#[derive(Default)]
+pub struct ScanLimiter {
+    /// Amount of running scans
+    active: Mutex<usize>,
+
+    released: Condvar,
+}
+
+impl ScanLimiter {
+    /// Locks the amount of running scans
+    fn lock_active(&self) -> MutexGuard<'_, usize> {
+        self.active.lock().expect("lock is poisoned")
+    }
+
+    /// Waits until less than `max` scans are running, and registers a new scan,
+    /// which stays registered until the returned permit is dropped.
+    ///
+    /// Returns `None` if the timeout expired.
+    pub fn acquire(self: &Arc<Self>, max: usize, timeout: Duration) -> Option<ScanPermit> {
+        // NOTE: A timeout too large to be represented as deadline never expires
+        let deadline = Instant::now().checked_add(timeout);
+
+        let mut active = self.lock_active();
+
+        while *active >= max {
+            let Some(deadline) = deadline else {
+                active = self.released.wait(active).expect("lock is poisoned");
+                continue;
+            };
+
+            let remaining = deadline.checked_duration_since(Instant::now())?;
+
+            let (guard, result) = self
+                .released
+                .wait_timeout(active, remaining)
+                .expect("lock is poisoned");
+            active = guard;
+
+            if result.timed_out() && *active >= max {
+                return None;
+            }
+        }
+
+        *active += 1;
+        drop(active);
+
+        Some(ScanPermit {
+            limiter: self.clone(),
+        })
+    }
+
+    /// Returns the amount of running scans.
+    pub fn active(&self) -> usize {
+        *self.lock_active()
+    }
+
+    fn release(&self) {
+        let mut active = self.lock_active();
+        *active -= 1;
+        drop(active);
+
+        self.released.notify_one();
+    }
+}
+
+/// Keeps a scan registered for as long as it is alive
+pub struct ScanPermit {
+    limiter: Arc<ScanLimiter>,
+}
+
+impl Drop for ScanPermit {
+    fn drop(&mut self) {
+        self.limiter.release();
+    }
+}
+
+/// Iterator that keeps its scan registered until it is dropped
+pub struct ThrottledIter<I> {
+    inner: I,
+
+    #[allow(unused)]
+    permit: ScanPermit,
+}
+
+impl<I: Iterator> Iterator for ThrottledIter<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<I: DoubleEndedIterator> DoubleEndedIterator for ThrottledIter<I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl Tree {
+    /// Returns the amount of expensive scans that are currently running,
+    /// if the amount of concurrent expensive scans is limited, see [`crate::Config::max_concurrent_scans`].
+    #[must_use]
+    pub fn active_scan_count(&self) -> usize {
+        self.scan_limiter.active()
+    }
+
+    /// Estimates the amount of bytes of segment files that are read when scanning the given range,
+    /// see [`crate::Config::scan_throttle_threshold`].
+    fn estimate_range_size(&self, bounds: &(Bound<UserKey>, Bound<UserKey>)) -> crate::Result<u64> {
+        let levels = self.read_lock_levels();
+
+        levels
+            .iter()
+            .map(|segment| segment.estimate_range_size(bounds))
+            .sum()
+    }
+
+    /// Registers the given iterator as expensive scan, if it is estimated to read
+    /// more than the scan throttle threshold, and the amount of concurrent expensive scans is limited.
+    ///
+    /// If no other expensive scan finishes before the queue timeout expires,
+    /// the returned iterator only yields an error.
+    pub(crate) fn throttle_scan<I>(
+        &self,
+        bounds: &(Bound<UserKey>, Bound<UserKey>),
+        create_iter: impl FnOnce() -> I,
+    ) -> Box<dyn DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static>
+    where
+        I: DoubleEndedIterator<Item = crate::Result<KvPair>> + 'static,
+    {
+        let max = self.config.max_concurrent_scans;
+
+        if max == 0 {
+            return Box::new(create_iter());
+        }
+
+        match self.estimate_range_size(bounds) {
+            Ok(size) if size < self.config.scan_throttle_threshold => {
+                return Box::new(create_iter());
+            }
+            Ok(_) => {}
+            Err(e) => return Box::new(std::iter::once(Err(e))),
+        }
+
+        let Some(permit) = self
+            .scan_limiter
+            .acquire(max, self.config.scan_queue_timeout)
+        else {
+            log::debug!("Scan of {bounds:?} timed out waiting for {max} running scans");
+            return Box::new(std::iter::once(Err(crate::Error::ScanQueueTimeout)));
+        };
+
+        Box::new(ThrottledIter {
+            inner: create_iter(),
+            permit,
+        })
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::Tree;
+use crate::{
+    file::SEGMENTS_FOLDER,
+    level_manifest::LevelManifest,
+    segment::{meta::SegmentId, Segment},
+};
+use std::{path::PathBuf, sync::Arc};
+
+/// Shared reference to the segment files of a tree
+///
+/// As long as the reference is alive, the segment files are not deleted,
+/// even if their segments are compacted away, so they can safely be shared
+/// (e.g. hard linked by [`Tree::fork`], or into another tree by [`Tree::absorb`]).
+/// Files of segments that were removed in the meantime are deleted
+/// once their last reference is dropped.
+///
+/// References are not persisted, so after reopening the tree,
+/// files that are not part of the tree anymore are cleaned up.
+///
+/// Files of segments that were absorbed with a seqno offset (see [`Tree::absorb`])
+/// still contain the seqnos of the absorbed tree.
+pub struct SegmentFiles {
+    folder: PathBuf,
+    segment_ids: Vec<SegmentId>,
+
+    /// Keeps the segments alive, so their files are not deleted
+    segments: Vec<Arc<Segment>>,
+}
+
+impl SegmentFiles {
+    /// Returns the IDs of the referenced segments.
+    #[must_use]
+    pub fn segment_ids(&self) -> &[SegmentId] {
+        &self.segment_ids
+    }
+
+    /// Returns the referenced segments.
+    pub(crate) fn segments(&self) -> &[Arc<Segment>] {
+        &self.segments
+    }
+
+    /// Returns the paths of the referenced segment files.
+    pub fn paths(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.segment_ids
+            .iter()
+            .map(|id| self.folder.join(id.to_string()))
+    }
+}
+
+impl Tree {
+    /// Acquires a reference to the files of all segments that currently exist in the tree.
+    ///
+    /// The files are not deleted before the returned reference is dropped,
+    /// even if their segments are removed from the tree in the meantime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    #[must_use]
+    pub fn acquire_segment_files(&self) -> SegmentFiles {
+        self.acquire_segment_files_locked(&self.read_lock_levels())
+    }
+
+    /// Acquires a reference to the files of all segments in the given levels,
+    /// which need to be the locked levels of this tree.
+    pub(crate) fn acquire_segment_files_locked(&self, levels: &LevelManifest) -> SegmentFiles {
+        let segments = levels.iter().cloned().collect::<Vec<_>>();
+
+        let segment_ids = segments
+            .iter()
+            .map(|segment| segment.metadata.id)
+            .collect::<Vec<_>>();
+
+        SegmentFiles {
+            folder: self.config.path.join(SEGMENTS_FOLDER),
+            segment_ids,
+            segments,
+        }
+    }
+}
@@ -0,0 +1,252 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::Tree;
+use crate::{InternalValue, Segment, SeqNo};
+use std::{
+    collections::VecDeque,
+    ops::{Bound, RangeBounds},
+    sync::Arc,
+};
+
+/// A segment or the (captured) memtable items, and the seqno range they cover
+enum Source {
+    Segment(Arc<Segment>),
+    Memtables(Vec<InternalValue>),
+}
+
+/// Sources with overlapping seqno ranges, which need to be sorted together
+struct Run {
+    sources: Vec<Source>,
+    seqnos: (SeqNo, SeqNo),
+}
+
+/// Yields items in seqno order, by sorting one run of overlapping sources at a time
+///
+/// Runs do not overlap, so concatenating them keeps the seqno order.
+struct SeqnoOrderedIter {
+    runs: VecDeque<Run>,
+    seqnos: (SeqNo, SeqNo),
+    buffer: std::vec::IntoIter<InternalValue>,
+}
+
+impl SeqnoOrderedIter {
+    fn new(mut sources: Vec<(Source, (SeqNo, SeqNo))>, seqnos: (SeqNo, SeqNo)) -> Self {
+        sources.sort_by_key(|(_, (lo, _))| *lo);
+
+        let mut runs = VecDeque::<Run>::new();
+
+        for (source, (lo, hi)) in sources {
+            match runs.back_mut() {
+                Some(run) if lo <= run.seqnos.1 => {
+                    run.seqnos.1 = run.seqnos.1.max(hi);
+                    run.sources.push(source);
+                }
+                _ => runs.push_back(Run {
+                    sources: vec![source],
+                    seqnos: (lo, hi),
+                }),
+            }
+        }
+
+        Self {
+            runs,
+            seqnos,
+            buffer: Vec::new().into_iter(),
+        }
+    }
+
+    fn load_run(&self, run: Run) -> crate::Result<Vec<InternalValue>> {
+        let (lo, hi) = self.seqnos;
+
+        let mut items = vec![];
+
+        for source in run.sources {
+            match source {
+                Source::Segment(segment) => {
+                    for item in segment.changes_since(lo) {
+                        let item = item?;
+
+                        if item.key.seqno <= hi {
+                            items.push(item);
+                        }
+                    }
+                }
+                Source::Memtables(memtable_items) => items.extend(memtable_items),
+            }
+        }
+
+        // NOTE: Items of the same batch share their seqno, so order them by key
+        items.sort_by(|a, b| (a.key.seqno, &a.key.user_key).cmp(&(b.key.seqno, &b.key.user_key)));
+
+        Ok(items)
+    }
+}
+
+impl Iterator for SeqnoOrderedIter {
+    type Item = crate::Result<InternalValue>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.buffer.next() {
+                return Some(Ok(item));
+            }
+
+            let run = self.runs.pop_front()?;
+
+            match self.load_run(run) {
+                Ok(items) => self.buffer = items.into_iter(),
+                Err(e) => {
+                    self.runs.clear();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+impl Tree {
+    /// Returns all items (including tombstones) that were written with a seqno
+    /// in the given range, ordered by seqno (and key, for items of the same batch).
+    ///
+    /// Unlike [`Tree::changes_since`], the items are returned in the order they were written,
+    /// so they can be replayed (e.g. by a replica, or into an audit trail) without a journal.
+    ///
+    /// Segments (and memtables) whose seqno ranges overlap are read and sorted together,
+    /// so the items of all overlapping segments are buffered in memory. Segments created
+    /// by flushes do not overlap, but compacted segments usually cover wide seqno ranges.
+    /// If the segments were written with a seqno index (see [`Config::seqno_index`]),
+    /// only the data blocks that contain items of the range are read.
+    ///
+    /// The segments and memtables are captured when the iterator is created.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lock is poisoned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let folder = tempfile::tempdir()?;
+    /// use lsm_tree::{AbstractTree, Config, Tree};
+    ///
+    /// let tree = Config::new(folder).seqno_index(true).open()?;
+    ///
+    /// tree.insert("b", "abc", 0);
+    /// tree.insert("a", "abc", 1);
+    /// tree.flush_active_memtable(0)?;
+    /// tree.remove("b", 2);
+    ///
+    /// let seqnos = tree
+    ///     .iter_by_seqno(1..)
+    ///     .map(|item| item.map(|item| item.key.seqno))
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// assert_eq!(vec![1, 2], seqnos);
+    /// #
+    /// # Ok::<(), lsm_tree::Error>(())
+    /// ```
+    ///
+    /// [`Config::seqno_index`]: crate::Config::seqno_index
+    pub fn iter_by_seqno<R: RangeBounds<SeqNo>>(
+        &self,
+        range: R,
+    ) -> impl Iterator<Item = crate::Result<InternalValue>> {
+        let lo = match range.start_bound() {
+            Bound::Included(&lo) => Some(lo),
+            Bound::Excluded(&lo) => lo.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let hi = match range.end_bound() {
+            Bound::Included(&hi) => Some(hi),
+            Bound::Excluded(&hi) => hi.checked_sub(1),
+            Bound::Unbounded => Some(SeqNo::MAX),
+        };
+
+        let Some(seqnos @ (lo, hi)) = lo.zip(hi).filter(|(lo, hi)| lo <= hi) else {
+            return SeqnoOrderedIter::new(vec![], (0, 0));
+        };
+
+        // NOTE: Mind lock order L -> M -> S
+        let levels = self.read_lock_levels();
+        let active_memtable = self.read_lock_active_memtable();
+        let sealed_memtables = self.read_lock_sealed_memtables();
+
+        let memtable_items = sealed_memtables
+            .iter()
+            .flat_map(|(_, memtable)| memtable.iter())
+            .chain(active_memtable.iter())
+            .filter(|item| (lo..=hi).contains(&item.key.seqno))
+            .collect::<Vec<_>>();
+
+        let mut sources = levels
+            .iter()
+            .filter(|segment| {
+                let (segment_lo, segment_hi) = segment.metadata.seqnos;
+                segment_lo <= hi && segment_hi >= lo
+            })
+            .map(|segment| (Source::Segment(segment.clone()), segment.metadata.seqnos))
+            .collect::<Vec<_>>();
+
+        drop(sealed_memtables);
+        drop(active_memtable);
+        drop(levels);
+
+        let memtable_seqnos = memtable_items
+            .iter()
+            .map(|item| item.key.seqno)
+            .min()
+            .zip(memtable_items.iter().map(|item| item.key.seqno).max());
+
+        if let Some(memtable_seqnos) = memtable_seqnos {
+            sources.push((Source::Memtables(memtable_items), memtable_seqnos));
+        }
+
+        SeqnoOrderedIter::new(sources, seqnos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ValueType;
+    use test_log::test;
+
+    fn memtable_source(seqnos: &[SeqNo]) -> (Source, (SeqNo, SeqNo)) {
+        let items = seqnos
+            .iter()
+            .map(|&seqno| InternalValue::from_components("a", "", seqno, ValueType::Value))
+            .collect::<Vec<_>>();
+
+        let lo = seqnos.iter().copied().min().unwrap_or_default();
+        let hi = seqnos.iter().copied().max().unwrap_or_default();
+
+        (Source::Memtables(items), (lo, hi))
+    }
+
+    #[test]
+    fn seqno_ordered_iter_runs() -> crate::Result<()> {
+        let iter = SeqnoOrderedIter::new(
+            vec![
+                memtable_source(&[9, 7]),
+                memtable_source(&[0, 4, 2]),
+                memtable_source(&[3, 5]),
+                memtable_source(&[8]),
+            ],
+            (0, SeqNo::MAX),
+        );
+
+        // NOTE: [0, 4] & [3, 5] overlap, [7, 9] & [8, 8] overlap
+        assert_eq!(
+            vec![(0, 5), (7, 9)],
+            iter.runs.iter().map(|run| run.seqnos).collect::<Vec<_>>()
+        );
+
+        let seqnos = iter
+            .map(|item| item.map(|item| item.key.seqno))
+            .collect::<crate::Result<Vec<_>>>()?;
+        assert_eq!(vec![0, 2, 3, 4, 5, 7, 8, 9], seqnos);
+
+        Ok(())
+    }
+}
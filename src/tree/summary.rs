@@ -0,0 +1,129 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::Tree;
+use std::fmt::Write as _;
+
+/// Formats a byte count using the largest fitting binary unit
+#[allow(clippy::cast_precision_loss)]
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1_024.0 && unit < UNITS.len() - 1 {
+        value /= 1_024.0;
+        unit += 1;
+    }
+
+    #[allow(clippy::indexing_slicing)]
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Describes the shape of the tree in a few lines of text
+pub fn summarize(tree: &Tree) -> String {
+    let mut out = String::new();
+
+    // NOTE: Mind lock order L -> M -> S
+    let levels = tree.read_lock_levels();
+    let active = tree.read_lock_active_memtable();
+    let sealed = tree.read_lock_sealed_memtables();
+
+    // NOTE: Writing into a String cannot fail
+    let _ = writeln!(out, "Levels:");
+
+    for (idx, level) in levels.levels.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "  L{idx}: {} segments, {}{}",
+            level.len(),
+            format_bytes(level.size()),
+            if level.is_disjoint {
+                ""
+            } else {
+                " (overlapping)"
+            },
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "Segments: {} total, {} on disk, {} items, {} tombstones",
+        levels.len(),
+        format_bytes(levels.size()),
+        levels.iter().map(|x| x.metadata.item_count).sum::<u64>(),
+        levels
+            .iter()
+            .map(|x| x.metadata.tombstone_count)
+            .sum::<u64>(),
+    );
+
+    let _ = writeln!(
+        out,
+        "Compactions: {} in flight",
+        levels.compaction_jobs().len(),
+    );
+
+    let _ = writeln!(
+        out,
+        "Memtable: {} items, {} ({} sealed memtables, {} items)",
+        active.len(),
+        format_bytes(active.size().into()),
+        sealed.len(),
+        sealed.iter().map(|(_, x)| x.len()).sum::<usize>(),
+    );
+
+    drop(sealed);
+    drop(active);
+    drop(levels);
+
+    let block_cache = &tree.config.block_cache;
+    let _ = writeln!(
+        out,
+        "Block cache: {} / {} ({} blocks), {} hits, {} misses",
+        format_bytes(block_cache.size()),
+        format_bytes(block_cache.capacity()),
+        block_cache.len(),
+        block_cache.hits(),
+        block_cache.misses(),
+    );
+
+    let _ = writeln!(
+        out,
+        "File descriptors: {} segments, {} open",
+        tree.config.descriptor_table.len(),
+        tree.config.descriptor_table.size(),
+    );
+
+    let amp = tree.amplification();
+    let _ = writeln!(
+        out,
+        "Amplification: read {}, space {:.2}, write {:.2}",
+        amp.read_amp,
+        amp.space_amp(),
+        amp.write_amp(),
+    );
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn summary_format_bytes() {
+        assert_eq!("0 B", format_bytes(0));
+        assert_eq!("1023 B", format_bytes(1_023));
+        assert_eq!("1.0 KiB", format_bytes(1_024));
+        assert_eq!("1.5 MiB", format_bytes(3 * 512 * 1_024));
+        assert_eq!("2.0 GiB", format_bytes(2 * 1_024 * 1_024 * 1_024));
+    }
+}
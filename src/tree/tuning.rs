@@ -0,0 +1,312 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::Tree;
+
+/// Minimum amount of observations before a rate is considered meaningful
+const MIN_SAMPLES: u64 = 1_000;
+
+/// Factor by which the observed bloom filter false positive rate
+/// may exceed the expected rate before more bits are advised
+const BLOOM_FPR_TOLERANCE: f64 = 2.0;
+
+/// False positive rate of a bloom filter is roughly `BLOOM_FPR_BASE ^ bits_per_key`
+const BLOOM_FPR_BASE: f64 = 0.6185;
+
+/// Block cache hit rate below which a larger block cache is advised
+const MIN_BLOCK_CACHE_HIT_RATE: f64 = 0.9;
+
+/// Amount of L0 segments above which point reads & scans suffer
+const MAX_FIRST_LEVEL_SEGMENTS: u64 = 20;
+
+/// Space amplification above which more aggressive compaction is advised
+const MAX_SPACE_AMP: f32 = 2.0;
+
+/// A concrete configuration change suggested by [`TuningReport`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum TuningAdvice {
+    /// Bloom filters let through more point reads of missing keys than expected
+    IncreaseBloomBits {
+        /// Currently configured bits per key
+        current: i8,
+
+        /// Suggested bits per key
+        suggested: i8,
+    },
+
+    /// Too many block lookups miss the block cache, while it is full
+    IncreaseBlockCache {
+        /// Current capacity in bytes
+        current: u64,
+
+        /// Suggested capacity in bytes
+        suggested: u64,
+    },
+
+    /// The first level (L0) grew deep, so compaction does not keep up with flushes
+    CompactFirstLevelSooner {
+        /// Highest amount of L0 segments observed
+        peak: u64,
+    },
+
+    /// Much of the disk space is taken by shadowed versions & tombstones
+    CompactMoreAggressively {
+        /// Estimated space amplification
+        space_amp: f32,
+    },
+}
+
+impl std::fmt::Display for TuningAdvice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IncreaseBloomBits { current, suggested } => {
+                write!(
+                    f,
+                    "increase bloom bits per key from {current} to {suggested}"
+                )
+            }
+            Self::IncreaseBlockCache { current, suggested } => {
+                #[allow(clippy::cast_precision_loss)]
+                let factor = *suggested as f64 / (*current).max(1) as f64;
+
+                write!(
+                    f,
+                    "block cache undersized by ~{factor:.0}x, increase capacity from {current} to {suggested} bytes",
+                )
+            }
+            Self::CompactFirstLevelSooner { peak } => write!(
+                f,
+                "L0 grew to {peak} segments, compact L0 sooner or use larger memtables",
+            ),
+            Self::CompactMoreAggressively { space_amp } => write!(
+                f,
+                "space amplification is {space_amp:.1}, compact more aggressively",
+            ),
+        }
+    }
+}
+
+/// Statistics of a tree, and the configuration changes they suggest
+///
+/// See [`Tree::tuning_report`].
+#[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::module_name_repetitions)]
+pub struct TuningReport {
+    /// Observed rate of bloom filter false positives, if enough point reads
+    /// of missing keys were observed
+    pub bloom_false_positive_rate: Option<f64>,
+
+    /// Expected rate of bloom filter false positives, given the configured bits per key
+    pub expected_bloom_false_positive_rate: Option<f64>,
+
+    /// Observed block cache hit rate, if enough block lookups were observed
+    pub block_cache_hit_rate: Option<f64>,
+
+    /// Highest amount of segments in the first level (L0) observed since the tree was opened
+    pub first_level_peak: u64,
+
+    /// Estimated space amplification
+    pub space_amp: f32,
+
+    /// Suggested configuration changes
+    pub advice: Vec<TuningAdvice>,
+}
+
+impl std::fmt::Display for TuningReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.advice.is_empty() {
+            return write!(f, "no tuning advice");
+        }
+
+        for advice in &self.advice {
+            writeln!(f, "- {advice}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns `part / total`, if there are enough observations.
+fn observed_rate(part: u64, total: u64) -> Option<f64> {
+    #[allow(clippy::cast_precision_loss)]
+    (total >= MIN_SAMPLES).then(|| part as f64 / total as f64)
+}
+
+impl TuningReport {
+    fn advise_bloom_bits(&mut self, bits_per_key: i8) {
+        let (Some(observed), Some(expected)) = (
+            self.bloom_false_positive_rate,
+            self.expected_bloom_false_positive_rate,
+        ) else {
+            return;
+        };
+
+        if observed <= expected * BLOOM_FPR_TOLERANCE {
+            return;
+        }
+
+        // NOTE: Every additional bit divides the false positive rate by 1 / BLOOM_FPR_BASE
+        let extra_bits = (observed / expected).log(BLOOM_FPR_BASE.recip()).ceil();
+
+        // NOTE: Bits per key are small, so they fit into i8
+        #[allow(clippy::cast_possible_truncation)]
+        let suggested = bits_per_key.saturating_add(extra_bits.min(f64::from(i8::MAX)) as i8);
+
+        self.advice.push(TuningAdvice::IncreaseBloomBits {
+            current: bits_per_key,
+            suggested,
+        });
+    }
+
+    fn advise_block_cache(&mut self, size: u64, capacity: u64) {
+        let Some(hit_rate) = self.block_cache_hit_rate else {
+            return;
+        };
+
+        // NOTE: A cache that is not full yet is not undersized
+        if hit_rate >= MIN_BLOCK_CACHE_HIT_RATE || size < capacity / 10 * 9 {
+            return;
+        }
+
+        self.advice.push(TuningAdvice::IncreaseBlockCache {
+            current: capacity,
+            suggested: capacity.saturating_mul(2).max(1),
+        });
+    }
+}
+
+impl Tree {
+    /// Analyzes the statistics collected since the tree was opened, and suggests
+    /// configuration changes.
+    ///
+    /// The advice is based on heuristics, so it should be treated as a starting point
+    /// for tuning, not applied blindly.
+    #[must_use]
+    pub fn tuning_report(&self) -> TuningReport {
+        let level_stats = self.level_stats();
+
+        let bloom_negatives = level_stats.iter().map(|x| x.bloom_negatives).sum::<u64>();
+        let bloom_false_positives = level_stats
+            .iter()
+            .map(|x| x.bloom_false_positives)
+            .sum::<u64>();
+
+        let bits_per_key = self.config.bloom_bits_per_key;
+
+        let block_cache = &self.config.block_cache;
+        let cache_hits = block_cache.hits();
+        let cache_misses = block_cache.misses();
+
+        let mut report = TuningReport {
+            bloom_false_positive_rate: observed_rate(
+                bloom_false_positives,
+                bloom_false_positives + bloom_negatives,
+            ),
+            expected_bloom_false_positive_rate: (bits_per_key > 0)
+                .then(|| BLOOM_FPR_BASE.powi(bits_per_key.into())),
+            block_cache_hit_rate: observed_rate(cache_hits, cache_hits + cache_misses),
+            first_level_peak: level_stats
+                .first()
+                .map(|x| x.peak_segment_count)
+                .unwrap_or_default(),
+            space_amp: self.amplification().space_amp(),
+            advice: vec![],
+        };
+
+        report.advise_bloom_bits(bits_per_key);
+        report.advise_block_cache(block_cache.size(), block_cache.capacity());
+
+        if report.first_level_peak > MAX_FIRST_LEVEL_SEGMENTS {
+            report.advice.push(TuningAdvice::CompactFirstLevelSooner {
+                peak: report.first_level_peak,
+            });
+        }
+
+        if report.space_amp > MAX_SPACE_AMP {
+            report.advice.push(TuningAdvice::CompactMoreAggressively {
+                space_amp: report.space_amp,
+            });
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    fn empty_report() -> TuningReport {
+        TuningReport {
+            bloom_false_positive_rate: None,
+            expected_bloom_false_positive_rate: None,
+            block_cache_hit_rate: None,
+            first_level_peak: 0,
+            space_amp: 1.0,
+            advice: vec![],
+        }
+    }
+
+    #[test]
+    fn tuning_observed_rate() {
+        assert_eq!(None, observed_rate(1, 10));
+        assert_eq!(Some(0.5), observed_rate(1_000, 2_000));
+    }
+
+    #[test]
+    fn tuning_advise_bloom_bits() {
+        let mut report = TuningReport {
+            bloom_false_positive_rate: Some(0.04),
+            expected_bloom_false_positive_rate: Some(BLOOM_FPR_BASE.powi(10)),
+            ..empty_report()
+        };
+        report.advise_bloom_bits(10);
+
+        // NOTE: 0.04 is ~5x the expected rate of ~0.8%, which takes 4 more bits
+        assert_eq!(
+            vec![TuningAdvice::IncreaseBloomBits {
+                current: 10,
+                suggested: 14,
+            }],
+            report.advice,
+        );
+
+        let mut report = TuningReport {
+            bloom_false_positive_rate: Some(0.01),
+            expected_bloom_false_positive_rate: Some(BLOOM_FPR_BASE.powi(10)),
+            ..empty_report()
+        };
+        report.advise_bloom_bits(10);
+        assert!(report.advice.is_empty());
+    }
+
+    #[test]
+    fn tuning_advise_block_cache() {
+        let mut report = TuningReport {
+            block_cache_hit_rate: Some(0.5),
+            ..empty_report()
+        };
+        report.advise_block_cache(1_000, 1_000);
+        assert_eq!(
+            vec![TuningAdvice::IncreaseBlockCache {
+                current: 1_000,
+                suggested: 2_000,
+            }],
+            report.advice,
+        );
+        assert_eq!(
+            "block cache undersized by ~2x, increase capacity from 1000 to 2000 bytes",
+            report.advice.first().expect("should exist").to_string(),
+        );
+
+        // NOTE: Cache is not full
+        let mut report = TuningReport {
+            block_cache_hit_rate: Some(0.5),
+            ..empty_report()
+        };
+        report.advise_block_cache(100, 1_000);
+        assert!(report.advice.is_empty());
+    }
+}
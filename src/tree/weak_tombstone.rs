@@ -0,0 +1,77 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use super::Tree;
+use crate::{key::InternalKey, InternalValue, SeqNo, ValueType};
+use std::{ops::Bound, sync::atomic::Ordering};
+
+impl Tree {
+    /// Returns the amount of weak tombstones that violated the single-delete contract,
+    /// see [`crate::Config::weak_tombstone_checks`].
+    #[must_use]
+    pub fn weak_tombstone_violations(&self) -> u64 {
+        self.weak_tombstone_violations.load(Ordering::Relaxed)
+    }
+
+    /// Checks that a key about to be weakly deleted was written at most once
+    /// since it was last deleted.
+    ///
+    /// Otherwise, the weak tombstone only cancels out the latest version,
+    /// and older versions are resurrected once they are compacted.
+    pub(crate) fn check_weak_tombstone(&self, key: &[u8]) -> crate::Result<()> {
+        let versions = self.get_key_versions(key)?;
+
+        let writes = versions
+            .iter()
+            .take_while(|version| !version.key.is_tombstone())
+            .count();
+
+        if writes > 1 {
+            self.weak_tombstone_violations
+                .fetch_add(1, Ordering::Relaxed);
+
+            log::error!(
+                "Weak tombstone violation: key {key:?} was written {writes} times since it was last deleted, older versions may be resurrected"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns all versions of a key in the tree, newest first.
+    fn get_key_versions(&self, key: &[u8]) -> crate::Result<Vec<InternalValue>> {
+        // NOTE: See range.rs for range explanation
+        let range = (
+            Bound::Included(InternalKey::new(key, SeqNo::MAX, ValueType::Tombstone)),
+            Bound::Included(InternalKey::new(key, 0, ValueType::Value)),
+        );
+
+        let mut versions = Vec::new();
+
+        // NOTE: Mind lock order L -> M -> S
+        let levels = self.read_lock_levels();
+
+        versions.extend(self.read_lock_active_memtable().range(range.clone()));
+
+        for (_, memtable) in self.read_lock_sealed_memtables().iter() {
+            versions.extend(memtable.range(range.clone()));
+        }
+
+        for segment in levels.iter() {
+            if !segment.metadata.key_range.contains_key(key) {
+                continue;
+            }
+
+            for item in segment.range((Bound::Included(key.into()), Bound::Included(key.into()))) {
+                versions.push(item?);
+            }
+        }
+
+        drop(levels);
+
+        versions.sort_by_key(|v| std::cmp::Reverse(v.key.seqno));
+
+        Ok(versions)
+    }
+}
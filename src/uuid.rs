@@ -0,0 +1,126 @@
+// Copyright (c) 2024-present, fjall-rs
+// This source code is licensed under both the Apache 2.0 and MIT License
+// (found in the LICENSE-* files in the repository)
+
+use crate::coding::{Decode, DecodeError, Encode, EncodeError};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+    hash::{BuildHasher, Hash, Hasher},
+    io::{Read, Write},
+};
+
+/// Unique identifier of a tree (random UUID, version 4)
+///
+/// The UUID is generated when the tree is created, and persisted in its manifest.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Uuid(u128);
+
+impl Uuid {
+    /// Generates a new random UUID.
+    pub(crate) fn new_v4() -> Self {
+        // NOTE: RandomState is seeded randomly (per thread), and every instance uses different keys
+        let state = std::collections::hash_map::RandomState::new();
+
+        let mut hasher = state.build_hasher();
+        crate::time::unix_timestamp().hash(&mut hasher);
+        std::process::id().hash(&mut hasher);
+        let hi = hasher.finish();
+
+        std::thread::current().id().hash(&mut hasher);
+        let lo = hasher.finish();
+
+        let value = (u128::from(hi) << 64) | u128::from(lo);
+
+        // NOTE: Set version (4) and variant (RFC 4122) bits
+        let value = (value & !(0xF << 76)) | (0x4 << 76);
+        let value = (value & !(0b11 << 62)) | (0b10 << 62);
+
+        Self(value)
+    }
+
+    /// Derives a UUID from a seed, for the deterministic mode.
+    pub(crate) fn from_seed(seed: u64) -> Self {
+        let value = xxhash_rust::xxh3::xxh3_128(&seed.to_be_bytes());
+
+        // NOTE: Set version (4) and variant (RFC 4122) bits
+        let value = (value & !(0xF << 76)) | (0x4 << 76);
+        let value = (value & !(0b11 << 62)) | (0b10 << 62);
+
+        Self(value)
+    }
+
+    /// Returns the UUID as a 128-bit integer.
+    #[must_use]
+    pub fn as_u128(&self) -> u128 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for Uuid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = self.0;
+
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            value >> 96,
+            (value >> 80) & 0xFFFF,
+            (value >> 64) & 0xFFFF,
+            (value >> 48) & 0xFFFF,
+            value & 0xFFFF_FFFF_FFFF,
+        )
+    }
+}
+
+impl Encode for Uuid {
+    fn encode_into<W: Write>(&self, writer: &mut W) -> Result<(), EncodeError> {
+        writer.write_u128::<BigEndian>(self.0)?;
+        Ok(())
+    }
+}
+
+impl Decode for Uuid {
+    fn decode_from<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
+        Ok(Self(reader.read_u128::<BigEndian>()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn uuid_v4() {
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+        assert_ne!(a, b);
+
+        let formatted = a.to_string();
+        assert_eq!(36, formatted.len());
+        assert_eq!(Some('4'), formatted.chars().nth(14));
+        assert!(matches!(
+            formatted.chars().nth(19),
+            Some('8' | '9' | 'a' | 'b')
+        ));
+    }
+
+    #[test]
+    fn uuid_from_seed() {
+        assert_eq!(Uuid::from_seed(7), Uuid::from_seed(7));
+        assert_ne!(Uuid::from_seed(7), Uuid::from_seed(8));
+        assert_eq!(4, (Uuid::from_seed(7).as_u128() >> 76) & 0xF);
+    }
+
+    #[test]
+    fn uuid_roundtrip() -> crate::Result<()> {
+        let before = Uuid::new_v4();
+
+        let bytes = before.encode_into_vec()?;
+        let after = Uuid::decode_from(&mut bytes.as_slice())?;
+
+        assert_eq!(before, after);
+
+        Ok(())
+    }
+}
@@ -5,12 +5,16 @@
 use crate::{
     coding::{Decode, DecodeError, Encode, EncodeError},
     key::InternalKey,
-    segment::block::ItemSize,
+    segment::block::{checksum::Checksum, ItemSize},
     Slice,
 };
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::io::{Read, Write};
 use varint_rs::{VarintReader, VarintWriter};
 
+/// Item flag that marks values which are followed by their checksum
+const VALUE_CHECKSUM_FLAG: u8 = 0x80;
+
 /// User defined key
 pub type UserKey = Slice;
 
@@ -138,6 +142,31 @@ impl InternalValue {
     pub fn is_tombstone(&self) -> bool {
         self.key.is_tombstone()
     }
+
+    /// Encodes the item like [`Encode::encode_into`], but stores a checksum (xxh3)
+    /// of the value after it, which is verified when the item is decoded.
+    ///
+    /// Tombstones do not have a value, so they are encoded without checksum.
+    pub(crate) fn encode_with_value_checksum<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<(), EncodeError> {
+        if self.is_tombstone() {
+            return self.encode_into(writer);
+        }
+
+        self.key
+            .encode_with_tag_flags(writer, VALUE_CHECKSUM_FLAG)?;
+
+        // NOTE: We know values are limited to 32-bit length
+        #[allow(clippy::cast_possible_truncation)]
+        writer.write_u32_varint(self.value.len() as u32)?;
+        writer.write_all(&self.value)?;
+
+        writer.write_u64::<BigEndian>(*Checksum::from_bytes(&self.value))?;
+
+        Ok(())
+    }
 }
 
 impl ItemSize for InternalValue {
@@ -182,7 +211,14 @@ impl Encode for InternalValue {
 
 impl Decode for InternalValue {
     fn decode_from<R: Read>(reader: &mut R) -> Result<Self, DecodeError> {
-        let key = InternalKey::decode_from(reader)?;
+        let (key, flags) = InternalKey::decode_with_tag_flags(reader)?;
+
+        if flags & !VALUE_CHECKSUM_FLAG != 0 {
+            return Err(DecodeError::InvalidTag((
+                "ValueType",
+                u8::from(key.value_type) | flags,
+            )));
+        }
 
         if key.is_tombstone() {
             Ok(Self {
@@ -196,6 +232,21 @@ impl Decode for InternalValue {
             let mut value = vec![0; value_len as usize];
             reader.read_exact(&mut value)?;
 
+            if flags & VALUE_CHECKSUM_FLAG != 0 {
+                let expected = Checksum::from_raw(reader.read_u64::<BigEndian>()?);
+                let got = Checksum::from_bytes(&value);
+
+                if got != expected {
+                    return Err(DecodeError::InvalidContent {
+                        name: "InternalValue",
+                        reason: format!(
+                            "value checksum mismatch: got={}, expected={}",
+                            *got, *expected,
+                        ),
+                    });
+                }
+            }
+
             Ok(Self {
                 key,
                 value: value.into(),
@@ -254,6 +305,59 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn value_with_value_checksum() -> crate::Result<()> {
+        let value =
+            InternalValue::from_components(vec![1, 2, 3], vec![3, 2, 1], 1, ValueType::Value);
+
+        let mut serialized = Vec::new();
+        value.encode_with_value_checksum(&mut serialized)?;
+
+        // NOTE: Flagged type tag, followed by the checksum after the value
+        assert_eq!(0x80, *serialized.get(1).expect("should exist"));
+        assert_eq!(10 + 8, serialized.len());
+
+        let deserialized = InternalValue::decode_from(&mut &serialized[..])?;
+        assert_eq!(value, deserialized);
+
+        // NOTE: Tombstones do not have a value, so they are not checksummed
+        let tombstone = InternalValue::new_tombstone(vec![1, 2, 3], 1);
+
+        let mut serialized = Vec::new();
+        tombstone.encode_with_value_checksum(&mut serialized)?;
+        assert_eq!(tombstone.encode_into_vec()?, serialized);
+
+        Ok(())
+    }
+
+    #[test]
+    fn value_checksum_mismatch() -> crate::Result<()> {
+        let value =
+            InternalValue::from_components(vec![1, 2, 3], vec![3, 2, 1], 1, ValueType::Value);
+
+        let mut serialized = Vec::new();
+        value.encode_with_value_checksum(&mut serialized)?;
+
+        // NOTE: Flip a bit in the value
+        *serialized.get_mut(7).expect("should exist") ^= 1;
+
+        assert!(matches!(
+            InternalValue::decode_from(&mut &serialized[..]),
+            Err(DecodeError::InvalidContent {
+                name: "InternalValue",
+                ..
+            })
+        ));
+
+        // NOTE: Keys do not carry item flags
+        assert!(matches!(
+            InternalKey::decode_from(&mut &serialized[..]),
+            Err(DecodeError::InvalidTag(("ValueType", 0x80)))
+        ));
+
+        Ok(())
+    }
+
     #[test]
     fn value_empty_value() -> crate::Result<()> {
         // Create an empty Value instance
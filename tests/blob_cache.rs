@@ -0,0 +1,35 @@
+use lsm_tree::{AbstractTree, BlockCache, Config};
+use std::sync::Arc;
+use test_log::test;
+
+#[test]
+fn blob_cache_does_not_pollute_block_cache() -> lsm_tree::Result<()> {
+    const ITEM_COUNT: usize = 32;
+    const VALUE_SIZE: usize = 256 * 1_024;
+
+    let folder = tempfile::tempdir()?;
+
+    let block_cache = Arc::new(BlockCache::with_capacity_bytes(4 * 1_024 * 1_024));
+
+    let tree = Config::new(&folder)
+        .block_cache(block_cache.clone())
+        .blob_cache_capacity(16 * 1_024 * 1_024)
+        .open_as_blob_tree()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), vec![x as u8; VALUE_SIZE], 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    for _ in 0..2 {
+        for x in 0..ITEM_COUNT {
+            let value = tree.get(x.to_be_bytes())?.expect("should exist");
+            assert_eq!(VALUE_SIZE, value.len());
+        }
+    }
+
+    // NOTE: The block cache only holds the small value handles of the index tree
+    assert!(block_cache.size() < (VALUE_SIZE as u64));
+
+    Ok(())
+}
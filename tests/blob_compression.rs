@@ -0,0 +1,42 @@
+#![cfg(feature = "miniz")]
+
+use lsm_tree::{AbstractTree, CompressionType, Config};
+use test_log::test;
+
+#[test]
+fn blob_compression_independent_of_block_compression() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let big_value = "neptune".repeat(10_000);
+
+    {
+        let tree = Config::new(&folder)
+            .compression(CompressionType::None)
+            .blob_compression(CompressionType::Miniz(9))
+            .open_as_blob_tree()?;
+
+        tree.insert("a", &big_value, 0);
+        tree.insert("b", "small", 1);
+        tree.flush_active_memtable(0)?;
+
+        assert_eq!(1, tree.blobs.segment_count());
+        assert!(tree.blobs.manifest.disk_space_used() < big_value.len() as u64);
+
+        assert_eq!(&*tree.get("a")?.unwrap(), big_value.as_bytes());
+    }
+
+    {
+        // NOTE: Blob compression is persisted, so it does not need to be set again
+        let tree = Config::new(&folder).open_as_blob_tree()?;
+
+        assert_eq!(
+            CompressionType::Miniz(9),
+            tree.index.config.blob_compression
+        );
+
+        assert_eq!(&*tree.get("a")?.unwrap(), big_value.as_bytes());
+        assert_eq!(&*tree.get("b")?.unwrap(), b"small");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use lsm_tree::{AbstractTree, Config, SequenceNumberCounter};
+use test_log::test;
+
+#[test]
+fn blob_gc_space_amp_target() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open_as_blob_tree()?;
+
+    let seqno = SequenceNumberCounter::default();
+
+    tree.insert("a", "neptune".repeat(10_000), seqno.next());
+    tree.insert("b", "neptune".repeat(10_000), seqno.next());
+    tree.insert("c", "neptune".repeat(10_000), seqno.next());
+    tree.flush_active_memtable(0)?;
+    assert_eq!(1, tree.blobs.segment_count());
+
+    // NOTE: Nothing is stale yet
+    assert_eq!(0, tree.gc_with_space_amp_target(2.0, seqno.next())?);
+    assert_eq!(1, tree.blobs.segment_count());
+
+    tree.insert("a", "a", seqno.next());
+    tree.insert("b", "b", seqno.next());
+
+    let disk_space_before = tree.disk_space();
+    let reclaimed = tree.gc_with_space_amp_target(1.0, seqno.next())?;
+    assert!(reclaimed > 0);
+    assert!(tree.disk_space() < disk_space_before);
+    assert_eq!(1.0, tree.blobs.space_amp());
+
+    assert_eq!(&*tree.get("a")?.unwrap(), b"a");
+    assert_eq!(&*tree.get("b")?.unwrap(), b"b");
+    assert_eq!(
+        &*tree.get("c")?.unwrap(),
+        "neptune".repeat(10_000).as_bytes()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn blob_gc_staleness_threshold() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open_as_blob_tree()?;
+
+    let seqno = SequenceNumberCounter::default();
+
+    tree.insert("a", "neptune".repeat(10_000), seqno.next());
+    tree.insert("b", "neptune".repeat(10_000), seqno.next());
+    tree.insert("c", "neptune".repeat(10_000), seqno.next());
+    tree.insert("d", "neptune".repeat(10_000), seqno.next());
+    tree.flush_active_memtable(0)?;
+    assert_eq!(1, tree.blobs.segment_count());
+
+    tree.remove("a", seqno.next());
+
+    // NOTE: Only 25% of the blob file is stale
+    assert_eq!(0, tree.gc_with_staleness_threshold(0.5, seqno.next())?);
+    assert_eq!(1, tree.blobs.segment_count());
+
+    tree.remove("b", seqno.next());
+    tree.remove("c", seqno.next());
+
+    assert!(tree.gc_with_staleness_threshold(0.5, seqno.next())? > 0);
+    assert_eq!(1, tree.blobs.segment_count());
+
+    assert!(tree.get("a")?.is_none());
+    assert_eq!(
+        &*tree.get("d")?.unwrap(),
+        "neptune".repeat(10_000).as_bytes()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn blob_gc_age_threshold() -> lsm_tree::Result<()> {
+    use std::time::Duration;
+
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open_as_blob_tree()?;
+
+    let seqno = SequenceNumberCounter::default();
+
+    for key in ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"] {
+        tree.insert(key, "neptune".repeat(10_000), seqno.next());
+    }
+    tree.flush_active_memtable(0)?;
+    assert_eq!(1, tree.blobs.segment_count());
+
+    // NOTE: Only 10% of the blob file is stale, which a staleness threshold would not pick up
+    tree.remove("a", seqno.next());
+    assert_eq!(0, tree.gc_with_staleness_threshold(0.5, seqno.next())?);
+
+    // NOTE: Blob file is not old enough yet
+    assert_eq!(
+        0,
+        tree.gc_with_age_threshold(Duration::from_secs(3_600), seqno.next())?
+    );
+
+    assert!(tree.gc_with_age_threshold(Duration::ZERO, seqno.next())? > 0);
+    assert_eq!(1, tree.blobs.segment_count());
+    assert_eq!(1.0, tree.blobs.space_amp());
+
+    assert!(tree.get("a")?.is_none());
+    assert_eq!(
+        &*tree.get("b")?.unwrap(),
+        "neptune".repeat(10_000).as_bytes()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn blob_gc_fragmentation_report() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open_as_blob_tree()?;
+
+    let seqno = SequenceNumberCounter::default();
+
+    tree.insert("a", "neptune".repeat(10_000), seqno.next());
+    tree.insert("b", "neptune".repeat(10_000), seqno.next());
+    tree.flush_active_memtable(0)?;
+
+    tree.insert("c", "neptune".repeat(10_000), seqno.next());
+    tree.flush_active_memtable(0)?;
+
+    tree.remove("a", seqno.next());
+    tree.gc_scan_stats(seqno.get())?;
+
+    let report = tree.fragmentation();
+    assert_eq!(2, report.blob_files.len());
+    assert_eq!(tree.blobs.space_amp(), report.space_amp());
+
+    let first = report.blob_files.first().unwrap();
+    assert_eq!(2, first.item_count);
+    assert_eq!(1, first.live_item_count);
+    assert_eq!(first.total_bytes / 2, first.stale_bytes);
+
+    let second = report.blob_files.get(1).unwrap();
+    assert_eq!(1, second.item_count);
+    assert_eq!(1, second.live_item_count);
+    assert_eq!(0, second.stale_bytes);
+
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use lsm_tree::{AbstractTree, Config};
+use std::io::{Read, Write};
+use test_log::test;
+
+#[test]
+fn blob_tree_insert_streaming() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let chunk = "abcdefgh".repeat(1_024);
+    let expected = chunk.repeat(128);
+
+    {
+        let tree = Config::new(&folder).open_as_blob_tree()?;
+
+        let mut writer = tree.insert_streaming("big", 0);
+        for _ in 0..128 {
+            writer.write_all(chunk.as_bytes())?;
+        }
+        writer.finish()?;
+
+        assert_eq!(1, tree.blobs.segment_count());
+        assert_eq!(Some(expected.as_bytes().into()), tree.get("big")?);
+
+        // NOTE: An unfinished value is discarded
+        let mut writer = tree.insert_streaming("discarded", 1);
+        writer.write_all(chunk.as_bytes())?;
+        drop(writer);
+
+        assert!(!tree.contains_key("discarded")?);
+
+        tree.insert("small", "abc", 2);
+        tree.flush_active_memtable(0)?;
+
+        assert_eq!(Some(expected.as_bytes().into()), tree.get("big")?);
+        assert_eq!(Some("abc".as_bytes().into()), tree.get("small")?);
+    }
+
+    {
+        let tree = Config::new(&folder).open_as_blob_tree()?;
+        assert_eq!(Some(expected.as_bytes().into()), tree.get("big")?);
+        assert_eq!(2, tree.len()?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn blob_tree_insert_streaming_chunked() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let chunk = "abcdefgh".repeat(1_024);
+    let expected = chunk.repeat(16);
+
+    {
+        let tree = Config::new(&folder)
+            .blob_chunk_size(20_000)
+            .open_as_blob_tree()?;
+
+        let mut writer = tree.insert_streaming("big", 0);
+        for _ in 0..16 {
+            writer.write_all(chunk.as_bytes())?;
+        }
+        writer.finish()?;
+
+        tree.insert_from_reader("big2", &mut expected.as_bytes(), 1)?;
+
+        // NOTE: Value fits into a single chunk
+        tree.insert_from_reader("exact", &mut &expected.as_bytes()[..20_000], 2)?;
+
+        assert_eq!(Some(expected.as_bytes().into()), tree.get("big")?);
+        assert_eq!(Some(expected.as_bytes().into()), tree.get("big2")?);
+        assert_eq!(
+            Some(expected.as_bytes()[..20_000].into()),
+            tree.get("exact")?
+        );
+
+        tree.flush_active_memtable(0)?;
+
+        assert_eq!(Some(expected.as_bytes().into()), tree.get("big")?);
+        assert_eq!(3, tree.iter().count());
+        assert!(tree.iter().all(|item| item.is_ok()));
+    }
+
+    {
+        let tree = Config::new(&folder).open_as_blob_tree()?;
+        assert_eq!(Some(expected.as_bytes().into()), tree.get("big")?);
+        assert_eq!(Some(expected.as_bytes().into()), tree.get("big2")?);
+        assert_eq!(3, tree.len()?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn blob_tree_get_streaming() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let chunk = "abcdefgh".repeat(1_024);
+    let expected = chunk.repeat(16);
+
+    let tree = Config::new(&folder)
+        .blob_chunk_size(20_000)
+        .open_as_blob_tree()?;
+
+    tree.insert_from_reader("big", &mut expected.as_bytes(), 0)?;
+    tree.insert("small", "abc", 1);
+    tree.insert("blob", &chunk, 2);
+
+    for key in ["big", "small", "blob"] {
+        let mut value = vec![];
+        let mut reader = tree.get_streaming(key, None)?.expect("should exist");
+
+        // NOTE: Read in small pieces, so reads cross chunk boundaries
+        let mut buf = [0; 1_000];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            value.extend_from_slice(&buf[..n]);
+        }
+
+        assert_eq!(tree.get(key)?.as_deref(), Some(&*value));
+    }
+
+    assert!(tree.get_streaming("missing", None)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn blob_tree_insert_streaming_chunked_gc() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let chunk = "abcdefgh".repeat(1_024);
+    let expected = chunk.repeat(16);
+
+    // NOTE: Looks like the blob key of the second chunk of "big"
+    let mut lookalike = b"big".to_vec();
+    lookalike.extend_from_slice(&1u32.to_be_bytes());
+
+    {
+        let tree = Config::new(&folder)
+            .blob_chunk_size(20_000)
+            .open_as_blob_tree()?;
+
+        tree.insert_from_reader("big", &mut chunk.as_bytes(), 0)?;
+        tree.insert(&lookalike, &chunk, 1);
+        tree.flush_active_memtable(0)?;
+
+        // NOTE: Makes the first version of "big" stale
+        tree.insert_from_reader("big", &mut expected.as_bytes(), 2)?;
+        tree.insert_from_reader("big2", &mut expected.as_bytes(), 3)?;
+        tree.flush_active_memtable(0)?;
+
+        tree.gc_with_staleness_threshold(0.0, 4)?;
+        tree.gc_drop_stale()?;
+        tree.flush_active_memtable(0)?;
+
+        assert_eq!(Some(expected.as_bytes().into()), tree.get("big")?);
+        assert_eq!(Some(expected.as_bytes().into()), tree.get("big2")?);
+        assert_eq!(Some(chunk.as_bytes().into()), tree.get(&lookalike)?);
+    }
+
+    {
+        let tree = Config::new(&folder).open_as_blob_tree()?;
+
+        assert_eq!(Some(expected.as_bytes().into()), tree.get("big")?);
+        assert_eq!(Some(expected.as_bytes().into()), tree.get("big2")?);
+        assert_eq!(Some(chunk.as_bytes().into()), tree.get(&lookalike)?);
+        assert_eq!(3, tree.len()?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn blob_tree_insert_streaming_max_size() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .max_streamed_value_size(10)
+        .open_as_blob_tree()?;
+
+    let mut writer = tree.insert_streaming("a", 0);
+    writer.write_all(b"0123456789")?;
+    assert!(writer.write_all(b"a").is_err());
+    drop(writer);
+
+    assert!(!tree.contains_key("a")?);
+
+    Ok(())
+}
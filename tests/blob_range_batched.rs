@@ -0,0 +1,111 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 200;
+
+fn value_for(x: u64) -> Vec<u8> {
+    // NOTE: Every other value is small enough to be inlined
+    if x % 2 == 0 {
+        x.to_be_bytes().repeat(1_000)
+    } else {
+        x.to_be_bytes().to_vec()
+    }
+}
+
+#[test]
+fn blob_range_batched() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .blob_separation_threshold(1_024)
+        .open_as_blob_tree()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), value_for(x), x);
+    }
+    tree.flush_active_memtable(0)?;
+    assert_eq!(1, tree.blobs.segment_count());
+
+    let forward = tree.iter().collect::<lsm_tree::Result<Vec<_>>>()?;
+    assert_eq!(ITEM_COUNT as usize, forward.len());
+
+    for (x, (k, v)) in forward.iter().enumerate() {
+        let x = x as u64;
+        assert_eq!(&**k, x.to_be_bytes());
+        assert_eq!(&**v, value_for(x));
+    }
+
+    let backward = tree.iter().rev().collect::<lsm_tree::Result<Vec<_>>>()?;
+    assert_eq!(forward.iter().rev().cloned().collect::<Vec<_>>(), backward);
+
+    Ok(())
+}
+
+#[test]
+fn blob_range_batched_double_ended() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .blob_separation_threshold(1_024)
+        .open_as_blob_tree()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), value_for(x), x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let mut iter = tree.range(10_u64.to_be_bytes()..100_u64.to_be_bytes());
+
+    let mut lo = 10_u64;
+    let mut hi = 99_u64;
+
+    while lo <= hi {
+        let (k, v) = iter.next().unwrap()?;
+        assert_eq!(&*k, lo.to_be_bytes());
+        assert_eq!(&*v, value_for(lo));
+        lo += 1;
+
+        if lo > hi {
+            break;
+        }
+
+        let (k, v) = iter.next_back().unwrap()?;
+        assert_eq!(&*k, hi.to_be_bytes());
+        assert_eq!(&*v, value_for(hi));
+        hi -= 1;
+    }
+
+    assert!(iter.next().is_none());
+    assert!(iter.next_back().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn blob_range_batched_without_read_ahead_threads() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .blob_separation_threshold(1_024)
+        .read_ahead_threads(0)
+        .open_as_blob_tree()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), value_for(x), x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let forward = tree.iter().collect::<lsm_tree::Result<Vec<_>>>()?;
+    assert_eq!(ITEM_COUNT as usize, forward.len());
+
+    for (x, (k, v)) in forward.iter().enumerate() {
+        let x = x as u64;
+        assert_eq!(&**k, x.to_be_bytes());
+        assert_eq!(&**v, value_for(x));
+    }
+
+    let backward = tree.iter().rev().collect::<lsm_tree::Result<Vec<_>>>()?;
+    assert_eq!(forward.iter().rev().cloned().collect::<Vec<_>>(), backward);
+
+    Ok(())
+}
@@ -22,3 +22,38 @@ fn blob_tree_separation_threshold() -> lsm_tree::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn blob_tree_separation_threshold_persisted() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let path = folder.path();
+
+    {
+        let tree = lsm_tree::Config::new(path)
+            .blob_separation_threshold(1_024)
+            .open_as_blob_tree()?;
+
+        assert_eq!(1_024, tree.index.config.blob_file_separation_threshold);
+    }
+
+    assert!(matches!(
+        lsm_tree::Config::new(path)
+            .blob_separation_threshold(64)
+            .open_as_blob_tree(),
+        Err(lsm_tree::Error::ConfigMismatch(_))
+    ));
+
+    {
+        let tree = lsm_tree::Config::new(path).open_as_blob_tree()?;
+
+        assert_eq!(1_024, tree.index.config.blob_file_separation_threshold);
+
+        tree.insert("a", "a".repeat(1_023), 0);
+        tree.flush_active_memtable(0)?;
+        assert_eq!(tree.blobs.segment_count(), 0);
+
+        assert_eq!(1, tree.len()?);
+    }
+
+    Ok(())
+}
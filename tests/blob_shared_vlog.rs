@@ -0,0 +1,174 @@
+use lsm_tree::{AbstractTree, Config, SequenceNumberCounter, SharedValueLog};
+use test_log::test;
+
+#[test]
+fn blob_shared_vlog_gc() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let vlog = SharedValueLog::open(folder.path().join("blobs"), &Config::default())?;
+
+    let tree_a = Config::new(folder.path().join("a"))
+        .shared_value_log(vlog.clone())
+        .open_as_blob_tree()?;
+
+    let tree_b = Config::new(folder.path().join("b"))
+        .shared_value_log(vlog.clone())
+        .open_as_blob_tree()?;
+
+    let seqno = SequenceNumberCounter::default();
+
+    tree_a.insert("a", "neptune".repeat(10_000), seqno.next());
+    tree_a.insert("b", "neptune".repeat(10_000), seqno.next());
+    tree_a.flush_active_memtable(0)?;
+
+    tree_b.insert("a", "saturn".repeat(10_000), seqno.next());
+    tree_b.insert("b", "saturn".repeat(10_000), seqno.next());
+    tree_b.flush_active_memtable(0)?;
+
+    assert_eq!(2, tree_a.blobs.segment_count());
+
+    // NOTE: Blobs of tree B are not stale, even though tree A does not reference them
+    tree_a.gc_scan_stats(seqno.get())?;
+    assert_eq!(1.0, tree_a.blobs.space_amp());
+
+    tree_b.insert("a", "a", seqno.next());
+
+    // NOTE: Tree A does not own the fragmented blob file, so it does not rewrite it
+    assert_eq!(0, tree_a.gc_with_staleness_threshold(0.25, seqno.next())?);
+    assert_eq!(2, tree_a.blobs.segment_count());
+
+    assert!(tree_b.gc_with_staleness_threshold(0.25, seqno.next())? > 0);
+    assert_eq!(2, tree_b.blobs.segment_count());
+
+    assert_eq!(
+        &*tree_a.get("a")?.unwrap(),
+        "neptune".repeat(10_000).as_bytes()
+    );
+    assert_eq!(
+        &*tree_a.get("b")?.unwrap(),
+        "neptune".repeat(10_000).as_bytes()
+    );
+    assert_eq!(&*tree_b.get("a")?.unwrap(), b"a");
+    assert_eq!(
+        &*tree_b.get("b")?.unwrap(),
+        "saturn".repeat(10_000).as_bytes()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn blob_shared_vlog_gc_closed_tree() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let seqno = SequenceNumberCounter::default();
+
+    let vlog = SharedValueLog::open(folder.path().join("blobs"), &Config::default())?;
+
+    let tree_a = Config::new(folder.path().join("a"))
+        .shared_value_log(vlog.clone())
+        .open_as_blob_tree()?;
+
+    {
+        let tree_b = Config::new(folder.path().join("b"))
+            .shared_value_log(vlog.clone())
+            .open_as_blob_tree()?;
+
+        tree_b.insert("a", "saturn".repeat(10_000), seqno.next());
+        tree_b.flush_active_memtable(0)?;
+    }
+
+    tree_a.insert("a", "neptune".repeat(10_000), seqno.next());
+    tree_a.flush_active_memtable(0)?;
+
+    // NOTE: Tree B is closed, so its blobs can not be told apart from stale blobs
+    assert!(tree_a.gc_scan_stats(seqno.get()).is_err());
+    assert!(tree_a.gc_drop_stale().is_ok());
+    assert_eq!(2, tree_a.blobs.segment_count());
+
+    // NOTE: Same after reopening the value log
+    drop(tree_a);
+    drop(vlog);
+
+    let vlog = SharedValueLog::open(folder.path().join("blobs"), &Config::default())?;
+
+    let tree_a = Config::new(folder.path().join("a"))
+        .shared_value_log(vlog.clone())
+        .open_as_blob_tree()?;
+    assert!(tree_a.gc_scan_stats(seqno.get()).is_err());
+
+    let tree_b = Config::new(folder.path().join("b"))
+        .shared_value_log(vlog.clone())
+        .open_as_blob_tree()?;
+
+    tree_a.gc_scan_stats(seqno.get())?;
+    assert_eq!(0, tree_a.gc_drop_stale()?);
+    assert_eq!(2, tree_a.blobs.segment_count());
+    assert_eq!(
+        &*tree_b.get("a")?.unwrap(),
+        "saturn".repeat(10_000).as_bytes()
+    );
+
+    // NOTE: After unregistering tree B, its blobs are stale
+    drop(tree_b);
+    vlog.unregister(folder.path().join("b"))?;
+
+    tree_a.gc_scan_stats(seqno.get())?;
+    assert!(tree_a.gc_drop_stale()? > 0);
+    assert_eq!(1, tree_a.blobs.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn blob_shared_vlog_recover_ownership() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let seqno = SequenceNumberCounter::default();
+
+    {
+        let vlog = SharedValueLog::open(folder.path().join("blobs"), &Config::default())?;
+
+        let tree_a = Config::new(folder.path().join("a"))
+            .shared_value_log(vlog.clone())
+            .open_as_blob_tree()?;
+
+        let tree_b = Config::new(folder.path().join("b"))
+            .shared_value_log(vlog.clone())
+            .open_as_blob_tree()?;
+
+        tree_a.insert("a", "neptune".repeat(10_000), seqno.next());
+        tree_a.flush_active_memtable(0)?;
+
+        tree_b.insert("a", "saturn".repeat(10_000), seqno.next());
+        tree_b.flush_active_memtable(0)?;
+    }
+
+    {
+        let vlog = SharedValueLog::open(folder.path().join("blobs"), &Config::default())?;
+
+        let tree_a = Config::new(folder.path().join("a"))
+            .shared_value_log(vlog.clone())
+            .open_as_blob_tree()?;
+
+        let tree_b = Config::new(folder.path().join("b"))
+            .shared_value_log(vlog.clone())
+            .open_as_blob_tree()?;
+
+        assert_eq!(2, tree_a.blobs.segment_count());
+
+        tree_a.remove("a", seqno.next());
+
+        // NOTE: Tree A still owns its blob file after recovery
+        assert!(tree_a.gc_with_staleness_threshold(0.5, seqno.next())? > 0);
+        assert_eq!(1, tree_a.blobs.segment_count());
+
+        assert!(tree_a.get("a")?.is_none());
+        assert_eq!(
+            &*tree_b.get("a")?.unwrap(),
+            "saturn".repeat(10_000).as_bytes()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use lsm_tree::{AbstractTree, BlockCache, Config};
+use std::sync::Arc;
+use test_log::test;
+
+const ITEM_COUNT: u64 = 10_000;
+
+#[test]
+fn block_cache_resize() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let block_cache = Arc::new(BlockCache::with_capacity_bytes(16 * 1_024 * 1_024));
+
+    let tree = Config::new(&folder)
+        .block_cache(block_cache.clone())
+        .open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "abc".repeat(10), x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    for x in 0..ITEM_COUNT {
+        assert!(tree.get(x.to_be_bytes())?.is_some());
+    }
+    assert!(block_cache.size() > 64 * 1_024);
+
+    block_cache.set_capacity(64 * 1_024);
+    assert_eq!(64 * 1_024, block_cache.capacity());
+    assert!(block_cache.size() <= 64 * 1_024);
+
+    block_cache.set_capacity(0);
+    assert!(block_cache.is_empty());
+
+    for x in 0..ITEM_COUNT {
+        assert!(tree.get(x.to_be_bytes())?.is_some());
+    }
+    assert!(block_cache.is_empty());
+
+    block_cache.set_capacity(16 * 1_024 * 1_024);
+
+    for x in 0..ITEM_COUNT {
+        assert!(tree.get(x.to_be_bytes())?.is_some());
+    }
+    assert!(!block_cache.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+//! Helpers that are shared between integration tests
+
+#![allow(dead_code)]
+
+#[cfg(feature = "encryption")]
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// XORs bytes with a keystream derived from the key ID, good enough for testing
+#[cfg(feature = "encryption")]
+pub struct XorCipher {
+    current_key_id: AtomicU32,
+}
+
+#[cfg(feature = "encryption")]
+impl XorCipher {
+    pub fn new(key_id: u32) -> Self {
+        Self {
+            current_key_id: AtomicU32::new(key_id),
+        }
+    }
+
+    /// Switches the key that is used to encrypt new blocks
+    pub fn rotate(&self, key_id: u32) {
+        self.current_key_id.store(key_id, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl lsm_tree::BlockCipher for XorCipher {
+    fn current_key_id(&self) -> u32 {
+        self.current_key_id.load(Ordering::Relaxed)
+    }
+
+    fn encrypt(
+        &self,
+        key_id: u32,
+        context: lsm_tree::BlockContext,
+        plaintext: &[u8],
+    ) -> std::io::Result<Vec<u8>> {
+        let mut ciphertext = plaintext
+            .iter()
+            .map(|b| b ^ (0xAA ^ key_id as u8))
+            .collect::<Vec<_>>();
+
+        // NOTE: Stands in for the authentication tag of a real cipher
+        ciphertext.extend_from_slice(&context.segment_id.to_be_bytes());
+        ciphertext.extend_from_slice(&context.offset.to_be_bytes());
+
+        Ok(ciphertext)
+    }
+
+    fn decrypt(
+        &self,
+        key_id: u32,
+        context: lsm_tree::BlockContext,
+        ciphertext: &[u8],
+    ) -> std::io::Result<Vec<u8>> {
+        let mut tag = context.segment_id.to_be_bytes().to_vec();
+        tag.extend_from_slice(&context.offset.to_be_bytes());
+
+        let Some(ciphertext) = ciphertext.strip_suffix(tag.as_slice()) else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "block was encrypted for another position",
+            ));
+        };
+
+        Ok(ciphertext
+            .iter()
+            .map(|b| b ^ (0xAA ^ key_id as u8))
+            .collect())
+    }
+}
@@ -0,0 +1,167 @@
+#![cfg(feature = "failpoints")]
+
+use lsm_tree::{
+    failpoints::{self, Action},
+    AbstractTree, BlockCache, Config, SyncMode,
+};
+use std::sync::{Arc, Mutex};
+use test_log::test;
+
+// NOTE: Fail points are global, so tests must not run concurrently
+static LOCK: Mutex<()> = Mutex::new(());
+
+const ERROR: Action = Action::Error(std::io::ErrorKind::Other);
+
+#[test]
+fn failpoints_segment_write() -> lsm_tree::Result<()> {
+    let _guard = LOCK.lock().expect("lock is poisoned");
+    failpoints::disable_all();
+
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder).open()?;
+
+        tree.insert("a", "a", 0);
+        tree.flush_active_memtable(0)?;
+
+        tree.insert("b", "b", 1);
+
+        failpoints::enable(failpoints::SEGMENT_WRITE, ERROR);
+        assert!(tree.flush_active_memtable(0).is_err());
+        failpoints::disable_all();
+
+        // NOTE: Sealed memtable is still readable
+        assert!(tree.contains_key("b")?);
+        assert_eq!(1, tree.segment_count());
+    }
+
+    {
+        let tree = Config::new(&folder).open()?;
+        assert!(tree.contains_key("a")?);
+        assert!(!tree.contains_key("b")?);
+        assert_eq!(1, tree.segment_count());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn failpoints_manifest_commit() -> lsm_tree::Result<()> {
+    let _guard = LOCK.lock().expect("lock is poisoned");
+    failpoints::disable_all();
+
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder).open()?;
+
+        for (seqno, key) in ["a", "b", "c"].into_iter().enumerate() {
+            tree.insert(key, key, seqno as u64);
+            tree.flush_active_memtable(0)?;
+        }
+        assert_eq!(3, tree.segment_count());
+
+        failpoints::enable(failpoints::MANIFEST_COMMIT, ERROR);
+        assert!(tree.major_compact(u64::MAX, 0).is_err());
+        failpoints::disable_all();
+
+        assert_eq!(3, tree.segment_count());
+        assert_eq!(3, tree.len()?);
+    }
+
+    {
+        let tree = Config::new(&folder).open()?;
+        assert_eq!(3, tree.segment_count());
+        assert_eq!(3, tree.len()?);
+
+        tree.major_compact(u64::MAX, 0)?;
+        assert_eq!(1, tree.segment_count());
+        assert_eq!(3, tree.len()?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn failpoints_fsync() -> lsm_tree::Result<()> {
+    let _guard = LOCK.lock().expect("lock is poisoned");
+    failpoints::disable_all();
+
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "a", 0);
+
+    failpoints::enable(failpoints::FSYNC, ERROR);
+    assert!(tree.flush_active_memtable(0).is_err());
+    failpoints::disable_all();
+
+    assert!(tree.contains_key("a")?);
+    assert_eq!(0, tree.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn failpoints_fsync_sync_mode_never() -> lsm_tree::Result<()> {
+    let _guard = LOCK.lock().expect("lock is poisoned");
+    failpoints::disable_all();
+
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).sync_mode(SyncMode::Never).open()?;
+
+    tree.insert("a", "a", 0);
+
+    // NOTE: Segments are synced before they are added to the tree, whatever the sync mode
+    failpoints::enable(failpoints::FSYNC, ERROR);
+    assert!(tree.flush_active_memtable(0).is_err());
+    failpoints::disable_all();
+
+    assert!(tree.contains_key("a")?);
+    assert_eq!(0, tree.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn failpoints_block_read() -> lsm_tree::Result<()> {
+    let _guard = LOCK.lock().expect("lock is poisoned");
+    failpoints::disable_all();
+
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .block_cache(Arc::new(BlockCache::with_capacity_bytes(0)))
+        .open()?;
+
+    tree.insert("a", "a", 0);
+    tree.flush_active_memtable(0)?;
+
+    let segment_id = tree
+        .levels
+        .read()
+        .expect("lock is poisoned")
+        .iter()
+        .map(|x| x.metadata.id)
+        .next();
+
+    failpoints::enable(failpoints::BLOCK_READ, ERROR);
+    let err = tree.get("a").expect_err("should fail");
+    failpoints::disable_all();
+
+    // NOTE: Context is attached, without hiding the kind of error
+    assert!(matches!(
+        &err,
+        lsm_tree::Error::Io(e) if e.kind() == std::io::ErrorKind::Other
+    ));
+    let context = err.context().expect("should have context");
+    assert_eq!(Some(lsm_tree::Operation::Read), context.operation);
+    assert_eq!(segment_id, context.segment_id);
+
+    assert!(tree.get("a")?.is_some());
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use lsm_tree::{compaction::Leveled, AbstractTree, Config, MemoryTree, Tree};
+use std::sync::Arc;
+use test_log::test;
+
+/// Simple xorshift PRNG, so the test is reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+fn assert_same_view(tree: &Tree, reference: &MemoryTree, seqno: u64) -> lsm_tree::Result<()> {
+    let actual = tree
+        .iter_with_seqno(seqno, None)
+        .collect::<Result<Vec<_>, _>>()?;
+    let expected = reference
+        .iter_with_seqno(seqno, None)
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(expected, actual, "full scan differs at seqno {seqno}");
+
+    let actual = tree
+        .range_with_seqno("k10".."k35", seqno, None)
+        .rev()
+        .collect::<Result<Vec<_>, _>>()?;
+    let expected = reference
+        .range_with_seqno("k10".."k35", seqno, None)
+        .rev()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(expected, actual, "reverse range differs at seqno {seqno}");
+
+    let actual = tree
+        .prefix_with_seqno("k4", seqno, None)
+        .collect::<Result<Vec<_>, _>>()?;
+    let expected = reference
+        .prefix_with_seqno("k4", seqno, None)
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(expected, actual, "prefix differs at seqno {seqno}");
+
+    for x in 0..64 {
+        let key = format!("k{x}");
+        assert_eq!(
+            reference.get_with_seqno(&key, seqno)?,
+            tree.get_with_seqno(&key, seqno)?,
+            "point read of {key} differs at seqno {seqno}",
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn memory_tree_differential() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).data_block_size(1_024).open()?;
+    let reference = MemoryTree::new();
+
+    let mut rng = Rng(0x5EED);
+
+    for seqno in 0..5_000 {
+        let key = format!("k{}", rng.next() % 64);
+
+        match rng.next() % 10 {
+            0..=6 => {
+                let value = format!("v{seqno}");
+                tree.insert(&key, &value, seqno);
+                reference.insert(&key, &value, seqno);
+            }
+            7 | 8 => {
+                tree.remove(&key, seqno);
+                reference.remove(&key, seqno);
+            }
+            _ => {
+                tree.remove_weak(&key, seqno);
+                reference.remove_weak(&key, seqno);
+            }
+        }
+
+        if seqno % 500 == 499 {
+            tree.flush_active_memtable(0)?;
+        }
+
+        if seqno % 1_500 == 1_499 {
+            // NOTE: Without GC, compactions must not change what is visible
+            tree.compact(Arc::new(Leveled::default()), 0)?;
+        }
+    }
+
+    for seqno in [0, 1, 250, 499, 500, 1_777, 3_000, 4_999, 5_000, u64::MAX] {
+        assert_same_view(&tree, &reference, seqno)?;
+    }
+
+    assert_eq!(reference.len()?, tree.len()?);
+    assert_eq!(reference.get_highest_seqno(), tree.get_highest_seqno());
+
+    Ok(())
+}
+
+#[test]
+fn memory_tree_snapshot() -> lsm_tree::Result<()> {
+    let tree = MemoryTree::new();
+
+    tree.insert("a", "1", 0);
+    tree.insert("b", "1", 1);
+
+    let snapshot = tree.snapshot(2);
+
+    tree.insert("a", "2", 2);
+    tree.remove("b", 3);
+    tree.insert("c", "2", 4);
+
+    assert_eq!(2, snapshot.len()?);
+    assert_eq!(Some("1".as_bytes().into()), snapshot.get("a")?);
+    assert!(snapshot.contains_key("b")?);
+    assert!(!snapshot.contains_key("c")?);
+
+    assert_eq!(2, tree.len()?);
+    assert_eq!(Some("2".as_bytes().into()), tree.get("a")?);
+    assert!(!tree.contains_key("b")?);
+
+    Ok(())
+}
+
+#[test]
+fn memory_tree_as_abstract_tree() -> lsm_tree::Result<()> {
+    fn check<T: AbstractTree>(tree: &T) -> lsm_tree::Result<()> {
+        tree.insert("a", "1", 0);
+        assert!(tree.rotate_memtable().is_none());
+
+        assert_eq!(0, tree.segment_count());
+        assert_eq!(1, tree.len()?);
+        assert_eq!(Some(0), tree.get_highest_seqno());
+
+        Ok(())
+    }
+
+    check(&MemoryTree::new())
+}
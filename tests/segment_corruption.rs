@@ -0,0 +1,174 @@
+use lsm_tree::{
+    segment::{block::header::Header as BlockHeader, trailer::SegmentFileTrailer},
+    AbstractTree, Config,
+};
+use std::{
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 100;
+
+fn write_segment(folder: &Path) -> lsm_tree::Result<PathBuf> {
+    let tree = Config::new(folder).open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "abc", x);
+    }
+    tree.flush_active_memtable(0)?;
+    assert_eq!(1, tree.segment_count());
+
+    let segment_id = tree
+        .levels
+        .read()
+        .expect("lock is poisoned")
+        .iter()
+        .map(|x| x.metadata.id)
+        .next()
+        .expect("should have segment");
+
+    Ok(folder.join("segments").join(segment_id.to_string()))
+}
+
+fn flip_byte(path: &Path, pos: u64) -> lsm_tree::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)?;
+
+    let mut byte = [0; 1];
+    file.seek(SeekFrom::Start(pos))?;
+    file.read_exact(&mut byte)?;
+
+    byte[0] ^= 0xFF;
+
+    file.seek(SeekFrom::Start(pos))?;
+    file.write_all(&byte)?;
+    file.sync_all()?;
+
+    Ok(())
+}
+
+#[test]
+fn segment_corruption_metadata() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let segment_path = write_segment(folder.path())?;
+
+    let trailer = SegmentFileTrailer::from_file(&segment_path, None)?;
+    assert!(trailer.checksummed_sections);
+
+    // NOTE: Skip length & checksum of section
+    flip_byte(&segment_path, trailer.offsets.metadata_ptr + 12)?;
+
+    assert!(matches!(
+        Config::new(&folder).open(),
+        Err(lsm_tree::Error::InvalidChecksum(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "bloom")]
+fn segment_corruption_bloom_filter() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let segment_path = write_segment(folder.path())?;
+
+    let trailer = SegmentFileTrailer::from_file(&segment_path, None)?;
+
+    // NOTE: Skip length & checksum of section
+    flip_byte(&segment_path, trailer.offsets.bloom_ptr + 12)?;
+
+    assert!(matches!(
+        Config::new(&folder).open(),
+        Err(lsm_tree::Error::InvalidChecksum(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn segment_corruption_block_index() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let segment_path = write_segment(folder.path())?;
+
+    let trailer = SegmentFileTrailer::from_file(&segment_path, None)?;
+
+    flip_byte(
+        &segment_path,
+        trailer.offsets.tli_ptr + BlockHeader::serialized_len() as u64,
+    )?;
+
+    assert!(matches!(
+        Config::new(&folder).open(),
+        Err(lsm_tree::Error::InvalidChecksum(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn segment_corruption_data_block_context() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let segment_path = write_segment(folder.path())?;
+
+    let segment_id = segment_path
+        .file_name()
+        .and_then(|x| x.to_str())
+        .and_then(|x| x.parse().ok())
+        .expect("should be segment ID");
+
+    // NOTE: Corrupt the header of the first data block
+    flip_byte(&segment_path, 0)?;
+
+    let tree = Config::new(&folder).open()?;
+
+    let err = tree.get(0u64.to_be_bytes()).expect_err("should fail");
+    assert!(err.is_corruption());
+    assert!(!err.is_io());
+
+    let context = err.context().expect("should have context");
+    assert_eq!(Some(lsm_tree::Operation::Read), context.operation);
+    assert_eq!(Some(segment_id), context.segment_id);
+    assert_eq!(Some(0), context.block_offset);
+
+    Ok(())
+}
+
+#[test]
+fn segment_corruption_data_block_offset() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let segment_path = write_segment(folder.path())?;
+
+    // NOTE: Corrupt the header of the first data block
+    flip_byte(&segment_path, 0)?;
+
+    let tree = Config::new(&folder).open()?;
+
+    let err = tree.get(0u64.to_be_bytes()).expect_err("should fail");
+    assert!(err.is_corruption());
+    assert!(!err.is_io());
+
+    // NOTE: The error describes which block is corrupted, and the header that was found
+    let lsm_tree::Error::Decode(lsm_tree::DecodeError::At {
+        name: "Block",
+        offset: 0,
+        source,
+        ..
+    }) = &err
+    else {
+        panic!("should describe the block: {err:?}");
+    };
+
+    assert!(matches!(
+        &**source,
+        lsm_tree::DecodeError::InvalidHeader {
+            name: "Block",
+            expected: b"LSM\x02",
+            found,
+        } if found.as_slice() != b"LSM\x02"
+    ));
+
+    Ok(())
+}
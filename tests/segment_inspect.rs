@@ -0,0 +1,56 @@
+use lsm_tree::{segment::inspect, AbstractTree, Config};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 1_000;
+
+#[test]
+fn segment_inspect() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).data_block_size(1_024).open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "abc", x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let segment_id = tree
+        .levels
+        .read()
+        .expect("lock is poisoned")
+        .iter()
+        .map(|x| x.metadata.id)
+        .next()
+        .expect("should have segment");
+
+    let segment_path = folder.path().join("segments").join(segment_id.to_string());
+
+    let inspector = inspect(&segment_path)?;
+    assert_eq!(segment_id, inspector.metadata.id);
+    assert_eq!(ITEM_COUNT, inspector.metadata.item_count);
+    assert_eq!(
+        inspector.metadata.data_block_count as usize,
+        inspector.block_handles.len(),
+    );
+    assert!(inspector.block_handles.len() > 1);
+
+    let mut item_count = 0;
+
+    for block in inspector.blocks()? {
+        let block = block?;
+
+        let last_key = &block
+            .items
+            .last()
+            .expect("should not be empty")
+            .key
+            .user_key;
+        assert_eq!(&block.handle.end_key, last_key);
+
+        item_count += block.items.len();
+    }
+
+    assert_eq!(ITEM_COUNT as usize, item_count);
+
+    Ok(())
+}
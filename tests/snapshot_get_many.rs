@@ -0,0 +1,167 @@
+use lsm_tree::{AbstractTree, Config, UserValue};
+use test_log::test;
+
+#[test]
+fn snapshot_get_many() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for x in 0..100u64 {
+        tree.insert(x.to_be_bytes(), x.to_string(), 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    tree.insert(5u64.to_be_bytes(), "new", 1);
+    tree.remove(7u64.to_be_bytes(), 1);
+    tree.insert(1_000u64.to_be_bytes(), "1000", 1);
+
+    let keys = [7u64, 1_000, 5, 3, 5, 2_000].map(u64::to_be_bytes);
+
+    assert_eq!(
+        vec![
+            Some("7".as_bytes().into()),
+            None,
+            Some("5".as_bytes().into()),
+            Some("3".as_bytes().into()),
+            Some("5".as_bytes().into()),
+            None,
+        ],
+        tree.snapshot(1).get_many(&keys)?,
+    );
+
+    let snapshot = tree.snapshot(2);
+
+    let expected: Vec<Option<UserValue>> = vec![
+        None,
+        Some("1000".as_bytes().into()),
+        Some("new".as_bytes().into()),
+        Some("3".as_bytes().into()),
+        Some("new".as_bytes().into()),
+        None,
+    ];
+    assert_eq!(expected, snapshot.get_many(&keys)?);
+
+    // NOTE: Not compacted, because compacting into the last level evicts the tombstone
+    tree.flush_active_memtable(0)?;
+    assert_eq!(expected, snapshot.get_many(&keys)?);
+
+    assert!(snapshot.get_many::<&[u8]>(&[])?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn snapshot_get_many_shares_blocks() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for x in 0..50u64 {
+        tree.insert(x.to_be_bytes(), "a", 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let keys = (0..50u64).map(u64::to_be_bytes).collect::<Vec<_>>();
+    let snapshot = tree.snapshot(1);
+
+    let lookups = || tree.config.block_cache.hits() + tree.config.block_cache.misses();
+
+    let before = lookups();
+    for key in &keys {
+        assert!(snapshot.get(key)?.is_some());
+    }
+    let single_lookups = lookups() - before;
+
+    let before = lookups();
+    let values = snapshot.get_many(&keys)?;
+    let batch_lookups = lookups() - before;
+
+    assert!(values.iter().all(Option::is_some));
+    assert!(
+        batch_lookups < single_lookups,
+        "batch: {batch_lookups}, single: {single_lookups}",
+    );
+
+    Ok(())
+}
+
+#[test]
+fn snapshot_get_many_concurrent_writes() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).data_block_size(1_024).open()?;
+
+    for x in 0..200u64 {
+        tree.insert(x.to_be_bytes(), "old", 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let snapshot = tree.snapshot(1);
+    let keys = (0..200u64).map(u64::to_be_bytes).collect::<Vec<_>>();
+
+    let writer = {
+        let tree = tree.clone();
+
+        std::thread::spawn(move || {
+            for seqno in 1..10 {
+                for x in 0..200u64 {
+                    tree.insert(x.to_be_bytes(), "new", seqno);
+                }
+                tree.flush_active_memtable(0)?;
+            }
+            tree.major_compact(u64::MAX, 0)
+        })
+    };
+
+    let expected: Option<UserValue> = Some("old".as_bytes().into());
+
+    while !writer.is_finished() {
+        assert!(snapshot
+            .get_many(&keys)?
+            .iter()
+            .all(|value| *value == expected));
+    }
+    writer.join().expect("should join")?;
+
+    assert!(snapshot
+        .get_many(&keys)?
+        .iter()
+        .all(|value| *value == expected));
+
+    Ok(())
+}
+
+#[test]
+fn snapshot_get_many_cached_older_version() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "old", 0);
+    tree.insert("a", "new", 5);
+    tree.insert("b", "b", 0);
+    tree.flush_active_memtable(0)?;
+
+    // NOTE: Load the data block into the block cache
+    assert_eq!(Some("new".as_bytes().into()), tree.get("a")?);
+
+    let hits = tree.config.block_cache.hits();
+
+    assert_eq!(
+        vec![
+            Some("old".as_bytes().into()),
+            Some("b".as_bytes().into()),
+            None
+        ],
+        tree.snapshot(1).get_many(&["a", "b", "c"])?,
+    );
+    assert!(tree.config.block_cache.hits() > hits);
+
+    assert_eq!(
+        vec![Some("new".as_bytes().into()), None],
+        tree.snapshot(6).get_many(&["a", "0"])?,
+    );
+
+    Ok(())
+}
@@ -0,0 +1,289 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_absorb() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    tree.insert("a", "old", 0);
+    tree.insert("b", "old", 1);
+    tree.flush_active_memtable(0)?;
+
+    let other = Config::new(&other_folder).open()?;
+
+    other.insert("b", "new", 0);
+    other.flush_active_memtable(0)?;
+
+    other.insert("x", "new", 1);
+    other.insert("y", "new", 2);
+    other.flush_active_memtable(0)?;
+
+    other.insert("z", "new", 3);
+
+    tree.absorb(&other, 2)?;
+
+    assert_eq!(3, tree.segment_count());
+    assert_eq!(5, tree.len()?);
+
+    assert_eq!(b"old", &*tree.get("a")?.unwrap());
+    assert_eq!(b"new", &*tree.get("b")?.unwrap());
+    assert_eq!(b"new", &*tree.get("x")?.unwrap());
+    assert_eq!(b"new", &*tree.get("z")?.unwrap());
+    assert_eq!(Some(5), tree.get_highest_seqno());
+
+    // NOTE: Seqnos of absorbed items are offset
+    assert_eq!(b"old", &*tree.snapshot(2).get("b")?.unwrap());
+    assert!(tree.snapshot(2).get("x")?.is_none());
+
+    // NOTE: The other tree is unchanged
+    assert_eq!(4, other.len()?);
+    drop(other);
+
+    tree.flush_active_memtable(0)?;
+    drop(tree);
+
+    let tree = Config::new(&folder).open()?;
+    assert_eq!(5, tree.len()?);
+    assert_eq!(b"new", &*tree.get("y")?.unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn tree_absorb_keeps_version_order() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    tree.insert("c", "old", 0);
+    tree.flush_active_memtable(0)?;
+
+    let other = Config::new(&other_folder).open()?;
+
+    other.insert("a", "old", 0);
+    other.insert("d", "old", 1);
+    other.flush_active_memtable(0)?;
+
+    // NOTE: Newer version of a key in another segment
+    other.insert("a", "new", 2);
+    other.flush_active_memtable(0)?;
+
+    tree.absorb(&other, 10)?;
+
+    assert_eq!(3, tree.segment_count());
+    assert_eq!(b"new", &*tree.get("a")?.unwrap());
+    assert_eq!(b"old", &*tree.get("d")?.unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn tree_absorb_itself() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    tree.insert("a", "old", 0);
+    tree.flush_active_memtable(0)?;
+
+    assert!(tree.absorb(&tree.clone(), 1).is_err());
+    assert_eq!(1, tree.segment_count());
+
+    Ok(())
+}
+
+#[test]
+fn tree_absorb_snapshot_isolation() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    tree.insert("a", "old", 0);
+    tree.flush_active_memtable(0)?;
+
+    let other = Config::new(&other_folder).open()?;
+    other.insert("x", "new", 0);
+    other.flush_active_memtable(0)?;
+
+    other.insert("z", "new", 1);
+
+    let snapshot = tree.snapshot(1);
+
+    tree.absorb(&other, 5)?;
+
+    assert_eq!(b"new", &*tree.get("x")?.unwrap());
+    assert_eq!(b"new", &*tree.get("z")?.unwrap());
+
+    // NOTE: Absorbed items get the seqno offset, so the older snapshot does not see them
+    assert_eq!(b"old", &*snapshot.get("a")?.unwrap());
+    assert!(snapshot.get("x")?.is_none());
+    assert!(snapshot.get("z")?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn tree_absorb_link_segments() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    tree.insert("a", "old", 0);
+    tree.flush_active_memtable(0)?;
+
+    let other = Config::new(&other_folder).open()?;
+    other.insert("x", "new", 1);
+    other.flush_active_memtable(0)?;
+
+    // NOTE: Seqnos do not change, so the segment is linked
+    tree.absorb(&other, 0)?;
+
+    assert_eq!(2, tree.segment_count());
+    assert_eq!(b"new", &*tree.get("x")?.unwrap());
+    assert!(tree.snapshot(1).get("x")?.is_none());
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn tree_absorb_link_segments_same_file() -> lsm_tree::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    tree.insert("a", "old", 0);
+    tree.flush_active_memtable(0)?;
+
+    // NOTE: Segments with non-overlapping key ranges
+    let other = Config::new(&other_folder).open()?;
+    other.insert("x", "new", 1);
+    other.flush_active_memtable(0)?;
+    other.insert("y", "new", 2);
+    other.flush_active_memtable(0)?;
+
+    tree.absorb(&other, 0)?;
+    assert_eq!(3, tree.segment_count());
+
+    let inodes = |path: &std::path::Path| -> lsm_tree::Result<Vec<u64>> {
+        std::fs::read_dir(path.join("segments"))?
+            .map(|entry| Ok(entry?.metadata()?.ino()))
+            .collect()
+    };
+
+    let other_inodes = inodes(other_folder.path())?;
+    let tree_inodes = inodes(folder.path())?;
+    assert_eq!(2, other_inodes.len());
+
+    // NOTE: The segment files are the same files, not rewritten copies
+    for inode in other_inodes {
+        assert!(tree_inodes.contains(&inode));
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn tree_absorb_link_segments_with_seqno_offset() -> lsm_tree::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    tree.insert("a", "old", 0);
+    tree.flush_active_memtable(0)?;
+
+    let other = Config::new(&other_folder).open()?;
+    other.insert("x", "new", 1);
+    other.flush_active_memtable(0)?;
+
+    tree.absorb(&other, 10)?;
+    assert_eq!(2, tree.segment_count());
+
+    let inodes = |path: &std::path::Path| -> lsm_tree::Result<Vec<u64>> {
+        std::fs::read_dir(path.join("segments"))?
+            .map(|entry| Ok(entry?.metadata()?.ino()))
+            .collect()
+    };
+
+    // NOTE: The segment file is linked, not rewritten, even though its seqnos change
+    let other_inodes = inodes(other_folder.path())?;
+    assert_eq!(1, other_inodes.len());
+    assert!(inodes(folder.path())?.contains(&other_inodes[0]));
+
+    assert_eq!(Some(11), tree.get_highest_seqno());
+    assert!(tree.snapshot(11).get("x")?.is_none());
+    assert_eq!(b"new", &*tree.snapshot(12).get("x")?.unwrap());
+    assert_eq!(
+        vec![b"new".to_vec()],
+        tree.snapshot(12)
+            .range("w"..="y")
+            .map(|item| item.map(|(_, v)| v.to_vec()))
+            .collect::<lsm_tree::Result<Vec<_>>>()?,
+    );
+
+    // NOTE: The seqno offset is persisted
+    drop(tree);
+    let tree = Config::new(&folder).open()?;
+    assert_eq!(Some(11), tree.get_highest_seqno());
+    assert!(tree.snapshot(11).get("x")?.is_none());
+
+    // NOTE: Compaction rewrites the items with the adjusted seqnos
+    tree.major_compact(u64::MAX, 12)?;
+    assert_eq!(1, tree.segment_count());
+    assert_eq!(Some(11), tree.get_highest_seqno());
+    assert!(tree.snapshot(11).get("x")?.is_none());
+    assert_eq!(b"new", &*tree.snapshot(12).get("x")?.unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn tree_absorb_seqno_overflow() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    let other = Config::new(&other_folder).open()?;
+    other.insert("x", "new", 1);
+    other.flush_active_memtable(0)?;
+
+    assert!(tree.absorb(&other, u64::MAX).is_err());
+    assert_eq!(0, tree.segment_count());
+    assert!(tree.get("x")?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn tree_absorb_each_other() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    tree.insert("a", "a", 0);
+    tree.flush_active_memtable(0)?;
+
+    let other = Config::new(&other_folder).open()?;
+    other.insert("b", "b", 0);
+    other.flush_active_memtable(0)?;
+
+    std::thread::scope(|s| {
+        let a = s.spawn(|| tree.absorb(&other, 10));
+        let b = s.spawn(|| other.absorb(&tree, 10));
+
+        a.join().expect("should join")?;
+        b.join().expect("should join")
+    })?;
+
+    assert!(tree.get("b")?.is_some());
+    assert!(other.get("a")?.is_some());
+
+    Ok(())
+}
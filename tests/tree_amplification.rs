@@ -0,0 +1,41 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_amplification() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    let report = tree.amplification();
+    assert_eq!(1, report.read_amp);
+    assert_eq!(0, report.total_bytes);
+    assert_eq!(0, report.ingested_bytes);
+
+    for key in 0u64..100 {
+        tree.insert(key.to_be_bytes(), "abc", 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    // NOTE: Overwrite all keys, shadowing the first segment
+    for key in 0u64..100 {
+        tree.insert(key.to_be_bytes(), "def", 1);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let report = tree.amplification();
+    assert_eq!(3, report.read_amp);
+    assert!(report.ingested_bytes > 0);
+    assert_eq!(report.total_bytes, report.written_bytes);
+    assert!(report.space_amp() > 1.5);
+
+    tree.major_compact(u64::MAX, 2)?;
+
+    let report = tree.amplification();
+    assert_eq!(2, report.read_amp);
+    assert_eq!(report.total_bytes, report.live_bytes);
+    assert!(report.written_bytes > report.total_bytes);
+    assert!(report.write_amp() > 1.0);
+
+    Ok(())
+}
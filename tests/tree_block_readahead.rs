@@ -0,0 +1,73 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 10_000;
+
+#[test]
+fn tree_block_readahead_scan() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder).data_block_size(1_024).open()?;
+
+        for x in 0..ITEM_COUNT {
+            tree.insert(x.to_be_bytes(), "abc".repeat(10), x);
+        }
+        tree.flush_active_memtable(0)?;
+    }
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .block_readahead(8)
+        .open()?;
+
+    let mut expected = 0u64;
+
+    for item in tree.iter() {
+        let (key, _) = item?;
+        assert_eq!(&*key, expected.to_be_bytes());
+        expected += 1;
+    }
+    assert_eq!(ITEM_COUNT, expected);
+
+    assert_eq!(
+        ITEM_COUNT,
+        tree.iter().rev().count() as u64,
+        "reverse scans should not be affected",
+    );
+
+    assert_eq!(
+        500,
+        tree.range(1_000u64.to_be_bytes()..1_500u64.to_be_bytes())
+            .count(),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tree_block_readahead_without_read_ahead_threads() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .block_readahead(8)
+        .read_ahead_threads(0)
+        .open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "abc".repeat(10), x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let mut expected = 0u64;
+
+    for item in tree.iter() {
+        let (key, _) = item?;
+        assert_eq!(&*key, expected.to_be_bytes());
+        expected += 1;
+    }
+    assert_eq!(ITEM_COUNT, expected);
+
+    Ok(())
+}
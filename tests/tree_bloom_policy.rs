@@ -0,0 +1,121 @@
+#![cfg(feature = "bloom")]
+
+use lsm_tree::{AbstractTree, BloomConstructionPolicy, Config};
+use test_log::test;
+
+fn bloom_filter_size(tree: &lsm_tree::Tree) -> usize {
+    tree.levels
+        .read()
+        .expect("lock is poisoned")
+        .iter()
+        .map(|segment| segment.bloom_filter_size())
+        .sum()
+}
+
+#[test]
+fn tree_bloom_level_policies() -> lsm_tree::Result<()> {
+    let mut sizes = vec![];
+
+    for policy in [
+        BloomConstructionPolicy::BitsPerKey(2),
+        BloomConstructionPolicy::BitsPerKey(20),
+        BloomConstructionPolicy::FpRate(0.000_001),
+    ] {
+        let folder = tempfile::tempdir()?;
+
+        let tree = Config::new(&folder)
+            .bloom_level_policies(vec![policy])
+            .open()?;
+
+        for x in 0..10_000u64 {
+            tree.insert(x.to_be_bytes(), "a", 0);
+        }
+        tree.flush_active_memtable(0)?;
+
+        sizes.push(bloom_filter_size(&tree));
+
+        for x in 0..10_000u64 {
+            assert!(tree.contains_key(x.to_be_bytes())?);
+        }
+    }
+
+    assert!(sizes.windows(2).all(|w| w[0] < w[1]), "{sizes:?}");
+
+    Ok(())
+}
+
+#[test]
+fn tree_bloom_level_policies_reopen() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let small = {
+        let tree = Config::new(&folder)
+            .bloom_level_policies(vec![BloomConstructionPolicy::BitsPerKey(2)])
+            .open()?;
+
+        for x in 0..1_000u64 {
+            tree.insert(x.to_be_bytes(), "a", 0);
+        }
+        tree.flush_active_memtable(0)?;
+
+        bloom_filter_size(&tree)
+    };
+
+    // NOTE: Segments keep the bloom filter they were written with
+    let tree = Config::new(&folder)
+        .bloom_level_policies(vec![BloomConstructionPolicy::BitsPerKey(20)])
+        .open()?;
+    assert_eq!(small, bloom_filter_size(&tree));
+
+    for x in 1_000..2_000u64 {
+        tree.insert(x.to_be_bytes(), "a", 1);
+    }
+    tree.flush_active_memtable(0)?;
+
+    assert!(bloom_filter_size(&tree) > small * 5);
+
+    Ok(())
+}
+
+#[test]
+fn tree_bloom_hash_functions() -> lsm_tree::Result<()> {
+    let mut false_positives = vec![];
+
+    for k in [None, Some(1)] {
+        let folder = tempfile::tempdir()?;
+
+        let mut config = Config::new(&folder)
+            .bloom_level_policies(vec![BloomConstructionPolicy::BitsPerKey(10)]);
+
+        if let Some(k) = k {
+            config = config.bloom_hash_functions(k);
+        }
+
+        let tree = config.open()?;
+
+        for x in (0..20_000u64).step_by(2) {
+            tree.insert(x.to_be_bytes(), "a", 0);
+        }
+        tree.flush_active_memtable(0)?;
+
+        for x in (0..20_000u64).step_by(2) {
+            assert!(tree.contains_key(x.to_be_bytes())?);
+        }
+
+        // NOTE: Odd keys are inside the segment's key range, but not in the segment
+        for x in (1..20_000u64).step_by(2) {
+            assert!(tree.get(x.to_be_bytes())?.is_none());
+        }
+
+        let l0 = tree.level_stats().first().cloned().expect("should exist");
+        false_positives.push(l0.bloom_false_positives);
+    }
+
+    // NOTE: A single hash function raises the false positive rate from ~1% to ~10%
+    assert!(
+        false_positives[0] * 3 < false_positives[1],
+        "{false_positives:?}",
+    );
+
+    Ok(())
+}
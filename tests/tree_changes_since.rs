@@ -0,0 +1,69 @@
+use lsm_tree::{AbstractTree, Config, SeqNo};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 1_000;
+
+fn changed_seqnos(tree: &lsm_tree::Tree, seqno: SeqNo) -> lsm_tree::Result<Vec<SeqNo>> {
+    let mut seqnos = tree
+        .changes_since(seqno)
+        .map(|item| item.map(|item| item.key.seqno))
+        .collect::<lsm_tree::Result<Vec<_>>>()?;
+    seqnos.sort_unstable();
+    Ok(seqnos)
+}
+
+fn run(seqno_index: bool) -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let config = || {
+        Config::new(&folder)
+            .data_block_size(1_024)
+            .seqno_index(seqno_index)
+    };
+
+    let tree = config().open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "a".repeat(50), x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    tree.remove(0u64.to_be_bytes(), ITEM_COUNT);
+    tree.flush_active_memtable(0)?;
+
+    tree.insert(5u64.to_be_bytes(), "b", ITEM_COUNT + 1);
+
+    assert_eq!(
+        (900..=ITEM_COUNT + 1).collect::<Vec<_>>(),
+        changed_seqnos(&tree, 900)?
+    );
+    assert_eq!(vec![ITEM_COUNT + 1], changed_seqnos(&tree, ITEM_COUNT + 1)?);
+    assert!(changed_seqnos(&tree, ITEM_COUNT + 2)?.is_empty());
+
+    let tombstone = tree
+        .changes_since(ITEM_COUNT)
+        .find_map(|item| item.ok().filter(|item| item.key.seqno == ITEM_COUNT))
+        .expect("should exist");
+    assert!(tombstone.is_tombstone());
+
+    drop(tree);
+
+    // NOTE: The memtable is not recovered
+    let tree = config().open()?;
+    assert_eq!(
+        (500..=ITEM_COUNT).collect::<Vec<_>>(),
+        changed_seqnos(&tree, 500)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tree_changes_since() -> lsm_tree::Result<()> {
+    run(false)
+}
+
+#[test]
+fn tree_changes_since_seqno_index() -> lsm_tree::Result<()> {
+    run(true)
+}
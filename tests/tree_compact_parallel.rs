@@ -0,0 +1,37 @@
+use lsm_tree::{compaction::Leveled, AbstractTree, Config};
+use std::sync::Arc;
+use test_log::test;
+
+const ITEM_COUNT: u64 = 10_000;
+
+#[test]
+fn tree_compact_parallel() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    for batch in 0..8 {
+        for x in 0..ITEM_COUNT {
+            tree.insert(x.to_be_bytes(), batch.to_string(), batch);
+        }
+        tree.flush_active_memtable(0)?;
+    }
+    assert_eq!(8, tree.segment_count());
+
+    let strategy = Arc::new(Leveled {
+        target_size: 32 * 1_024,
+        ..Default::default()
+    });
+    tree.compact_parallel(strategy, 0, 4)?;
+
+    // NOTE: The first level has been compacted away
+    assert_eq!(0, tree.first_level_segment_count());
+    assert_eq!(ITEM_COUNT as usize, tree.len()?);
+    assert_eq!(b"7", &*tree.get(0u64.to_be_bytes())?.unwrap());
+
+    drop(tree);
+
+    let tree = Config::new(&folder).open()?;
+    assert_eq!(ITEM_COUNT as usize, tree.len()?);
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use lsm_tree::{metrics, metrics::MetricsSink, AbstractTree, BlockCache, Config};
+use std::sync::{
+    atomic::{AtomicU64, Ordering::Relaxed},
+    Arc,
+};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 10_000;
+const HOT_COUNT: u64 = 100;
+
+#[derive(Default)]
+struct MissCounter(AtomicU64);
+
+impl MetricsSink for MissCounter {
+    fn counter(&self, name: &'static str, value: u64) {
+        if name == metrics::BLOCK_CACHE_MISSES {
+            self.0.fetch_add(value, Relaxed);
+        }
+    }
+
+    fn gauge(&self, _: &'static str, _: u64) {}
+
+    fn histogram(&self, _: &'static str, _: u64) {}
+}
+
+fn read_hot_keys_after_compaction(warming: bool) -> lsm_tree::Result<(u64, usize)> {
+    let folder = tempfile::tempdir()?;
+
+    let sink = Arc::new(MissCounter::default());
+    let block_cache = Arc::new(BlockCache::with_capacity_bytes(64 * 1_024 * 1_024));
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .block_cache(block_cache.clone())
+        .metrics_sink(sink.clone())
+        .compaction_cache_warming(warming)
+        .open()?;
+
+    for seqno in 0..2 {
+        for x in 0..ITEM_COUNT {
+            tree.insert(x.to_be_bytes(), seqno.to_string().repeat(10), seqno);
+        }
+        tree.flush_active_memtable(0)?;
+    }
+
+    for x in 0..HOT_COUNT {
+        assert!(tree.contains_key(x.to_be_bytes())?);
+    }
+    let cached_blocks = block_cache.len();
+
+    tree.major_compact(u64::MAX, 2)?;
+    assert_eq!(1, tree.segment_count());
+
+    let warmed_blocks = block_cache.len() - cached_blocks;
+
+    let misses_before = sink.0.load(Relaxed);
+
+    for x in 0..HOT_COUNT {
+        assert_eq!(
+            b"1111111111",
+            &*tree.get(x.to_be_bytes())?.expect("should exist"),
+        );
+    }
+
+    Ok((sink.0.load(Relaxed) - misses_before, warmed_blocks))
+}
+
+#[test]
+fn tree_compaction_cache_warming() -> lsm_tree::Result<()> {
+    let (misses, warmed_blocks) = read_hot_keys_after_compaction(true)?;
+    assert_eq!(0, misses);
+    assert!(warmed_blocks > 0);
+
+    // NOTE: Only the hot key range is carried forward
+    assert!(warmed_blocks < 20);
+
+    Ok(())
+}
+
+#[test]
+fn tree_compaction_cache_warming_disabled() -> lsm_tree::Result<()> {
+    let (misses, warmed_blocks) = read_hot_keys_after_compaction(false)?;
+    assert!(misses > 0);
+    assert_eq!(0, warmed_blocks);
+
+    Ok(())
+}
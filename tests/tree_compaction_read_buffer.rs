@@ -0,0 +1,44 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 10_000;
+
+#[test]
+fn tree_compaction_read_buffer() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder).data_block_size(1_024).open()?;
+
+        for seqno in 0..2 {
+            for x in 0..ITEM_COUNT {
+                tree.insert(x.to_be_bytes(), seqno.to_string().repeat(10), seqno);
+            }
+            tree.flush_active_memtable(0)?;
+        }
+    }
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .compaction_read_buffer_size(4_096)
+        .open()?;
+    assert_eq!(2, tree.segment_count());
+
+    tree.major_compact(u64::MAX, 0)?;
+    assert_eq!(1, tree.segment_count());
+
+    // NOTE: The input data blocks are streamed, and not put into the block cache
+    assert!(tree.tree_config().block_cache.len() < 100);
+
+    let mut expected = 0u64;
+
+    for item in tree.iter() {
+        let (key, value) = item?;
+        assert_eq!(&*key, expected.to_be_bytes());
+        assert_eq!(&*value, "1".repeat(10).as_bytes());
+        expected += 1;
+    }
+    assert_eq!(ITEM_COUNT, expected);
+
+    Ok(())
+}
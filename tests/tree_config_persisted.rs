@@ -0,0 +1,74 @@
+use lsm_tree::{AbstractTree, CompressionType, Config};
+use test_log::test;
+
+#[test]
+fn tree_config_persisted() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder)
+            .compression(CompressionType::None)
+            .data_block_size(8_192)
+            .open()?;
+
+        tree.insert("a", "abc", 0);
+        tree.flush_active_memtable(0)?;
+    }
+
+    {
+        let tree = Config::new(&folder).open()?;
+        assert_eq!(8_192, tree.config.data_block_size);
+        assert_eq!(CompressionType::None, tree.config.compression);
+        assert_eq!(1, tree.len()?);
+    }
+
+    {
+        let tree = Config::new(&folder)
+            .compression(CompressionType::None)
+            .data_block_size(8_192)
+            .open()?;
+        assert_eq!(1, tree.len()?);
+    }
+
+    // NOTE: Segments store their own block layout, so the block size can be changed
+    {
+        let tree = Config::new(&folder).data_block_size(4_096).open()?;
+        assert_eq!(4_096, tree.config.data_block_size);
+        assert_eq!(1, tree.len()?);
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "lz4")]
+fn tree_config_compression_change() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder).open()?;
+        tree.insert("a", "abc", 0);
+        tree.flush_active_memtable(0)?;
+    }
+
+    // NOTE: Segments record their own compression, so the compression can be changed
+    {
+        let tree = Config::new(&folder)
+            .compression(CompressionType::Lz4)
+            .open()?;
+        assert_eq!(CompressionType::Lz4, tree.compression());
+
+        tree.insert("b", "def", 1);
+        tree.flush_active_memtable(0)?;
+        assert_eq!(2, tree.len()?);
+    }
+
+    // NOTE: Without a configured compression, the tree uses the compression it was created with
+    {
+        let tree = Config::new(&folder).open()?;
+        assert_eq!(CompressionType::None, tree.compression());
+        assert_eq!(2, tree.len()?);
+    }
+
+    Ok(())
+}
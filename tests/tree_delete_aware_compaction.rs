@@ -0,0 +1,191 @@
+use lsm_tree::{
+    compaction::{Choice, CompactionStrategy, DeleteAware, Input},
+    level_manifest::LevelManifest,
+    AbstractTree, Config,
+};
+use std::sync::Arc;
+use test_log::test;
+
+/// Moves all L0 segments into the destination level, without rewriting them
+struct MoveDown(u8);
+
+impl CompactionStrategy for MoveDown {
+    fn choose(&self, levels: &LevelManifest, _: &Config) -> Choice {
+        let resolved_view = levels.resolved_view();
+
+        Choice::Move(Input {
+            segment_ids: resolved_view
+                .first()
+                .expect("L0 should exist")
+                .segments
+                .iter()
+                .map(|x| x.metadata.id)
+                .collect(),
+            dest_level: self.0,
+            target_size: u64::MAX,
+        })
+    }
+}
+
+fn level_tombstone_counts(tree: &lsm_tree::Tree, level: usize) -> Vec<u64> {
+    tree.levels
+        .read()
+        .expect("lock is poisoned")
+        .levels
+        .get(level)
+        .expect("level should exist")
+        .segments
+        .iter()
+        .map(|x| x.metadata.tombstone_count)
+        .collect()
+}
+
+#[test]
+fn tree_delete_aware_compaction() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    // NOTE: Base data in the last level
+    for x in 0..1_000u64 {
+        tree.insert(x.to_be_bytes(), "old", 0);
+    }
+    tree.flush_active_memtable(0)?;
+    tree.compact(Arc::new(MoveDown(6)), 0)?;
+
+    // NOTE: Segment without deletions in L5
+    for x in 2_000..3_000u64 {
+        tree.insert(x.to_be_bytes(), "new", 1);
+    }
+    tree.flush_active_memtable(0)?;
+    tree.compact(Arc::new(MoveDown(5)), 0)?;
+
+    // NOTE: Segment dense with deletions in L5, overlapping the last level
+    for x in 0..500u64 {
+        tree.remove(x.to_be_bytes(), 2);
+    }
+    tree.flush_active_memtable(0)?;
+    tree.compact(Arc::new(MoveDown(5)), 0)?;
+
+    let mut counts = level_tombstone_counts(&tree, 5);
+    counts.sort_unstable();
+    assert_eq!(vec![0, 500], counts);
+    assert_eq!(1_500, tree.len()?);
+
+    tree.compact(Arc::new(DeleteAware::default()), 3)?;
+
+    // NOTE: Only the segment with tombstones was merged down, dropping the tombstones
+    assert_eq!(vec![0], level_tombstone_counts(&tree, 5));
+    assert_eq!(vec![0], level_tombstone_counts(&tree, 6));
+    assert_eq!(1_500, tree.len()?);
+
+    // NOTE: There are no tombstones left, so there is nothing to reclaim
+    let segment_count = tree.segment_count();
+    tree.compact(Arc::new(DeleteAware::default()), 3)?;
+    assert_eq!(segment_count, tree.segment_count());
+
+    for x in 0..500u64 {
+        assert!(tree.get(x.to_be_bytes())?.is_none());
+    }
+    for x in 500..1_000u64 {
+        assert_eq!(b"old", &*tree.get(x.to_be_bytes())?.expect("should exist"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tree_delete_aware_compaction_skip_upper_levels() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for x in 0..1_000u64 {
+        tree.insert(x.to_be_bytes(), "old", 0);
+    }
+    tree.flush_active_memtable(0)?;
+    tree.compact(Arc::new(MoveDown(2)), 0)?;
+
+    for x in 0..500u64 {
+        tree.remove(x.to_be_bytes(), 1);
+    }
+    tree.flush_active_memtable(0)?;
+    tree.compact(Arc::new(MoveDown(1)), 0)?;
+
+    // NOTE: The tombstones shadow the items in the deeper level
+    assert_eq!(500, tree.len()?);
+
+    // NOTE: The tombstones would not be dropped by merging them into L2, so nothing is compacted
+    tree.compact(Arc::new(DeleteAware::default()), 3)?;
+    assert_eq!(vec![500], level_tombstone_counts(&tree, 1));
+    assert_eq!(500, tree.len()?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_delete_aware_compaction_last_level() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for x in 0..1_000u64 {
+        tree.insert(x.to_be_bytes(), "old", 0);
+    }
+    for x in 0..500u64 {
+        tree.remove(x.to_be_bytes(), 1);
+    }
+    tree.flush_active_memtable(0)?;
+
+    // NOTE: Moving keeps the tombstones, even though they are in the last level now
+    tree.compact(Arc::new(MoveDown(6)), 0)?;
+    assert_eq!(vec![500], level_tombstone_counts(&tree, 6));
+
+    tree.compact(Arc::new(DeleteAware::default()), 3)?;
+
+    assert_eq!(vec![0], level_tombstone_counts(&tree, 6));
+    assert_eq!(500, tree.len()?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_delete_aware_compaction_overlapping_level() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for x in 0..1_000u64 {
+        tree.insert(x.to_be_bytes(), "base", 0);
+    }
+    tree.flush_active_memtable(0)?;
+    tree.compact(Arc::new(MoveDown(6)), 0)?;
+
+    for x in 0..500u64 {
+        tree.insert(x.to_be_bytes(), "old", 1);
+    }
+    tree.flush_active_memtable(0)?;
+    tree.compact(Arc::new(MoveDown(5)), 0)?;
+
+    // NOTE: Overlaps with the other segment in L5, so L5 is not disjoint
+    for x in 0..500u64 {
+        tree.remove(x.to_be_bytes(), 2);
+    }
+    tree.flush_active_memtable(0)?;
+    tree.compact(Arc::new(MoveDown(5)), 0)?;
+
+    assert_eq!(500, tree.len()?);
+
+    tree.compact(Arc::new(DeleteAware::default()), 3)?;
+
+    // NOTE: Both overlapping segments were merged down, so the older versions were not left behind
+    assert!(level_tombstone_counts(&tree, 5).is_empty());
+    assert_eq!(vec![0], level_tombstone_counts(&tree, 6));
+    assert_eq!(500, tree.len()?);
+
+    for x in 0..500u64 {
+        assert!(tree.get(x.to_be_bytes())?.is_none());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use lsm_tree::{compaction::Leveled, AbstractTree, Config};
+use std::{collections::BTreeMap, path::Path, sync::Arc};
+use test_log::test;
+
+fn read_all_files(folder: &Path) -> std::io::Result<BTreeMap<String, Vec<u8>>> {
+    let mut files = BTreeMap::new();
+    let mut stack = vec![folder.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        for dirent in std::fs::read_dir(dir)? {
+            let path = dirent?.path();
+
+            if path.is_dir() {
+                stack.push(path);
+            } else {
+                let name = path
+                    .strip_prefix(folder)
+                    .expect("should be in folder")
+                    .to_string_lossy()
+                    .to_string();
+
+                files.insert(name, std::fs::read(&path)?);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn build_tree(folder: &Path, seed: u64) -> lsm_tree::Result<()> {
+    let tree = Config::new(folder)
+        .data_block_size(1_024)
+        .deterministic(seed)
+        .open()?;
+
+    let mut seqno = 0;
+
+    for batch in 0..10u64 {
+        for x in 0..1_000u64 {
+            tree.insert((x * 7 + batch).to_be_bytes(), batch.to_string(), seqno);
+            seqno += 1;
+        }
+        tree.flush_active_memtable(0)?;
+    }
+
+    tree.compact_parallel(
+        Arc::new(Leveled {
+            target_size: 16 * 1_024,
+            ..Default::default()
+        }),
+        0,
+        4,
+    )?;
+
+    for segment in tree.levels.read().expect("lock is poisoned").iter() {
+        assert_eq!(u128::from(segment.metadata.id), segment.metadata.created_at);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tree_deterministic() -> lsm_tree::Result<()> {
+    let a = tempfile::tempdir()?;
+    let b = tempfile::tempdir()?;
+    let c = tempfile::tempdir()?;
+
+    build_tree(a.path(), 42)?;
+    build_tree(b.path(), 42)?;
+    build_tree(c.path(), 43)?;
+
+    let a = read_all_files(a.path())?;
+    let b = read_all_files(b.path())?;
+    let c = read_all_files(c.path())?;
+
+    assert!(a.len() > 2);
+    assert_eq!(a, b);
+
+    // NOTE: Different seed, different tree ID
+    assert_ne!(a, c);
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 10_000;
+
+#[test]
+#[allow(clippy::cast_precision_loss)]
+fn tree_distinct_key_estimate() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "a", 0);
+    }
+    let a = tree.flush_active_memtable(0)?.expect("should flush");
+
+    for x in (ITEM_COUNT / 2)..(ITEM_COUNT * 3 / 2) {
+        tree.insert(x.to_be_bytes(), "b", 1);
+    }
+    let b = tree.flush_active_memtable(0)?.expect("should flush");
+
+    let a_sketch = a.metadata.key_sketch.as_ref().expect("should have sketch");
+    let b_sketch = b.metadata.key_sketch.as_ref().expect("should have sketch");
+
+    let overlap = a_sketch.intersection_estimate(b_sketch) as f64;
+    assert!(
+        (overlap - 5_000.0).abs() / 5_000.0 < 0.3,
+        "overlap={overlap}"
+    );
+
+    let estimate = tree.distinct_key_estimate() as f64;
+    assert!(
+        (estimate - 15_000.0).abs() / 15_000.0 < 0.1,
+        "estimate={estimate}"
+    );
+
+    let level_estimates = tree.level_distinct_key_estimates();
+    assert_eq!(7, level_estimates.len());
+    assert_eq!(Some(&tree.distinct_key_estimate()), level_estimates.first());
+    assert!(level_estimates.iter().skip(1).all(|&x| x == 0));
+
+    drop(tree);
+
+    let tree = Config::new(&folder).open()?;
+    assert_eq!(estimate as u64, tree.distinct_key_estimate());
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use lsm_tree::{AbstractTree, Config, MemoryTree};
+use std::sync::Arc;
+use test_log::test;
+
+fn exercise(tree: &dyn AbstractTree) -> lsm_tree::Result<()> {
+    tree.insert_bytes(b"a", b"1", 0, lsm_tree::ValueType::Value);
+    tree.insert_bytes(b"b", b"2", 1, lsm_tree::ValueType::Value);
+    tree.insert_bytes(b"a", &[], 2, lsm_tree::ValueType::Tombstone);
+
+    assert_eq!(None, tree.get_bytes(b"a", None)?);
+    assert_eq!(Some("1".as_bytes().into()), tree.get_bytes(b"a", Some(2))?);
+    assert!(tree.contains_key_bytes(b"b", None)?);
+    assert_eq!(1, tree.len()?);
+
+    Ok(())
+}
+
+fn open_trees(folder: &std::path::Path) -> lsm_tree::Result<Vec<Box<dyn AbstractTree>>> {
+    Ok(vec![
+        Box::new(Config::new(folder.join("lsm")).open()?),
+        Box::new(Config::new(folder.join("blob")).open_as_blob_tree()?),
+        Box::new(MemoryTree::new()),
+    ])
+}
+
+#[test]
+fn tree_dyn_object_safe() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    for tree in open_trees(folder.path())? {
+        exercise(&*tree)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tree_dyn_boxed() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    for tree in open_trees(folder.path())? {
+        tree.insert("a", "1", 0);
+        tree.insert("ab", "2", 1);
+        tree.insert("b", "3", 2);
+        tree.remove("b", 3);
+
+        assert_eq!(Some("1".as_bytes().into()), tree.get("a")?);
+        assert!(!tree.contains_key("b")?);
+        assert!(tree.contains_key_with_seqno("b", 3)?);
+        assert_eq!(2, tree.prefix("a").count());
+        assert_eq!(1, tree.range("aa"..).count());
+        assert_eq!(3, tree.iter_with_seqno(3, None).count());
+
+        let snapshot = tree.snapshot(2);
+        tree.insert("c", "4", 4);
+        assert_eq!(2, snapshot.len()?);
+        assert_eq!(3, tree.len()?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tree_dyn_shared() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree: Arc<dyn AbstractTree + Send + Sync> = Arc::new(Config::new(&folder).open()?);
+
+    let handle = {
+        let tree = tree.clone();
+        std::thread::spawn(move || tree.insert("a", "1", 0))
+    };
+    handle.join().expect("should join");
+
+    let (_, memtable) = tree.rotate_memtable().expect("should have memtable");
+    let segment = tree
+        .flush_memtable(tree.get_next_segment_id(), &memtable, 0)?
+        .expect("should flush");
+    tree.register_segments(&[segment])?;
+
+    assert_eq!(1, tree.segment_count());
+    assert_eq!(Some("1".as_bytes().into()), tree.get("a")?);
+
+    Ok(())
+}
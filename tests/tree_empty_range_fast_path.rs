@@ -0,0 +1,69 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[cfg(feature = "bloom")]
+fn block_lookups(tree: &lsm_tree::Tree) -> u64 {
+    tree.config.block_cache.hits() + tree.config.block_cache.misses()
+}
+
+#[test]
+#[cfg(feature = "bloom")]
+#[allow(clippy::reversed_empty_ranges)]
+fn tree_empty_range_fast_path() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .bloom_bits_per_key(20)
+        .prefix_fence_len(2)
+        .open()?;
+
+    // NOTE: Sparse keyspace, only even keys exist
+    for x in (0..100u64).step_by(2) {
+        tree.insert(format!("a:{x:0>3}"), "abc", x);
+        tree.insert(format!("c:{x:0>3}"), "abc", x);
+    }
+    tree.flush_active_memtable(0)?;
+    assert_eq!(1, tree.segment_count());
+
+    let before = block_lookups(&tree);
+
+    // NOTE: Empty & inverted ranges
+    assert_eq!(0, tree.range("a:010".."a:010").count());
+    assert_eq!(0, tree.range("c:000".."a:000").count());
+    assert_eq!(0, tree.range("c:000"..="a:000").rev().count());
+
+    // NOTE: Missing single keys inside the segment's key range
+    for x in (1..20u64).step_by(2) {
+        let key = format!("a:{x:0>3}");
+        assert_eq!(0, tree.range(key.clone()..=key).count());
+    }
+
+    // NOTE: Prefix between the segment's prefixes
+    assert_eq!(0, tree.prefix("b:").count());
+
+    assert_eq!(before, block_lookups(&tree));
+
+    // NOTE: Existing keys are still found
+    assert_eq!(1, tree.range("a:010"..="a:010").count());
+    assert_eq!(50, tree.prefix("c:").count());
+    assert!(block_lookups(&tree) > before);
+
+    Ok(())
+}
+
+#[test]
+#[allow(clippy::reversed_empty_ranges)]
+fn tree_empty_range_memtable() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    tree.insert("a", "abc", 0);
+    tree.insert("b", "abc", 1);
+
+    assert_eq!(0, tree.range("b".."a").count());
+    assert_eq!(0, tree.range("a".."a").count());
+    assert_eq!(1, tree.range("a"..="a").count());
+    assert_eq!(2, tree.range("a"..="b").count());
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "encryption")]
+
+mod common;
+
+use common::XorCipher;
+use lsm_tree::{AbstractTree, Config};
+use std::sync::Arc;
+use test_log::test;
+
+fn contains_subslice(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+#[test]
+fn tree_encryption() -> lsm_tree::Result<()> {
+    const ITEM_COUNT: u64 = 1_000;
+
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder)
+            .block_cipher(Arc::new(XorCipher::new(1)))
+            .open()?;
+
+        for x in 0..ITEM_COUNT {
+            tree.insert(x.to_be_bytes(), "super secret value", x);
+        }
+        tree.flush_active_memtable(0)?;
+
+        for x in 0..ITEM_COUNT {
+            tree.insert(x.to_be_bytes(), "super secret value", ITEM_COUNT + x);
+        }
+        tree.flush_active_memtable(0)?;
+
+        tree.major_compact(u64::MAX, 0)?;
+        assert_eq!(1, tree.segment_count());
+
+        assert_eq!(ITEM_COUNT as usize, tree.len()?);
+        assert_eq!(0, tree.verify()?);
+    }
+
+    for dirent in std::fs::read_dir(folder.path().join("segments"))? {
+        let bytes = std::fs::read(dirent?.path())?;
+        assert!(!contains_subslice(&bytes, b"super secret value"));
+    }
+
+    {
+        let tree = Config::new(&folder)
+            .block_cipher(Arc::new(XorCipher::new(1)))
+            .open()?;
+
+        assert_eq!(ITEM_COUNT as usize, tree.len()?);
+        assert_eq!(
+            Some("super secret value".as_bytes().into()),
+            tree.get(5_u64.to_be_bytes())?
+        );
+    }
+
+    assert!(matches!(
+        Config::new(&folder).open(),
+        Err(lsm_tree::Error::MissingCipher(1))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn tree_encryption_absorb() -> lsm_tree::Result<()> {
+    const ITEM_COUNT: u64 = 100;
+
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .block_cipher(Arc::new(XorCipher::new(1)))
+        .open()?;
+
+    tree.insert("a", "a", 0);
+    tree.flush_active_memtable(0)?;
+
+    let other = Config::new(&other_folder)
+        .block_cipher(Arc::new(XorCipher::new(1)))
+        .open()?;
+
+    for x in 0..ITEM_COUNT {
+        other.insert(x.to_be_bytes(), "super secret value", x);
+    }
+    other.flush_active_memtable(0)?;
+
+    // NOTE: The absorbed segment gets a new ID, but its blocks
+    // stay bound to the ID it was written with
+    tree.absorb(&other, 1)?;
+    assert_eq!(2, tree.segment_count());
+    assert_eq!(ITEM_COUNT as usize + 1, tree.len()?);
+    assert_eq!(0, tree.verify()?);
+    drop(tree);
+
+    let tree = Config::new(&folder)
+        .block_cipher(Arc::new(XorCipher::new(1)))
+        .open()?;
+
+    assert_eq!(ITEM_COUNT as usize + 1, tree.len()?);
+    assert_eq!(
+        Some("super secret value".as_bytes().into()),
+        tree.get(5_u64.to_be_bytes())?
+    );
+
+    tree.major_compact(u64::MAX, 0)?;
+    assert_eq!(ITEM_COUNT as usize + 1, tree.len()?);
+
+    Ok(())
+}
+
+#[test]
+fn blob_tree_encryption_unsupported() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let result = Config::new(&folder)
+        .block_cipher(Arc::new(XorCipher::new(1)))
+        .open_as_blob_tree();
+
+    assert!(matches!(
+        result,
+        Err(lsm_tree::Error::Io(e)) if e.kind() == std::io::ErrorKind::Unsupported
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "encryption")]
+
+mod common;
+
+use common::XorCipher;
+use lsm_tree::{AbstractTree, Config};
+use std::sync::Arc;
+use test_log::test;
+
+#[test]
+fn tree_encryption_key_rotation() -> lsm_tree::Result<()> {
+    const ITEM_COUNT: u64 = 100;
+
+    let folder = tempfile::tempdir()?;
+
+    let cipher = Arc::new(XorCipher::new(1));
+
+    let tree = Config::new(&folder).block_cipher(cipher.clone()).open()?;
+
+    for batch in 0..3 {
+        for x in 0..ITEM_COUNT {
+            tree.insert(
+                x.to_be_bytes(),
+                "super secret value",
+                batch * ITEM_COUNT + x,
+            );
+        }
+        tree.flush_active_memtable(0)?;
+    }
+    assert_eq!(3, tree.segment_count());
+    assert!(tree
+        .levels
+        .read()
+        .unwrap()
+        .iter()
+        .all(|x| x.key_id() == Some(1)));
+
+    // NOTE: New segments use the new key, while old segments stay readable
+    cipher.rotate(2);
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "super secret value", 3 * ITEM_COUNT + x);
+    }
+    tree.flush_active_memtable(0)?;
+    assert_eq!(4, tree.segment_count());
+    assert_eq!(ITEM_COUNT as usize, tree.len()?);
+
+    assert_eq!(0, tree.rewrite_with_key(2, 0)?);
+    assert_eq!(4, tree.segment_count());
+    assert!(tree
+        .levels
+        .read()
+        .unwrap()
+        .iter()
+        .all(|x| x.key_id() == Some(2)));
+
+    assert_eq!(ITEM_COUNT as usize, tree.len()?);
+    assert_eq!(0, tree.verify()?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_encryption_key_rotation_missing_cipher() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    tree.insert("a", "a", 0);
+    tree.flush_active_memtable(0)?;
+
+    assert!(matches!(
+        tree.rewrite_with_key(2, 0),
+        Err(lsm_tree::Error::MissingCipher(2))
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use byteorder::{BigEndian, ReadBytesExt};
+use lsm_tree::{AbstractTree, Config};
+use std::io::{Cursor, Read};
+use test_log::test;
+
+#[test]
+fn tree_export_range() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "old", 0);
+    tree.insert("a", "abc", 1);
+    tree.insert("b", "def", 2);
+    tree.flush_active_memtable(0)?;
+
+    tree.insert("c", "ghi", 3);
+    tree.remove("b", 4);
+    tree.insert("d", "jkl", 5);
+
+    let mut buf = vec![];
+    assert_eq!(2, tree.export_range("a".."d", &mut buf)?);
+
+    let mut reader = Cursor::new(buf);
+
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    assert_eq!(b"LSMTCOL1", &magic);
+
+    assert_eq!(2, reader.read_u32::<BigEndian>()?);
+
+    assert_eq!(1, reader.read_u16::<BigEndian>()?);
+    assert_eq!(1, reader.read_u16::<BigEndian>()?);
+    let mut keys = [0; 2];
+    reader.read_exact(&mut keys)?;
+    assert_eq!(b"ac", &keys);
+
+    assert_eq!(3, reader.read_u32::<BigEndian>()?);
+    assert_eq!(3, reader.read_u32::<BigEndian>()?);
+    let mut values = [0; 6];
+    reader.read_exact(&mut values)?;
+    assert_eq!(b"abcghi", &values);
+
+    assert_eq!(1, reader.read_u64::<BigEndian>()?);
+    assert_eq!(3, reader.read_u64::<BigEndian>()?);
+
+    assert_eq!(0, reader.read_u32::<BigEndian>()?);
+    assert_eq!(2, reader.read_u64::<BigEndian>()?);
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 100;
+
+#[test]
+fn tree_flush_coalesce() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    for batch in 0..4u64 {
+        for x in 0..ITEM_COUNT {
+            tree.insert(x.to_be_bytes(), batch.to_be_bytes(), batch);
+        }
+        assert!(tree.rotate_memtable().is_some());
+    }
+    assert_eq!(4, tree.sealed_memtable_count());
+
+    let segment = tree.flush_sealed_memtables(0)?.expect("should flush");
+    assert_eq!(1, tree.segment_count());
+    assert_eq!(0, tree.sealed_memtable_count());
+
+    // NOTE: All versions are kept
+    assert_eq!(ITEM_COUNT * 4, segment.metadata.item_count);
+
+    assert_eq!(ITEM_COUNT as usize, tree.len()?);
+    assert_eq!(3u64.to_be_bytes(), &*tree.get(0u64.to_be_bytes())?.unwrap());
+    assert_eq!(
+        1u64.to_be_bytes(),
+        &*tree.snapshot(2).get(0u64.to_be_bytes())?.unwrap()
+    );
+
+    assert!(tree.flush_sealed_memtables(0)?.is_none());
+
+    drop(tree);
+
+    let tree = Config::new(&folder).open()?;
+    assert_eq!(ITEM_COUNT as usize, tree.len()?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_flush_coalesce_evict() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    for batch in 0..2u64 {
+        for x in 0..ITEM_COUNT {
+            tree.insert(x.to_be_bytes(), batch.to_be_bytes(), batch);
+        }
+        assert!(tree.rotate_memtable().is_some());
+    }
+
+    // NOTE: Old versions are evicted during the merge
+    let segment = tree.flush_sealed_memtables(2)?.expect("should flush");
+    assert_eq!(ITEM_COUNT, segment.metadata.item_count);
+
+    Ok(())
+}
+
+#[test]
+fn tree_flush_coalesce_concurrent() -> lsm_tree::Result<()> {
+    const THREADS: u64 = 8;
+    const ROUNDS: u64 = 50;
+
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+    let seqno = lsm_tree::SequenceNumberCounter::default();
+
+    std::thread::scope(|s| {
+        let threads = (0..THREADS)
+            .map(|thread| {
+                let tree = &tree;
+                let seqno = &seqno;
+
+                s.spawn(move || -> lsm_tree::Result<()> {
+                    for round in 0..ROUNDS {
+                        for x in 0..ITEM_COUNT {
+                            let key = format!("{thread}:{round}:{x}");
+                            tree.insert(key, "abc", seqno.next());
+                        }
+                        tree.rotate_memtable();
+
+                        // NOTE: Coalesced flushes race each other and the per-memtable flush
+                        if round % 2 == 0 {
+                            tree.flush_sealed_memtables(0)?;
+                        } else {
+                            tree.flush_active_memtable(0)?;
+                        }
+                    }
+                    Ok(())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join().expect("thread should not panic")?;
+        }
+
+        Ok::<_, lsm_tree::Error>(())
+    })?;
+
+    while tree.flush_sealed_memtables(0)?.is_some() {}
+    assert_eq!(0, tree.sealed_memtable_count());
+
+    let expected_len = (THREADS * ROUNDS * ITEM_COUNT) as usize;
+    assert_eq!(expected_len, tree.len()?);
+
+    // NOTE: Every memtable is flushed exactly once
+    assert_eq!(expected_len, tree.approximate_len());
+
+    let segment_ids = tree
+        .levels
+        .read()
+        .expect("lock is poisoned")
+        .iter()
+        .map(|segment| segment.metadata.id)
+        .collect::<Vec<_>>();
+    let unique_ids = segment_ids.iter().collect::<std::collections::HashSet<_>>();
+    assert_eq!(segment_ids.len(), unique_ids.len());
+
+    drop(tree);
+
+    let tree = Config::new(&folder).open()?;
+    assert_eq!(expected_len, tree.len()?);
+
+    Ok(())
+}
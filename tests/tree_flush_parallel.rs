@@ -0,0 +1,48 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 10_000;
+
+#[test]
+fn tree_flush_parallel() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "abc", 0);
+        tree.insert(x.to_be_bytes(), "def", 1);
+    }
+
+    let segments = tree.flush_active_memtable_parallel(0, 4)?;
+    assert_eq!(4, segments.len());
+    assert_eq!(4, tree.segment_count());
+    assert_eq!(0, tree.sealed_memtable_count());
+
+    // NOTE: Partitions are disjoint, and all versions of a key are in the same partition
+    assert_eq!(
+        ITEM_COUNT * 2,
+        segments.iter().map(|x| x.metadata.item_count).sum::<u64>()
+    );
+    for pair in segments.windows(2) {
+        if let [a, b] = pair {
+            assert!(
+                a.metadata.key_range.1 < b.metadata.key_range.0
+                    || b.metadata.key_range.1 < a.metadata.key_range.0
+            );
+        }
+    }
+
+    assert_eq!(ITEM_COUNT as usize, tree.len()?);
+    assert_eq!(b"def", &*tree.get(0u64.to_be_bytes())?.unwrap());
+    assert_eq!(b"abc", &*tree.snapshot(1).get(0u64.to_be_bytes())?.unwrap());
+
+    assert!(tree.flush_active_memtable_parallel(0, 4)?.is_empty());
+
+    drop(tree);
+
+    let tree = Config::new(&folder).open()?;
+    assert_eq!(4, tree.segment_count());
+    assert_eq!(ITEM_COUNT as usize, tree.len()?);
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+const ITEM_COUNT: usize = 100;
+
+#[test]
+fn tree_fork() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let fork_folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for x in 0..ITEM_COUNT as u64 {
+        tree.insert(x.to_be_bytes(), "abc", 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    // NOTE: Unflushed items are part of the fork too
+    tree.insert("memtable", "abc", 1);
+
+    let fork = tree.fork(&fork_folder)?;
+    assert_eq!(1, fork.segment_count());
+    assert_eq!(ITEM_COUNT + 1, fork.len()?);
+    assert!(fork.contains_key("memtable")?);
+
+    // NOTE: Writes diverge after forking
+    tree.insert("a", "abc", 2);
+    fork.insert("b", "abc", 2);
+    fork.remove(0u64.to_be_bytes(), 3);
+
+    assert!(tree.contains_key("a")?);
+    assert!(!tree.contains_key("b")?);
+    assert!(tree.contains_key(0u64.to_be_bytes())?);
+
+    assert!(!fork.contains_key("a")?);
+    assert!(fork.contains_key("b")?);
+    assert!(!fork.contains_key(0u64.to_be_bytes())?);
+
+    // NOTE: Compacting the fork does not affect the tree's segments
+    fork.flush_active_memtable(0)?;
+    fork.major_compact(u64::MAX, 4)?;
+    drop(fork);
+
+    assert_eq!(ITEM_COUNT + 2, tree.len()?);
+
+    let fork = Config::new(&fork_folder).open()?;
+    assert_eq!(ITEM_COUNT + 1, fork.len()?);
+    assert!(fork.contains_key("b")?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_fork_occupied_folder() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let fork_folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    let _other = Config::new(&fork_folder).open()?;
+
+    assert!(tree.fork(&fork_folder).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_gc_watermark_latest() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    assert_eq!(0, tree.gc_watermark());
+
+    tree.insert("a", "old", 0);
+    tree.insert("a", "new", 1);
+    assert_eq!(2, tree.gc_watermark());
+
+    tree.flush_active_memtable_auto()?;
+
+    assert_eq!(b"new", &*tree.get("a")?.expect("should exist"));
+    assert!(tree.get_with_seqno("a", 1)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn tree_gc_watermark_floor() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    for seqno in 0..10 {
+        tree.insert("a", seqno.to_string().as_bytes(), seqno);
+    }
+    assert_eq!(10, tree.gc_watermark());
+
+    tree.set_gc_floor(5);
+    assert_eq!(5, tree.gc_watermark());
+
+    tree.flush_active_memtable_auto()?;
+
+    for seqno in 6..=10 {
+        let expected = (seqno - 1).to_string();
+        assert_eq!(
+            expected.as_bytes(),
+            &*tree.get_with_seqno("a", seqno)?.expect("should exist"),
+        );
+    }
+
+    tree.clear_gc_floor();
+    assert_eq!(10, tree.gc_watermark());
+
+    tree.major_compact_auto(u64::MAX)?;
+    assert!(tree.get_with_seqno("a", 9)?.is_none());
+    assert_eq!(b"9", &*tree.get("a")?.expect("should exist"));
+
+    Ok(())
+}
+
+#[test]
+fn tree_gc_watermark_snapshot() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "old", 0);
+    tree.insert("b", "old", 1);
+
+    let snapshot = tree.snapshot(2);
+    assert_eq!(1, tree.open_snapshot_count());
+    assert_eq!(0, tree.gc_watermark());
+
+    tree.insert("a", "new", 2);
+    tree.insert("b", "new", 3);
+
+    tree.flush_active_memtable_auto()?;
+    tree.major_compact_auto(u64::MAX)?;
+
+    assert_eq!(b"old", &*snapshot.get("a")?.expect("should exist"));
+    assert_eq!(b"old", &*snapshot.get("b")?.expect("should exist"));
+
+    let iter = snapshot.iter();
+    drop(snapshot);
+
+    // NOTE: The iterator keeps the snapshot registered
+    assert_eq!(1, tree.open_snapshot_count());
+    assert_eq!(0, tree.gc_watermark());
+
+    assert_eq!(2, iter.count());
+
+    assert_eq!(0, tree.open_snapshot_count());
+    assert_eq!(4, tree.gc_watermark());
+
+    Ok(())
+}
+
+#[test]
+fn tree_gc_watermark_snapshot_clone() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "old", 0);
+
+    let snapshot = tree.snapshot(1);
+    let cloned = snapshot.clone();
+    assert_eq!(2, tree.open_snapshot_count());
+
+    drop(snapshot);
+    assert_eq!(1, tree.open_snapshot_count());
+    assert_eq!(0, tree.gc_watermark());
+
+    drop(cloned);
+    assert_eq!(0, tree.open_snapshot_count());
+    assert_eq!(1, tree.gc_watermark());
+
+    Ok(())
+}
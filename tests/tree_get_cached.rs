@@ -0,0 +1,61 @@
+use lsm_tree::{AbstractTree, Config, SequenceNumberCounter};
+use test_log::test;
+
+#[test]
+fn tree_get_cached_deeper_level_is_not_preferred() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    let seqno = SequenceNumberCounter::default();
+
+    tree.insert("a", "old", seqno.next());
+    tree.insert("b", "b", seqno.next());
+    tree.flush_active_memtable(0)?;
+    tree.major_compact(u64::MAX, 0)?;
+
+    // NOTE: Load the data block of the deeper segment into the block cache
+    assert_eq!(Some("old".as_bytes().into()), tree.get("a")?);
+
+    tree.insert("a", "new", seqno.next());
+    tree.flush_active_memtable(0)?;
+
+    assert_eq!(Some("new".as_bytes().into()), tree.get("a")?);
+    assert_eq!(Some("new".as_bytes().into()), tree.get("a")?);
+    assert_eq!(Some("b".as_bytes().into()), tree.get("b")?);
+
+    tree.remove("a", seqno.next());
+    tree.flush_active_memtable(0)?;
+
+    assert_eq!(None, tree.get("a")?);
+    assert_eq!(None, tree.get("a")?);
+    assert_eq!(Some("new".as_bytes().into()), tree.snapshot_at(3).get("a")?);
+    assert_eq!(Some("old".as_bytes().into()), tree.snapshot_at(2).get("a")?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_get_cached_skips_uncached_segments_without_key() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for x in 0..10u64 {
+        tree.insert(x.to_be_bytes(), "a", x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    for x in 0..10u64 {
+        tree.insert((x * 2).to_be_bytes(), "b", 10 + x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    for _ in 0..2 {
+        for x in 0..10u64 {
+            let expected = if x % 2 == 0 { "b" } else { "a" };
+            assert_eq!(Some(expected.as_bytes().into()), tree.get(x.to_be_bytes())?);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_get_with() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "disk", 0);
+    tree.insert("b", "deleted", 1);
+    tree.flush_active_memtable(0)?;
+
+    tree.insert("c", "memtable", 2);
+    tree.remove("b", 3);
+
+    assert_eq!(Some(4), tree.get_with("a", <[u8]>::len)?);
+    assert_eq!(Some(true), tree.get_with("a", |value| value == b"disk")?);
+    assert_eq!(
+        Some(b"memtable".to_vec()),
+        tree.get_with("c", <[u8]>::to_vec)?
+    );
+    assert_eq!(None, tree.get_with("b", <[u8]>::len)?);
+    assert_eq!(None, tree.get_with("d", <[u8]>::len)?);
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use lsm_tree::{AbstractTree, Config, SequenceNumberCounter};
+use test_log::test;
+
+const THREADS: u64 = 8;
+const ITEM_COUNT: u64 = 1_000;
+
+#[test]
+fn tree_group_commit() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    let seqno = SequenceNumberCounter::default();
+
+    let threads = (0..THREADS)
+        .map(|thread| {
+            let tree = tree.clone();
+            let seqno = seqno.clone();
+
+            std::thread::spawn(move || -> lsm_tree::Result<()> {
+                for x in 0..ITEM_COUNT {
+                    let key = format!("{thread}:{x:0>4}");
+                    tree.insert_grouped(key, "abc", &seqno)?;
+                }
+                Ok(())
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().expect("should join")?;
+    }
+
+    assert_eq!(THREADS * ITEM_COUNT, seqno.get());
+    assert_eq!(Some(THREADS * ITEM_COUNT - 1), tree.get_highest_seqno());
+    assert_eq!(THREADS * ITEM_COUNT, tree.len()? as u64);
+
+    let (tombstone_seqno, _) = tree.remove_grouped("0:0000", &seqno)?;
+    assert_eq!(THREADS * ITEM_COUNT, tombstone_seqno);
+    assert!(tree.get("0:0000")?.is_none());
+    assert_eq!(THREADS * ITEM_COUNT - 1, tree.len()? as u64);
+
+    Ok(())
+}
+
+#[test]
+fn tree_group_commit_invalid_key() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    let seqno = SequenceNumberCounter::default();
+
+    assert!(matches!(
+        tree.insert_grouped("", "abc", &seqno),
+        Err(lsm_tree::Error::EmptyKey)
+    ));
+    assert!(matches!(
+        tree.remove_grouped(vec![0; 70_000], &seqno),
+        Err(lsm_tree::Error::KeyTooLarge(70_000))
+    ));
+
+    // NOTE: No seqno was allocated for the invalid writes
+    assert_eq!(0, seqno.get());
+
+    tree.insert_grouped("a", "abc", &seqno)?;
+    assert_eq!(1, tree.len()?);
+
+    Ok(())
+}
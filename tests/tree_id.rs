@@ -0,0 +1,56 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_id_persisted() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    let id = tree.id();
+    assert!(id.is_some());
+
+    drop(tree);
+
+    let tree = Config::new(&folder).open()?;
+    assert_eq!(id, tree.id());
+
+    let fork_folder = tempfile::tempdir()?;
+    let fork = tree.fork(&fork_folder)?;
+    assert!(fork.id().is_some());
+    assert_ne!(id, fork.id());
+
+    Ok(())
+}
+
+#[test]
+fn tree_id_mismatch() -> lsm_tree::Result<()> {
+    use lsm_tree::file::MANIFEST_FILE;
+
+    let folder = tempfile::tempdir()?;
+    let other_folder = tempfile::tempdir()?;
+    let replaced_folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    let other = Config::new(&other_folder).open()?;
+    other.insert("a", "a", 0);
+    other.flush_active_memtable(0)?;
+
+    // NOTE: Replace the other tree's folder by a different tree
+    let _replaced = Config::new(&replaced_folder).open()?;
+    std::fs::copy(
+        replaced_folder.path().join(MANIFEST_FILE),
+        other_folder.path().join(MANIFEST_FILE),
+    )?;
+
+    assert!(matches!(
+        tree.absorb(&other, 1),
+        Err(lsm_tree::Error::TreeIdMismatch)
+    ));
+    assert!(matches!(
+        other.fork(tempfile::tempdir()?),
+        Err(lsm_tree::Error::TreeIdMismatch)
+    ));
+    assert_eq!(0, tree.segment_count());
+
+    Ok(())
+}
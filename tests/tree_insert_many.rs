@@ -0,0 +1,51 @@
+use lsm_tree::{AbstractTree, Config, SequenceNumberCounter};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 1_000;
+
+#[test]
+fn tree_insert_many() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    let seqno = SequenceNumberCounter::new(5);
+
+    let memtable_size =
+        tree.insert_many((0..ITEM_COUNT).map(|x| (x.to_be_bytes(), "abc")), &seqno)?;
+
+    assert_eq!(memtable_size, tree.active_memtable_size());
+    assert_eq!(5 + ITEM_COUNT, seqno.get());
+    assert_eq!(ITEM_COUNT as usize, tree.len()?);
+    assert_eq!(Some(5 + ITEM_COUNT - 1), tree.get_highest_seqno());
+
+    // NOTE: Items are assigned seqnos in iteration order
+    assert!(tree.snapshot(6).get(0u64.to_be_bytes())?.is_some());
+    assert!(tree.snapshot(6).get(1u64.to_be_bytes())?.is_none());
+
+    assert_eq!(
+        memtable_size,
+        tree.insert_many::<&[u8], &[u8], _>([], &seqno)?
+    );
+    assert_eq!(5 + ITEM_COUNT, seqno.get());
+
+    Ok(())
+}
+
+#[test]
+fn tree_insert_many_invalid_key() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    let seqno = SequenceNumberCounter::default();
+
+    assert!(matches!(
+        tree.insert_many([("a", "abc"), ("", "abc"), ("c", "abc")], &seqno),
+        Err(lsm_tree::Error::EmptyKey)
+    ));
+
+    // NOTE: Nothing of the batch was inserted
+    assert_eq!(0, seqno.get());
+    assert!(tree.is_empty()?);
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use lsm_tree::{AbstractTree, Config, KeyRange, TreeType};
+use test_log::test;
+
+#[test]
+fn tree_inspect() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    let inspection = lsm_tree::inspect(&folder)?;
+    assert_eq!(TreeType::Standard, inspection.tree_type);
+    assert_eq!(tree.id(), inspection.id);
+    assert_eq!(0, inspection.segment_count());
+    assert_eq!(None, inspection.key_range());
+    assert_eq!(None, inspection.seqno_range());
+
+    tree.insert("b", "1", 0);
+    tree.insert("c", "1", 1);
+    tree.flush_active_memtable(0)?;
+
+    tree.insert("a", "2", 2);
+    tree.remove("d", 3);
+    tree.flush_active_memtable(0)?;
+
+    tree.compact(std::sync::Arc::new(lsm_tree::compaction::PullDown(0, 1)), 0)?;
+
+    tree.insert("e", "3", 4);
+    tree.flush_active_memtable(0)?;
+
+    let inspection = lsm_tree::inspect(&folder)?;
+    assert_eq!(tree.segment_count(), inspection.segment_count());
+    assert_eq!(tree.disk_space(), inspection.disk_space());
+    assert_eq!(5, inspection.item_count());
+    assert_eq!(1, inspection.tombstone_count());
+    assert_eq!(Some(KeyRange::from("a"..="e")), inspection.key_range());
+    assert_eq!(Some((0, 4)), inspection.seqno_range());
+
+    let l0 = inspection.levels.first().expect("should exist");
+    assert_eq!(1, l0.segments.len());
+    assert_eq!(Some((4, 4)), l0.seqno_range());
+
+    let l1 = inspection.levels.get(1).expect("should exist");
+    assert_eq!(4, l1.item_count());
+    assert_eq!(Some(KeyRange::from("a"..="d")), l1.key_range());
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use lsm_tree::{AbstractTree, BlockCache, Config};
+use std::sync::Arc;
+use test_log::test;
+
+const ITEM_COUNT: usize = 1_000;
+
+#[test]
+fn tree_iter_across_compaction() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .block_cache(Arc::new(BlockCache::with_capacity_bytes(0)))
+        .open()?;
+
+    for batch in 0..2 {
+        for x in 0..ITEM_COUNT {
+            let key = format!("{x:0>10}");
+            tree.insert(key, batch.to_string(), (batch * ITEM_COUNT + x) as u64);
+        }
+        tree.flush_active_memtable(0)?;
+    }
+    assert_eq!(2, tree.segment_count());
+
+    let mut iter = tree.iter();
+    assert!(iter.next().is_some());
+
+    let segment_file_count = std::fs::read_dir(folder.path().join("segments"))?.count();
+    assert_eq!(2, segment_file_count);
+
+    tree.major_compact(u64::MAX, u64::MAX)?;
+    assert_eq!(1, tree.segment_count());
+
+    // NOTE: The old segment files are kept for the iterator
+    assert!(tree.obsolete_segment_bytes() > 0);
+
+    let segment_file_count = std::fs::read_dir(folder.path().join("segments"))?.count();
+    assert_eq!(3, segment_file_count);
+
+    let rest = iter.by_ref().collect::<lsm_tree::Result<Vec<_>>>()?;
+    assert_eq!(ITEM_COUNT - 1, rest.len());
+
+    // NOTE: The files are deleted as soon as the iterator is dropped
+    drop(iter);
+    assert_eq!(0, tree.obsolete_segment_bytes());
+
+    let segment_file_count = std::fs::read_dir(folder.path().join("segments"))?.count();
+    assert_eq!(1, segment_file_count);
+
+    assert_eq!(ITEM_COUNT, tree.len()?);
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use lsm_tree::{AbstractTree, Config, SeqNo};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 1_000;
+
+fn seqnos<R: std::ops::RangeBounds<SeqNo>>(
+    tree: &lsm_tree::Tree,
+    range: R,
+) -> lsm_tree::Result<Vec<SeqNo>> {
+    tree.iter_by_seqno(range)
+        .map(|item| item.map(|item| item.key.seqno))
+        .collect()
+}
+
+fn run(seqno_index: bool) -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .seqno_index(seqno_index)
+        .open()?;
+
+    // NOTE: Keys are written in reverse order, so key order != seqno order
+    for x in 0..ITEM_COUNT {
+        tree.insert((ITEM_COUNT - x).to_be_bytes(), "a".repeat(50), x);
+
+        if x % 250 == 249 {
+            tree.flush_active_memtable(0)?;
+        }
+    }
+    assert_eq!(4, tree.segment_count());
+
+    tree.remove(1u64.to_be_bytes(), ITEM_COUNT);
+    tree.flush_active_memtable(0)?;
+    tree.insert(5u64.to_be_bytes(), "b", ITEM_COUNT + 1);
+
+    assert_eq!((0..=ITEM_COUNT + 1).collect::<Vec<_>>(), seqnos(&tree, ..)?);
+    assert_eq!((200..300).collect::<Vec<_>>(), seqnos(&tree, 200..300)?);
+    assert_eq!(
+        (900..=ITEM_COUNT + 1).collect::<Vec<_>>(),
+        seqnos(&tree, 900..)?
+    );
+    assert!(seqnos(&tree, ITEM_COUNT + 2..)?.is_empty());
+    assert!(seqnos(&tree, 5..5)?.is_empty());
+
+    let tombstone = tree
+        .iter_by_seqno(ITEM_COUNT..=ITEM_COUNT)
+        .next()
+        .expect("should exist")?;
+    assert!(tombstone.is_tombstone());
+
+    // NOTE: Compacted segments overlap with each other in seqno ranges
+    //
+    // The tombstone is evicted, because it is compacted into the last level
+    tree.major_compact(16_000, 0)?;
+    assert!(tree.segment_count() > 1);
+    assert_eq!(
+        (0..ITEM_COUNT)
+            .chain(std::iter::once(ITEM_COUNT + 1))
+            .collect::<Vec<_>>(),
+        seqnos(&tree, ..)?
+    );
+    assert_eq!((200..300).collect::<Vec<_>>(), seqnos(&tree, 200..300)?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_iter_by_seqno() -> lsm_tree::Result<()> {
+    run(false)
+}
+
+#[test]
+fn tree_iter_by_seqno_with_seqno_index() -> lsm_tree::Result<()> {
+    run(true)
+}
+
+#[test]
+fn tree_iter_by_seqno_batch() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("c", "abc", 1);
+    tree.insert("b", "abc", 0);
+    tree.insert("a", "abc", 1);
+    tree.flush_active_memtable(0)?;
+
+    let items = tree
+        .iter_by_seqno(..)
+        .map(|item| item.map(|item| (item.key.user_key, item.key.seqno)))
+        .collect::<lsm_tree::Result<Vec<_>>>()?;
+
+    assert_eq!(
+        vec![
+            ("b".as_bytes().into(), 0),
+            ("a".as_bytes().into(), 1),
+            ("c".as_bytes().into(), 1),
+        ],
+        items
+    );
+
+    Ok(())
+}
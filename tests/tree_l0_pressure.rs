@@ -0,0 +1,47 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_l0_pressure() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).l0_pressure_thresholds(2, 6).open()?;
+    assert_eq!(0.0, tree.l0_pressure());
+
+    let expected = [0.0, 0.0, 0.25, 0.5, 0.75, 1.0, 1.0];
+
+    for (seqno, expected) in expected.into_iter().enumerate() {
+        tree.insert("a", "abc", seqno as u64);
+        tree.flush_active_memtable(0)?;
+
+        assert_eq!(seqno + 1, tree.first_level_segment_count());
+        assert_eq!(expected, tree.l0_pressure());
+    }
+
+    tree.major_compact(u64::MAX, u64::MAX)?;
+    assert_eq!(0.0, tree.l0_pressure());
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "stop threshold should be larger than slowdown threshold")]
+fn tree_l0_pressure_invalid_thresholds() {
+    let _ = Config::new("unused").l0_pressure_thresholds(4, 4);
+}
+
+#[test]
+fn tree_l0_pressure_default_thresholds() -> lsm_tree::Result<()> {
+    use lsm_tree::compaction::{CompactionStrategy, Leveled};
+
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    // NOTE: The pressure starts to rise once L0 reaches the compaction trigger
+    let l0_threshold = Leveled::default().l0_threshold();
+    assert_eq!(Some(tree.l0_slowdown_threshold()), l0_threshold);
+    assert_eq!(2 * tree.l0_slowdown_threshold(), tree.l0_stop_threshold());
+
+    Ok(())
+}
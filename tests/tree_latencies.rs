@@ -0,0 +1,37 @@
+#![cfg(feature = "metrics")]
+
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_latency_histograms() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "abc", 0);
+    tree.insert("b", "def", 1);
+    tree.flush_active_memtable(0)?;
+    tree.insert("c", "ghi", 2);
+    tree.flush_active_memtable(0)?;
+    assert_eq!(2, tree.latencies().flush.count());
+
+    tree.major_compact(u64::MAX, 3)?;
+    assert_eq!(1, tree.latencies().compaction.count());
+
+    assert!(tree.get("a")?.is_some());
+    assert!(tree.get("d")?.is_none());
+    assert_eq!(2, tree.latencies().get.count());
+
+    assert_eq!(3, tree.iter().count());
+    assert_eq!(1, tree.iter().rev().take(1).count());
+
+    // NOTE: 3 items + end of iterator, and 1 step backwards
+    assert_eq!(5, tree.latencies().range_next.count());
+
+    let get = &tree.latencies().get;
+    assert!(get.percentile(50.0) <= get.percentile(99.0));
+    assert!(get.percentile(99.0) <= get.max());
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_level_stats() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    let stats = tree.level_stats();
+    assert_eq!(7, stats.len());
+    assert!(stats.iter().all(|x| x.reads_served == 0));
+
+    tree.insert("a", "abc", 0);
+    tree.insert("b", "def", 1);
+    tree.flush_active_memtable(0)?;
+
+    assert_eq!(&*tree.get("a")?.unwrap(), b"abc");
+
+    let l0 = tree.level_stats().first().cloned().unwrap();
+    assert_eq!(1, l0.reads_served);
+    assert_eq!(4, l0.bytes_read);
+
+    tree.major_compact(u64::MAX, 2)?;
+
+    let stats = tree.level_stats();
+    let l0 = stats.first().unwrap();
+    let l6 = stats.last().unwrap();
+    assert!(l0.compaction_bytes_in > 0);
+    assert_eq!(0, l0.compaction_bytes_out);
+    assert!(l6.compaction_bytes_out > 0);
+
+    assert_eq!(&*tree.get("b")?.unwrap(), b"def");
+    assert!(tree.get("c")?.is_none());
+
+    let l6 = tree.level_stats().last().cloned().unwrap();
+    assert_eq!(1, l6.reads_served);
+    assert_eq!(4, l6.bytes_read);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "bloom")]
+fn tree_level_stats_bloom_negatives() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "abc", 0);
+    tree.insert("z", "def", 1);
+    tree.flush_active_memtable(0)?;
+
+    // NOTE: "m" is inside the segment's key range, but not in the segment
+    assert!(tree.get("m")?.is_none());
+
+    let l0 = tree.level_stats().first().cloned().unwrap();
+    assert_eq!(1, l0.bloom_negatives);
+    assert_eq!(0, l0.reads_served);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "bloom")]
+fn tree_level_stats_bloom_negatives_cached_read() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "abc", 0);
+    tree.insert("m", "def", 1);
+    tree.insert("z", "ghi", 2);
+    tree.flush_active_memtable(0)?;
+    tree.major_compact(u64::MAX, 3)?;
+
+    // NOTE: Load the block of "m" into the block cache
+    assert_eq!(&*tree.get("m")?.unwrap(), b"def");
+
+    tree.insert("b", "abc", 3);
+    tree.insert("y", "def", 4);
+    tree.flush_active_memtable(0)?;
+
+    // NOTE: "m" is inside the key range of the L0 segment, but not in the segment,
+    // and is answered from the cached block of the last level
+    assert_eq!(&*tree.get("m")?.unwrap(), b"def");
+
+    let stats = tree.level_stats();
+    let l0 = stats.first().unwrap();
+    let l6 = stats.last().unwrap();
+    assert_eq!(1, l0.bloom_negatives);
+    assert_eq!(0, l0.bloom_false_positives);
+    assert_eq!(2, l6.reads_served);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "bloom")]
+fn tree_level_stats_bloom_snapshot_miss() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "abc", 5);
+    tree.insert("b", "def", 1);
+    tree.flush_active_memtable(0)?;
+
+    // NOTE: "a" exists, but is not visible at the snapshot, so the bloom filter was right
+    assert!(tree.get_with_seqno("a", 3)?.is_none());
+    assert!(tree.get_with_seqno("a", 3)?.is_none());
+
+    let stats = tree.level_stats();
+    let l0 = stats.first().unwrap();
+    assert_eq!(0, l0.bloom_false_positives);
+
+    Ok(())
+}
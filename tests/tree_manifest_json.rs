@@ -0,0 +1,41 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_manifest_json() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).level_count(2).open()?;
+
+    tree.insert("a", "1", 0);
+    tree.insert("b", "2", 1);
+    tree.remove("c", 2);
+    tree.flush_active_memtable(0)?;
+
+    let segment = tree
+        .levels
+        .read()
+        .expect("lock is poisoned")
+        .iter()
+        .next()
+        .cloned()
+        .expect("should exist");
+
+    let json = tree.manifest_json();
+
+    assert!(json.starts_with("{\"levels\":[{\"level\":0,\"disjoint\":true,"));
+    assert!(json.contains(&format!(
+        "{{\"id\":\"{}\",\"file_size\":\"{}\",",
+        segment.metadata.id, segment.metadata.file_size,
+    )));
+    assert!(json.contains("\"items\":\"3\",\"tombstones\":\"1\","));
+    assert!(json.contains("\"key_range\":[\"61\",\"63\"],\"seqnos\":[\"0\",\"2\"],"));
+    assert!(json.contains("\"compacting\":false}"));
+    assert!(json.ends_with("{\"level\":1,\"disjoint\":true,\"size\":\"0\",\"segments\":[]}]}"));
+
+    let mut buf = vec![];
+    tree.write_manifest_json(&mut buf)?;
+    assert_eq!(json.as_bytes(), buf);
+
+    Ok(())
+}
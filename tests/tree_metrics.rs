@@ -0,0 +1,122 @@
+use lsm_tree::{metrics, metrics::MetricsSink, AbstractTree, Config};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use test_log::test;
+
+#[derive(Default)]
+struct RecordingSink {
+    counters: Mutex<HashMap<&'static str, u64>>,
+    gauges: Mutex<HashMap<&'static str, u64>>,
+    histograms: Mutex<HashMap<&'static str, Vec<u64>>>,
+}
+
+impl RecordingSink {
+    fn counter_value(&self, name: &str) -> u64 {
+        self.counters
+            .lock()
+            .unwrap()
+            .get(name)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn gauge_value(&self, name: &str) -> Option<u64> {
+        self.gauges.lock().unwrap().get(name).copied()
+    }
+
+    fn sample_count(&self, name: &str) -> usize {
+        self.histograms
+            .lock()
+            .unwrap()
+            .get(name)
+            .map_or(0, Vec::len)
+    }
+}
+
+impl MetricsSink for RecordingSink {
+    fn counter(&self, name: &'static str, value: u64) {
+        *self.counters.lock().unwrap().entry(name).or_default() += value;
+    }
+
+    fn gauge(&self, name: &'static str, value: u64) {
+        self.gauges.lock().unwrap().insert(name, value);
+    }
+
+    fn histogram(&self, name: &'static str, value: u64) {
+        self.histograms
+            .lock()
+            .unwrap()
+            .entry(name)
+            .or_default()
+            .push(value);
+    }
+}
+
+#[test]
+fn tree_metrics_sink() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let sink = Arc::new(RecordingSink::default());
+
+    let tree = Config::new(&folder).metrics_sink(sink.clone()).open()?;
+
+    tree.insert("a", "abc", 0);
+    tree.insert("b", "def", 1);
+    assert!(sink.counter_value(metrics::BYTES_WRITTEN) > 0);
+
+    tree.flush_active_memtable(0)?;
+    assert_eq!(1, sink.counter_value(metrics::FLUSHES));
+    assert!(sink.counter_value(metrics::FLUSH_BYTES_WRITTEN) > 0);
+    assert_eq!(1, sink.sample_count(metrics::FLUSH_DURATION_MICROS));
+    assert_eq!(Some(1), sink.gauge_value(metrics::FIRST_LEVEL_SEGMENTS));
+
+    tree.insert("c", "ghi", 2);
+    tree.flush_active_memtable(0)?;
+    assert_eq!(2, sink.counter_value(metrics::FLUSHES));
+    assert_eq!(Some(2), sink.gauge_value(metrics::SEGMENTS));
+
+    tree.major_compact(u64::MAX, 3)?;
+    assert_eq!(1, sink.counter_value(metrics::COMPACTIONS));
+    assert_eq!(
+        sink.counter_value(metrics::FLUSH_BYTES_WRITTEN),
+        sink.counter_value(metrics::COMPACTION_BYTES_READ),
+    );
+    assert!(sink.counter_value(metrics::COMPACTION_BYTES_WRITTEN) > 0);
+    assert_eq!(1, sink.sample_count(metrics::COMPACTION_DURATION_MICROS));
+    assert_eq!(Some(1), sink.gauge_value(metrics::SEGMENTS));
+
+    let misses_before = sink.counter_value(metrics::BLOCK_CACHE_MISSES);
+    assert_eq!(&*tree.get("a")?.unwrap(), b"abc");
+    assert!(sink.counter_value(metrics::BLOCK_CACHE_MISSES) > misses_before);
+
+    let hits_before = sink.counter_value(metrics::BLOCK_CACHE_HITS);
+    assert_eq!(&*tree.get("b")?.unwrap(), b"def");
+    assert!(sink.counter_value(metrics::BLOCK_CACHE_HITS) > hits_before);
+
+    assert_eq!(6, sink.counter_value(metrics::BYTES_READ));
+
+    Ok(())
+}
+
+#[test]
+fn tree_metrics_sink_reload() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder).open()?;
+        tree.insert("a", "abc", 0);
+        tree.flush_active_memtable(0)?;
+    }
+
+    let sink = Arc::new(RecordingSink::default());
+
+    let tree = Config::new(&folder).metrics_sink(sink.clone()).open()?;
+
+    // NOTE: Recovered segments report to the sink, too
+    assert_eq!(&*tree.get("a")?.unwrap(), b"abc");
+    assert!(sink.counter_value(metrics::BLOCK_CACHE_MISSES) > 0);
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_mlock_index_and_filters() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder)
+            .pin_index_blocks(true)
+            .mlock_index_and_filters(true)
+            .open()?;
+
+        for x in 0_u64..1_000 {
+            tree.insert(x.to_be_bytes(), "abc", x);
+        }
+        tree.flush_active_memtable(0)?;
+        tree.major_compact(u64::MAX, 0)?;
+
+        assert!(tree.contains_key(500_u64.to_be_bytes())?);
+        assert!(!tree.contains_key(1_000_u64.to_be_bytes())?);
+    }
+
+    // NOTE: Locking is best-effort, so recovery works even if the OS refuses to lock memory
+    let tree = Config::new(&folder)
+        .pin_index_blocks(true)
+        .mlock_index_and_filters(true)
+        .open()?;
+
+    assert_eq!(1_000, tree.len()?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_mlock_index_and_filters_pins_index_blocks() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder).open()?;
+
+        for x in 0_u64..1_000 {
+            tree.insert(x.to_be_bytes(), "abc", x);
+        }
+        tree.flush_active_memtable(0)?;
+    }
+
+    let cold_read_misses = |config: Config| -> lsm_tree::Result<u64> {
+        let tree = config.open()?;
+        let misses = tree.config.block_cache.misses();
+        assert!(tree.contains_key(500_u64.to_be_bytes())?);
+        Ok(tree.config.block_cache.misses() - misses)
+    };
+
+    let unlocked = cold_read_misses(Config::new(&folder))?;
+    let locked = cold_read_misses(Config::new(&folder).mlock_index_and_filters(true))?;
+
+    // NOTE: Index blocks are pinned (and locked), so they are not read through the block cache
+    assert!(locked < unlocked, "locked: {locked}, unlocked: {unlocked}");
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+fn segment_index_types(tree: &lsm_tree::Tree) -> Vec<bool> {
+    let mut types = tree
+        .levels
+        .read()
+        .expect("lock is poisoned")
+        .iter()
+        .map(|segment| segment.metadata.one_level_index)
+        .collect::<Vec<_>>();
+
+    types.sort_unstable();
+    types
+}
+
+#[test]
+fn tree_one_level_index() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder)
+            .data_block_size(1_024)
+            .index_block_size(1_024)
+            .one_level_index_max_size(4_096)
+            .open()?;
+
+        // NOTE: Small segment
+        for x in 0..100u64 {
+            tree.insert(x.to_be_bytes(), "abc", 0);
+        }
+        tree.flush_active_memtable(0)?;
+
+        // NOTE: Large segment
+        for x in 100..100_000u64 {
+            tree.insert(x.to_be_bytes(), "abc", 0);
+        }
+        tree.flush_active_memtable(0)?;
+
+        assert_eq!(vec![false, true], segment_index_types(&tree));
+
+        for x in 0..100_000u64 {
+            assert!(tree.contains_key(x.to_be_bytes())?);
+        }
+    }
+
+    // NOTE: The choice is stored in the segment metadata
+    let tree = Config::new(&folder).open()?;
+    assert_eq!(vec![false, true], segment_index_types(&tree));
+
+    for x in 0..100_000u64 {
+        assert!(tree.contains_key(x.to_be_bytes())?);
+    }
+    assert_eq!(100_000, tree.iter().count());
+
+    Ok(())
+}
+
+#[test]
+fn tree_one_level_index_disabled() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "abc", 0);
+    tree.flush_active_memtable(0)?;
+
+    assert_eq!(vec![false], segment_index_types(&tree));
+
+    Ok(())
+}
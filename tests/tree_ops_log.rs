@@ -0,0 +1,52 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_ops_log() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).ops_log(1_000_000).open()?;
+
+    tree.insert("a", "abc", 0);
+    tree.flush_active_memtable(0)?;
+
+    tree.insert("b", "def", 1);
+    tree.flush_active_memtable(0)?;
+
+    tree.major_compact(u64::MAX, 2)?;
+
+    let log = std::fs::read_to_string(folder.path().join("ops.log"))?;
+    let lines = log.lines().collect::<Vec<_>>();
+
+    assert_eq!(
+        2,
+        lines
+            .iter()
+            .filter(|x| x.contains("\"event\":\"flush\""))
+            .count()
+    );
+    assert_eq!(
+        1,
+        lines
+            .iter()
+            .filter(|x| x.contains("\"event\":\"compaction\""))
+            .count()
+    );
+    assert!(lines.iter().all(|x| x.starts_with('{') && x.ends_with('}')));
+
+    Ok(())
+}
+
+#[test]
+fn tree_ops_log_disabled() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "abc", 0);
+    tree.flush_active_memtable(0)?;
+
+    assert!(!folder.path().join("ops.log").try_exists()?);
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use lsm_tree::{AbstractTree, Config, ReadOptions};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 1_000;
+const PAGE_SIZE: usize = 50;
+
+#[test]
+fn tree_pagination() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .block_readahead(8)
+        .open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "a".repeat(50), x);
+
+        if x % 300 == 299 {
+            tree.flush_active_memtable(0)?;
+        }
+    }
+
+    // NOTE: Tombstones in between must not count towards the limit
+    for x in (0..ITEM_COUNT).step_by(10) {
+        tree.remove(x.to_be_bytes(), ITEM_COUNT + x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let mut cursor: Option<Vec<u8>> = None;
+    let mut seen = 0;
+    let mut pages = 0;
+
+    loop {
+        let mut options = ReadOptions::default().limit(PAGE_SIZE);
+
+        if let Some(cursor) = &cursor {
+            options = options.start_after(cursor.as_slice());
+        }
+
+        let page = tree
+            .range_with_options(10u64.to_be_bytes().., &options)
+            .collect::<lsm_tree::Result<Vec<_>>>()?;
+
+        assert!(page.len() <= PAGE_SIZE);
+
+        let Some((last_key, _)) = page.last() else {
+            break;
+        };
+
+        for (key, _) in &page {
+            let key = u64::from_be_bytes((**key).try_into().expect("should be u64"));
+            assert!(key >= 10);
+            assert_ne!(0, key % 10);
+        }
+
+        cursor = Some(last_key.to_vec());
+        seen += page.len();
+        pages += 1;
+    }
+
+    assert_eq!(891, seen);
+    assert_eq!(18, pages);
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use lsm_tree::{AbstractTree, Config, ScanOrder};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 10_000;
+
+#[test]
+fn tree_par_range() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .index_block_size(1_024)
+        .open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "abc", x);
+
+        if x % 2_500 == 0 {
+            tree.flush_active_memtable(0)?;
+        }
+    }
+
+    let expected = tree.iter().collect::<lsm_tree::Result<Vec<_>>>()?;
+    assert_eq!(ITEM_COUNT as usize, expected.len());
+
+    let sorted = tree
+        .par_range::<&[u8], _>(.., 4, ScanOrder::Sorted)?
+        .collect::<lsm_tree::Result<Vec<_>>>()?;
+    assert_eq!(expected, sorted);
+
+    let mut unsorted = tree
+        .par_range::<&[u8], _>(.., 4, ScanOrder::Unsorted)?
+        .collect::<lsm_tree::Result<Vec<_>>>()?;
+    unsorted.sort();
+    assert_eq!(expected, unsorted);
+
+    let range = 1_000u64.to_be_bytes()..5_000u64.to_be_bytes();
+    let expected = tree
+        .range(range.clone())
+        .collect::<lsm_tree::Result<Vec<_>>>()?;
+    let sorted = tree
+        .par_range(range, 3, ScanOrder::Sorted)?
+        .collect::<lsm_tree::Result<Vec<_>>>()?;
+    assert_eq!(4_000, sorted.len());
+    assert_eq!(expected, sorted);
+
+    assert_eq!(
+        ITEM_COUNT as usize,
+        tree.par_range::<&[u8], _>(.., 1, ScanOrder::Sorted)?
+            .count()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tree_par_range_snapshot() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let tree = Config::new(&folder).open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "abc", x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let scan = tree.par_range::<&[u8], _>(.., 2, ScanOrder::Sorted)?;
+
+    tree.insert(ITEM_COUNT.to_be_bytes(), "abc", ITEM_COUNT);
+
+    assert_eq!(ITEM_COUNT as usize, scan.count());
+
+    Ok(())
+}
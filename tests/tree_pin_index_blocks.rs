@@ -0,0 +1,59 @@
+use lsm_tree::{AbstractTree, BlockCache, Config};
+use std::sync::Arc;
+use test_log::test;
+
+const SEGMENT_COUNT: u64 = 10;
+const ITEM_COUNT: u64 = 100;
+
+#[test]
+fn tree_pin_index_blocks() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder)
+            .data_block_size(1_024)
+            .index_block_size(1_024)
+            .pin_index_blocks(true)
+            .block_cache(Arc::new(BlockCache::with_capacity_bytes(0)))
+            .open()?;
+
+        for segment in 0..SEGMENT_COUNT {
+            for x in 0..ITEM_COUNT {
+                let key = (segment * ITEM_COUNT + x).to_be_bytes();
+                tree.insert(key, "a".repeat(100), segment);
+            }
+            tree.flush_active_memtable(0)?;
+        }
+
+        assert_eq!((SEGMENT_COUNT * ITEM_COUNT) as usize, tree.len()?);
+    }
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .index_block_size(1_024)
+        .pin_index_blocks(true)
+        .block_cache(Arc::new(BlockCache::with_capacity_bytes(0)))
+        .open()?;
+
+    assert_eq!(SEGMENT_COUNT as usize, tree.segment_count());
+
+    let key = (5 * ITEM_COUNT + 50).to_be_bytes();
+    assert!(tree.contains_key(key)?);
+    assert!(!tree.contains_key(u64::MAX.to_be_bytes())?);
+
+    assert_eq!((SEGMENT_COUNT * ITEM_COUNT) as usize, tree.len()?);
+    assert_eq!(
+        150,
+        tree.range(250u64.to_be_bytes()..400u64.to_be_bytes())
+            .count()
+    );
+    assert_eq!(
+        (SEGMENT_COUNT * ITEM_COUNT) as usize,
+        tree.iter().rev().count()
+    );
+
+    tree.major_compact(u64::MAX, 0)?;
+    assert_eq!((SEGMENT_COUNT * ITEM_COUNT) as usize, tree.len()?);
+
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use lsm_tree::{AbstractTree, BlockCache, Config};
+use std::sync::Arc;
+use test_log::test;
+
+const ITEM_COUNT: usize = 1_000;
+
+#[test]
+fn tree_pin_range_accounting() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let block_cache = Arc::new(BlockCache::with_capacity_bytes(1_000_000));
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .block_cache(block_cache.clone())
+        .open()?;
+
+    for x in 0..10 {
+        tree.insert(format!("config:{x}"), "abc", 0);
+    }
+    for x in 0..ITEM_COUNT {
+        tree.insert(format!("data:{x:0>5}"), "a".repeat(100), 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    assert_eq!(0, tree.pinned_bytes());
+    assert!(!tree.unpin_range("config:".."config;"));
+
+    tree.pin_range("config:".."config;")?;
+
+    let pinned_bytes = tree.pinned_bytes();
+    assert!(pinned_bytes > 0);
+    assert_eq!(pinned_bytes, block_cache.pinned_bytes());
+
+    // NOTE: Only the index block & the first data blocks are pinned
+    assert!(block_cache.pinned_len() < 5);
+
+    // NOTE: Pinning the same range again replaces the previous pins
+    tree.pin_range("config:".."config;")?;
+    assert_eq!(pinned_bytes, tree.pinned_bytes());
+    assert_eq!(pinned_bytes, block_cache.pinned_bytes());
+
+    // NOTE: Overlapping ranges share their blocks
+    tree.pin_range("config:0"..="config:5")?;
+    assert_eq!(pinned_bytes, tree.pinned_bytes());
+
+    assert!(tree.unpin_range("config:".."config;"));
+    assert!(tree.pinned_bytes() > 0);
+
+    assert!(tree.unpin_range("config:0"..="config:5"));
+    assert_eq!(0, tree.pinned_bytes());
+    assert_eq!(0, block_cache.pinned_bytes());
+    assert_eq!(0, block_cache.pinned_len());
+
+    Ok(())
+}
+
+#[test]
+fn tree_pin_range_no_eviction() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let block_cache = Arc::new(BlockCache::with_capacity_bytes(1_000_000));
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .block_cache(block_cache.clone())
+        .open()?;
+
+    for x in 0..10 {
+        tree.insert(format!("config:{x}"), "abc", 0);
+    }
+    for x in 0..ITEM_COUNT {
+        tree.insert(format!("data:{x:0>5}"), "a".repeat(100), 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    tree.pin_range("config:".."config;")?;
+    let pinned_bytes = tree.pinned_bytes();
+
+    // NOTE: Shrink the cache, so scanning the tree would evict the pinned blocks
+    block_cache.set_capacity(pinned_bytes);
+
+    for _ in 0..3 {
+        assert_eq!(ITEM_COUNT + 10, tree.iter().count());
+    }
+
+    assert!(block_cache.size() >= pinned_bytes);
+    assert_eq!(pinned_bytes, block_cache.pinned_bytes());
+
+    for x in 0..10 {
+        assert!(tree.contains_key(format!("config:{x}"))?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tree_pin_range_released_on_drop() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let block_cache = Arc::new(BlockCache::with_capacity_bytes(1_000_000));
+
+    {
+        let tree = Config::new(&folder)
+            .block_cache(block_cache.clone())
+            .open()?;
+
+        tree.insert("a", "abc", 0);
+        tree.flush_active_memtable(0)?;
+
+        tree.pin_range::<&str, _>(..)?;
+        assert!(block_cache.pinned_bytes() > 0);
+    }
+
+    assert_eq!(0, block_cache.pinned_bytes());
+    assert_eq!(0, block_cache.pinned_len());
+
+    Ok(())
+}
+
+#[test]
+fn tree_pin_range_released_on_compaction() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let block_cache = Arc::new(BlockCache::with_capacity_bytes(1_000_000));
+
+    let tree = Config::new(&folder)
+        .block_cache(block_cache.clone())
+        .open()?;
+
+    tree.insert("a", "abc", 0);
+    tree.flush_active_memtable(0)?;
+
+    tree.pin_range::<&str, _>(..)?;
+    assert!(tree.pinned_bytes() > 0);
+
+    tree.insert("b", "abc", 1);
+    tree.flush_active_memtable(0)?;
+    tree.major_compact(u64::MAX, 2)?;
+    assert_eq!(1, tree.segment_count());
+
+    // NOTE: The pinned segment was compacted away, so its blocks are released
+    assert_eq!(0, tree.pinned_bytes());
+    assert_eq!(0, block_cache.pinned_bytes());
+    assert_eq!(0, block_cache.pinned_len());
+
+    // NOTE: Unpinning the range again is harmless
+    assert!(tree.unpin_range::<&str, _>(..));
+    assert_eq!(0, block_cache.pinned_bytes());
+
+    Ok(())
+}
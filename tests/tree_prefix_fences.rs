@@ -0,0 +1,106 @@
+use lsm_tree::{metrics, metrics::MetricsSink, AbstractTree, BlockCache, Config};
+use std::sync::{
+    atomic::{AtomicU64, Ordering::Relaxed},
+    Arc,
+};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 1_000;
+
+#[derive(Default)]
+struct MissCounter(AtomicU64);
+
+impl MetricsSink for MissCounter {
+    fn counter(&self, name: &'static str, value: u64) {
+        if name == metrics::BLOCK_CACHE_MISSES {
+            self.0.fetch_add(value, Relaxed);
+        }
+    }
+
+    fn gauge(&self, _: &'static str, _: u64) {}
+
+    fn histogram(&self, _: &'static str, _: u64) {}
+}
+
+/// Scans a prefix that is inside the key range of every segment, but not in any segment,
+/// and returns the amount of block cache misses of the scans.
+fn scan_missing_prefix(prefix_fence_len: u8) -> lsm_tree::Result<u64> {
+    let folder = tempfile::tempdir()?;
+
+    let sink = Arc::new(MissCounter::default());
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .block_cache(Arc::new(BlockCache::with_capacity_bytes(
+            64 * 1_024 * 1_024,
+        )))
+        .metrics_sink(sink.clone())
+        .prefix_fence_len(prefix_fence_len)
+        .open()?;
+
+    for seqno in 0..2 {
+        for x in 0..ITEM_COUNT {
+            tree.insert(format!("a:{x:0>5}"), "abc", seqno);
+            tree.insert(format!("c:{x:0>5}"), "abc", seqno);
+        }
+        tree.flush_active_memtable(0)?;
+    }
+    assert_eq!(2, tree.segment_count());
+
+    let misses_before = sink.0.load(Relaxed);
+
+    assert_eq!(0, tree.prefix("b:").count());
+    assert_eq!(0, tree.prefix("b:").rev().count());
+    assert_eq!(0, tree.range("a;".."c:").count());
+
+    let misses = sink.0.load(Relaxed) - misses_before;
+
+    assert_eq!(ITEM_COUNT as usize, tree.prefix("a:").count());
+    assert_eq!(ITEM_COUNT as usize, tree.prefix("c").rev().count());
+
+    Ok(misses)
+}
+
+#[test]
+fn tree_prefix_fences_skip_segments() -> lsm_tree::Result<()> {
+    assert_eq!(0, scan_missing_prefix(2)?);
+    assert!(scan_missing_prefix(0)? > 0);
+    Ok(())
+}
+
+#[test]
+fn tree_prefix_fences_recover_and_compact() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder).prefix_fence_len(2).open()?;
+
+        for x in 0..ITEM_COUNT {
+            tree.insert(format!("a:{x:0>5}"), "abc", 0);
+            tree.insert(format!("c:{x:0>5}"), "abc", 0);
+        }
+        tree.flush_active_memtable(0)?;
+    }
+
+    let sink = Arc::new(MissCounter::default());
+
+    let tree = Config::new(&folder)
+        .prefix_fence_len(2)
+        .metrics_sink(sink.clone())
+        .open()?;
+
+    let misses_before = sink.0.load(Relaxed);
+    assert_eq!(0, tree.prefix("b:").count());
+    assert_eq!(misses_before, sink.0.load(Relaxed));
+
+    tree.insert("b:1", "abc", 1);
+    tree.flush_active_memtable(0)?;
+    tree.major_compact(u64::MAX, 0)?;
+    assert_eq!(1, tree.segment_count());
+
+    assert_eq!(1, tree.prefix("b:").count());
+    assert_eq!(0, tree.prefix("b:0").count());
+    assert_eq!(2 * ITEM_COUNT as usize + 1, tree.iter().count());
+
+    Ok(())
+}
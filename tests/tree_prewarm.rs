@@ -0,0 +1,58 @@
+use lsm_tree::{AbstractTree, BlockCache, Config};
+use std::sync::Arc;
+use test_log::test;
+
+const SEGMENT_COUNT: u64 = 5;
+const ITEM_COUNT: u64 = 100;
+
+#[test]
+fn tree_prewarm() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder).data_block_size(1_024).open()?;
+
+        for segment in 0..SEGMENT_COUNT {
+            for x in 0..ITEM_COUNT {
+                let key = (segment * ITEM_COUNT + x).to_be_bytes();
+                tree.insert(key, "a".repeat(100), segment);
+            }
+            tree.flush_active_memtable(0)?;
+        }
+    }
+
+    let block_cache = Arc::new(BlockCache::with_capacity_bytes(16 * 1_024 * 1_024));
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .block_cache(block_cache.clone())
+        .open()?;
+
+    assert!(block_cache.is_empty());
+
+    tree.prewarm(.., false)
+        .expect("should spawn")
+        .join()
+        .expect("should join")?;
+
+    let index_blocks = block_cache.len();
+    assert!(index_blocks >= SEGMENT_COUNT as usize);
+
+    tree.prewarm(0..1, true)
+        .expect("should spawn")
+        .join()
+        .expect("should join")?;
+
+    assert_eq!(index_blocks + SEGMENT_COUNT as usize, block_cache.len());
+
+    // NOTE: Levels without segments are a no-op
+    tree.prewarm(3.., true)
+        .expect("should spawn")
+        .join()
+        .expect("should join")?;
+
+    assert_eq!(index_blocks + SEGMENT_COUNT as usize, block_cache.len());
+    assert_eq!((SEGMENT_COUNT * ITEM_COUNT) as usize, tree.len()?);
+
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_range_len() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).data_block_size(1_024).open()?;
+
+    let mut seqno = 0;
+
+    // NOTE: Disjoint segments, countable using their metadata
+    for batch in 0..4u64 {
+        for x in 0..100u64 {
+            tree.insert((batch * 1_000 + x).to_be_bytes(), "a", seqno);
+            seqno += 1;
+        }
+        tree.flush_active_memtable(0)?;
+    }
+
+    assert_eq!(400, tree.range_len::<&[u8], _>(..)?);
+    assert_eq!(
+        150,
+        tree.range_len(1_050u64.to_be_bytes()..3_000u64.to_be_bytes())?
+    );
+
+    // NOTE: Overlapping segment, with an update and a tombstone
+    tree.insert(1_010u64.to_be_bytes(), "b", seqno);
+    tree.remove(1_020u64.to_be_bytes(), seqno + 1);
+    tree.flush_active_memtable(0)?;
+    seqno += 2;
+
+    // NOTE: Memtable items
+    tree.insert(2_010u64.to_be_bytes(), "b", seqno);
+    tree.insert(2_500u64.to_be_bytes(), "b", seqno + 1);
+    tree.remove(3_030u64.to_be_bytes(), seqno + 2);
+
+    for (lo, hi) in [
+        (0u64, u64::MAX),
+        (0, 1_000),
+        (1_000, 2_000),
+        (1_015, 1_025),
+        (1_050, 3_000),
+        (2_000, 4_000),
+        (3_030, 3_031),
+        (5_000, 6_000),
+    ] {
+        let range = lo.to_be_bytes()..hi.to_be_bytes();
+        assert_eq!(
+            tree.range(range.clone()).count(),
+            tree.range_len(range)?,
+            "range {lo}..{hi} differs",
+        );
+    }
+
+    assert_eq!(399, tree.len()?);
+    assert_eq!(399, tree.range_len::<&[u8], _>(..)?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_prefix_len() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for (seqno, prefix) in ["a", "b", "c"].into_iter().enumerate() {
+        for x in 0..10 {
+            tree.insert(format!("{prefix}:{x}"), "a", seqno as u64);
+        }
+        tree.flush_active_memtable(0)?;
+    }
+
+    assert_eq!(10, tree.prefix_len("b:")?);
+    assert_eq!(1, tree.prefix_len("b:5")?);
+    assert_eq!(0, tree.prefix_len("d")?);
+
+    tree.remove("b:5", 3);
+    assert_eq!(9, tree.prefix_len("b:")?);
+    assert_eq!(10, tree.prefix_len("c:")?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_prefix_len_blob() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open_as_blob_tree()?;
+
+    for x in 0..10 {
+        tree.insert(format!("a:{x}"), "a".repeat(10_000), 0);
+        tree.insert(format!("b:{x}"), "b".repeat(10_000), 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    assert_eq!(10, tree.prefix_len("a:")?);
+    assert_eq!(20, tree.range_len("a".."c")?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_range_len_partial_segment() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).data_block_size(1_024).open()?;
+
+    for x in 0..1_000u64 {
+        tree.insert(x.to_be_bytes(), "a".repeat(50), x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    for (lo, hi) in [
+        (0u64, 1_000u64),
+        (1, 999),
+        (100, 900),
+        (250, 260),
+        (990, 5_000),
+    ] {
+        let range = lo.to_be_bytes()..hi.to_be_bytes();
+        assert_eq!(
+            tree.range(range.clone()).count(),
+            tree.range_len(range)?,
+            "range {lo}..{hi} differs",
+        );
+    }
+
+    assert_eq!(
+        501,
+        tree.range_len(250u64.to_be_bytes()..=750u64.to_be_bytes())?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tree_range_len_partial_index_blocks() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .index_block_size(1_024)
+        .open()?;
+
+    for x in 0..10_000u64 {
+        tree.insert(x.to_be_bytes(), "a".repeat(50), x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    for (lo, hi) in [
+        (0u64, 10_000u64),
+        (1, 9_999),
+        (1_000, 9_000),
+        (2_500, 2_510),
+        (4_321, 8_765),
+        (9_990, 50_000),
+    ] {
+        let range = lo.to_be_bytes()..hi.to_be_bytes();
+        assert_eq!(
+            tree.range(range.clone()).count(),
+            tree.range_len(range)?,
+            "range {lo}..{hi} differs",
+        );
+    }
+
+    Ok(())
+}
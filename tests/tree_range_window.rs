@@ -0,0 +1,64 @@
+use lsm_tree::{AbstractTree, Config, InternalValue};
+use test_log::test;
+
+fn keys(
+    iter: impl Iterator<Item = lsm_tree::Result<InternalValue>>,
+) -> lsm_tree::Result<Vec<(String, u64, bool)>> {
+    iter.map(|item| {
+        item.map(|item| {
+            (
+                String::from_utf8_lossy(&item.key.user_key).into_owned(),
+                item.key.seqno,
+                item.is_tombstone(),
+            )
+        })
+    })
+    .collect()
+}
+
+#[test]
+fn tree_range_window() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "1", 0);
+    tree.insert("b", "1", 1);
+    tree.flush_active_memtable(0)?;
+
+    tree.insert("a", "2", 2);
+    tree.insert("c", "1", 3);
+    tree.flush_active_memtable(0)?;
+
+    tree.remove("b", 4);
+    tree.insert("a", "3", 5);
+    tree.insert("d", "1", 6);
+
+    assert_eq!(
+        vec![
+            ("a".to_owned(), 2, false),
+            ("b".to_owned(), 4, true),
+            ("c".to_owned(), 3, false),
+        ],
+        keys(tree.range_window::<&str, _>(.., 2..5))?,
+    );
+
+    assert_eq!(
+        vec![("a".to_owned(), 5, false), ("d".to_owned(), 6, false)],
+        keys(tree.range_window::<&str, _>(.., 5..u64::MAX))?,
+    );
+
+    assert_eq!(
+        vec![("b".to_owned(), 4, true), ("c".to_owned(), 3, false)],
+        keys(tree.range_window("b"..="c", 0..5))?,
+    );
+
+    assert_eq!(
+        vec![("a".to_owned(), 0, false)],
+        keys(tree.prefix_window("a", 0..1))?,
+    );
+
+    assert!(tree.range_window::<&str, _>(.., 3..3).next().is_none());
+
+    Ok(())
+}
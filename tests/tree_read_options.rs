@@ -0,0 +1,118 @@
+use lsm_tree::{AbstractTree, BlockCache, Config, ReadOptions};
+use std::sync::Arc;
+use test_log::test;
+
+const ITEM_COUNT: u64 = 100;
+
+#[test]
+fn tree_read_options() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let block_cache = Arc::new(BlockCache::with_capacity_bytes(16 * 1_024 * 1_024));
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .block_cache(block_cache.clone())
+        .open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "a".repeat(100), x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let options = ReadOptions::default().fill_cache(false).readahead(4);
+    assert_eq!(
+        ITEM_COUNT as usize,
+        tree.iter_with_options(&options).count()
+    );
+    assert!(block_cache.is_empty());
+
+    let options = ReadOptions::default().seqno(50);
+    assert_eq!(50, tree.iter_with_options(&options).count());
+    assert_eq!(50, tree.iter_with_options(&options).rev().count());
+
+    let options = ReadOptions::default().limit(10);
+    assert_eq!(
+        10,
+        tree.range_with_options(20u64.to_be_bytes().., &options)
+            .count()
+    );
+    assert_eq!(
+        Some(99u64.to_be_bytes().into()),
+        tree.iter_with_options(&options)
+            .next_back()
+            .transpose()?
+            .map(|(key, _)| key)
+    );
+
+    let options = ReadOptions::default().key_only(true);
+    for item in tree.prefix_with_options([0; 7], &options) {
+        let (_, value) = item?;
+        assert!(value.is_empty());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn snapshot_read_options() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "a", x);
+    }
+
+    let snapshot = tree.snapshot(50);
+
+    // NOTE: The snapshot seqno is an upper bound
+    let options = ReadOptions::default().seqno(60);
+    assert_eq!(50, snapshot.iter_with_options(&options).count());
+
+    let options = ReadOptions::default().seqno(20);
+    assert_eq!(20, snapshot.iter_with_options(&options).count());
+
+    let options = ReadOptions::default().limit(5);
+    assert_eq!(
+        5,
+        snapshot
+            .range_with_options(10u64.to_be_bytes().., &options)
+            .count()
+    );
+    assert_eq!(1, snapshot.prefix_with_options([0; 8], &options).count());
+
+    Ok(())
+}
+
+#[test]
+fn blob_tree_read_options_key_only() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open_as_blob_tree()?;
+
+    let big_value = "a".repeat(10_000);
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), &big_value, x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let options = ReadOptions::default().key_only(true).limit(10);
+    let items = tree
+        .iter_with_options(&options)
+        .collect::<lsm_tree::Result<Vec<_>>>()?;
+    assert_eq!(10, items.len());
+    assert!(items.iter().all(|(_, value)| value.is_empty()));
+
+    let options = ReadOptions::default().limit(10);
+    let items = tree
+        .iter_with_options(&options)
+        .collect::<lsm_tree::Result<Vec<_>>>()?;
+    assert_eq!(10, items.len());
+    assert!(items
+        .iter()
+        .all(|(_, value)| **value == *big_value.as_bytes()));
+
+    Ok(())
+}
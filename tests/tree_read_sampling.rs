@@ -0,0 +1,88 @@
+use lsm_tree::{
+    metrics::{ReadSample, ReadSampleSink},
+    AbstractTree, Config,
+};
+use std::sync::{Arc, Mutex};
+use test_log::test;
+
+#[derive(Default)]
+struct Samples(Mutex<Vec<ReadSample>>);
+
+impl ReadSampleSink for Samples {
+    fn record(&self, sample: ReadSample) {
+        self.0.lock().expect("lock is poisoned").push(sample);
+    }
+}
+
+#[test]
+fn tree_read_sampling() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let samples = Arc::new(Samples::default());
+
+    let tree = Config::new(&folder)
+        .read_sampling(samples.clone(), 1.0)
+        .open()?;
+
+    tree.insert("a", "abc", 0);
+    tree.insert("b", "abc", 1);
+
+    // NOTE: Memtable reads do not touch any block
+    assert!(tree.get("a")?.is_some());
+    assert!(samples.0.lock().expect("lock is poisoned").is_empty());
+
+    tree.flush_active_memtable(0)?;
+
+    let segment_id = tree
+        .levels
+        .read()
+        .expect("lock is poisoned")
+        .iter()
+        .map(|x| x.metadata.id)
+        .next()
+        .expect("should have segment");
+
+    assert!(tree.get("a")?.is_some());
+    assert!(tree.get("a")?.is_some());
+    assert!(tree.get("b")?.is_some());
+
+    let samples = samples.0.lock().expect("lock is poisoned");
+    assert_eq!(3, samples.len());
+    assert!(samples.iter().all(|x| x.segment_id == segment_id));
+    assert_eq!(
+        vec![
+            lsm_tree::Slice::from("a".as_bytes()),
+            lsm_tree::Slice::from("a".as_bytes()),
+            lsm_tree::Slice::from("b".as_bytes()),
+        ],
+        samples
+            .iter()
+            .map(|x| x.key.clone())
+            .collect::<Vec<lsm_tree::Slice>>()
+    );
+
+    // NOTE: The block was loaded by the first read
+    assert!(samples.get(1).expect("should exist").cache_hit);
+    assert!(samples.get(2).expect("should exist").cache_hit);
+
+    Ok(())
+}
+
+#[test]
+fn tree_read_sampling_disabled() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let samples = Arc::new(Samples::default());
+
+    let tree = Config::new(&folder)
+        .read_sampling(samples.clone(), 0.0)
+        .open()?;
+
+    tree.insert("a", "abc", 0);
+    tree.flush_active_memtable(0)?;
+    assert!(tree.get("a")?.is_some());
+
+    assert!(samples.0.lock().expect("lock is poisoned").is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use lsm_tree::{AbstractTree, BlockCache, Config, ReadOptions};
+use std::sync::Arc;
+use test_log::test;
+
+const ITEM_COUNT: u64 = 10_000;
+
+#[test]
+fn tree_readahead_bytes_scan() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder).data_block_size(1_024).open()?;
+
+        for x in 0..ITEM_COUNT {
+            tree.insert(x.to_be_bytes(), x.to_string().repeat(5), 0);
+        }
+        tree.flush_active_memtable(0)?;
+    }
+
+    let block_cache = Arc::new(BlockCache::with_capacity_bytes(64 * 1_024 * 1_024));
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .block_cache(block_cache.clone())
+        .readahead_bytes(64 * 1_024)
+        .open()?;
+
+    // NOTE: Cache some blocks, so the scan has to skip over them
+    for x in (0..ITEM_COUNT).step_by(1_000) {
+        assert!(tree.contains_key(x.to_be_bytes())?);
+    }
+
+    let mut expected = 0u64;
+
+    for item in tree.iter() {
+        let (key, value) = item?;
+        assert_eq!(expected.to_be_bytes(), &*key);
+        assert_eq!(expected.to_string().repeat(5).as_bytes(), &*value);
+        expected += 1;
+    }
+    assert_eq!(ITEM_COUNT, expected);
+
+    // NOTE: Blocks that are read ahead are still inserted into the block cache
+    let cached = block_cache.len();
+    assert_eq!(ITEM_COUNT as usize, tree.iter().count());
+    assert_eq!(cached, block_cache.len());
+
+    assert_eq!(
+        500,
+        tree.range(1_000u64.to_be_bytes()..1_500u64.to_be_bytes())
+            .count()
+    );
+    assert_eq!(
+        500,
+        tree.range(1_000u64.to_be_bytes()..1_500u64.to_be_bytes())
+            .rev()
+            .count()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tree_readahead_bytes_no_fill_cache() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let block_cache = Arc::new(BlockCache::with_capacity_bytes(64 * 1_024 * 1_024));
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .block_cache(block_cache.clone())
+        .open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), x.to_string().repeat(5), 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let cached = block_cache.len();
+
+    let options = ReadOptions::default()
+        .fill_cache(false)
+        .readahead_bytes(16 * 1_024);
+
+    assert_eq!(
+        ITEM_COUNT as usize,
+        tree.iter_with_options(&options).count()
+    );
+    assert_eq!(cached, block_cache.len());
+
+    let options = ReadOptions::default().readahead_bytes(16 * 1_024);
+
+    assert_eq!(
+        ITEM_COUNT as usize,
+        tree.iter_with_options(&options).count()
+    );
+    assert!(block_cache.len() > cached);
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+const SEGMENT_COUNT: u64 = 25;
+
+#[test]
+fn tree_recover_parallel() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder).open()?;
+
+        for x in 0..SEGMENT_COUNT {
+            tree.insert(x.to_be_bytes(), "a", x);
+            tree.flush_active_memtable(0)?;
+        }
+
+        assert_eq!(SEGMENT_COUNT as usize, tree.segment_count());
+    }
+
+    for threads in [1, 4, 100] {
+        let tree = Config::new(&folder).recovery_threads(threads).open()?;
+
+        assert_eq!(SEGMENT_COUNT as usize, tree.segment_count());
+        assert_eq!(SEGMENT_COUNT as usize, tree.len()?);
+        assert_eq!(Some(SEGMENT_COUNT - 1), tree.get_highest_persisted_seqno());
+
+        for x in 0..SEGMENT_COUNT {
+            assert!(tree.contains_key(x.to_be_bytes())?);
+        }
+    }
+
+    Ok(())
+}
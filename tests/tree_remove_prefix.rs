@@ -0,0 +1,200 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_remove_prefix() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    // NOTE: Segment that only contains the prefix
+    for x in 0..100 {
+        tree.insert(format!("b:{x:0>3}"), "old", 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    // NOTE: Segment that spans other keys, too
+    tree.insert("a", "a", 1);
+    tree.insert("b:050", "new", 1);
+    tree.insert("c", "c", 1);
+    tree.flush_active_memtable(0)?;
+
+    // NOTE: Memtable
+    tree.insert("b:200", "memtable", 2);
+
+    assert_eq!(2, tree.segment_count());
+    assert_eq!(101, tree.prefix("b:").count());
+
+    tree.remove_prefix("b:", 3)?;
+
+    // NOTE: The prefix-only segment is dropped, the tombstones are flushed
+    assert_eq!(2, tree.segment_count());
+    assert_eq!(0, tree.prefix("b:").count());
+    assert_eq!(2, tree.len()?);
+    assert!(tree.contains_key("a")?);
+    assert!(tree.contains_key("c")?);
+
+    // NOTE: The older version of "b:050" must not be resurrected
+    assert!(!tree.contains_key("b:050")?);
+
+    tree.flush_active_memtable(0)?;
+    assert_eq!(0, tree.prefix("b:").count());
+
+    Ok(())
+}
+
+#[test]
+fn tree_remove_prefix_covered_segments_without_tombstones() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for x in 0..10_000u64 {
+        tree.insert(format!("b:{x:0>5}"), "old", 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    tree.insert("a", "a", 1);
+    tree.insert("c", "c", 1);
+    tree.flush_active_memtable(0)?;
+
+    tree.remove_prefix("b:", 2)?;
+
+    // NOTE: The keys of the prefix are only stored in a covered segment,
+    // so it is dropped without writing any tombstones
+    assert_eq!(1, tree.segment_count());
+    assert_eq!(0, tree.active_memtable_size());
+    assert_eq!(0, tree.prefix("b:").count());
+    assert_eq!(2, tree.len()?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_remove_prefix_keeps_newer_writes() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for x in 0..10 {
+        tree.insert(format!("b:{x}"), "old", 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    tree.insert("b:new", "new", 5);
+    tree.flush_active_memtable(0)?;
+
+    tree.remove_prefix("b:", 1)?;
+
+    // NOTE: The newer segment is kept, and its key has not been deleted, so no tombstones are written
+    assert_eq!(1, tree.segment_count());
+    assert_eq!(1, tree.prefix("b:").count());
+    assert!(tree.contains_key("b:new")?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_remove_prefix_older_versions_stay_deleted() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder).open()?;
+
+        // NOTE: Older versions in a segment that spans other keys, too
+        tree.insert("a", "a", 0);
+        tree.insert("b:1", "old", 0);
+        tree.insert("b:2", "old", 0);
+        tree.insert("c", "c", 0);
+        tree.flush_active_memtable(0)?;
+
+        // NOTE: Newer versions in a segment that only contains the prefix
+        tree.insert("b:1", "new", 1);
+        tree.insert("b:2", "new", 1);
+        tree.flush_active_memtable(0)?;
+
+        tree.remove_prefix("b:", 2)?;
+
+        assert_eq!(0, tree.prefix("b:").count());
+        assert!(!tree.contains_key("b:1")?);
+        assert!(!tree.contains_key("b:2")?);
+    }
+
+    {
+        let tree = Config::new(&folder).open()?;
+
+        assert_eq!(0, tree.prefix("b:").count());
+        assert!(!tree.contains_key("b:1")?);
+        assert!(!tree.contains_key("b:2")?);
+        assert_eq!(2, tree.len()?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tree_remove_prefix_many_keys() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "a", 0);
+    for x in 0..2_500u64 {
+        tree.insert(format!("b:{x:0>4}"), "old", 0);
+    }
+    tree.insert("c", "c", 0);
+    tree.flush_active_memtable(0)?;
+
+    // NOTE: Written at the same seqno as the deletion, so it is kept
+    tree.insert("b:1000", "new", 1);
+
+    tree.remove_prefix("b:", 1)?;
+
+    assert_eq!(1, tree.prefix("b:").count());
+    assert_eq!(Some("new".as_bytes().into()), tree.get("b:1000")?);
+    assert_eq!(3, tree.len()?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_remove_prefix_concurrent_insert() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "a", 0);
+    for x in 0..5_000u64 {
+        tree.insert(format!("b:{x:0>4}"), "old", 0);
+    }
+    tree.insert("c", "c", 0);
+    tree.flush_active_memtable(0)?;
+
+    let barrier = std::sync::Barrier::new(2);
+
+    std::thread::scope(|s| -> lsm_tree::Result<()> {
+        let writer = s.spawn(|| {
+            barrier.wait();
+
+            // NOTE: Interleaved with the keys that are being removed
+            for x in 0..5_000u64 {
+                tree.insert(format!("b:{x:0>4}:new"), "new", 2);
+            }
+        });
+
+        barrier.wait();
+        tree.remove_prefix("b:", 1)?;
+
+        writer.join().expect("writer should not panic");
+
+        Ok(())
+    })?;
+
+    assert_eq!(5_000, tree.prefix("b:").count());
+    assert!(tree
+        .prefix("b:")
+        .all(|item| item.is_ok_and(|(_, value)| &*value == b"new")));
+    assert_eq!(5_002, tree.len()?);
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_reserved_headroom() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let headroom_path = folder.path().join("headroom");
+
+    {
+        let tree = Config::new(&folder).reserved_headroom(1_000_000).open()?;
+        assert_eq!(1_000_000, std::fs::metadata(&headroom_path)?.len());
+
+        for x in 0..10_u64 {
+            tree.insert(x.to_be_bytes(), "", x);
+            tree.flush_active_memtable(0)?;
+        }
+
+        tree.major_compact(u64::MAX, 0)?;
+        assert_eq!(1, tree.segment_count());
+    }
+
+    // NOTE: Reservation is restored on reopen
+    std::fs::remove_file(&headroom_path)?;
+
+    {
+        let tree = Config::new(&folder).reserved_headroom(1_000_000).open()?;
+        assert_eq!(1_000_000, std::fs::metadata(&headroom_path)?.len());
+        assert_eq!(10, tree.len()?);
+    }
+
+    Ok(())
+}
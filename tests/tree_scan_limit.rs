@@ -0,0 +1,111 @@
+use lsm_tree::{AbstractTree, Config};
+use std::time::Duration;
+use test_log::test;
+
+const ITEM_COUNT: usize = 1_000;
+
+#[test]
+fn tree_scan_limit_timeout() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .index_block_size(1_024)
+        .max_concurrent_scans(1, Duration::from_millis(50))
+        .scan_throttle_threshold(50_000)
+        .open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(format!("{x:0>5}"), "a".repeat(100), 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let mut scan = tree.iter();
+    assert_eq!(1, tree.active_scan_count());
+    assert!(scan.next().is_some());
+
+    let mut queued = tree.iter();
+    assert!(matches!(
+        queued.next(),
+        Some(Err(lsm_tree::Error::ScanQueueTimeout))
+    ));
+    assert!(queued.next().is_none());
+
+    // NOTE: Large range scans are limited as well
+    let mut queued = tree.range("00000".."00900");
+    assert!(matches!(
+        queued.next(),
+        Some(Err(lsm_tree::Error::ScanQueueTimeout))
+    ));
+
+    // NOTE: Small range & prefix scans are not limited
+    assert_eq!(2, tree.range("00000"..="00001").count());
+    assert_eq!(1, tree.prefix("00005").count());
+
+    // NOTE: Neither are reads of the first or last item
+    assert!(!tree.is_empty()?);
+    assert!(tree.first_key_value()?.is_some());
+    assert!(tree.last_key_value()?.is_some());
+
+    drop(scan);
+    assert_eq!(0, tree.active_scan_count());
+
+    assert_eq!(ITEM_COUNT, tree.iter().count());
+    assert_eq!(0, tree.active_scan_count());
+
+    Ok(())
+}
+
+#[test]
+fn tree_scan_limit_queue() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .max_concurrent_scans(1, Duration::from_secs(10))
+        .scan_throttle_threshold(0)
+        .open()?;
+
+    tree.insert("a", "abc", 0);
+
+    let scan = tree.iter();
+
+    let queued = std::thread::spawn({
+        let tree = tree.clone();
+        move || tree.iter().count()
+    });
+
+    std::thread::sleep(Duration::from_millis(50));
+    drop(scan);
+
+    assert_eq!(1, queued.join().expect("should join"));
+    assert_eq!(0, tree.active_scan_count());
+
+    Ok(())
+}
+
+#[test]
+fn tree_scan_limit_queue_without_timeout() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .max_concurrent_scans(1, Duration::MAX)
+        .scan_throttle_threshold(0)
+        .open()?;
+
+    tree.insert("a", "abc", 0);
+
+    let scan = tree.iter();
+
+    let queued = std::thread::spawn({
+        let tree = tree.clone();
+        move || tree.iter().count()
+    });
+
+    std::thread::sleep(Duration::from_millis(50));
+    drop(scan);
+
+    assert_eq!(1, queued.join().expect("should join"));
+    assert_eq!(0, tree.active_scan_count());
+
+    Ok(())
+}
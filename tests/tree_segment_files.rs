@@ -0,0 +1,94 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_segment_files_deferred_delete() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for seqno in 0..3 {
+        tree.insert("a", "abc", seqno);
+        tree.flush_active_memtable(0)?;
+    }
+    assert_eq!(3, tree.segment_count());
+
+    let files = tree.acquire_segment_files();
+    let paths = files.paths().collect::<Vec<_>>();
+    assert_eq!(3, paths.len());
+
+    let second_files = tree.acquire_segment_files();
+
+    tree.major_compact(u64::MAX, 3)?;
+    assert_eq!(1, tree.segment_count());
+
+    // NOTE: The files are still referenced
+    assert!(paths
+        .iter()
+        .all(|path| path.try_exists().unwrap_or_default()));
+
+    drop(files);
+    assert!(paths
+        .iter()
+        .all(|path| path.try_exists().unwrap_or_default()));
+
+    drop(second_files);
+    assert!(paths.iter().all(|path| !path.try_exists().unwrap_or(true)));
+
+    assert_eq!(1, tree.len()?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_segment_files_live_segment_kept() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "abc", 0);
+    tree.flush_active_memtable(0)?;
+
+    let files = tree.acquire_segment_files();
+    let paths = files.paths().collect::<Vec<_>>();
+    drop(files);
+
+    // NOTE: Segment is still part of the tree, so its file is not deleted
+    assert!(paths
+        .iter()
+        .all(|path| path.try_exists().unwrap_or_default()));
+    assert_eq!(1, tree.len()?);
+
+    Ok(())
+}
+
+#[test]
+fn tree_segment_files_kept_by_iterator() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for seqno in 0..3 {
+        tree.insert("a", "abc", seqno);
+        tree.flush_active_memtable(0)?;
+    }
+
+    let paths = tree.acquire_segment_files().paths().collect::<Vec<_>>();
+    assert_eq!(3, paths.len());
+
+    let mut iter = tree.iter();
+
+    tree.major_compact(u64::MAX, 3)?;
+    assert_eq!(1, tree.segment_count());
+
+    // NOTE: The compacted segments are still read by the iterator
+    assert!(paths
+        .iter()
+        .all(|path| path.try_exists().unwrap_or_default()));
+    assert!(iter.next().is_some());
+
+    drop(iter);
+    assert!(paths.iter().all(|path| !path.try_exists().unwrap_or(true)));
+
+    Ok(())
+}
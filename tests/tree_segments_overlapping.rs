@@ -0,0 +1,42 @@
+use lsm_tree::{AbstractTree, Config, KeyRange};
+use test_log::test;
+
+#[test]
+fn tree_segments_overlapping() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "abc", 0);
+    tree.insert("c", "abc", 1);
+    tree.flush_active_memtable(0)?;
+    tree.compact(std::sync::Arc::new(lsm_tree::compaction::PullDown(0, 1)), 0)?;
+
+    tree.insert("e", "abc", 2);
+    tree.insert("g", "abc", 3);
+    tree.flush_active_memtable(0)?;
+
+    let levels = tree.segments_overlapping::<&str, _>(..);
+    assert_eq!(7, levels.len());
+    assert_eq!(1, levels.first().expect("should exist").segments.len());
+    assert_eq!(1, levels.get(1).expect("should exist").segments.len());
+
+    let levels = tree.segments_overlapping("b".."f");
+    assert_eq!(
+        Some(KeyRange::from("e"..="g")),
+        levels.first().expect("should exist").key_range()
+    );
+    assert_eq!(
+        Some(KeyRange::from("a"..="c")),
+        levels.get(1).expect("should exist").key_range()
+    );
+
+    let levels = tree.segments_overlapping("d".."e");
+    assert!(levels.iter().all(|level| level.segments.is_empty()));
+
+    let levels = tree.segments_overlapping("d"..="e");
+    assert_eq!(1, levels.first().expect("should exist").segments.len());
+    assert!(levels.get(1).expect("should exist").segments.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "lz4")]
+
+use lsm_tree::{AbstractTree, CompressionType, Config};
+use test_log::test;
+
+#[test]
+fn tree_set_compression() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .compression(CompressionType::None)
+        .open()?;
+
+    tree.insert("a", "abc", 0);
+    tree.flush_active_memtable(0)?;
+
+    tree.set_compression(CompressionType::Lz4);
+    assert_eq!(CompressionType::Lz4, tree.compression());
+
+    tree.insert("b", "def", 1);
+    tree.flush_active_memtable(0)?;
+
+    {
+        let levels = tree.levels.read().expect("lock is poisoned");
+        let mut compressions = levels
+            .iter()
+            .map(|x| (x.metadata.id, x.metadata.compression))
+            .collect::<Vec<_>>();
+        compressions.sort_by_key(|(id, _)| *id);
+
+        assert_eq!(
+            vec![CompressionType::None, CompressionType::Lz4],
+            compressions.into_iter().map(|(_, c)| c).collect::<Vec<_>>(),
+        );
+    }
+
+    tree.major_compact(u64::MAX, 2)?;
+
+    {
+        let levels = tree.levels.read().expect("lock is poisoned");
+        assert!(levels
+            .iter()
+            .all(|x| x.metadata.compression == CompressionType::Lz4));
+    }
+
+    assert_eq!(2, tree.len()?);
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use lsm_tree::{segment::inspect, AbstractTree, Config};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 2_000;
+
+fn key(x: u64) -> String {
+    format!("some/very/long/common/key/prefix/{x:0>10}/suffix")
+}
+
+/// Writes a segment, and returns the summed up length of its index keys.
+fn write_segment(shorten_index_keys: bool) -> lsm_tree::Result<usize> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .shorten_index_keys(shorten_index_keys)
+        .open()?;
+
+    for x in 0..ITEM_COUNT {
+        tree.insert(key(x), "abc", 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    let segment_id = tree
+        .levels
+        .read()
+        .expect("lock is poisoned")
+        .iter()
+        .map(|x| x.metadata.id)
+        .next()
+        .expect("should have segment");
+
+    let segment_path = folder.path().join("segments").join(segment_id.to_string());
+    let inspector = inspect(&segment_path)?;
+    assert!(inspector.block_handles.len() > 1);
+
+    let blocks = inspector.blocks()?.collect::<lsm_tree::Result<Vec<_>>>()?;
+
+    for (idx, block) in blocks.iter().enumerate() {
+        let first_key = &block
+            .items
+            .first()
+            .expect("should not be empty")
+            .key
+            .user_key;
+        let last_key = &block
+            .items
+            .last()
+            .expect("should not be empty")
+            .key
+            .user_key;
+
+        assert!(&block.handle.end_key >= last_key);
+
+        if let Some(next) = blocks.get(idx + 1) {
+            let next_first_key = &next
+                .items
+                .first()
+                .expect("should not be empty")
+                .key
+                .user_key;
+            assert!(&block.handle.end_key < next_first_key);
+        } else {
+            assert_eq!(&block.handle.end_key, last_key);
+        }
+
+        assert!(first_key <= last_key);
+    }
+
+    for x in 0..ITEM_COUNT {
+        assert!(tree.contains_key(key(x))?);
+    }
+    assert!(!tree.contains_key(key(ITEM_COUNT))?);
+    assert!(!tree.contains_key("some/very/long/common/key/prefix/")?);
+
+    assert_eq!(ITEM_COUNT as usize, tree.iter().count());
+    assert_eq!(ITEM_COUNT as usize, tree.iter().rev().count());
+    assert_eq!(500, tree.range(key(1_000)..key(1_500)).count());
+    assert_eq!(500, tree.range(key(1_000)..key(1_500)).rev().count());
+
+    Ok(inspector
+        .block_handles
+        .iter()
+        .map(|handle| handle.end_key.len())
+        .sum())
+}
+
+#[test]
+fn tree_shortened_index_keys() -> lsm_tree::Result<()> {
+    let full = write_segment(false)?;
+    let shortened = write_segment(true)?;
+
+    assert!(shortened < full);
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+const ITEM_COUNT: u64 = 100;
+
+#[test]
+fn tree_split() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+    let left_folder = tempfile::tempdir()?;
+    let right_folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    // NOTE: First segment lies fully left of the split key
+    for x in 0..ITEM_COUNT {
+        tree.insert(x.to_be_bytes(), "abc", 0);
+    }
+    tree.flush_active_memtable(0)?;
+
+    // NOTE: Second segment straddles the split key
+    for x in ITEM_COUNT..(ITEM_COUNT * 3) {
+        tree.insert(x.to_be_bytes(), "abc", 1);
+    }
+    tree.flush_active_memtable(0)?;
+
+    // NOTE: Unflushed items are distributed too
+    tree.insert(0u64.to_be_bytes(), "def", 2);
+    tree.insert((ITEM_COUNT * 3).to_be_bytes(), "def", 2);
+
+    let split_key = (ITEM_COUNT * 2).to_be_bytes();
+
+    let (left, right) = tree.split(split_key, &left_folder, &right_folder)?;
+
+    assert_eq!(2, left.segment_count());
+    assert_eq!(1, right.segment_count());
+
+    assert_eq!(ITEM_COUNT as usize * 2, left.len()?);
+    assert_eq!(ITEM_COUNT as usize + 1, right.len()?);
+
+    assert!(left.keys().all(|key| &*key.unwrap() < split_key.as_slice()));
+    assert!(right
+        .keys()
+        .all(|key| &*key.unwrap() >= split_key.as_slice()));
+
+    assert_eq!(b"def", &*left.get(0u64.to_be_bytes())?.unwrap());
+    assert!(right.contains_key((ITEM_COUNT * 3).to_be_bytes())?);
+
+    // NOTE: Older versions are kept, too
+    assert_eq!(b"abc", &*left.snapshot(1).get(0u64.to_be_bytes())?.unwrap());
+
+    // NOTE: The original tree is unchanged
+    assert_eq!(ITEM_COUNT as usize * 3 + 1, tree.len()?);
+    assert_eq!(2, tree.segment_count());
+
+    drop(left);
+    drop(right);
+
+    // NOTE: Unflushed items are not persisted
+    let left = Config::new(&left_folder).open()?;
+    assert_eq!(ITEM_COUNT as usize * 2, left.len()?);
+    assert_eq!(b"abc", &*left.get(0u64.to_be_bytes())?.unwrap());
+
+    Ok(())
+}
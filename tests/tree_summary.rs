@@ -0,0 +1,57 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_summary() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).level_count(3).open()?;
+
+    tree.insert("a", "1", 0);
+    tree.remove("b", 1);
+    tree.flush_active_memtable(0)?;
+    tree.insert("c", "1", 2);
+
+    let summary = tree.summary();
+
+    assert_eq!(3, summary.lines().filter(|x| x.starts_with("  L")).count());
+    assert!(summary.contains("  L0: 1 segments, "));
+    assert!(summary.contains("  L2: 0 segments, 0 B\n"));
+    assert!(summary.contains("Segments: 1 total, "));
+    assert!(summary.contains(" 2 items, 1 tombstones\n"));
+    assert!(summary.contains("Memtable: 1 items, "));
+    assert!(summary.contains("(0 sealed memtables, 0 items)"));
+    assert!(summary.contains("Block cache: "));
+
+    Ok(())
+}
+
+#[test]
+fn tree_summary_block_cache_hits() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "1", 0);
+    tree.flush_active_memtable(0)?;
+
+    let block_cache = &tree.config.block_cache;
+    let lookups = || block_cache.hits() + block_cache.misses();
+
+    let before = lookups();
+    assert!(tree.get("a")?.is_some());
+    assert!(lookups() > before);
+
+    // NOTE: The second read is answered by the cache
+    let hits = block_cache.hits();
+    assert!(tree.get("a")?.is_some());
+    assert!(block_cache.hits() > hits);
+
+    assert!(tree.summary().contains(&format!(
+        ", {} hits, {} misses\n",
+        block_cache.hits(),
+        block_cache.misses(),
+    )));
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use lsm_tree::{AbstractTree, Config, SyncMode};
+use std::time::Duration;
+use test_log::test;
+
+const ITEM_COUNT: u64 = 100;
+
+#[test]
+fn tree_sync_mode() -> lsm_tree::Result<()> {
+    for mode in [
+        SyncMode::Always,
+        SyncMode::Bytes(1_024),
+        SyncMode::Interval(Duration::from_secs(1)),
+        SyncMode::Never,
+    ] {
+        let folder = tempfile::tempdir()?;
+
+        {
+            let tree = Config::new(&folder).sync_mode(mode).open()?;
+
+            for batch in 0..4u64 {
+                for x in 0..ITEM_COUNT {
+                    tree.insert(x.to_be_bytes(), batch.to_be_bytes(), batch);
+                }
+                tree.flush_active_memtable(0)?;
+            }
+
+            tree.major_compact(u64::MAX, 4)?;
+            assert_eq!(1, tree.segment_count());
+
+            tree.sync()?;
+        }
+
+        let tree = Config::new(&folder).sync_mode(mode).open()?;
+        assert_eq!(ITEM_COUNT as usize, tree.len()?);
+        assert_eq!(3u64.to_be_bytes(), &*tree.get(0u64.to_be_bytes())?.unwrap());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use lsm_tree::{AbstractTree, Config};
+use std::sync::Arc;
+use test_log::test;
+
+#[test]
+fn tree_tombstone_count() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    assert_eq!(0, tree.tombstone_count());
+    assert!(tree.level_tombstone_ratios().iter().all(|&x| x == 0.0));
+
+    for x in 0..4u64 {
+        tree.insert(x.to_be_bytes(), "a", x);
+    }
+    tree.remove(0u64.to_be_bytes(), 4);
+
+    // NOTE: Memtables are not counted
+    assert_eq!(0, tree.tombstone_count());
+
+    tree.flush_active_memtable(0)?;
+    assert_eq!(1, tree.tombstone_count());
+
+    tree.compact(Arc::new(lsm_tree::compaction::PullDown(0, 1)), 0)?;
+
+    tree.remove(1u64.to_be_bytes(), 5);
+    tree.remove(2u64.to_be_bytes(), 6);
+    tree.flush_active_memtable(0)?;
+
+    assert_eq!(3, tree.tombstone_count());
+
+    let counts = tree.level_tombstone_counts();
+    assert_eq!(Some(&2), counts.first());
+    assert_eq!(Some(&1), counts.get(1));
+    assert_eq!(3, counts.iter().sum::<u64>());
+
+    let ratios = tree.level_tombstone_ratios();
+    assert_eq!(counts.len(), ratios.len());
+    assert_eq!(Some(&1.0), ratios.first());
+    // NOTE: Both versions of key 0 are stored, so L1 contains 5 items
+    assert_eq!(Some(&0.2), ratios.get(1));
+    assert_eq!(Some(&0.0), ratios.get(2));
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use lsm_tree::{AbstractTree, Config, Error};
+use test_log::test;
+
+#[test]
+fn tree_try_insert() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .max_key_size(4)
+        .max_value_size(8)
+        .open()?;
+
+    tree.try_insert("abcd", "abcdefgh", 0)?;
+
+    assert!(matches!(tree.try_insert("", "a", 1), Err(Error::EmptyKey)));
+    assert!(matches!(
+        tree.try_insert("abcde", "a", 1),
+        Err(Error::KeyTooLarge(5))
+    ));
+    assert!(matches!(
+        tree.try_insert("a", "abcdefghi", 1),
+        Err(Error::ValueTooLarge(9))
+    ));
+
+    assert_eq!(1, tree.len()?);
+
+    Ok(())
+}
+
+#[test]
+fn blob_tree_try_insert() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .max_value_size(1_024)
+        .open_as_blob_tree()?;
+
+    tree.try_insert("a", "a".repeat(1_024), 0)?;
+
+    assert!(matches!(
+        tree.try_insert("b", "b".repeat(1_025), 1),
+        Err(Error::ValueTooLarge(1_025))
+    ));
+
+    assert_eq!(1, tree.len()?);
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use lsm_tree::{AbstractTree, Config, TuningAdvice};
+use test_log::test;
+
+#[test]
+fn tree_tuning_report_empty() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    let report = tree.tuning_report();
+    assert_eq!(None, report.bloom_false_positive_rate);
+    assert_eq!(None, report.block_cache_hit_rate);
+    assert_eq!(0, report.first_level_peak);
+    assert!(report.advice.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn tree_tuning_report_first_level_depth() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    for seqno in 0..25 {
+        tree.insert(seqno.to_string(), "abc", seqno);
+        tree.flush_active_memtable(0)?;
+    }
+
+    let report = tree.tuning_report();
+    assert_eq!(25, report.first_level_peak);
+    assert!(report
+        .advice
+        .contains(&TuningAdvice::CompactFirstLevelSooner { peak: 25 }));
+
+    Ok(())
+}
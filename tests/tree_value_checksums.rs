@@ -0,0 +1,73 @@
+use lsm_tree::{AbstractTree, CompressionType, Config};
+use test_log::test;
+
+const VALUE: &[u8] = b"this value will be corrupted";
+
+#[test]
+fn tree_value_checksums() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    {
+        let tree = Config::new(&folder)
+            .compression(CompressionType::None)
+            .value_checksums(true)
+            .open()?;
+
+        for x in 0..100u64 {
+            tree.insert(x.to_be_bytes(), "abc", 0);
+        }
+        tree.insert("corrupt", VALUE, 0);
+        tree.remove("deleted", 1);
+        tree.flush_active_memtable(0)?;
+
+        assert_eq!(Some(VALUE.into()), tree.get("corrupt")?);
+        assert_eq!(None, tree.get("deleted")?);
+        assert_eq!(0, tree.verify()?);
+
+        tree.major_compact(u64::MAX, 0)?;
+        assert_eq!(Some(VALUE.into()), tree.get("corrupt")?);
+        assert_eq!(0, tree.verify()?);
+    }
+
+    // NOTE: Flip a bit of the value in the segment file
+    let segments_folder = folder.path().join("segments");
+    for dirent in std::fs::read_dir(&segments_folder)? {
+        let path = dirent?.path();
+        let mut bytes = std::fs::read(&path)?;
+
+        if let Some(pos) = bytes.windows(VALUE.len()).position(|x| x == VALUE) {
+            bytes[pos] ^= 1;
+            std::fs::write(&path, bytes)?;
+        }
+    }
+
+    let tree = Config::new(&folder).open()?;
+
+    let err = tree.get("corrupt").expect_err("value should be corrupted");
+    assert!(err.is_corruption());
+    assert!(err.to_string().contains("value checksum mismatch"));
+
+    Ok(())
+}
+
+#[test]
+fn tree_value_checksums_mixed_segments() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+    tree.insert("a", "old", 0);
+    tree.flush_active_memtable(0)?;
+
+    let tree = Config::new(&folder).value_checksums(true).open()?;
+    tree.insert("b", "new", 1);
+    tree.flush_active_memtable(0)?;
+
+    assert_eq!(2, tree.segment_count());
+    assert_eq!(Some("old".as_bytes().into()), tree.get("a")?);
+    assert_eq!(Some("new".as_bytes().into()), tree.get("b")?);
+
+    tree.major_compact(u64::MAX, 0)?;
+    assert_eq!(2, tree.len()?);
+
+    Ok(())
+}
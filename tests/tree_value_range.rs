@@ -0,0 +1,91 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_get_range_of_value() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    let value = (0..=255u8).collect::<Vec<_>>();
+    tree.insert("a", &value, 0);
+    tree.flush_active_memtable(0)?;
+
+    assert_eq!(
+        Some(value.get(10..20).expect("should exist").into()),
+        tree.get_range_of_value("a", 10..20)?
+    );
+    assert_eq!(
+        Some(value.get(250..).expect("should exist").into()),
+        tree.get_range_of_value("a", 250..1_000)?
+    );
+    assert_eq!(
+        Some(value.get(..=0).expect("should exist").into()),
+        tree.get_range_of_value("a", ..=0)?
+    );
+    assert_eq!(
+        Some(lsm_tree::Slice::from(&[][..])),
+        tree.get_range_of_value("a", 1_000..)?
+    );
+    assert_eq!(None, tree.get_range_of_value("b", ..)?);
+
+    Ok(())
+}
+
+#[test]
+fn blob_tree_get_range_of_value() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open_as_blob_tree()?;
+
+    let value = "abcdefghij".repeat(1_000);
+    tree.insert("a", &value, 0);
+    tree.flush_active_memtable(0)?;
+
+    assert_eq!(
+        Some("cde".as_bytes().into()),
+        tree.get_range_of_value("a", 5_002..5_005)?
+    );
+    assert_eq!(
+        Some(value.as_bytes().into()),
+        tree.get_range_of_value("a", ..)?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn blob_tree_get_range_of_chunked_value() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .blob_chunk_size(20_000)
+        .open_as_blob_tree()?;
+
+    let value = (0..100_000u32).map(|x| (x % 251) as u8).collect::<Vec<_>>();
+    tree.insert_from_reader("a", &mut value.as_slice(), 0)?;
+
+    // NOTE: Windows inside a chunk, across chunks and past the end of the value
+    for (start, end) in [
+        (5, 10),
+        (19_990, 20_010),
+        (15_000, 65_000),
+        (99_990, 200_000),
+    ] {
+        assert_eq!(
+            Some(
+                value
+                    .get(start..end.min(value.len()))
+                    .expect("should exist")
+                    .into()
+            ),
+            tree.get_range_of_value("a", start..end)?,
+        );
+    }
+    assert_eq!(
+        Some(value.as_slice().into()),
+        tree.get_range_of_value("a", ..)?
+    );
+
+    Ok(())
+}
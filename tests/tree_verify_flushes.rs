@@ -0,0 +1,46 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+const ITEM_COUNT: usize = 1_000;
+
+#[test]
+fn tree_verify_flushes() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .data_block_size(1_024)
+        .verify_flushes(true)
+        .open()?;
+
+    for x in 0..ITEM_COUNT as u64 {
+        tree.insert(x.to_be_bytes(), "abc", x);
+    }
+    tree.remove(0u64.to_be_bytes(), ITEM_COUNT as u64);
+    tree.flush_active_memtable(0)?;
+
+    assert_eq!(1, tree.segment_count());
+    assert_eq!(0, tree.sealed_memtable_count());
+    assert_eq!(ITEM_COUNT - 1, tree.len()?);
+
+    Ok(())
+}
+
+#[test]
+fn blob_tree_verify_flushes() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder)
+        .blob_file_separation_threshold(1)
+        .verify_flushes(true)
+        .open_as_blob_tree()?;
+
+    for x in 0..ITEM_COUNT as u64 {
+        tree.insert(x.to_be_bytes(), "abc", x);
+    }
+    tree.flush_active_memtable(0)?;
+
+    assert_eq!(1, tree.segment_count());
+    assert_eq!(ITEM_COUNT, tree.len()?);
+
+    Ok(())
+}
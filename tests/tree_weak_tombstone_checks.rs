@@ -0,0 +1,50 @@
+use lsm_tree::{AbstractTree, Config};
+use test_log::test;
+
+#[test]
+fn tree_weak_tombstone_checks() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).weak_tombstone_checks(true).open()?;
+
+    // NOTE: Written once, so the weak tombstone is fine
+    tree.insert("a", "a", 0);
+    tree.remove_weak("a", 1);
+    assert_eq!(0, tree.weak_tombstone_violations());
+
+    // NOTE: Written again after the weak tombstone, and weakly deleted again
+    tree.insert("a", "a", 2);
+    tree.flush_active_memtable(0)?;
+    tree.remove_weak("a", 3);
+    assert_eq!(0, tree.weak_tombstone_violations());
+
+    // NOTE: Overwritten across a flush, so the older version would be resurrected
+    tree.insert("b", "old", 4);
+    tree.flush_active_memtable(0)?;
+    tree.insert("b", "new", 5);
+    tree.remove_weak("b", 6);
+    assert_eq!(1, tree.weak_tombstone_violations());
+
+    // NOTE: A strong delete in between resets the contract
+    tree.insert("c", "old", 7);
+    tree.remove("c", 8);
+    tree.insert("c", "new", 9);
+    tree.remove_weak("c", 10);
+    assert_eq!(1, tree.weak_tombstone_violations());
+
+    Ok(())
+}
+
+#[test]
+fn tree_weak_tombstone_checks_disabled() -> lsm_tree::Result<()> {
+    let folder = tempfile::tempdir()?;
+
+    let tree = Config::new(&folder).open()?;
+
+    tree.insert("a", "old", 0);
+    tree.insert("a", "new", 1);
+    tree.remove_weak("a", 2);
+    assert_eq!(0, tree.weak_tombstone_violations());
+
+    Ok(())
+}